@@ -0,0 +1,272 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Criterion benchmarks for the hot paths that don't need a live server:
+//! folder listing, caching a fetched batch, UID-set building, cache reads and
+//! searches, and pool acquire/release against an in-process mock IMAP server.
+//!
+//! ```text
+//! cargo bench --bench core_operations
+//! cargo bench --bench core_operations -- --save-baseline main   # record
+//! cargo bench --bench core_operations -- --baseline main        # compare
+//! ```
+//!
+//! See `docs/BENCHMARKS.md` for the reference numbers.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rustymail::connection_pool::{ConnectionFactory, ConnectionPool, PoolConfig};
+use rustymail::dashboard::services::cache::{CacheConfig, CacheService};
+use rustymail::imap::types::{uid_set, Address, Email, Envelope, Folder};
+use rustymail::imap::{AsyncImapSessionWrapper, ConnectionMode, ImapClient, ImapError, ImapTransport};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+
+const ACCOUNT: &str = "bench@example.com";
+const FOLDER: &str = "INBOX";
+const CACHED_EMAILS: u32 = 2_000;
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build tokio runtime")
+}
+
+fn make_email(uid: u32) -> Email {
+    let sender = format!("sender{}", uid % 50);
+    Email {
+        uid,
+        flags: vec!["Seen".to_string()],
+        envelope: Some(Envelope {
+            date: Some("Mon, 1 Jan 2024 12:00:00 +0000".to_string()),
+            subject: Some(format!("Quarterly report {} for project {}", uid, uid % 20)),
+            from: vec![Address {
+                name: Some(format!("Sender {}", uid % 50)),
+                mailbox: Some(sender),
+                host: Some("example.com".to_string()),
+            }],
+            reply_to: vec![],
+            to: vec![Address {
+                name: None,
+                mailbox: Some("bench".to_string()),
+                host: Some("example.com".to_string()),
+            }],
+            cc: vec![],
+            bcc: vec![],
+            in_reply_to: None,
+            message_id: Some(format!("<bench-{}@example.com>", uid)),
+        }),
+        internal_date: Some(Utc::now()),
+        body: None,
+        mime_parts: vec![],
+        text_body: Some(format!("Body of message {}. Please review the attached figures.", uid)),
+        html_body: None,
+        attachments: vec![],
+    }
+}
+
+/// A cache backed by a fresh SQLite file with one account and `count` emails.
+async fn seeded_cache(dir: &tempfile::TempDir, count: u32) -> CacheService {
+    let mut cache = CacheService::new(CacheConfig {
+        database_url: format!("sqlite:{}", dir.path().join("bench.db").display()),
+        ..CacheConfig::default()
+    });
+    cache.initialize().await.expect("failed to initialize cache");
+
+    let pool = cache.db_pool.as_ref().expect("cache has no pool");
+    sqlx::query(
+        "INSERT INTO accounts (email_address, display_name, imap_host, imap_port, imap_user, imap_pass) \
+         VALUES (?, 'Bench', 'imap.example.com', 993, ?, 'bench')",
+    )
+    .bind(ACCOUNT)
+    .bind(ACCOUNT)
+    .execute(pool)
+    .await
+    .expect("failed to create bench account");
+
+    for uid in 1..=count {
+        cache.cache_email(FOLDER, &make_email(uid), ACCOUNT).await.expect("failed to seed cache");
+    }
+    cache
+}
+
+/// LIST output shaped like a large account: 20 top-level folders with 25
+/// children each, plus INBOX.
+fn list_response() -> Vec<(String, Option<String>, Vec<String>)> {
+    let mut folders = vec![("INBOX".to_string(), Some("/".to_string()), vec![])];
+    for top in 0..20 {
+        folders.push((format!("Projects{}", top), Some("/".to_string()), vec!["NoSelect".to_string()]));
+        for child in 0..25 {
+            folders.push((format!("Projects{}/Client{}", top, child), Some("/".to_string()), vec![]));
+        }
+    }
+    folders
+}
+
+fn bench_list_folders(c: &mut Criterion) {
+    let listed = list_response();
+    c.bench_function("list_folders/build_hierarchy_521", |b| {
+        b.iter(|| Folder::build_hierarchy(black_box(listed.clone())))
+    });
+}
+
+fn bench_uid_set(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uid_set");
+    let contiguous: Vec<u32> = (1..=10_000).collect();
+    let sparse: Vec<u32> = (1..=10_000).map(|n| n * 3).collect();
+    group.throughput(Throughput::Elements(10_000));
+    group.bench_function("contiguous_10k", |b| b.iter(|| uid_set(black_box(&contiguous))));
+    group.bench_function("sparse_10k", |b| b.iter(|| uid_set(black_box(&sparse))));
+    group.finish();
+}
+
+fn bench_batch_fetch(c: &mut Criterion) {
+    let rt = runtime();
+    let dir = tempfile::tempdir().expect("failed to create temp dir");
+    let cache = rt.block_on(seeded_cache(&dir, 0));
+    let batch: Vec<Email> = (1..=100).map(make_email).collect();
+
+    let mut group = c.benchmark_group("batch_fetch");
+    group.throughput(Throughput::Elements(batch.len() as u64));
+    // What sync does with each fetched batch; rewriting the same UIDs keeps
+    // the table size constant between iterations.
+    group.bench_function("cache_batch_100", |b| {
+        b.to_async(&rt).iter(|| async {
+            for email in &batch {
+                cache.cache_email(FOLDER, email, ACCOUNT).await.unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_cache_reads(c: &mut Criterion) {
+    let rt = runtime();
+    let dir = tempfile::tempdir().expect("failed to create temp dir");
+    let cache = rt.block_on(seeded_cache(&dir, CACHED_EMAILS));
+
+    let mut group = c.benchmark_group("cache");
+    for limit in [20usize, 200] {
+        group.bench_with_input(BenchmarkId::new("page", limit), &limit, |b, &limit| {
+            b.to_async(&rt).iter(|| async {
+                cache.get_cached_emails_for_account(FOLDER, ACCOUNT, limit, 0, true).await.unwrap()
+            })
+        });
+    }
+    group.bench_function("get_by_uid", |b| {
+        b.to_async(&rt).iter(|| async {
+            cache.get_email_by_uid_for_account(FOLDER, black_box(CACHED_EMAILS / 2), ACCOUNT).await.unwrap()
+        })
+    });
+    group.bench_function("search_subject", |b| {
+        b.to_async(&rt).iter(|| async {
+            cache.search_cached_emails_for_account(FOLDER, "project 7", 50, ACCOUNT).await.unwrap()
+        })
+    });
+    group.finish();
+}
+
+/// Minimal IMAP server on localhost: greets, accepts any LOGIN and answers
+/// CAPABILITY, NOOP and LOGOUT. Enough for a real session to log in and be
+/// pooled, so the benchmarks below time the client and pool, not a network.
+async fn mock_imap_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("failed to bind mock IMAP server");
+    let port = listener.local_addr().expect("mock IMAP server has no address").port();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (reader, mut writer) = socket.into_split();
+                let _ = writer.write_all(b"* OK [CAPABILITY IMAP4rev1] mock ready\r\n").await;
+                let mut lines = BufReader::new(reader).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let mut words = line.split_whitespace();
+                    let tag = words.next().unwrap_or("*").to_string();
+                    let reply = match words.next().map(|c| c.to_ascii_uppercase()).as_deref() {
+                        Some("LOGIN") => format!("{} OK LOGIN completed\r\n", tag),
+                        Some("CAPABILITY") => format!("* CAPABILITY IMAP4rev1 UIDPLUS MOVE\r\n{} OK done\r\n", tag),
+                        Some("NOOP") => format!("{} OK NOOP completed\r\n", tag),
+                        Some("LOGOUT") => {
+                            let _ = writer.write_all(format!("* BYE\r\n{} OK LOGOUT completed\r\n", tag).as_bytes()).await;
+                            break;
+                        }
+                        _ => format!("{} BAD not implemented by the mock\r\n", tag),
+                    };
+                    if writer.write_all(reply.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    port
+}
+
+/// Logs in to the mock server over plaintext, so the pool gets real sessions.
+struct MockSessionFactory {
+    port: u16,
+}
+
+#[async_trait]
+impl ConnectionFactory for MockSessionFactory {
+    async fn create(&self) -> Result<Arc<ImapClient<AsyncImapSessionWrapper>>, ImapError> {
+        let transport = ImapTransport { mode: ConnectionMode::Plaintext, allow_plaintext_auth: true, ..Default::default() };
+        let client = ImapClient::<AsyncImapSessionWrapper>::connect_with_transport("127.0.0.1", self.port, "bench", "bench", &transport).await?;
+        Ok(Arc::new(client))
+    }
+
+    async fn validate(&self, client: &Arc<ImapClient<AsyncImapSessionWrapper>>) -> bool {
+        client.noop().await.is_ok()
+    }
+}
+
+fn bench_pool(c: &mut Criterion) {
+    let rt = runtime();
+    let pool = rt.block_on(async {
+        let port = mock_imap_server().await;
+        // One connection, so every acquire waits for the previous release and
+        // reuses the same logged-in session
+        let pool = ConnectionPool::new(Arc::new(MockSessionFactory { port }), PoolConfig {
+            min_connections: 0,
+            max_connections: 1,
+            idle_timeout: Duration::from_secs(300),
+            health_check_interval: Duration::from_secs(3600),
+            acquire_timeout: Duration::from_secs(5),
+            max_session_duration: Duration::from_secs(3600),
+            ..PoolConfig::default()
+        });
+        drop(Arc::clone(&pool).acquire().await.expect("mock session failed to log in"));
+        pool
+    });
+
+    let mut group = c.benchmark_group("pool");
+    group.bench_function("acquire_release", |b| {
+        b.to_async(&rt).iter(|| async {
+            let handle = Arc::clone(&pool).acquire().await.unwrap();
+            drop(black_box(handle));
+        })
+    });
+    group.bench_function("acquire_noop_release", |b| {
+        b.to_async(&rt).iter(|| async {
+            let handle = Arc::clone(&pool).acquire().await.unwrap();
+            handle.client().noop().await.unwrap();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_list_folders,
+    bench_uid_set,
+    bench_batch_fetch,
+    bench_cache_reads,
+    bench_pool
+);
+criterion_main!(benches);
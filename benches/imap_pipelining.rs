@@ -0,0 +1,78 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Compares sequential and pipelined STATUS across every folder of a live
+//! account. Latency savings grow with the round-trip time to the server, so
+//! run it against a distant server to see the effect.
+//!
+//! ```text
+//! IMAP_HOST=imap.example.com IMAP_PORT=993 IMAP_USER=me@example.com IMAP_PASS=... \
+//!     cargo bench --bench imap_pipelining
+//! ```
+//!
+//! Optional: `BENCH_ITERATIONS` (default 5), `IMAP_PIPELINE_MAX_IN_FLIGHT` (default 16).
+
+use rustymail::imap::{AsyncImapOps, AsyncImapSessionWrapper, PipelineConfig};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn mean(samples: &[Duration]) -> Duration {
+    samples.iter().sum::<Duration>() / samples.len().max(1) as u32
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    let (host, user, pass) = match (
+        std::env::var("IMAP_HOST"),
+        std::env::var("IMAP_USER"),
+        std::env::var("IMAP_PASS"),
+    ) {
+        (Ok(h), Ok(u), Ok(p)) => (h, u, p),
+        _ => {
+            eprintln!("Skipping imap_pipelining benchmark: set IMAP_HOST, IMAP_USER and IMAP_PASS");
+            return;
+        }
+    };
+    let port: u16 = std::env::var("IMAP_PORT").ok().and_then(|v| v.parse().ok()).unwrap_or(993);
+    let iterations: usize = std::env::var("BENCH_ITERATIONS").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+    let max_in_flight = PipelineConfig::from_env().max_in_flight;
+
+    let session = AsyncImapSessionWrapper::connect(&host, port, Arc::new(user), Arc::new(pass), Duration::from_secs(35))
+        .await
+        .expect("failed to connect to IMAP server");
+    let folders = session.list_folders().await.expect("failed to list folders");
+    println!("Benchmarking STATUS over {} folders on {} ({} iterations, max {} in flight)",
+             folders.len(), host, iterations, max_in_flight);
+
+    // Warm up both paths so TLS and server-side caches do not skew the first sample
+    session.folder_statuses_sequential(&folders).await.expect("sequential STATUS failed");
+    session.folder_statuses_pipelined(&folders, max_in_flight).await.expect("pipelined STATUS failed");
+
+    let mut sequential = Vec::with_capacity(iterations);
+    let mut pipelined = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let seq = session.folder_statuses_sequential(&folders).await.expect("sequential STATUS failed");
+        sequential.push(start.elapsed());
+
+        let start = Instant::now();
+        let pip = session.folder_statuses_pipelined(&folders, max_in_flight).await.expect("pipelined STATUS failed");
+        pipelined.push(start.elapsed());
+
+        assert_eq!(seq.len(), pip.len(), "pipelined STATUS returned a different number of folders");
+    }
+
+    let seq_mean = mean(&sequential);
+    let pip_mean = mean(&pipelined);
+    println!("sequential: {:>10.2?} mean", seq_mean);
+    println!("pipelined:  {:>10.2?} mean", pip_mean);
+    if pip_mean > Duration::ZERO {
+        println!("speedup:    {:>10.2}x", seq_mean.as_secs_f64() / pip_mean.as_secs_f64());
+    }
+
+    let _ = session.logout().await;
+}
@@ -78,22 +78,21 @@ export function useSSEChatbot(options: StreamChatbotOptions = {}) {
                   break;
 
                 case 'content':
-                  accumulatedTextRef.current = parsed.text;
-                  options.onContent?.(parsed.text, false);
-
-                  // When we get the full content, call onComplete
-                  if (parsed.email_data || parsed.followup_suggestions) {
-                    options.onComplete?.({
-                      text: parsed.text,
-                      conversation_id: parsed.conversation_id,
-                      emailData: parsed.email_data,
-                      followupSuggestions: parsed.followup_suggestions
-                    });
-                  }
+                  // Chunks arrive as the model generates them
+                  accumulatedTextRef.current += parsed.text;
+                  options.onContent?.(accumulatedTextRef.current, true);
                   break;
 
                 case 'complete':
-                  // Streaming complete
+                  // Full answer; replaces the streamed text
+                  accumulatedTextRef.current = parsed.text ?? accumulatedTextRef.current;
+                  options.onContent?.(accumulatedTextRef.current, false);
+                  options.onComplete?.({
+                    text: accumulatedTextRef.current,
+                    conversation_id: parsed.conversation_id,
+                    emailData: parsed.email_data,
+                    followupSuggestions: parsed.followup_suggestions
+                  });
                   break;
 
                 case 'error':
@@ -166,12 +166,12 @@ export function AccountFormDialog({
           imap_host: result.imap_host!,
           imap_port: result.imap_port || 993,
           imap_use_tls: result.imap_use_tls !== undefined ? result.imap_use_tls : true,
-          imap_user: prev.email_address,
+          imap_user: result.username || prev.email_address,
           smtp_host: result.smtp_host || '',
           smtp_port: result.smtp_port || 587,
           smtp_use_tls: result.smtp_use_tls !== undefined ? result.smtp_use_tls : false,
           smtp_use_starttls: result.smtp_use_starttls !== undefined ? result.smtp_use_starttls : true,
-          smtp_user: prev.email_address,
+          smtp_user: result.username || prev.email_address,
           account_name: prev.account_name || result.display_name || result.provider_name || prev.email_address,
         }));
 
@@ -40,6 +40,7 @@ interface Email {
   internal_date: string | null;
   flags: string[];
   body_text: string | null;
+  snippet?: string | null;
   has_attachments: boolean;
 }
 
@@ -814,9 +815,9 @@ const EmailList: React.FC<EmailListProps> = ({ currentFolder, setCurrentFolder,
                           )}
                           <span>{email.subject || '(No subject)'}</span>
                         </div>
-                        {email.body_text && (
+                        {(email.snippet || email.body_text) && (
                           <div className="text-xs text-gray-600 mt-1">
-                            {truncateText(email.body_text, 100)}
+                            {email.snippet || truncateText(email.body_text, 100)}
                           </div>
                         )}
                       </div>
@@ -181,6 +181,8 @@ export interface AutoConfigResult {
   smtp_use_starttls?: boolean;
   supports_oauth: boolean;
   oauth_provider?: string;
+  username?: string;
+  discovery_source?: 'template' | 'autoconfig' | 'ispdb' | 'srv' | 'guess';
 }
 
 export interface AccountFormData {
@@ -0,0 +1,15 @@
+-- Embedding vectors for semantic search (SEMANTIC_SEARCH_ENABLED).
+-- One row per email and embedding model; vectors are little-endian f32 arrays.
+-- Switching EMBEDDING_MODEL re-indexes on the next sync without discarding
+-- vectors produced by the previous model.
+CREATE TABLE IF NOT EXISTS email_embeddings (
+    email_id INTEGER NOT NULL,
+    model TEXT NOT NULL,
+    dimensions INTEGER NOT NULL,
+    vector BLOB NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (email_id, model),
+    FOREIGN KEY (email_id) REFERENCES emails(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_embeddings_model ON email_embeddings(model);
@@ -0,0 +1,15 @@
+-- Per-account mailbox activity (received, sent, moved, deleted, flagged),
+-- recorded from EventBus MailboxActivity events so the timeline survives
+-- restarts. Capped per account by ACTIVITY_LOG_MAX_ENTRIES.
+CREATE TABLE IF NOT EXISTS activity_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    folder TEXT,
+    uids TEXT NOT NULL DEFAULT '[]',
+    detail TEXT,
+    source TEXT NOT NULL,
+    occurred_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_activity_log_account ON activity_log(account_id, id DESC);
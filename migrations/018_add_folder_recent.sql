@@ -0,0 +1,5 @@
+-- RECENT count reported by the server the last time sync selected each folder.
+-- \Recent is per-session (RFC 3501 section 2.3.2): the session that selects a
+-- folder first claims it, so this is "arrived since the previous sync".
+ALTER TABLE folders ADD COLUMN recent_messages INTEGER DEFAULT 0;
+ALTER TABLE folders ADD COLUMN recent_checked_at TIMESTAMP;
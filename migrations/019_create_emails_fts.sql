@@ -0,0 +1,37 @@
+-- Full-text index over cached emails for search_cached_emails_ranked.
+-- External-content FTS5 table: the text lives in `emails`, the triggers keep
+-- the index in step with inserts, upserts and deletes.
+CREATE VIRTUAL TABLE IF NOT EXISTS emails_fts USING fts5(
+    subject,
+    from_name,
+    body_text,
+    content='emails',
+    content_rowid='id',
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS emails_fts_insert
+    AFTER INSERT ON emails
+    BEGIN
+        INSERT INTO emails_fts(rowid, subject, from_name, body_text)
+        VALUES (NEW.id, NEW.subject, NEW.from_name, NEW.body_text);
+    END;
+
+CREATE TRIGGER IF NOT EXISTS emails_fts_delete
+    AFTER DELETE ON emails
+    BEGIN
+        INSERT INTO emails_fts(emails_fts, rowid, subject, from_name, body_text)
+        VALUES ('delete', OLD.id, OLD.subject, OLD.from_name, OLD.body_text);
+    END;
+
+CREATE TRIGGER IF NOT EXISTS emails_fts_update
+    AFTER UPDATE OF subject, from_name, body_text ON emails
+    BEGIN
+        INSERT INTO emails_fts(emails_fts, rowid, subject, from_name, body_text)
+        VALUES ('delete', OLD.id, OLD.subject, OLD.from_name, OLD.body_text);
+        INSERT INTO emails_fts(rowid, subject, from_name, body_text)
+        VALUES (NEW.id, NEW.subject, NEW.from_name, NEW.body_text);
+    END;
+
+-- Index the messages cached before this migration
+INSERT INTO emails_fts(emails_fts) VALUES ('rebuild');
@@ -0,0 +1,4 @@
+-- HIGHESTMODSEQ (RFC 7162 CONDSTORE) of each folder as of its last sync.
+-- Sync asks the server only for flags changed after it; NULL means the
+-- server has no CONDSTORE or the folder has not been synced since.
+ALTER TABLE sync_state ADD COLUMN modseq INTEGER;
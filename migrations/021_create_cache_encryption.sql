@@ -0,0 +1,10 @@
+-- Key material for at-rest encryption of cached subjects and bodies.
+-- One row at most: the Argon2 salt for the passphrase and a value sealed
+-- with the derived key, used to reject a wrong passphrase at startup.
+-- No row means the cache is stored in plaintext.
+CREATE TABLE IF NOT EXISTS cache_encryption (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    salt BLOB NOT NULL,
+    key_check TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,8 @@
+-- Send-later support for the outbox queue.
+-- Items with a scheduled_at in the future stay pending until that time;
+-- NULL means send as soon as the worker picks the item up. Cancelled items
+-- keep status 'cancelled' and are never sent.
+
+ALTER TABLE outbox_queue ADD COLUMN scheduled_at TIMESTAMP;
+
+CREATE INDEX idx_outbox_queue_due ON outbox_queue(status, scheduled_at) WHERE status = 'pending';
@@ -0,0 +1,6 @@
+-- Human-readable preview of each cached email for list views: the new
+-- content of the message with HTML, quoted replies and the signature
+-- removed, about 200 characters. Filled in when an email is cached; NULL
+-- for rows cached before this column existed, which get one built from the
+-- body when read.
+ALTER TABLE emails ADD COLUMN snippet TEXT;
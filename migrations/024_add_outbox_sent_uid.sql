@@ -0,0 +1,5 @@
+-- UID of the copy the outbox worker appended to the Sent folder, from the
+-- server's APPENDUID response (UIDPLUS), or of the copy the server filed
+-- itself when the APPEND was skipped. NULL when neither is known.
+
+ALTER TABLE outbox_queue ADD COLUMN sent_uid INTEGER;
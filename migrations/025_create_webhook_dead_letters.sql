@@ -0,0 +1,13 @@
+-- Webhook deliveries given up on after WEBHOOK_MAX_ATTEMPTS failed attempts,
+-- kept with the exact body that was sent so they can be inspected or replayed.
+CREATE TABLE IF NOT EXISTS webhook_dead_letters (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    last_error TEXT NOT NULL,
+    failed_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_dead_letters_url ON webhook_dead_letters(url, id DESC);
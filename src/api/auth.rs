@@ -7,22 +7,35 @@
 //!
 //! This module provides API key-based authentication for the REST API.
 //! It includes middleware for request validation and API key management.
+//! Signed JWTs (`Authorization: Bearer <jwt>`) are accepted as an
+//! alternative when the `[auth]` settings configure a signing key.
 
 use actix_web::{
+    body::MessageBody,
     dev::{ServiceRequest, ServiceResponse},
     error::ErrorUnauthorized,
-    Error as ActixError, HttpMessage,
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    Error as ActixError, HttpMessage, HttpResponse,
 };
 use actix_web_lab::middleware::Next;
 use chrono::{DateTime, Utc};
-use log::{debug, info, warn};
+use dashmap::DashMap;
+use jsonwebtoken::{
+    decode, decode_header,
+    errors::ErrorKind,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::api::errors::ApiError;
+use crate::config::{AuthConfig, Settings};
 
 /// API Key metadata and permissions
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,24 +71,100 @@ pub struct ImapCredentials {
     pub port: u16,
 }
 
-/// Rate limiting configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Rate limiting configuration, enforced per key with a token bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RateLimit {
-    /// Requests per minute
+    /// Sustained requests per minute (0 = unlimited)
     pub requests_per_minute: u32,
-    /// Requests per hour
-    pub requests_per_hour: u32,
+    /// Requests allowed in a burst after the key has been idle
+    /// (defaults to `requests_per_minute`)
+    #[serde(default)]
+    pub burst: Option<u32>,
 }
 
 impl Default for RateLimit {
     fn default() -> Self {
         Self {
             requests_per_minute: 60,
-            requests_per_hour: 1000,
+            burst: None,
         }
     }
 }
 
+impl RateLimit {
+    /// Load the default per-key limit from environment variables
+    pub fn from_env() -> Self {
+        let requests_per_minute = std::env::var("API_KEY_RATE_LIMIT_PER_MINUTE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+
+        let burst = std::env::var("API_KEY_RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &u32| n > 0);
+
+        Self { requests_per_minute, burst }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute == 0
+    }
+
+    fn capacity(&self) -> u32 {
+        self.burst.filter(|&b| b > 0).unwrap_or(self.requests_per_minute)
+    }
+}
+
+/// Token bucket for one API key. It holds up to the burst size in tokens,
+/// refills at `requests_per_minute / 60` tokens a second, and each request
+/// takes one token.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket for `limit`, which must not be unlimited
+    pub fn new(limit: &RateLimit) -> Self {
+        let capacity = limit.capacity() as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: limit.requests_per_minute as f64 / 60.0,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Take a token. Returns the whole tokens left, or how long until the
+    /// next one is available.
+    pub fn try_take(&mut self) -> Result<u32, Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(self.tokens.floor() as u32)
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec))
+        }
+    }
+
+    /// Whole tokens available right now
+    pub fn remaining(&mut self) -> u32 {
+        self.refill();
+        self.tokens.floor() as u32
+    }
+}
+
 /// API permission scopes
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ApiScope {
@@ -91,20 +180,31 @@ pub enum ApiScope {
     Admin,
 }
 
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    /// Accepts the variant names in any case, with or without separators
+    /// (`ReadEmail`, `read_email`, `read-email`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace(['_', '-'], "").as_str() {
+            "reademail" => Ok(ApiScope::ReadEmail),
+            "writeemail" => Ok(ApiScope::WriteEmail),
+            "managefolders" => Ok(ApiScope::ManageFolders),
+            "dashboard" => Ok(ApiScope::Dashboard),
+            "admin" => Ok(ApiScope::Admin),
+            other => Err(format!("Unknown scope '{}'", other)),
+        }
+    }
+}
+
 /// API Key store that manages all API keys
 #[derive(Debug, Clone)]
 pub struct ApiKeyStore {
     keys: Arc<RwLock<HashMap<String, ApiKey>>>,
-    /// Track request counts for rate limiting
-    request_counts: Arc<RwLock<HashMap<String, RequestCounter>>>,
-}
-
-#[derive(Debug, Clone)]
-struct RequestCounter {
-    minute_count: u32,
-    minute_reset: DateTime<Utc>,
-    hour_count: u32,
-    hour_reset: DateTime<Utc>,
+    /// Rate limit token buckets, keyed by API key
+    buckets: Arc<DashMap<String, TokenBucket>>,
+    /// Rate limit given to keys that are not created with their own
+    default_rate_limit: Arc<RwLock<RateLimit>>,
 }
 
 impl ApiKeyStore {
@@ -112,13 +212,29 @@ impl ApiKeyStore {
     pub fn new() -> Self {
         Self {
             keys: Arc::new(RwLock::new(HashMap::new())),
-            request_counts: Arc::new(RwLock::new(HashMap::new())),
+            buckets: Arc::new(DashMap::new()),
+            default_rate_limit: Arc::new(RwLock::new(RateLimit::default())),
         }
     }
 
-    /// Initialize API keys from environment variables
-    /// Reads RUSTYMAIL_API_KEY to create an admin API key for dashboard/MCP access
+    /// Initialize API keys from environment variables, with the default
+    /// rate limit from API_KEY_RATE_LIMIT_PER_MINUTE / API_KEY_RATE_LIMIT_BURST
     pub async fn init_from_env(&self) {
+        self.init_with_defaults(RateLimit::from_env()).await;
+    }
+
+    /// Initialize API keys from environment variables
+    /// Reads RUSTYMAIL_API_KEY to create an admin API key for dashboard/MCP access.
+    /// `default_limit` applies to that key and to keys created later without
+    /// a limit of their own.
+    pub async fn init_with_defaults(&self, default_limit: RateLimit) {
+        info!(
+            "Default API key rate limit: {} requests/minute, burst {}",
+            default_limit.requests_per_minute,
+            default_limit.capacity()
+        );
+        *self.default_rate_limit.write().await = default_limit.clone();
+
         if let Ok(api_key) = std::env::var("RUSTYMAIL_API_KEY") {
             if api_key.is_empty() || api_key == "your-secure-api-key-here" {
                 warn!("RUSTYMAIL_API_KEY is not configured - API authentication will fail");
@@ -139,7 +255,7 @@ impl ApiKeyStore {
                 created_at: Utc::now(),
                 last_used: None,
                 is_active: true,
-                rate_limit: RateLimit::default(),
+                rate_limit: default_limit,
                 allowed_ips: vec![],
                 scopes: vec![
                     ApiScope::ReadEmail,
@@ -208,7 +324,7 @@ impl ApiKeyStore {
             created_at: Utc::now(),
             last_used: None,
             is_active: true,
-            rate_limit: RateLimit::default(),
+            rate_limit: self.default_rate_limit.read().await.clone(),
             allowed_ips: vec![],
             scopes,
         };
@@ -248,44 +364,71 @@ impl ApiKeyStore {
         false
     }
 
-    /// Check rate limits for an API key
-    pub async fn check_rate_limit(&self, key: &str) -> Result<(), ApiError> {
+    /// Check rate limits for an API key, taking a token on success.
+    /// Returns the requests left in the current burst.
+    pub async fn check_rate_limit(&self, key: &str) -> Result<u32, ApiError> {
         let api_key = self.validate_key(key).await?;
-        let mut counters = self.request_counts.write().await;
-
-        let now = Utc::now();
-        let counter = counters.entry(key.to_string()).or_insert_with(|| {
-            RequestCounter {
-                minute_count: 0,
-                minute_reset: now + chrono::Duration::minutes(1),
-                hour_count: 0,
-                hour_reset: now + chrono::Duration::hours(1),
-            }
-        });
+        self.try_acquire(&api_key).map_err(|wait| ApiError::RateLimitExceeded {
+            message: format!(
+                "API key rate limit of {} requests per minute exceeded, retry in {}s",
+                api_key.rate_limit.requests_per_minute,
+                retry_after_secs(wait)
+            ),
+        })
+    }
 
-        // Reset counters if time windows have passed
-        if now > counter.minute_reset {
-            counter.minute_count = 0;
-            counter.minute_reset = now + chrono::Duration::minutes(1);
-        }
-        if now > counter.hour_reset {
-            counter.hour_count = 0;
-            counter.hour_reset = now + chrono::Duration::hours(1);
+    /// Take a token from the key's bucket. Unlimited keys always pass with
+    /// `u32::MAX` remaining; otherwise the error is how long to wait.
+    pub fn try_acquire(&self, api_key: &ApiKey) -> Result<u32, Duration> {
+        if api_key.rate_limit.is_unlimited() {
+            return Ok(u32::MAX);
         }
+        self.buckets
+            .entry(api_key.key.clone())
+            .or_insert_with(|| TokenBucket::new(&api_key.rate_limit))
+            .try_take()
+    }
 
-        // Check rate limits
-        if counter.minute_count >= api_key.rate_limit.requests_per_minute {
-            return Err(ApiError::RateLimitExceeded { message: "API key rate limit exceeded (per minute)".to_string() });
-        }
-        if counter.hour_count >= api_key.rate_limit.requests_per_hour {
-            return Err(ApiError::RateLimitExceeded { message: "API key rate limit exceeded (per hour)".to_string() });
+    /// Change a key's rate limit; its bucket starts over full
+    pub async fn set_rate_limit(&self, key: &str, rate_limit: RateLimit) -> Result<(), ApiError> {
+        let mut keys = self.keys.write().await;
+        match keys.get_mut(key) {
+            Some(api_key) => {
+                api_key.rate_limit = rate_limit;
+                self.buckets.remove(key);
+                Ok(())
+            }
+            None => Err(ApiError::NotFound { resource: "API key".to_string() })
         }
+    }
 
-        // Increment counters
-        counter.minute_count += 1;
-        counter.hour_count += 1;
-
-        Ok(())
+    /// Rate limit usage of every key, for the admin endpoint
+    pub async fn usage(&self) -> Vec<ApiKeyUsage> {
+        let keys = self.keys.read().await;
+        let mut usage: Vec<ApiKeyUsage> = keys.values()
+            .map(|api_key| {
+                let remaining = if api_key.rate_limit.is_unlimited() {
+                    None
+                } else {
+                    Some(match self.buckets.get_mut(&api_key.key) {
+                        Some(mut bucket) => bucket.remaining(),
+                        None => api_key.rate_limit.capacity(),
+                    })
+                };
+                ApiKeyUsage {
+                    key_prefix: api_key.key.chars().take(10).collect(),
+                    name: api_key.name.clone(),
+                    email: api_key.email.clone(),
+                    is_active: api_key.is_active,
+                    requests_per_minute: api_key.rate_limit.requests_per_minute,
+                    burst: api_key.rate_limit.capacity(),
+                    remaining,
+                    last_used: api_key.last_used,
+                }
+            })
+            .collect();
+        usage.sort_by(|a, b| a.name.cmp(&b.name));
+        usage
     }
 
     /// Update last used timestamp for an API key
@@ -318,15 +461,7 @@ impl ApiKeyStore {
     /// Get API key metadata (without sensitive info)
     pub async fn get_key_info(&self, key: &str) -> Result<ApiKeyInfo, ApiError> {
         let api_key = self.validate_key(key).await?;
-
-        Ok(ApiKeyInfo {
-            name: api_key.name,
-            email: api_key.email,
-            created_at: api_key.created_at,
-            last_used: api_key.last_used,
-            is_active: api_key.is_active,
-            scopes: api_key.scopes,
-        })
+        Ok(ApiKeyInfo::from(api_key))
     }
 
     /// Revoke an API key
@@ -336,6 +471,7 @@ impl ApiKeyStore {
         match keys.get_mut(key) {
             Some(api_key) => {
                 api_key.is_active = false;
+                self.buckets.remove(key);
                 info!("Revoked API key: {}", key);
                 Ok(())
             }
@@ -348,6 +484,231 @@ impl ApiKeyStore {
         let api_key = self.validate_key(key).await?;
         Ok(api_key.imap_credentials)
     }
+
+    /// Request principal for a validated JWT. It gets the default rate limit
+    /// and, if an active key is registered for the same email, that key's
+    /// IMAP credentials.
+    pub async fn principal_for_jwt(&self, claims: &JwtClaims, scope_claim: &str) -> ApiKey {
+        let email = claims.email.clone().unwrap_or_else(|| claims.sub.clone());
+        let imap_credentials = self.keys.read().await
+            .values()
+            .find(|key| key.is_active && key.email.eq_ignore_ascii_case(&email))
+            .map(|key| key.imap_credentials.clone())
+            .unwrap_or(ImapCredentials {
+                username: String::new(),
+                password: String::new(),
+                server: String::new(),
+                port: 0,
+            });
+
+        ApiKey {
+            key: format!("jwt:{}", claims.sub),
+            name: claims.name.clone().unwrap_or_else(|| claims.sub.clone()),
+            email,
+            imap_credentials,
+            created_at: Utc::now(),
+            last_used: Some(Utc::now()),
+            is_active: true,
+            rate_limit: self.default_rate_limit.read().await.clone(),
+            allowed_ips: vec![],
+            scopes: claims.scopes(scope_claim),
+        }
+    }
+}
+
+/// How long a fetched JWKS is used before it is fetched again
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// Shortest gap between JWKS fetches caused by an unknown `kid`
+const JWKS_REFRESH_MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Claims read from a bearer JWT
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtClaims {
+    pub sub: String,
+    pub exp: u64,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Everything else, including the scope claim
+    #[serde(flatten)]
+    pub other: HashMap<String, serde_json::Value>,
+}
+
+impl JwtClaims {
+    /// Scopes granted by `claim`, a space-separated string or an array of
+    /// strings. Names that are not RustyMail scopes are ignored.
+    pub fn scopes(&self, claim: &str) -> Vec<ApiScope> {
+        let names: Vec<&str> = match self.other.get(claim) {
+            Some(serde_json::Value::String(s)) => s.split_whitespace().collect(),
+            Some(serde_json::Value::Array(values)) => values.iter().filter_map(|v| v.as_str()).collect(),
+            _ => vec![],
+        };
+        let mut scopes = Vec::new();
+        for scope in names.into_iter().filter_map(|name| name.parse::<ApiScope>().ok()) {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        scopes
+    }
+}
+
+/// Verifies HS256 and RS256 bearer tokens against the `[auth]` settings
+pub struct JwtValidator {
+    config: AuthConfig,
+    hs256_key: Option<DecodingKey>,
+    rs256_key: Option<DecodingKey>,
+    jwks: RwLock<Option<(Instant, JwkSet)>>,
+    http: reqwest::Client,
+}
+
+fn invalid_token(reason: impl Into<String>) -> ApiError {
+    ApiError::InvalidToken { reason: reason.into() }
+}
+
+impl JwtValidator {
+    pub fn new(config: AuthConfig) -> Result<Self, String> {
+        let hs256_key = config.jwt_secret.as_deref()
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| DecodingKey::from_secret(secret.as_bytes()));
+
+        let rs256_key = match config.jwt_public_key.as_deref().filter(|key| !key.is_empty()) {
+            Some(value) => {
+                // Inline PEM, or a path to one
+                let pem = if value.trim_start().starts_with("-----BEGIN") {
+                    value.as_bytes().to_vec()
+                } else {
+                    std::fs::read(value)
+                        .map_err(|e| format!("Failed to read JWT public key {}: {}", value, e))?
+                };
+                Some(DecodingKey::from_rsa_pem(&pem).map_err(|e| format!("Invalid JWT public key: {}", e))?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            config,
+            hs256_key,
+            rs256_key,
+            jwks: RwLock::new(None),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .map_err(|e| format!("Failed to build JWKS client: {}", e))?,
+        })
+    }
+
+    /// Validator for the `[auth]` settings, if JWT auth is configured
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        let config = settings.auth.clone().filter(AuthConfig::jwt_enabled)?;
+        match Self::new(config) {
+            Ok(validator) => {
+                info!("JWT bearer authentication enabled");
+                Some(validator)
+            }
+            Err(e) => {
+                error!("JWT bearer authentication disabled: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Claim that lists the token's scopes
+    pub fn scope_claim(&self) -> &str {
+        &self.config.scope_claim
+    }
+
+    /// Verify the signature, `exp`/`nbf` (with the configured clock skew),
+    /// `iss` and `aud`, and return the claims
+    pub async fn validate(&self, token: &str) -> Result<JwtClaims, ApiError> {
+        let header = decode_header(token).map_err(|e| invalid_token(format!("Malformed token: {}", e)))?;
+
+        // The algorithm picks the key, so HS256 is only ever checked against
+        // the shared secret and RS256 only against public keys
+        let key = match header.alg {
+            Algorithm::HS256 => self.hs256_key.clone()
+                .ok_or_else(|| invalid_token("HS256 tokens are not accepted"))?,
+            Algorithm::RS256 => self.rs256_key_for(header.kid.as_deref()).await?,
+            other => return Err(invalid_token(format!("Unsupported algorithm {:?}", other))),
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = self.config.clock_skew_seconds;
+        validation.validate_nbf = true;
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        match self.config.audience.as_deref() {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = self.config.issuer.as_deref() {
+            validation.set_issuer(&[issuer]);
+        }
+
+        decode::<JwtClaims>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => ApiError::TokenExpired,
+                ErrorKind::ImmatureSignature => invalid_token("Token is not valid yet"),
+                ErrorKind::InvalidAudience => invalid_token("Token audience does not match"),
+                ErrorKind::InvalidIssuer => invalid_token("Token issuer does not match"),
+                ErrorKind::InvalidSignature => invalid_token("Signature verification failed"),
+                _ => invalid_token(e.to_string()),
+            })
+    }
+
+    async fn rs256_key_for(&self, kid: Option<&str>) -> Result<DecodingKey, ApiError> {
+        if let Some(url) = self.config.jwks_url.as_deref().filter(|url| !url.is_empty()) {
+            if let Some(jwk) = self.jwks_key(url, kid).await? {
+                return DecodingKey::from_jwk(&jwk).map_err(|e| invalid_token(format!("Unusable JWKS key: {}", e)));
+            }
+        }
+        self.rs256_key.clone().ok_or_else(|| invalid_token("No RS256 key matches the token"))
+    }
+
+    async fn jwks_key(&self, url: &str, kid: Option<&str>) -> Result<Option<Jwk>, ApiError> {
+        let cached = self.jwks.read().await.clone();
+        let (fetched_at, set) = match cached {
+            Some((fetched_at, set)) if fetched_at.elapsed() < JWKS_CACHE_TTL => (fetched_at, set),
+            _ => self.refresh_jwks(url).await?,
+        };
+
+        let found = find_jwk(&set, kid);
+        if found.is_none() && fetched_at.elapsed() >= JWKS_REFRESH_MIN_INTERVAL {
+            // The provider may have rotated its keys since the last fetch
+            let (_, set) = self.refresh_jwks(url).await?;
+            return Ok(find_jwk(&set, kid));
+        }
+        Ok(found)
+    }
+
+    async fn refresh_jwks(&self, url: &str) -> Result<(Instant, JwkSet), ApiError> {
+        debug!("Fetching JWKS from {}", url);
+        let set = async {
+            self.http.get(url).send().await?
+                .error_for_status()?
+                .json::<JwkSet>().await
+        }
+        .await
+        .map_err(|e| {
+            warn!("Failed to fetch JWKS from {}: {}", url, e);
+            ApiError::ServiceUnavailable { service: "JWKS".to_string() }
+        })?;
+
+        let entry = (Instant::now(), set);
+        *self.jwks.write().await = Some(entry.clone());
+        Ok(entry)
+    }
+}
+
+/// Key for `kid`; a token without one matches a set holding a single key
+fn find_jwk(set: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+    match kid {
+        Some(kid) => set.find(kid).cloned(),
+        None if set.keys.len() == 1 => set.keys.first().cloned(),
+        None => None,
+    }
 }
 
 /// Public API key information (no sensitive data)
@@ -361,6 +722,39 @@ pub struct ApiKeyInfo {
     pub scopes: Vec<ApiScope>,
 }
 
+impl From<ApiKey> for ApiKeyInfo {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            name: api_key.name,
+            email: api_key.email,
+            created_at: api_key.created_at,
+            last_used: api_key.last_used,
+            is_active: api_key.is_active,
+            scopes: api_key.scopes,
+        }
+    }
+}
+
+/// Rate limit usage of one API key
+#[derive(Debug, Serialize)]
+pub struct ApiKeyUsage {
+    /// Leading characters of the key, enough to tell keys apart
+    pub key_prefix: String,
+    pub name: String,
+    pub email: String,
+    pub is_active: bool,
+    pub requests_per_minute: u32,
+    pub burst: u32,
+    /// Requests available right now; None for unlimited keys
+    pub remaining: Option<u32>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// Whole seconds to wait, at least one
+fn retry_after_secs(wait: Duration) -> u64 {
+    (wait.as_secs_f64().ceil() as u64).max(1)
+}
+
 /// Enhanced API key validation middleware
 pub async fn validate_api_key_enhanced<B>(
     req: ServiceRequest,
@@ -415,11 +809,46 @@ where
     next.call(req).await
 }
 
-/// Simple validation middleware that stores API key in app data
+/// Bearer token from the Authorization header when it is shaped like a JWT
+/// (three dot-separated parts) and no X-API-Key header is present
+fn bearer_jwt(req: &ServiceRequest) -> Option<&str> {
+    if req.headers().contains_key("X-API-Key") {
+        return None;
+    }
+    req.headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| token.split('.').count() == 3)
+}
+
+/// Validation middleware for `X-API-Key` / `Authorization: Bearer` requests.
+/// Bearer JWTs are checked by the configured `JwtValidator`; anything else
+/// must be a key in the store. The caller is stored in the request
+/// extensions as an `ApiKey`.
 pub async fn simple_validate_api_key(
     req: ServiceRequest,
     next: Next<impl actix_web::body::MessageBody>,
 ) -> Result<ServiceResponse<impl actix_web::body::MessageBody>, ActixError> {
+    let state = req.app_data::<actix_web::web::Data<crate::api::rest::AppState>>().cloned();
+
+    if let Some(app_state) = state.as_ref() {
+        if let (Some(validator), Some(token)) = (app_state.jwt_validator.as_ref(), bearer_jwt(&req)) {
+            let claims = match validator.validate(token).await {
+                Ok(claims) => claims,
+                Err(e) => {
+                    warn!("Rejected bearer token: {}", e);
+                    return Err(e.into());
+                }
+            };
+            let principal = app_state.api_key_store.principal_for_jwt(&claims, validator.scope_claim()).await;
+            debug!("Authenticated JWT subject {}", claims.sub);
+            req.extensions_mut().insert(principal);
+            return next.call(req).await;
+        }
+    }
+
     // Check for API key in header
     let api_key = req.headers()
         .get("X-API-Key")
@@ -441,15 +870,14 @@ pub async fn simple_validate_api_key(
         }
     };
 
-    // Get the API key store from app state
-    let state = req.app_data::<actix_web::web::Data<crate::api::rest::AppState>>();
-
     if let Some(app_state) = state {
         // Validate the key
         match app_state.api_key_store.validate_key(api_key).await {
-            Ok(_) => {
-                // Store API key in request for later retrieval
-                // Note: We'll pass it through headers for simplicity
+            Ok(api_key_data) => {
+                app_state.api_key_store.update_last_used(api_key).await;
+                // Store API key data in request extensions for rate limiting
+                // and handlers
+                req.extensions_mut().insert(api_key_data);
                 next.call(req).await
             }
             Err(e) => {
@@ -462,6 +890,55 @@ pub async fn simple_validate_api_key(
     }
 }
 
+fn insert_rate_limit_headers(headers: &mut HeaderMap, limit: u32, remaining: u32) {
+    if let Ok(val) = HeaderValue::from_str(&limit.to_string()) {
+        headers.insert(HeaderName::from_static("x-ratelimit-limit"), val);
+    }
+    if let Ok(val) = HeaderValue::from_str(&remaining.to_string()) {
+        headers.insert(HeaderName::from_static("x-ratelimit-remaining"), val);
+    }
+}
+
+/// Per-key rate limiting middleware. Must run after `simple_validate_api_key`
+/// (wrap it first), which leaves the validated key in the request extensions.
+pub async fn rate_limit_api_key(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, ActixError> {
+    let api_key = req.extensions().get::<ApiKey>().cloned();
+    let store = req.app_data::<actix_web::web::Data<crate::api::rest::AppState>>()
+        .map(|state| state.api_key_store.clone());
+
+    let (api_key, store) = match (api_key, store) {
+        (Some(api_key), Some(store)) if !api_key.rate_limit.is_unlimited() => (api_key, store),
+        _ => return Ok(next.call(req).await?.map_into_left_body()),
+    };
+    let limit = api_key.rate_limit.requests_per_minute;
+
+    match store.try_acquire(&api_key) {
+        Ok(remaining) => {
+            let mut res = next.call(req).await?;
+            insert_rate_limit_headers(res.headers_mut(), limit, remaining);
+            Ok(res.map_into_left_body())
+        }
+        Err(wait) => {
+            let retry_after = retry_after_secs(wait);
+            warn!("Rate limit exceeded for API key '{}', retry in {}s", api_key.name, retry_after);
+
+            let mut response = HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(serde_json::json!({
+                    "error": "rate_limit_exceeded",
+                    "message": format!("API key rate limit of {} requests per minute exceeded", limit),
+                    "retry_after": retry_after
+                }));
+            insert_rate_limit_headers(response.headers_mut(), limit, 0);
+
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -495,15 +972,74 @@ mod tests {
         store.init_with_test_defaults().await;
 
         let test_key = "test-api-key-12345";
+        store.set_rate_limit(test_key, RateLimit { requests_per_minute: 60, burst: Some(10) }).await.unwrap();
 
-        // Should succeed for first requests
-        for _ in 0..10 {
+        // Should succeed for the whole burst
+        for expected_remaining in (0..10).rev() {
             let result = store.check_rate_limit(test_key).await;
-            assert!(result.is_ok());
+            assert_eq!(result.unwrap(), expected_remaining);
         }
 
-        // Eventually should hit rate limit
-        // (In real implementation, would need to adjust rate limits or time)
+        // Bucket is empty and refills one token a second
+        let result = store.check_rate_limit(test_key).await;
+        assert!(matches!(result, Err(ApiError::RateLimitExceeded { .. })));
+
+        let usage = store.usage().await;
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].remaining, Some(0));
+        assert_eq!(usage[0].burst, 10);
+
+        // Unlimited keys always pass
+        store.set_rate_limit(test_key, RateLimit { requests_per_minute: 0, burst: None }).await.unwrap();
+        assert!(store.check_rate_limit(test_key).await.is_ok());
+        assert_eq!(store.usage().await[0].remaining, None);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_validation() {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        let validator = JwtValidator::new(AuthConfig {
+            jwt_secret: Some("test-secret".to_string()),
+            issuer: Some("https://sso.example.com".to_string()),
+            audience: Some("rustymail".to_string()),
+            clock_skew_seconds: 60,
+            ..AuthConfig::default()
+        }).unwrap();
+        let now = Utc::now().timestamp();
+        let token = |exp: i64, aud: &str| encode(
+            &Header::default(),
+            &serde_json::json!({
+                "sub": "user-1",
+                "email": "user@example.com",
+                "iss": "https://sso.example.com",
+                "aud": aud,
+                "exp": exp,
+                "scope": "read_email admin openid",
+            }),
+            &EncodingKey::from_secret(b"test-secret"),
+        ).unwrap();
+
+        let claims = validator.validate(&token(now + 300, "rustymail")).await.unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.scopes("scope"), vec![ApiScope::ReadEmail, ApiScope::Admin]);
+
+        // Inside the clock skew tolerance
+        assert!(validator.validate(&token(now - 30, "rustymail")).await.is_ok());
+        assert!(matches!(
+            validator.validate(&token(now - 300, "rustymail")).await,
+            Err(ApiError::TokenExpired)
+        ));
+        assert!(matches!(
+            validator.validate(&token(now + 300, "other-app")).await,
+            Err(ApiError::InvalidToken { .. })
+        ));
+
+        let store = ApiKeyStore::new();
+        let principal = store.principal_for_jwt(&claims, validator.scope_claim()).await;
+        assert_eq!(principal.key, "jwt:user-1");
+        assert_eq!(principal.email, "user@example.com");
+        assert!(principal.scopes.contains(&ApiScope::Admin));
     }
 
     #[tokio::test]
@@ -83,6 +83,12 @@ pub enum ApiError {
     #[error("API key expired")]
     ApiKeyExpired,
 
+    #[error("Invalid bearer token: {reason}")]
+    InvalidToken { reason: String },
+
+    #[error("Bearer token expired")]
+    TokenExpired,
+
     #[error("Rate limit exceeded: {message}")]
     RateLimitExceeded { message: String },
 
@@ -140,6 +146,9 @@ pub enum ApiError {
     #[error("External service unavailable: {service}")]
     ServiceUnavailable { service: String },
 
+    #[error("Upstream service temporarily unavailable: {service}")]
+    UpstreamUnavailable { service: String },
+
     #[error("Gateway timeout: {service}")]
     GatewayTimeout { service: String },
 
@@ -170,6 +179,8 @@ impl ApiError {
             ApiError::InvalidApiKey { .. } => "INVALID_API_KEY".to_string(),
             ApiError::Forbidden { .. } => "FORBIDDEN".to_string(),
             ApiError::ApiKeyExpired => "API_KEY_EXPIRED".to_string(),
+            ApiError::InvalidToken { .. } => "INVALID_TOKEN".to_string(),
+            ApiError::TokenExpired => "TOKEN_EXPIRED".to_string(),
             ApiError::RateLimitExceeded { .. } => "RATE_LIMIT_EXCEEDED".to_string(),
 
             // Validation
@@ -193,6 +204,7 @@ impl ApiError {
             ApiError::ImapOperation { .. } => "IMAP_OPERATION_ERROR".to_string(),
             ApiError::DatabaseError { .. } => "DATABASE_ERROR".to_string(),
             ApiError::ServiceUnavailable { .. } => "SERVICE_UNAVAILABLE".to_string(),
+            ApiError::UpstreamUnavailable { .. } => "UPSTREAM_UNAVAILABLE".to_string(),
             ApiError::GatewayTimeout { .. } => "GATEWAY_TIMEOUT".to_string(),
 
             // Content
@@ -216,6 +228,9 @@ impl ApiError {
                 "Check your API key is correct".to_string(),
                 "Generate a new API key if needed".to_string(),
             ]),
+            ApiError::TokenExpired => Some(vec![
+                "Obtain a new token from your identity provider".to_string(),
+            ]),
             ApiError::RateLimitExceeded { .. } => Some(vec![
                 "Wait before making more requests".to_string(),
                 "Consider implementing request batching".to_string(),
@@ -236,10 +251,26 @@ impl ApiError {
         }
     }
 
+    /// Bearer challenge for rejected tokens (RFC 6750)
+    pub fn www_authenticate(&self) -> Option<String> {
+        let description = match self {
+            ApiError::TokenExpired => "The token has expired".to_string(),
+            ApiError::InvalidToken { reason } => reason.replace(['"', '\\'], "'"),
+            _ => return None,
+        };
+        Some(format!(
+            "Bearer realm=\"rustymail\", error=\"invalid_token\", error_description=\"{}\"",
+            description
+        ))
+    }
+
     /// Get help links for the error
     pub fn help_links(&self) -> Option<Vec<String>> {
         match self {
-            ApiError::Unauthorized | ApiError::InvalidApiKey { .. } => Some(vec![
+            ApiError::Unauthorized
+            | ApiError::InvalidApiKey { .. }
+            | ApiError::InvalidToken { .. }
+            | ApiError::TokenExpired => Some(vec![
                 "/docs/authentication".to_string(),
             ]),
             ApiError::ValidationFailed { .. } | ApiError::BadRequest { .. } => Some(vec![
@@ -267,7 +298,9 @@ impl ResponseError for ApiError {
             // 401 Unauthorized
             ApiError::Unauthorized |
             ApiError::InvalidApiKey { .. } |
-            ApiError::ApiKeyExpired => StatusCode::UNAUTHORIZED,
+            ApiError::ApiKeyExpired |
+            ApiError::InvalidToken { .. } |
+            ApiError::TokenExpired => StatusCode::UNAUTHORIZED,
 
             // 403 Forbidden
             ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
@@ -311,7 +344,8 @@ impl ResponseError for ApiError {
             ApiError::GatewayTimeout { .. } => StatusCode::BAD_GATEWAY,
 
             // 503 Service Unavailable
-            ApiError::ServiceUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::ServiceUnavailable { .. } |
+            ApiError::UpstreamUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 
@@ -351,7 +385,11 @@ impl ResponseError for ApiError {
             timestamp: chrono::Utc::now(),
         };
 
-        HttpResponse::build(status).json(error_response)
+        let mut response = HttpResponse::build(status);
+        if let Some(challenge) = self.www_authenticate() {
+            response.insert_header(("WWW-Authenticate", challenge));
+        }
+        response.json(error_response)
     }
 }
 
@@ -366,12 +404,16 @@ impl From<ImapError> for ApiError {
             ImapError::EmailNotFound(uids) => ApiError::EmailNotFound {
                 uid: uids.first().copied().unwrap_or(0)
             },
+            ImapError::NotSelectable(_) => ApiError::BadRequest { message: err.to_string() },
             ImapError::FolderExists(folder) => ApiError::Conflict {
                 resource: format!("Folder '{}'", folder)
             },
             ImapError::Tls(msg) | ImapError::InvalidMailbox(msg) => ApiError::ImapConnection {
                 message: msg
             },
+            ImapError::TlsVerification(_) => ApiError::ImapConnection {
+                message: err.to_string()
+            },
             _ => ApiError::InternalError { message: err.to_string() },
         }
     }
@@ -480,6 +522,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_token_errors_challenge() {
+        let expired = ApiError::TokenExpired;
+        assert_eq!(expired.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(expired.code(), "TOKEN_EXPIRED");
+        let response = expired.error_response();
+        let challenge = response.headers().get("WWW-Authenticate").unwrap().to_str().unwrap();
+        assert!(challenge.starts_with("Bearer "));
+        assert!(challenge.contains("error=\"invalid_token\""));
+
+        let invalid = ApiError::InvalidToken { reason: "bad \"aud\"".to_string() };
+        assert_eq!(
+            invalid.www_authenticate().unwrap(),
+            "Bearer realm=\"rustymail\", error=\"invalid_token\", error_description=\"bad 'aud'\""
+        );
+        assert!(ApiError::Unauthorized.www_authenticate().is_none());
+    }
+
     #[test]
     fn test_suggestions() {
         let auth_error = ApiError::Unauthorized;
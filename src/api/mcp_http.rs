@@ -4,11 +4,13 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use actix_web::{web, HttpRequest, HttpResponse, Error as ActixError};
+use actix_web::error::{InternalError, JsonPayloadError};
 use serde::Deserialize;
+use actix_web::http::StatusCode;
 use actix_web::http::header::{ACCEPT, ORIGIN};
 use futures::stream::Stream;
 use futures::StreamExt;
-use log::{info, error, debug, warn};
+use tracing::{info, error, debug, warn};
 use serde_json::{json, Value};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -21,9 +23,10 @@ use std::task::{Context, Poll};
 use uuid::Uuid;
 use actix_web::web::Bytes;
 
+use crate::api::mcp_prompts;
+use crate::api::mcp_resources::{self, ResourceError};
 use crate::dashboard::services::DashboardState;
-
-
+use crate::mcp::error_codes::ErrorCode;
 
 const SESSION_TIMEOUT: Duration = Duration::from_secs(600); // 10 minutes
 const EVENT_HISTORY_SIZE: usize = 100;
@@ -97,6 +100,164 @@ impl SessionData {
     }
 }
 
+/// A failure of the MCP HTTP transport itself, as opposed to a tool that
+/// ran and failed (those come back as a result with `isError: true`).
+///
+/// Every variant is reported as a JSON-RPC error object whose `data.category`
+/// names the failure, so clients can tell e.g. a rejected key from a bad body
+/// without parsing the message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransportError {
+    /// Body isn't valid JSON
+    Parse(String),
+    /// Valid JSON but not a usable JSON-RPC request
+    InvalidRequest(String),
+    MethodNotFound(String),
+    InvalidParams(String),
+    /// Missing or wrong API key
+    Unauthorized(String),
+    /// Origin not in ALLOWED_ORIGINS
+    Forbidden(String),
+    RateLimited { message: String, retry_after: i64 },
+    /// Server can't authenticate anyone (no API key configured)
+    Misconfigured(String),
+}
+
+/// Codes /mcp has always sent for a rejected or missing API key and for a
+/// server without one; clients match on them, so they don't change.
+const UNAUTHORIZED_CODE: i32 = -32002;
+const MISCONFIGURED_CODE: i32 = -32001;
+
+impl TransportError {
+    pub fn code(&self) -> i32 {
+        match self {
+            TransportError::Parse(_) => ErrorCode::ParseError as i32,
+            // A bad Origin has always been reported as an invalid request
+            TransportError::InvalidRequest(_) | TransportError::Forbidden(_) => ErrorCode::InvalidRequest as i32,
+            TransportError::MethodNotFound(_) => ErrorCode::MethodNotFound as i32,
+            TransportError::InvalidParams(_) => ErrorCode::InvalidParams as i32,
+            TransportError::Unauthorized(_) => UNAUTHORIZED_CODE,
+            TransportError::RateLimited { .. } => ErrorCode::TransportRateLimited as i32,
+            TransportError::Misconfigured(_) => MISCONFIGURED_CODE,
+        }
+    }
+
+    /// Machine-readable `data.category` of the error object.
+    pub fn category(&self) -> &'static str {
+        match self {
+            TransportError::Parse(_) => "parse_error",
+            TransportError::InvalidRequest(_) => "invalid_request",
+            TransportError::MethodNotFound(_) => "method_not_found",
+            TransportError::InvalidParams(_) => "invalid_params",
+            TransportError::Unauthorized(_) => "unauthorized",
+            TransportError::Forbidden(_) => "forbidden",
+            TransportError::RateLimited { .. } => "rate_limited",
+            TransportError::Misconfigured(_) => "server_config",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            TransportError::Parse(m)
+            | TransportError::InvalidRequest(m)
+            | TransportError::MethodNotFound(m)
+            | TransportError::InvalidParams(m)
+            | TransportError::Unauthorized(m)
+            | TransportError::Forbidden(m)
+            | TransportError::Misconfigured(m) => m,
+            TransportError::RateLimited { message, .. } => message,
+        }
+    }
+
+    /// HTTP status for the response. Errors about the JSON-RPC call itself
+    /// (unknown method, bad params) arrive on a successful exchange and stay
+    /// 200; with MCP_HTTP_ERROR_STATUS=false everything is sent as 200 for
+    /// clients that discard non-2xx bodies.
+    pub fn http_status(&self) -> StatusCode {
+        if !http_error_status_enabled() {
+            return StatusCode::OK;
+        }
+        match self {
+            TransportError::Parse(_) | TransportError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            TransportError::MethodNotFound(_) | TransportError::InvalidParams(_) => StatusCode::OK,
+            TransportError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            TransportError::Forbidden(_) => StatusCode::FORBIDDEN,
+            TransportError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            TransportError::Misconfigured(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The JSON-RPC response; `id` is null when the request's id is unknown.
+    pub fn to_jsonrpc(&self, id: Option<Value>) -> Value {
+        let mut data = json!({ "category": self.category() });
+        if let TransportError::RateLimited { retry_after, .. } = self {
+            data["retry_after"] = json!(retry_after);
+        }
+        json!({
+            "jsonrpc": "2.0",
+            "id": id.unwrap_or(Value::Null),
+            "error": {
+                "code": self.code(),
+                "message": self.message(),
+                "data": data
+            }
+        })
+    }
+
+    pub fn to_response(&self, id: Option<Value>) -> HttpResponse {
+        let mut builder = HttpResponse::build(self.http_status());
+        match self {
+            TransportError::Unauthorized(_) => {
+                builder.insert_header(("WWW-Authenticate", "Bearer realm=\"MCP API\""));
+            }
+            TransportError::RateLimited { retry_after, .. } => {
+                builder.insert_header(("Retry-After", retry_after.to_string()));
+            }
+            _ => {}
+        }
+        builder.json(self.to_jsonrpc(id))
+    }
+}
+
+fn http_error_status_enabled() -> bool {
+    std::env::var("MCP_HTTP_ERROR_STATUS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Turn body extraction failures into JSON-RPC errors instead of actix's
+/// plain-text 400s.
+fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> ActixError {
+    let error = match &err {
+        JsonPayloadError::Deserialize(e) => TransportError::Parse(format!("Parse error: {}", e)),
+        JsonPayloadError::ContentType => {
+            TransportError::InvalidRequest("Invalid request: Content-Type must be application/json".to_string())
+        }
+        other => TransportError::InvalidRequest(format!("Invalid request: {}", other)),
+    };
+    debug!("Rejected MCP request body: {}", err);
+    let response = error.to_response(None);
+    InternalError::from_response(err, response).into()
+}
+
+/// Check the envelope of a JSON-RPC request, returning its method.
+fn validate_jsonrpc_request(request: &Value) -> Result<&str, TransportError> {
+    let object = match request {
+        Value::Object(object) => object,
+        Value::Array(_) => {
+            return Err(TransportError::InvalidRequest("Invalid request: batch requests are not supported".to_string()))
+        }
+        _ => return Err(TransportError::InvalidRequest("Invalid request: expected a JSON-RPC object".to_string())),
+    };
+    if object.get("jsonrpc").and_then(|v| v.as_str()) != Some("2.0") {
+        return Err(TransportError::InvalidRequest("Invalid request: jsonrpc must be \"2.0\"".to_string()));
+    }
+    object.get("method")
+        .and_then(|m| m.as_str())
+        .ok_or_else(|| TransportError::InvalidRequest("Invalid request: missing method".to_string()))
+}
+
 // Global state to manage SSE connections and their message queues
 lazy_static::lazy_static! {
     static ref SSE_SESSIONS: Arc<RwLock<HashMap<String, SessionData>>> =
@@ -202,20 +363,15 @@ fn validate_origin(req: &HttpRequest) -> bool {
 
 /// Validate API key from request headers
 /// Extracts key from X-Api-Key or Authorization: Bearer header
-/// Returns Ok(()) if valid, Err with JSON-RPC error response if invalid
-fn validate_api_key(req: &HttpRequest) -> Result<(), Value> {
+fn validate_api_key(req: &HttpRequest) -> Result<(), TransportError> {
     // Get the configured API key from environment
     let configured_key = match std::env::var("RUSTYMAIL_API_KEY") {
         Ok(key) if !key.is_empty() && key != "your-secure-api-key-here" => key,
         _ => {
             warn!("MCP API key validation failed: RUSTYMAIL_API_KEY not configured");
-            return Err(json!({
-                "jsonrpc": "2.0",
-                "error": {
-                    "code": -32001,
-                    "message": "Server configuration error: API key not configured"
-                }
-            }));
+            return Err(TransportError::Misconfigured(
+                "Server configuration error: API key not configured".to_string()
+            ));
         }
     };
 
@@ -240,23 +396,13 @@ fn validate_api_key(req: &HttpRequest) -> Result<(), Value> {
         }
         Some(_) => {
             warn!("MCP API key validation failed: invalid key provided");
-            Err(json!({
-                "jsonrpc": "2.0",
-                "error": {
-                    "code": -32002,
-                    "message": "Unauthorized: invalid API key"
-                }
-            }))
+            Err(TransportError::Unauthorized("Unauthorized: invalid API key".to_string()))
         }
         None => {
             debug!("MCP API key validation failed: no key provided");
-            Err(json!({
-                "jsonrpc": "2.0",
-                "error": {
-                    "code": -32002,
-                    "message": "Unauthorized: API key required. Provide via X-Api-Key header or Authorization: Bearer"
-                }
-            }))
+            Err(TransportError::Unauthorized(
+                "Unauthorized: API key required. Provide via X-Api-Key header or Authorization: Bearer".to_string()
+            ))
         }
     }
 }
@@ -301,7 +447,9 @@ async fn handle_mcp_request(request: Value, state: web::Data<DashboardState>, va
                 "result": {
                     "protocolVersion": "2025-03-26",
                     "capabilities": {
-                        "tools": {}
+                        "tools": {},
+                        "resources": {},
+                        "prompts": {}
                     },
                     "serverInfo": {
                         "name": "rustymail-mcp",
@@ -328,8 +476,78 @@ async fn handle_mcp_request(request: Value, state: web::Data<DashboardState>, va
                 }
             })
         },
+        "resources/list" => {
+            match mcp_resources::list_resources(state.as_ref()).await {
+                Ok(resources) => json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "result": {
+                        "resources": resources
+                    }
+                }),
+                Err(e) => resource_error(e, request_id),
+            }
+        },
+        "resources/templates/list" => {
+            json!({
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "result": {
+                    "resourceTemplates": mcp_resources::resource_templates()
+                }
+            })
+        },
+        "resources/read" => {
+            let uri = match params.get("uri").and_then(|u| u.as_str()) {
+                Some(uri) if !uri.is_empty() => uri,
+                _ => return Some(
+                    TransportError::InvalidParams("Missing required parameter: uri".to_string()).to_jsonrpc(request_id)
+                ),
+            };
+            match mcp_resources::read_resource(state.as_ref(), uri).await {
+                Ok(contents) => json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "result": {
+                        "contents": contents
+                    }
+                }),
+                Err(e) => resource_error(e, request_id),
+            }
+        },
+        "prompts/list" => {
+            json!({
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "result": {
+                    "prompts": mcp_prompts::list_prompts()
+                }
+            })
+        },
+        "prompts/get" => {
+            let name = match params.get("name").and_then(|n| n.as_str()) {
+                Some(name) if !name.is_empty() => name,
+                _ => return Some(
+                    TransportError::InvalidParams("Missing required parameter: name".to_string()).to_jsonrpc(request_id)
+                ),
+            };
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            match mcp_prompts::get_prompt(name, &arguments) {
+                Ok(result) => json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "result": result
+                }),
+                Err(message) => TransportError::InvalidParams(message).to_jsonrpc(request_id),
+            }
+        },
         "tools/call" => {
-            let tool_name = params.get("name").and_then(|n| n.as_str()).unwrap_or("");
+            let tool_name = match params.get("name").and_then(|n| n.as_str()) {
+                Some(name) if !name.is_empty() => name,
+                _ => return Some(
+                    TransportError::InvalidParams("Missing required parameter: name".to_string()).to_jsonrpc(request_id)
+                ),
+            };
             let tool_params = params.get("arguments").cloned().unwrap_or(json!({}));
             if tool_name == "get_workflow_status" {
                 let job_id = tool_params.get("jobId").and_then(|id| id.as_str());
@@ -346,24 +564,10 @@ async fn handle_mcp_request(request: Value, state: web::Data<DashboardState>, va
                             }
                         })
                     } else {
-                        json!({
-                            "jsonrpc": "2.0",
-                            "id": request_id,
-                            "error": {
-                                "code": -32603,
-                                "message": format!("Job not found: {}", job_id)
-                            }
-                        })
+                        TransportError::InvalidParams(format!("Job not found: {}", job_id)).to_jsonrpc(request_id)
                     }
                 } else {
-                    json!({
-                        "jsonrpc": "2.0",
-                        "id": request_id,
-                        "error": {
-                            "code": -32602,
-                            "message": "Missing required parameter: jobId"
-                        }
-                    })
+                    TransportError::InvalidParams("Missing required parameter: jobId".to_string()).to_jsonrpc(request_id)
                 };
                 return Some(response);
             }
@@ -446,21 +650,31 @@ async fn handle_mcp_request(request: Value, state: web::Data<DashboardState>, va
                 }
             }
         },
-        _ => {
-            json!({
-                "jsonrpc": "2.0",
-                "id": request_id,
-                "error": {
-                    "code": -32601,
-                    "message": format!("Method not found: {}", method)
-                }
-            })
-        }
+        _ => TransportError::MethodNotFound(format!("Method not found: {}", method)).to_jsonrpc(request_id),
     };
 
     Some(response)
 }
 
+/// JSON-RPC error for a failed `resources/*` call. An unknown resource gets
+/// -32002, the code MCP assigns to it.
+fn resource_error(error: ResourceError, id: Option<Value>) -> Value {
+    let (code, category, message) = match error {
+        ResourceError::InvalidUri(m) => (ErrorCode::InvalidParams, "invalid_params", m),
+        ResourceError::NotFound(m) => (ErrorCode::ImapFolderNotFound, "resource_not_found", m),
+        ResourceError::Internal(m) => (ErrorCode::InternalError, "internal_error", m),
+    };
+    json!({
+        "jsonrpc": "2.0",
+        "id": id.unwrap_or(Value::Null),
+        "error": {
+            "code": code as i32,
+            "message": message,
+            "data": { "category": category }
+        }
+    })
+}
+
 /// POST handler for MCP endpoint
 /// Handles JSON-RPC requests and returns responses
 pub async fn mcp_post_handler(
@@ -474,20 +688,12 @@ pub async fn mcp_post_handler(
 
     // Validate Origin header for security
     if !validate_origin(&req) {
-        return Ok(HttpResponse::Forbidden().json(json!({
-            "jsonrpc": "2.0",
-            "error": {
-                "code": -32600,
-                "message": "Invalid origin"
-            }
-        })));
+        return Ok(TransportError::Forbidden("Invalid origin".to_string()).to_response(None));
     }
 
     // Validate API key for authentication
-    if let Err(error_response) = validate_api_key(&req) {
-        return Ok(HttpResponse::Unauthorized()
-            .insert_header(("WWW-Authenticate", "Bearer realm=\"MCP API\""))
-            .json(error_response));
+    if let Err(e) = validate_api_key(&req) {
+        return Ok(e.to_response(None));
     }
 
     // Check Accept header
@@ -512,8 +718,11 @@ pub async fn mcp_post_handler(
         }
     }
 
-    // Process the JSON-RPC request
+    // Process the JSON-RPC request; malformed ones are answered even without an id
     let request = body.into_inner();
+    if let Err(e) = validate_jsonrpc_request(&request) {
+        return Ok(e.to_response(request.get("id").cloned()));
+    }
     let response_opt = handle_mcp_request(request.clone(), state, variant).await;
 
     // If this is a notification, don't send a response
@@ -564,14 +773,12 @@ pub async fn mcp_get_handler(
 
     // Validate Origin header
     if !validate_origin(&req) {
-        return Ok(HttpResponse::Forbidden().finish());
+        return Ok(TransportError::Forbidden("Invalid origin".to_string()).to_response(None));
     }
 
     // Validate API key for authentication
-    if let Err(error_response) = validate_api_key(&req) {
-        return Ok(HttpResponse::Unauthorized()
-            .insert_header(("WWW-Authenticate", "Bearer realm=\"MCP API\""))
-            .json(error_response));
+    if let Err(e) = validate_api_key(&req) {
+        return Ok(e.to_response(None));
     }
 
     // Check Accept header - must request text/event-stream
@@ -667,6 +874,7 @@ pub fn configure_mcp_routes(cfg: &mut web::ServiceConfig) {
     // Main MCP endpoint supporting both GET and POST
     cfg.service(
         web::resource("/mcp")
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
             .route(web::post().to(mcp_post_handler))
             .route(web::get().to(mcp_get_handler))
     );
@@ -674,6 +882,7 @@ pub fn configure_mcp_routes(cfg: &mut web::ServiceConfig) {
     // API versioned endpoint
     cfg.service(
         web::resource("/mcp/v1")
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
             .route(web::post().to(mcp_post_handler))
             .route(web::get().to(mcp_get_handler))
     );
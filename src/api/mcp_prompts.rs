@@ -0,0 +1,177 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! MCP prompts: built-in email workflows a client can offer in its prompt
+//! menu. Each one expands to a user message telling the agent which tools to
+//! call and in what order, so triage works the same way across agents.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+struct PromptArgument {
+    name: &'static str,
+    description: &'static str,
+    required: bool,
+}
+
+struct Prompt {
+    name: &'static str,
+    description: &'static str,
+    arguments: &'static [PromptArgument],
+    render: fn(&HashMap<String, String>) -> String,
+}
+
+const ACCOUNT_ARG: PromptArgument = PromptArgument {
+    name: "account_id",
+    description: "Email address of the account",
+    required: true,
+};
+
+const PROMPTS: &[Prompt] = &[
+    Prompt {
+        name: "summarize_inbox",
+        description: "Summarize recent mail in a folder, grouped by what needs attention",
+        arguments: &[
+            ACCOUNT_ARG,
+            PromptArgument { name: "folder", description: "Folder to summarize (default: INBOX)", required: false },
+        ],
+        render: summarize_inbox,
+    },
+    Prompt {
+        name: "draft_reply",
+        description: "Draft a reply to one email and save it to Drafts without sending",
+        arguments: &[
+            ACCOUNT_ARG,
+            PromptArgument { name: "folder", description: "Folder holding the email", required: true },
+            PromptArgument { name: "uid", description: "UID of the email to reply to", required: true },
+            PromptArgument { name: "tone", description: "Tone of the reply, e.g. friendly or formal (default: professional)", required: false },
+        ],
+        render: draft_reply,
+    },
+    Prompt {
+        name: "categorize_unread",
+        description: "Sort unread mail into action, reply, read-later and ignore",
+        arguments: &[
+            ACCOUNT_ARG,
+            PromptArgument { name: "folder", description: "Folder to triage (default: INBOX)", required: false },
+        ],
+        render: categorize_unread,
+    },
+];
+
+fn arg<'a>(args: &'a HashMap<String, String>, name: &str, default: &'a str) -> &'a str {
+    args.get(name).map(|v| v.trim()).filter(|v| !v.is_empty()).unwrap_or(default)
+}
+
+fn summarize_inbox(args: &HashMap<String, String>) -> String {
+    let account = arg(args, "account_id", "");
+    let folder = arg(args, "folder", "INBOX");
+    format!(
+        "Summarize the recent mail in folder \"{folder}\" of account {account}.\n\n\
+         1. Call list_cached_emails with account_id=\"{account}\", folder=\"{folder}\" and limit=30.\n\
+         2. Call batch_get_synopsis with the UIDs you got to read what each email says.\n\
+         3. Reply with a short summary in three groups: needs action, FYI, and newsletters or notifications. \
+         Give sender, subject and one line per email, most urgent first.\n\n\
+         Only read mail; do not move, flag or delete anything."
+    )
+}
+
+fn draft_reply(args: &HashMap<String, String>) -> String {
+    let account = arg(args, "account_id", "");
+    let folder = arg(args, "folder", "INBOX");
+    let uid = arg(args, "uid", "");
+    let tone = arg(args, "tone", "professional");
+    format!(
+        "Draft a {tone} reply to email UID {uid} in folder \"{folder}\" of account {account}.\n\n\
+         1. Call get_email_by_uid with account_id=\"{account}\", folder=\"{folder}\" and uid={uid}.\n\
+         2. If it has a message_id, call get_email_thread to read the earlier messages in the conversation.\n\
+         3. Write a reply that answers every question asked, in a {tone} tone, without inventing facts or commitments.\n\
+         4. Call save_draft with account_id=\"{account}\", to set to the original sender, subject set to \"Re: \" plus the \
+         original subject, and the reply as body.\n\n\
+         Do not call send_email. Show me the draft when it is saved."
+    )
+}
+
+fn categorize_unread(args: &HashMap<String, String>) -> String {
+    let account = arg(args, "account_id", "");
+    let folder = arg(args, "folder", "INBOX");
+    format!(
+        "Triage the unread mail in folder \"{folder}\" of account {account}.\n\n\
+         1. Call list_emails_by_flag with account_id=\"{account}\", folder=\"{folder}\" and unread_only=true.\n\
+         2. Call batch_get_synopsis with those UIDs.\n\
+         3. Put each email in exactly one category: action required, reply needed, read later, or ignore.\n\
+         4. Reply with a table of category, sender, subject and UID, and a one-line reason for each action-required email.\n\n\
+         Do not mark anything as read or change any flags unless I ask."
+    )
+}
+
+/// Result of `prompts/list`.
+pub fn list_prompts() -> Value {
+    Value::Array(PROMPTS.iter().map(|prompt| json!({
+        "name": prompt.name,
+        "description": prompt.description,
+        "arguments": prompt.arguments.iter().map(|a| json!({
+            "name": a.name,
+            "description": a.description,
+            "required": a.required
+        })).collect::<Vec<_>>()
+    })).collect())
+}
+
+/// Result of `prompts/get`, or a message for an unknown prompt or a missing
+/// required argument.
+pub fn get_prompt(name: &str, arguments: &Value) -> Result<Value, String> {
+    let prompt = PROMPTS.iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Unknown prompt: {}", name))?;
+
+    let args: HashMap<String, String> = arguments.as_object()
+        .map(|map| map.iter().map(|(k, v)| {
+            let value = v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string());
+            (k.clone(), value)
+        }).collect())
+        .unwrap_or_default();
+
+    for required in prompt.arguments.iter().filter(|a| a.required) {
+        if !args.get(required.name).is_some_and(|v| !v.trim().is_empty()) {
+            return Err(format!("Prompt '{}' requires argument '{}'", name, required.name));
+        }
+    }
+
+    Ok(json!({
+        "description": prompt.description,
+        "messages": [{
+            "role": "user",
+            "content": {
+                "type": "text",
+                "text": (prompt.render)(&args)
+            }
+        }]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_prompt() {
+        let names: Vec<String> = list_prompts().as_array().unwrap().iter()
+            .map(|p| p["name"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["summarize_inbox", "draft_reply", "categorize_unread"]);
+
+        let result = get_prompt("draft_reply", &json!({"account_id": "me@example.com", "folder": "INBOX", "uid": 42})).unwrap();
+        let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("uid=42"));
+        assert!(text.contains("professional tone"));
+        assert!(text.contains("save_draft"));
+
+        assert!(get_prompt("draft_reply", &json!({"account_id": "me@example.com", "folder": "INBOX"})).unwrap_err().contains("'uid'"));
+        assert!(get_prompt("summarize_inbox", &json!({})).is_err());
+        assert!(get_prompt("no_such_prompt", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! MCP resources: cached folders and messages addressable by URI, so a
+//! client can attach an email as context without calling a tool.
+//!
+//! - `imap://<account>/<folder>` lists the folder's most recent messages
+//! - `imap://<account>/<folder>/<uid>` is one message's body
+//!
+//! Account and folder are percent-encoded, so folder names containing `/`
+//! stay one path segment. Folders come from the cache; a message that isn't
+//! cached is fetched from the server as `message/rfc822`.
+
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::dashboard::services::email::EmailServiceError;
+use crate::dashboard::services::DashboardState;
+use crate::imap::error::ImapError;
+use crate::utils::plain_text;
+
+const SCHEME: &str = "imap://";
+
+/// Messages listed when a folder resource is read.
+const FOLDER_READ_LIMIT: usize = 50;
+
+/// What a resource URI points at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceUri {
+    Folder { account: String, folder: String },
+    Message { account: String, folder: String, uid: u32 },
+}
+
+/// Why a resource couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceError {
+    /// Not an `imap://` URI this server understands
+    InvalidUri(String),
+    NotFound(String),
+    Internal(String),
+}
+
+pub fn folder_uri(account: &str, folder: &str) -> String {
+    format!("{}{}/{}", SCHEME, urlencoding::encode(account), urlencoding::encode(folder))
+}
+
+pub fn message_uri(account: &str, folder: &str, uid: u32) -> String {
+    format!("{}/{}", folder_uri(account, folder), uid)
+}
+
+pub fn parse_uri(uri: &str) -> Option<ResourceUri> {
+    let rest = uri.strip_prefix(SCHEME)?;
+    let segments: Vec<&str> = rest.split('/').collect();
+    let decode = |s: &str| urlencoding::decode(s).ok().map(|d| d.into_owned()).filter(|d| !d.is_empty());
+    match segments.as_slice() {
+        [account, folder] => Some(ResourceUri::Folder {
+            account: decode(account)?,
+            folder: decode(folder)?,
+        }),
+        [account, folder, uid] => Some(ResourceUri::Message {
+            account: decode(account)?,
+            folder: decode(folder)?,
+            uid: uid.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+/// URI template for messages, for `resources/templates/list`.
+pub fn resource_templates() -> Value {
+    json!([{
+        "uriTemplate": "imap://{account}/{folder}/{uid}",
+        "name": "Email message",
+        "description": "One email by account, folder and UID; account and folder are percent-encoded",
+        "mimeType": "text/plain"
+    }])
+}
+
+/// Every cached folder of every account.
+pub async fn list_resources(state: &DashboardState) -> Result<Value, ResourceError> {
+    let accounts = {
+        let account_service = state.account_service.lock().await;
+        account_service.list_accounts().await
+            .map_err(|e| ResourceError::Internal(format!("Failed to list accounts: {}", e)))?
+    };
+
+    let mut resources = Vec::new();
+    for account in accounts {
+        let folders = state.cache_service.get_all_cached_folders_for_account(&account.email_address).await
+            .map_err(|e| ResourceError::Internal(format!("Failed to list folders of {}: {}", account.email_address, e)))?;
+        for folder in folders {
+            resources.push(json!({
+                "uri": folder_uri(&account.email_address, &folder.name),
+                "name": format!("{} ({})", folder.name, account.email_address),
+                "description": format!("{} messages, {} unread", folder.total_messages, folder.unseen_messages),
+                "mimeType": "application/json"
+            }));
+        }
+    }
+    Ok(Value::Array(resources))
+}
+
+/// The `contents` of a `resources/read` result.
+pub async fn read_resource(state: &DashboardState, uri: &str) -> Result<Value, ResourceError> {
+    let resource = parse_uri(uri).ok_or_else(|| ResourceError::InvalidUri(format!(
+        "Unsupported resource URI '{}'; expected imap://<account>/<folder> or imap://<account>/<folder>/<uid>", uri
+    )))?;
+    match resource {
+        ResourceUri::Folder { account, folder } => read_folder(state, uri, &account, &folder).await,
+        ResourceUri::Message { account, folder, uid } => read_message(state, uri, &account, &folder, uid).await,
+    }
+}
+
+async fn read_folder(state: &DashboardState, uri: &str, account: &str, folder: &str) -> Result<Value, ResourceError> {
+    let emails = state.cache_service.get_cached_emails_for_account(folder, account, FOLDER_READ_LIMIT, 0, true).await
+        .map_err(|e| ResourceError::Internal(format!("Failed to read {}: {}", folder, e)))?;
+    let messages: Vec<Value> = emails.iter().map(|email| json!({
+        "uri": message_uri(account, folder, email.uid),
+        "uid": email.uid,
+        "subject": email.subject,
+        "from_address": email.from_address,
+        "from_name": email.from_name,
+        "date": email.date,
+        "flags": email.flags,
+        "snippet": email.snippet
+    })).collect();
+    let text = serde_json::to_string(&json!({ "folder": folder, "messages": messages }))
+        .map_err(|e| ResourceError::Internal(e.to_string()))?;
+    Ok(json!([{ "uri": uri, "mimeType": "application/json", "text": text }]))
+}
+
+/// The message as plain text with its main headers on top, plus the HTML
+/// body when it has one. Falls back to the raw message from the server
+/// when it isn't cached.
+async fn read_message(state: &DashboardState, uri: &str, account: &str, folder: &str, uid: u32) -> Result<Value, ResourceError> {
+    let cached = state.cache_service.get_cached_email(folder, uid, account).await
+        .map_err(|e| ResourceError::Internal(format!("Failed to read message {}: {}", uid, e)))?;
+
+    let Some(email) = cached else {
+        let raw = state.email_service.fetch_raw_message_for_account(folder, uid, account).await
+            .map_err(|e| match e {
+                EmailServiceError::ImapError(ImapError::EmailNotFound(_) | ImapError::MissingData(_))
+                | EmailServiceError::AccountNotFound(_) => ResourceError::NotFound(format!("Email {} not found in {}", uid, folder)),
+                e => ResourceError::Internal(format!("Failed to fetch message {}: {}", uid, e)),
+            })?;
+        return Ok(match String::from_utf8(raw) {
+            Ok(text) => json!([{ "uri": uri, "mimeType": "message/rfc822", "text": text }]),
+            Err(e) => json!([{
+                "uri": uri,
+                "mimeType": "message/rfc822",
+                "blob": base64::engine::general_purpose::STANDARD.encode(e.into_bytes())
+            }]),
+        });
+    };
+
+    let mut text = String::new();
+    if let Some(name) = email.from_name.as_deref().filter(|n| !n.is_empty()) {
+        text.push_str(&format!("From: {} <{}>\n", name, email.from_address.as_deref().unwrap_or("")));
+    } else if let Some(address) = &email.from_address {
+        text.push_str(&format!("From: {}\n", address));
+    }
+    if !email.to_addresses.is_empty() {
+        text.push_str(&format!("To: {}\n", email.to_addresses.join(", ")));
+    }
+    if let Some(date) = email.date.or(email.internal_date) {
+        text.push_str(&format!("Date: {}\n", date.to_rfc2822()));
+    }
+    text.push_str(&format!("Subject: {}\n\n", email.subject.as_deref().unwrap_or("")));
+    text.push_str(&plain_text::best_plain_text(email.body_text.as_deref(), email.body_html.as_deref()).0);
+
+    let mut contents = vec![json!({ "uri": uri, "mimeType": "text/plain", "text": text })];
+    if let Some(html) = email.body_html {
+        contents.push(json!({ "uri": uri, "mimeType": "text/html", "text": html }));
+    }
+    Ok(Value::Array(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_uri_round_trip() {
+        let uri = message_uri("me@example.com", "Archive/2024", 42);
+        assert_eq!(uri, "imap://me%40example.com/Archive%2F2024/42");
+        assert_eq!(parse_uri(&uri), Some(ResourceUri::Message {
+            account: "me@example.com".to_string(),
+            folder: "Archive/2024".to_string(),
+            uid: 42,
+        }));
+        assert_eq!(parse_uri(&folder_uri("me@example.com", "INBOX")), Some(ResourceUri::Folder {
+            account: "me@example.com".to_string(),
+            folder: "INBOX".to_string(),
+        }));
+        // Unencoded slashes in the folder are taken as extra segments
+        assert_eq!(parse_uri("imap://me@example.com/INBOX/Sub/7"), None);
+        assert_eq!(parse_uri("imap://me@example.com/INBOX/abc"), None);
+        assert_eq!(parse_uri("imap://me@example.com/"), None);
+        assert_eq!(parse_uri("file:///etc/passwd"), None);
+    }
+}
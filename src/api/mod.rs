@@ -10,10 +10,13 @@ pub mod auth;
 pub mod errors;  // New comprehensive error module
 pub mod openapi_docs;  // OpenAPI documentation
 pub mod rate_limit;  // Rate limiting middleware
+pub mod request_id;  // Request ID middleware and tracing span
 pub mod rest;
 pub mod validation;
 // pub mod sse;
 pub mod mcp_sse;
 pub mod mcp_http;  // MCP Streamable HTTP transport
+pub mod mcp_resources;  // Folders and messages as MCP resources
+pub mod mcp_prompts;  // Built-in MCP prompts
 
 // pub mod sse; // Will be added later 
\ No newline at end of file
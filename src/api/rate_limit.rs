@@ -21,6 +21,8 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use tokio::sync::RwLock;
 
+use crate::api::mcp_http::TransportError;
+
 /// Rate limit configuration loaded from environment variables
 #[derive(Debug, Clone)]
 pub struct RateLimitConfig {
@@ -252,16 +254,26 @@ where
                     let (limit, _, reset) = state.get_limit_info(&client_ip).await;
                     let retry_after = (reset - chrono::Utc::now().timestamp()).max(1);
 
-                    let response = HttpResponse::TooManyRequests()
-                        .insert_header(("X-RateLimit-Limit", limit.to_string()))
-                        .insert_header(("X-RateLimit-Remaining", "0"))
-                        .insert_header(("X-RateLimit-Reset", reset.to_string()))
-                        .insert_header(("Retry-After", retry_after.to_string()))
-                        .json(serde_json::json!({
-                            "error": "rate_limit_exceeded",
-                            "message": message,
-                            "retry_after": retry_after
-                        }));
+                    // MCP clients only understand JSON-RPC error objects
+                    let mut response = if req.path().starts_with("/mcp") {
+                        TransportError::RateLimited { message, retry_after }.to_response(None)
+                    } else {
+                        HttpResponse::TooManyRequests()
+                            .insert_header(("Retry-After", retry_after.to_string()))
+                            .json(serde_json::json!({
+                                "error": "rate_limit_exceeded",
+                                "message": message,
+                                "retry_after": retry_after
+                            }))
+                    };
+                    let headers = response.headers_mut();
+                    if let Ok(val) = HeaderValue::from_str(&limit.to_string()) {
+                        headers.insert(HeaderName::from_static("x-ratelimit-limit"), val);
+                    }
+                    headers.insert(HeaderName::from_static("x-ratelimit-remaining"), HeaderValue::from_static("0"));
+                    if let Ok(val) = HeaderValue::from_str(&reset.to_string()) {
+                        headers.insert(HeaderName::from_static("x-ratelimit-reset"), val);
+                    }
 
                     Ok(req.into_response(response).map_into_right_body())
                 }
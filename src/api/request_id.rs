@@ -0,0 +1,138 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Request ID Middleware
+//!
+//! Gives every request an id, taken from its `X-Request-Id` header when the
+//! caller sent a usable one and generated otherwise. The request is handled
+//! inside a `request` tracing span carrying the id, so every log line it
+//! produces, down to the IMAP session, can be matched up. The id is echoed
+//! in the `X-Request-Id` response header.
+
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+use futures_util::future::{ok, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest caller-supplied id kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id of the request being handled, in the request's extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// The caller's id if it is short printable ASCII, else a new UUID.
+pub fn request_id_from_header(value: Option<&str>) -> String {
+    match value.map(str::trim) {
+        Some(id) if !id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.bytes().all(|b| b.is_ascii_graphic()) => id.to_string(),
+        _ => uuid::Uuid::new_v4().to_string(),
+    }
+}
+
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdMiddlewareService { service: Rc::new(service) })
+    }
+}
+
+pub struct RequestIdMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = request_id_from_header(
+            req.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok())
+        );
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.path(),
+        );
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+
+        // Inner services may log before their future is first polled
+        let fut = span.in_scope(|| self.service.call(req));
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(res)
+        }.instrument(span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[test]
+    fn test_request_id_from_header() {
+        assert_eq!(request_id_from_header(Some(" abc-123 ")), "abc-123");
+        for rejected in [None, Some(""), Some("has space"), Some("naïve")] {
+            let id = request_id_from_header(rejected);
+            assert!(uuid::Uuid::parse_str(&id).is_ok(), "{:?} should get a generated id", rejected);
+        }
+        let long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+        assert_ne!(request_id_from_header(Some(&long)), long);
+    }
+
+    #[actix_web::test]
+    async fn test_request_id_header_echoed() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestIdMiddleware)
+                .route("/", web::get().to(|req: actix_web::HttpRequest| async move {
+                    let id = req.extensions().get::<RequestId>().map(|id| id.0.clone()).unwrap_or_default();
+                    HttpResponse::Ok().body(id)
+                }))
+        ).await;
+
+        let req = test::TestRequest::get().uri("/").insert_header((REQUEST_ID_HEADER, "agent-42")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "agent-42");
+        assert_eq!(test::read_body(res).await, "agent-42");
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+        assert!(res.headers().contains_key(REQUEST_ID_HEADER));
+    }
+}
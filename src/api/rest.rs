@@ -6,7 +6,7 @@
 use actix_web::{
     web::{self, Data, Json, Path, Query},
     get, post, delete, put, // Added PUT and DELETE
-    App, HttpRequest, HttpResponse, HttpServer,
+    App, HttpMessage, HttpRequest, HttpResponse, HttpServer,
 };
 use actix_web_lab::middleware::from_fn as mw_from_fn;
 use log::info; // Only keep info for now
@@ -17,7 +17,7 @@ use tokio::sync::Mutex as TokioMutex;
 // Crate-local imports
 use crate::{ // Group crate imports
     api::{
-        auth::{ApiKeyStore, ApiScope, simple_validate_api_key},
+        auth::{ApiKey, ApiKeyInfo, ApiKeyStore, ApiScope, JwtValidator, rate_limit_api_key, simple_validate_api_key},
         errors::{ApiError}, // Use new error module
     },
     config::Settings,
@@ -27,7 +27,7 @@ use crate::{ // Group crate imports
         client::ImapClient,
         session::AsyncImapSessionWrapper, // Import session type
         types::{ // Import necessary IMAP types
-            FlagOperation, Flags,
+            FetchBatch, FlagOperation, Flags,
         },
     },
     mcp::handler::McpHandler,
@@ -42,6 +42,8 @@ pub struct AppState {
     pub session_manager: Arc<SessionManager>,
     pub dashboard_state: Option<Arc<TokioMutex<DashboardState>>>,
     pub api_key_store: Arc<ApiKeyStore>,
+    /// Set when `[auth]` configures JWT bearer authentication
+    pub jwt_validator: Option<Arc<JwtValidator>>,
 }
 
 // ApiError is now in the errors module and imported above
@@ -62,6 +64,8 @@ pub fn configure_rest_service(cfg: &mut web::ServiceConfig) {
     // Scope for authenticated IMAP operations
     cfg.service(
         web::scope("/api/v1")
+            // The last wrap runs first: auth below, then this per-key rate limit
+            .wrap(mw_from_fn(rate_limit_api_key))
             .wrap(mw_from_fn(simple_validate_api_key))
             // Folder operations
             .service(list_folders)
@@ -85,11 +89,13 @@ pub fn configure_rest_service(cfg: &mut web::ServiceConfig) {
     // API Key management endpoints (require admin scope)
     cfg.service(
         web::scope("/api/v1/auth")
+            .wrap(mw_from_fn(rate_limit_api_key))
             .wrap(mw_from_fn(simple_validate_api_key))
             .service(get_api_key_info)
             .service(create_api_key)
             .service(revoke_api_key)
             .service(list_api_keys)
+            .service(api_key_usage)
     );
 
     // Dashboard routes are configured separately in the main server setup
@@ -97,24 +103,18 @@ pub fn configure_rest_service(cfg: &mut web::ServiceConfig) {
 
 // --- Helper Functions ---
 
+// The caller as authenticated by the auth middleware: a stored API key or
+// the principal built from a JWT
+fn caller(req: &HttpRequest) -> Result<ApiKey, ApiError> {
+    req.extensions().get::<ApiKey>().cloned().ok_or(ApiError::Unauthorized)
+}
+
 // Helper to get an IMAP session for the account specified by API key
 async fn get_session(state: &AppState, req: &HttpRequest) -> Result<Arc<ImapClient<AsyncImapSessionWrapper>>, ApiError> {
-    // Get API key from headers
-    let api_key = req.headers()
-        .get("X-API-Key")
-        .or_else(|| req.headers().get("Authorization"))
-        .and_then(|h| h.to_str().ok())
-        .map(|s| {
-            if s.starts_with("Bearer ") {
-                &s[7..]
-            } else {
-                s
-            }
-        })
-        .ok_or(ApiError::Unauthorized)?;
-
-    // Get API key data from store
-    let api_key_data = state.api_key_store.validate_key(api_key).await?;
+    let api_key_data = caller(req)?;
+    if api_key_data.imap_credentials.username.is_empty() {
+        return Err(ApiError::InvalidApiKey { reason: "No IMAP account is linked to this identity".to_string() });
+    }
 
     // Try to get existing session
     let session_result = state.session_manager.as_ref().get_session(&api_key_data.key).await;
@@ -291,15 +291,18 @@ async fn list_emails(state: Data<AppState>, req: HttpRequest, path: Path<String>
         .copied()
         .collect();
 
-    // Fetch email headers for the paginated results
-    let emails = if !paginated_uids.is_empty() {
-        session.fetch_emails(&paginated_uids).await?
+    // Fetch email headers for the paginated results; messages that fail to
+    // parse are reported in `errors` rather than failing the whole page,
+    // unless IMAP_FETCH_TOLERATE_PARTIAL is off
+    let batch = if !paginated_uids.is_empty() {
+        session.fetch_batch(&paginated_uids).await?
     } else {
-        vec![]
+        FetchBatch::default()
     };
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "emails": emails,
+        "emails": batch.emails,
+        "errors": batch.errors,
         "total": uids.len(),
         "limit": limit,
         "offset": offset,
@@ -374,17 +377,14 @@ async fn search_emails(state: Data<AppState>, req: HttpRequest, query: Query<Sea
     let _ = session.select_folder(folder).await?;
 
     let search_criteria = query.q.as_deref().unwrap_or("ALL");
-    let uids = session.search_emails(search_criteria).await?;
+    let mut uids = session.search_emails(search_criteria).await?;
+    uids.sort_unstable();
+    uids.dedup();
 
-    // Apply pagination
-    let limit = query.limit.unwrap_or(50).min(100);
+    // Only the newest matches are fetched; `total` counts every distinct match
+    let limit = crate::imap::types::search_result_limit(query.limit);
     let offset = query.offset.unwrap_or(0);
-
-    let paginated_uids: Vec<u32> = uids.iter()
-        .skip(offset)
-        .take(limit)
-        .copied()
-        .collect();
+    let paginated_uids = crate::imap::types::newest_uids(&uids, offset, limit);
 
     let emails = if !paginated_uids.is_empty() {
         session.fetch_emails(&paginated_uids).await?
@@ -395,6 +395,9 @@ async fn search_emails(state: Data<AppState>, req: HttpRequest, query: Query<Sea
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "results": emails,
         "total": uids.len(),
+        "total_matches": uids.len(),
+        "limit": limit,
+        "offset": offset,
         "query": search_criteria,
         "folder": folder,
     })))
@@ -484,19 +487,11 @@ struct MoveEmailRequest {
 // === API Key Management ===
 
 #[get("/keys/current")]
-async fn get_api_key_info(state: Data<AppState>, req: HttpRequest) -> Result<HttpResponse, ApiError> {
+async fn get_api_key_info(req: HttpRequest) -> Result<HttpResponse, ApiError> {
     info!("Handling GET /auth/keys/current");
 
-    // Get API key from headers
-    let api_key = req.headers()
-        .get("X-API-Key")
-        .and_then(|h| h.to_str().ok())
-        .ok_or(ApiError::Unauthorized)?;
-
     // Return key info without sensitive data
-    let info = state.api_key_store.get_key_info(api_key).await?;
-
-    Ok(HttpResponse::Ok().json(info))
+    Ok(HttpResponse::Ok().json(ApiKeyInfo::from(caller(&req)?)))
 }
 
 #[post("/keys")]
@@ -504,12 +499,8 @@ async fn create_api_key(state: Data<AppState>, req: HttpRequest, payload: Json<C
     info!("Handling POST /auth/keys");
 
     // Check if requester has admin scope
-    let api_key = req.headers()
-        .get("X-API-Key")
-        .and_then(|h| h.to_str().ok())
-        .ok_or(ApiError::Unauthorized)?;
-
-    if !state.api_key_store.has_scope(api_key, &ApiScope::Admin).await {
+    let caller = caller(&req)?;
+    if !caller.scopes.contains(&ApiScope::Admin) {
         return Err(ApiError::Unauthorized);
     }
 
@@ -544,6 +535,10 @@ async fn create_api_key(state: Data<AppState>, req: HttpRequest, payload: Json<C
         ]),
     ).await;
 
+    if let Some(rate_limit) = payload.rate_limit.clone() {
+        state.api_key_store.set_rate_limit(&new_key, rate_limit).await?;
+    }
+
     Ok(HttpResponse::Created().json(serde_json::json!({
         "api_key": new_key,
         "message": "API key created successfully",
@@ -557,6 +552,8 @@ struct CreateApiKeyRequest {
     email: String,
     imap_credentials: crate::api::auth::ImapCredentials,
     scopes: Option<Vec<ApiScope>>,
+    /// Defaults to the store's default limit
+    rate_limit: Option<crate::api::auth::RateLimit>,
 }
 
 #[delete("/keys/{key}")]
@@ -565,17 +562,13 @@ async fn revoke_api_key(state: Data<AppState>, req: HttpRequest, path: Path<Stri
     info!("Handling DELETE /auth/keys/{}", key_to_revoke);
 
     // Check if requester has admin scope
-    let api_key = req.headers()
-        .get("X-API-Key")
-        .and_then(|h| h.to_str().ok())
-        .ok_or(ApiError::Unauthorized)?;
-
-    if !state.api_key_store.has_scope(api_key, &ApiScope::Admin).await {
+    let caller = caller(&req)?;
+    if !caller.scopes.contains(&ApiScope::Admin) {
         return Err(ApiError::Unauthorized);
     }
 
     // Don't allow self-revocation
-    if api_key == key_to_revoke {
+    if caller.key == key_to_revoke {
         return Err(ApiError::BadRequest { message: "Cannot revoke your own API key".to_string() });
     }
 
@@ -587,16 +580,12 @@ async fn revoke_api_key(state: Data<AppState>, req: HttpRequest, path: Path<Stri
 }
 
 #[get("/keys")]
-async fn list_api_keys(state: Data<AppState>, req: HttpRequest) -> Result<HttpResponse, ApiError> {
+async fn list_api_keys(_state: Data<AppState>, req: HttpRequest) -> Result<HttpResponse, ApiError> {
     info!("Handling GET /auth/keys");
 
     // Check if requester has admin scope
-    let api_key = req.headers()
-        .get("X-API-Key")
-        .and_then(|h| h.to_str().ok())
-        .ok_or(ApiError::Unauthorized)?;
-
-    if !state.api_key_store.has_scope(api_key, &ApiScope::Admin).await {
+    let caller = caller(&req)?;
+    if !caller.scopes.contains(&ApiScope::Admin) {
         return Err(ApiError::Unauthorized);
     }
 
@@ -608,6 +597,23 @@ async fn list_api_keys(state: Data<AppState>, req: HttpRequest) -> Result<HttpRe
     })))
 }
 
+#[get("/usage")]
+async fn api_key_usage(state: Data<AppState>, req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    info!("Handling GET /auth/usage");
+
+    // Check if requester has admin scope
+    let caller = caller(&req)?;
+    if !caller.scopes.contains(&ApiScope::Admin) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let usage = state.api_key_store.usage().await;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "keys": usage,
+        "count": usage.len()
+    })))
+}
+
 // === Bulk Operations ===
 
 #[post("/folders/{folder_name}/expunge")]
@@ -637,6 +643,7 @@ pub async fn run_server(settings: Settings, mcp_handler: Arc<dyn McpHandler>, se
     // Initialize API key store from environment
     let api_key_store = Arc::new(ApiKeyStore::new());
     api_key_store.init_from_env().await;
+    let jwt_validator = JwtValidator::from_settings(&settings).map(Arc::new);
 
     let app_state = Data::new(AppState {
         settings: Arc::new(settings),
@@ -644,6 +651,7 @@ pub async fn run_server(settings: Settings, mcp_handler: Arc<dyn McpHandler>, se
         session_manager,
         dashboard_state,
         api_key_store: Arc::clone(&api_key_store),
+        jwt_validator,
     });
 
     HttpServer::new(move || {
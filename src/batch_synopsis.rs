@@ -14,6 +14,8 @@ use serde::Serialize;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 
+use crate::dashboard::services::cache_crypto::reveal;
+
 /// Maximum UIDs allowed per batch call.
 const MAX_BATCH_SIZE: usize = 50;
 
@@ -195,7 +197,11 @@ impl BatchSynopsisProcessor {
         }
 
         let rows = query.fetch_all(&self.db_pool).await?;
-        Ok(rows)
+        Ok(rows.into_iter().map(|mut row| {
+            row.subject = reveal(row.subject);
+            row.body_text = reveal(row.body_text);
+            row
+        }).collect())
     }
 }
 
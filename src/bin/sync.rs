@@ -13,6 +13,7 @@
 //!   rustymail-sync                              # Sync all accounts and folders
 //!   rustymail-sync --account <email>            # Sync all folders for one account
 //!   rustymail-sync --account <email> --folder <name>  # Sync one folder for one account
+//!   rustymail-sync --reencrypt                  # Encrypt a plaintext cache (needs CACHE_ENCRYPTION_PASSPHRASE)
 //!
 //! Exit codes:
 //!   0 - Success
@@ -20,6 +21,9 @@
 //!   2 - Another sync is already running (not an error, just informational)
 //!
 //! The main server spawns this binary periodically. SQLite is the communication channel.
+//! Configured webhooks are notified from here directly, since this process
+//! has no event bus: `new_mail` per folder and `sync_completed`/`sync_failed`
+//! per account.
 
 use clap::Parser;
 use log::{info, error, warn, debug};
@@ -27,6 +31,9 @@ use sqlx::{SqlitePool, Row};
 use std::fs::File;
 use std::io::Write as IoWrite;
 use chrono::Utc;
+use rustymail::dashboard::services::cache_crypto::{self, CacheCipher};
+use rustymail::dashboard::services::events::{DashboardEvent, MailboxAction};
+use rustymail::dashboard::services::webhooks::WebhookDispatcher;
 
 // Use jemalloc for consistency with main server
 #[cfg(all(not(target_env = "msvc"), not(feature = "system-alloc"), not(feature = "mimalloc-alloc")))]
@@ -50,6 +57,10 @@ struct Cli {
     /// Force re-sync all emails (ignore last synced UID, re-download everything)
     #[arg(long)]
     force: bool,
+
+    /// Encrypt every plaintext subject and body in the cache, then exit
+    #[arg(long)]
+    reencrypt: bool,
 }
 
 /// Account row from database
@@ -133,7 +144,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
 
     // Initialize logger
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    rustymail::logging::init();
 
     let cli = Cli::parse();
 
@@ -178,6 +189,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let pool = SqlitePool::connect(&cli.database_url).await?;
     info!("Connected to database: {}", cli.database_url);
 
+    let passphrase = cache_crypto::passphrase(None);
+    if cli.reencrypt {
+        let Some(passphrase) = passphrase else {
+            error!("--reencrypt needs CACHE_ENCRYPTION_PASSPHRASE to be set");
+            std::process::exit(1);
+        };
+        let (_, report) = cache_crypto::reencrypt(&pool, &passphrase).await?;
+        info!("Encrypted {} of {} cached emails", report.rows_encrypted, report.rows_scanned);
+        return Ok(());
+    }
+
+    // A plaintext cache with a passphrase set is read-only until --reencrypt
+    let encryption = cache_crypto::prepare(&pool, passphrase.as_deref()).await?;
+    let cipher = encryption.writer()?;
+
     // Build query based on whether we're filtering by account
     let rows = if let Some(ref account_filter) = cli.account {
         sqlx::query(
@@ -226,10 +252,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("Found {} account(s) to sync", accounts.len());
 
+    let webhooks = match rustymail::config::Settings::new(None) {
+        Ok(settings) => WebhookDispatcher::from_settings(&settings, pool.clone()),
+        Err(e) => {
+            warn!("Failed to load settings, webhooks disabled: {}", e);
+            None
+        }
+    };
+
     // Sync each account (or single account if filtered)
     for account in accounts {
-        if let Err(e) = sync_account(&pool, &account, cli.folder.as_deref(), cli.force).await {
-            error!("Failed to sync {}: {}", account.email_address, e);
+        let event = match sync_account(&pool, cipher, webhooks.as_ref(), &account, cli.folder.as_deref(), cli.force).await {
+            Ok((folders_synced, failed_folders)) => DashboardEvent::SyncCompleted {
+                account_id: account.email_address.clone(),
+                folders_synced,
+                failed_folders,
+                timestamp: Utc::now(),
+            },
+            Err(e) => {
+                error!("Failed to sync {}: {}", account.email_address, e);
+                DashboardEvent::SyncFailed {
+                    account_id: account.email_address.clone(),
+                    error: e.to_string(),
+                    timestamp: Utc::now(),
+                }
+            }
+        };
+        // Delivered before exiting, retries included
+        if let Some(webhooks) = &webhooks {
+            webhooks.dispatch(&event).await;
         }
     }
 
@@ -239,7 +290,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 /// Sync folders for a single account
 /// If folder_filter is Some, only sync that specific folder
-async fn sync_account(pool: &SqlitePool, account: &AccountRow, folder_filter: Option<&str>, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+/// Returns how many folders synced and which ones failed
+async fn sync_account(
+    pool: &SqlitePool,
+    cipher: Option<&CacheCipher>,
+    webhooks: Option<&WebhookDispatcher>,
+    account: &AccountRow,
+    folder_filter: Option<&str>,
+    force: bool,
+) -> Result<(usize, Vec<String>), Box<dyn std::error::Error>> {
     let mode = match folder_filter {
         Some(f) => format!("folder {}", f),
         None => "all folders".to_string(),
@@ -280,10 +339,12 @@ async fn sync_account(pool: &SqlitePool, account: &AccountRow, folder_filter: Op
     };
 
     // Sync each folder
+    let mut failed_folders = Vec::new();
     for folder in &folders_to_sync {
-        if let Err(e) = sync_folder(pool, &client, &account.email_address, folder, force).await {
+        if let Err(e) = sync_folder(pool, cipher, webhooks, &client, &account.email_address, folder, force).await {
             warn!("Failed to sync folder {} for {}: {}", folder, account.email_address, e);
             // Continue with other folders (only relevant in all-folders mode)
+            failed_folders.push(folder.clone());
         }
     }
 
@@ -293,12 +354,14 @@ async fn sync_account(pool: &SqlitePool, account: &AccountRow, folder_filter: Op
     }
 
     info!("Finished syncing account: {}", account.email_address);
-    Ok(())
+    Ok((folders_to_sync.len() - failed_folders.len(), failed_folders))
 }
 
 /// Sync a single folder for an account
 async fn sync_folder(
     pool: &SqlitePool,
+    cipher: Option<&CacheCipher>,
+    webhooks: Option<&WebhookDispatcher>,
     client: &rustymail::imap::client::ImapClient<rustymail::imap::session::AsyncImapSessionWrapper>,
     account_email: &str,
     folder_name: &str,
@@ -358,7 +421,7 @@ async fn sync_folder(
         let emails = client.fetch_emails(chunk).await?;
 
         for email in &emails {
-            if let Err(e) = cache_email(pool, folder_name, email, account_email).await {
+            if let Err(e) = cache_email(pool, cipher, folder_name, email, account_email).await {
                 error!("Failed to cache email {}: {}", email.uid, e);
             } else {
                 if email.uid > max_uid {
@@ -381,6 +444,40 @@ async fn sync_folder(
     // Update sync state
     update_sync_state(pool, folder_name, max_uid, account_email).await?;
 
+    // This process has no EventBus, so new mail goes straight to the activity
+    // feed table and the webhooks. The initial backfill of a folder is not
+    // reported.
+    if last_uid_synced > 0 {
+        let new_uids: Vec<u32> = uids.iter().copied().filter(|uid| *uid > last_uid_synced).collect();
+        if !new_uids.is_empty() {
+            let activity = rustymail::dashboard::services::activity::ActivityLogService::from_env(pool.clone());
+            let detail = format!("{} new message(s)", new_uids.len());
+            let now = Utc::now();
+            if let Err(e) = activity.record(
+                account_email,
+                MailboxAction::Received,
+                Some(folder_name),
+                &new_uids,
+                Some(&detail),
+                "sync",
+                now,
+            ).await {
+                warn!("Failed to record activity for {}: {}", account_email, e);
+            }
+            if let Some(webhooks) = webhooks {
+                webhooks.dispatch(&DashboardEvent::MailboxActivity {
+                    account_id: account_email.to_string(),
+                    action: MailboxAction::Received,
+                    folder: Some(folder_name.to_string()),
+                    uids: new_uids,
+                    detail: Some(detail),
+                    source: "sync".to_string(),
+                    timestamp: now,
+                }).await;
+            }
+        }
+    }
+
     info!("Synced {} emails in folder {}", uids.len(), folder_name);
     Ok(())
 }
@@ -567,10 +664,11 @@ async fn update_sync_state(pool: &SqlitePool, folder_name: &str, last_uid: u32,
 /// This matches the schema used by CacheService in cache.rs
 async fn cache_email(
     pool: &SqlitePool,
+    cipher: Option<&CacheCipher>,
     folder_name: &str,
     email: &rustymail::imap::Email,
     account_id: &str,
-) -> Result<(), sqlx::Error> {
+) -> Result<(), Box<dyn std::error::Error>> {
     // Get or create folder_id first
     let folder_id = get_or_create_folder_id(pool, folder_name, account_id).await?;
 
@@ -627,6 +725,19 @@ async fn cache_email(
         })
     });
 
+    let snippet = rustymail::utils::plain_text::generate_snippet(email);
+
+    // Encrypt the private columns when the cache is encrypted at rest (matches cache.rs)
+    let (subject, text_body, html_body, snippet) = match cipher {
+        Some(cipher) => (
+            cipher.seal_opt(subject.as_deref())?,
+            cipher.seal_opt(email.text_body.as_deref())?,
+            cipher.seal_opt(email.html_body.as_deref())?,
+            cipher.seal(&snippet)?,
+        ),
+        None => (subject, email.text_body.clone(), email.html_body.clone(), snippet),
+    };
+
     // Insert or update email in database (matches cache.rs schema)
     sqlx::query(
         r#"
@@ -634,8 +745,8 @@ async fn cache_email(
             folder_id, uid, message_id, subject, from_address, from_name,
             to_addresses, cc_addresses, date, internal_date, size, flags,
             headers, body_text, body_html, has_attachments,
-            in_reply_to, references_header
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            in_reply_to, references_header, snippet
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ON CONFLICT(folder_id, uid) DO UPDATE SET
             message_id = excluded.message_id,
             subject = excluded.subject,
@@ -653,6 +764,7 @@ async fn cache_email(
             has_attachments = excluded.has_attachments,
             in_reply_to = excluded.in_reply_to,
             references_header = excluded.references_header,
+            snippet = excluded.snippet,
             updated_at = CURRENT_TIMESTAMP
         "#
     )
@@ -669,11 +781,12 @@ async fn cache_email(
     .bind(email.body.as_ref().map(|b| b.len() as i64))
     .bind(&flags_json)
     .bind("{}")  // headers placeholder
-    .bind(&email.text_body)
-    .bind(&email.html_body)
+    .bind(&text_body)
+    .bind(&html_body)
     .bind(has_attachments)
     .bind(&in_reply_to)
     .bind(&references_header)
+    .bind(&snippet)
     .execute(pool)
     .await?;
 
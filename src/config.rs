@@ -54,6 +54,131 @@ pub struct DashboardConfig {
     pub path: Option<String>, // Path to static frontend files
 }
 
+/// JWT bearer authentication, accepted alongside API keys. Off unless a
+/// secret, public key or JWKS URL is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Shared secret for HS256 tokens
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+    /// RS256 public key, as PEM text or a path to a PEM file
+    #[serde(default)]
+    pub jwt_public_key: Option<String>,
+    /// JWKS endpoint for RS256 keys, selected by the token's `kid`
+    #[serde(default)]
+    pub jwks_url: Option<String>,
+    /// Required `iss` claim
+    #[serde(default)]
+    pub issuer: Option<String>,
+    /// Required `aud` claim
+    #[serde(default)]
+    pub audience: Option<String>,
+    /// Clock skew tolerated when checking `exp` and `nbf`, in seconds
+    #[serde(default = "default_clock_skew_seconds")]
+    pub clock_skew_seconds: u64,
+    /// Claim holding the granted scopes, as a space-separated string or an array
+    #[serde(default = "default_scope_claim")]
+    pub scope_claim: String,
+}
+
+fn default_clock_skew_seconds() -> u64 {
+    60
+}
+
+fn default_scope_claim() -> String {
+    "scope".to_string()
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: None,
+            jwt_public_key: None,
+            jwks_url: None,
+            issuer: None,
+            audience: None,
+            clock_skew_seconds: default_clock_skew_seconds(),
+            scope_claim: default_scope_claim(),
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Whether any JWT signing key is configured
+    pub fn jwt_enabled(&self) -> bool {
+        self.jwt_secret.as_deref().is_some_and(|s| !s.is_empty())
+            || self.jwt_public_key.as_deref().is_some_and(|s| !s.is_empty())
+            || self.jwks_url.as_deref().is_some_and(|s| !s.is_empty())
+    }
+}
+
+/// At-rest encryption of the email cache (`[cache_encryption]` section).
+/// Needs the `cache-encryption` feature.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheEncryptionConfig {
+    /// Passphrase the cache key is derived from; unset leaves the cache in plaintext
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+/// DKIM signing of outgoing mail (`[dkim]` section).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DkimConfig {
+    /// One signing key per sending domain
+    #[serde(default)]
+    pub domains: Vec<DkimDomainConfig>,
+    /// Header names to sign; empty uses the built-in list. From is always signed.
+    #[serde(default)]
+    pub signed_headers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DkimDomainConfig {
+    /// Domain of the From address, the `d=` tag
+    pub domain: String,
+    /// Selector the public key is published under (`<selector>._domainkey.<domain>`)
+    pub selector: String,
+    /// RSA private key, as PEM text or a path to a PEM file
+    pub private_key: String,
+}
+
+/// TLS verification for IMAP and ManageSieve connections (`[tls]` section,
+/// or `tls` on an account, which then replaces the section for it).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Oldest version to negotiate, "1.2" unless set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<String>,
+    /// PEM file of extra CA certificates to trust, for servers on a private PKI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_path: Option<String>,
+    /// SHA-256 fingerprint of the server certificate, hex with or without
+    /// colons; comma-separate several to allow for a rotation. Checked on
+    /// top of normal verification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_sha256: Option<String>,
+    /// Accept certificates issued for another host name. Insecure.
+    #[serde(default)]
+    pub accept_invalid_hostnames: bool,
+    /// Comma-separated IANA cipher suite names to offer, e.g.
+    /// "TLS13_AES_256_GCM_SHA384, TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384";
+    /// all of rustls' suites unless set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cipher_suites: Option<String>,
+}
+
+/// Endpoint notified of mail and sync events (`[[webhooks]]` entries).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key for the HMAC-SHA256 `X-Signature` header
+    pub secret: String,
+    /// SSE event type names to deliver; empty means new_mail, sync_completed
+    /// and sync_failed
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub interface: InterfaceType,
@@ -67,6 +192,25 @@ pub struct Settings {
     pub sse: Option<SseConfig>, // SSE configuration
     pub dashboard: Option<DashboardConfig>, // Dashboard configuration
     pub api_key: Option<String>, // API key for authentication
+    /// When true, account-scoped tools and endpoints reject requests that omit
+    /// `account_id` instead of falling back to the default account
+    #[serde(default)]
+    pub require_explicit_account: bool,
+    /// JWT bearer authentication (`[auth]` section)
+    #[serde(default)]
+    pub auth: Option<AuthConfig>,
+    /// Cache encryption at rest (`[cache_encryption]` section)
+    #[serde(default)]
+    pub cache_encryption: Option<CacheEncryptionConfig>,
+    /// DKIM signing keys (`[dkim]` section)
+    #[serde(default)]
+    pub dkim: Option<DkimConfig>,
+    /// TLS verification for IMAP and ManageSieve (`[tls]` section)
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Webhook endpoints (`[[webhooks]]` entries)
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
 }
 
 impl Settings {
@@ -87,6 +231,7 @@ impl Settings {
 
             // Dashboard defaults
             .set_default("dashboard.enabled", false)?
+            .set_default("require_explicit_account", false)?
             // Log defaults
             .set_default("log.level", "info")?;
         
@@ -119,6 +264,15 @@ impl Settings {
             ("DASHBOARD_ENABLED", "dashboard.enabled"),
             ("DASHBOARD_PORT", "dashboard.port"),
             ("DASHBOARD_PATH", "dashboard.path"),
+            ("REQUIRE_EXPLICIT_ACCOUNT", "require_explicit_account"),
+            ("LOG_LEVEL", "log.level"),
+            ("JWT_SECRET", "auth.jwt_secret"),
+            ("JWT_PUBLIC_KEY", "auth.jwt_public_key"),
+            ("JWT_JWKS_URL", "auth.jwks_url"),
+            ("JWT_ISSUER", "auth.issuer"),
+            ("JWT_AUDIENCE", "auth.audience"),
+            ("JWT_CLOCK_SKEW_SECONDS", "auth.clock_skew_seconds"),
+            ("CACHE_ENCRYPTION_PASSPHRASE", "cache_encryption.passphrase"),
         ];
         
         for (env_var, config_path) in &env_vars {
@@ -130,7 +284,13 @@ impl Settings {
                     } else {
                         warn!("Invalid port value in {}: {}", env_var, value);
                     }
-                } else if *env_var == "DASHBOARD_ENABLED" || *env_var == "REST_ENABLED" || *env_var == "SSE_ENABLED" {
+                } else if *env_var == "JWT_CLOCK_SKEW_SECONDS" {
+                    if let Ok(seconds) = value.parse::<u64>() {
+                        config_builder = config_builder.set_override(config_path, seconds)?;
+                    } else {
+                        warn!("Invalid number of seconds in {}: {}", env_var, value);
+                    }
+                } else if *env_var == "DASHBOARD_ENABLED" || *env_var == "REST_ENABLED" || *env_var == "SSE_ENABLED" || *env_var == "REQUIRE_EXPLICIT_ACCOUNT" {
                     if let Ok(enabled) = value.parse::<bool>() {
                         config_builder = config_builder.set_override(config_path, enabled)?;
                     } else {
@@ -220,6 +380,15 @@ impl Default for Settings {
                 std::env::var("RUSTYMAIL_API_KEY")
                     .expect("RUSTYMAIL_API_KEY environment variable must be set")
             ),
+            require_explicit_account: std::env::var("REQUIRE_EXPLICIT_ACCOUNT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            auth: None,
+            cache_encryption: None,
+            dkim: None,
+            tls: None,
+            webhooks: Vec::new(),
         }
     }
 }
@@ -4,16 +4,18 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::collections::VecDeque;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use crossbeam::queue::ArrayQueue;
 use dashmap::DashMap;
 use log::{debug, error, info, warn};
+use rand::Rng;
+use serde::Serialize;
 use thiserror::Error;
-use tokio::sync::{Mutex as TokioMutex, Semaphore};
+use tokio::sync::{Mutex as TokioMutex, Notify, Semaphore};
 use tokio::time::sleep;
 use uuid::Uuid;
 
@@ -30,6 +32,19 @@ pub enum PoolError {
     Unhealthy,
     #[error("Pool is shutting down")]
     ShuttingDown,
+    #[error("Circuit open after repeated connection failures; retry in {}s", .0.as_secs().max(1))]
+    CircuitOpen(Duration),
+}
+
+impl From<PoolError> for ImapError {
+    fn from(err: PoolError) -> Self {
+        match err {
+            PoolError::CircuitOpen(retry_after) => ImapError::CircuitOpen(format!(
+                "not connecting for another {}s after repeated failures", retry_after.as_secs().max(1)
+            )),
+            other => ImapError::Connection(other.to_string()),
+        }
+    }
 }
 
 /// Configuration for the connection pool
@@ -41,14 +56,38 @@ pub struct PoolConfig {
     pub max_connections: usize,
     /// Time before an idle connection is closed
     pub idle_timeout: Duration,
-    /// Time between health checks
+    /// Time between health checks of an idle connection
     pub health_check_interval: Duration,
+    /// Fraction each connection's check interval is randomly moved by
+    /// (0.2 = ±20%), so connections aren't all checked in the same instant
+    pub health_check_jitter: f64,
+    /// Consecutive failed health checks before a connection is discarded
+    pub max_validation_failures: u32,
+    /// Longest wait before re-checking a connection that failed validation
+    pub validation_backoff_cap: Duration,
     /// Maximum wait time for acquiring a connection
     pub acquire_timeout: Duration,
     /// Maximum duration a session can be active
     pub max_session_duration: Duration,
     /// Maximum number of concurrent connection creations allowed
     pub max_concurrent_creations: usize,
+    /// Connections background acquisitions may not take, kept free for interactive requests
+    pub reserved_interactive: usize,
+    /// Consecutive create/validate failures that open the circuit breaker (0 disables it)
+    pub breaker_failure_threshold: usize,
+    /// How long an open circuit fails fast before letting one probe through
+    pub breaker_cooldown: Duration,
+    /// Negotiate COMPRESS=DEFLATE on new connections when the server offers it
+    pub compress_deflate: bool,
+}
+
+/// Who is asking for a connection. Background work (sync, bulk jobs) is
+/// capped below `max_connections` so interactive requests from the API and
+/// MCP tools are not left waiting behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcquirePriority {
+    Interactive,
+    Background,
 }
 
 impl Default for PoolConfig {
@@ -71,6 +110,21 @@ impl Default for PoolConfig {
                     .and_then(|v| v.parse().ok())
                     .unwrap_or(30)
             ),
+            health_check_jitter: std::env::var("POOL_HEALTH_CHECK_JITTER_PERCENT")
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .map(|percent| (percent / 100.0).clamp(0.0, 1.0))
+                .unwrap_or(0.2),
+            max_validation_failures: std::env::var("POOL_HEALTH_CHECK_MAX_FAILURES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            validation_backoff_cap: Duration::from_secs(
+                std::env::var("POOL_HEALTH_CHECK_BACKOFF_MAX_SECONDS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60)
+            ),
             acquire_timeout: Duration::from_secs(
                 std::env::var("POOL_ACQUIRE_TIMEOUT_SECONDS")
                     .ok()
@@ -87,6 +141,193 @@ impl Default for PoolConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(10),
+            reserved_interactive: std::env::var("POOL_RESERVED_INTERACTIVE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2),
+            breaker_failure_threshold: std::env::var("POOL_BREAKER_FAILURE_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            breaker_cooldown: Duration::from_secs(
+                std::env::var("POOL_BREAKER_COOLDOWN_SECONDS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(30)
+            ),
+            compress_deflate: crate::imap::compress::deflate_requested(),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Sizing for one account's sub-pool in a `MultiAccountPool`. Every
+    /// pooled connection holds one of the account's connection slots (see
+    /// `imap::connection_limits`), so the default leaves room for sync and
+    /// IDLE next to it.
+    ///
+    /// - `ACCOUNT_POOL_MIN_CONNECTIONS` (default 0): connections kept open
+    ///   even when idle
+    /// - `ACCOUNT_POOL_MAX_CONNECTIONS` (default 2)
+    pub fn per_account() -> Self {
+        let env = |name: &str, default: usize| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        let max_connections = env("ACCOUNT_POOL_MAX_CONNECTIONS", 2).max(1);
+        Self {
+            min_connections: env("ACCOUNT_POOL_MIN_CONNECTIONS", 0).min(max_connections),
+            max_connections,
+            max_concurrent_creations: max_connections,
+            reserved_interactive: 0,
+            ..Self::default()
+        }
+    }
+}
+
+/// State of a pool's circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Connections are attempted normally
+    Closed,
+    /// Recent attempts all failed; new connections fail fast until the cooldown ends
+    Open,
+    /// Cooldown over; one probe connection is being attempted
+    HalfOpen,
+}
+
+/// Breaker state as reported by `ConnectionPool::pool_status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStatus {
+    pub circuit: CircuitState,
+    pub consecutive_failures: usize,
+    /// Seconds until an open circuit lets a probe through
+    pub retry_after_seconds: Option<u64>,
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: CircuitState,
+    consecutive_failures: usize,
+    opened_at: Option<Instant>,
+}
+
+/// Stops a pool from hammering a server that is down. After
+/// `breaker_failure_threshold` consecutive failed creates or health checks
+/// the circuit opens and new connections fail at once with
+/// `PoolError::CircuitOpen`. Once `breaker_cooldown` has passed a single
+/// probe is let through: success closes the circuit, failure opens it for
+/// another cooldown. Connections already in the pool stay usable.
+#[derive(Debug)]
+struct CircuitBreaker {
+    threshold: usize,
+    cooldown: Duration,
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            inner: Mutex::new(BreakerInner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerInner> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Whether a connection attempt may go ahead. An open circuit whose
+    /// cooldown has passed turns half-open and admits this caller as the probe.
+    fn try_pass(&self) -> Result<(), PoolError> {
+        if self.threshold == 0 {
+            return Ok(());
+        }
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::HalfOpen => Err(PoolError::CircuitOpen(self.cooldown)),
+            CircuitState::Open => {
+                let elapsed = inner.opened_at.map(|t| t.elapsed()).unwrap_or(self.cooldown);
+                if elapsed >= self.cooldown {
+                    inner.state = CircuitState::HalfOpen;
+                    debug!("Circuit half-open; letting one probe connection through");
+                    Ok(())
+                } else {
+                    Err(PoolError::CircuitOpen(self.cooldown - elapsed))
+                }
+            }
+        }
+    }
+
+    fn record_success(&self) {
+        let mut inner = self.lock();
+        if inner.state != CircuitState::Closed {
+            info!("Connection succeeded; closing circuit");
+        }
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+    }
+
+    fn record_failure(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+        let mut inner = self.lock();
+        inner.consecutive_failures += 1;
+        let reopen = inner.state == CircuitState::HalfOpen;
+        if reopen || (inner.state == CircuitState::Closed && inner.consecutive_failures >= self.threshold) {
+            warn!("Opening circuit after {} consecutive connection failures; failing fast for {:?}",
+                  inner.consecutive_failures, self.cooldown);
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn status(&self) -> PoolStatus {
+        let inner = self.lock();
+        let retry_after_seconds = match (inner.state, inner.opened_at) {
+            (CircuitState::Open, Some(opened_at)) => Some(self.cooldown.saturating_sub(opened_at.elapsed()).as_secs()),
+            _ => None,
+        };
+        PoolStatus {
+            circuit: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            retry_after_seconds,
+        }
+    }
+}
+
+/// One connection attempt counted by the breaker. Dropping it without
+/// `succeeded()` counts as a failure, so an attempt abandoned by a timeout
+/// is not lost and a half-open probe can't stay pending forever.
+struct BreakerAttempt<'a> {
+    breaker: &'a CircuitBreaker,
+    finished: bool,
+}
+
+impl<'a> BreakerAttempt<'a> {
+    fn start(breaker: &'a CircuitBreaker) -> Result<Self, PoolError> {
+        breaker.try_pass()?;
+        Ok(Self { breaker, finished: false })
+    }
+
+    fn succeeded(mut self) {
+        self.finished = true;
+        self.breaker.record_success();
+    }
+}
+
+impl Drop for BreakerAttempt<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.breaker.record_failure();
         }
     }
 }
@@ -100,10 +341,14 @@ struct PooledConnection {
     last_used: Instant,
     is_healthy: bool,
     in_use: bool,
+    /// When the health check loop next validates this connection
+    next_check: Instant,
+    /// Health checks failed in a row; reset by a passing check
+    failed_checks: u32,
 }
 
 impl PooledConnection {
-    fn new(client: Arc<ImapClient<AsyncImapSessionWrapper>>) -> Self {
+    fn new(client: Arc<ImapClient<AsyncImapSessionWrapper>>, first_check: Duration) -> Self {
         let now = Instant::now();
         Self {
             id: Uuid::new_v4(),
@@ -112,6 +357,8 @@ impl PooledConnection {
             last_used: now,
             is_healthy: true,
             in_use: false,
+            next_check: now + first_check,
+            failed_checks: 0,
         }
     }
 
@@ -154,6 +401,14 @@ impl SessionHandle {
     pub fn client(&self) -> &Arc<ImapClient<AsyncImapSessionWrapper>> {
         &self.client
     }
+
+    /// Give the connection back marked unhealthy, so it is logged out and
+    /// replaced instead of being handed to the next caller.
+    pub fn discard(self) {
+        if let Some(mut conn_ref) = self.pool.connections.get_mut(&self.connection_id) {
+            conn_ref.is_healthy = false;
+        }
+    }
 }
 
 impl Drop for SessionHandle {
@@ -208,11 +463,36 @@ impl ConnectionFactory for ImapConnectionFactory {
         Ok(Arc::new(client))
     }
 
-    async fn validate(&self, _client: &Arc<ImapClient<AsyncImapSessionWrapper>>) -> bool {
-        // TODO: Implement proper connection validation
-        // For now, we'll rely on operations failing when connections are bad
-        // and the reconnection logic will handle it
-        true
+    async fn validate(&self, client: &Arc<ImapClient<AsyncImapSessionWrapper>>) -> bool {
+        client.noop().await.is_ok()
+    }
+}
+
+/// Factory for one account's sub-pool: logs in with the account's stored
+/// credentials (password or XOAUTH2) through the shared session factory.
+pub struct AccountConnectionFactory {
+    session_factory: crate::imap::CloneableImapSessionFactory,
+    account: crate::dashboard::services::account::Account,
+}
+
+impl AccountConnectionFactory {
+    pub fn new(
+        session_factory: crate::imap::CloneableImapSessionFactory,
+        account: crate::dashboard::services::account::Account,
+    ) -> Self {
+        Self { session_factory, account }
+    }
+}
+
+#[async_trait]
+impl ConnectionFactory for AccountConnectionFactory {
+    async fn create(&self) -> Result<Arc<ImapClient<AsyncImapSessionWrapper>>, ImapError> {
+        let client = self.session_factory.create_session_for_account(&self.account).await?;
+        Ok(Arc::new(client))
+    }
+
+    async fn validate(&self, client: &Arc<ImapClient<AsyncImapSessionWrapper>>) -> bool {
+        client.noop().await.is_ok()
     }
 }
 
@@ -240,6 +520,14 @@ pub struct ConnectionPool {
     /// High-concurrency metrics
     acquire_timeouts: Arc<AtomicUsize>,
     creation_failures: Arc<AtomicUsize>,
+    /// Set once the initial pre-warm pass has finished (successfully or not)
+    prewarm_complete: Arc<AtomicBool>,
+    /// Signalled whenever a connection goes back to the pool
+    slot_released: Arc<Notify>,
+    /// Background acquisitions that had to wait for a non-reserved slot
+    background_waits: Arc<AtomicUsize>,
+    /// Fails connection attempts fast while the server keeps refusing them
+    breaker: CircuitBreaker,
 }
 
 impl ConnectionPool {
@@ -247,6 +535,12 @@ impl ConnectionPool {
     pub fn new(factory: Arc<dyn ConnectionFactory>, config: PoolConfig) -> Arc<Self> {
         let semaphore = Arc::new(Semaphore::new(config.max_connections));
         let creation_semaphore = Arc::new(Semaphore::new(config.max_concurrent_creations));
+        if config.reserved_interactive >= config.max_connections {
+            warn!(
+                "POOL_RESERVED_INTERACTIVE ({}) leaves no room for background work with {} max connections; background is limited to one",
+                config.reserved_interactive, config.max_connections
+            );
+        }
         // Use max_connections as queue capacity - should be sufficient
         let available_queue = Arc::new(ArrayQueue::new(config.max_connections));
 
@@ -264,6 +558,10 @@ impl ConnectionPool {
             current_active: Arc::new(AtomicUsize::new(0)),
             acquire_timeouts: Arc::new(AtomicUsize::new(0)),
             creation_failures: Arc::new(AtomicUsize::new(0)),
+            prewarm_complete: Arc::new(AtomicBool::new(false)),
+            slot_released: Arc::new(Notify::new()),
+            background_waits: Arc::new(AtomicUsize::new(0)),
+            breaker: CircuitBreaker::new(config.breaker_failure_threshold, config.breaker_cooldown),
         });
 
         // Start background tasks
@@ -285,6 +583,8 @@ impl ConnectionPool {
                     warn!("Failed to pre-warm connection: {}", e);
                 }
             }
+            pool_clone.prewarm_complete.store(true, Ordering::SeqCst);
+            debug!("Connection pool pre-warm pass complete");
         });
 
         pool
@@ -300,16 +600,23 @@ impl ConnectionPool {
         let _creation_permit = self.creation_semaphore.acquire().await
             .map_err(|_| PoolError::PoolExhausted)?;
 
-        // Create new connection
+        // Create new connection, unless the breaker says the server is down
+        let attempt = BreakerAttempt::start(&self.breaker)?;
         let client = match self.factory.create().await {
-            Ok(client) => client,
+            Ok(client) => {
+                attempt.succeeded();
+                if self.config.compress_deflate {
+                    client.enable_compression().await;
+                }
+                client
+            }
             Err(e) => {
                 self.creation_failures.fetch_add(1, Ordering::SeqCst);
                 return Err(PoolError::ConnectionFailed(e.to_string()));
             }
         };
 
-        let conn = PooledConnection::new(client);
+        let conn = PooledConnection::new(client, self.next_check_delay());
         let conn_id = conn.id;
 
         // Add to connections map (lock-free)
@@ -359,8 +666,52 @@ impl ConnectionPool {
         }
     }
 
-    /// Acquire a session handle from the pool (optimized for high concurrency)
+    /// Most connections background work may hold at once. Always at least
+    /// one, so a misconfigured reservation can't stall sync entirely.
+    fn background_limit(&self) -> usize {
+        self.config.max_connections
+            .saturating_sub(self.config.reserved_interactive)
+            .max(1)
+    }
+
+    /// Wait until fewer than `background_limit()` connections are in use, or
+    /// fail with `PoolExhausted` after `acquire_timeout`. The check is not
+    /// atomic with the acquisition that follows, so the reservation is soft:
+    /// a burst of background callers can briefly overshoot by a connection or two.
+    async fn wait_for_background_slot(&self) -> Result<(), PoolError> {
+        let limit = self.background_limit();
+        let deadline = tokio::time::Instant::now() + self.config.acquire_timeout;
+        let mut counted = false;
+
+        loop {
+            // Register for the wakeup before checking so a release in between isn't missed
+            let released = self.slot_released.notified();
+            if self.current_active.load(Ordering::SeqCst) < limit {
+                return Ok(());
+            }
+            if !counted {
+                counted = true;
+                self.background_waits.fetch_add(1, Ordering::SeqCst);
+                debug!("Background acquisition waiting: {} connections in use, limit {}",
+                       self.current_active.load(Ordering::SeqCst), limit);
+            }
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                self.acquire_timeouts.fetch_add(1, Ordering::SeqCst);
+                warn!("Background acquisition timed out; {} connections reserved for interactive use",
+                      self.config.reserved_interactive);
+                return Err(PoolError::PoolExhausted);
+            }
+        }
+    }
+
+    /// Acquire a session handle for an interactive (API/tool) request
     pub async fn acquire(self: Arc<Self>) -> Result<SessionHandle, PoolError> {
+        self.acquire_with_priority(AcquirePriority::Interactive).await
+    }
+
+    /// Acquire a session handle from the pool (optimized for high concurrency).
+    /// Background callers wait while only reserved interactive slots are left.
+    pub async fn acquire_with_priority(self: Arc<Self>, priority: AcquirePriority) -> Result<SessionHandle, PoolError> {
         use tokio::time::{timeout, Instant};
 
         let start_time = Instant::now();
@@ -370,11 +721,15 @@ impl ConnectionPool {
             return Err(PoolError::ShuttingDown);
         }
 
+        if priority == AcquirePriority::Background {
+            self.wait_for_background_slot().await?;
+        }
+
         // Fast path: try to get an available connection (lock-free)
         if let Some(conn_id) = self.available.pop() {
             if let Some(mut conn_ref) = self.connections.get_mut(&conn_id) {
                 // Check if connection is still valid
-                if !conn_ref.is_expired(self.config.idle_timeout) && conn_ref.is_healthy {
+                if !conn_ref.is_expired(self.config.idle_timeout) && conn_ref.is_healthy && !conn_ref.client.is_invalid() {
                     conn_ref.mark_in_use();
                     let client = conn_ref.client.clone();
                     drop(conn_ref); // Release the reference early
@@ -439,6 +794,16 @@ impl ConnectionPool {
 
     /// Release a connection back to the pool (optimized for high concurrency)
     async fn release_connection(&self, connection_id: Uuid) {
+        // A command timed out mid-response: nothing can be sent on this
+        // connection again, not even LOGOUT, so it is dropped outright
+        if self.connections.get(&connection_id).is_some_and(|conn| conn.client.is_invalid()) {
+            self.connections.remove(&connection_id);
+            self.current_active.fetch_sub(1, Ordering::SeqCst);
+            self.slot_released.notify_waiters();
+            warn!("Dropped connection {} after a command timed out on it", connection_id);
+            return;
+        }
+
         if let Some(mut conn_ref) = self.connections.get_mut(&connection_id) {
             conn_ref.mark_available();
             drop(conn_ref); // Release the reference
@@ -451,8 +816,11 @@ impl ConnectionPool {
 
             self.total_released.fetch_add(1, Ordering::SeqCst);
             self.current_active.fetch_sub(1, Ordering::SeqCst);
+            self.slot_released.notify_waiters();
 
             debug!("Released connection {} back to pool", connection_id);
+        } else if *self.is_shutting_down.lock().await {
+            debug!("Connection {} returned after pool shutdown; closing it", connection_id);
         } else {
             warn!("Attempted to release unknown connection {}", connection_id);
         }
@@ -543,43 +911,69 @@ impl ConnectionPool {
         }
     }
 
-    /// Periodic health checking of connections
+    /// Time until a healthy connection's next check: the configured
+    /// interval with jitter.
+    fn next_check_delay(&self) -> Duration {
+        jittered(self.config.health_check_interval, self.config.health_check_jitter)
+    }
+
+    /// Periodic health checking of connections. Each connection has its own
+    /// jittered schedule; one that fails is re-checked after an exponential
+    /// backoff and discarded after `max_validation_failures` failures in a row.
     async fn health_check_loop(self: Arc<Self>) {
+        let tick = self.config.health_check_interval.min(Duration::from_secs(5));
         loop {
             if *self.is_shutting_down.lock().await {
                 break;
             }
 
-            sleep(self.config.health_check_interval).await;
+            sleep(tick).await;
 
+            let now = Instant::now();
             let mut to_check = Vec::new();
-            // Collect connections to health check (lock-free iteration)
+            // Collect connections that are due (lock-free iteration)
             for entry in self.connections.iter() {
                 let (id, conn) = entry.pair();
-                // Only check connections that are available and haven't been checked recently
-                if !conn.in_use && conn.last_used.elapsed() > Duration::from_secs(30) {
+                // Connections used in the last 30s have just proven themselves
+                if !conn.in_use && conn.next_check <= now && conn.last_used.elapsed() > Duration::from_secs(30) {
                     to_check.push((*id, conn.client.clone()));
                 }
             }
 
-            let mut unhealthy_ids = Vec::new();
             let mut to_reconnect = Vec::new();
 
             // Perform actual health checks
             for (id, client) in to_check {
-                if !self.factory.validate(&client).await {
-                    unhealthy_ids.push(id);
-                    to_reconnect.push(id);
-                    warn!("Connection {} failed health check", id);
+                let passed = self.factory.validate(&client).await;
+                if passed {
+                    self.breaker.record_success();
                 } else {
+                    self.breaker.record_failure();
+                }
+
+                let Some(mut conn_ref) = self.connections.get_mut(&id) else {
+                    continue;
+                };
+                if passed {
+                    if conn_ref.failed_checks > 0 {
+                        info!("Connection {} recovered after {} failed health checks", id, conn_ref.failed_checks);
+                    }
+                    conn_ref.failed_checks = 0;
+                    conn_ref.next_check = Instant::now() + self.next_check_delay();
                     debug!("Connection {} passed health check", id);
+                    continue;
                 }
-            }
 
-            // Mark connections as unhealthy (lock-free operations)
-            for id in &unhealthy_ids {
-                if let Some(mut conn_ref) = self.connections.get_mut(id) {
+                conn_ref.failed_checks += 1;
+                if conn_ref.failed_checks >= self.config.max_validation_failures.max(1) {
+                    warn!("Connection {} failed {} health checks in a row, discarding it", id, conn_ref.failed_checks);
                     conn_ref.is_healthy = false;
+                    to_reconnect.push(id);
+                } else {
+                    let backoff = validation_backoff(conn_ref.failed_checks, self.config.validation_backoff_cap);
+                    let delay = jittered(backoff, self.config.health_check_jitter);
+                    warn!("Connection {} failed health check ({} in a row), re-checking in {:?}", id, conn_ref.failed_checks, delay);
+                    conn_ref.next_check = Instant::now() + delay;
                 }
             }
 
@@ -613,9 +1007,20 @@ impl ConnectionPool {
         // Try to create a new connection
         let max_retries = 3;
         for attempt in 1..=max_retries {
+            let breaker_attempt = match BreakerAttempt::start(&self.breaker) {
+                Ok(breaker_attempt) => breaker_attempt,
+                Err(e) => {
+                    debug!("Not reconnecting connection {}: {}", connection_id, e);
+                    return;
+                }
+            };
             match self.factory.create().await {
                 Ok(new_client) => {
-                    let mut new_conn = PooledConnection::new(new_client);
+                    breaker_attempt.succeeded();
+                    if self.config.compress_deflate {
+                        new_client.enable_compression().await;
+                    }
+                    let mut new_conn = PooledConnection::new(new_client, self.next_check_delay());
                     new_conn.id = connection_id; // Reuse the same ID for tracking
 
                     // Add the new connection
@@ -647,7 +1052,12 @@ impl ConnectionPool {
         info!("Shutting down connection pool");
         *self.is_shutting_down.lock().await = true;
 
-        // Clear all connections
+        // Log out idle connections; in-use ones close when their handle drops
+        let idle: Vec<Uuid> = self.connections.iter()
+            .filter(|entry| !entry.value().in_use)
+            .map(|entry| *entry.key())
+            .collect();
+        self.remove_connections_with_logout(idle);
         self.connections.clear();
         // ArrayQueue doesn't have clear(), but we can drain it
         while self.available.pop().is_some() {
@@ -657,6 +1067,64 @@ impl ConnectionPool {
         info!("Connection pool shutdown complete");
     }
 
+    /// Shut the pool down for process exit: refuse new borrowers, give
+    /// borrowed connections until `limit` to come back, then LOGOUT every
+    /// connection and wait for the server to answer (a few seconds at most,
+    /// even if the wait used up `limit`).
+    pub async fn drain(&self, limit: Duration) -> PoolDrain {
+        *self.is_shutting_down.lock().await = true;
+        let deadline = Instant::now() + limit;
+        while self.connections.iter().any(|entry| entry.value().in_use) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            let _ = tokio::time::timeout(left.min(Duration::from_millis(100)), self.slot_released.notified()).await;
+        }
+
+        let mut drain = PoolDrain::default();
+        let ids: Vec<Uuid> = self.connections.iter().map(|entry| *entry.key()).collect();
+        let mut logouts = futures::stream::FuturesUnordered::new();
+        for id in ids {
+            if let Some((_, conn)) = self.connections.remove(&id) {
+                if conn.in_use {
+                    // Closed without LOGOUT when its handle drops
+                    drain.abandoned += 1;
+                } else {
+                    logouts.push(async move { (id, conn.client.logout().await) });
+                }
+            }
+        }
+        while self.available.pop().is_some() {}
+
+        let pending = logouts.len();
+        let budget = deadline.saturating_duration_since(Instant::now()).max(DRAIN_LOGOUT_MIN);
+        let _ = tokio::time::timeout(budget, async {
+            use futures::StreamExt;
+            while let Some((id, result)) = logouts.next().await {
+                match result {
+                    Ok(()) => drain.logged_out += 1,
+                    Err(e) => {
+                        debug!("Logout failed for connection {} during drain: {}", id, e);
+                        drain.logout_failed += 1;
+                    }
+                }
+            }
+        }).await;
+        drain.abandoned += pending - drain.logged_out - drain.logout_failed;
+        drain
+    }
+
+    /// Circuit breaker state, for health reporting.
+    pub fn pool_status(&self) -> PoolStatus {
+        self.breaker.status()
+    }
+
+    /// Whether the initial pre-warm pass has finished
+    pub fn is_warmed(&self) -> bool {
+        self.prewarm_complete.load(Ordering::SeqCst)
+    }
+
     /// Get pool statistics
     pub async fn stats(&self) -> PoolStats {
         let total = self.connections.len();
@@ -673,6 +1141,8 @@ impl ConnectionPool {
             total_released: self.total_released.load(Ordering::SeqCst),
             acquire_timeouts: self.acquire_timeouts.load(Ordering::SeqCst),
             creation_failures: self.creation_failures.load(Ordering::SeqCst),
+            reserved_interactive: self.config.reserved_interactive,
+            background_waits: self.background_waits.load(Ordering::SeqCst),
         }
     }
 
@@ -688,6 +1158,7 @@ impl ConnectionPool {
                     last_used: conn.last_used,
                     is_healthy: conn.is_healthy,
                     in_use: conn.in_use,
+                    failed_checks: conn.failed_checks,
                 }
             })
             .collect()
@@ -738,6 +1209,139 @@ impl ConnectionPool {
     }
 }
 
+/// One `ConnectionPool` per account, created on first use from the
+/// account's stored settings. Each sub-pool sizes, reaps and health-checks
+/// its own connections; evicting an account shuts its sub-pool down.
+pub struct MultiAccountPool {
+    pools: DashMap<String, Arc<ConnectionPool>>,
+    config: PoolConfig,
+}
+
+impl MultiAccountPool {
+    pub fn new(config: PoolConfig) -> Self {
+        Self { pools: DashMap::new(), config }
+    }
+
+    /// Whether account operations borrow pooled connections
+    /// (`ACCOUNT_POOL_ENABLED`, default true) rather than logging in each time.
+    pub fn enabled() -> bool {
+        std::env::var("ACCOUNT_POOL_ENABLED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(true)
+    }
+
+    /// The account's sub-pool, created with `factory` if it doesn't exist yet.
+    pub fn pool_for<F>(&self, account: &str, factory: F) -> Arc<ConnectionPool>
+    where
+        F: FnOnce() -> Arc<dyn ConnectionFactory>,
+    {
+        self.pools
+            .entry(account.to_lowercase())
+            .or_insert_with(|| {
+                debug!("Creating connection pool for account {}", account);
+                ConnectionPool::new(factory(), self.config.clone())
+            })
+            .clone()
+    }
+
+    /// Borrow a connection for an account, creating its sub-pool on first use.
+    /// Background callers (sync, bulk jobs) leave the reserved interactive
+    /// connections alone.
+    pub async fn acquire<F>(&self, account: &str, priority: AcquirePriority, factory: F) -> Result<SessionHandle, PoolError>
+    where
+        F: FnOnce() -> Arc<dyn ConnectionFactory>,
+    {
+        self.pool_for(account, factory).acquire_with_priority(priority).await
+    }
+
+    /// Shut down and forget an account's sub-pool, e.g. after the account was
+    /// deleted or its credentials changed. Returns whether it had one.
+    pub async fn evict(&self, account: &str) -> bool {
+        match self.pools.remove(&account.to_lowercase()) {
+            Some((_, pool)) => {
+                info!("Evicting connection pool for account {}", account);
+                pool.shutdown().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drain every sub-pool at once; see `ConnectionPool::drain`.
+    pub async fn drain(&self, limit: Duration) -> PoolDrain {
+        let pools: Vec<Arc<ConnectionPool>> = self.pools.iter().map(|entry| entry.value().clone()).collect();
+        self.pools.clear();
+        futures::future::join_all(pools.iter().map(|pool| pool.drain(limit))).await
+            .into_iter()
+            .fold(PoolDrain::default(), |total, drain| total + drain)
+    }
+
+    /// Circuit breaker state of every sub-pool, by account.
+    pub fn statuses(&self) -> Vec<(String, PoolStatus)> {
+        let mut statuses: Vec<(String, PoolStatus)> = self.pools.iter()
+            .map(|entry| (entry.key().clone(), entry.value().pool_status()))
+            .collect();
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        statuses
+    }
+
+    /// Statistics of every sub-pool, by account.
+    pub async fn stats(&self) -> Vec<(String, PoolStats)> {
+        let pools: Vec<(String, Arc<ConnectionPool>)> = self.pools.iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        let mut stats = Vec::with_capacity(pools.len());
+        for (account, pool) in pools {
+            stats.push((account, pool.stats().await));
+        }
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        stats
+    }
+}
+
+/// `interval` moved at random by up to `jitter` of itself either way.
+fn jittered(interval: Duration, jitter: f64) -> Duration {
+    if jitter <= 0.0 {
+        return interval;
+    }
+    let factor = 1.0 + rand::thread_rng().gen_range(-jitter..=jitter);
+    interval.mul_f64(factor.max(0.0))
+}
+
+/// Wait before re-checking a connection after `failures` failed checks in
+/// a row: 2s, 4s, 8s, ... up to `cap`.
+fn validation_backoff(failures: u32, cap: Duration) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(failures.max(1))).min(cap)
+}
+
+/// Time LOGOUTs get during a drain whose wait for borrowed connections
+/// used up the limit.
+const DRAIN_LOGOUT_MIN: Duration = Duration::from_secs(3);
+
+/// What happened to a pool's connections when it was drained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolDrain {
+    /// Logged out cleanly
+    pub logged_out: usize,
+    /// LOGOUT failed, usually because the server had already hung up
+    pub logout_failed: usize,
+    /// Still borrowed, or LOGOUT unanswered, when time ran out
+    pub abandoned: usize,
+}
+
+impl std::ops::Add for PoolDrain {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            logged_out: self.logged_out + other.logged_out,
+            logout_failed: self.logout_failed + other.logout_failed,
+            abandoned: self.abandoned + other.abandoned,
+        }
+    }
+}
+
 /// Statistics about the pool
 #[derive(Debug, Clone)]
 pub struct PoolStats {
@@ -750,6 +1354,8 @@ pub struct PoolStats {
     pub total_released: usize,
     pub acquire_timeouts: usize,
     pub creation_failures: usize,
+    pub reserved_interactive: usize,
+    pub background_waits: usize,
 }
 
 /// Information about a session
@@ -760,11 +1366,14 @@ pub struct SessionInfo {
     pub last_used: Instant,
     pub is_healthy: bool,
     pub in_use: bool,
+    /// Health checks failed in a row
+    pub failed_checks: u32,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::imap::{ConnectionMode, ImapTransport};
 
     struct MockConnectionFactory;
 
@@ -790,4 +1399,214 @@ mod tests {
         let stats = pool.stats().await;
         assert_eq!(stats.max_connections, 50);  // Updated to match new memory-optimized default
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_background_limit_leaves_reserved_slots() {
+        let config = PoolConfig {
+            min_connections: 0,
+            max_connections: 10,
+            reserved_interactive: 3,
+            ..PoolConfig::default()
+        };
+        let pool = ConnectionPool::new(Arc::new(MockConnectionFactory), config);
+        assert_eq!(pool.background_limit(), 7);
+
+        let config = PoolConfig {
+            min_connections: 0,
+            max_connections: 2,
+            reserved_interactive: 5,
+            ..PoolConfig::default()
+        };
+        let pool = ConnectionPool::new(Arc::new(MockConnectionFactory), config);
+        assert_eq!(pool.background_limit(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_background_waits_while_only_reserved_slots_remain() {
+        let config = PoolConfig {
+            min_connections: 0,
+            max_connections: 4,
+            reserved_interactive: 2,
+            acquire_timeout: Duration::from_millis(50),
+            ..PoolConfig::default()
+        };
+        let pool = ConnectionPool::new(Arc::new(MockConnectionFactory), config);
+        pool.current_active.store(2, Ordering::SeqCst);
+
+        let result = Arc::clone(&pool).acquire_with_priority(AcquirePriority::Background).await;
+        assert!(matches!(result, Err(PoolError::PoolExhausted)));
+        let stats = pool.stats().await;
+        assert_eq!(stats.background_waits, 1);
+        assert_eq!(stats.reserved_interactive, 2);
+
+        pool.current_active.store(1, Ordering::SeqCst);
+        assert!(pool.wait_for_background_slot().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_multi_account_pool_keeps_accounts_apart() {
+        let config = PoolConfig {
+            min_connections: 0,
+            max_connections: 3,
+            reserved_interactive: 0,
+            ..PoolConfig::default()
+        };
+        let pools = MultiAccountPool::new(config);
+        let factory = || Arc::new(MockConnectionFactory) as Arc<dyn ConnectionFactory>;
+
+        let a = pools.pool_for("A@example.com", factory);
+        let same = pools.pool_for("a@example.com", || panic!("sub-pool should be reused"));
+        assert!(Arc::ptr_eq(&a, &same));
+        let b = pools.pool_for("b@example.com", factory);
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(pools.stats().await.len(), 2);
+
+        assert!(pools.evict("a@example.com").await);
+        assert!(!pools.evict("a@example.com").await);
+        assert!(matches!(Arc::clone(&a).acquire().await, Err(PoolError::ShuttingDown)));
+        assert_eq!(pools.stats().await.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), vec!["b@example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_drain_refuses_new_borrowers() {
+        let config = PoolConfig {
+            min_connections: 0,
+            max_connections: 2,
+            reserved_interactive: 0,
+            ..PoolConfig::default()
+        };
+        let pools = MultiAccountPool::new(config);
+        let pool = pools.pool_for("a@example.com", || Arc::new(MockConnectionFactory) as Arc<dyn ConnectionFactory>);
+
+        let drain = pools.drain(Duration::from_millis(50)).await;
+        assert_eq!(drain, PoolDrain::default());
+        assert!(matches!(Arc::clone(&pool).acquire().await, Err(PoolError::ShuttingDown)));
+        assert!(pools.stats().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast_then_probes() {
+        let config = PoolConfig {
+            min_connections: 0,
+            max_connections: 4,
+            reserved_interactive: 0,
+            breaker_failure_threshold: 2,
+            breaker_cooldown: Duration::from_millis(50),
+            ..PoolConfig::default()
+        };
+        let pool = ConnectionPool::new(Arc::new(MockConnectionFactory), config);
+
+        for _ in 0..2 {
+            assert!(matches!(Arc::clone(&pool).acquire().await, Err(PoolError::ConnectionFailed(_))));
+        }
+        assert_eq!(pool.pool_status().circuit, CircuitState::Open);
+        assert!(matches!(Arc::clone(&pool).acquire().await, Err(PoolError::CircuitOpen(_))));
+        assert_eq!(pool.stats().await.creation_failures, 2);
+        assert!(matches!(ImapError::from(PoolError::CircuitOpen(Duration::from_secs(9))), ImapError::CircuitOpen(_)));
+
+        // After the cooldown one probe reaches the factory; its failure reopens the circuit
+        sleep(Duration::from_millis(60)).await;
+        assert!(matches!(Arc::clone(&pool).acquire().await, Err(PoolError::ConnectionFailed(_))));
+        assert_eq!(pool.stats().await.creation_failures, 3);
+        assert_eq!(pool.pool_status().circuit, CircuitState::Open);
+
+        pool.breaker.record_success();
+        let status = pool.pool_status();
+        assert_eq!(status.circuit, CircuitState::Closed);
+        assert_eq!(status.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_health_check_jitter_and_backoff() {
+        let interval = Duration::from_secs(60);
+        for _ in 0..100 {
+            let delay = jittered(interval, 0.2);
+            assert!(delay >= Duration::from_millis(47_999) && delay <= Duration::from_millis(72_001), "{:?}", delay);
+        }
+        assert_eq!(jittered(interval, 0.0), interval);
+
+        let cap = Duration::from_secs(30);
+        assert_eq!(validation_backoff(1, cap), Duration::from_secs(2));
+        assert_eq!(validation_backoff(3, cap), Duration::from_secs(8));
+        assert_eq!(validation_backoff(10, cap), cap);
+        assert_eq!(validation_backoff(200, cap), cap);
+    }
+
+    /// An IMAP server on localhost that accepts the login and then never
+    /// answers another command, as if it hung mid-session.
+    async fn stalling_server() -> u16 {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (reader, mut writer) = socket.into_split();
+                    let _ = writer.write_all(b"* OK ready\r\n").await;
+                    let mut lines = BufReader::new(reader).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let mut words = line.split_whitespace();
+                        let tag = words.next().unwrap_or("*").to_string();
+                        let reply = match words.next().map(|c| c.to_ascii_uppercase()).as_deref() {
+                            Some("LOGIN") => format!("{} OK LOGIN completed\r\n", tag),
+                            Some("CAPABILITY") => format!("* CAPABILITY IMAP4rev1\r\n{} OK done\r\n", tag),
+                            _ => continue,
+                        };
+                        let _ = writer.write_all(reply.as_bytes()).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    struct StallingConnectionFactory {
+        port: u16,
+    }
+
+    #[async_trait]
+    impl ConnectionFactory for StallingConnectionFactory {
+        async fn create(&self) -> Result<Arc<ImapClient<AsyncImapSessionWrapper>>, ImapError> {
+            let transport = ImapTransport { mode: ConnectionMode::Plaintext, allow_plaintext_auth: true, ..Default::default() };
+            let client = ImapClient::<AsyncImapSessionWrapper>::connect_with_transport("127.0.0.1", self.port, "user", "pass", &transport).await?;
+            Ok(Arc::new(client))
+        }
+
+        async fn validate(&self, _client: &Arc<ImapClient<AsyncImapSessionWrapper>>) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_timeout_invalidates_session() {
+        let port = stalling_server().await;
+        let client = StallingConnectionFactory { port }.create().await.unwrap();
+        assert!(!client.is_invalid());
+
+        let err = client.with_command_timeout(Duration::from_millis(100)).noop().await.unwrap_err();
+        assert!(matches!(err, ImapError::Timeout(_)), "{:?}", err);
+        assert!(client.is_invalid(), "every handle on the session sees the timeout");
+
+        let err = client.list_folders().await.unwrap_err();
+        assert!(matches!(err, ImapError::Connection(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_connection_is_not_returned() {
+        let port = stalling_server().await;
+        let config = PoolConfig { min_connections: 0, max_connections: 2, ..PoolConfig::default() };
+        let pool = ConnectionPool::new(Arc::new(StallingConnectionFactory { port }), config);
+
+        let handle = Arc::clone(&pool).acquire().await.unwrap();
+        let err = handle.client().with_command_timeout(Duration::from_millis(100)).noop().await.unwrap_err();
+        assert!(matches!(err, ImapError::Timeout(_)), "{:?}", err);
+        drop(handle);
+
+        // The handle is released from a spawned task
+        sleep(Duration::from_millis(50)).await;
+        assert!(pool.connections.is_empty());
+        assert!(pool.available.pop().is_none());
+        assert_eq!(pool.current_active.load(Ordering::SeqCst), 0);
+    }
+}
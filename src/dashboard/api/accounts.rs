@@ -5,8 +5,11 @@
 
 use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
-use log::{info, error};
-use crate::dashboard::services::{DashboardState, Account, AutoConfigResult};
+use log::{debug, info, error};
+use crate::dashboard::services::{DashboardState, Account, AccountService, AutoConfigResult};
+use crate::dashboard::services::autodiscovery::AutodiscoveryService;
+use crate::imap::ConnectionMode;
+use tokio::sync::Mutex as TokioMutex;
 
 #[derive(Debug, Deserialize)]
 pub struct AutoConfigRequest {
@@ -20,11 +23,21 @@ pub struct CreateAccountRequest {
     pub display_name: String,
     pub email_address: String,
     pub provider_type: Option<String>,
+    /// Left empty, the server settings are autodiscovered from the address
+    #[serde(default)]
     pub imap_host: String,
+    #[serde(default)]
     pub imap_port: i64,
+    #[serde(default)]
     pub imap_user: String,
     pub imap_pass: String,
+    #[serde(default)]
     pub imap_use_tls: bool,
+    /// `implicit` (default), `starttls` or `plaintext`
+    #[serde(default)]
+    pub imap_connection_mode: ConnectionMode,
+    #[serde(default)]
+    pub imap_allow_plaintext_auth: bool,
     pub smtp_host: Option<String>,
     pub smtp_port: Option<i64>,
     pub smtp_user: Option<String>,
@@ -47,6 +60,8 @@ pub struct UpdateAccountRequest {
     pub imap_user: Option<String>,
     pub imap_pass: Option<String>,
     pub imap_use_tls: Option<bool>,
+    pub imap_connection_mode: Option<ConnectionMode>,
+    pub imap_allow_plaintext_auth: Option<bool>,
     pub smtp_host: Option<String>,
     pub smtp_port: Option<i64>,
     pub smtp_user: Option<String>,
@@ -76,58 +91,47 @@ pub struct AutoConfigResponse {
     pub config: AutoConfigResult,
 }
 
+/// Settings for `email_address`: a stored provider template if one matches
+/// the domain, otherwise whatever autodiscovery finds. The account service
+/// is only locked for the template lookup, not for the network probes.
+async fn discover_settings(
+    account_service: &TokioMutex<AccountService>,
+    email_address: &str,
+) -> Result<AutoConfigResult, String> {
+    let template = account_service.lock().await.auto_configure(email_address).await;
+    match template {
+        Ok(result) if result.provider_found => return Ok(result),
+        Ok(_) => {}
+        Err(e) => debug!("Provider template lookup failed for {}: {}", email_address, e),
+    }
+
+    let autodiscovery_service = AutodiscoveryService::new()
+        .map_err(|e| format!("Failed to initialize autodiscovery: {}", e))?;
+    autodiscovery_service.discover(email_address)
+        .await
+        .map_err(|e| format!("Could not autodiscover email settings: {}", e))
+}
+
 /// Auto-configure email settings based on email address
 pub async fn auto_configure(
-    _state: web::Data<DashboardState>,
+    state: web::Data<DashboardState>,
     req: web::Json<AutoConfigRequest>,
 ) -> HttpResponse {
-    use crate::dashboard::services::autodiscovery::AutodiscoveryService;
-
     info!("Auto-configuring for email: {}", req.email_address);
 
-    // Create autodiscovery service
-    let autodiscovery_service = match AutodiscoveryService::new() {
-        Ok(svc) => svc,
-        Err(e) => {
-            error!("Failed to create autodiscovery service: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": format!("Failed to initialize autodiscovery: {}", e)
-            }));
-        }
-    };
-
-    // Attempt autodiscovery
-    match autodiscovery_service.discover(&req.email_address).await {
+    match discover_settings(&state.account_service, &req.email_address).await {
         Ok(config) => {
-            info!("Autodiscovery successful for {}", req.email_address);
-
-            // Convert EmailConfig to AutoConfigResult
-            let result = AutoConfigResult {
-                provider_found: true,
-                provider_type: Some("Auto-discovered".to_string()),
-                display_name: Some("Auto-discovered".to_string()),
-                imap_host: Some(config.imap_host),
-                imap_port: Some(config.imap_port as i64),
-                imap_use_tls: Some(config.imap_use_tls),
-                smtp_host: config.smtp_host,
-                smtp_port: config.smtp_port.map(|p| p as i64),
-                smtp_use_tls: config.smtp_use_tls,
-                smtp_use_starttls: config.smtp_use_starttls,
-                supports_oauth: false,
-                oauth_provider: None,
-            };
-
+            info!("Autodiscovery successful for {} (source: {:?})", req.email_address, config.discovery_source);
             HttpResponse::Ok().json(AutoConfigResponse {
                 success: true,
-                config: result,
+                config,
             })
         }
         Err(e) => {
             error!("Autodiscovery failed for {}: {}", req.email_address, e);
             HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": format!("Could not autodiscover email settings: {}", e)
+                "error": e
             }))
         }
     }
@@ -140,10 +144,8 @@ pub async fn create_account(
 ) -> HttpResponse {
     info!("Creating account: {}", req.display_name);
 
-    let account_service = state.account_service.lock().await;
-
     // Build Account struct from request
-    let new_account = Account {
+    let mut new_account = Account {
         email_address: req.email_address.clone(),
         id: req.email_address.clone(), // Set id to match email_address
         display_name: req.display_name.clone(),
@@ -153,6 +155,8 @@ pub async fn create_account(
         imap_user: req.imap_user.clone(),
         imap_pass: req.imap_pass.clone(),
         imap_use_tls: req.imap_use_tls,
+        imap_connection_mode: req.imap_connection_mode,
+        imap_allow_plaintext_auth: req.imap_allow_plaintext_auth,
         smtp_host: req.smtp_host.clone(),
         smtp_port: req.smtp_port,
         smtp_user: req.smtp_user.clone(),
@@ -165,9 +169,44 @@ pub async fn create_account(
         oauth_token_expiry: None,
         is_active: true,
         is_default: req.is_default,
+        tls: None,
         connection_status: None, // Will be populated after validation
     };
 
+    // No server given: fill in whatever autodiscovery finds
+    if new_account.imap_host.trim().is_empty() {
+        let config = match discover_settings(&state.account_service, &req.email_address).await {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Autodiscovery failed for new account {}: {}", req.email_address, e);
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("No IMAP host given and {}", e)
+                }));
+            }
+        };
+        new_account.imap_host = config.imap_host.unwrap_or_default();
+        new_account.imap_port = config.imap_port.unwrap_or(993);
+        new_account.imap_use_tls = config.imap_use_tls.unwrap_or(true);
+        if new_account.imap_user.is_empty() {
+            new_account.imap_user = config.username.clone().unwrap_or_else(|| req.email_address.clone());
+        }
+        if new_account.smtp_host.is_none() {
+            new_account.smtp_host = config.smtp_host;
+            new_account.smtp_port = config.smtp_port;
+            new_account.smtp_use_tls = config.smtp_use_tls;
+            new_account.smtp_use_starttls = config.smtp_use_starttls;
+            if new_account.smtp_user.is_none() {
+                new_account.smtp_user = config.username;
+            }
+        }
+        if new_account.provider_type.is_none() {
+            new_account.provider_type = config.provider_type;
+        }
+    }
+
+    let account_service = state.account_service.lock().await;
+
     // Validate connection if requested
     if req.validate_connection.unwrap_or(true) {
         if let Err(e) = account_service.validate_connection(&new_account).await {
@@ -191,6 +230,11 @@ pub async fn create_account(
                 }
             }
 
+            // Push notifications for the new account's watched folders
+            if new_account.is_active {
+                state.sync_service.watch_idle(&new_account.email_address);
+            }
+
             HttpResponse::Ok().json(AccountResponse {
                 success: true,
                 message: "Account created successfully".to_string(),
@@ -283,6 +327,8 @@ pub async fn update_account(
         }
     };
 
+    let previous_email = account.email_address.clone();
+
     // Apply updates from request (only non-None fields)
     if let Some(name) = &req.display_name {
         account.display_name = name.clone();
@@ -311,6 +357,12 @@ pub async fn update_account(
     if let Some(use_tls) = req.imap_use_tls {
         account.imap_use_tls = use_tls;
     }
+    if let Some(mode) = req.imap_connection_mode {
+        account.imap_connection_mode = mode;
+    }
+    if let Some(allow) = req.imap_allow_plaintext_auth {
+        account.imap_allow_plaintext_auth = allow;
+    }
     if let Some(smtp_host) = &req.smtp_host {
         account.smtp_host = Some(smtp_host.clone());
     }
@@ -349,6 +401,20 @@ pub async fn update_account(
                 }
             }
 
+            // Pooled connections still use the old server settings
+            state.email_service.evict_account_connections(&previous_email).await;
+            if account.email_address != previous_email {
+                state.email_service.evict_account_connections(&account.email_address).await;
+            }
+
+            // IDLE watchers follow the account's address and active flag
+            if account.email_address != previous_email || !account.is_active {
+                state.sync_service.stop_idle(&previous_email);
+            }
+            if account.is_active {
+                state.sync_service.watch_idle(&account.email_address);
+            }
+
             HttpResponse::Ok().json(AccountResponse {
                 success: true,
                 message: "Account updated successfully".to_string(),
@@ -374,9 +440,16 @@ pub async fn delete_account(
     info!("Deleting account ID: {}", account_id);
 
     let account_service = state.account_service.lock().await;
+    let email_address = account_service.get_account(&account_id).await
+        .map(|account| account.email_address)
+        .ok();
 
     match account_service.delete_account(&account_id).await {
         Ok(()) => {
+            if let Some(email_address) = email_address {
+                state.sync_service.stop_idle(&email_address);
+                state.email_service.evict_account_connections(&email_address).await;
+            }
             HttpResponse::Ok().json(serde_json::json!({
                 "success": true,
                 "message": "Account deleted successfully"
@@ -497,3 +570,204 @@ pub async fn validate_connection(
         }
     }
 }
+
+/// Reconnect an account now: drop its IDLE sessions, re-read its
+/// credentials and report a test login
+pub async fn refresh_connections(
+    state: web::Data<DashboardState>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let account_id = path.into_inner();
+    info!("Refreshing connections for account ID: {}", account_id);
+
+    state.sync_service.reconnect_idle(&account_id);
+    match state.email_service.refresh_connections_for_account(&account_id).await {
+        Ok(refresh) if refresh.connected => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": refresh
+        })),
+        Ok(refresh) => HttpResponse::BadGateway().json(serde_json::json!({
+            "success": false,
+            "error": format!("Test login failed: {}", refresh.error.as_deref().unwrap_or("unknown error")),
+            "data": refresh
+        })),
+        Err(e) => {
+            error!("Failed to refresh connections for account {}: {}", account_id, e);
+            HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": format!("Account not found: {}", e)
+            }))
+        }
+    }
+}
+
+/// Pagination for the account activity feed
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Get the persisted activity feed (received, sent, moved, deleted, flagged)
+/// for an account, newest first
+pub async fn get_account_activity(
+    state: web::Data<DashboardState>,
+    path: web::Path<String>,
+    query: web::Query<ActivityQuery>,
+) -> HttpResponse {
+    use crate::dashboard::services::activity::ActivityLogService;
+
+    let account_id = path.into_inner();
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+    info!("Getting activity for account ID: {} (limit {}, offset {})", account_id, limit, offset);
+
+    let pool = match state.cache_service.db_pool.as_ref() {
+        Some(pool) => pool.clone(),
+        None => {
+            return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "success": false,
+                "error": "Database not available"
+            }));
+        }
+    };
+
+    match ActivityLogService::from_env(pool).list(&account_id, limit, offset).await {
+        Ok((entries, total)) => {
+            let has_more = offset + (entries.len() as i64) < total;
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "account_id": account_id,
+                "activity": entries,
+                "total": total,
+                "limit": limit,
+                "offset": offset,
+                "has_more": has_more
+            }))
+        },
+        Err(e) => {
+            error!("Failed to get activity for account {}: {}", account_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": format!("Failed to get activity: {}", e)
+            }))
+        }
+    }
+}
+
+fn template_service(state: &DashboardState) -> Result<crate::dashboard::services::templates::TemplateService, HttpResponse> {
+    match state.cache_service.db_pool.as_ref() {
+        Some(pool) => Ok(crate::dashboard::services::templates::TemplateService::new(pool.clone())),
+        None => Err(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "success": false,
+            "error": "Database not available"
+        }))),
+    }
+}
+
+fn template_error_response(e: crate::dashboard::services::templates::TemplateError) -> HttpResponse {
+    use crate::dashboard::services::templates::TemplateError;
+
+    let body = serde_json::json!({
+        "success": false,
+        "error": e.to_string()
+    });
+    match e {
+        TemplateError::NotFound(_) => HttpResponse::NotFound().json(body),
+        TemplateError::AlreadyExists(_) => HttpResponse::Conflict().json(body),
+        TemplateError::Invalid(_) | TemplateError::MissingVariables(_) => HttpResponse::BadRequest().json(body),
+        TemplateError::Database(_) => {
+            error!("Template storage error: {}", e);
+            HttpResponse::InternalServerError().json(body)
+        }
+    }
+}
+
+/// List an account's email templates
+pub async fn list_templates(
+    state: web::Data<DashboardState>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let account_id = path.into_inner();
+    let service = match template_service(&state) {
+        Ok(service) => service,
+        Err(response) => return response,
+    };
+    match service.list(&account_id).await {
+        Ok(templates) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "account_id": account_id,
+            "templates": templates
+        })),
+        Err(e) => template_error_response(e),
+    }
+}
+
+/// Get one email template by name
+pub async fn get_template(
+    state: web::Data<DashboardState>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (account_id, name) = path.into_inner();
+    let service = match template_service(&state) {
+        Ok(service) => service,
+        Err(response) => return response,
+    };
+    match service.get(&account_id, &name).await {
+        Ok(template) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "template": template })),
+        Err(e) => template_error_response(e),
+    }
+}
+
+/// Create an email template ({{variable}} placeholders in subject and bodies)
+pub async fn create_template(
+    state: web::Data<DashboardState>,
+    path: web::Path<String>,
+    input: web::Json<crate::dashboard::services::templates::TemplateInput>,
+) -> HttpResponse {
+    let account_id = path.into_inner();
+    info!("Creating template '{}' for account {}", input.name, account_id);
+    let service = match template_service(&state) {
+        Ok(service) => service,
+        Err(response) => return response,
+    };
+    match service.create(&account_id, &input).await {
+        Ok(template) => HttpResponse::Created().json(serde_json::json!({ "success": true, "template": template })),
+        Err(e) => template_error_response(e),
+    }
+}
+
+/// Replace (and optionally rename) an email template
+pub async fn update_template(
+    state: web::Data<DashboardState>,
+    path: web::Path<(String, String)>,
+    input: web::Json<crate::dashboard::services::templates::TemplateInput>,
+) -> HttpResponse {
+    let (account_id, name) = path.into_inner();
+    info!("Updating template '{}' for account {}", name, account_id);
+    let service = match template_service(&state) {
+        Ok(service) => service,
+        Err(response) => return response,
+    };
+    match service.update(&account_id, &name, &input).await {
+        Ok(template) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "template": template })),
+        Err(e) => template_error_response(e),
+    }
+}
+
+/// Delete an email template
+pub async fn delete_template(
+    state: web::Data<DashboardState>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (account_id, name) = path.into_inner();
+    info!("Deleting template '{}' for account {}", name, account_id);
+    let service = match template_service(&state) {
+        Ok(service) => service,
+        Err(response) => return response,
+    };
+    match service.delete(&account_id, &name).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "success": true })),
+        Err(e) => template_error_response(e),
+    }
+}
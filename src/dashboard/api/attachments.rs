@@ -218,7 +218,10 @@ pub async fn download_attachments_zip(
         &zip_path,
     )
     .await
-    .map_err(|e| ApiError::InternalError(format!("Failed to create ZIP: {}", e)))?;
+    .map_err(|e| match e {
+        attachment_storage::AttachmentError::TooLarge(msg) => ApiError::BadRequest(format!("Failed to create ZIP: {}", msg)),
+        e => ApiError::InternalError(format!("Failed to create ZIP: {}", e)),
+    })?;
 
     info!("Created ZIP archive for message_id: {} at {:?}", message_id, result_path);
 
@@ -227,6 +230,42 @@ pub async fn download_attachments_zip(
         .map_err(|e| ApiError::InternalError(format!("Failed to open ZIP file: {}", e)))
 }
 
+/// Handler for downloading the archive built by a finished ZIP job
+/// (download_email_attachments or download_folder_attachments)
+/// GET /api/attachments/jobs/{job_id}/zip
+pub async fn download_job_archive(
+    path: web::Path<String>,  // job_id
+    state: web::Data<DashboardState>,
+) -> Result<NamedFile, ApiError> {
+    use crate::dashboard::services::jobs::JobStatus;
+
+    let job_id = path.into_inner();
+    debug!("Handling GET /api/attachments/jobs/{}/zip", job_id);
+
+    let zip_path = match state.jobs.get(&job_id).map(|job| job.status.clone()) {
+        Some(JobStatus::Completed(data)) => data.get("zip_path")
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from)
+            .ok_or_else(|| ApiError::NotFound(format!("Job {} did not produce an archive", job_id)))?,
+        Some(JobStatus::Running) => return Err(ApiError::BadRequest(format!("Job {} is still running", job_id))),
+        Some(_) => return Err(ApiError::BadRequest(format!("Job {} did not complete", job_id))),
+        None => return Err(ApiError::NotFound(format!("Job not found: {}", job_id))),
+    };
+
+    // Only serve archives this server wrote to the temp directory
+    let is_our_archive = zip_path.parent() == Some(std::env::temp_dir().as_path())
+        && zip_path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("rustymail_") && n.ends_with(".zip"));
+    if !is_our_archive {
+        error!("Refusing to serve job {} result outside the temp directory: {:?}", job_id, zip_path);
+        return Err(ApiError::NotFound(format!("Job {} did not produce an archive", job_id)));
+    }
+
+    NamedFile::open(&zip_path)
+        .map_err(|e| ApiError::NotFound(format!("Archive for job {} is no longer available: {}", job_id, e)))
+}
+
 /// Handler for downloading an inline attachment by Content-ID
 /// GET /api/attachments/{message_id}/inline/{content_id}
 /// This is used to serve images referenced by cid: URIs in HTML emails
@@ -188,4 +188,12 @@ pub async fn validate_config(state: web::Data<DashboardState>) -> Result<HttpRes
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Re-read the config file and environment, apply what can change live and
+/// report what needs a restart. SIGHUP does the same.
+pub async fn reload_config(state: web::Data<DashboardState>) -> Result<HttpResponse, ApiError> {
+    info!("Reloading configuration on request");
+    let report = crate::dashboard::services::config_reload::reload(&state).await;
+    Ok(HttpResponse::Ok().json(report))
+}
@@ -8,9 +8,10 @@ use actix_web::web::Data;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::convert::Infallible;
-use log::{debug, warn, info, error};
+use tracing::{debug, warn, info, error};
 use crate::dashboard::api::errors::ApiError;
 use crate::dashboard::services::DashboardState;
+use crate::dashboard::services::cache::{EmailCursor, PageStart};
 use crate::dashboard::api::models::{ChatbotQuery, ServerConfig};
 use crate::dashboard::api::sse::EventType;
 use crate::dashboard::services::ai::provider_manager::ProviderConfig;
@@ -103,13 +104,21 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
     vec![
         serde_json::json!({
             "name": "list_folders",
-            "description": "List all email folders in the account",
+            "description": "List all email folders in the account. With include_state, each folder comes with its IMAP state: 'selectable' is false for \\Noselect container folders that cannot hold messages, and 'subscribed' reflects LSUB. Check this before listing emails in a parent folder.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
                     "account_id": {
                         "type": "string",
                         "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "include_state": {
+                        "type": "boolean",
+                        "description": "Optional. Return {name, selectable, subscribed} objects instead of names (default: false)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional, with include_state. Return only this folder's state"
                     }
                 },
                 "required": ["account_id"]
@@ -117,7 +126,7 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
         }),
         serde_json::json!({
             "name": "list_folders_hierarchical",
-            "description": "List folders with hierarchical structure",
+            "description": "List folders as a nested tree built with the server's hierarchy delimiter. Each node has name, full_path, children, attributes and cached unread count; levels the server doesn't list appear with the NonExistent attribute",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -137,7 +146,11 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                 "properties": {
                     "folder_name": {
                         "type": "string",
-                        "description": "Name of the folder to create (e.g., INBOX.Archive)"
+                        "description": "Name of the folder to create (e.g., INBOX.Archive). Placed under the server's personal namespace prefix if it lacks one"
+                    },
+                    "parent_folder": {
+                        "type": "string",
+                        "description": "Optional folder to create it in; the name is joined with the server's hierarchy delimiter"
                     },
                     "account_id": {
                         "type": "string",
@@ -177,7 +190,7 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                     },
                     "new_name": {
                         "type": "string",
-                        "description": "New name for the folder (e.g., INBOX.Projects)"
+                        "description": "New name for the folder (e.g., INBOX.Projects). A name without the server's hierarchy delimiter keeps the folder under its current parent"
                     },
                     "account_id": {
                         "type": "string",
@@ -221,7 +234,7 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                     },
                     "target_folder": {
                         "type": "string",
-                        "description": "Target folder"
+                        "description": "Target folder, or a special-use name such as \\Trash or \\Junk for the account's folder with that role"
                     },
                     "uid": {
                         "type": "integer",
@@ -247,7 +260,7 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                     },
                     "target_folder": {
                         "type": "string",
-                        "description": "Target folder"
+                        "description": "Target folder, or a special-use name such as \\Trash or \\Junk for the account's folder with that role"
                     },
                     "uids": {
                         "type": "string",
@@ -291,7 +304,7 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                 "properties": {
                     "folder": {
                         "type": "string",
-                        "description": "Folder containing messages"
+                        "description": "Folder containing messages, or a special-use name such as \\Trash"
                     },
                     "uids": {
                         "type": "string",
@@ -391,7 +404,7 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
         }),
         serde_json::json!({
             "name": "list_cached_emails",
-            "description": "List cached emails from database",
+            "description": "List cached emails from database, newest first. For large folders page with cursor rather than offset: pass cursor=\"\" for the first page, then the next_cursor of each response until it is null. With cursor set, data is {emails, next_cursor} instead of a plain list",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -405,7 +418,11 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                     },
                     "offset": {
                         "type": "integer",
-                        "description": "Pagination offset (default: 0)"
+                        "description": "Pagination offset (default: 0). Ignored when cursor is set"
+                    },
+                    "cursor": {
+                        "type": "string",
+                        "description": "Optional. next_cursor from the previous page; empty string for the first page. Preferred over offset: stays fast in large folders and doesn't skip or repeat emails when new mail arrives"
                     },
                     "account_id": {
                         "type": "string",
@@ -417,7 +434,7 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
         }),
         serde_json::json!({
             "name": "get_email_by_uid",
-            "description": "Get full cached email by UID",
+            "description": "Get full cached email by UID. On a cache miss the email is fetched from IMAP and cached unless cache_only is true",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -432,6 +449,10 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                     "account_id": {
                         "type": "string",
                         "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "cache_only": {
+                        "type": "boolean",
+                        "description": "Optional. Only read the cache; don't fetch from IMAP on a miss (default: false)"
                     }
                 },
                 "required": ["uid", "account_id"]
@@ -583,6 +604,25 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                         "type": "string",
                         "description": "Optional. HTML email body (multipart with plain text fallback)"
                     },
+                    "verify_recipients": {
+                        "type": "boolean",
+                        "description": "Optional. Check every recipient first (see verify_recipient) and refuse to send if any is clearly undeliverable (default: false)"
+                    },
+                    "attachments": {
+                        "type": "array",
+                        "description": "Optional. Files to attach. Give each one either base64 data or the path of a stored attachment; set content_id to embed an image referenced as cid:<id> in body_html",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "filename": { "type": "string" },
+                                "content_type": { "type": "string" },
+                                "data": { "type": "string", "description": "Base64-encoded file content" },
+                                "path": { "type": "string", "description": "Path under the attachment storage directory" },
+                                "content_id": { "type": "string" }
+                            },
+                            "required": ["filename"]
+                        }
+                    },
                     "account_id": {
                         "type": "string",
                         "description": "Optional. Email address of the sending account (uses default if not specified)"
@@ -995,506 +1035,4218 @@ pub fn get_mcp_tools_jsonrpc_format() -> Vec<serde_json::Value> {
                 },
                 "required": ["account_id", "folder", "uids"]
             }
-        })
-    ]
-}
-
-// Query parameters for MCP tools endpoint
-#[derive(Debug, Deserialize)]
-pub struct McpToolsQuery {
-    #[serde(default = "default_variant")]
-    pub variant: String,
-}
-
-fn default_variant() -> String {
-    "low-level".to_string()
-}
-
-pub async fn list_mcp_tools(
-    _state: web::Data<DashboardState>,
-    query: web::Query<McpToolsQuery>,
-) -> Result<impl Responder, ApiError> {
-    debug!("Listing MCP tools with variant: {}", query.variant);
-
-    // Check which variant to return
-    let tools = if query.variant == "high-level" {
-        // Return high-level tools
-        use crate::dashboard::api::high_level_tools;
-        let high_level_tools = high_level_tools::get_mcp_high_level_tools_jsonrpc_format();
-
-        // Convert from JSON-RPC format to dashboard format
-        high_level_tools.iter().map(|tool| {
-            let name = tool["name"].as_str().unwrap_or("unknown");
-            let description = tool["description"].as_str().unwrap_or("");
-            let input_schema = &tool["inputSchema"];
-
-            // Extract parameters from inputSchema
-            let mut parameters = serde_json::json!({});
-            if let Some(props) = input_schema.get("properties") {
-                if let Some(props_obj) = props.as_object() {
-                    for (key, value) in props_obj {
-                        let desc = value.get("description")
-                            .and_then(|d| d.as_str())
-                            .unwrap_or("");
-                        parameters[key] = serde_json::Value::String(desc.to_string());
-                    }
-                }
-            }
-
-            serde_json::json!({
-                "name": name,
-                "description": description,
-                "parameters": parameters
-            })
-        }).collect()
-    } else {
-        // Return low-level tools (existing implementation)
-        vec![
-        serde_json::json!({
-            "name": "list_folders",
-            "description": "List all email folders in the account",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
-            }
         }),
         serde_json::json!({
-            "name": "list_folders_hierarchical",
-            "description": "List folders with hierarchical structure",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "transfer_messages",
+            "description": "Move or copy messages from one account's folder to another account's folder. Fetches each raw message from the source, appends it to the destination with its flags and received date, and, once the server has confirmed the append, optionally deletes it from the source. Use this to consolidate mail when migrating between providers.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source_account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account to transfer from"
+                    },
+                    "source_folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Folder containing the messages in the source account"
+                    },
+                    "uids": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "REQUIRED. UIDs of the messages to transfer"
+                    },
+                    "dest_account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account to transfer to"
+                    },
+                    "dest_folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Destination folder in the destination account"
+                    },
+                    "delete_source": {
+                        "type": "boolean",
+                        "description": "Optional. Delete transferred messages from the source afterwards, making this a move (default: false). Needs UIDPLUS on the source server so that only these messages are expunged"
+                    }
+                },
+                "required": ["source_account_id", "source_folder", "uids", "dest_account_id", "dest_folder"]
             }
         }),
         serde_json::json!({
-            "name": "create_folder",
-            "description": "Create a new email folder in the account",
-            "parameters": {
-                "folder_name": "Name of the folder to create (e.g., INBOX.Archive)",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "get_server_info",
+            "description": "Get the server's current time (UTC and local), its UTC offset, and how dates are interpreted in searches and filters. Call this before building date-based queries to avoid off-by-one-day results.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
             }
         }),
         serde_json::json!({
-            "name": "delete_folder",
-            "description": "Delete an email folder from the account",
-            "parameters": {
-                "folder_name": "Name of the folder to delete (e.g., INBOX.OldEmails)",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "semantic_search",
+            "description": "Search cached emails by meaning rather than exact words. Embeds the query and returns the closest emails by cosine similarity. Requires SEMANTIC_SEARCH_ENABLED; when disabled, falls back to keyword search over the cache and reports mode 'keyword'.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "REQUIRED. Natural-language description of the emails to find"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Restrict to one folder. Searches all folders when omitted."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional. Maximum results (default: 10, max: 100)"
+                    }
+                },
+                "required": ["account_id", "query"]
             }
         }),
         serde_json::json!({
-            "name": "rename_folder",
-            "description": "Rename an email folder in the account",
-            "parameters": {
-                "old_name": "Current name of the folder (e.g., INBOX.Temp)",
-                "new_name": "New name for the folder (e.g., INBOX.Projects)",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "all_accounts_summary",
+            "description": "Aggregate statistics across every configured account in one call: cached message and unread totals, a per-account breakdown with sync status and last-sync timestamps, and connection pool health. Reads from the cache; no IMAP round-trips.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
             }
         }),
         serde_json::json!({
-            "name": "fetch_emails_with_mime",
-            "description": "Fetch email content with MIME data",
-            "parameters": {
-                "folder": "Folder containing the email",
-                "uid": "Email UID",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "flag_messages",
+            "description": "Flag (star) messages as important by adding the \\Flagged flag. Updates the cache so list_flagged reflects the change immediately.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Folder containing the messages (e.g., 'INBOX')"
+                    },
+                    "uids": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "REQUIRED. UIDs of the messages to flag"
+                    }
+                },
+                "required": ["account_id", "folder", "uids"]
             }
         }),
         serde_json::json!({
-            "name": "atomic_move_message",
-            "description": "Move a single message to another folder",
-            "parameters": {
-                "source_folder": "Source folder",
-                "target_folder": "Target folder",
-                "uid": "Message UID to move",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "unflag_messages",
+            "description": "Remove the \\Flagged flag (star) from messages. Updates the cache so list_flagged reflects the change immediately.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Folder containing the messages (e.g., 'INBOX')"
+                    },
+                    "uids": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "REQUIRED. UIDs of the messages to unflag"
+                    }
+                },
+                "required": ["account_id", "folder", "uids"]
             }
         }),
         serde_json::json!({
-            "name": "atomic_batch_move",
-            "description": "Move multiple messages to another folder",
-            "parameters": {
-                "source_folder": "Source folder",
-                "target_folder": "Target folder",
-                "uids": "Comma-separated list of UIDs",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "list_flagged",
+            "description": "List flagged (starred) emails for an account across all cached folders, newest first. Returns metadata only (folder, UID, subject, sender, date, flags).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional. Maximum results (default: 50)"
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "Optional. Number of results to skip for pagination (default: 0)"
+                    }
+                },
+                "required": ["account_id"]
             }
         }),
         serde_json::json!({
-            "name": "mark_as_deleted",
-            "description": "Mark messages as deleted",
-            "parameters": {
-                "folder": "Folder containing messages",
-                "uids": "Comma-separated list of UIDs",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "sieve_list_scripts",
+            "description": "List the server-side Sieve filter scripts for an account via ManageSieve (port 4190, same host and credentials as IMAP unless overridden). Shows which script is active. Returns supported=false if the server has no ManageSieve service.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    }
+                },
+                "required": ["account_id"]
             }
         }),
         serde_json::json!({
-            "name": "delete_messages",
-            "description": "Permanently delete messages",
-            "parameters": {
-                "folder": "Folder containing messages",
-                "uids": "Comma-separated list of UIDs",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "sieve_get_script",
+            "description": "Download the source of a server-side Sieve script via ManageSieve.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "REQUIRED. Name of the Sieve script"
+                    }
+                },
+                "required": ["account_id", "name"]
             }
         }),
         serde_json::json!({
-            "name": "undelete_messages",
-            "description": "Unmark messages as deleted",
-            "parameters": {
-                "folder": "Folder containing messages",
-                "uids": "Comma-separated list of UIDs",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "sieve_put_script",
+            "description": "Upload (create or replace) a server-side Sieve script via ManageSieve. The server validates the script and rejects it with an error message if it does not compile. Does not activate the script.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "REQUIRED. Name of the Sieve script"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "REQUIRED. Sieve script source (RFC 5228)"
+                    }
+                },
+                "required": ["account_id", "name", "content"]
             }
         }),
         serde_json::json!({
-            "name": "expunge",
-            "description": "Expunge deleted messages from folder",
-            "parameters": {
-                "folder": "Folder to expunge",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "sieve_activate_script",
+            "description": "Make a Sieve script the active filter set via ManageSieve. Only one script can be active; pass an empty name to deactivate all scripts.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "REQUIRED. Name of the script to activate, or empty string to deactivate filtering"
+                    }
+                },
+                "required": ["account_id", "name"]
             }
         }),
-        // Cache-based tools
         serde_json::json!({
-            "name": "list_cached_emails",
-            "description": "List cached emails from database",
-            "parameters": {
-                "folder": "Folder name (default: INBOX)",
-                "limit": "Maximum number of emails (default: 20)",
-                "offset": "Pagination offset (default: 0)",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "sieve_delete_script",
+            "description": "Delete a server-side Sieve script via ManageSieve. The active script cannot be deleted; deactivate it first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "REQUIRED. Name of the Sieve script"
+                    }
+                },
+                "required": ["account_id", "name"]
             }
         }),
         serde_json::json!({
-            "name": "get_email_by_uid",
-            "description": "Get full cached email by UID",
-            "parameters": {
-                "folder": "Folder name (default: INBOX)",
-                "uid": "Email UID",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "refresh_email",
+            "description": "Force a fresh fetch of one email's full message from IMAP and overwrite its cached copy. Use when a cached body is missing, truncated or stale. Idempotent; returns the refreshed email in the same shape as get_email_by_uid.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Folder containing the email (e.g., 'INBOX')"
+                    },
+                    "uid": {
+                        "type": "integer",
+                        "description": "REQUIRED. UID of the email to refresh"
+                    }
+                },
+                "required": ["account_id", "folder", "uid"]
             }
         }),
         serde_json::json!({
-            "name": "get_email_by_index",
-            "description": "Get cached email by position index",
-            "parameters": {
-                "folder": "Folder name (default: INBOX)",
-                "index": "Zero-based position index",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "download_folder_attachments",
+            "description": "Download the attachments of every email in a folder that has them (optionally filtered by sender, subject and date) into one ZIP with a directory per email. Runs as a background job; poll get_job_status with the returned job_id for progress and the final zip_path. Only the newest max_emails cached emails in the folder are scanned; scan_limit_reached in the result says older ones were skipped, in which case raise max_emails or narrow by date. Refused up front if the estimated total exceeds the ZIP size limit.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Folder to collect attachments from (e.g., 'INBOX/Invoices')"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "Optional. Only emails whose sender address or name contains this text"
+                    },
+                    "subject": {
+                        "type": "string",
+                        "description": "Optional. Only emails whose subject contains this text"
+                    },
+                    "since": {
+                        "type": "string",
+                        "description": "Optional. Only emails on or after this date (RFC 3339 or YYYY-MM-DD)"
+                    },
+                    "before": {
+                        "type": "string",
+                        "description": "Optional. Only emails on or before this date (RFC 3339 or YYYY-MM-DD; a date includes the whole day)"
+                    },
+                    "max_emails": {
+                        "type": "integer",
+                        "description": "Optional. How many of the folder's newest cached emails to scan (default: 500)"
+                    }
+                },
+                "required": ["account_id", "folder"]
             }
         }),
         serde_json::json!({
-            "name": "count_emails_in_folder",
-            "description": "Count total emails in cached folder",
-            "parameters": {
-                "folder": "Folder name (default: INBOX)",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "verify_recipient",
+            "description": "Check whether email addresses look deliverable before sending: syntax, the domain's MX records, and (only when the server enables RECIPIENT_SMTP_PROBE) an SMTP RCPT probe that sends nothing. Returns a verdict (deliverable, risky, undeliverable, unknown) with a confidence between 0 and 1 for each address.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "address": {
+                        "oneOf": [
+                            {"type": "string"},
+                            {"type": "array", "items": {"type": "string"}}
+                        ],
+                        "description": "REQUIRED. Address or array of addresses to check"
+                    },
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Account used as the envelope sender for the SMTP probe (uses default if not specified)"
+                    }
+                },
+                "required": ["address"]
             }
         }),
         serde_json::json!({
-            "name": "get_folder_stats",
-            "description": "Get statistics about cached folder",
-            "parameters": {
-                "folder": "Folder name (default: INBOX)",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "send_from_template",
+            "description": "Send an email built from a stored template. The template's {{variable}} placeholders in subject and body are replaced with the given values; every placeholder must be supplied. Templates are managed under /api/dashboard/accounts/{id}/templates.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "template": {
+                        "type": "string",
+                        "description": "REQUIRED. Name of the template"
+                    },
+                    "to": {
+                        "type": "array",
+                        "description": "REQUIRED. Array of recipient email addresses",
+                        "items": {
+                            "type": "string"
+                        }
+                    },
+                    "variables": {
+                        "type": "object",
+                        "description": "Optional. Values for the template's placeholders, e.g. {\"name\": \"Ana\", \"invoice\": \"1042\"}",
+                        "additionalProperties": {
+                            "type": ["string", "number", "boolean"]
+                        }
+                    },
+                    "cc": {
+                        "type": "array",
+                        "description": "Optional. Array of CC recipient email addresses",
+                        "items": {
+                            "type": "string"
+                        }
+                    },
+                    "bcc": {
+                        "type": "array",
+                        "description": "Optional. Array of BCC recipient email addresses",
+                        "items": {
+                            "type": "string"
+                        }
+                    },
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the sending account that owns the template (uses default if not specified)"
+                    }
+                },
+                "required": ["template", "to"]
             }
         }),
         serde_json::json!({
-            "name": "search_cached_emails",
-            "description": "Search within cached emails",
-            "parameters": {
-                "folder": "Folder name (default: INBOX)",
-                "query": "Search query text",
-                "limit": "Maximum number of results (default: 20)",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "mark_read_matching",
+            "description": "Mark every unread message matching a server-side SEARCH as read in one STORE, without listing UIDs first. Filters are ANDed; at least one is required. Use dry_run to see how many messages would change.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Folder to search (e.g. INBOX)"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "Optional. Sender address or name substring"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Optional. Recipient address or name substring"
+                    },
+                    "subject": {
+                        "type": "string",
+                        "description": "Optional. Subject substring"
+                    },
+                    "body": {
+                        "type": "string",
+                        "description": "Optional. Body text substring"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Optional. Substring matched against headers and body"
+                    },
+                    "since": {
+                        "type": "string",
+                        "description": "Optional. Only messages on or after this date (YYYY-MM-DD or RFC 3339)"
+                    },
+                    "before": {
+                        "type": "string",
+                        "description": "Optional. Only messages on or before this date (YYYY-MM-DD or RFC 3339)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Optional. Report matches without changing flags (default false)"
+                    },
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    }
+                },
+                "required": ["folder"]
             }
         }),
-        // Account management tools
-        serde_json::json!({
-            "name": "list_accounts",
-            "description": "List all configured email accounts",
-            "parameters": {}
-        }),
         serde_json::json!({
-            "name": "set_current_account",
-            "description": "Set the current account for email operations",
-            "parameters": {
-                "account_id": "Account ID to set as current"
+            "name": "folder_threads",
+            "description": "Group a folder's cached emails into conversation threads (by Message-ID, In-Reply-To, References and normalized subject) and return thread count, the largest thread, top participants and the thread list, most recently active first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Folder to analyze (default: INBOX)"
+                    },
+                    "max_emails": {
+                        "type": "integer",
+                        "description": "Optional. Most recent cached emails to group (default: 1000)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional. Max threads to return in the list (default: 50); statistics cover all grouped emails"
+                    },
+                    "top_participants": {
+                        "type": "integer",
+                        "description": "Optional. Number of most frequent senders to return (default: 10)"
+                    }
+                },
+                "required": []
             }
         }),
-        // SMTP email sending
         serde_json::json!({
-            "name": "send_email",
-            "description": "Send an email via SMTP",
-            "parameters": {
-                "to": "REQUIRED. Array of recipient email addresses",
-                "subject": "REQUIRED. Email subject line",
-                "body": "REQUIRED. Plain text email body",
-                "cc": "Optional. Array of CC recipient email addresses",
-                "bcc": "Optional. Array of BCC recipient email addresses",
-                "body_html": "Optional. HTML email body (multipart with plain text fallback)",
-                "account_id": "Optional. Email address of the sending account (uses default if not specified)"
+            "name": "list_recent",
+            "description": "List cached emails carrying the \\Recent flag, i.e. messages that arrived since the previous sync pass, plus the RECENT count the server reported for each folder. \\Recent is distinct from unseen and is per-session: if another client selects a folder before sync does, it claims the flag and those messages never appear here. IMAP_RECENT_MODE=until_seen keeps the marker until the message is read.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Limit to one folder (default: all cached folders)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional. Max emails to return (default: 50)"
+                    }
+                },
+                "required": []
             }
         }),
-        // Attachment management tools
         serde_json::json!({
-            "name": "list_email_attachments",
-            "description": "List all attachments for a specific email",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
-                "folder": "Folder containing the email (when using uid)",
-                "uid": "Email UID (alternative to message_id)",
-                "message_id": "Message ID (alternative to folder+uid)"
+            "name": "get_email_text",
+            "description": "Get the best plain-text view of an email: the text/plain part if present, otherwise the HTML part converted to text. Served from the cache, fetching the message on a cache miss. Optionally strips the quoted reply tail (\"On ... wrote:\", Outlook header blocks, trailing > lines).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Folder containing the email (default: INBOX)"
+                    },
+                    "uid": {
+                        "type": "integer",
+                        "description": "REQUIRED. UID of the email"
+                    },
+                    "strip_quotes": {
+                        "type": "boolean",
+                        "description": "Optional. Remove the quoted previous message from replies (default: false)"
+                    },
+                    "cache_only": {
+                        "type": "boolean",
+                        "description": "Optional. Don't fetch from the server on a cache miss (default: false)"
+                    }
+                },
+                "required": ["uid"]
             }
         }),
         serde_json::json!({
-            "name": "download_email_attachments",
-            "description": "Download attachments from an email to local directory",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
-                "folder": "Folder containing the email (when using uid)",
-                "uid": "Email UID (alternative to message_id)",
-                "message_id": "Message ID (alternative to folder+uid)",
-                "destination": "Destination directory path (optional)",
-                "create_zip": "Create ZIP archive instead of individual files (optional, boolean)"
-            }
-        }),
-        serde_json::json!({
-            "name": "cleanup_attachments",
-            "description": "Delete downloaded attachments for a specific email",
-            "parameters": {
-                "message_id": "REQUIRED. The message ID of the email",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "check_uids_exist",
+            "description": "Check which of the given UIDs still exist in a folder on the server, using a single UID FETCH FLAGS. Useful for validating stale UIDs (e.g. from the cache or an earlier search) before acting on them. Also returns the folder's UIDVALIDITY; if it differs from when the UIDs were obtained, they no longer refer to the same messages.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "REQUIRED. Folder to check"
+                    },
+                    "uids": {
+                        "type": "array",
+                        "items": {"type": "integer"},
+                        "description": "REQUIRED. UIDs to check"
+                    }
+                },
+                "required": ["folder", "uids"]
             }
         }),
         serde_json::json!({
-            "name": "get_attachment_content",
-            "description": "Get a single attachment's content as base64",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "message_id": "Message-ID (provide this OR folder+uid)",
-                "folder": "Folder name (if message_id not provided)",
-                "uid": "Email UID (if message_id not provided)",
-                "filename": "REQUIRED. Filename of the attachment"
+            "name": "next_unread",
+            "description": "Get the next unread email in a folder for triage: the oldest unseen message by default, or the newest. Served from the cache; returns the envelope, UID and the folder's unread count, or found=false when everything is read.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Folder to triage (default: INBOX)"
+                    },
+                    "order": {
+                        "type": "string",
+                        "enum": ["oldest", "newest"],
+                        "description": "Optional. Which unread email to return (default: oldest)"
+                    }
+                },
+                "required": []
             }
         }),
         serde_json::json!({
-            "name": "mark_as_read",
-            "description": "Mark messages as read (adds \\Seen flag)",
-            "parameters": {
-                "folder": "REQUIRED. Folder containing messages",
-                "uids": "REQUIRED. Array of message UIDs to mark as read",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "cache_status",
+            "description": "Report how current the cache is for each folder: last sync time and age, whether a sync is running or failed, cached vs server message counts, and a stale flag with the reason. Folders are stale when never synced, when the last sync failed or is older than the threshold, or (with live=true) when the server's STATUS count differs from the cache.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Only report this folder (default: all cached folders)"
+                    },
+                    "live": {
+                        "type": "boolean",
+                        "description": "Optional. Ask the server for current message counts with STATUS (default: false)"
+                    },
+                    "stale_after_seconds": {
+                        "type": "integer",
+                        "description": "Optional. Sync age after which a folder is stale (default: CACHE_STALE_AFTER_SECONDS or 900)"
+                    }
+                },
+                "required": []
             }
         }),
         serde_json::json!({
-            "name": "mark_as_unread",
-            "description": "Mark messages as unread (removes \\Seen flag)",
-            "parameters": {
-                "folder": "REQUIRED. Folder containing messages",
-                "uids": "REQUIRED. Array of message UIDs to mark as unread",
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            "name": "compare_folders",
+            "description": "Compare two folders (in the same or different accounts) by Message-ID to verify a migration or transfer copied everything. Reads from the cache and returns the messages present in only one folder. Messages without a Message-ID are matched by a hash of subject, sender, date and size. With verify_on_server=true, Message-IDs missing from one folder's cache are searched for on that folder's server, so a lagging cache isn't reported as missing mail.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source_account_id": {
+                        "type": "string",
+                        "description": "Optional. Account of the source folder (uses default if not specified)"
+                    },
+                    "source_folder": {
+                        "type": "string",
+                        "description": "Folder messages were copied from"
+                    },
+                    "dest_account_id": {
+                        "type": "string",
+                        "description": "Optional. Account of the destination folder (defaults to the source account)"
+                    },
+                    "dest_folder": {
+                        "type": "string",
+                        "description": "Folder messages were copied to"
+                    },
+                    "verify_on_server": {
+                        "type": "boolean",
+                        "description": "Optional. Search the server for Message-IDs missing from the cache (default: false)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional. Maximum messages listed for each side (default: 100; counts are always complete)"
+                    }
+                },
+                "required": ["source_folder", "dest_folder"]
             }
         }),
         serde_json::json!({
-            "name": "sync_emails",
-            "description": "Trigger email sync for a specific folder or all folders",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
-                "folder": "Optional. Specific folder to sync (e.g., 'INBOX', 'INBOX/resumes'). If omitted, syncs all folders."
+            "name": "extract_links",
+            "description": "List the hyperlinks in a cached email for triage or security review. Parses the HTML body's anchors (href plus visible text) and bare URLs in the plain-text body, de-duplicated by URL, noting which part each link came from. Flags domain_mismatch when an anchor's visible text names a different domain than its href, a common phishing signal. Reads only from the cache.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Folder containing the email (default: INBOX)"
+                    },
+                    "uid": {
+                        "type": "integer",
+                        "description": "UID of the email"
+                    }
+                },
+                "required": ["uid"]
             }
         }),
         serde_json::json!({
-            "name": "get_email_synopsis",
-            "description": "Get a concise synopsis of an email (subject + first sentences)",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "folder": "Optional. Folder name (default: INBOX)",
-                "uid": "REQUIRED. Email UID",
-                "max_lines": "Optional. Max sentences to extract (default: 3)"
+            "name": "empty_folder",
+            "description": "Permanently delete all messages in a folder (e.g. empty Trash or Spam): marks them \\Deleted, expunges, and clears the folder from the cache. Give either a folder name or a special-use role such as 'trash' or 'junk', which is resolved from the server's folder attributes. Requires confirm=true. Use older_than_days to purge only older messages; this needs UIDPLUS on the server so that other messages flagged \\Deleted are left alone. This cannot be undone.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Folder to empty (give this or role)"
+                    },
+                    "role": {
+                        "type": "string",
+                        "enum": ["trash", "junk", "spam", "sent", "drafts", "archive"],
+                        "description": "Special-use role of the folder to empty (give this or folder)"
+                    },
+                    "confirm": {
+                        "type": "boolean",
+                        "description": "Must be true; the deletion is permanent"
+                    },
+                    "older_than_days": {
+                        "type": "integer",
+                        "description": "Optional. Only purge messages received more than this many days ago"
+                    }
+                },
+                "required": ["confirm"]
             }
         }),
         serde_json::json!({
-            "name": "get_email_thread",
-            "description": "Get all emails in a conversation thread by message_id",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "message_id": "REQUIRED. Message-ID of any email in the thread"
+            "name": "hierarchical_unread",
+            "description": "Unread counts for every folder of an account, each rolled up over its subfolders using the server's hierarchy delimiter (unread_with_descendants is the badge a collapsed parent shows), plus the account total. Counts come from the cache unless live=true, which asks the server with STATUS.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "live": {
+                        "type": "boolean",
+                        "description": "Optional. Get unread counts from the server with STATUS instead of the cache (default: false)"
+                    }
+                },
+                "required": []
             }
         }),
         serde_json::json!({
-            "name": "search_by_domain",
-            "description": "Search cached emails by sender/recipient domain",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "domain": "REQUIRED. Domain to search for (e.g., 'gmail.com')",
-                "search_in": "Optional. Array of fields: 'from', 'to', 'cc' (default: ['from'])",
-                "limit": "Optional. Max results (default: 50)"
+            "name": "sync_lag",
+            "description": "Health check for how far the cache trails the server: for each folder, compares a live STATUS (MESSAGES, UNSEEN) with the cached counts and reports the deltas and the age of the last sync, then aggregates an account-level lag score and status (ok, behind, failing). Checks one account, or every account with all_accounts=true.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account (uses default if not specified)"
+                    },
+                    "all_accounts": {
+                        "type": "boolean",
+                        "description": "Optional. Check every configured account (default: false)"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Only check this folder"
+                    },
+                    "stale_after_seconds": {
+                        "type": "integer",
+                        "description": "Optional. A last sync older than this marks the account as behind (default: CACHE_STALE_AFTER_SECONDS or 900)"
+                    }
+                },
+                "required": []
             }
         }),
         serde_json::json!({
-            "name": "get_address_report",
-            "description": "Get aggregated report of unique email addresses and domains",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account"
+            "name": "refresh_account_connections",
+            "description": "Force fresh IMAP connections for an account without restarting, e.g. after rotating its password or a provider outage: drops the account's IDLE sessions so they log in again, re-reads its stored credentials, clears any connection-limit backoff, and performs a test login. Returns whether the login succeeded, how long it took and the server's capabilities.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account to reconnect"
+                    }
+                },
+                "required": ["account_id"]
             }
         }),
         serde_json::json!({
-            "name": "list_emails_by_flag",
-            "description": "Filter cached emails by IMAP flags (Seen, Flagged, Answered, etc.)",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "folder": "Optional. Folder name (default: INBOX)",
-                "flags_include": "Optional. Array of flags emails must have (e.g., ['Flagged'])",
-                "flags_exclude": "Optional. Array of flags emails must not have (e.g., ['Seen'] for unread)",
-                "unread_only": "Optional. Boolean shorthand for flags_exclude=['Seen']",
-                "limit": "Optional. Max results (default: 50)",
-                "offset": "Optional. Pagination offset (default: 0)"
+            "name": "search_server",
+            "description": "Search a folder on the IMAP server itself (UID SEARCH) rather than the local cache, so messages that have not been synced yet are found too. Criteria are combined with AND; an empty criteria object matches every message. Only the newest `limit` matches (highest UIDs) are returned, with envelope previews (subject, from, to, date, flags); total_matches counts every match and may exceed the number returned.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Folder to search (default: INBOX)"
+                    },
+                    "criteria": {
+                        "type": "object",
+                        "description": "Optional. Search keys, all of which must match",
+                        "properties": {
+                            "from": {"type": "string", "description": "Substring of the From header"},
+                            "to": {"type": "string", "description": "Substring of the To header"},
+                            "subject": {"type": "string", "description": "Substring of the subject"},
+                            "body": {"type": "string", "description": "Substring of the body"},
+                            "text": {"type": "string", "description": "Substring of headers or body"},
+                            "since": {"type": "string", "description": "Received on or after this date (YYYY-MM-DD or RFC 3339)"},
+                            "before": {"type": "string", "description": "Received before this date (YYYY-MM-DD or RFC 3339)"},
+                            "unseen": {"type": "boolean", "description": "true for unread messages only, false for read only"},
+                            "flagged": {"type": "boolean", "description": "true for flagged messages only, false for unflagged only"}
+                        }
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional. Maximum matches to return, newest first (default: SEARCH_RESULT_LIMIT or 50, capped at SEARCH_RESULT_MAX or 500)"
+                    },
+                    "sort_by": {
+                        "type": "string",
+                        "description": "Optional. Order matches by the server (IMAP SORT) instead of newest UID first: comma-separated keys from arrival, date, from, subject, size; prefix a key with '-' to reverse it (e.g. '-date' for newest sent first, 'from,-date'). 'limit' then applies to this order"
+                    }
+                },
+                "required": ["account_id"]
             }
         }),
         serde_json::json!({
-            "name": "search_by_attachment_type",
-            "description": "Search for attachments matching MIME type patterns (e.g., 'image/*', 'application/pdf')",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
-                "mime_types": "REQUIRED. Array of MIME type patterns (e.g., ['image/*', 'application/pdf'])",
-                "limit": "Optional. Maximum results to return (default: 50)"
+            "name": "search_cached_emails_fts",
+            "description": "Full-text search of cached emails using the SQLite FTS5 index over subject, sender name and body. Results are ordered by relevance (bm25; subject matches weigh most) and include a body snippet with matched terms in [brackets]. Every word must match; end a word with * for prefix matching. Much faster than search_cached_emails on large caches.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Words to search for, e.g. 'budget review' or 'invoic*'"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Optional. Only search this folder (default: all folders)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional. Maximum results (default: 20)"
+                    }
+                },
+                "required": ["account_id", "query"]
             }
         }),
         serde_json::json!({
-            "name": "export_evidence",
-            "description": "Export emails and attachments into an organized evidence directory for attorney review",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
-                "folder": "Optional. Folder name to limit export. If omitted, exports all folders.",
-                "search_query": "Optional. Search string to filter emails.",
-                "output_path": "Optional. Override output directory (default: EVIDENCE_EXPORT_DIR env or data/evidence_exports)."
+            "name": "copy_message",
+            "description": "Copy an email to another folder while keeping the original in place (e.g. file a message to a project folder but leave it in the inbox). Nothing is deleted or expunged. When the server supports UIDPLUS the result includes the copy's new UID in the target folder. Pass 'uids' instead of 'uid' to copy several messages at once.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "source_folder": {
+                        "type": "string",
+                        "description": "Folder containing the email (e.g., 'INBOX')"
+                    },
+                    "target_folder": {
+                        "type": "string",
+                        "description": "Folder to copy the email into"
+                    },
+                    "uid": {
+                        "type": "integer",
+                        "description": "UID of the email in the source folder"
+                    },
+                    "uids": {
+                        "type": "array",
+                        "items": {"type": "integer"},
+                        "description": "Optional. UIDs of several emails to copy, instead of 'uid'"
+                    }
+                },
+                "required": ["account_id", "source_folder", "target_folder"]
             }
         }),
         serde_json::json!({
-            "name": "export_folder_metadata",
-            "description": "Export email metadata (no body content) to a file on disk. Returns file path only — ideal for large folders.",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "folder": "REQUIRED. Folder name (e.g., 'INBOX')",
-                "format": "Optional. 'json' (default) or 'csv'",
-                "fields": "Optional. Comma-separated field names to include (e.g., 'uid,subject,date')",
-                "limit": "Optional. Max rows to export (default: 10000)"
+            "name": "get_quota",
+            "description": "Get the mailbox storage quota (RFC 2087 QUOTA): used and maximum size in KB, message count and limit, and percent_used for the fullest resource. Useful before appending or saving large messages, since a full mailbox makes APPEND fail. Fails with a clear error when the server does not support QUOTA.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Mailbox whose quota root to report (default: 'INBOX')"
+                    }
+                },
+                "required": ["account_id"]
             }
         }),
         serde_json::json!({
-            "name": "filter_emails_by_subject",
-            "description": "Filter emails by subject line patterns. Returns metadata only (no body content).",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "folder": "REQUIRED. Folder name (e.g., 'INBOX')",
-                "subject_patterns": "REQUIRED. Array of keywords to match against subjects (case-insensitive)",
-                "match_mode": "Optional. 'any' (default) or 'all'",
-                "sender_filter": "Optional. Restrict to sender address/domain",
-                "recipient_filter": "Optional. Restrict to recipient address/domain",
-                "date_after": "Optional. ISO 8601 date lower bound",
-                "date_before": "Optional. ISO 8601 date upper bound",
-                "max_results": "Optional. Max results (default: 500)"
+            "name": "get_thread",
+            "description": "Get the full conversation an email belongs to, read from the IMAP server: every message in the folder linked to it by In-Reply-To/References (or, with algorithm 'ordered_subject', by subject only), as a nested tree of replies with envelopes (subject, from, to, date, flags). Pass the UID of any message in the conversation; the tree starts at the conversation's first message. Unlike get_email_thread this works on messages that have not been synced to the cache.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Folder containing the email (e.g., 'INBOX')"
+                    },
+                    "uid": {
+                        "type": "integer",
+                        "description": "UID of any email in the conversation"
+                    },
+                    "algorithm": {
+                        "type": "string",
+                        "enum": ["references", "ordered_subject"],
+                        "description": "Optional. How messages are linked (default: 'references')"
+                    }
+                },
+                "required": ["account_id", "folder", "uid"]
             }
         }),
         serde_json::json!({
-            "name": "batch_get_synopsis",
-            "description": "Get compact synopses for multiple emails in a single call (max 50 UIDs).",
-            "parameters": {
-                "account_id": "REQUIRED. Email address of the account",
-                "folder": "REQUIRED. Folder name (e.g., 'INBOX')",
-                "uids": "REQUIRED. Array of email UIDs (max 50 per call)",
-                "max_chars_per_synopsis": "Optional. Character cap per synopsis (default: 300, max: 1500)"
+            "name": "save_draft",
+            "description": "Save an email as a draft without sending it. The message is built the same way as send_email (From, Reply-To, recipients, plain text and optional HTML body, attachments) and appended to the account's Drafts folder with the \\Draft flag. The Drafts folder is the one configured on the account, otherwise the folder the server marks as \\Drafts, otherwise 'Drafts' (created if missing). Returns the folder and, when the server supports UIDPLUS, the UID of the saved draft.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Optional. Email address of the account to save the draft for (uses default account if not specified)"
+                    },
+                    "to": {
+                        "oneOf": [
+                            {"type": "string"},
+                            {"type": "array", "items": {"type": "string"}}
+                        ],
+                        "description": "Recipient email address(es)"
+                    },
+                    "cc": {
+                        "oneOf": [
+                            {"type": "string"},
+                            {"type": "array", "items": {"type": "string"}}
+                        ],
+                        "description": "Optional. CC recipient email address(es)"
+                    },
+                    "bcc": {
+                        "oneOf": [
+                            {"type": "string"},
+                            {"type": "array", "items": {"type": "string"}}
+                        ],
+                        "description": "Optional. BCC recipient email address(es)"
+                    },
+                    "subject": {
+                        "type": "string",
+                        "description": "Optional. Email subject"
+                    },
+                    "body": {
+                        "type": "string",
+                        "description": "Optional. Plain text body"
+                    },
+                    "body_html": {
+                        "type": "string",
+                        "description": "Optional. HTML body"
+                    },
+                    "attachments": {
+                        "type": "array",
+                        "description": "Optional. Files to attach, same form as send_email",
+                        "items": {"type": "object"}
+                    }
+                },
+                "required": ["to"]
+            }
+        }),
+        serde_json::json!({
+            "name": "get_special_folders",
+            "description": "Get which folders an account uses for Sent, Drafts, Trash, Junk and Archive. Folders are matched by the RFC 6154 special-use attributes the server reports in LIST (\\Sent, \\Drafts, \\Trash, \\Junk, \\Archive), then by the provider's usual names (e.g. '[Gmail]/Trash', 'Deleted Items', 'INBOX.Trash'). Roles with no matching folder are listed under 'missing'. The same folders are used when a move or delete tool is given a special-use name such as \\Trash as its folder.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "refresh": {
+                        "type": "boolean",
+                        "description": "Optional. List folders again instead of using the stored result (default: false)"
+                    }
+                },
+                "required": ["account_id"]
+            }
+        }),
+        serde_json::json!({
+            "name": "move_to_trash",
+            "description": "Delete messages safely by moving them to the account's Trash folder. The Trash folder is found from the server's special-use attributes or the provider's usual name (e.g. '[Gmail]/Trash', 'Deleted Items'), so the caller doesn't need to know it. Nothing is expunged, so the messages can be restored from Trash. If the account has no Trash folder the messages are only flagged \\Deleted and the response carries a warning.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Folder containing the messages"
+                    },
+                    "uids": {
+                        "type": "array",
+                        "items": {"type": "integer"},
+                        "description": "UIDs of the messages to move to Trash"
+                    }
+                },
+                "required": ["account_id", "folder", "uids"]
+            }
+        }),
+        serde_json::json!({
+            "name": "fetch_email_partial",
+            "description": "Fetch part of an email without downloading all of it: a byte range of the raw message, or of one MIME part such as '1' (often text/plain) or '1.2', so text can be read without the attachments. Content is returned as stored, in its transfer encoding (e.g. base64 or quoted-printable parts stay encoded). 'truncated' tells whether more bytes follow, and 'next_offset' is where to continue.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "folder": {
+                        "type": "string",
+                        "description": "Folder containing the email"
+                    },
+                    "uid": {
+                        "type": "integer",
+                        "description": "UID of the email"
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "First byte to return (default 0)"
+                    },
+                    "length": {
+                        "type": "integer",
+                        "description": "Number of bytes to return (default 2048, max 1048576)"
+                    },
+                    "section": {
+                        "type": "string",
+                        "description": "MIME part number ('1', '1.2') or section ('HEADER', 'TEXT', '2.TEXT', '1.MIME'). Omit for the whole message."
+                    }
+                },
+                "required": ["account_id", "folder", "uid"]
+            }
+        }),
+        serde_json::json!({
+            "name": "get_unread_counts",
+            "description": "Get total and unread message counts for every folder of an account in one call, e.g. for a folder tree with unread badges. Folders synced recently are answered from the cache; the rest get a live IMAP STATUS over a single connection. Each folder reports its source ('cache' or 'live') and last_sync; if live STATUS fails the cached counts are returned with an error.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "max_age_seconds": {
+                        "type": "integer",
+                        "description": "Use cached counts for folders synced within this many seconds (default: 300; 0 always asks the server)"
+                    }
+                },
+                "required": ["account_id"]
+            }
+        }),
+        serde_json::json!({
+            "name": "bulk_flag",
+            "description": "Add, remove or set flags on messages in several folders in one call, e.g. marking everything read across a triage set. Each folder is selected once; groups naming the same folder are merged. Returns one result per folder with success or the error, so a failure in one folder doesn't hide the others.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "groups": {
+                        "type": "array",
+                        "description": "Folders and the UIDs to change in each",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "folder": { "type": "string" },
+                                "uids": { "type": "array", "items": { "type": "integer" } }
+                            },
+                            "required": ["folder", "uids"]
+                        }
+                    },
+                    "operation": {
+                        "type": "string",
+                        "enum": ["add", "remove", "set"],
+                        "description": "Add or remove the flags, or replace all flags with them (default: add)"
+                    },
+                    "flags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Flags such as \\Seen, \\Flagged or a keyword (default: [\"\\\\Seen\"])"
+                    }
+                },
+                "required": ["account_id", "groups"]
+            }
+        }),
+        serde_json::json!({
+            "name": "get_server_capabilities",
+            "description": "Get what the account's IMAP server supports, as advertised after login. Returns the raw CAPABILITY list plus a summary of the extensions that change how tools behave: IDLE (push updates), SORT (server-side sorting, otherwise sorted locally), THREAD algorithms, QUOTA, CONDSTORE/QRESYNC (incremental flag sync), MOVE, UIDPLUS, NAMESPACE and COMPRESS=DEFLATE. The set is kept per account after each login, so this normally needs no new connection.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account_id": {
+                        "type": "string",
+                        "description": "Email address of the account"
+                    },
+                    "refresh": {
+                        "type": "boolean",
+                        "description": "Optional. Ask the server again instead of using the stored result (default: false)"
+                    }
+                },
+                "required": ["account_id"]
             }
         })
     ]
-    }; // End of if-else for variant
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "tools": tools
-    })))
 }
 
-/// Helper function to get account_id from request parameters
-/// REQUIRES account_id to be provided as an email address
-/// Returns the email address directly (no UUID lookup)
-async fn get_account_id_to_use(
-    params: &serde_json::Value,
-    _state: &web::Data<DashboardState>,
-) -> Result<String, ApiError> {
-    // account_id is REQUIRED and must be an email address
-    if let Some(account_id) = params.get("account_id").and_then(|v| v.as_str()) {
-        return Ok(account_id.to_string());
-    }
-
-    // If account_id not provided, return error
-    Err(ApiError::BadRequest(
-        "account_id parameter is required and must be an email address (e.g., user@example.com)".to_string()
-    ))
+// Query parameters for MCP tools endpoint
+#[derive(Debug, Deserialize)]
+pub struct McpToolsQuery {
+    #[serde(default = "default_variant")]
+    pub variant: String,
 }
 
-/// Validate that an account exists by checking if it can be retrieved
-/// Returns the account_id (email address) if the account is found
-async fn validate_account_exists(
-    account_id: &str,
-    state: &DashboardState,
-) -> Result<String, ApiError> {
-    // Verify the account exists by looking it up
-    let account_service = state.account_service.lock().await;
-    let _account = account_service.get_account(account_id).await
-        .map_err(|e| ApiError::NotFound(format!("Account not found: {}", e)))?;
-    drop(account_service); // Release lock
-
-    // Return the account_id
-    Ok(account_id.to_string())
+fn default_variant() -> String {
+    "low-level".to_string()
 }
 
-/// Inner function that executes MCP tools and returns raw JSON result
-/// Can be called from both HTTP handler and MCP protocol handler
-pub async fn execute_mcp_tool_inner(
-    state: &DashboardState,
-    tool_name: &str,
-    params: serde_json::Value,
-) -> serde_json::Value {
-    debug!("Executing MCP tool: {} with params: {:?}", tool_name, params);
+pub async fn list_mcp_tools(
+    _state: web::Data<DashboardState>,
+    query: web::Query<McpToolsQuery>,
+) -> Result<impl Responder, ApiError> {
+    debug!("Listing MCP tools with variant: {}", query.variant);
 
-    // Get the email service from the state
-    let email_service = state.email_service.clone();
+    // Check which variant to return
+    let tools = if query.variant == "high-level" {
+        // Return high-level tools
+        use crate::dashboard::api::high_level_tools;
+        let high_level_tools = high_level_tools::get_mcp_high_level_tools_jsonrpc_format();
 
-    // Create a temporary web::Data wrapper for helper functions that need it
-    let state_data = web::Data::new(state.clone());
+        // Convert from JSON-RPC format to dashboard format
+        high_level_tools.iter().map(|tool| {
+            let name = tool["name"].as_str().unwrap_or("unknown");
+            let description = tool["description"].as_str().unwrap_or("");
+            let input_schema = &tool["inputSchema"];
 
-    // Execute the appropriate tool
-    let result = match tool_name {
-        "list_folders" => {
-            // Get account ID from request or use default
-            let account_id = match get_account_id_to_use(&params, &state_data).await {
-                Ok(id) => id,
-                Err(e) => return serde_json::json!({
-                    "success": false,
-                    "error": format!("Failed to determine account: {}", e),
-                    "tool": tool_name
-                })
-            };
+            // Extract parameters from inputSchema
+            let mut parameters = serde_json::json!({});
+            if let Some(props) = input_schema.get("properties") {
+                if let Some(props_obj) = props.as_object() {
+                    for (key, value) in props_obj {
+                        let desc = value.get("description")
+                            .and_then(|d| d.as_str())
+                            .unwrap_or("");
+                        parameters[key] = serde_json::Value::String(desc.to_string());
+                    }
+                }
+            }
+
+            serde_json::json!({
+                "name": name,
+                "description": description,
+                "parameters": parameters
+            })
+        }).collect()
+    } else {
+        // Return low-level tools (existing implementation)
+        vec![
+        serde_json::json!({
+            "name": "list_folders",
+            "description": "List all email folders in the account, optionally with selectable (\\Noselect) and subscribed state",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
+                "include_state": "Optional. Return {name, selectable, subscribed} objects instead of names (default: false)",
+                "folder": "Optional, with include_state. Return only this folder's state"
+            }
+        }),
+        serde_json::json!({
+            "name": "list_folders_hierarchical",
+            "description": "List folders as a nested tree built with the server's hierarchy delimiter. Each node has name, full_path, children, attributes and cached unread count; levels the server doesn't list appear with the NonExistent attribute",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "create_folder",
+            "description": "Create a new email folder in the account",
+            "parameters": {
+                "folder_name": "Name of the folder to create (e.g., INBOX.Archive). Placed under the server's personal namespace prefix if it lacks one",
+                "parent_folder": "Optional folder to create it in; the name is joined with the server's hierarchy delimiter",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "delete_folder",
+            "description": "Delete an email folder from the account",
+            "parameters": {
+                "folder_name": "Name of the folder to delete (e.g., INBOX.OldEmails)",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "rename_folder",
+            "description": "Rename an email folder in the account",
+            "parameters": {
+                "old_name": "Current name of the folder (e.g., INBOX.Temp)",
+                "new_name": "New name for the folder (e.g., INBOX.Projects). A name without the server's hierarchy delimiter keeps the folder under its current parent",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "fetch_emails_with_mime",
+            "description": "Fetch email content with MIME data",
+            "parameters": {
+                "folder": "Folder containing the email",
+                "uid": "Email UID",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "atomic_move_message",
+            "description": "Move a single message to another folder",
+            "parameters": {
+                "source_folder": "Source folder",
+                "target_folder": "Target folder, or a special-use name such as \\Trash",
+                "uid": "Message UID to move",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "atomic_batch_move",
+            "description": "Move multiple messages to another folder",
+            "parameters": {
+                "source_folder": "Source folder",
+                "target_folder": "Target folder, or a special-use name such as \\Trash",
+                "uids": "Comma-separated list of UIDs",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "mark_as_deleted",
+            "description": "Mark messages as deleted",
+            "parameters": {
+                "folder": "Folder containing messages",
+                "uids": "Comma-separated list of UIDs",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "delete_messages",
+            "description": "Permanently delete messages",
+            "parameters": {
+                "folder": "Folder containing messages, or a special-use name such as \\Trash",
+                "uids": "Comma-separated list of UIDs",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "undelete_messages",
+            "description": "Unmark messages as deleted",
+            "parameters": {
+                "folder": "Folder containing messages",
+                "uids": "Comma-separated list of UIDs",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "expunge",
+            "description": "Expunge deleted messages from folder",
+            "parameters": {
+                "folder": "Folder to expunge",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        // Cache-based tools
+        serde_json::json!({
+            "name": "list_cached_emails",
+            "description": "List cached emails from database",
+            "parameters": {
+                "folder": "Folder name (default: INBOX)",
+                "limit": "Maximum number of emails (default: 20)",
+                "offset": "Pagination offset (default: 0)",
+                "cursor": "Optional. next_cursor of the previous page (empty for the first); preferred over offset",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_email_by_uid",
+            "description": "Get full cached email by UID",
+            "parameters": {
+                "folder": "Folder name (default: INBOX)",
+                "uid": "Email UID",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
+                "cache_only": "Optional. Don't fetch from IMAP on a cache miss (default: false)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_email_by_index",
+            "description": "Get cached email by position index",
+            "parameters": {
+                "folder": "Folder name (default: INBOX)",
+                "index": "Zero-based position index",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "count_emails_in_folder",
+            "description": "Count total emails in cached folder",
+            "parameters": {
+                "folder": "Folder name (default: INBOX)",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_folder_stats",
+            "description": "Get statistics about cached folder",
+            "parameters": {
+                "folder": "Folder name (default: INBOX)",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "search_cached_emails",
+            "description": "Search within cached emails",
+            "parameters": {
+                "folder": "Folder name (default: INBOX)",
+                "query": "Search query text",
+                "limit": "Maximum number of results (default: 20)",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        // Account management tools
+        serde_json::json!({
+            "name": "list_accounts",
+            "description": "List all configured email accounts",
+            "parameters": {}
+        }),
+        serde_json::json!({
+            "name": "set_current_account",
+            "description": "Set the current account for email operations",
+            "parameters": {
+                "account_id": "Account ID to set as current"
+            }
+        }),
+        // SMTP email sending
+        serde_json::json!({
+            "name": "send_email",
+            "description": "Send an email via SMTP",
+            "parameters": {
+                "to": "REQUIRED. Array of recipient email addresses",
+                "subject": "REQUIRED. Email subject line",
+                "body": "REQUIRED. Plain text email body",
+                "cc": "Optional. Array of CC recipient email addresses",
+                "bcc": "Optional. Array of BCC recipient email addresses",
+                "body_html": "Optional. HTML email body (multipart with plain text fallback)",
+                "verify_recipients": "Optional. Refuse to send if any recipient is clearly undeliverable (default: false)",
+                "attachments": "Optional. Array of {filename, content_type, data (base64) or path, content_id for inline images}",
+                "account_id": "Optional. Email address of the sending account (uses default if not specified)"
+            }
+        }),
+        // Attachment management tools
+        serde_json::json!({
+            "name": "list_email_attachments",
+            "description": "List all attachments for a specific email",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
+                "folder": "Folder containing the email (when using uid)",
+                "uid": "Email UID (alternative to message_id)",
+                "message_id": "Message ID (alternative to folder+uid)"
+            }
+        }),
+        serde_json::json!({
+            "name": "download_email_attachments",
+            "description": "Download attachments from an email to local directory",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
+                "folder": "Folder containing the email (when using uid)",
+                "uid": "Email UID (alternative to message_id)",
+                "message_id": "Message ID (alternative to folder+uid)",
+                "destination": "Destination directory path (optional)",
+                "create_zip": "Create ZIP archive instead of individual files (optional, boolean)"
+            }
+        }),
+        serde_json::json!({
+            "name": "cleanup_attachments",
+            "description": "Delete downloaded attachments for a specific email",
+            "parameters": {
+                "message_id": "REQUIRED. The message ID of the email",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_attachment_content",
+            "description": "Get a single attachment's content as base64",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "message_id": "Message-ID (provide this OR folder+uid)",
+                "folder": "Folder name (if message_id not provided)",
+                "uid": "Email UID (if message_id not provided)",
+                "filename": "REQUIRED. Filename of the attachment"
+            }
+        }),
+        serde_json::json!({
+            "name": "mark_as_read",
+            "description": "Mark messages as read (adds \\Seen flag)",
+            "parameters": {
+                "folder": "REQUIRED. Folder containing messages",
+                "uids": "REQUIRED. Array of message UIDs to mark as read",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "mark_as_unread",
+            "description": "Mark messages as unread (removes \\Seen flag)",
+            "parameters": {
+                "folder": "REQUIRED. Folder containing messages",
+                "uids": "REQUIRED. Array of message UIDs to mark as unread",
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)"
+            }
+        }),
+        serde_json::json!({
+            "name": "sync_emails",
+            "description": "Trigger email sync for a specific folder or all folders",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
+                "folder": "Optional. Specific folder to sync (e.g., 'INBOX', 'INBOX/resumes'). If omitted, syncs all folders."
+            }
+        }),
+        serde_json::json!({
+            "name": "get_email_synopsis",
+            "description": "Get a concise synopsis of an email (subject + first sentences)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "Optional. Folder name (default: INBOX)",
+                "uid": "REQUIRED. Email UID",
+                "max_lines": "Optional. Max sentences to extract (default: 3)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_email_thread",
+            "description": "Get all emails in a conversation thread by message_id",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "message_id": "REQUIRED. Message-ID of any email in the thread"
+            }
+        }),
+        serde_json::json!({
+            "name": "search_by_domain",
+            "description": "Search cached emails by sender/recipient domain",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "domain": "REQUIRED. Domain to search for (e.g., 'gmail.com')",
+                "search_in": "Optional. Array of fields: 'from', 'to', 'cc' (default: ['from'])",
+                "limit": "Optional. Max results (default: 50)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_address_report",
+            "description": "Get aggregated report of unique email addresses and domains",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account"
+            }
+        }),
+        serde_json::json!({
+            "name": "list_emails_by_flag",
+            "description": "Filter cached emails by IMAP flags (Seen, Flagged, Answered, etc.)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "Optional. Folder name (default: INBOX)",
+                "flags_include": "Optional. Array of flags emails must have (e.g., ['Flagged'])",
+                "flags_exclude": "Optional. Array of flags emails must not have (e.g., ['Seen'] for unread)",
+                "unread_only": "Optional. Boolean shorthand for flags_exclude=['Seen']",
+                "limit": "Optional. Max results (default: 50)",
+                "offset": "Optional. Pagination offset (default: 0)"
+            }
+        }),
+        serde_json::json!({
+            "name": "search_by_attachment_type",
+            "description": "Search for attachments matching MIME type patterns (e.g., 'image/*', 'application/pdf')",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
+                "mime_types": "REQUIRED. Array of MIME type patterns (e.g., ['image/*', 'application/pdf'])",
+                "limit": "Optional. Maximum results to return (default: 50)"
+            }
+        }),
+        serde_json::json!({
+            "name": "export_evidence",
+            "description": "Export emails and attachments into an organized evidence directory for attorney review",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account (e.g., user@example.com)",
+                "folder": "Optional. Folder name to limit export. If omitted, exports all folders.",
+                "search_query": "Optional. Search string to filter emails.",
+                "output_path": "Optional. Override output directory (default: EVIDENCE_EXPORT_DIR env or data/evidence_exports)."
+            }
+        }),
+        serde_json::json!({
+            "name": "export_folder_metadata",
+            "description": "Export email metadata (no body content) to a file on disk. Returns file path only — ideal for large folders.",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "REQUIRED. Folder name (e.g., 'INBOX')",
+                "format": "Optional. 'json' (default) or 'csv'",
+                "fields": "Optional. Comma-separated field names to include (e.g., 'uid,subject,date')",
+                "limit": "Optional. Max rows to export (default: 10000)"
+            }
+        }),
+        serde_json::json!({
+            "name": "filter_emails_by_subject",
+            "description": "Filter emails by subject line patterns. Returns metadata only (no body content).",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "REQUIRED. Folder name (e.g., 'INBOX')",
+                "subject_patterns": "REQUIRED. Array of keywords to match against subjects (case-insensitive)",
+                "match_mode": "Optional. 'any' (default) or 'all'",
+                "sender_filter": "Optional. Restrict to sender address/domain",
+                "recipient_filter": "Optional. Restrict to recipient address/domain",
+                "date_after": "Optional. ISO 8601 date lower bound",
+                "date_before": "Optional. ISO 8601 date upper bound",
+                "max_results": "Optional. Max results (default: 500)"
+            }
+        }),
+        serde_json::json!({
+            "name": "batch_get_synopsis",
+            "description": "Get compact synopses for multiple emails in a single call (max 50 UIDs).",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "REQUIRED. Folder name (e.g., 'INBOX')",
+                "uids": "REQUIRED. Array of email UIDs (max 50 per call)",
+                "max_chars_per_synopsis": "Optional. Character cap per synopsis (default: 300, max: 1500)"
+            }
+        }),
+        serde_json::json!({
+            "name": "transfer_messages",
+            "description": "Transfer messages between accounts (fetch raw, append to destination, optionally delete source)",
+            "parameters": {
+                "source_account_id": "REQUIRED. Email address of the account to transfer from",
+                "source_folder": "REQUIRED. Source folder",
+                "uids": "REQUIRED. Array of UIDs to transfer",
+                "dest_account_id": "REQUIRED. Email address of the account to transfer to",
+                "dest_folder": "REQUIRED. Destination folder",
+                "delete_source": "Optional. Delete verified messages from the source (default: false)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_server_info",
+            "description": "Get current server time, timezone offset, and date interpretation rules",
+            "parameters": {}
+        }),
+        serde_json::json!({
+            "name": "semantic_search",
+            "description": "Search cached emails by meaning (embeddings); falls back to keyword search when disabled",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "query": "REQUIRED. Natural-language query",
+                "folder": "Optional. Restrict to one folder",
+                "limit": "Optional. Max results (default: 10)"
+            }
+        }),
+        serde_json::json!({
+            "name": "all_accounts_summary",
+            "description": "Totals, unread counts, sync status and pool health across all accounts",
+            "parameters": {}
+        }),
+        serde_json::json!({
+            "name": "flag_messages",
+            "description": "Flag (star) messages as important (\\Flagged)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "REQUIRED. Folder containing the messages",
+                "uids": "REQUIRED. Array of message UIDs"
+            }
+        }),
+        serde_json::json!({
+            "name": "unflag_messages",
+            "description": "Remove the \\Flagged flag (star) from messages",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "REQUIRED. Folder containing the messages",
+                "uids": "REQUIRED. Array of message UIDs"
+            }
+        }),
+        serde_json::json!({
+            "name": "list_flagged",
+            "description": "List flagged (starred) emails across all folders, newest first",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "limit": "Optional. Max results (default: 50)",
+                "offset": "Optional. Pagination offset (default: 0)"
+            }
+        }),
+        serde_json::json!({
+            "name": "sieve_list_scripts",
+            "description": "List server-side Sieve filter scripts (ManageSieve)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account"
+            }
+        }),
+        serde_json::json!({
+            "name": "sieve_get_script",
+            "description": "Get the source of a Sieve script (ManageSieve)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "name": "REQUIRED. Name of the Sieve script"
+            }
+        }),
+        serde_json::json!({
+            "name": "sieve_put_script",
+            "description": "Upload or replace a Sieve script (ManageSieve)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "name": "REQUIRED. Name of the Sieve script",
+                "content": "REQUIRED. Sieve script source"
+            }
+        }),
+        serde_json::json!({
+            "name": "sieve_activate_script",
+            "description": "Activate a Sieve script (ManageSieve)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "name": "REQUIRED. Script to activate, or empty string to deactivate all"
+            }
+        }),
+        serde_json::json!({
+            "name": "sieve_delete_script",
+            "description": "Delete a Sieve script (ManageSieve)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "name": "REQUIRED. Name of the Sieve script"
+            }
+        }),
+        serde_json::json!({
+            "name": "refresh_email",
+            "description": "Re-fetch an email from IMAP and repair its cached body",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "REQUIRED. Folder containing the email",
+                "uid": "REQUIRED. UID of the email"
+            }
+        }),
+        serde_json::json!({
+            "name": "download_folder_attachments",
+            "description": "ZIP all attachments in a folder, one directory per email (background job)",
+            "parameters": {
+                "account_id": "REQUIRED. Email address of the account",
+                "folder": "REQUIRED. Folder to collect attachments from",
+                "from": "Optional. Sender address or name contains",
+                "subject": "Optional. Subject contains",
+                "since": "Optional. On or after this date",
+                "before": "Optional. On or before this date",
+                "max_emails": "Optional. How many of the newest cached emails to scan (default: 500)"
+            }
+        }),
+        serde_json::json!({
+            "name": "verify_recipient",
+            "description": "Check addresses for deliverability (syntax, MX, optional SMTP probe)",
+            "parameters": {
+                "address": "REQUIRED. Address or array of addresses to check",
+                "account_id": "Optional. Account used as the SMTP probe sender"
+            }
+        }),
+        serde_json::json!({
+            "name": "send_from_template",
+            "description": "Send an email from a stored template with {{variable}} substitution",
+            "parameters": {
+                "template": "REQUIRED. Name of the template",
+                "to": "REQUIRED. Array of recipient email addresses",
+                "variables": "Optional. Object of placeholder values",
+                "cc": "Optional. Array of CC recipient email addresses",
+                "bcc": "Optional. Array of BCC recipient email addresses",
+                "account_id": "Optional. Sending account that owns the template (uses default if not specified)"
+            }
+        }),
+        serde_json::json!({
+            "name": "mark_read_matching",
+            "description": "Mark all unread messages matching a server-side search as read",
+            "parameters": {
+                "folder": "REQUIRED. Folder to search",
+                "from": "Optional. Sender substring",
+                "to": "Optional. Recipient substring",
+                "subject": "Optional. Subject substring",
+                "body": "Optional. Body substring",
+                "text": "Optional. Header or body substring",
+                "since": "Optional. Start date (YYYY-MM-DD or RFC 3339)",
+                "before": "Optional. End date (YYYY-MM-DD or RFC 3339)",
+                "dry_run": "Optional. Only count matches (default false)",
+                "account_id": "Optional. Account email address (uses default if not specified)"
+            }
+        }),
+        serde_json::json!({
+            "name": "folder_threads",
+            "description": "Get thread count, largest thread, top participants and the thread list for a folder",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "Optional. Folder name (default: INBOX)",
+                "max_emails": "Optional. Cached emails to group (default: 1000)",
+                "limit": "Optional. Max threads to list (default: 50)",
+                "top_participants": "Optional. Number of top senders (default: 10)"
+            }
+        }),
+        serde_json::json!({
+            "name": "list_recent",
+            "description": "List emails marked \\Recent (arrived since the previous sync) and per-folder RECENT counts",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "Optional. Folder name (default: all folders)",
+                "limit": "Optional. Max results (default: 50)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_email_text",
+            "description": "Get an email's plain-text body (text/plain or converted HTML)",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "Optional. Folder name (default: INBOX)",
+                "uid": "REQUIRED. Email UID",
+                "strip_quotes": "Optional. Remove quoted reply text (default: false)",
+                "cache_only": "Optional. Skip server fetch on cache miss (default: false)"
+            }
+        }),
+        serde_json::json!({
+            "name": "check_uids_exist",
+            "description": "Check which UIDs still exist in a folder on the server",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "REQUIRED. Folder to check",
+                "uids": "REQUIRED. Array of UIDs to check"
+            }
+        }),
+        serde_json::json!({
+            "name": "next_unread",
+            "description": "Get the oldest (or newest) unread email in a folder",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "Optional. Folder name (default: INBOX)",
+                "order": "Optional. 'oldest' or 'newest' (default: oldest)"
+            }
+        }),
+        serde_json::json!({
+            "name": "cache_status",
+            "description": "Get per-folder cache freshness: last sync, sync in progress, cached vs server counts, staleness",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "Optional. Only report this folder",
+                "live": "Optional. Check server counts with STATUS (default: false)",
+                "stale_after_seconds": "Optional. Staleness threshold in seconds (default: 900)"
+            }
+        }),
+        serde_json::json!({
+            "name": "compare_folders",
+            "description": "Compare two folders by Message-ID and list messages present in only one of them",
+            "parameters": {
+                "source_account_id": "Optional. Account of the source folder (uses default if not specified)",
+                "source_folder": "Folder messages were copied from",
+                "dest_account_id": "Optional. Account of the destination folder (defaults to the source account)",
+                "dest_folder": "Folder messages were copied to",
+                "verify_on_server": "Optional. Search the server for Message-IDs missing from the cache (default: false)",
+                "limit": "Optional. Maximum messages listed for each side (default: 100)"
+            }
+        }),
+        serde_json::json!({
+            "name": "extract_links",
+            "description": "List de-duplicated links in a cached email, flagging anchors whose text domain differs from the href",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "Folder containing the email (default: INBOX)",
+                "uid": "UID of the email"
+            }
+        }),
+        serde_json::json!({
+            "name": "empty_folder",
+            "description": "Permanently delete all (or only old) messages in a folder, by name or special-use role like trash/junk",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "folder": "Folder to empty (give this or role)",
+                "role": "Special-use role: trash, junk/spam, sent, drafts, archive (give this or folder)",
+                "confirm": "Must be true; the deletion is permanent",
+                "older_than_days": "Optional. Only purge messages older than this many days"
+            }
+        }),
+        serde_json::json!({
+            "name": "hierarchical_unread",
+            "description": "Per-folder unread counts rolled up over subfolders, plus the account total",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "live": "Optional. Use server STATUS counts instead of the cache (default: false)"
+            }
+        }),
+        serde_json::json!({
+            "name": "sync_lag",
+            "description": "Compare live server counts with the cache per folder and score each account's sync lag",
+            "parameters": {
+                "account_id": "Optional. Email address of the account (uses default if not specified)",
+                "all_accounts": "Optional. Check every configured account (default: false)",
+                "folder": "Optional. Only check this folder",
+                "stale_after_seconds": "Optional. Sync age that counts as behind (default: 900)"
+            }
+        }),
+        serde_json::json!({
+            "name": "refresh_account_connections",
+            "description": "Drop an account's sessions, re-read its credentials and test a fresh login",
+            "parameters": {
+                "account_id": "Email address of the account to reconnect"
+            }
+        }),
+        serde_json::json!({
+            "name": "search_server",
+            "description": "Search a folder on the IMAP server, including messages not yet cached",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "folder": "Optional. Folder to search (default: INBOX)",
+                "criteria": "Optional. Object with from, to, subject, body, text, since, before, unseen, flagged (empty matches all)",
+                "limit": "Optional. Maximum matches returned, newest first (default: 50, max: 500); total_matches may be higher",
+                "sort_by": "Optional. Sort keys arrival, date, from, subject, size; '-' prefix reverses (e.g. '-date')"
+            }
+        }),
+        serde_json::json!({
+            "name": "search_cached_emails_fts",
+            "description": "Relevance-ranked full-text search of cached emails with snippets",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "query": "Words to search for (all must match; word* for prefixes)",
+                "folder": "Optional. Only search this folder (default: all folders)",
+                "limit": "Optional. Maximum results (default: 20)"
+            }
+        }),
+        serde_json::json!({
+            "name": "copy_message",
+            "description": "Copy emails to another folder, keeping the originals",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "source_folder": "Folder containing the email",
+                "target_folder": "Folder to copy the email into",
+                "uid": "UID of the email to copy",
+                "uids": "Optional. Several UIDs to copy instead of 'uid'"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_quota",
+            "description": "Get mailbox storage quota and usage",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "folder": "Optional. Mailbox whose quota to report (default: INBOX)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_thread",
+            "description": "Get the conversation tree an email belongs to",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "folder": "Folder containing the email",
+                "uid": "UID of any email in the conversation",
+                "algorithm": "Optional. references or ordered_subject (default: references)"
+            }
+        }),
+        serde_json::json!({
+            "name": "save_draft",
+            "description": "Save an email to the Drafts folder without sending it",
+            "parameters": {
+                "account_id": "Optional. Account email address (uses default if not specified)",
+                "to": "Recipient email address(es) (string or array)",
+                "cc": "Optional. CC email address(es)",
+                "bcc": "Optional. BCC email address(es)",
+                "subject": "Optional. Email subject",
+                "body": "Optional. Plain text body",
+                "body_html": "Optional. HTML body",
+                "attachments": "Optional. Files to attach, as for send_email"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_special_folders",
+            "description": "Get the account's Sent, Drafts, Trash, Junk and Archive folders",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "refresh": "Optional. List folders again instead of using the stored result (default: false)"
+            }
+        }),
+        serde_json::json!({
+            "name": "move_to_trash",
+            "description": "Move messages to the account's Trash folder",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "folder": "Folder containing the messages",
+                "uids": "UIDs of the messages to move to Trash"
+            }
+        }),
+        serde_json::json!({
+            "name": "fetch_email_partial",
+            "description": "Fetch a byte range of an email or one MIME part without downloading all of it",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "folder": "Folder containing the email",
+                "uid": "UID of the email",
+                "offset": "First byte to return (default 0)",
+                "length": "Number of bytes to return (default 2048, max 1048576)",
+                "section": "MIME part or section, e.g. '1', '1.2', 'HEADER' or '2.TEXT' (optional; whole message if omitted)"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_unread_counts",
+            "description": "Get total and unread counts for every folder in one call",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "max_age_seconds": "Optional. Use cached counts synced within this many seconds (default: 300)"
+            }
+        }),
+        serde_json::json!({
+            "name": "bulk_flag",
+            "description": "Add, remove or set flags on messages in several folders in one call",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "groups": "Array of {folder, uids} objects",
+                "operation": "Optional. add, remove or set (default: add)",
+                "flags": "Optional. Flags to change (default: [\"\\\\Seen\"])"
+            }
+        }),
+        serde_json::json!({
+            "name": "get_server_capabilities",
+            "description": "Get the IMAP extensions the account's server supports",
+            "parameters": {
+                "account_id": "Email address of the account",
+                "refresh": "Optional. Ask the server again instead of using the stored result (default: false)"
+            }
+        })
+    ]
+    }; // End of if-else for variant
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "tools": tools
+    })))
+}
+
+/// Helper function to get account_id from request parameters
+/// REQUIRES account_id to be provided as an email address
+/// Returns the email address directly (no UUID lookup)
+async fn get_account_id_to_use(
+    params: &serde_json::Value,
+    _state: &web::Data<DashboardState>,
+) -> Result<String, ApiError> {
+    // account_id is REQUIRED and must be an email address
+    if let Some(account_id) = params.get("account_id").and_then(|v| v.as_str()) {
+        return Ok(account_id.to_string());
+    }
+
+    // If account_id not provided, return error
+    Err(ApiError::BadRequest(
+        "account_id parameter is required and must be an email address (e.g., user@example.com)".to_string()
+    ))
+}
+
+/// A folder parameter with special-use aliases such as `\Trash` or
+/// `\Junk` replaced by the account's folder for that role.
+async fn resolve_folder_param(
+    state: &DashboardState,
+    params: &serde_json::Value,
+    folder: &str,
+) -> Result<String, String> {
+    if crate::imap::special_use::SpecialUse::from_folder_alias(folder).is_none() {
+        return Ok(folder.to_string());
+    }
+    let account_id = resolve_account_or_default(params.get("account_id").and_then(|v| v.as_str()), state)
+        .await
+        .map_err(|e| format!("Failed to determine account: {}", e))?;
+    state.email_service.resolve_folder_for_account(folder, &account_id)
+        .await
+        .map_err(|e| format!("Failed to resolve folder '{}': {}", folder, e))
+}
+
+/// Resolve the account for an account-scoped dashboard operation: the explicit
+/// `account_id` when given, otherwise the default account. With
+/// `require_explicit_account` enabled the default is never assumed.
+async fn resolve_account_or_default(
+    account_id: Option<&str>,
+    state: &DashboardState,
+) -> Result<String, ApiError> {
+    if let Some(account_id) = account_id {
+        return Ok(account_id.to_string());
+    }
+
+    if state.config_service.settings().require_explicit_account {
+        return Err(ApiError::BadRequest(
+            "account_id is required (require_explicit_account is enabled, so the default account is not used)".to_string()
+        ));
+    }
+
+    let account_service = state.account_service.lock().await;
+    match account_service.get_default_account().await {
+        Ok(Some(account)) => Ok(account.email_address),
+        Ok(None) => Err(ApiError::NotFound("No default account configured".to_string())),
+        Err(e) => Err(ApiError::InternalError(format!("Failed to get default account: {}", e))),
+    }
+}
+
+/// Validate that an account exists by checking if it can be retrieved
+/// Returns the account_id (email address) if the account is found
+async fn validate_account_exists(
+    account_id: &str,
+    state: &DashboardState,
+) -> Result<String, ApiError> {
+    // Verify the account exists by looking it up
+    let account_service = state.account_service.lock().await;
+    let _account = account_service.get_account(account_id).await
+        .map_err(|e| ApiError::NotFound(format!("Account not found: {}", e)))?;
+    drop(account_service); // Release lock
+
+    // Return the account_id
+    Ok(account_id.to_string())
+}
+
+/// Error result for a ManageSieve tool. Servers without ManageSieve are
+/// reported with `"supported": false` so callers can tell them apart from
+/// script errors.
+fn sieve_failure(
+    tool_name: &str,
+    error: crate::dashboard::services::email::EmailServiceError,
+) -> serde_json::Value {
+    use crate::dashboard::services::email::EmailServiceError;
+    use crate::sieve::SieveError;
+
+    let supported = !matches!(error, EmailServiceError::SieveError(SieveError::NotSupported(_)));
+    serde_json::json!({
+        "success": false,
+        "error": error.to_string(),
+        "supported": supported,
+        "tool": tool_name
+    })
+}
+
+/// A conversation tree as JSON, each message with its envelope and its
+/// replies nested under it.
+fn thread_json(
+    node: &crate::imap::threading::ThreadNode,
+    messages: &std::collections::HashMap<u32, &crate::imap::types::Email>,
+) -> serde_json::Value {
+    let email = messages.get(&node.uid);
+    let envelope = email.and_then(|e| e.envelope.as_ref());
+    serde_json::json!({
+        "uid": node.uid,
+        "message_id": envelope.and_then(|e| e.message_id.clone()),
+        "in_reply_to": envelope.and_then(|e| e.in_reply_to.clone()),
+        "subject": envelope.and_then(|e| e.subject.clone()),
+        "from": envelope.map(|e| e.from.clone()).unwrap_or_default(),
+        "to": envelope.map(|e| e.to.clone()).unwrap_or_default(),
+        "date": envelope.and_then(|e| e.date.clone()),
+        "flags": email.map(|e| e.flags.clone()).unwrap_or_default(),
+        "replies": node.children.iter().map(|child| thread_json(child, messages)).collect::<Vec<_>>()
+    })
+}
+
+/// Build a server-side SEARCH from the optional `from`, `to`, `subject`,
+/// `body`, `text`, `since` and `before` parameters. At least one is required
+/// so a bulk operation never silently applies to a whole folder.
+fn search_criteria_from_params(
+    params: &serde_json::Value,
+) -> Result<Vec<crate::imap::types::SearchCriteria>, String> {
+    let criteria = search_terms_from_params(params)?;
+    if criteria.is_empty() {
+        return Err("At least one of from, to, subject, body, text, since or before is required".to_string());
+    }
+    Ok(criteria)
+}
+
+/// The SEARCH keys given in `params`, in a fixed order; empty when none are.
+fn search_terms_from_params(
+    params: &serde_json::Value,
+) -> Result<Vec<crate::imap::types::SearchCriteria>, String> {
+    use crate::imap::dates;
+    use crate::imap::types::SearchCriteria;
+
+    let text = |key: &str| params.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty());
+    let mut criteria = Vec::new();
+    if let Some(v) = text("from") {
+        criteria.push(SearchCriteria::From(v.to_string()));
+    }
+    if let Some(v) = text("to") {
+        criteria.push(SearchCriteria::To(v.to_string()));
+    }
+    if let Some(v) = text("subject") {
+        criteria.push(SearchCriteria::Subject(v.to_string()));
+    }
+    if let Some(v) = text("body") {
+        criteria.push(SearchCriteria::Body(v.to_string()));
+    }
+    if let Some(v) = text("text") {
+        criteria.push(SearchCriteria::Text(v.to_string()));
+    }
+    if let Some(v) = text("since") {
+        criteria.push(SearchCriteria::Since(dates::parse_timestamp(v)?));
+    }
+    if let Some(v) = text("before") {
+        criteria.push(SearchCriteria::Before(dates::parse_range_end(v)?));
+    }
+    Ok(criteria)
+}
+
+/// Inner function that executes MCP tools and returns raw JSON result
+/// Can be called from both HTTP handler and MCP protocol handler
+#[tracing::instrument(skip_all, fields(tool = %tool_name))]
+pub async fn execute_mcp_tool_inner(
+    state: &DashboardState,
+    tool_name: &str,
+    params: serde_json::Value,
+) -> serde_json::Value {
+    use crate::dashboard::services::tool_dedup;
+
+    if !tool_dedup::dedup_enabled() {
+        return run_mcp_tool(state, tool_name, params).await;
+    }
+
+    if tool_dedup::is_cacheable(tool_name) {
+        if let Some(result) = tool_dedup::lookup(tool_name, &params) {
+            debug!("Reusing recent result for MCP tool {}", tool_name);
+            return result;
+        }
+        let result = run_mcp_tool(state, tool_name, params.clone()).await;
+        tool_dedup::store(tool_name, &params, &result);
+        return result;
+    }
+
+    let result = run_mcp_tool(state, tool_name, params).await;
+    if tool_dedup::is_mutating(tool_name) && result.get("success").and_then(|v| v.as_bool()) == Some(true) {
+        tool_dedup::invalidate_all();
+    }
+    result
+}
+
+async fn run_mcp_tool(
+    state: &DashboardState,
+    tool_name: &str,
+    params: serde_json::Value,
+) -> serde_json::Value {
+    debug!("Executing MCP tool: {} with params: {:?}", tool_name, params);
+
+    // Get the email service from the state
+    let email_service = state.email_service.clone();
+
+    // Create a temporary web::Data wrapper for helper functions that need it
+    let state_data = web::Data::new(state.clone());
+
+    // Execute the appropriate tool
+    let result = match tool_name {
+        "list_folders" => {
+            // Get account ID from request or use default
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            if params.get("include_state").and_then(|v| v.as_bool()).unwrap_or(false) {
+                // Selectability (\Noselect) and LSUB subscription per folder
+                let folder = params.get("folder").and_then(|v| v.as_str());
+                match email_service.list_folder_states_for_account(&account_id).await {
+                    Ok(states) => {
+                        let states: Vec<_> = match folder {
+                            Some(name) => states
+                                .into_iter()
+                                .filter(|s| crate::imap::pipeline::mailbox_matches(name, &s.name))
+                                .collect(),
+                            None => states,
+                        };
+                        match folder {
+                            Some(name) if states.is_empty() => serde_json::json!({
+                                "success": false,
+                                "error": format!("Folder not found: {}", name),
+                                "tool": tool_name
+                            }),
+                            _ => serde_json::json!({
+                                "success": true,
+                                "data": states,
+                                "tool": tool_name
+                            }),
+                        }
+                    }
+                    Err(e) => serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to list folder states: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            } else {
+                match email_service.list_folders_for_account(&account_id).await {
+                    Ok(folders) => {
+                        serde_json::json!({
+                            "success": true,
+                            "data": folders,
+                            "tool": tool_name
+                        })
+                    }
+                    Err(e) => {
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to list folders: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+            }
+        }
+        "list_folders_hierarchical" => {
+            // Get account ID from request or use default
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            match email_service.list_folders_tree_for_account(&account_id).await {
+                Ok(tree) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": tree,
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to list folders: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "create_folder" => {
+            let folder_name = match params.get("folder_name").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder_name' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            // Get account ID from request or use default
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let parent_folder = params.get("parent_folder").and_then(|v| v.as_str());
+
+            match email_service.create_folder_for_account(folder_name, parent_folder, &account_id).await {
+                Ok(created) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "folder_name": created,
+                            "account_id": account_id
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to create folder: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "delete_folder" => {
+            let folder_name = match params.get("folder_name").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder_name' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            // Get account ID from request or use default
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            match email_service.delete_folder_for_account(folder_name, &account_id).await {
+                Ok(_) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "folder_name": folder_name,
+                            "account_id": account_id
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to delete folder: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "rename_folder" => {
+            let old_name = match params.get("old_name").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'old_name' parameter",
+                    "tool": tool_name
+                })
+            };
+            let new_name = match params.get("new_name").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'new_name' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            // Get account ID from request or use default
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            match email_service.rename_folder_for_account(old_name, new_name, &account_id).await {
+                Ok(renamed) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "old_name": old_name,
+                            "new_name": renamed,
+                            "account_id": account_id
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to rename folder: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "fetch_emails_with_mime" => {
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                Some(u) => u as u32,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uid' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            // Get account ID from request or use default
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            // fetch_emails expects an array of UIDs
+            match email_service.fetch_emails_for_account(folder, &[uid], &account_id).await {
+                Ok(emails) => {
+                    // Return just the first email if found
+                    let email_data = emails.into_iter().next();
+                    serde_json::json!({
+                        "success": true,
+                        "data": email_data,
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to fetch email: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        // Cache-based tools
+        "list_cached_emails" => {
+            let folder = params.get("folder")
+                .and_then(|v| v.as_str())
+                .unwrap_or("INBOX");
+            let limit = params.get("limit")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(20);
+            let offset = params.get("offset")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(0);
+            let preview_mode = params.get("preview_mode")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);  // Default to preview mode for token efficiency
+            let cursor = params.get("cursor").and_then(|v| v.as_str());
+            let start = match cursor.map(str::trim) {
+                None | Some("") => PageStart::Offset(if cursor.is_some() { 0 } else { offset }),
+                Some(c) => match EmailCursor::decode(c) {
+                    Some(cursor) => PageStart::After(cursor),
+                    None => {
+                        return serde_json::json!({
+                            "success": false,
+                            "error": format!("Invalid cursor '{}'; pass the next_cursor of a previous page", c),
+                            "tool": tool_name
+                        });
+                    }
+                },
+            };
+
+            // Get account ID from request or use default
+            match get_account_id_to_use(&params, &state_data).await {
+                Ok(account_id) => {
+                    let account_email = match validate_account_exists(&account_id, &state).await {
+                        Ok(id) => id,
+                        Err(e) => {
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to lookup account: {}", e)
+                            });
+                        }
+                    };
+                    match state.cache_service.get_cached_emails_page(folder, &account_email, limit, start, preview_mode).await {
+                        Ok((emails, next_cursor)) => {
+                            let next_cursor = next_cursor.map(|c| c.encode());
+                            let count = emails.len();
+                            // Cursor callers get the cursor inside data, which is all MCP clients see
+                            let data = if cursor.is_some() {
+                                serde_json::json!({ "emails": emails, "next_cursor": next_cursor })
+                            } else {
+                                serde_json::json!(emails)
+                            };
+                            serde_json::json!({
+                                "success": true,
+                                "data": data,
+                                "folder": folder,
+                                "count": count,
+                                "next_cursor": next_cursor,
+                                "tool": tool_name
+                            })
+                        }
+                        Err(e) => {
+                            serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to get cached emails: {}", e),
+                                "tool": tool_name
+                            })
+                        }
+                    }
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to determine account: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "get_email_by_uid" => {
+            let folder = params.get("folder")
+                .and_then(|v| v.as_str())
+                .unwrap_or("INBOX");
+            let uid = params.get("uid")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32);
+
+            // Get account ID from request or use default
+            match get_account_id_to_use(&params, &state_data).await {
+                Ok(account_id) => {
+                    let account_email = match validate_account_exists(&account_id, &state).await {
+                        Ok(id) => id,
+                        Err(e) => {
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to lookup account: {}", e)
+                            });
+                        }
+                    };
+                    if let Some(uid) = uid {
+                        let cache_only = params.get("cache_only")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false);
+                        match email_service.get_email_by_uid_with_backfill(folder, uid, &account_email, cache_only).await {
+                            Ok(Some(email)) => {
+                                // Serialize email and parse attachment_parts from
+                                // JSON string into a proper nested array
+                                let mut data = serde_json::to_value(&email)
+                                    .unwrap_or_else(|_| serde_json::json!({}));
+                                if let Some(parts_str) = data.get("attachment_parts")
+                                    .and_then(|v| v.as_str())
+                                {
+                                    if let Ok(parts) = serde_json::from_str::<serde_json::Value>(parts_str) {
+                                        data["attachment_parts"] = parts;
+                                    }
+                                }
+                                // Fallback: if attachment_parts is still null but has_attachments
+                                // is true, query attachment_metadata table directly.
+                                let parts_is_null = data.get("attachment_parts")
+                                    .map(|v| v.is_null()).unwrap_or(true);
+                                if parts_is_null && email.has_attachments {
+                                    if let (Some(pool), Some(ref msg_id)) = (state.cache_service.db_pool.as_ref(), &email.message_id) {
+                                        if let Ok(metas) = crate::dashboard::services::attachment_storage::get_attachments_metadata(pool, &account_email, msg_id).await {
+                                            if !metas.is_empty() {
+                                                let parts: Vec<serde_json::Value> = metas.iter().map(|m| {
+                                                    serde_json::json!({
+                                                        "filename": m.filename,
+                                                        "content_type": m.content_type,
+                                                        "size": m.size_bytes
+                                                    })
+                                                }).collect();
+                                                data["attachment_parts"] = serde_json::json!(parts);
+                                            }
+                                        }
+                                    }
+                                }
+                                serde_json::json!({
+                                    "success": true,
+                                    "data": data,
+                                    "tool": tool_name
+                                })
+                            }
+                            Ok(None) => {
+                                serde_json::json!({
+                                    "success": false,
+                                    "error": format!("Email with UID {} not found in {}", uid, folder),
+                                    "tool": tool_name
+                                })
+                            }
+                            Err(e) => {
+                                serde_json::json!({
+                                    "success": false,
+                                    "error": format!("Failed to get email by UID: {}", e),
+                                    "tool": tool_name
+                                })
+                            }
+                        }
+                    } else {
+                        serde_json::json!({
+                            "success": false,
+                            "error": "UID parameter is required",
+                            "tool": tool_name
+                        })
+                    }
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to determine account: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "get_email_by_index" => {
+            let folder = params.get("folder")
+                .and_then(|v| v.as_str())
+                .unwrap_or("INBOX");
+            let index = params.get("index")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
+
+            // Get account ID from request or use default
+            match get_account_id_to_use(&params, &state_data).await {
+                Ok(account_id) => {
+                    let account_email = match validate_account_exists(&account_id, &state).await {
+                        Ok(id) => id,
+                        Err(e) => {
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to lookup account: {}", e)
+                            });
+                        }
+                    };
+                    if let Some(index) = index {
+                        // Get emails sorted by date DESC, then select by index
+                        // Dashboard UI needs full content for display
+                        match state.cache_service.get_cached_emails_for_account(folder, &account_email, index + 1, index, false).await {
+                    Ok(emails) if !emails.is_empty() => {
+                        serde_json::json!({
+                            "success": true,
+                            "data": emails[0],
+                            "tool": tool_name
+                        })
+                    }
+                    Ok(_) => {
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("No email at index {} in {}", index, folder),
+                            "tool": tool_name
+                        })
+                    }
+                    Err(e) => {
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to get email by index: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                        }
+                    } else {
+                        serde_json::json!({
+                            "success": false,
+                            "error": "index parameter is required",
+                            "tool": tool_name
+                        })
+                    }
+                }
+                Err(e) => {
+                serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+                }
+            }
+        }
+        "count_emails_in_folder" => {
+            let folder = params.get("folder")
+                .and_then(|v| v.as_str())
+                .unwrap_or("INBOX");
+
+            // Get account ID from request or use default
+            match get_account_id_to_use(&params, &state_data).await {
+                Ok(account_id) => {
+                    let account_email = match validate_account_exists(&account_id, &state).await {
+                        Ok(id) => id,
+                        Err(e) => {
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to lookup account: {}", e)
+                            });
+                        }
+                    };
+
+            match state.cache_service.count_emails_in_folder_for_account(folder, &account_email).await {
+                Ok(count) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "count": count,
+                            "folder": folder
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to count emails: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to determine account: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "get_folder_stats" => {
+            let folder = params.get("folder")
+                .and_then(|v| v.as_str())
+                .unwrap_or("INBOX");
+
+            // Get account ID from request or use default
+            match get_account_id_to_use(&params, &state_data).await {
+                Ok(account_id) => {
+                    let account_email = match validate_account_exists(&account_id, &state).await {
+                        Ok(id) => id,
+                        Err(e) => {
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to lookup account: {}", e)
+                            });
+                        }
+                    };
+
+            match state.cache_service.get_folder_stats_for_account(folder, &account_email).await {
+                Ok(stats) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": stats,
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to get folder stats: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to determine account: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "search_cached_emails" => {
+            let folder = params.get("folder")
+                .and_then(|v| v.as_str())
+                .unwrap_or("INBOX");
+            let query = params.get("query")
+                .and_then(|v| v.as_str());
+            let limit = params.get("limit")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(20);
+
+            // Get account ID from request or use default
+            match get_account_id_to_use(&params, &state_data).await {
+                Ok(account_id) => {
+                    let account_email = match validate_account_exists(&account_id, &state).await {
+                        Ok(id) => id,
+                        Err(e) => {
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to lookup account: {}", e)
+                            });
+                        }
+                    };
+
+            if let Some(query) = query {
+                match state.cache_service.search_cached_emails_for_account(folder, query, limit, &account_email).await {
+                    Ok(emails) => {
+                        serde_json::json!({
+                            "success": true,
+                            "data": emails,
+                            "query": query,
+                            "folder": folder,
+                            "count": emails.len(),
+                            "tool": tool_name
+                        })
+                    }
+                    Err(e) => {
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to search emails: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+            } else {
+                serde_json::json!({
+                    "success": false,
+                    "error": "query parameter is required",
+                    "tool": tool_name
+                })
+            }
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to determine account: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "atomic_move_message" => {
+            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                Some(u) => u as u32,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uid' parameter",
+                    "tool": tool_name
+                })
+            };
+            let from_folder = match params.get("from_folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'from_folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let to_folder = match params.get("to_folder").and_then(|v| v.as_str()) {
+                Some(t) => t,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'to_folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let to_folder = match resolve_folder_param(state, &params, to_folder).await {
+                Ok(folder) => folder,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": e,
+                    "tool": tool_name
+                })
+            };
+            let to_folder = to_folder.as_str();
+
+            match email_service.atomic_move_message(uid, from_folder, to_folder).await {
+                Ok(outcome) => {
+                    let already_gone = !outcome.already_gone.is_empty();
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uid": uid,
+                            "from_folder": from_folder,
+                            "to_folder": to_folder,
+                            "moved": !already_gone,
+                            "already_gone": already_gone
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to move message: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "atomic_batch_move" => {
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uids' parameter",
+                    "tool": tool_name
+                })
+            };
+            let from_folder = match params.get("from_folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'from_folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let to_folder = match params.get("to_folder").and_then(|v| v.as_str()) {
+                Some(t) => t,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'to_folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let to_folder = match resolve_folder_param(state, &params, to_folder).await {
+                Ok(folder) => folder,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": e,
+                    "tool": tool_name
+                })
+            };
+            let to_folder = to_folder.as_str();
+
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' parameter cannot be empty",
+                    "tool": tool_name
+                });
+            }
+
+            match email_service.atomic_batch_move(&uids, from_folder, to_folder).await {
+                Ok(outcome) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uids": outcome.applied,
+                            "from_folder": from_folder,
+                            "to_folder": to_folder,
+                            "count": outcome.applied.len(),
+                            "already_gone": outcome.already_gone
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to batch move messages: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "mark_as_read" => {
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uids' parameter",
+                    "tool": tool_name
+                })
+            };
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' parameter cannot be empty",
+                    "tool": tool_name
+                });
+            }
+
+            match email_service.mark_as_read(folder, &uids).await {
+                Ok(_) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uids": uids,
+                            "folder": folder,
+                            "count": uids.len()
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to mark messages as read: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "mark_as_unread" => {
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uids' parameter",
+                    "tool": tool_name
+                })
+            };
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' parameter cannot be empty",
+                    "tool": tool_name
+                });
+            }
+
+            match email_service.mark_as_unread(folder, &uids).await {
+                Ok(_) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uids": uids,
+                            "folder": folder,
+                            "count": uids.len()
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to mark messages as unread: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "mark_as_deleted" => {
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uids' parameter",
+                    "tool": tool_name
+                })
+            };
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' parameter cannot be empty",
+                    "tool": tool_name
+                });
+            }
+
+            match email_service.mark_as_deleted(folder, &uids).await {
+                Ok(outcome) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uids": outcome.applied,
+                            "folder": folder,
+                            "count": outcome.applied.len(),
+                            "already_gone": outcome.already_gone
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to mark messages as deleted: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "delete_messages" => {
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uids' parameter",
+                    "tool": tool_name
+                })
+            };
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let folder = match resolve_folder_param(state, &params, folder).await {
+                Ok(folder) => folder,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": e,
+                    "tool": tool_name
+                })
+            };
+            let folder = folder.as_str();
+
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' parameter cannot be empty",
+                    "tool": tool_name
+                });
+            }
+
+            match email_service.delete_messages(folder, &uids).await {
+                Ok(outcome) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uids": outcome.applied,
+                            "folder": folder,
+                            "count": outcome.applied.len(),
+                            "already_gone": outcome.already_gone
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to delete messages: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "undelete_messages" => {
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uids' parameter",
+                    "tool": tool_name
+                })
+            };
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' parameter cannot be empty",
+                    "tool": tool_name
+                });
+            }
+
+            match email_service.undelete_messages(folder, &uids).await {
+                Ok(_) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uids": uids,
+                            "folder": folder,
+                            "count": uids.len()
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to undelete messages: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "expunge" => {
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            match email_service.expunge(folder).await {
+                Ok(_) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "folder": folder
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to expunge folder: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "list_accounts" => {
+            // List all configured email accounts
+            let account_service = state.account_service.lock().await;
+            match account_service.list_accounts().await {
+                Ok(accounts) => {
+                    serde_json::json!({
+                        "success": true,
+                        "data": accounts,
+                        "count": accounts.len(),
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to list accounts: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "set_current_account" => {
+            // Set the current account context
+            let account_id = match params.get("account_id").and_then(|v| v.as_str()) {
+                Some(id) => id,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'account_id' parameter",
+                    "tool": tool_name
+                })
+            };
+
+            // Validate that the account exists
+            let account_service = state.account_service.lock().await;
+            match account_service.get_account(account_id).await {
+                Ok(account) => {
+                    // Account exists - in a real implementation, we would store this in session state
+                    // For now, just return success with the account info
+                    serde_json::json!({
+                        "success": true,
+                        "message": format!("Current account set to: {}", account_id),
+                        "data": {
+                            "account_id": account_id,
+                            "display_name": account.display_name,
+                            "email_address": account.email_address
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Account not found: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "send_email" => {
+            use crate::dashboard::services::{OutgoingAttachment, SendEmailRequest};
+
+            // Helper function to parse email addresses (handles string or array)
+            let parse_emails = |key: &str, required: bool| -> Result<Vec<String>, String> {
+                match params.get(key) {
+                    Some(val) => {
+                        if val.is_string() {
+                            let s = val.as_str().unwrap_or("");
+                            if s.is_empty() {
+                                if required {
+                                    return Err(format!("{} cannot be empty", key));
+                                }
+                                Ok(vec![])
+                            } else {
+                                Ok(vec![s.to_string()])
+                            }
+                        } else if let Some(arr) = val.as_array() {
+                            let emails = arr
+                                .iter()
+                                .filter_map(|v| v.as_str().map(String::from))
+                                .filter(|s| !s.is_empty())
+                                .collect::<Vec<String>>();
+                            if required && emails.is_empty() {
+                                return Err(format!("{} cannot be empty", key));
+                            }
+                            Ok(emails)
+                        } else {
+                            Err(format!("{} must be a string or array", key))
+                        }
+                    }
+                    None => {
+                        if required {
+                            Err(format!("{} is required", key))
+                        } else {
+                            Ok(vec![])
+                        }
+                    }
+                }
+            };
+
+            // Parse required fields
+            let to = match parse_emails("to", true) {
+                Ok(emails) => emails,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": e,
+                    "tool": tool_name
+                })
+            };
+
+            let subject = params.get("subject")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .ok_or("subject is required")
+                .map(String::from);
+
+            let subject = match subject {
+                Ok(s) => s,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": e,
+                    "tool": tool_name
+                })
+            };
+
+            let body = params.get("body")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .ok_or("body is required")
+                .map(String::from);
+
+            let body = match body {
+                Ok(b) => b,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": e,
+                    "tool": tool_name
+                })
+            };
+
+            // Parse optional fields
+            let cc = parse_emails("cc", false).ok()
+                .filter(|v| !v.is_empty());
+
+            let bcc = parse_emails("bcc", false).ok()
+                .filter(|v| !v.is_empty());
+
+            let body_html = params.get("body_html")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(String::from);
+
+            let attachments = match params.get("attachments").filter(|v| !v.is_null()) {
+                Some(value) => match serde_json::from_value::<Vec<OutgoingAttachment>>(value.clone()) {
+                    Ok(list) if !list.is_empty() => Some(list),
+                    Ok(_) => None,
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Invalid attachments: {}", e),
+                        "tool": tool_name
+                    })
+                },
+                None => None,
+            };
+
+            // Build the send request
+            let send_request = SendEmailRequest {
+                to,
+                cc,
+                bcc,
+                subject,
+                body,
+                body_html,
+                in_reply_to: None,
+                references: None,
+                attachments,
+            };
+
+            // Get account email - use account_id from params or default
+            let account_email = match resolve_account_or_default(
+                params.get("account_id").and_then(|v| v.as_str()),
+                state,
+            ).await {
+                Ok(email) => email,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            if params.get("verify_recipients").and_then(|v| v.as_bool()).unwrap_or(false) {
+                use crate::dashboard::services::recipient_verify::{self, Deliverability};
+
+                let recipients: Vec<String> = send_request.to.iter()
+                    .chain(send_request.cc.iter().flatten())
+                    .chain(send_request.bcc.iter().flatten())
+                    .cloned()
+                    .collect();
+                let checks = recipient_verify::verify_recipients(&recipients, &account_email).await;
+                let rejected: Vec<_> = checks.into_iter()
+                    .filter(|c| c.verdict == Deliverability::Undeliverable)
+                    .collect();
+                if !rejected.is_empty() {
+                    let reasons: Vec<String> = rejected.iter().map(|c| c.reason.clone()).collect();
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!("Not sent: undeliverable recipient(s): {}", reasons.join("; ")),
+                        "undeliverable": rejected,
+                        "tool": tool_name
+                    });
+                }
+            }
+
+            // Send the email using SMTP service
+            match state.smtp_service.send_email(&account_email, send_request).await {
+                Ok(response) => {
+                    if response.success {
+                        state.event_bus.publish_mailbox_activity(
+                            &account_email,
+                            crate::dashboard::services::events::MailboxAction::Sent,
+                            None,
+                            Vec::new(),
+                            response.message_id.clone(),
+                            "mcp",
+                        ).await;
+                    }
+                    serde_json::json!({
+                        "success": response.success,
+                        "message": response.message,
+                        "message_id": response.message_id,
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to send email: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "list_email_attachments" => {
+            use crate::dashboard::services::attachment_storage;
+
+            // Get account ID
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            // Get database pool
+            let db_pool = match state.cache_service.db_pool.as_ref() {
+                Some(pool) => pool,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
+            };
+
+            let folder_param = params.get("folder").and_then(|v| v.as_str());
+            let uid_param = params.get("uid").and_then(|v| v.as_u64()).map(|u| u as u32);
+
+            // Fast path: read attachment_parts from the emails cache table.
+            // This avoids expensive IMAP re-fetch and works for all synced emails.
+            // Enriches with storage_path/downloaded_at from attachment_metadata when available.
+            if let (Some(folder), Some(uid)) = (folder_param, uid_param) {
+                if let Ok(Some(cached)) = state.cache_service
+                    .get_email_by_uid_for_account(folder, uid, &account_id).await
+                {
+                    if let Some(ref parts_json) = cached.attachment_parts {
+                        if let Ok(mut parts) = serde_json::from_str::<serde_json::Value>(parts_json) {
+                            // Enrich with attachment_metadata (storage_path, downloaded_at, etc.)
+                            if let Some(ref msg_id) = cached.message_id {
+                                if let Ok(metas) = attachment_storage::get_attachments_metadata(db_pool, &account_id, msg_id).await {
+                                    if let Some(arr) = parts.as_array_mut() {
+                                        for part in arr.iter_mut() {
+                                            let fname = part.get("filename").and_then(|v| v.as_str()).unwrap_or("");
+                                            if let Some(meta) = metas.iter().find(|m| m.filename == fname) {
+                                                part["storage_path"] = serde_json::json!(meta.storage_path);
+                                                part["downloaded_at"] = serde_json::json!(meta.downloaded_at);
+                                                part["content_id"] = serde_json::json!(meta.content_id);
+                                                part["size_bytes"] = serde_json::json!(meta.size_bytes);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            return serde_json::json!({
+                                "success": true,
+                                "data": {
+                                    "account_id": account_id,
+                                    "folder": folder,
+                                    "uid": uid,
+                                    "attachments": parts,
+                                    "count": parts.as_array().map(|a| a.len()).unwrap_or(0),
+                                },
+                                "tool": tool_name
+                            });
+                        }
+                    }
+                }
+            }
+
+            // Slow path: resolve message_id and query attachment_metadata table
+            let (message_id, folder_opt, uid_opt) = if let Some(msg_id) = params.get("message_id").and_then(|v| v.as_str()) {
+                (msg_id.to_string(), None, None)
+            } else {
+                let folder = match folder_param {
+                    Some(f) => f,
+                    None => return serde_json::json!({
+                        "success": false,
+                        "error": "folder parameter required when message_id not provided",
+                        "tool": tool_name
+                    })
+                };
+
+                let uid = match uid_param {
+                    Some(u) => u,
+                    None => return serde_json::json!({
+                        "success": false,
+                        "error": "uid parameter required when message_id not provided",
+                        "tool": tool_name
+                    })
+                };
+
+                let msg_id = match email_service.fetch_emails_for_account(folder, &[uid], &account_id).await {
+                    Ok(mut emails) if !emails.is_empty() => {
+                        let email = emails.remove(0);
+                        attachment_storage::ensure_message_id(&email, &account_id)
+                    }
+                    Ok(_) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Email with UID {} not found", uid),
+                        "tool": tool_name
+                    }),
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to fetch email: {}", e),
+                        "tool": tool_name
+                    })
+                };
+
+                (msg_id, Some(folder.to_string()), Some(uid))
+            };
+
+            // Get attachments metadata from database
+            let attachments = match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
+                Ok(atts) => atts,
+                Err(e) => {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to get attachments: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            };
+
+            // If no attachments found in database and we have folder+uid, fetch from IMAP
+            if let (true, Some(folder), Some(uid)) = (attachments.is_empty(), folder_opt, uid_opt) {
+                debug!("No attachments in database for message_id {}. Fetching from IMAP...", message_id);
+
+                match email_service.fetch_email_with_attachments(&folder, uid, &account_id).await {
+                    Ok((_, attachment_infos)) => {
+                        serde_json::json!({
+                            "success": true,
+                            "data": {
+                                "message_id": message_id,
+                                "account_id": account_id,
+                                "attachments": attachment_infos,
+                                "count": attachment_infos.len(),
+                                "fetched_from_imap": true,
+                            },
+                            "tool": tool_name
+                        })
+                    }
+                    Err(e) => {
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to fetch attachments from IMAP: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+            } else {
+                serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "message_id": message_id,
+                        "account_id": account_id,
+                        "attachments": attachments,
+                        "count": attachments.len(),
+                    },
+                    "tool": tool_name
+                })
+            }
+        }
+        "download_email_attachments" => {
+            use crate::dashboard::services::attachment_storage;
+
+            // Get account ID
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            // Get database pool
+            let db_pool = match state.cache_service.db_pool.as_ref() {
+                Some(pool) => pool,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
+            };
+
+            // Determine message_id - either directly provided or resolve from folder+uid
+            // Also track folder and uid for potential IMAP fetch
+            let (message_id, folder_opt, uid_opt) = if let Some(msg_id) = params.get("message_id").and_then(|v| v.as_str()) {
+                // message_id provided directly - no folder/uid available
+                (msg_id.to_string(), None, None)
+            } else {
+                // Resolve from folder + uid
+                let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                    Some(f) => f,
+                    None => return serde_json::json!({
+                        "success": false,
+                        "error": "folder parameter required when message_id not provided",
+                        "tool": tool_name
+                    })
+                };
+
+                let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                    Some(u) => u as u32,
+                    None => return serde_json::json!({
+                        "success": false,
+                        "error": "uid parameter required when message_id not provided",
+                        "tool": tool_name
+                    })
+                };
+
+                // Fetch email to get message_id
+                let msg_id = match email_service.fetch_emails_for_account(folder, &[uid], &account_id).await {
+                    Ok(mut emails) if !emails.is_empty() => {
+                        let email = emails.remove(0);
+                        attachment_storage::ensure_message_id(&email, &account_id)
+                    }
+                    Ok(_) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Email with UID {} not found", uid),
+                        "tool": tool_name
+                    }),
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to fetch email: {}", e),
+                        "tool": tool_name
+                    })
+                };
+
+                (msg_id, Some(folder.to_string()), Some(uid))
+            };
+
+            // Check if attachments exist in database, fetch from IMAP if not
+            let mut attachments = match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
+                Ok(atts) => atts,
+                Err(e) => {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to get attachments: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            };
+
+            // If no attachments found in database and we have folder+uid, fetch from IMAP
+            if let (true, Some(folder), Some(uid)) = (attachments.is_empty(), folder_opt.as_ref(), uid_opt) {
+                debug!("No attachments in database for message_id {}. Fetching from IMAP...", message_id);
+
+                // Fetch email with attachments from IMAP (this will save them to DB)
+                match email_service.fetch_email_with_attachments(folder, uid, &account_id).await {
+                    Ok((_, attachment_infos)) => {
+                        // Attachments now saved to database
+                        debug!("Successfully fetched and saved {} attachments from IMAP", attachment_infos.len());
+
+                        // Re-query database to get the saved attachments
+                        attachments = match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
+                            Ok(atts) => atts,
+                            Err(e) => {
+                                return serde_json::json!({
+                                    "success": false,
+                                    "error": format!("Failed to get attachments after IMAP fetch: {}", e),
+                                    "tool": tool_name
+                                })
+                            }
+                        };
+                    }
+                    Err(e) => {
+                        return serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to fetch attachments from IMAP: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+            }
+
+            // Check if user wants ZIP archive
+            let create_zip = params.get("create_zip")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true); // Default to ZIP for convenience
+
+            if create_zip {
+                // Create ZIP archive
+                let temp_dir = std::env::temp_dir();
+                let sanitized_id = attachment_storage::sanitize_message_id(&message_id);
+                let zip_path = temp_dir.join(format!("rustymail_attachments_{}.zip", sanitized_id));
+
+                // Large archives are built in the background with progress on the job record
+                let zip_config = attachment_storage::ZipConfig::from_env();
+                let total_size = attachment_storage::attachments_total_size(db_pool, &account_id, &message_id).await.unwrap_or(0);
+                if total_size > zip_config.max_total_bytes {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!("Attachments total {} bytes, which exceeds the ZIP limit of {} bytes", total_size, zip_config.max_total_bytes),
+                        "tool": tool_name
+                    });
+                }
+                if total_size > zip_config.job_threshold_bytes {
+                    let job_id = start_zip_job(state, db_pool.clone(), account_id.clone(), message_id.clone(), zip_path.clone(), zip_config).await;
+                    return serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "message": "ZIP archive is being created in the background; poll get_job_status for progress",
+                            "download_url": format!("/api/attachments/jobs/{}/zip", job_id),
+                            "job_id": job_id,
+                            "zip_path": zip_path.to_string_lossy(),
+                            "total_bytes": total_size,
+                            "message_id": message_id,
+                            "account_id": account_id,
+                        },
+                        "tool": tool_name
+                    });
+                }
+
+                match attachment_storage::create_zip_archive_with_progress(db_pool, &account_id, &message_id, &zip_path, &zip_config, None).await {
+                    Ok(result_path) => {
+                        serde_json::json!({
+                            "success": true,
+                            "data": {
+                                "message": "ZIP archive created",
+                                "zip_path": result_path.to_string_lossy(),
+                                "message_id": message_id,
+                                "account_id": account_id,
+                            },
+                            "tool": tool_name
+                        })
+                    }
+                    Err(e) => {
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to create ZIP: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+            } else {
+                // Just list attachment paths without creating ZIP
+                match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
+                    Ok(attachments) => {
+                        let paths: Vec<String> = attachments.iter()
+                            .map(|a| a.storage_path.clone())
+                            .collect();
+
+                        serde_json::json!({
+                            "success": true,
+                            "data": {
+                                "message": "Attachment paths retrieved",
+                                "paths": paths,
+                                "attachments": attachments,
+                                "message_id": message_id,
+                                "account_id": account_id,
+                            },
+                            "tool": tool_name
+                        })
+                    }
+                    Err(e) => {
+                        serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to get attachments: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+            }
+        }
+        "cleanup_attachments" => {
+            use crate::dashboard::services::attachment_storage;
+
+            // Get required parameters
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let message_id = match params.get("message_id").and_then(|v| v.as_str()) {
+                Some(id) => id,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "message_id parameter is required",
+                    "tool": tool_name
+                })
+            };
+
+            // Get database pool
+            let db_pool = match state.cache_service.db_pool.as_ref() {
+                Some(pool) => pool,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
+            };
+
+            // Delete attachments
+            match attachment_storage::delete_attachments_for_email(db_pool, message_id, &account_id).await {
+                Ok(_) => {
+                    serde_json::json!({
+                        "success": true,
+                        "message": format!("Attachments deleted for message {}", message_id),
+                        "message_id": message_id,
+                        "account_id": account_id,
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => {
+                    serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to delete attachments: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+        }
+        "get_attachment_content" => {
+            use crate::dashboard::services::attachment_storage;
+            use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let filename = match params.get("filename").and_then(|v| v.as_str()) {
+                Some(f) => f.to_string(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "filename parameter is required",
+                    "tool": tool_name
+                })
+            };
+
+            let db_pool = match state.cache_service.db_pool.as_ref() {
+                Some(pool) => pool,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
+            };
+
+            // Resolve message_id from params or folder+uid
+            let message_id = if let Some(mid) = params.get("message_id").and_then(|v| v.as_str()) {
+                mid.to_string()
+            } else {
+                let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                    Some(f) => f,
+                    None => return serde_json::json!({
+                        "success": false,
+                        "error": "folder parameter required when message_id not provided",
+                        "tool": tool_name
+                    })
+                };
+                let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                    Some(u) => u as u32,
+                    None => return serde_json::json!({
+                        "success": false,
+                        "error": "uid parameter required when message_id not provided",
+                        "tool": tool_name
+                    })
+                };
+                match state.cache_service.get_email_by_uid_for_account(folder, uid, &account_id).await {
+                    Ok(Some(email)) => email.message_id.unwrap_or_default(),
+                    Ok(None) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Email UID {} not found in {}", uid, folder),
+                        "tool": tool_name
+                    }),
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to look up email: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            };
+
+            // Try reading from disk first; if metadata-only, fetch from IMAP
+            match attachment_storage::read_attachment_content(db_pool, &account_id, &message_id, &filename).await {
+                Ok((_name, content_type, content)) => {
+                    serde_json::json!({
+                        "success": true,
+                        "filename": filename,
+                        "content_type": content_type,
+                        "size_bytes": content.len(),
+                        "content_base64": BASE64.encode(&content),
+                        "message_id": message_id,
+                        "tool": tool_name
+                    })
+                }
+                Err(attachment_storage::AttachmentError::NotFound(msg)) if msg.contains("not yet downloaded") => {
+                    // Metadata exists but file not on disk - need IMAP fetch
+                    let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX");
+                    let uid = params.get("uid").and_then(|v| v.as_u64()).map(|u| u as u32);
+
+                    if let Some(uid) = uid {
+                        match email_service.fetch_email_with_attachments(folder, uid, &account_id).await {
+                            Ok(_) => {
+                                // Retry reading after IMAP download
+                                match attachment_storage::read_attachment_content(db_pool, &account_id, &message_id, &filename).await {
+                                    Ok((_name, content_type, content)) => {
+                                        serde_json::json!({
+                                            "success": true,
+                                            "filename": filename,
+                                            "content_type": content_type,
+                                            "size_bytes": content.len(),
+                                            "content_base64": BASE64.encode(&content),
+                                            "message_id": message_id,
+                                            "tool": tool_name
+                                        })
+                                    }
+                                    Err(e) => serde_json::json!({
+                                        "success": false,
+                                        "error": format!("Failed to read after IMAP fetch: {}", e),
+                                        "tool": tool_name
+                                    })
+                                }
+                            }
+                            Err(e) => serde_json::json!({
+                                "success": false,
+                                "error": format!("Failed to fetch from IMAP: {}", e),
+                                "tool": tool_name
+                            })
+                        }
+                    } else {
+                        serde_json::json!({
+                            "success": false,
+                            "error": "Attachment not yet downloaded. Provide folder+uid to trigger IMAP fetch.",
+                            "tool": tool_name
+                        })
+                    }
+                }
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to read attachment: {}", e),
+                    "tool": tool_name
+                })
+            }
+        }
+        // === Job Management Tools ===
+        "list_jobs" => {
+            use crate::dashboard::services::jobs::JobStatus;
+            let status_filter = params.get("status_filter").and_then(|v| v.as_str());
+
+            let jobs: Vec<_> = state.jobs.iter()
+                .filter(|entry| {
+                    match status_filter {
+                        Some("running") => matches!(entry.value().status, JobStatus::Running),
+                        Some("completed") => matches!(entry.value().status, JobStatus::Completed(_)),
+                        Some("failed") => matches!(entry.value().status, JobStatus::Failed(_)),
+                        _ => true, // No filter, return all
+                    }
+                })
+                .map(|entry| {
+                    let job = entry.value();
+                    serde_json::json!({
+                        "job_id": job.job_id,
+                        "instruction": job.instruction,
+                        "status": &job.status,
+                        "elapsed_seconds": job.started_at.elapsed().as_secs()
+                    })
+                })
+                .collect();
+
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "jobs": jobs,
+                    "total": jobs.len()
+                },
+                "tool": tool_name
+            })
+        }
+        "get_job_status" => {
+            let job_id = match params.get("job_id").and_then(|v| v.as_str()) {
+                Some(id) => id,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing required parameter: job_id",
+                    "tool": tool_name
+                }),
+            };
+
+            // Latest checkpoint, e.g. ZIP progress, from the persisted record
+            let progress = match state.job_persistence {
+                Some(ref job_persistence) => job_persistence.get_job(job_id).await.ok().flatten()
+                    .and_then(|job| job.resume_checkpoint)
+                    .and_then(|checkpoint| serde_json::from_str::<serde_json::Value>(&checkpoint).ok()),
+                None => None,
+            };
+
+            match state.jobs.get(job_id) {
+                Some(job) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "job_id": job.job_id,
+                        "instruction": job.instruction,
+                        "status": &job.status,
+                        "progress": progress,
+                        "elapsed_seconds": job.started_at.elapsed().as_secs()
+                    },
+                    "tool": tool_name
+                }),
+                None => serde_json::json!({
+                    "success": false,
+                    "error": format!("Job not found: {}", job_id),
+                    "tool": tool_name
+                }),
+            }
+        }
+        "cancel_job" => {
+            use crate::dashboard::services::jobs::JobStatus;
+            let job_id = match params.get("job_id").and_then(|v| v.as_str()) {
+                Some(id) => id,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing required parameter: job_id",
+                    "tool": tool_name
+                }),
+            };
+
+            // Get current status before removal
+            let job_info = state.jobs.get(job_id).map(|job| {
+                let was_running = matches!(job.status, JobStatus::Running);
+                serde_json::json!({
+                    "job_id": job.job_id,
+                    "status": &job.status,
+                    "was_running": was_running,
+                    "elapsed_seconds": job.started_at.elapsed().as_secs()
+                })
+            });
+
+            match job_info {
+                Some(info) => {
+                    // Remove the job from the map
+                    state.jobs.remove(job_id);
+                    serde_json::json!({
+                        "success": true,
+                        "message": "Job cancelled",
+                        "data": info,
+                        "tool": tool_name
+                    })
+                }
+                None => serde_json::json!({
+                    "success": false,
+                    "error": format!("Job not found: {}", job_id),
+                    "tool": tool_name
+                }),
+            }
+        }
+        "get_email_synopsis" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX");
+            let uid = match params.get("uid").and_then(|v| v.as_u64()).map(|u| u as u32) {
+                Some(u) => u,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "uid parameter is required",
+                    "tool": tool_name
+                })
+            };
+            let max_lines = params.get("max_lines").and_then(|v| v.as_u64()).unwrap_or(3) as usize;
+
+            match state.cache_service.get_email_by_uid_for_account(folder, uid, &account_id).await {
+                Ok(Some(email)) => {
+                    let subject = email.subject.as_deref().unwrap_or("(no subject)");
+                    let synopsis = match &email.body_text {
+                        Some(body) => {
+                            let sentences: Vec<&str> = body
+                                .split(|c: char| c == '.' || c == '!' || c == '?')
+                                .map(|s| s.trim())
+                                .filter(|s| !s.is_empty() && s.len() > 5)
+                                .take(max_lines)
+                                .collect();
+                            if sentences.is_empty() {
+                                body.lines()
+                                    .map(|l| l.trim())
+                                    .filter(|l| !l.is_empty())
+                                    .take(max_lines)
+                                    .collect::<Vec<_>>()
+                                    .join("\n")
+                            } else {
+                                sentences.join(". ") + "."
+                            }
+                        }
+                        None => "(no body text available)".to_string(),
+                    };
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "uid": uid,
+                            "folder": folder,
+                            "subject": subject,
+                            "synopsis": synopsis,
+                            "from": email.from_address,
+                            "date": email.date,
+                            "has_attachments": email.has_attachments,
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Ok(None) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Email UID {} not found in {}", uid, folder),
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to fetch email: {}", e),
+                    "tool": tool_name
+                })
+            }
+        }
+        "get_email_thread" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let message_id = match params.get("message_id").and_then(|v| v.as_str()) {
+                Some(mid) => mid.to_string(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "message_id parameter is required",
+                    "tool": tool_name
+                })
+            };
+
+            match state.cache_service.get_thread_emails(&message_id, &account_id).await {
+                Ok(emails) => {
+                    let thread: Vec<serde_json::Value> = emails.iter().map(|e| {
+                        serde_json::json!({
+                            "uid": e.uid,
+                            "message_id": e.message_id,
+                            "subject": e.subject,
+                            "from_address": e.from_address,
+                            "from_name": e.from_name,
+                            "date": e.date,
+                            "in_reply_to": e.in_reply_to,
+                            "has_attachments": e.has_attachments,
+                            "flags": e.flags,
+                        })
+                    }).collect();
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "thread_count": thread.len(),
+                            "thread": thread,
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to fetch thread: {}", e),
+                    "tool": tool_name
+                })
+            }
+        }
+        "search_by_domain" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let domain = match params.get("domain").and_then(|v| v.as_str()) {
+                Some(d) => d.to_string(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "domain parameter is required",
+                    "tool": tool_name
+                })
+            };
+
+            let search_in: Vec<&str> = params.get("search_in")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|s| s.as_str()).collect())
+                .unwrap_or_else(|| vec!["from"]);
+            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
 
-            match email_service.list_folders_for_account(&account_id).await {
-                Ok(folders) => {
+            match state.cache_service.search_by_domain(&domain, &search_in, &account_id, limit).await {
+                Ok(emails) => {
+                    let results: Vec<serde_json::Value> = emails.iter().map(|e| {
+                        serde_json::json!({
+                            "uid": e.uid,
+                            "subject": e.subject,
+                            "from_address": e.from_address,
+                            "from_name": e.from_name,
+                            "date": e.date,
+                            "flags": e.flags,
+                            "has_attachments": e.has_attachments,
+                        })
+                    }).collect();
                     serde_json::json!({
                         "success": true,
-                        "data": folders,
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to list folders: {}", e),
+                        "data": {
+                            "domain": domain,
+                            "count": results.len(),
+                            "emails": results,
+                        },
                         "tool": tool_name
                     })
                 }
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Domain search failed: {}", e),
+                    "tool": tool_name
+                })
             }
         }
-        "list_folders_hierarchical" => {
-            // Get account ID from request or use default
+        "get_address_report" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -1504,36 +5256,22 @@ pub async fn execute_mcp_tool_inner(
                 })
             };
 
-            // For now, just use regular list_folders since hierarchical is not implemented
-            match email_service.list_folders_for_account(&account_id).await {
-                Ok(folders) => {
+            match state.cache_service.get_address_report(&account_id).await {
+                Ok(report) => {
                     serde_json::json!({
                         "success": true,
-                        "data": folders,
-                        "tool": tool_name,
-                        "note": "Using flat list - hierarchical not yet implemented"
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to list folders: {}", e),
+                        "data": report,
                         "tool": tool_name
                     })
                 }
-            }
-        }
-        "create_folder" => {
-            let folder_name = match params.get("folder_name").and_then(|v| v.as_str()) {
-                Some(f) => f,
-                None => return serde_json::json!({
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "Missing 'folder_name' parameter",
+                    "error": format!("Failed to generate report: {}", e),
                     "tool": tool_name
                 })
-            };
-
-            // Get account ID from request or use default
+            }
+        }
+        "list_emails_by_flag" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -1543,37 +5281,104 @@ pub async fn execute_mcp_tool_inner(
                 })
             };
 
-            match email_service.create_folder_for_account(folder_name, &account_id).await {
-                Ok(_) => {
-                    serde_json::json!({
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX");
+            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+            let offset = params.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+
+            let flags_include: Vec<String> = params.get("flags_include")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                .unwrap_or_default();
+            let flags_exclude: Vec<String> = params.get("flags_exclude")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                .unwrap_or_default();
+
+            // Shorthand: unread_only adds "Seen" to exclude list
+            if params.get("unread_only").and_then(|v| v.as_bool()).unwrap_or(false) {
+                let mut exclude = flags_exclude;
+                if !exclude.contains(&"Seen".to_string()) {
+                    exclude.push("Seen".to_string());
+                }
+                match state.cache_service.get_cached_emails_by_flags(
+                    folder, &account_id, &flags_include, &exclude, limit, offset,
+                ).await {
+                    Ok(emails) => serde_json::json!({
                         "success": true,
-                        "data": {
-                            "folder_name": folder_name,
-                            "account_id": account_id
-                        },
+                        "data": emails,
+                        "count": emails.len(),
+                        "folder": folder,
+                        "tool": tool_name
+                    }),
+                    Err(e) => serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to filter emails: {}", e),
                         "tool": tool_name
                     })
                 }
-                Err(e) => {
-                    serde_json::json!({
+            } else {
+                match state.cache_service.get_cached_emails_by_flags(
+                    folder, &account_id, &flags_include, &flags_exclude, limit, offset,
+                ).await {
+                    Ok(emails) => serde_json::json!({
+                        "success": true,
+                        "data": emails,
+                        "count": emails.len(),
+                        "folder": folder,
+                        "tool": tool_name
+                    }),
+                    Err(e) => serde_json::json!({
                         "success": false,
-                        "error": format!("Failed to create folder: {}", e),
+                        "error": format!("Failed to filter emails: {}", e),
                         "tool": tool_name
                     })
                 }
             }
         }
-        "delete_folder" => {
-            let folder_name = match params.get("folder_name").and_then(|v| v.as_str()) {
-                Some(f) => f,
-                None => return serde_json::json!({
+        "search_by_attachment_type" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'folder_name' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
 
-            // Get account ID from request or use default
+            let mime_types: Vec<String> = params.get("mime_types")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                .unwrap_or_default();
+
+            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+
+            let pool = state.cache_service.db_pool.as_ref();
+            match pool {
+                Some(pool) => {
+                    match crate::dashboard::services::attachment_storage::search_by_attachment_type(
+                        pool, &account_id, &mime_types, limit
+                    ).await {
+                        Ok(results) => serde_json::json!({
+                            "success": true,
+                            "data": results,
+                            "count": results.len(),
+                            "tool": tool_name
+                        }),
+                        Err(e) => serde_json::json!({
+                            "success": false,
+                            "error": format!("Search failed: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+                None => serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
+            }
+        }
+        "sync_emails" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -1583,45 +5388,97 @@ pub async fn execute_mcp_tool_inner(
                 })
             };
 
-            match email_service.delete_folder_for_account(folder_name, &account_id).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "folder_name": folder_name,
-                            "account_id": account_id
-                        },
-                        "tool": tool_name
-                    })
+            let folder = params.get("folder").and_then(|v| v.as_str()).map(|s| s.to_string());
+            let sync_service = state.sync_service.clone();
+
+            match folder {
+                Some(ref f) => {
+                    info!("MCP sync_emails: syncing folder '{}' for account '{}'", f, account_id);
+                    match sync_service.sync_folder(&account_id, f).await {
+                        Ok(()) => serde_json::json!({
+                            "success": true,
+                            "data": {
+                                "message": format!("Synced folder '{}' for account '{}'", f, account_id),
+                            },
+                            "tool": tool_name
+                        }),
+                        Err(e) => serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to sync folder '{}': {}", f, e),
+                            "tool": tool_name
+                        })
+                    }
                 }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to delete folder: {}", e),
-                        "tool": tool_name
-                    })
+                None => {
+                    info!("MCP sync_emails: syncing all folders for account '{}'", account_id);
+                    match sync_service.sync_all_folders(&account_id).await {
+                        Ok(()) => serde_json::json!({
+                            "success": true,
+                            "data": {
+                                "message": format!("Synced all folders for account '{}'", account_id),
+                            },
+                            "tool": tool_name
+                        }),
+                        Err(e) => serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to sync all folders: {}", e),
+                            "tool": tool_name
+                        })
+                    }
                 }
             }
         }
-        "rename_folder" => {
-            let old_name = match params.get("old_name").and_then(|v| v.as_str()) {
-                Some(f) => f,
-                None => return serde_json::json!({
+        "export_folder_metadata" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'old_name' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-            let new_name = match params.get("new_name").and_then(|v| v.as_str()) {
-                Some(f) => f,
+
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f.to_string(),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'new_name' parameter",
+                    "error": "folder parameter is required",
+                    "tool": tool_name
+                })
+            };
+
+            let format = params.get("format").and_then(|v| v.as_str());
+            let fields = params.get("fields").and_then(|v| v.as_str());
+            let limit = params.get("limit").and_then(|v| v.as_u64()).map(|v| v as usize);
+
+            match state.cache_service.db_pool.as_ref() {
+                Some(pool) => {
+                    let exporter = crate::metadata_export::MetadataExporter::new(pool.clone());
+                    match exporter.export(&account_id, &folder, format, fields, limit).await {
+                        Ok(result) => serde_json::json!({
+                            "success": true,
+                            "data": {
+                                "file_path": result.file_path,
+                                "email_count": result.email_count,
+                                "format": result.format,
+                            },
+                            "tool": tool_name
+                        }),
+                        Err(e) => serde_json::json!({
+                            "success": false,
+                            "error": format!("Metadata export failed: {}", e),
+                            "tool": tool_name
+                        })
+                    }
+                }
+                None => serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
                     "tool": tool_name
                 })
-            };
-
-            // Get account ID from request or use default
+            }
+        }
+        "filter_emails_by_subject" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -1631,508 +5488,360 @@ pub async fn execute_mcp_tool_inner(
                 })
             };
 
-            match email_service.rename_folder_for_account(old_name, new_name, &account_id).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "old_name": old_name,
-                            "new_name": new_name,
-                            "account_id": account_id
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to rename folder: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        "fetch_emails_with_mime" => {
             let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
-                None => return serde_json::json!({
-                    "success": false,
-                    "error": "Missing 'folder' parameter",
-                    "tool": tool_name
-                })
-            };
-            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
-                Some(u) => u as u32,
+                Some(f) => f.to_string(),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'uid' parameter",
+                    "error": "folder parameter is required",
                     "tool": tool_name
                 })
             };
 
-            // Get account ID from request or use default
-            let account_id = match get_account_id_to_use(&params, &state_data).await {
-                Ok(id) => id,
-                Err(e) => return serde_json::json!({
+            let patterns: Vec<String> = match params.get("subject_patterns").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect(),
+                None => return serde_json::json!({
                     "success": false,
-                    "error": format!("Failed to determine account: {}", e),
+                    "error": "subject_patterns parameter is required (array of strings)",
                     "tool": tool_name
                 })
             };
 
-            // fetch_emails expects an array of UIDs
-            match email_service.fetch_emails_for_account(folder, &[uid], &account_id).await {
-                Ok(emails) => {
-                    // Return just the first email if found
-                    let email_data = emails.into_iter().next();
-                    serde_json::json!({
-                        "success": true,
-                        "data": email_data,
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to fetch email: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        // Cache-based tools
-        "list_cached_emails" => {
-            let folder = params.get("folder")
-                .and_then(|v| v.as_str())
-                .unwrap_or("INBOX");
-            let limit = params.get("limit")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as usize)
-                .unwrap_or(20);
-            let offset = params.get("offset")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as usize)
-                .unwrap_or(0);
-            let preview_mode = params.get("preview_mode")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(true);  // Default to preview mode for token efficiency
-
-            // Get account ID from request or use default
-            match get_account_id_to_use(&params, &state_data).await {
-                Ok(account_id) => {
-                    let account_email = match validate_account_exists(&account_id, &state).await {
-                        Ok(id) => id,
-                        Err(e) => {
-                            return serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to lookup account: {}", e)
-                            });
-                        }
-                    };
-                    match state.cache_service.get_cached_emails_for_account(folder, &account_email, limit, offset, preview_mode).await {
-                        Ok(emails) => {
-                            serde_json::json!({
-                                "success": true,
-                                "data": emails,
-                                "folder": folder,
-                                "count": emails.len(),
-                                "tool": tool_name
-                            })
-                        }
-                        Err(e) => {
-                            serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to get cached emails: {}", e),
-                                "tool": tool_name
-                            })
-                        }
-                    }
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to determine account: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        "get_email_by_uid" => {
-            let folder = params.get("folder")
-                .and_then(|v| v.as_str())
-                .unwrap_or("INBOX");
-            let uid = params.get("uid")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as u32);
-
-            // Get account ID from request or use default
-            match get_account_id_to_use(&params, &state_data).await {
-                Ok(account_id) => {
-                    let account_email = match validate_account_exists(&account_id, &state).await {
-                        Ok(id) => id,
-                        Err(e) => {
-                            return serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to lookup account: {}", e)
-                            });
-                        }
-                    };
-                    if let Some(uid) = uid {
-                        match state.cache_service.get_email_by_uid_for_account(folder, uid, &account_email).await {
-                            Ok(Some(email)) => {
-                                // Serialize email and parse attachment_parts from
-                                // JSON string into a proper nested array
-                                let mut data = serde_json::to_value(&email)
-                                    .unwrap_or_else(|_| serde_json::json!({}));
-                                if let Some(parts_str) = data.get("attachment_parts")
-                                    .and_then(|v| v.as_str())
-                                {
-                                    if let Ok(parts) = serde_json::from_str::<serde_json::Value>(parts_str) {
-                                        data["attachment_parts"] = parts;
-                                    }
-                                }
-                                // Fallback: if attachment_parts is still null but has_attachments
-                                // is true, query attachment_metadata table directly.
-                                let parts_is_null = data.get("attachment_parts")
-                                    .map(|v| v.is_null()).unwrap_or(true);
-                                if parts_is_null && email.has_attachments {
-                                    if let (Some(pool), Some(ref msg_id)) = (state.cache_service.db_pool.as_ref(), &email.message_id) {
-                                        if let Ok(metas) = crate::dashboard::services::attachment_storage::get_attachments_metadata(pool, &account_email, msg_id).await {
-                                            if !metas.is_empty() {
-                                                let parts: Vec<serde_json::Value> = metas.iter().map(|m| {
-                                                    serde_json::json!({
-                                                        "filename": m.filename,
-                                                        "content_type": m.content_type,
-                                                        "size": m.size_bytes
-                                                    })
-                                                }).collect();
-                                                data["attachment_parts"] = serde_json::json!(parts);
-                                            }
-                                        }
-                                    }
-                                }
-                                serde_json::json!({
-                                    "success": true,
-                                    "data": data,
-                                    "tool": tool_name
-                                })
-                            }
-                            Ok(None) => {
-                                serde_json::json!({
-                                    "success": false,
-                                    "error": format!("Email with UID {} not found in {}", uid, folder),
-                                    "tool": tool_name
-                                })
-                            }
-                            Err(e) => {
-                                serde_json::json!({
-                                    "success": false,
-                                    "error": format!("Failed to get email by UID: {}", e),
-                                    "tool": tool_name
-                                })
-                            }
-                        }
-                    } else {
-                        serde_json::json!({
-                            "success": false,
-                            "error": "UID parameter is required",
-                            "tool": tool_name
-                        })
-                    }
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to determine account: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        "get_email_by_index" => {
-            let folder = params.get("folder")
-                .and_then(|v| v.as_str())
-                .unwrap_or("INBOX");
-            let index = params.get("index")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as usize);
+            let match_mode = params.get("match_mode").and_then(|v| v.as_str());
+            let sender_filter = params.get("sender_filter").and_then(|v| v.as_str());
+            let recipient_filter = params.get("recipient_filter").and_then(|v| v.as_str());
+            let date_after = params.get("date_after").and_then(|v| v.as_str());
+            let date_before = params.get("date_before").and_then(|v| v.as_str());
+            let max_results = params.get("max_results").and_then(|v| v.as_u64()).map(|v| v as usize);
 
-            // Get account ID from request or use default
-            match get_account_id_to_use(&params, &state_data).await {
-                Ok(account_id) => {
-                    let account_email = match validate_account_exists(&account_id, &state).await {
-                        Ok(id) => id,
-                        Err(e) => {
-                            return serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to lookup account: {}", e)
-                            });
-                        }
-                    };
-                    if let Some(index) = index {
-                        // Get emails sorted by date DESC, then select by index
-                        // Dashboard UI needs full content for display
-                        match state.cache_service.get_cached_emails_for_account(folder, &account_email, index + 1, index, false).await {
-                    Ok(emails) if !emails.is_empty() => {
-                        serde_json::json!({
+            match state.cache_service.db_pool.as_ref() {
+                Some(pool) => {
+                    let filter = crate::filter_emails::SubjectFilter::new(pool.clone());
+                    match filter.filter(
+                        &account_id, &folder, &patterns, match_mode,
+                        sender_filter, recipient_filter, date_after, date_before, max_results,
+                    ).await {
+                        Ok(result) => serde_json::json!({
                             "success": true,
-                            "data": emails[0],
-                            "tool": tool_name
-                        })
-                    }
-                    Ok(_) => {
-                        serde_json::json!({
-                            "success": false,
-                            "error": format!("No email at index {} in {}", index, folder),
-                            "tool": tool_name
-                        })
-                    }
-                    Err(e) => {
-                        serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to get email by index: {}", e),
+                            "data": {
+                                "account": result.account,
+                                "folder": result.folder,
+                                "patterns_used": result.patterns_used,
+                                "match_mode": result.match_mode,
+                                "total_matched": result.total_matched,
+                                "results": result.results,
+                            },
                             "tool": tool_name
-                        })
-                    }
-                        }
-                    } else {
-                        serde_json::json!({
+                        }),
+                        Err(e) => serde_json::json!({
                             "success": false,
-                            "error": "index parameter is required",
+                            "error": format!("Subject filter failed: {}", e),
                             "tool": tool_name
                         })
                     }
                 }
-                Err(e) => {
-                serde_json::json!({
+                None => serde_json::json!({
                     "success": false,
-                    "error": format!("Failed to determine account: {}", e),
+                    "error": "Database not available",
                     "tool": tool_name
                 })
-                }
             }
         }
-        "count_emails_in_folder" => {
-            let folder = params.get("folder")
-                .and_then(|v| v.as_str())
-                .unwrap_or("INBOX");
+        "batch_get_synopsis" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
 
-            // Get account ID from request or use default
-            match get_account_id_to_use(&params, &state_data).await {
-                Ok(account_id) => {
-                    let account_email = match validate_account_exists(&account_id, &state).await {
-                        Ok(id) => id,
-                        Err(e) => {
-                            return serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to lookup account: {}", e)
-                            });
-                        }
-                    };
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f.to_string(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "folder parameter is required",
+                    "tool": tool_name
+                })
+            };
 
-            match state.cache_service.count_emails_in_folder_for_account(folder, &account_email).await {
-                Ok(count) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "count": count,
-                            "folder": folder
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to count emails: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to determine account: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        "get_folder_stats" => {
-            let folder = params.get("folder")
-                .and_then(|v| v.as_str())
-                .unwrap_or("INBOX");
+            let uids: Vec<i64> = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter()
+                    .filter_map(|v| v.as_i64())
+                    .collect(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "uids parameter is required (array of integers)",
+                    "tool": tool_name
+                })
+            };
 
-            // Get account ID from request or use default
-            match get_account_id_to_use(&params, &state_data).await {
-                Ok(account_id) => {
-                    let account_email = match validate_account_exists(&account_id, &state).await {
-                        Ok(id) => id,
-                        Err(e) => {
-                            return serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to lookup account: {}", e)
-                            });
-                        }
-                    };
+            let max_chars = params.get("max_chars_per_synopsis")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize);
 
-            match state.cache_service.get_folder_stats_for_account(folder, &account_email).await {
-                Ok(stats) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": stats,
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to get folder stats: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to determine account: {}", e),
-                        "tool": tool_name
-                    })
+            match state.cache_service.db_pool.as_ref() {
+                Some(pool) => {
+                    let processor = crate::batch_synopsis::BatchSynopsisProcessor::new(pool.clone());
+                    match processor.process(&account_id, &folder, &uids, max_chars).await {
+                        Ok(result) => serde_json::json!({
+                            "success": true,
+                            "data": {
+                                "account": result.account,
+                                "folder": result.folder,
+                                "requested": result.requested,
+                                "returned": result.returned,
+                                "synopses": result.synopses,
+                                "errors": result.errors,
+                            },
+                            "tool": tool_name
+                        }),
+                        Err(e) => serde_json::json!({
+                            "success": false,
+                            "error": format!("Batch synopsis failed: {}", e),
+                            "tool": tool_name
+                        })
+                    }
                 }
+                None => serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
             }
         }
-        "search_cached_emails" => {
-            let folder = params.get("folder")
-                .and_then(|v| v.as_str())
-                .unwrap_or("INBOX");
-            let query = params.get("query")
-                .and_then(|v| v.as_str());
-            let limit = params.get("limit")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as usize)
-                .unwrap_or(20);
+        "export_evidence" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
 
-            // Get account ID from request or use default
-            match get_account_id_to_use(&params, &state_data).await {
-                Ok(account_id) => {
-                    let account_email = match validate_account_exists(&account_id, &state).await {
-                        Ok(id) => id,
-                        Err(e) => {
-                            return serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to lookup account: {}", e)
-                            });
-                        }
-                    };
+            let folder = params.get("folder").and_then(|v| v.as_str());
+            let search_query = params.get("search_query").and_then(|v| v.as_str());
+            let output_path = params.get("output_path").and_then(|v| v.as_str());
 
-            if let Some(query) = query {
-                match state.cache_service.search_cached_emails_for_account(folder, query, limit, &account_email).await {
-                    Ok(emails) => {
-                        serde_json::json!({
+            match state.cache_service.db_pool.as_ref() {
+                Some(pool) => {
+                    let exporter = crate::evidence_export::EvidenceExporter::new(
+                        state.cache_service.clone(), pool.clone()
+                    );
+                    match exporter.export(&account_id, folder, search_query, output_path).await {
+                        Ok(result) => serde_json::json!({
                             "success": true,
-                            "data": emails,
-                            "query": query,
-                            "folder": folder,
-                            "count": emails.len(),
+                            "data": {
+                                "export_path": result.export_path,
+                                "email_count": result.email_count,
+                                "attachment_count": result.attachment_count,
+                            },
                             "tool": tool_name
-                        })
-                    }
-                    Err(e) => {
-                        serde_json::json!({
+                        }),
+                        Err(e) => serde_json::json!({
                             "success": false,
-                            "error": format!("Failed to search emails: {}", e),
+                            "error": format!("Export failed: {}", e),
                             "tool": tool_name
                         })
                     }
                 }
-            } else {
-                serde_json::json!({
+                None => serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
+            }
+        }
+        "transfer_messages" => {
+            let mut required = Vec::new();
+            for key in ["source_account_id", "source_folder", "dest_account_id", "dest_folder"] {
+                match params.get(key).and_then(|v| v.as_str()) {
+                    Some(value) => required.push(value.to_string()),
+                    None => return serde_json::json!({
+                        "success": false,
+                        "error": format!("{} parameter is required", key),
+                        "tool": tool_name
+                    })
+                }
+            }
+            let (source_account_id, source_folder, dest_account_id, dest_folder) =
+                (&required[0], &required[1], &required[2], &required[3]);
+
+            let uids: Vec<u32> = params.get("uids")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect())
+                .unwrap_or_default();
+            if uids.is_empty() {
+                return serde_json::json!({
                     "success": false,
-                    "error": "query parameter is required",
+                    "error": "'uids' parameter is required and cannot be empty",
                     "tool": tool_name
-                })
+                });
             }
-                }
-                Err(e) => {
-                    serde_json::json!({
+
+            if source_account_id == dest_account_id && source_folder == dest_folder {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "Source and destination are the same folder",
+                    "tool": tool_name
+                });
+            }
+
+            for account_id in [source_account_id, dest_account_id] {
+                if let Err(e) = validate_account_exists(account_id, state).await {
+                    return serde_json::json!({
                         "success": false,
-                        "error": format!("Failed to determine account: {}", e),
+                        "error": format!("{}", e),
                         "tool": tool_name
-                    })
+                    });
                 }
             }
+
+            let delete_source = params.get("delete_source").and_then(|v| v.as_bool()).unwrap_or(false);
+
+            match email_service.transfer_messages(
+                source_account_id, source_folder, &uids,
+                dest_account_id, dest_folder, delete_source,
+            ).await {
+                Ok(result) => serde_json::json!({
+                    "success": result.failed.is_empty() && result.delete_error.is_none(),
+                    "data": result,
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to transfer messages: {}", e),
+                    "tool": tool_name
+                })
+            }
         }
-        "atomic_move_message" => {
-            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
-                Some(u) => u as u32,
-                None => return serde_json::json!({
+        "get_server_info" => {
+            let now_utc = chrono::Utc::now();
+            let now_local = now_utc.with_timezone(&chrono::Local);
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "server_time_utc": now_utc.to_rfc3339(),
+                    "server_time_local": now_local.to_rfc3339(),
+                    "server_utc_offset": now_local.format("%:z").to_string(),
+                    "date_handling": {
+                        "timezone": "UTC",
+                        "imap_date_format": "DD-Mon-YYYY",
+                        "accepted_input_formats": ["RFC 3339 (2024-01-15T09:00:00-05:00)", "YYYY-MM-DDTHH:MM:SS (UTC)", "YYYY-MM-DD (midnight UTC)"],
+                        "summary": crate::imap::dates::DATE_HANDLING_SUMMARY
+                    }
+                },
+                "tool": tool_name
+            })
+        }
+        "semantic_search" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'uid' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-            let from_folder = match params.get("from_folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
+
+            let query = match params.get("query").and_then(|v| v.as_str()).filter(|q| !q.trim().is_empty()) {
+                Some(q) => q,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'from_folder' parameter",
+                    "error": "query parameter is required",
                     "tool": tool_name
                 })
             };
-            let to_folder = match params.get("to_folder").and_then(|v| v.as_str()) {
-                Some(t) => t,
+            let folder = params.get("folder").and_then(|v| v.as_str());
+            let limit = params.get("limit").and_then(|v| v.as_u64()).map(|v| v as usize);
+
+            let pool = match state.cache_service.db_pool.as_ref() {
+                Some(pool) => pool.clone(),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'to_folder' parameter",
+                    "error": "Database not available",
                     "tool": tool_name
                 })
             };
 
-            match email_service.atomic_move_message(uid, from_folder, to_folder).await {
-                Ok(_) => {
-                    serde_json::json!({
+            match crate::semantic_search::SemanticIndex::from_env(pool) {
+                Some(index) => match index.search(&account_id, folder, query, limit).await {
+                    Ok(matches) => serde_json::json!({
                         "success": true,
-                        "data": {
-                            "uid": uid,
-                            "from_folder": from_folder,
-                            "to_folder": to_folder
-                        },
+                        "data": matches,
+                        "mode": "semantic",
+                        "query": query,
+                        "count": matches.len(),
                         "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
+                    }),
+                    Err(e) => serde_json::json!({
                         "success": false,
-                        "error": format!("Failed to move message: {}", e),
+                        "error": format!("Semantic search failed: {}", e),
                         "tool": tool_name
                     })
+                },
+                None => {
+                    // An empty folder name searches every cached folder
+                    match state.cache_service.search_cached_emails_for_account(folder.unwrap_or(""), query, limit.unwrap_or(10), &account_id).await {
+                        Ok(emails) => serde_json::json!({
+                            "success": true,
+                            "data": emails,
+                            "mode": "keyword",
+                            "note": "Semantic search is disabled (SEMANTIC_SEARCH_ENABLED); returned keyword matches instead",
+                            "query": query,
+                            "folder": folder,
+                            "count": emails.len(),
+                            "tool": tool_name
+                        }),
+                        Err(e) => serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to search emails: {}", e),
+                            "tool": tool_name
+                        })
+                    }
                 }
             }
         }
-        "atomic_batch_move" => {
-            let uids = match params.get("uids").and_then(|v| v.as_array()) {
-                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
-                None => return serde_json::json!({
+        "all_accounts_summary" => {
+            match build_all_accounts_summary(state).await {
+                Ok(summary) => serde_json::json!({
+                    "success": true,
+                    "data": summary,
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "Missing 'uids' parameter",
+                    "error": format!("Failed to build accounts summary: {}", e),
+                    "tool": tool_name
+                })
+            }
+        }
+        "flag_messages" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-            let from_folder = match params.get("from_folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'from_folder' parameter",
+                    "error": "Missing 'uids' parameter",
                     "tool": tool_name
                 })
             };
-            let to_folder = match params.get("to_folder").and_then(|v| v.as_str()) {
-                Some(t) => t,
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'to_folder' parameter",
+                    "error": "Missing 'folder' parameter",
                     "tool": tool_name
                 })
             };
@@ -2145,29 +5854,33 @@ pub async fn execute_mcp_tool_inner(
                 });
             }
 
-            match email_service.atomic_batch_move(&uids, from_folder, to_folder).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "uids": uids,
-                            "from_folder": from_folder,
-                            "to_folder": to_folder,
-                            "count": uids.len()
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to batch move messages: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            match email_service.set_flagged_for_account(folder, &uids, true, &account_id).await {
+                Ok(_) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "uids": uids,
+                        "folder": folder,
+                        "flagged": true,
+                        "count": uids.len()
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to flag messages: {}", e),
+                    "tool": tool_name
+                })
             }
         }
-        "mark_as_read" => {
+        "unflag_messages" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
             let uids = match params.get("uids").and_then(|v| v.as_array()) {
                 Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
                 None => return serde_json::json!({
@@ -2193,467 +5906,306 @@ pub async fn execute_mcp_tool_inner(
                 });
             }
 
-            match email_service.mark_as_read(folder, &uids).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "uids": uids,
-                            "folder": folder,
-                            "count": uids.len()
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to mark messages as read: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            match email_service.set_flagged_for_account(folder, &uids, false, &account_id).await {
+                Ok(_) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "uids": uids,
+                        "folder": folder,
+                        "flagged": false,
+                        "count": uids.len()
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to unflag messages: {}", e),
+                    "tool": tool_name
+                })
             }
         }
-        "mark_as_unread" => {
-            let uids = match params.get("uids").and_then(|v| v.as_array()) {
-                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
-                None => return serde_json::json!({
+        "list_flagged" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'uids' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-            let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
-                None => return serde_json::json!({
+            let limit = params.get("limit").and_then(|v| v.as_u64()).map(|v| v as usize).unwrap_or(50);
+            let offset = params.get("offset").and_then(|v| v.as_u64()).map(|v| v as usize).unwrap_or(0);
+
+            match state.cache_service.get_flagged_emails_for_account(&account_id, limit, offset).await {
+                Ok(emails) => serde_json::json!({
+                    "success": true,
+                    "data": emails,
+                    "count": emails.len(),
+                    "limit": limit,
+                    "offset": offset,
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "Missing 'folder' parameter",
+                    "error": format!("Failed to list flagged emails: {}", e),
                     "tool": tool_name
                 })
-            };
-
-            if uids.is_empty() {
-                return serde_json::json!({
+            }
+        }
+        "sieve_list_scripts" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "'uids' parameter cannot be empty",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
-                });
+                })
+            };
+
+            let mut client = match email_service.connect_sieve(&account_id).await {
+                Ok(c) => c,
+                Err(e) => return sieve_failure(tool_name, e)
+            };
+            let result = client.list_scripts().await;
+            if let Err(e) = client.logout().await {
+                debug!("ManageSieve logout failed for {}: {}", account_id, e);
             }
 
-            match email_service.mark_as_unread(folder, &uids).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "uids": uids,
-                            "folder": folder,
-                            "count": uids.len()
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to mark messages as unread: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            match result {
+                Ok(scripts) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "account_id": account_id,
+                        "scripts": scripts
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => sieve_failure(tool_name, e.into())
             }
         }
-        "mark_as_deleted" => {
-            let uids = match params.get("uids").and_then(|v| v.as_array()) {
-                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
-                None => return serde_json::json!({
+        "sieve_get_script" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'uids' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-            let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
+            let name = match params.get("name").and_then(|v| v.as_str()) {
+                Some(n) => n,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'folder' parameter",
+                    "error": "Missing 'name' parameter",
                     "tool": tool_name
                 })
             };
 
-            if uids.is_empty() {
-                return serde_json::json!({
-                    "success": false,
-                    "error": "'uids' parameter cannot be empty",
-                    "tool": tool_name
-                });
+            let mut client = match email_service.connect_sieve(&account_id).await {
+                Ok(c) => c,
+                Err(e) => return sieve_failure(tool_name, e)
+            };
+            let result = client.get_script(name).await;
+            if let Err(e) = client.logout().await {
+                debug!("ManageSieve logout failed for {}: {}", account_id, e);
             }
 
-            match email_service.mark_as_deleted(folder, &uids).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "uids": uids,
-                            "folder": folder,
-                            "count": uids.len()
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to mark messages as deleted: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            match result {
+                Ok(script) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "account_id": account_id,
+                        "name": name,
+                        "content": script
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => sieve_failure(tool_name, e.into())
             }
         }
-        "delete_messages" => {
-            let uids = match params.get("uids").and_then(|v| v.as_array()) {
-                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
-                None => return serde_json::json!({
+        "sieve_put_script" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'uids' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-            let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
+            let name = match params.get("name").and_then(|v| v.as_str()) {
+                Some(n) => n,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'folder' parameter",
+                    "error": "Missing 'name' parameter",
                     "tool": tool_name
                 })
             };
-
-            if uids.is_empty() {
-                return serde_json::json!({
+            let content = match params.get("content").and_then(|v| v.as_str()) {
+                Some(c) => c,
+                None => return serde_json::json!({
                     "success": false,
-                    "error": "'uids' parameter cannot be empty",
+                    "error": "Missing 'content' parameter",
                     "tool": tool_name
-                });
+                })
+            };
+
+            let mut client = match email_service.connect_sieve(&account_id).await {
+                Ok(c) => c,
+                Err(e) => return sieve_failure(tool_name, e)
+            };
+            let result = client.put_script(name, content).await;
+            if let Err(e) = client.logout().await {
+                debug!("ManageSieve logout failed for {}: {}", account_id, e);
             }
 
-            match email_service.delete_messages(folder, &uids).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "uids": uids,
-                            "folder": folder,
-                            "count": uids.len()
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to delete messages: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            match result {
+                Ok(_) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "account_id": account_id,
+                        "name": name,
+                        "bytes": content.len()
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => sieve_failure(tool_name, e.into())
             }
         }
-        "undelete_messages" => {
-            let uids = match params.get("uids").and_then(|v| v.as_array()) {
-                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
-                None => return serde_json::json!({
+        "sieve_activate_script" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'uids' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-            let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
+            let name = match params.get("name").and_then(|v| v.as_str()) {
+                Some(n) => n,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'folder' parameter",
+                    "error": "Missing 'name' parameter",
                     "tool": tool_name
                 })
             };
 
-            if uids.is_empty() {
-                return serde_json::json!({
-                    "success": false,
-                    "error": "'uids' parameter cannot be empty",
-                    "tool": tool_name
-                });
+            let mut client = match email_service.connect_sieve(&account_id).await {
+                Ok(c) => c,
+                Err(e) => return sieve_failure(tool_name, e)
+            };
+            let result = client.set_active(name).await;
+            if let Err(e) = client.logout().await {
+                debug!("ManageSieve logout failed for {}: {}", account_id, e);
             }
 
-            match email_service.undelete_messages(folder, &uids).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "uids": uids,
-                            "folder": folder,
-                            "count": uids.len()
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to undelete messages: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            match result {
+                Ok(_) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "account_id": account_id,
+                        "active": if name.is_empty() { None } else { Some(name) }
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => sieve_failure(tool_name, e.into())
             }
         }
-        "expunge" => {
-            let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f,
-                None => return serde_json::json!({
+        "sieve_delete_script" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'folder' parameter",
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-
-            match email_service.expunge(folder).await {
-                Ok(_) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "folder": folder
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to expunge folder: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        "list_accounts" => {
-            // List all configured email accounts
-            let account_service = state.account_service.lock().await;
-            match account_service.list_accounts().await {
-                Ok(accounts) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": accounts,
-                        "count": accounts.len(),
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to list accounts: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        "set_current_account" => {
-            // Set the current account context
-            let account_id = match params.get("account_id").and_then(|v| v.as_str()) {
-                Some(id) => id,
+            let name = match params.get("name").and_then(|v| v.as_str()) {
+                Some(n) => n,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing 'account_id' parameter",
+                    "error": "Missing 'name' parameter",
                     "tool": tool_name
                 })
             };
 
-            // Validate that the account exists
-            let account_service = state.account_service.lock().await;
-            match account_service.get_account(account_id).await {
-                Ok(account) => {
-                    // Account exists - in a real implementation, we would store this in session state
-                    // For now, just return success with the account info
-                    serde_json::json!({
-                        "success": true,
-                        "message": format!("Current account set to: {}", account_id),
-                        "data": {
-                            "account_id": account_id,
-                            "display_name": account.display_name,
-                            "email_address": account.email_address
-                        },
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Account not found: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            }
-        }
-        "send_email" => {
-            use crate::dashboard::services::{SendEmailRequest};
-
-            // Helper function to parse email addresses (handles string or array)
-            let parse_emails = |key: &str, required: bool| -> Result<Vec<String>, String> {
-                match params.get(key) {
-                    Some(val) => {
-                        if val.is_string() {
-                            let s = val.as_str().unwrap_or("");
-                            if s.is_empty() {
-                                if required {
-                                    return Err(format!("{} cannot be empty", key));
-                                }
-                                Ok(vec![])
-                            } else {
-                                Ok(vec![s.to_string()])
-                            }
-                        } else if let Some(arr) = val.as_array() {
-                            let emails = arr
-                                .iter()
-                                .filter_map(|v| v.as_str().map(String::from))
-                                .filter(|s| !s.is_empty())
-                                .collect::<Vec<String>>();
-                            if required && emails.is_empty() {
-                                return Err(format!("{} cannot be empty", key));
-                            }
-                            Ok(emails)
-                        } else {
-                            Err(format!("{} must be a string or array", key))
-                        }
-                    }
-                    None => {
-                        if required {
-                            Err(format!("{} is required", key))
-                        } else {
-                            Ok(vec![])
-                        }
-                    }
-                }
+            let mut client = match email_service.connect_sieve(&account_id).await {
+                Ok(c) => c,
+                Err(e) => return sieve_failure(tool_name, e)
             };
+            let result = client.delete_script(name).await;
+            if let Err(e) = client.logout().await {
+                debug!("ManageSieve logout failed for {}: {}", account_id, e);
+            }
 
-            // Parse required fields
-            let to = match parse_emails("to", true) {
-                Ok(emails) => emails,
+            match result {
+                Ok(_) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "account_id": account_id,
+                        "deleted": name
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => sieve_failure(tool_name, e.into())
+            }
+        }
+        "refresh_email" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
                 Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": e,
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-
-            let subject = params.get("subject")
-                .and_then(|v| v.as_str())
-                .filter(|s| !s.is_empty())
-                .ok_or("subject is required")
-                .map(String::from);
-
-            let subject = match subject {
-                Ok(s) => s,
-                Err(e) => return serde_json::json!({
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
                     "success": false,
-                    "error": e,
+                    "error": "Missing 'folder' parameter",
                     "tool": tool_name
                 })
             };
-
-            let body = params.get("body")
-                .and_then(|v| v.as_str())
-                .filter(|s| !s.is_empty())
-                .ok_or("body is required")
-                .map(String::from);
-
-            let body = match body {
-                Ok(b) => b,
-                Err(e) => return serde_json::json!({
+            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                Some(uid) => uid as u32,
+                None => return serde_json::json!({
                     "success": false,
-                    "error": e,
+                    "error": "Missing 'uid' parameter",
                     "tool": tool_name
                 })
             };
 
-            // Parse optional fields
-            let cc = parse_emails("cc", false).ok()
-                .filter(|v| !v.is_empty());
-
-            let bcc = parse_emails("bcc", false).ok()
-                .filter(|v| !v.is_empty());
-
-            let body_html = params.get("body_html")
-                .and_then(|v| v.as_str())
-                .filter(|s| !s.is_empty())
-                .map(String::from);
-
-            // Build the send request
-            let send_request = SendEmailRequest {
-                to,
-                cc,
-                bcc,
-                subject,
-                body,
-                body_html,
-            };
-
-            // Get account email - use account_id from params or default
-            let account_email = if let Some(account_id_val) = params.get("account_id") {
-                if let Some(account_id_str) = account_id_val.as_str() {
-                    account_id_str.to_string()
-                } else {
-                    // Get default account if account_id is not a string
-                    let account_service = state.account_service.lock().await;
-                    match account_service.get_default_account().await {
-                        Ok(Some(account)) => account.email_address,
-                        Ok(None) => return serde_json::json!({
-                            "success": false,
-                            "error": "No default account configured",
-                            "tool": tool_name
-                        }),
-                        Err(e) => return serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to get default account: {}", e),
-                            "tool": tool_name
-                        })
+            match email_service.refresh_email_for_account(folder, uid, &account_id).await {
+                Ok(Some(email)) => {
+                    let mut data = serde_json::to_value(&email)
+                        .unwrap_or_else(|_| serde_json::json!({}));
+                    if let Some(parts_str) = data.get("attachment_parts").and_then(|v| v.as_str()) {
+                        if let Ok(parts) = serde_json::from_str::<serde_json::Value>(parts_str) {
+                            data["attachment_parts"] = parts;
+                        }
                     }
-                }
-            } else {
-                // No account_id provided - use default
-                let account_service = state.account_service.lock().await;
-                match account_service.get_default_account().await {
-                    Ok(Some(account)) => account.email_address,
-                    Ok(None) => return serde_json::json!({
-                        "success": false,
-                        "error": "No default account configured",
-                        "tool": tool_name
-                    }),
-                    Err(e) => return serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to get default account: {}", e),
-                        "tool": tool_name
-                    })
-                }
-            };
-
-            // Send the email using SMTP service
-            match state.smtp_service.send_email(&account_email, send_request).await {
-                Ok(response) => {
-                    serde_json::json!({
-                        "success": response.success,
-                        "message": response.message,
-                        "message_id": response.message_id,
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
                     serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to send email: {}", e),
+                        "success": true,
+                        "data": data,
                         "tool": tool_name
                     })
                 }
+                Ok(None) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Email with UID {} not found on server in {}", uid, folder),
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to refresh email: {}", e),
+                    "tool": tool_name
+                })
             }
         }
-        "list_email_attachments" => {
+        "download_folder_attachments" => {
             use crate::dashboard::services::attachment_storage;
+            use crate::imap::dates;
 
-            // Get account ID
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -2662,10 +6214,16 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
-            // Get database pool
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f.to_string(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
             let db_pool = match state.cache_service.db_pool.as_ref() {
-                Some(pool) => pool,
+                Some(pool) => pool.clone(),
                 None => return serde_json::json!({
                     "success": false,
                     "error": "Database not available",
@@ -2673,147 +6231,269 @@ pub async fn execute_mcp_tool_inner(
                 })
             };
 
-            let folder_param = params.get("folder").and_then(|v| v.as_str());
-            let uid_param = params.get("uid").and_then(|v| v.as_u64()).map(|u| u as u32);
+            let from = params.get("from").and_then(|v| v.as_str()).map(|s| s.to_lowercase());
+            let subject = params.get("subject").and_then(|v| v.as_str()).map(|s| s.to_lowercase());
+            let since = match params.get("since").and_then(|v| v.as_str()).map(dates::parse_timestamp).transpose() {
+                Ok(d) => d,
+                Err(e) => return serde_json::json!({"success": false, "error": e, "tool": tool_name})
+            };
+            let before = match params.get("before").and_then(|v| v.as_str()).map(dates::parse_range_end).transpose() {
+                Ok(d) => d,
+                Err(e) => return serde_json::json!({"success": false, "error": e, "tool": tool_name})
+            };
+            let max_emails = params.get("max_emails").and_then(|v| v.as_u64()).unwrap_or(500) as usize;
 
-            // Fast path: read attachment_parts from the emails cache table.
-            // This avoids expensive IMAP re-fetch and works for all synced emails.
-            // Enriches with storage_path/downloaded_at from attachment_metadata when available.
-            if let (Some(folder), Some(uid)) = (folder_param, uid_param) {
-                if let Ok(Some(cached)) = state.cache_service
-                    .get_email_by_uid_for_account(folder, uid, &account_id).await
-                {
-                    if let Some(ref parts_json) = cached.attachment_parts {
-                        if let Ok(mut parts) = serde_json::from_str::<serde_json::Value>(parts_json) {
-                            // Enrich with attachment_metadata (storage_path, downloaded_at, etc.)
-                            if let Some(ref msg_id) = cached.message_id {
-                                if let Ok(metas) = attachment_storage::get_attachments_metadata(db_pool, &account_id, msg_id).await {
-                                    if let Some(arr) = parts.as_array_mut() {
-                                        for part in arr.iter_mut() {
-                                            let fname = part.get("filename").and_then(|v| v.as_str()).unwrap_or("");
-                                            if let Some(meta) = metas.iter().find(|m| m.filename == fname) {
-                                                part["storage_path"] = serde_json::json!(meta.storage_path);
-                                                part["downloaded_at"] = serde_json::json!(meta.downloaded_at);
-                                                part["content_id"] = serde_json::json!(meta.content_id);
-                                                part["size_bytes"] = serde_json::json!(meta.size_bytes);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            return serde_json::json!({
-                                "success": true,
-                                "data": {
-                                    "account_id": account_id,
-                                    "folder": folder,
-                                    "uid": uid,
-                                    "attachments": parts,
-                                    "count": parts.as_array().map(|a| a.len()).unwrap_or(0),
-                                },
-                                "tool": tool_name
-                            });
-                        }
-                    }
-                }
+            let cached = match state.cache_service.get_cached_emails_for_account(&folder, &account_id, max_emails, 0, true).await {
+                Ok(emails) => emails,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to read cached emails: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            // Only the newest `max_emails` cached emails are scanned
+            let scanned = cached.len();
+            let matching: Vec<_> = cached.into_iter()
+                .filter(|e| e.has_attachments)
+                .filter(|e| from.as_ref().is_none_or(|needle| {
+                    e.from_address.as_deref().unwrap_or("").to_lowercase().contains(needle)
+                        || e.from_name.as_deref().unwrap_or("").to_lowercase().contains(needle)
+                }))
+                .filter(|e| subject.as_ref().is_none_or(|needle| {
+                    e.subject.as_deref().unwrap_or("").to_lowercase().contains(needle)
+                }))
+                .filter(|e| {
+                    let date = e.date.or(e.internal_date);
+                    since.is_none_or(|s| date.is_some_and(|d| d >= s))
+                        && before.is_none_or(|b| date.is_some_and(|d| d <= b))
+                })
+                .collect();
+
+            if matching.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": format!("No cached emails with attachments among the newest {} in {} match the filters", scanned, folder),
+                    "tool": tool_name
+                });
             }
 
-            // Slow path: resolve message_id and query attachment_metadata table
-            let (message_id, folder_opt, uid_opt) = if let Some(msg_id) = params.get("message_id").and_then(|v| v.as_str()) {
-                (msg_id.to_string(), None, None)
-            } else {
-                let folder = match folder_param {
-                    Some(f) => f,
-                    None => return serde_json::json!({
-                        "success": false,
-                        "error": "folder parameter required when message_id not provided",
-                        "tool": tool_name
-                    })
-                };
+            // Estimate from the attachment sizes recorded at sync time so an
+            // oversized request fails now rather than after every download
+            let zip_config = attachment_storage::ZipConfig::from_env();
+            let estimated_bytes: u64 = matching.iter()
+                .filter_map(|e| e.attachment_parts.as_deref())
+                .filter_map(|parts| serde_json::from_str::<Vec<serde_json::Value>>(parts).ok())
+                .flatten()
+                .filter_map(|part| part.get("size").and_then(|v| v.as_u64()))
+                .sum();
+            if estimated_bytes > zip_config.max_total_bytes {
+                return serde_json::json!({
+                    "success": false,
+                    "error": format!("Attachments total about {} bytes, which exceeds the ZIP limit of {} bytes; narrow the filters", estimated_bytes, zip_config.max_total_bytes),
+                    "tool": tool_name
+                });
+            }
 
-                let uid = match uid_param {
-                    Some(u) => u,
-                    None => return serde_json::json!({
-                        "success": false,
-                        "error": "uid parameter required when message_id not provided",
-                        "tool": tool_name
-                    })
-                };
+            let zip_path = std::env::temp_dir().join(format!(
+                "rustymail_folder_attachments_{}_{}.zip",
+                attachment_storage::sanitize_message_id(&folder),
+                chrono::Utc::now().format("%Y%m%d%H%M%S"),
+            ));
+            let email_count = matching.len();
+            let job_id = start_folder_zip_job(
+                state, db_pool, account_id.clone(), folder.clone(), matching, zip_path.clone(), zip_config,
+            ).await;
 
-                let msg_id = match email_service.fetch_emails_for_account(folder, &[uid], &account_id).await {
-                    Ok(mut emails) if !emails.is_empty() => {
-                        let email = emails.remove(0);
-                        attachment_storage::ensure_message_id(&email, &account_id)
-                    }
-                    Ok(_) => return serde_json::json!({
-                        "success": false,
-                        "error": format!("Email with UID {} not found", uid),
-                        "tool": tool_name
-                    }),
-                    Err(e) => return serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to fetch email: {}", e),
-                        "tool": tool_name
-                    })
-                };
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "message": "Attachments are being downloaded and zipped in the background; poll get_job_status for progress",
+                    "download_url": format!("/api/attachments/jobs/{}/zip", job_id),
+                    "job_id": job_id,
+                    "zip_path": zip_path.to_string_lossy(),
+                    "emails": email_count,
+                    "estimated_bytes": estimated_bytes,
+                    "scanned": scanned,
+                    "scan_limit_reached": scanned >= max_emails,
+                    "folder": folder,
+                    "account_id": account_id,
+                },
+                "tool": tool_name
+            })
+        }
+        "verify_recipient" => {
+            use crate::dashboard::services::recipient_verify::{self, Deliverability};
+
+            let addresses: Vec<String> = match params.get("address") {
+                Some(v) if v.is_string() => vec![v.as_str().unwrap_or_default().to_string()],
+                Some(v) if v.is_array() => v.as_array().unwrap()
+                    .iter()
+                    .filter_map(|a| a.as_str().map(String::from))
+                    .collect(),
+                _ => vec![],
+            };
+            if addresses.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'address' parameter",
+                    "tool": tool_name
+                });
+            }
+
+            let probe_from = match resolve_account_or_default(
+                params.get("account_id").and_then(|v| v.as_str()),
+                state,
+            ).await {
+                Ok(email) => email,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let results = recipient_verify::verify_recipients(&addresses, &probe_from).await;
+            let undeliverable = results.iter().filter(|r| r.verdict == Deliverability::Undeliverable).count();
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "results": results,
+                    "undeliverable": undeliverable,
+                    "smtp_probe_enabled": recipient_verify::smtp_probe_enabled()
+                },
+                "tool": tool_name
+            })
+        }
+        "send_from_template" => {
+            use crate::dashboard::services::SendEmailRequest;
+            use crate::dashboard::services::templates::{TemplateError, TemplateService};
+
+            let template_name = match params.get("template").and_then(|v| v.as_str()) {
+                Some(name) => name.to_string(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'template' parameter",
+                    "tool": tool_name
+                })
+            };
+            let addresses = |key: &str| -> Vec<String> {
+                match params.get(key) {
+                    Some(v) if v.is_string() => v.as_str().filter(|s| !s.is_empty()).map(String::from).into_iter().collect(),
+                    Some(v) => v.as_array()
+                        .map(|arr| arr.iter().filter_map(|a| a.as_str()).filter(|s| !s.is_empty()).map(String::from).collect())
+                        .unwrap_or_default(),
+                    None => vec![],
+                }
+            };
+            let to = addresses("to");
+            if to.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "to is required",
+                    "tool": tool_name
+                });
+            }
+            let variables: std::collections::HashMap<String, String> = params.get("variables")
+                .and_then(|v| v.as_object())
+                .map(|obj| obj.iter().filter_map(|(k, v)| {
+                    let value = match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        serde_json::Value::Number(_) | serde_json::Value::Bool(_) => v.to_string(),
+                        _ => return None,
+                    };
+                    Some((k.clone(), value))
+                }).collect())
+                .unwrap_or_default();
 
-                (msg_id, Some(folder.to_string()), Some(uid))
+            let account_email = match resolve_account_or_default(
+                params.get("account_id").and_then(|v| v.as_str()),
+                state,
+            ).await {
+                Ok(email) => email,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let templates = match state.cache_service.db_pool.as_ref() {
+                Some(pool) => TemplateService::new(pool.clone()),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Database not available",
+                    "tool": tool_name
+                })
             };
 
-            // Get attachments metadata from database
-            let attachments = match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
-                Ok(atts) => atts,
-                Err(e) => {
+            let rendered = match templates.get(&account_email, &template_name).await {
+                Ok(template) => template.render(&variables),
+                Err(TemplateError::NotFound(_)) => {
+                    let available: Vec<String> = templates.list(&account_email).await
+                        .map(|list| list.into_iter().map(|t| t.name).collect())
+                        .unwrap_or_default();
                     return serde_json::json!({
                         "success": false,
-                        "error": format!("Failed to get attachments: {}", e),
+                        "error": format!("Template '{}' not found for {}", template_name, account_email),
+                        "available_templates": available,
                         "tool": tool_name
-                    })
+                    });
                 }
+                Err(e) => Err(e),
+            };
+            let rendered = match rendered {
+                Ok(r) => r,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": e.to_string(),
+                    "tool": tool_name
+                })
             };
 
-            // If no attachments found in database and we have folder+uid, fetch from IMAP
-            if let (true, Some(folder), Some(uid)) = (attachments.is_empty(), folder_opt, uid_opt) {
-                debug!("No attachments in database for message_id {}. Fetching from IMAP...", message_id);
+            let cc = addresses("cc");
+            let bcc = addresses("bcc");
+            let send_request = SendEmailRequest {
+                to,
+                cc: Some(cc).filter(|v| !v.is_empty()),
+                bcc: Some(bcc).filter(|v| !v.is_empty()),
+                subject: rendered.subject,
+                body: rendered.body,
+                body_html: rendered.body_html,
+                in_reply_to: None,
+                references: None,
+                attachments: None,
+            };
 
-                match email_service.fetch_email_with_attachments(&folder, uid, &account_id).await {
-                    Ok((_, attachment_infos)) => {
-                        serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "message_id": message_id,
-                                "account_id": account_id,
-                                "attachments": attachment_infos,
-                                "count": attachment_infos.len(),
-                                "fetched_from_imap": true,
-                            },
-                            "tool": tool_name
-                        })
-                    }
-                    Err(e) => {
-                        serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to fetch attachments from IMAP: {}", e),
-                            "tool": tool_name
-                        })
+            match state.smtp_service.send_email(&account_email, send_request).await {
+                Ok(response) => {
+                    if response.success {
+                        state.event_bus.publish_mailbox_activity(
+                            &account_email,
+                            crate::dashboard::services::events::MailboxAction::Sent,
+                            None,
+                            Vec::new(),
+                            response.message_id.clone(),
+                            "mcp",
+                        ).await;
                     }
+                    serde_json::json!({
+                        "success": response.success,
+                        "message": response.message,
+                        "message_id": response.message_id,
+                        "template": template_name,
+                        "tool": tool_name
+                    })
                 }
-            } else {
-                serde_json::json!({
-                    "success": true,
-                    "data": {
-                        "message_id": message_id,
-                        "account_id": account_id,
-                        "attachments": attachments,
-                        "count": attachments.len(),
-                    },
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to send email: {}", e),
                     "tool": tool_name
                 })
             }
         }
-        "download_email_attachments" => {
-            use crate::dashboard::services::attachment_storage;
+        "mark_read_matching" => {
+            use crate::imap::types::SearchCriteria;
 
-            // Get account ID
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -2822,173 +6502,84 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
-            // Get database pool
-            let db_pool = match state.cache_service.db_pool.as_ref() {
-                Some(pool) => pool,
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f.to_string(),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": "Missing 'folder' parameter",
                     "tool": tool_name
                 })
             };
-
-            // Determine message_id - either directly provided or resolve from folder+uid
-            // Also track folder and uid for potential IMAP fetch
-            let (message_id, folder_opt, uid_opt) = if let Some(msg_id) = params.get("message_id").and_then(|v| v.as_str()) {
-                // message_id provided directly - no folder/uid available
-                (msg_id.to_string(), None, None)
-            } else {
-                // Resolve from folder + uid
-                let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                    Some(f) => f,
-                    None => return serde_json::json!({
-                        "success": false,
-                        "error": "folder parameter required when message_id not provided",
-                        "tool": tool_name
-                    })
-                };
-
-                let uid = match params.get("uid").and_then(|v| v.as_u64()) {
-                    Some(u) => u as u32,
-                    None => return serde_json::json!({
-                        "success": false,
-                        "error": "uid parameter required when message_id not provided",
-                        "tool": tool_name
-                    })
-                };
-
-                // Fetch email to get message_id
-                let msg_id = match email_service.fetch_emails_for_account(folder, &[uid], &account_id).await {
-                    Ok(mut emails) if !emails.is_empty() => {
-                        let email = emails.remove(0);
-                        attachment_storage::ensure_message_id(&email, &account_id)
-                    }
-                    Ok(_) => return serde_json::json!({
-                        "success": false,
-                        "error": format!("Email with UID {} not found", uid),
-                        "tool": tool_name
-                    }),
-                    Err(e) => return serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to fetch email: {}", e),
-                        "tool": tool_name
-                    })
-                };
-
-                (msg_id, Some(folder.to_string()), Some(uid))
-            };
-
-            // Check if attachments exist in database, fetch from IMAP if not
-            let mut attachments = match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
-                Ok(atts) => atts,
-                Err(e) => {
-                    return serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to get attachments: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            let mut filters = match search_criteria_from_params(&params) {
+                Ok(filters) => filters,
+                Err(e) => return serde_json::json!({"success": false, "error": e, "tool": tool_name})
             };
+            filters.push(SearchCriteria::Unseen);
+            let criteria = SearchCriteria::And(filters);
+            let dry_run = params.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
 
-            // If no attachments found in database and we have folder+uid, fetch from IMAP
-            if let (true, Some(folder), Some(uid)) = (attachments.is_empty(), folder_opt.as_ref(), uid_opt) {
-                debug!("No attachments in database for message_id {}. Fetching from IMAP...", message_id);
-
-                // Fetch email with attachments from IMAP (this will save them to DB)
-                match email_service.fetch_email_with_attachments(folder, uid, &account_id).await {
-                    Ok((_, attachment_infos)) => {
-                        // Attachments now saved to database
-                        debug!("Successfully fetched and saved {} attachments from IMAP", attachment_infos.len());
-
-                        // Re-query database to get the saved attachments
-                        attachments = match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
-                            Ok(atts) => atts,
-                            Err(e) => {
-                                return serde_json::json!({
-                                    "success": false,
-                                    "error": format!("Failed to get attachments after IMAP fetch: {}", e),
-                                    "tool": tool_name
-                                })
-                            }
-                        };
-                    }
-                    Err(e) => {
-                        return serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to fetch attachments from IMAP: {}", e),
-                            "tool": tool_name
-                        })
-                    }
-                }
+            match email_service.mark_read_matching_for_account(&folder, &criteria, dry_run, &account_id).await {
+                Ok(uids) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "count": uids.len(),
+                        "dry_run": dry_run,
+                        "uid_set": crate::imap::types::uid_set(&uids),
+                        "criteria": criteria.to_string()
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to mark matching emails as read: {}", e),
+                    "tool": tool_name
+                })
             }
+        }
+        "folder_threads" => {
+            use crate::dashboard::services::threads;
 
-            // Check if user wants ZIP archive
-            let create_zip = params.get("create_zip")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(true); // Default to ZIP for convenience
-
-            if create_zip {
-                // Create ZIP archive
-                let temp_dir = std::env::temp_dir();
-                let sanitized_id = attachment_storage::sanitize_message_id(&message_id);
-                let zip_path = temp_dir.join(format!("rustymail_attachments_{}.zip", sanitized_id));
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX").to_string();
+            let max_emails = params.get("max_emails").and_then(|v| v.as_u64()).unwrap_or(1000) as usize;
+            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+            let top_participants = params.get("top_participants").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
 
-                match attachment_storage::create_zip_archive(db_pool, &account_id, &message_id, &zip_path).await {
-                    Ok(result_path) => {
-                        serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "message": "ZIP archive created",
-                                "zip_path": result_path.to_string_lossy(),
-                                "message_id": message_id,
-                                "account_id": account_id,
-                            },
-                            "tool": tool_name
-                        })
-                    }
-                    Err(e) => {
-                        serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to create ZIP: {}", e),
-                            "tool": tool_name
-                        })
-                    }
-                }
-            } else {
-                // Just list attachment paths without creating ZIP
-                match attachment_storage::get_attachments_metadata(db_pool, &account_id, &message_id).await {
-                    Ok(attachments) => {
-                        let paths: Vec<String> = attachments.iter()
-                            .map(|a| a.storage_path.clone())
-                            .collect();
+            let emails = match state.cache_service.get_cached_emails_for_account(&folder, &account_id, max_emails, 0, true).await {
+                Ok(emails) => emails,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to read cached emails: {}", e),
+                    "tool": tool_name
+                })
+            };
 
-                        serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "message": "Attachment paths retrieved",
-                                "paths": paths,
-                                "attachments": attachments,
-                                "message_id": message_id,
-                                "account_id": account_id,
-                            },
-                            "tool": tool_name
-                        })
-                    }
-                    Err(e) => {
-                        serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to get attachments: {}", e),
-                            "tool": tool_name
-                        })
-                    }
-                }
-            }
+            let mut stats = threads::folder_threads(&emails, top_participants);
+            let truncated = stats.threads.len() > limit;
+            stats.threads.truncate(limit);
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "folder": folder,
+                    "message_count": stats.message_count,
+                    "thread_count": stats.thread_count,
+                    "largest_thread": stats.largest_thread,
+                    "top_participants": stats.top_participants,
+                    "threads": stats.threads,
+                    "threads_truncated": truncated
+                },
+                "tool": tool_name
+            })
         }
-        "cleanup_attachments" => {
-            use crate::dashboard::services::attachment_storage;
-
-            // Get required parameters
+        "list_recent" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -2997,50 +6588,94 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
+            let folder = params.get("folder").and_then(|v| v.as_str());
+            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
 
-            let message_id = match params.get("message_id").and_then(|v| v.as_str()) {
-                Some(id) => id,
-                None => return serde_json::json!({
+            let emails = match state.cache_service.get_recent_emails_for_account(&account_id, folder, limit).await {
+                Ok(emails) => emails,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "message_id parameter is required",
+                    "error": format!("Failed to read recent emails: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let folder_counts = match state.cache_service.get_folder_recent_counts(&account_id, folder).await {
+                Ok(counts) => counts,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to read folder RECENT counts: {}", e),
                     "tool": tool_name
                 })
             };
 
-            // Get database pool
-            let db_pool = match state.cache_service.db_pool.as_ref() {
-                Some(pool) => pool,
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "count": emails.len(),
+                    "emails": emails,
+                    "folders": folder_counts,
+                    "note": "\\Recent is session-specific: it marks mail that arrived since the previous sync pass, not mail the user hasn't read. Another client selecting the folder first clears it for everyone else."
+                },
+                "tool": tool_name
+            })
+        }
+        "get_email_text" => {
+            use crate::utils::plain_text;
+
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX").to_string();
+            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                Some(uid) => uid as u32,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": "Missing 'uid' parameter",
                     "tool": tool_name
                 })
             };
+            let strip_quotes = params.get("strip_quotes").and_then(|v| v.as_bool()).unwrap_or(false);
+            let cache_only = params.get("cache_only").and_then(|v| v.as_bool()).unwrap_or(false);
 
-            // Delete attachments
-            match attachment_storage::delete_attachments_for_email(db_pool, message_id, &account_id).await {
-                Ok(_) => {
+            match email_service.get_email_by_uid_with_backfill(&folder, uid, &account_id, cache_only).await {
+                Ok(Some(email)) => {
+                    let (mut text, source) = plain_text::best_plain_text(email.body_text.as_deref(), email.body_html.as_deref());
+                    if strip_quotes {
+                        text = plain_text::strip_quoted_reply(&text);
+                    }
                     serde_json::json!({
                         "success": true,
-                        "message": format!("Attachments deleted for message {}", message_id),
-                        "message_id": message_id,
-                        "account_id": account_id,
-                        "tool": tool_name
-                    })
-                }
-                Err(e) => {
-                    serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to delete attachments: {}", e),
+                        "data": {
+                            "folder": folder,
+                            "uid": uid,
+                            "subject": email.subject,
+                            "from_address": email.from_address,
+                            "date": email.date,
+                            "source": source,
+                            "quotes_stripped": strip_quotes,
+                            "text": text
+                        },
                         "tool": tool_name
                     })
                 }
+                Ok(None) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Email {} not found in {}", uid, folder),
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to get email: {}", e),
+                    "tool": tool_name
+                })
             }
         }
-        "get_attachment_content" => {
-            use crate::dashboard::services::attachment_storage;
-            use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
-
+        "check_uids_exist" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3049,225 +6684,415 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
-            let filename = match params.get("filename").and_then(|v| v.as_str()) {
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
                 Some(f) => f.to_string(),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "filename parameter is required",
+                    "error": "Missing 'folder' parameter",
                     "tool": tool_name
                 })
             };
-
-            let db_pool = match state.cache_service.db_pool.as_ref() {
-                Some(pool) => pool,
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": "Missing 'uids' parameter",
                     "tool": tool_name
                 })
             };
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' must contain at least one UID",
+                    "tool": tool_name
+                });
+            }
 
-            // Resolve message_id from params or folder+uid
-            let message_id = if let Some(mid) = params.get("message_id").and_then(|v| v.as_str()) {
-                mid.to_string()
-            } else {
-                let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                    Some(f) => f,
-                    None => return serde_json::json!({
-                        "success": false,
-                        "error": "folder parameter required when message_id not provided",
-                        "tool": tool_name
-                    })
-                };
-                let uid = match params.get("uid").and_then(|v| v.as_u64()) {
-                    Some(u) => u as u32,
-                    None => return serde_json::json!({
-                        "success": false,
-                        "error": "uid parameter required when message_id not provided",
-                        "tool": tool_name
-                    })
-                };
-                match state.cache_service.get_email_by_uid_for_account(folder, uid, &account_id).await {
-                    Ok(Some(email)) => email.message_id.unwrap_or_default(),
-                    Ok(None) => return serde_json::json!({
-                        "success": false,
-                        "error": format!("Email UID {} not found in {}", uid, folder),
-                        "tool": tool_name
-                    }),
-                    Err(e) => return serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to look up email: {}", e),
-                        "tool": tool_name
-                    })
-                }
+            match email_service.check_uids_exist_for_account(&folder, &uids, &account_id).await {
+                Ok((existing, missing, uid_validity)) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "uid_validity": uid_validity,
+                        "checked": existing.len() + missing.len(),
+                        "existing": existing,
+                        "missing": missing
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to check UIDs: {}", e),
+                    "tool": tool_name
+                })
+            }
+        }
+        "next_unread" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX").to_string();
+            let newest_first = match params.get("order").and_then(|v| v.as_str()).unwrap_or("oldest") {
+                "oldest" => false,
+                "newest" => true,
+                other => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Invalid 'order' value '{}': expected 'oldest' or 'newest'", other),
+                    "tool": tool_name
+                })
             };
 
-            // Try reading from disk first; if metadata-only, fetch from IMAP
-            match attachment_storage::read_attachment_content(db_pool, &account_id, &message_id, &filename).await {
-                Ok((_name, content_type, content)) => {
-                    serde_json::json!({
-                        "success": true,
-                        "filename": filename,
-                        "content_type": content_type,
-                        "size_bytes": content.len(),
-                        "content_base64": BASE64.encode(&content),
-                        "message_id": message_id,
-                        "tool": tool_name
-                    })
-                }
-                Err(attachment_storage::AttachmentError::NotFound(msg)) if msg.contains("not yet downloaded") => {
-                    // Metadata exists but file not on disk - need IMAP fetch
-                    let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX");
-                    let uid = params.get("uid").and_then(|v| v.as_u64()).map(|u| u as u32);
+            let next = match state.cache_service.get_next_unread_uid(&folder, &account_id, newest_first).await {
+                Ok(next) => next,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to query unread emails: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let Some((uid, unread_count)) = next else {
+                return serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "found": false,
+                        "unread_count": 0,
+                        "message": format!("No unread emails in {}", folder)
+                    },
+                    "tool": tool_name
+                });
+            };
 
-                    if let Some(uid) = uid {
-                        match email_service.fetch_email_with_attachments(folder, uid, &account_id).await {
-                            Ok(_) => {
-                                // Retry reading after IMAP download
-                                match attachment_storage::read_attachment_content(db_pool, &account_id, &message_id, &filename).await {
-                                    Ok((_name, content_type, content)) => {
-                                        serde_json::json!({
-                                            "success": true,
-                                            "filename": filename,
-                                            "content_type": content_type,
-                                            "size_bytes": content.len(),
-                                            "content_base64": BASE64.encode(&content),
-                                            "message_id": message_id,
-                                            "tool": tool_name
-                                        })
-                                    }
-                                    Err(e) => serde_json::json!({
-                                        "success": false,
-                                        "error": format!("Failed to read after IMAP fetch: {}", e),
-                                        "tool": tool_name
-                                    })
+            match state.cache_service.get_cached_email(&folder, uid, &account_id).await {
+                Ok(Some(email)) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "found": true,
+                        "unread_count": unread_count,
+                        "email": {
+                            "uid": email.uid,
+                            "message_id": email.message_id,
+                            "subject": email.subject,
+                            "from_address": email.from_address,
+                            "from_name": email.from_name,
+                            "to_addresses": email.to_addresses,
+                            "cc_addresses": email.cc_addresses,
+                            "date": email.date,
+                            "internal_date": email.internal_date,
+                            "flags": email.flags,
+                            "has_attachments": email.has_attachments
+                        }
+                    },
+                    "tool": tool_name
+                }),
+                Ok(None) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Email {} disappeared from the cache for {}", uid, folder),
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to read email {}: {}", uid, e),
+                    "tool": tool_name
+                })
+            }
+        }
+        "cache_status" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let folder = params.get("folder").and_then(|v| v.as_str());
+            let live = params.get("live").and_then(|v| v.as_bool()).unwrap_or(false);
+            let stale_after_seconds = params.get("stale_after_seconds")
+                .and_then(|v| v.as_i64())
+                .or_else(|| std::env::var("CACHE_STALE_AFTER_SECONDS").ok().and_then(|v| v.parse().ok()))
+                .unwrap_or(900)
+                .max(0);
+
+            let mut folders = match state.cache_service.get_folder_freshness(&account_id, folder).await {
+                Ok(folders) => folders,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to read cache status: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            let mut live_error = None;
+            if live && !folders.is_empty() {
+                let names: Vec<String> = folders.iter().map(|f| f.folder.clone()).collect();
+                match email_service.folder_statuses_for_account(&names, &account_id).await {
+                    Ok(statuses) => {
+                        for status in statuses {
+                            if let Some(entry) = folders.iter_mut().find(|f| f.folder == status.name) {
+                                if let Some(messages) = status.messages.filter(|_| status.error.is_none()) {
+                                    entry.server_count = Some(messages as i64);
+                                    entry.live = true;
                                 }
                             }
-                            Err(e) => serde_json::json!({
-                                "success": false,
-                                "error": format!("Failed to fetch from IMAP: {}", e),
-                                "tool": tool_name
-                            })
                         }
-                    } else {
-                        serde_json::json!({
+                    }
+                    // Fall back to the counts recorded at the last sync
+                    Err(e) => live_error = Some(format!("Live STATUS failed: {}", e)),
+                }
+            }
+
+            let now = chrono::Utc::now();
+            let stale_after = chrono::Duration::seconds(stale_after_seconds);
+            for entry in &mut folders {
+                entry.evaluate(now, stale_after);
+            }
+            let stale_count = folders.iter().filter(|f| f.stale).count();
+
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "account_id": account_id,
+                    "checked_at": now,
+                    "stale_after_seconds": stale_after_seconds,
+                    "stale_count": stale_count,
+                    "live_error": live_error,
+                    "folders": folders
+                },
+                "tool": tool_name
+            })
+        }
+        "compare_folders" => {
+            let (source_folder, dest_folder) = match (
+                params.get("source_folder").and_then(|v| v.as_str()),
+                params.get("dest_folder").and_then(|v| v.as_str()),
+            ) {
+                (Some(source), Some(dest)) => (source, dest),
+                _ => return serde_json::json!({
+                    "success": false,
+                    "error": "source_folder and dest_folder parameters are required",
+                    "tool": tool_name
+                })
+            };
+            let source_account_id = match params.get("source_account_id").and_then(|v| v.as_str()) {
+                Some(id) => id.to_string(),
+                None => match get_account_id_to_use(&params, &state_data).await {
+                    Ok(id) => id,
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to determine account: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            };
+            let dest_account_id = params.get("dest_account_id")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| source_account_id.clone());
+            let verify_on_server = params.get("verify_on_server").and_then(|v| v.as_bool()).unwrap_or(false);
+            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(100) as usize;
+
+            let mut sides = Vec::new();
+            for (account_id, folder) in [(&source_account_id, source_folder), (&dest_account_id, dest_folder)] {
+                match state.cache_service.get_folder_message_keys(folder, account_id).await {
+                    Ok(Some(keys)) => sides.push(keys),
+                    Ok(None) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Folder '{}' for {} is not in the cache; sync it first", folder, account_id),
+                        "tool": tool_name
+                    }),
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to read cached messages: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            }
+            let (source_keys, dest_keys) = (&sides[0], &sides[1]);
+            let mut diff = crate::dashboard::services::cache::FolderDiff::compare(source_keys, dest_keys);
+
+            // Ask each side's server about Message-IDs its cache lacks; hash-only
+            // entries can't be searched for and stay in the diff
+            let mut found_on_server = serde_json::json!({"source": [], "dest": []});
+            if verify_on_server {
+                for (missing, account_id, folder, side) in [
+                    (&mut diff.only_in_a, &dest_account_id, dest_folder, "dest"),
+                    (&mut diff.only_in_b, &source_account_id, source_folder, "source"),
+                ] {
+                    let ids: Vec<String> = missing.iter().filter_map(|m| m.message_id.clone()).collect();
+                    if ids.is_empty() {
+                        continue;
+                    }
+                    match email_service.find_message_ids_for_account(folder, &ids, account_id).await {
+                        Ok(found) => {
+                            missing.retain(|m| m.message_id.as_ref().is_none_or(|id| !found.contains(id)));
+                            found_on_server[side] = serde_json::json!(found);
+                        }
+                        Err(e) => return serde_json::json!({
                             "success": false,
-                            "error": "Attachment not yet downloaded. Provide folder+uid to trigger IMAP fetch.",
+                            "error": format!("Server search in '{}' failed: {}", folder, e),
                             "tool": tool_name
                         })
                     }
                 }
-                Err(e) => serde_json::json!({
-                    "success": false,
-                    "error": format!("Failed to read attachment: {}", e),
-                    "tool": tool_name
-                })
             }
-        }
-        // === Job Management Tools ===
-        "list_jobs" => {
-            use crate::dashboard::services::jobs::JobStatus;
-            let status_filter = params.get("status_filter").and_then(|v| v.as_str());
 
-            let jobs: Vec<_> = state.jobs.iter()
-                .filter(|entry| {
-                    match status_filter {
-                        Some("running") => matches!(entry.value().status, JobStatus::Running),
-                        Some("completed") => matches!(entry.value().status, JobStatus::Completed(_)),
-                        Some("failed") => matches!(entry.value().status, JobStatus::Failed(_)),
-                        _ => true, // No filter, return all
-                    }
-                })
-                .map(|entry| {
-                    let job = entry.value();
-                    serde_json::json!({
-                        "job_id": job.job_id,
-                        "instruction": job.instruction,
-                        "status": &job.status,
-                        "elapsed_seconds": job.started_at.elapsed().as_secs()
-                    })
-                })
-                .collect();
+            let only_in_source_count = diff.only_in_a.len();
+            let only_in_dest_count = diff.only_in_b.len();
+            diff.only_in_a.truncate(limit);
+            diff.only_in_b.truncate(limit);
 
             serde_json::json!({
                 "success": true,
                 "data": {
-                    "jobs": jobs,
-                    "total": jobs.len()
+                    "source": {"account_id": source_account_id, "folder": source_folder, "cached": source_keys.len()},
+                    "dest": {"account_id": dest_account_id, "folder": dest_folder, "cached": dest_keys.len()},
+                    "complete": only_in_source_count == 0,
+                    "matched": diff.matched,
+                    "without_message_id": diff.hashed,
+                    "only_in_source_count": only_in_source_count,
+                    "only_in_dest_count": only_in_dest_count,
+                    "only_in_source": diff.only_in_a,
+                    "only_in_dest": diff.only_in_b,
+                    "found_on_server": found_on_server
                 },
                 "tool": tool_name
             })
         }
-        "get_job_status" => {
-            let job_id = match params.get("job_id").and_then(|v| v.as_str()) {
-                Some(id) => id,
+        "extract_links" => {
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX");
+            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                Some(uid) => uid as u32,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "Missing required parameter: job_id",
+                    "error": "uid parameter is required",
                     "tool": tool_name
-                }),
+                })
+            };
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
             };
 
-            match state.jobs.get(job_id) {
-                Some(job) => serde_json::json!({
-                    "success": true,
-                    "data": {
-                        "job_id": job.job_id,
-                        "instruction": job.instruction,
-                        "status": &job.status,
-                        "elapsed_seconds": job.started_at.elapsed().as_secs()
-                    },
+            match state.cache_service.get_email_by_uid_for_account(folder, uid, &account_id).await {
+                Ok(Some(email)) => {
+                    let links = crate::dashboard::services::links::extract_links(
+                        email.body_text.as_deref(),
+                        email.body_html.as_deref(),
+                    );
+                    let mismatches = links.iter().filter(|l| l.domain_mismatch).count();
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "folder": folder,
+                            "uid": uid,
+                            "subject": email.subject,
+                            "count": links.len(),
+                            "domain_mismatches": mismatches,
+                            "links": links
+                        },
+                        "tool": tool_name
+                    })
+                }
+                Ok(None) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Email UID {} not found in cache for folder '{}'", uid, folder),
                     "tool": tool_name
                 }),
-                None => serde_json::json!({
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": format!("Job not found: {}", job_id),
+                    "error": format!("Failed to read cached email: {}", e),
                     "tool": tool_name
-                }),
+                })
             }
         }
-        "cancel_job" => {
-            use crate::dashboard::services::jobs::JobStatus;
-            let job_id = match params.get("job_id").and_then(|v| v.as_str()) {
-                Some(id) => id,
-                None => return serde_json::json!({
+        "empty_folder" => {
+            if params.get("confirm").and_then(|v| v.as_bool()) != Some(true) {
+                return serde_json::json!({
                     "success": false,
-                    "error": "Missing required parameter: job_id",
+                    "error": "empty_folder permanently deletes messages; pass confirm: true to proceed",
+                    "tool": tool_name
+                });
+            }
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
-                }),
-            };
-
-            // Get current status before removal
-            let job_info = state.jobs.get(job_id).map(|job| {
-                let was_running = matches!(job.status, JobStatus::Running);
-                serde_json::json!({
-                    "job_id": job.job_id,
-                    "status": &job.status,
-                    "was_running": was_running,
-                    "elapsed_seconds": job.started_at.elapsed().as_secs()
                 })
-            });
-
-            match job_info {
-                Some(info) => {
-                    // Remove the job from the map
-                    state.jobs.remove(job_id);
-                    serde_json::json!({
-                        "success": true,
-                        "message": "Job cancelled",
-                        "data": info,
-                        "tool": tool_name
-                    })
+            };
+            let older_than_days = params.get("older_than_days").and_then(|v| v.as_u64()).map(|d| d as u32);
+
+            let (folder, role) = match (
+                params.get("folder").and_then(|v| v.as_str()),
+                params.get("role").and_then(|v| v.as_str()),
+            ) {
+                (Some(folder), None) => (folder.to_string(), None),
+                (None, Some(role)) => {
+                    let folders = match email_service.list_folder_states_for_account(&account_id).await {
+                        Ok(folders) => folders,
+                        Err(e) => return serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to list folders: {}", e),
+                            "tool": tool_name
+                        })
+                    };
+                    let profile = match email_service.provider_profile_for_account(&account_id).await {
+                        Ok(profile) => profile,
+                        Err(e) => return serde_json::json!({
+                            "success": false,
+                            "error": format!("Failed to load account: {}", e),
+                            "tool": tool_name
+                        })
+                    };
+                    match profile.find_special_use(&folders, role) {
+                        Some(found) => (found.name.clone(), Some(role.to_string())),
+                        None => return serde_json::json!({
+                            "success": false,
+                            "error": format!("No folder found for role '{}'", role),
+                            "tool": tool_name
+                        })
+                    }
                 }
-                None => serde_json::json!({
+                _ => return serde_json::json!({
                     "success": false,
-                    "error": format!("Job not found: {}", job_id),
+                    "error": "Give exactly one of folder or role",
+                    "tool": tool_name
+                })
+            };
+
+            match email_service.empty_folder_for_account(&folder, older_than_days, &account_id).await {
+                Ok((purged, remaining)) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "role": role,
+                        "older_than_days": older_than_days,
+                        "purged": purged.len(),
+                        "uids": purged,
+                        "remaining": remaining
+                    },
                     "tool": tool_name
                 }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to empty folder '{}': {}", folder, e),
+                    "tool": tool_name
+                })
             }
         }
-        "get_email_synopsis" => {
+        "hierarchical_unread" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3276,69 +7101,150 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
+            let live = params.get("live").and_then(|v| v.as_bool()).unwrap_or(false);
 
-            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX");
-            let uid = match params.get("uid").and_then(|v| v.as_u64()).map(|u| u as u32) {
-                Some(u) => u,
-                None => return serde_json::json!({
+            let folders = match email_service.list_folder_states_for_account(&account_id).await {
+                Ok(folders) => folders,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "uid parameter is required",
+                    "error": format!("Failed to list folders: {}", e),
                     "tool": tool_name
                 })
             };
-            let max_lines = params.get("max_lines").and_then(|v| v.as_u64()).unwrap_or(3) as usize;
 
-            match state.cache_service.get_email_by_uid_for_account(folder, uid, &account_id).await {
-                Ok(Some(email)) => {
-                    let subject = email.subject.as_deref().unwrap_or("(no subject)");
-                    let synopsis = match &email.body_text {
-                        Some(body) => {
-                            let sentences: Vec<&str> = body
-                                .split(|c: char| c == '.' || c == '!' || c == '?')
-                                .map(|s| s.trim())
-                                .filter(|s| !s.is_empty() && s.len() > 5)
-                                .take(max_lines)
-                                .collect();
-                            if sentences.is_empty() {
-                                body.lines()
-                                    .map(|l| l.trim())
-                                    .filter(|l| !l.is_empty())
-                                    .take(max_lines)
-                                    .collect::<Vec<_>>()
-                                    .join("\n")
-                            } else {
-                                sentences.join(". ") + "."
-                            }
-                        }
-                        None => "(no body text available)".to_string(),
-                    };
-                    serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "uid": uid,
-                            "folder": folder,
-                            "subject": subject,
-                            "synopsis": synopsis,
-                            "from": email.from_address,
-                            "date": email.date,
-                            "has_attachments": email.has_attachments,
-                        },
+            let unread: std::collections::HashMap<String, i64> = if live {
+                let names: Vec<String> = folders.iter()
+                    .filter(|f| f.selectable)
+                    .map(|f| f.name.clone())
+                    .collect();
+                match email_service.folder_statuses_for_account(&names, &account_id).await {
+                    Ok(statuses) => statuses.into_iter()
+                        .filter_map(|s| s.unseen.map(|u| (s.name, u as i64)))
+                        .collect(),
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to get folder status: {}", e),
                         "tool": tool_name
                     })
                 }
-                Ok(None) => serde_json::json!({
+            } else {
+                match state.cache_service.get_unread_counts_for_account(&account_id).await {
+                    Ok(counts) => counts,
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to read unread counts: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            };
+
+            let rollups = crate::imap::types::rollup_unread(&folders, &unread);
+            let total_unread: i64 = rollups.iter().map(|r| r.unread).sum();
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "account_id": account_id,
+                    "source": if live { "live" } else { "cache" },
+                    "total_unread": total_unread,
+                    "folders": rollups
+                },
+                "tool": tool_name
+            })
+        }
+        "sync_lag" => {
+            use futures_util::stream;
+
+            let folder = params.get("folder").and_then(|v| v.as_str());
+            let stale_after_seconds = params.get("stale_after_seconds")
+                .and_then(|v| v.as_i64())
+                .or_else(|| std::env::var("CACHE_STALE_AFTER_SECONDS").ok().and_then(|v| v.parse().ok()))
+                .unwrap_or(900)
+                .max(0);
+
+            let account_ids: Vec<String> = if params.get("all_accounts").and_then(|v| v.as_bool()).unwrap_or(false) {
+                let account_service = state.account_service.lock().await;
+                match account_service.list_accounts().await {
+                    Ok(accounts) => accounts.into_iter().map(|a| a.email_address).collect(),
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to list accounts: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            } else {
+                match get_account_id_to_use(&params, &state_data).await {
+                    Ok(id) => vec![id],
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to determine account: {}", e),
+                        "tool": tool_name
+                    })
+                }
+            };
+
+            // Each account holds one IMAP session while its folders are STATUSed
+            let concurrency = std::env::var("SYNC_LAG_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &usize| n > 0)
+                .unwrap_or(4);
+            let now = chrono::Utc::now();
+            let stale_after = chrono::Duration::seconds(stale_after_seconds);
+
+            let mut accounts: Vec<crate::dashboard::services::cache::AccountLag> = stream::iter(account_ids)
+                .map(|account_id| async move {
+                    account_sync_lag(state, &account_id, folder, now, stale_after).await
+                })
+                .buffer_unordered(concurrency)
+                .collect()
+                .await;
+            accounts.sort_by(|a, b| b.lag_score.cmp(&a.lag_score).then_with(|| a.account_id.cmp(&b.account_id)));
+
+            serde_json::json!({
+                "success": true,
+                "data": {
+                    "checked_at": now,
+                    "stale_after_seconds": stale_after_seconds,
+                    "accounts_failing": accounts.iter().filter(|a| a.status == "failing").count(),
+                    "accounts_behind": accounts.iter().filter(|a| a.status == "behind").count(),
+                    "accounts": accounts
+                },
+                "tool": tool_name
+            })
+        }
+        "refresh_account_connections" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": format!("Email UID {} not found in {}", uid, folder),
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            state.sync_service.reconnect_idle(&account_id);
+            match email_service.refresh_connections_for_account(&account_id).await {
+                Ok(refresh) if refresh.connected => serde_json::json!({
+                    "success": true,
+                    "data": refresh,
+                    "tool": tool_name
+                }),
+                Ok(refresh) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Test login failed: {}", refresh.error.as_deref().unwrap_or("unknown error")),
+                    "data": refresh,
                     "tool": tool_name
                 }),
                 Err(e) => serde_json::json!({
                     "success": false,
-                    "error": format!("Failed to fetch email: {}", e),
+                    "error": format!("Failed to refresh connections: {}", e),
                     "tool": tool_name
                 })
             }
         }
-        "get_email_thread" => {
+        "search_server" => {
+            use crate::imap::types::SearchCriteria;
+
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3347,48 +7253,84 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
-            let message_id = match params.get("message_id").and_then(|v| v.as_str()) {
-                Some(mid) => mid.to_string(),
-                None => return serde_json::json!({
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX").to_string();
+            let limit = crate::imap::types::search_result_limit(
+                params.get("limit").and_then(|v| v.as_u64()).map(|n| n as usize)
+            );
+
+            let empty = serde_json::json!({});
+            let criteria_params = params.get("criteria").unwrap_or(&empty);
+            if !criteria_params.is_object() {
+                return serde_json::json!({
                     "success": false,
-                    "error": "message_id parameter is required",
+                    "error": "'criteria' must be an object",
                     "tool": tool_name
-                })
+                });
+            }
+            let mut terms = match search_terms_from_params(criteria_params) {
+                Ok(terms) => terms,
+                Err(e) => return serde_json::json!({"success": false, "error": e, "tool": tool_name})
+            };
+            match criteria_params.get("unseen").and_then(|v| v.as_bool()) {
+                Some(true) => terms.push(SearchCriteria::Unseen),
+                Some(false) => terms.push(SearchCriteria::Seen),
+                None => {}
+            }
+            match criteria_params.get("flagged").and_then(|v| v.as_bool()) {
+                Some(true) => terms.push(SearchCriteria::Flagged),
+                Some(false) => terms.push(SearchCriteria::Unflagged),
+                None => {}
+            }
+            let criteria = SearchCriteria::all_of(terms);
+            let sort_keys = match params.get("sort_by").and_then(|v| v.as_str()) {
+                Some(spec) => match crate::imap::sort::parse_sort_keys(spec) {
+                    Ok(keys) => Some(keys),
+                    Err(e) => return serde_json::json!({"success": false, "error": e, "tool": tool_name})
+                },
+                None => None,
             };
 
-            match state.cache_service.get_thread_emails(&message_id, &account_id).await {
-                Ok(emails) => {
-                    let thread: Vec<serde_json::Value> = emails.iter().map(|e| {
+            let searched = match &sort_keys {
+                Some(keys) => email_service.list_folder_sorted(&folder, &criteria, keys, limit, &account_id).await,
+                None => email_service.search_uids_for_account(&folder, &criteria, limit, &account_id).await,
+            };
+            match searched {
+                Ok(search) => {
+                    let previews: Vec<serde_json::Value> = search.previews.into_iter().map(|email| {
+                        let envelope = email.envelope.as_ref();
                         serde_json::json!({
-                            "uid": e.uid,
-                            "message_id": e.message_id,
-                            "subject": e.subject,
-                            "from_address": e.from_address,
-                            "from_name": e.from_name,
-                            "date": e.date,
-                            "in_reply_to": e.in_reply_to,
-                            "has_attachments": e.has_attachments,
-                            "flags": e.flags,
+                            "uid": email.uid,
+                            "subject": envelope.and_then(|e| e.subject.clone()),
+                            "from": envelope.map(|e| e.from.clone()).unwrap_or_default(),
+                            "to": envelope.map(|e| e.to.clone()).unwrap_or_default(),
+                            "date": envelope.and_then(|e| e.date.clone()),
+                            "internal_date": email.internal_date,
+                            "flags": email.flags
                         })
                     }).collect();
                     serde_json::json!({
                         "success": true,
                         "data": {
-                            "thread_count": thread.len(),
-                            "thread": thread,
+                            "folder": folder,
+                            "criteria": criteria.to_string(),
+                            "sort_by": sort_keys.as_ref().map(|keys| keys.iter().map(|k| k.to_string()).collect::<Vec<_>>()),
+                            "total_matches": search.total_matches,
+                            "returned": search.uids.len(),
+                            "truncated": search.total_matches > search.uids.len(),
+                            "uids": search.uids,
+                            "previews": previews
                         },
                         "tool": tool_name
                     })
                 }
                 Err(e) => serde_json::json!({
                     "success": false,
-                    "error": format!("Failed to fetch thread: {}", e),
+                    "error": format!("Server search failed: {}", e),
                     "tool": tool_name
                 })
             }
         }
-        "search_by_domain" => {
+        "search_cached_emails_fts" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3397,53 +7339,47 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
-            let domain = match params.get("domain").and_then(|v| v.as_str()) {
-                Some(d) => d.to_string(),
+            let query = match params.get("query").and_then(|v| v.as_str()).map(str::trim).filter(|q| !q.is_empty()) {
+                Some(q) => q,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "domain parameter is required",
+                    "error": "query parameter is required",
                     "tool": tool_name
                 })
             };
-
-            let search_in: Vec<&str> = params.get("search_in")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|s| s.as_str()).collect())
-                .unwrap_or_else(|| vec!["from"]);
-            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
-
-            match state.cache_service.search_by_domain(&domain, &search_in, &account_id, limit).await {
-                Ok(emails) => {
-                    let results: Vec<serde_json::Value> = emails.iter().map(|e| {
-                        serde_json::json!({
-                            "uid": e.uid,
-                            "subject": e.subject,
-                            "from_address": e.from_address,
-                            "from_name": e.from_name,
-                            "date": e.date,
-                            "flags": e.flags,
-                            "has_attachments": e.has_attachments,
-                        })
-                    }).collect();
+            let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("");
+            let limit = params.get("limit").and_then(|v| v.as_u64()).map(|v| v as usize).unwrap_or(20);
+
+            match state.cache_service.search_cached_emails_ranked(folder, query, limit, &account_id).await {
+                Ok(hits) => {
+                    let results: Vec<serde_json::Value> = hits.into_iter().map(|hit| serde_json::json!({
+                        "uid": hit.email.uid,
+                        "folder": hit.folder,
+                        "message_id": hit.email.message_id,
+                        "subject": hit.email.subject,
+                        "from_address": hit.email.from_address,
+                        "from_name": hit.email.from_name,
+                        "date": hit.email.date,
+                        "flags": hit.email.flags,
+                        "score": hit.score,
+                        "snippet": hit.snippet
+                    })).collect();
                     serde_json::json!({
                         "success": true,
-                        "data": {
-                            "domain": domain,
-                            "count": results.len(),
-                            "emails": results,
-                        },
+                        "data": results,
+                        "query": query,
+                        "count": results.len(),
                         "tool": tool_name
                     })
                 }
                 Err(e) => serde_json::json!({
                     "success": false,
-                    "error": format!("Domain search failed: {}", e),
+                    "error": format!("Failed to search emails: {}", e),
                     "tool": tool_name
                 })
             }
         }
-        "get_address_report" => {
+        "copy_message" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3452,23 +7388,70 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
+            let source_folder = match params.get("source_folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'source_folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let target_folder = match params.get("target_folder").and_then(|v| v.as_str()) {
+                Some(t) => t,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'target_folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let uids: Vec<u32> = match (params.get("uid").and_then(|v| v.as_u64()), params.get("uids").and_then(|v| v.as_array())) {
+                (_, Some(arr)) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect(),
+                (Some(uid), None) => vec![uid as u32],
+                (None, None) => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uid' parameter",
+                    "tool": tool_name
+                })
+            };
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "No valid UIDs provided",
+                    "tool": tool_name
+                });
+            }
 
-            match state.cache_service.get_address_report(&account_id).await {
-                Ok(report) => {
-                    serde_json::json!({
-                        "success": true,
-                        "data": report,
-                        "tool": tool_name
-                    })
-                }
+            let copied = if uids.len() == 1 {
+                email_service.copy_messages(&uids, source_folder, target_folder, &account_id).await
+            } else {
+                email_service.batch_copy(&uids, source_folder, target_folder, &account_id).await
+            };
+            match copied {
+                Ok(outcome) if outcome.copied.is_empty() => serde_json::json!({
+                    "success": false,
+                    "error": format!("No matching messages in {}: {:?}", source_folder, outcome.missing),
+                    "tool": tool_name
+                }),
+                Ok(outcome) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "source_folder": source_folder,
+                        "target_folder": target_folder,
+                        "copied": outcome.copied,
+                        "missing": outcome.missing,
+                        "target_uid_validity": outcome.target_uid_validity,
+                        "new_uids": outcome.new_uids
+                    },
+                    "tool": tool_name
+                }),
                 Err(e) => serde_json::json!({
                     "success": false,
-                    "error": format!("Failed to generate report: {}", e),
+                    "error": format!("Failed to copy message: {}", e),
                     "tool": tool_name
                 })
             }
         }
-        "list_emails_by_flag" => {
+        "get_quota" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3477,105 +7460,177 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
             let folder = params.get("folder").and_then(|v| v.as_str()).unwrap_or("INBOX");
-            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
-            let offset = params.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-
-            let flags_include: Vec<String> = params.get("flags_include")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default();
-            let flags_exclude: Vec<String> = params.get("flags_exclude")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default();
 
-            // Shorthand: unread_only adds "Seen" to exclude list
-            if params.get("unread_only").and_then(|v| v.as_bool()).unwrap_or(false) {
-                let mut exclude = flags_exclude;
-                if !exclude.contains(&"Seen".to_string()) {
-                    exclude.push("Seen".to_string());
-                }
-                match state.cache_service.get_cached_emails_by_flags(
-                    folder, &account_id, &flags_include, &exclude, limit, offset,
-                ).await {
-                    Ok(emails) => serde_json::json!({
-                        "success": true,
-                        "data": emails,
-                        "count": emails.len(),
+            match email_service.get_quota_for_account(folder, &account_id).await {
+                Ok(quota) => serde_json::json!({
+                    "success": true,
+                    "data": {
                         "folder": folder,
-                        "tool": tool_name
-                    }),
-                    Err(e) => serde_json::json!({
+                        "root": quota.root,
+                        "used_kb": quota.used_kb,
+                        "limit_kb": quota.limit_kb,
+                        "used_messages": quota.used_messages,
+                        "limit_messages": quota.limit_messages,
+                        "percent_used": quota.percent_used()
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to get quota: {}", e),
+                    "tool": tool_name
+                })
+            }
+        }
+        "get_thread" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+            let folder = match params.get("folder").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'folder' parameter",
+                    "tool": tool_name
+                })
+            };
+            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                Some(uid) => uid as u32,
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uid' parameter",
+                    "tool": tool_name
+                })
+            };
+            let algorithm = match params.get("algorithm").and_then(|v| v.as_str()) {
+                Some(name) => match name.parse::<crate::imap::threading::ThreadAlgorithm>() {
+                    Ok(algorithm) => algorithm,
+                    Err(e) => return serde_json::json!({
                         "success": false,
-                        "error": format!("Failed to filter emails: {}", e),
+                        "error": e,
                         "tool": tool_name
                     })
-                }
-            } else {
-                match state.cache_service.get_cached_emails_by_flags(
-                    folder, &account_id, &flags_include, &flags_exclude, limit, offset,
-                ).await {
-                    Ok(emails) => serde_json::json!({
+                },
+                None => crate::imap::threading::ThreadAlgorithm::References,
+            };
+
+            match email_service.get_thread(folder, uid, algorithm, &account_id).await {
+                Ok(Some(conversation)) => {
+                    let messages: std::collections::HashMap<u32, &crate::imap::types::Email> =
+                        conversation.messages.iter().map(|m| (m.uid, m)).collect();
+                    serde_json::json!({
                         "success": true,
-                        "data": emails,
-                        "count": emails.len(),
-                        "folder": folder,
-                        "tool": tool_name
-                    }),
-                    Err(e) => serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to filter emails: {}", e),
+                        "data": {
+                            "folder": folder,
+                            "algorithm": algorithm,
+                            "message_count": conversation.thread.uids().len(),
+                            "uids": conversation.thread.uids(),
+                            "thread": thread_json(&conversation.thread, &messages)
+                        },
                         "tool": tool_name
                     })
                 }
-            }
-        }
-        "search_by_attachment_type" => {
-            let account_id = match get_account_id_to_use(&params, &state_data).await {
-                Ok(id) => id,
-                Err(e) => return serde_json::json!({
+                Ok(None) => serde_json::json!({
                     "success": false,
-                    "error": format!("Failed to determine account: {}", e),
+                    "error": format!("No message with UID {} in {}", uid, folder),
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to build thread: {}", e),
                     "tool": tool_name
                 })
+            }
+        }
+        "save_draft" => {
+            use crate::dashboard::services::{OutgoingAttachment, SendEmailRequest};
+
+            let addresses = |key: &str| -> Vec<String> {
+                match params.get(key) {
+                    Some(serde_json::Value::String(s)) => s.split(',')
+                        .map(|addr| addr.trim().to_string())
+                        .filter(|addr| !addr.is_empty())
+                        .collect(),
+                    Some(serde_json::Value::Array(list)) => list.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|addr| addr.trim().to_string())
+                        .filter(|addr| !addr.is_empty())
+                        .collect(),
+                    _ => vec![],
+                }
             };
+            let text = |key: &str| params.get(key).and_then(|v| v.as_str()).map(String::from);
 
-            let mime_types: Vec<String> = params.get("mime_types")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default();
+            let to = addresses("to");
+            if to.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "to is required",
+                    "tool": tool_name
+                });
+            }
 
-            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+            let attachments = match params.get("attachments").filter(|v| !v.is_null()) {
+                Some(value) => match serde_json::from_value::<Vec<OutgoingAttachment>>(value.clone()) {
+                    Ok(list) if !list.is_empty() => Some(list),
+                    Ok(_) => None,
+                    Err(e) => return serde_json::json!({
+                        "success": false,
+                        "error": format!("Invalid attachments: {}", e),
+                        "tool": tool_name
+                    })
+                },
+                None => None,
+            };
 
-            let pool = state.cache_service.db_pool.as_ref();
-            match pool {
-                Some(pool) => {
-                    match crate::dashboard::services::attachment_storage::search_by_attachment_type(
-                        pool, &account_id, &mime_types, limit
-                    ).await {
-                        Ok(results) => serde_json::json!({
-                            "success": true,
-                            "data": results,
-                            "count": results.len(),
-                            "tool": tool_name
-                        }),
-                        Err(e) => serde_json::json!({
-                            "success": false,
-                            "error": format!("Search failed: {}", e),
-                            "tool": tool_name
-                        })
-                    }
-                }
-                None => serde_json::json!({
+            let draft_request = SendEmailRequest {
+                to,
+                cc: Some(addresses("cc")).filter(|v| !v.is_empty()),
+                bcc: Some(addresses("bcc")).filter(|v| !v.is_empty()),
+                subject: text("subject").unwrap_or_default(),
+                body: text("body").unwrap_or_default(),
+                body_html: text("body_html").filter(|s| !s.is_empty()),
+                in_reply_to: None,
+                references: None,
+                attachments,
+            };
+
+            let account_email = match resolve_account_or_default(
+                params.get("account_id").and_then(|v| v.as_str()),
+                state,
+            ).await {
+                Ok(email) => email,
+                Err(e) => return serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": format!("Failed to determine account: {}", e),
+                    "tool": tool_name
+                })
+            };
+
+            match state.smtp_service.save_draft(&account_email, &draft_request).await {
+                Ok(saved) => serde_json::json!({
+                    "success": true,
+                    "data": saved,
+                    "account_id": account_email,
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to save draft: {}", e),
+                    "account_id": account_email,
                     "tool": tool_name
                 })
             }
         }
-        "sync_emails" => {
+        "get_special_folders" => {
+            use crate::imap::special_use::SpecialUse;
+
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3584,48 +7639,32 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
+            let refresh = params.get("refresh").and_then(|v| v.as_bool()).unwrap_or(false);
 
-            let folder = params.get("folder").and_then(|v| v.as_str()).map(|s| s.to_string());
-            let sync_service = state.sync_service.clone();
-
-            match folder {
-                Some(ref f) => {
-                    info!("MCP sync_emails: syncing folder '{}' for account '{}'", f, account_id);
-                    match sync_service.sync_folder(&account_id, f).await {
-                        Ok(()) => serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "message": format!("Synced folder '{}' for account '{}'", f, account_id),
-                            },
-                            "tool": tool_name
-                        }),
-                        Err(e) => serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to sync folder '{}': {}", f, e),
-                            "tool": tool_name
-                        })
-                    }
-                }
-                None => {
-                    info!("MCP sync_emails: syncing all folders for account '{}'", account_id);
-                    match sync_service.sync_all_folders(&account_id).await {
-                        Ok(()) => serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "message": format!("Synced all folders for account '{}'", account_id),
-                            },
-                            "tool": tool_name
-                        }),
-                        Err(e) => serde_json::json!({
-                            "success": false,
-                            "error": format!("Failed to sync all folders: {}", e),
-                            "tool": tool_name
-                        })
-                    }
+            match email_service.special_folders_for_account(&account_id, refresh).await {
+                Ok(folders) => {
+                    let missing: Vec<SpecialUse> = SpecialUse::ALL.into_iter()
+                        .filter(|role| !folders.contains_key(role))
+                        .collect();
+                    serde_json::json!({
+                        "success": true,
+                        "data": {
+                            "folders": folders,
+                            "missing": missing
+                        },
+                        "account_id": account_id,
+                        "tool": tool_name
+                    })
                 }
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to find special-use folders: {}", e),
+                    "account_id": account_id,
+                    "tool": tool_name
+                })
             }
         }
-        "export_folder_metadata" => {
+        "move_to_trash" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3634,48 +7673,66 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
             let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f.to_string(),
+                Some(f) => f,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "folder parameter is required",
+                    "error": "Missing 'folder' parameter",
                     "tool": tool_name
                 })
             };
+            let uids = match params.get("uids").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect::<Vec<u32>>(),
+                None => return serde_json::json!({
+                    "success": false,
+                    "error": "Missing 'uids' parameter",
+                    "tool": tool_name
+                })
+            };
+            if uids.is_empty() {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'uids' parameter cannot be empty",
+                    "tool": tool_name
+                });
+            }
 
-            let format = params.get("format").and_then(|v| v.as_str());
-            let fields = params.get("fields").and_then(|v| v.as_str());
-            let limit = params.get("limit").and_then(|v| v.as_u64()).map(|v| v as usize);
-
-            match state.cache_service.db_pool.as_ref() {
-                Some(pool) => {
-                    let exporter = crate::metadata_export::MetadataExporter::new(pool.clone());
-                    match exporter.export(&account_id, &folder, format, fields, limit).await {
-                        Ok(result) => serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "file_path": result.file_path,
-                                "email_count": result.email_count,
-                                "format": result.format,
-                            },
-                            "tool": tool_name
-                        }),
-                        Err(e) => serde_json::json!({
-                            "success": false,
-                            "error": format!("Metadata export failed: {}", e),
-                            "tool": tool_name
-                        })
-                    }
-                }
-                None => serde_json::json!({
+            match email_service.move_to_trash_for_account(folder, &uids, &account_id).await {
+                Ok((Some(trash_folder), outcome)) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "trash_folder": trash_folder,
+                        "uids": outcome.applied,
+                        "count": outcome.applied.len(),
+                        "already_gone": outcome.already_gone
+                    },
+                    "account_id": account_id,
+                    "tool": tool_name
+                }),
+                Ok((None, outcome)) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "trash_folder": null,
+                        "uids": outcome.applied,
+                        "count": outcome.applied.len(),
+                        "already_gone": outcome.already_gone,
+                        "flagged_deleted": true
+                    },
+                    "warning": "No Trash folder was found for this account, so the messages were flagged \\Deleted instead of moved. They stay in the folder until it is expunged and can be recovered with undelete_messages.",
+                    "account_id": account_id,
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": format!("Failed to move messages to Trash: {}", e),
+                    "account_id": account_id,
                     "tool": tool_name
                 })
             }
         }
-        "filter_emails_by_subject" => {
+        "fetch_email_partial" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3684,68 +7741,53 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
-
             let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f.to_string(),
+                Some(f) => f,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "folder parameter is required",
+                    "error": "Missing 'folder' parameter",
                     "tool": tool_name
                 })
             };
-
-            let patterns: Vec<String> = match params.get("subject_patterns").and_then(|v| v.as_array()) {
-                Some(arr) => arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect(),
+            let uid = match params.get("uid").and_then(|v| v.as_u64()) {
+                Some(u) => u as u32,
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "subject_patterns parameter is required (array of strings)",
+                    "error": "Missing 'uid' parameter",
                     "tool": tool_name
                 })
             };
+            let offset = params.get("offset").and_then(|v| v.as_u64()).unwrap_or(0).min(u32::MAX as u64) as u32;
+            // Capped at 1 MiB; larger ranges can be read in several calls
+            let length = params.get("length").and_then(|v| v.as_u64()).unwrap_or(2048).clamp(1, 1_048_576) as u32;
+            let section = params.get("section").and_then(|v| v.as_str()).filter(|s| !s.trim().is_empty());
 
-            let match_mode = params.get("match_mode").and_then(|v| v.as_str());
-            let sender_filter = params.get("sender_filter").and_then(|v| v.as_str());
-            let recipient_filter = params.get("recipient_filter").and_then(|v| v.as_str());
-            let date_after = params.get("date_after").and_then(|v| v.as_str());
-            let date_before = params.get("date_before").and_then(|v| v.as_str());
-            let max_results = params.get("max_results").and_then(|v| v.as_u64()).map(|v| v as usize);
-
-            match state.cache_service.db_pool.as_ref() {
-                Some(pool) => {
-                    let filter = crate::filter_emails::SubjectFilter::new(pool.clone());
-                    match filter.filter(
-                        &account_id, &folder, &patterns, match_mode,
-                        sender_filter, recipient_filter, date_after, date_before, max_results,
-                    ).await {
-                        Ok(result) => serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "account": result.account,
-                                "folder": result.folder,
-                                "patterns_used": result.patterns_used,
-                                "match_mode": result.match_mode,
-                                "total_matched": result.total_matched,
-                                "results": result.results,
-                            },
-                            "tool": tool_name
-                        }),
-                        Err(e) => serde_json::json!({
-                            "success": false,
-                            "error": format!("Subject filter failed: {}", e),
-                            "tool": tool_name
-                        })
-                    }
-                }
-                None => serde_json::json!({
+            match email_service.fetch_email_partial_for_account(folder, uid, section, offset, length, &account_id).await {
+                Ok(partial) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "folder": folder,
+                        "uid": partial.uid,
+                        "section": partial.section,
+                        "offset": partial.offset,
+                        "bytes": partial.data.len(),
+                        "truncated": partial.truncated,
+                        "next_offset": partial.truncated.then(|| partial.offset as usize + partial.data.len()),
+                        "message_size": partial.message_size,
+                        "content": String::from_utf8_lossy(&partial.data)
+                    },
+                    "account_id": account_id,
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": format!("Failed to fetch partial email: {}", e),
+                    "account_id": account_id,
                     "tool": tool_name
                 })
             }
         }
-        "batch_get_synopsis" => {
+        "get_unread_counts" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3754,62 +7796,101 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
+            let max_age_seconds = params.get("max_age_seconds").and_then(|v| v.as_i64()).unwrap_or(300).max(0);
 
-            let folder = match params.get("folder").and_then(|v| v.as_str()) {
-                Some(f) => f.to_string(),
-                None => return serde_json::json!({
+            match email_service.unread_counts_for_account(&account_id, chrono::Duration::seconds(max_age_seconds)).await {
+                Ok(folders) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "account_id": account_id,
+                        "total_unread": folders.values().map(|c| c.unread as u64).sum::<u64>(),
+                        "folders": folders
+                    },
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "folder parameter is required",
+                    "error": format!("Failed to get unread counts: {}", e),
+                    "tool": tool_name
+                })
+            }
+        }
+        "bulk_flag" => {
+            let account_id = match get_account_id_to_use(&params, &state_data).await {
+                Ok(id) => id,
+                Err(e) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to determine account: {}", e),
                     "tool": tool_name
                 })
             };
-
-            let uids: Vec<i64> = match params.get("uids").and_then(|v| v.as_array()) {
-                Some(arr) => arr.iter()
-                    .filter_map(|v| v.as_i64())
-                    .collect(),
+            let groups: Vec<crate::dashboard::services::email::FlagGroup> = match params.get("groups")
+                .cloned()
+                .map(serde_json::from_value)
+            {
+                Some(Ok(groups)) => groups,
+                Some(Err(e)) => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Invalid 'groups' parameter, expected [{{folder, uids}}]: {}", e),
+                    "tool": tool_name
+                }),
                 None => return serde_json::json!({
                     "success": false,
-                    "error": "uids parameter is required (array of integers)",
+                    "error": "Missing 'groups' parameter",
                     "tool": tool_name
                 })
             };
+            if groups.iter().all(|g| g.uids.is_empty()) {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'groups' must name at least one UID",
+                    "tool": tool_name
+                });
+            }
+            let operation = match params.get("operation").and_then(|v| v.as_str()).unwrap_or("add").to_ascii_lowercase().as_str() {
+                "add" => crate::imap::types::FlagOperation::Add,
+                "remove" => crate::imap::types::FlagOperation::Remove,
+                "set" => crate::imap::types::FlagOperation::Set,
+                other => return serde_json::json!({
+                    "success": false,
+                    "error": format!("Invalid 'operation' '{}', expected add, remove or set", other),
+                    "tool": tool_name
+                })
+            };
+            let flags: Vec<String> = match params.get("flags").and_then(|v| v.as_array()) {
+                Some(arr) => arr.iter().filter_map(|v| v.as_str()).map(String::from).collect(),
+                None => vec!["\\Seen".to_string()],
+            };
+            if operation != crate::imap::types::FlagOperation::Set && !flags.iter().any(|f| crate::imap::types::imap_flag_syntax(f).is_some()) {
+                return serde_json::json!({
+                    "success": false,
+                    "error": "'flags' must contain at least one valid flag",
+                    "tool": tool_name
+                });
+            }
 
-            let max_chars = params.get("max_chars_per_synopsis")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as usize);
-
-            match state.cache_service.db_pool.as_ref() {
-                Some(pool) => {
-                    let processor = crate::batch_synopsis::BatchSynopsisProcessor::new(pool.clone());
-                    match processor.process(&account_id, &folder, &uids, max_chars).await {
-                        Ok(result) => serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "account": result.account,
-                                "folder": result.folder,
-                                "requested": result.requested,
-                                "returned": result.returned,
-                                "synopses": result.synopses,
-                                "errors": result.errors,
-                            },
-                            "tool": tool_name
-                        }),
-                        Err(e) => serde_json::json!({
-                            "success": false,
-                            "error": format!("Batch synopsis failed: {}", e),
-                            "tool": tool_name
-                        })
-                    }
+            match email_service.bulk_flag_for_account(&groups, operation, &flags, &account_id).await {
+                Ok(results) => {
+                    let failed = results.iter().filter(|r| !r.success).count();
+                    serde_json::json!({
+                        "success": failed < results.len(),
+                        "data": {
+                            "account_id": account_id,
+                            "updated": results.iter().filter(|r| r.success).map(|r| r.uids.len()).sum::<usize>(),
+                            "failed_folders": failed,
+                            "groups": results
+                        },
+                        "tool": tool_name
+                    })
                 }
-                None => serde_json::json!({
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": format!("Failed to change flags: {}", e),
                     "tool": tool_name
                 })
             }
         }
-        "export_evidence" => {
+        "get_server_capabilities" => {
             let account_id = match get_account_id_to_use(&params, &state_data).await {
                 Ok(id) => id,
                 Err(e) => return serde_json::json!({
@@ -3818,36 +7899,33 @@ pub async fn execute_mcp_tool_inner(
                     "tool": tool_name
                 })
             };
+            let refresh = params.get("refresh").and_then(|v| v.as_bool()).unwrap_or(false);
 
-            let folder = params.get("folder").and_then(|v| v.as_str());
-            let search_query = params.get("search_query").and_then(|v| v.as_str());
-            let output_path = params.get("output_path").and_then(|v| v.as_str());
-
-            match state.cache_service.db_pool.as_ref() {
-                Some(pool) => {
-                    let exporter = crate::evidence_export::EvidenceExporter::new(
-                        state.cache_service.clone(), pool.clone()
-                    );
-                    match exporter.export(&account_id, folder, search_query, output_path).await {
-                        Ok(result) => serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "export_path": result.export_path,
-                                "email_count": result.email_count,
-                                "attachment_count": result.attachment_count,
-                            },
-                            "tool": tool_name
-                        }),
-                        Err(e) => serde_json::json!({
-                            "success": false,
-                            "error": format!("Export failed: {}", e),
-                            "tool": tool_name
-                        })
-                    }
-                }
-                None => serde_json::json!({
+            match email_service.server_capabilities_for_account(&account_id, refresh).await {
+                Ok(caps) => serde_json::json!({
+                    "success": true,
+                    "data": {
+                        "capabilities": caps.names().collect::<Vec<_>>(),
+                        "supports": {
+                            "idle": caps.supports_idle(),
+                            "sort": caps.supports_sort(),
+                            "thread": caps.thread_algorithms(),
+                            "quota": caps.supports_quota(),
+                            "condstore": caps.supports_condstore(),
+                            "qresync": caps.has("QRESYNC"),
+                            "move": caps.supports_move(),
+                            "uidplus": caps.supports_uidplus(),
+                            "namespace": caps.has("NAMESPACE"),
+                            "compress_deflate": caps.has("COMPRESS=DEFLATE")
+                        }
+                    },
+                    "account_id": account_id,
+                    "tool": tool_name
+                }),
+                Err(e) => serde_json::json!({
                     "success": false,
-                    "error": "Database not available",
+                    "error": format!("Failed to get server capabilities: {}", e),
+                    "account_id": account_id,
                     "tool": tool_name
                 })
             }
@@ -3862,9 +7940,347 @@ pub async fn execute_mcp_tool_inner(
         }
     };
 
+    publish_tool_activity(state, tool_name, &params, &result).await;
+
     result
 }
 
+/// Register a running background job in memory and, when available, in the
+/// job table. Returns the new job ID.
+async fn register_job(state: &DashboardState, instruction: String, account_id: &str) -> String {
+    use crate::dashboard::services::jobs::{JobRecord, JobStatus, PersistedJob};
+
+    let job_id = uuid::Uuid::new_v4().to_string();
+    state.jobs.insert(job_id.clone(), JobRecord {
+        job_id: job_id.clone(),
+        status: JobStatus::Running,
+        started_at: std::time::Instant::now(),
+        instruction: Some(instruction.clone()),
+    });
+    if let Some(ref job_persistence) = state.job_persistence {
+        let persisted = PersistedJob::new(job_id.clone(), Some(instruction), Some(account_id.to_string()));
+        if let Err(e) = job_persistence.create_job(&persisted).await {
+            warn!("Failed to persist job {}: {}", job_id, e);
+        }
+    }
+    job_id
+}
+
+/// Save each progress update sent on the returned channel as the job's
+/// checkpoint. The task ends when the sender is dropped.
+fn spawn_progress_checkpoints<T: serde::Serialize + Send + 'static>(
+    state: &DashboardState,
+    job_id: &str,
+) -> (mpsc::UnboundedSender<T>, tokio::task::JoinHandle<()>) {
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel::<T>();
+    let persistence = state.job_persistence.clone();
+    let job_id = job_id.to_string();
+    let task = tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            if let Some(ref job_persistence) = persistence {
+                if let Err(e) = job_persistence.save_checkpoint(&job_id, &serde_json::json!(progress)).await {
+                    warn!("Failed to save progress for job {}: {}", job_id, e);
+                }
+            }
+        }
+    });
+    (progress_tx, task)
+}
+
+/// Record a background job's outcome in memory and in the job table.
+async fn finish_job(state: &DashboardState, job_id: &str, result: Result<serde_json::Value, String>) {
+    use crate::dashboard::services::jobs::JobStatus;
+
+    let final_status = match result {
+        Ok(data) => {
+            if let Some(ref job_persistence) = state.job_persistence {
+                if let Err(e) = job_persistence.complete_job(job_id, &data).await {
+                    warn!("Failed to persist job completion {}: {}", job_id, e);
+                }
+            }
+            JobStatus::Completed(data)
+        }
+        Err(error) => {
+            if let Some(ref job_persistence) = state.job_persistence {
+                if let Err(pe) = job_persistence.fail_job(job_id, &error).await {
+                    warn!("Failed to persist job failure {}: {}", job_id, pe);
+                }
+            }
+            JobStatus::Failed(error)
+        }
+    };
+    state.jobs.entry(job_id.to_string()).and_modify(|record| record.status = final_status);
+}
+
+/// Build an attachment ZIP as a background job. Progress is saved to the
+/// job's checkpoint after each file; the result holds the archive path.
+async fn start_zip_job(
+    state: &DashboardState,
+    pool: sqlx::SqlitePool,
+    account_id: String,
+    message_id: String,
+    zip_path: std::path::PathBuf,
+    config: crate::dashboard::services::attachment_storage::ZipConfig,
+) -> String {
+    use crate::dashboard::services::attachment_storage;
+
+    let job_id = register_job(state, format!("Create attachment ZIP for {}", message_id), &account_id).await;
+
+    let state = state.clone();
+    let job_id_clone = job_id.clone();
+    tokio::spawn(async move {
+        let (progress_tx, progress_task) = spawn_progress_checkpoints(&state, &job_id_clone);
+        let result = attachment_storage::create_zip_archive_with_progress(
+            &pool, &account_id, &message_id, &zip_path, &config, Some(progress_tx),
+        ).await;
+        let _ = progress_task.await;
+
+        let outcome = result
+            .map(|path| serde_json::json!({
+                "zip_path": path.to_string_lossy(),
+                "message_id": message_id,
+                "account_id": account_id,
+            }))
+            .map_err(|e| format!("Failed to create ZIP: {}", e));
+        finish_job(&state, &job_id_clone, outcome).await;
+    });
+
+    job_id
+}
+
+/// Progress of a folder attachment download, saved as the job checkpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+enum FolderZipProgress {
+    Fetching { emails_done: usize, emails_total: usize },
+    Zipping(crate::dashboard::services::attachment_storage::ZipProgress),
+}
+
+/// Download the attachments of `emails` (fetching from IMAP any not yet
+/// stored) and package them into one ZIP with a directory per email, as a
+/// background job. Returns the job ID.
+async fn start_folder_zip_job(
+    state: &DashboardState,
+    pool: sqlx::SqlitePool,
+    account_id: String,
+    folder: String,
+    emails: Vec<crate::dashboard::services::cache::CachedEmail>,
+    zip_path: std::path::PathBuf,
+    config: crate::dashboard::services::attachment_storage::ZipConfig,
+) -> String {
+    use crate::dashboard::services::attachment_storage;
+    use futures_util::stream;
+
+    let job_id = register_job(
+        state,
+        format!("Download attachments of {} emails in {}", emails.len(), folder),
+        &account_id,
+    ).await;
+
+    let state = state.clone();
+    let job_id_clone = job_id.clone();
+    tokio::spawn(async move {
+        let (progress_tx, progress_task) = spawn_progress_checkpoints::<FolderZipProgress>(&state, &job_id_clone);
+        let emails_total = emails.len();
+
+        // Resolve each email to the message ID its attachments are stored under,
+        // fetching from IMAP with bounded concurrency when nothing is stored yet
+        let resolved: Vec<(u32, Option<String>, Result<String, String>)> = stream::iter(emails)
+            .map(|email| {
+                let state = &state;
+                let pool = &pool;
+                let account_id = &account_id;
+                let folder = &folder;
+                async move {
+                    if let Some(message_id) = email.message_id.clone() {
+                        let stored = attachment_storage::get_attachments_metadata(pool, account_id, &message_id).await;
+                        if matches!(stored, Ok(ref atts) if !atts.is_empty()) {
+                            return (email.uid, email.subject, Ok(message_id));
+                        }
+                    }
+                    let outcome = state.email_service
+                        .fetch_email_with_attachments(folder, email.uid, account_id).await
+                        .map(|(fetched, _)| attachment_storage::ensure_message_id(&fetched, account_id))
+                        .map_err(|e| e.to_string());
+                    (email.uid, email.subject, outcome)
+                }
+            })
+            .buffer_unordered(config.concurrency)
+            .enumerate()
+            .map(|(done, item)| {
+                let _ = progress_tx.send(FolderZipProgress::Fetching { emails_done: done + 1, emails_total });
+                item
+            })
+            .collect()
+            .await;
+
+        let mut messages = Vec::new();
+        let mut failures = Vec::new();
+        for (uid, subject, outcome) in resolved {
+            match outcome {
+                Ok(message_id) => {
+                    let subject: String = subject.unwrap_or_default().chars().take(60).collect();
+                    let directory = if subject.trim().is_empty() {
+                        uid.to_string()
+                    } else {
+                        format!("{} - {}", uid, subject.trim())
+                    };
+                    messages.push((directory, message_id));
+                }
+                Err(e) => failures.push(serde_json::json!({"uid": uid, "error": e})),
+            }
+        }
+        messages.sort();
+
+        let (zip_tx, mut zip_rx) = mpsc::unbounded_channel();
+        let forward_tx = progress_tx.clone();
+        let forward = tokio::spawn(async move {
+            while let Some(progress) = zip_rx.recv().await {
+                let _ = forward_tx.send(FolderZipProgress::Zipping(progress));
+            }
+        });
+        let result = attachment_storage::create_multi_zip_archive(
+            &pool, &account_id, &messages, &zip_path, &config, Some(zip_tx),
+        ).await;
+        let _ = forward.await;
+        drop(progress_tx);
+        let _ = progress_task.await;
+
+        let outcome = result
+            .map(|path| serde_json::json!({
+                "zip_path": path.to_string_lossy(),
+                "folder": folder,
+                "account_id": account_id,
+                "emails_included": messages.len(),
+                "failed": failures,
+            }))
+            .map_err(|e| format!("Failed to create ZIP: {}", e));
+        finish_job(&state, &job_id_clone, outcome).await;
+    });
+
+    job_id
+}
+
+/// Compare one account's cached folder counts with a live STATUS of each
+/// folder. Failures end up in the result rather than aborting, so one broken
+/// account doesn't hide the others.
+async fn account_sync_lag(
+    state: &DashboardState,
+    account_id: &str,
+    folder: Option<&str>,
+    now: chrono::DateTime<chrono::Utc>,
+    stale_after: chrono::Duration,
+) -> crate::dashboard::services::cache::AccountLag {
+    use crate::dashboard::services::cache::{AccountLag, FolderLag};
+
+    let freshness = match state.cache_service.get_folder_freshness(account_id, folder).await {
+        Ok(freshness) => freshness,
+        Err(e) => return AccountLag::summarize(account_id, Vec::new(), Some(format!("Failed to read cache: {}", e)), now, stale_after),
+    };
+    let unread = state.cache_service.get_unread_counts_for_account(account_id).await.unwrap_or_default();
+
+    let mut folders: Vec<FolderLag> = freshness.into_iter()
+        .map(|f| FolderLag {
+            cached_unseen: unread.get(&f.folder).copied().unwrap_or(0),
+            folder: f.folder,
+            cached_messages: f.cached_count,
+            last_sync: f.last_sync,
+            error: f.sync_error.map(|e| format!("Last sync failed: {}", e)),
+            ..Default::default()
+        })
+        .collect();
+
+    let mut error = None;
+    if !folders.is_empty() {
+        let names: Vec<String> = folders.iter().map(|f| f.folder.clone()).collect();
+        match state.email_service.folder_statuses_for_account(&names, account_id).await {
+            Ok(statuses) => {
+                for status in statuses {
+                    if let Some(entry) = folders.iter_mut().find(|f| f.folder == status.name) {
+                        match status.error {
+                            Some(e) => entry.error = Some(format!("STATUS failed: {}", e)),
+                            None => {
+                                entry.server_messages = status.messages.map(i64::from);
+                                entry.server_unseen = status.unseen.map(i64::from);
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => error = Some(format!("Live STATUS failed: {}", e)),
+        }
+    }
+
+    AccountLag::summarize(account_id, folders, error, now, stale_after)
+}
+
+/// Publish a mailbox activity event for a successful state-changing tool call
+/// so it shows up in the account's persisted activity feed.
+async fn publish_tool_activity(
+    state: &DashboardState,
+    tool_name: &str,
+    params: &serde_json::Value,
+    result: &serde_json::Value,
+) {
+    use crate::dashboard::services::events::MailboxAction;
+
+    if result.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        return;
+    }
+    // A move or delete that found the message already gone changed nothing
+    if result.pointer("/data/already_gone").and_then(|v| v.as_bool()) == Some(true) {
+        return;
+    }
+
+    let str_param = |key: &str| params.get(key).and_then(|v| v.as_str()).map(String::from);
+    // Prefer the UIDs the tool reports it acted on over the requested ones
+    let uids: Vec<u32> = match result.pointer("/data/uids").or_else(|| params.get("uids")).and_then(|v| v.as_array()) {
+        Some(arr) => arr.iter().filter_map(|v| v.as_u64()).map(|v| v as u32).collect(),
+        None => params.get("uid").and_then(|v| v.as_u64()).map(|v| vec![v as u32]).unwrap_or_default(),
+    };
+
+    let (account_id, action, folder, detail) = match tool_name {
+        "atomic_move_message" | "atomic_batch_move" => (
+            str_param("account_id"),
+            MailboxAction::Moved,
+            str_param("from_folder"),
+            str_param("to_folder").map(|to| format!("to {}", to)),
+        ),
+        "transfer_messages" => (
+            str_param("source_account_id"),
+            MailboxAction::Moved,
+            str_param("source_folder"),
+            match (str_param("dest_account_id"), str_param("dest_folder")) {
+                (Some(account), Some(folder)) => Some(format!("to {} in {}", folder, account)),
+                _ => None,
+            },
+        ),
+        "mark_as_deleted" | "delete_messages" | "expunge" => (
+            str_param("account_id"),
+            MailboxAction::Deleted,
+            str_param("folder"),
+            Some(tool_name.to_string()),
+        ),
+        "empty_folder" => (
+            str_param("account_id"),
+            MailboxAction::Deleted,
+            result.pointer("/data/folder").and_then(|v| v.as_str()).map(String::from),
+            Some("emptied".to_string()),
+        ),
+        "flag_messages" | "unflag_messages" => (
+            str_param("account_id"),
+            MailboxAction::Flagged,
+            str_param("folder"),
+            Some(if tool_name == "flag_messages" { "flagged" } else { "unflagged" }.to_string()),
+        ),
+        _ => return,
+    };
+
+    if let Some(account_id) = account_id {
+        state.event_bus.publish_mailbox_activity(&account_id, action, folder, uids, detail, "mcp").await;
+    }
+}
+
 // Query parameter for MCP tool variant
 #[derive(Debug, Deserialize)]
 pub struct McpExecuteQuery {
@@ -3872,6 +8288,11 @@ pub struct McpExecuteQuery {
     pub variant: String,
 }
 
+// Handler for MCP tool result deduplication counters
+pub async fn get_mcp_dedup_stats() -> Result<impl Responder, ApiError> {
+    Ok(HttpResponse::Ok().json(crate::dashboard::services::tool_dedup::stats()))
+}
+
 // HTTP Handler for executing MCP tools - wraps execute_mcp_tool_inner
 pub async fn execute_mcp_tool(
     state: web::Data<DashboardState>,
@@ -3908,14 +8329,18 @@ pub async fn stream_chatbot(
 
     let (tx, rx) = mpsc::channel(100);
     let ai_service = state.ai_service.clone();
-    let query = req.into_inner();
+    let mut query = req.into_inner();
+    // Fix the id up front so the start event and the reply agree on it
+    let conversation_id = query.conversation_id
+        .get_or_insert_with(|| uuid::Uuid::new_v4().to_string())
+        .clone();
 
     // Spawn task to process query and stream response
     tokio::spawn(async move {
         // First send a "start" event
         let start_event = sse::Data::new(serde_json::json!({
             "type": "start",
-            "conversation_id": query.conversation_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+            "conversation_id": conversation_id
         }).to_string())
             .event("chatbot");
 
@@ -3923,25 +8348,37 @@ pub async fn stream_chatbot(
             return;
         }
 
-        // Process the query
-        match ai_service.process_query(query).await {
-            Ok(response) => {
-                // For now, send the full response at once
-                // TODO: Implement actual token-by-token streaming when provider supports it
+        // Forward reply text as the provider generates it, while the query
+        // (including any tool calls) is processed
+        let (chunk_tx, mut chunk_rx) = mpsc::channel::<String>(100);
+        let events = &tx;
+        // Owns the receiver, so chunks are dropped once the client is gone
+        // instead of filling the channel
+        let forward = async move {
+            while let Some(text) = chunk_rx.recv().await {
                 let content_event = sse::Data::new(serde_json::json!({
                     "type": "content",
-                    "text": response.text,
-                    "conversation_id": response.conversation_id,
-                    "email_data": response.email_data,
-                    "followup_suggestions": response.followup_suggestions
+                    "text": text,
+                    "conversation_id": conversation_id
                 }).to_string())
                     .event("chatbot");
+                if events.send(Ok(sse::Event::Data(content_event))).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let (result, _) = tokio::join!(ai_service.process_query_stream(query, chunk_tx), forward);
 
-                let _ = tx.send(Ok(sse::Event::Data(content_event))).await;
-
-                // Send completion event
+        match result {
+            Ok(response) => {
+                // Completion carries the full answer for clients that want to
+                // replace the streamed text
                 let complete_event = sse::Data::new(serde_json::json!({
-                    "type": "complete"
+                    "type": "complete",
+                    "text": response.text,
+                    "conversation_id": response.conversation_id,
+                    "email_data": response.email_data,
+                    "followup_suggestions": response.followup_suggestions
                 }).to_string())
                     .event("chatbot");
 
@@ -4125,6 +8562,9 @@ pub async fn get_available_event_types() -> Result<impl Responder, ApiError> {
         EventType::ClientDisconnected.to_string(),
         EventType::SystemAlert.to_string(),
         EventType::ConfigurationUpdated.to_string(),
+        EventType::NewMail.to_string(),
+        EventType::SyncCompleted.to_string(),
+        EventType::SyncFailed.to_string(),
         EventType::DashboardEvent.to_string(),
     ];
 
@@ -4137,6 +8577,9 @@ pub async fn get_available_event_types() -> Result<impl Responder, ApiError> {
             "client_disconnected": "Notifications when clients disconnect",
             "system_alert": "System alerts and warnings",
             "configuration_updated": "Configuration change notifications",
+            "new_mail": "New messages found by a sync",
+            "sync_completed": "An account finished syncing",
+            "sync_failed": "An account failed to sync",
             "dashboard_event": "Generic dashboard events"
         }
     })))
@@ -4186,6 +8629,36 @@ pub async fn get_ai_providers(
     Ok(HttpResponse::Ok().json(response))
 }
 
+#[derive(Debug, Serialize)]
+pub struct AiStatusResponse {
+    pub current_provider: Option<String>,
+    pub current_model: Option<String>,
+    pub concurrency: Vec<crate::dashboard::services::ai::provider_manager::ProviderConcurrency>,
+}
+
+// Handler for AI status: current selection and per-provider in-flight requests
+pub async fn get_ai_status(
+    state: web::Data<DashboardState>,
+) -> Result<impl Responder, ApiError> {
+    debug!("Handling GET /api/dashboard/ai/status");
+
+    let response = AiStatusResponse {
+        current_provider: state.ai_service.get_current_provider_name().await,
+        current_model: state.ai_service.get_current_model_name().await,
+        concurrency: state.ai_service.provider_concurrency().await,
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handler for cumulative AI token usage and estimated cost
+pub async fn get_ai_usage(
+    state: web::Data<DashboardState>,
+) -> Result<impl Responder, ApiError> {
+    debug!("Handling GET /api/dashboard/ai/usage");
+    Ok(HttpResponse::Ok().json(state.ai_service.usage_report()))
+}
+
 // Handler for setting the current AI provider
 pub async fn set_ai_provider(
     req: web::Json<SetProviderRequest>,
@@ -4695,6 +9168,9 @@ pub async fn trigger_email_sync(
     if force {
         cmd.arg("--force");
     }
+    if let Some(passphrase) = state.config.cache_encryption.as_ref().and_then(|c| c.passphrase.as_ref()) {
+        cmd.env("CACHE_ENCRYPTION_PASSPHRASE", passphrase);
+    }
 
     // Spawn the sync process and wait for it to complete (or detect already running)
     match cmd.spawn() {
@@ -4785,18 +9261,7 @@ pub async fn get_sync_status(
     query: web::Query<EmailQueryParams>,
 ) -> Result<impl Responder, ApiError> {
     // Get account ID from query parameters or use default
-    let account_id = match query.account_id.as_ref() {
-        Some(id) => id.clone(),
-        None => {
-            // Get default account if no account_id provided
-            let account_service = state.account_service.lock().await;
-            match account_service.get_default_account().await {
-                Ok(Some(account)) => account.email_address,
-                Ok(None) => return Err(ApiError::NotFound("No default account configured".to_string())),
-                Err(e) => return Err(ApiError::InternalError(format!("Failed to get default account: {}", e))),
-            }
-        }
-    };
+    let account_id = resolve_account_or_default(query.account_id.as_deref(), &state).await?;
 
     let account_email = match validate_account_exists(&account_id, &state).await {
         Ok(id) => id,
@@ -4841,12 +9306,102 @@ pub async fn get_sync_status(
     }
 }
 
+/// Aggregate cache totals, sync recency and connection health across every
+/// account. Shared by the `all_accounts_summary` tool and
+/// `GET /api/dashboard/accounts/summary`.
+async fn build_all_accounts_summary(state: &DashboardState) -> Result<serde_json::Value, ApiError> {
+    let accounts = {
+        let account_service = state.account_service.lock().await;
+        account_service.list_accounts().await
+            .map_err(|e| ApiError::InternalError(format!("Failed to list accounts: {}", e)))?
+    };
+
+    let mut summaries: std::collections::HashMap<String, crate::dashboard::services::cache::AccountCacheSummary> = state.cache_service
+        .get_account_cache_summaries()
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to summarise cache: {}", e)))?
+        .into_iter()
+        .map(|summary| (summary.account_id.clone(), summary))
+        .collect();
+
+    let mut total_messages = 0i64;
+    let mut total_unread = 0i64;
+    let mut total_size = 0i64;
+    let mut per_account = Vec::with_capacity(accounts.len());
+
+    for account in accounts {
+        let summary = summaries.remove(&account.email_address);
+        let sync_status = match &summary {
+            None => "never_synced",
+            Some(s) if s.last_sync.is_none() => "never_synced",
+            Some(s) if s.folders_syncing > 0 => "syncing",
+            Some(s) if s.folders_in_error > 0 => "error",
+            Some(_) => "idle",
+        };
+        if let Some(s) = &summary {
+            total_messages += s.total_messages;
+            total_unread += s.unread_messages;
+            total_size += s.size_bytes;
+        }
+
+        per_account.push(serde_json::json!({
+            "account_id": account.email_address,
+            "display_name": account.display_name,
+            "is_active": account.is_active,
+            "is_default": account.is_default,
+            "folder_count": summary.as_ref().map_or(0, |s| s.folder_count),
+            "total_messages": summary.as_ref().map_or(0, |s| s.total_messages),
+            "unread_messages": summary.as_ref().map_or(0, |s| s.unread_messages),
+            "size_bytes": summary.as_ref().map_or(0, |s| s.size_bytes),
+            "sync_status": sync_status,
+            "last_sync": summary.as_ref().and_then(|s| s.last_sync),
+            "oldest_folder_sync": summary.as_ref().and_then(|s| s.oldest_folder_sync),
+            "folders_syncing": summary.as_ref().map_or(0, |s| s.folders_syncing),
+            "folders_in_error": summary.as_ref().map_or(0, |s| s.folders_in_error),
+            "connection_status": account.connection_status,
+        }));
+    }
+
+    let pool = state.connection_pool.stats().await;
+
+    Ok(serde_json::json!({
+        "account_count": per_account.len(),
+        "total_messages": total_messages,
+        "total_unread": total_unread,
+        "total_size_bytes": total_size,
+        "accounts": per_account,
+        "connection_pool": {
+            "active_connections": pool.active_connections,
+            "available_connections": pool.available_connections,
+            "total_connections": pool.total_connections,
+            "max_connections": pool.max_connections,
+            "acquire_timeouts": pool.acquire_timeouts,
+            "creation_failures": pool.creation_failures,
+            "reserved_interactive": pool.reserved_interactive,
+            "background_waits": pool.background_waits,
+            "warmed": state.connection_pool.is_warmed()
+        },
+        "generated_at": chrono::Utc::now().to_rfc3339()
+    }))
+}
+
+/// Totals across all accounts for a multi-account home screen
+pub async fn get_all_accounts_summary(
+    state: Data<DashboardState>,
+) -> Result<impl Responder, ApiError> {
+    debug!("Handling GET /api/dashboard/accounts/summary");
+    let summary = build_all_accounts_summary(&state).await?;
+    Ok(HttpResponse::Ok().json(summary))
+}
+
 /// Get cached emails from the database
 #[derive(serde::Deserialize)]
 pub struct EmailQueryParams {
     folder: Option<String>,
     limit: Option<usize>,
     offset: Option<usize>,
+    /// `next_cursor` of the previous page; takes precedence over offset
+    cursor: Option<String>,
     account_id: Option<String>,
 }
 
@@ -4855,18 +9410,7 @@ pub async fn list_folders(
     query: web::Query<EmailQueryParams>,
 ) -> Result<impl Responder, ApiError> {
     // Get account ID from query parameters or use default
-    let account_id = match query.account_id.as_ref() {
-        Some(id) => id.clone(),
-        None => {
-            // Get default account if no account_id provided
-            let account_service = state.account_service.lock().await;
-            match account_service.get_default_account().await {
-                Ok(Some(account)) => account.email_address,
-                Ok(None) => return Err(ApiError::NotFound("No default account configured".to_string())),
-                Err(e) => return Err(ApiError::InternalError(format!("Failed to get default account: {}", e))),
-            }
-        }
-    };
+    let account_id = resolve_account_or_default(query.account_id.as_deref(), &state).await?;
 
     info!("Listing folders for account: {}", account_id);
 
@@ -4891,17 +9435,7 @@ pub async fn list_cached_folders(
     state: Data<DashboardState>,
     query: web::Query<EmailQueryParams>,
 ) -> Result<impl Responder, ApiError> {
-    let account_id = match query.account_id.as_ref() {
-        Some(id) => id.clone(),
-        None => {
-            let account_service = state.account_service.lock().await;
-            match account_service.get_default_account().await {
-                Ok(Some(account)) => account.email_address,
-                Ok(None) => return Err(ApiError::NotFound("No default account configured".to_string())),
-                Err(e) => return Err(ApiError::InternalError(format!("Failed to get default account: {}", e))),
-            }
-        }
-    };
+    let account_id = resolve_account_or_default(query.account_id.as_deref(), &state).await?;
 
     info!("Listing cached folders for account: {}", account_id);
 
@@ -4929,20 +9463,14 @@ pub async fn get_cached_emails(
     let folder = query.folder.as_deref().unwrap_or("INBOX");
     let limit = query.limit.unwrap_or(50);
     let offset = query.offset.unwrap_or(0);
+    let start = match query.cursor.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        Some(c) => PageStart::After(EmailCursor::decode(c)
+            .ok_or_else(|| ApiError::BadRequest(format!("Invalid cursor '{}'", c)))?),
+        None => PageStart::Offset(offset),
+    };
 
     // Get account ID from query parameters or use default
-    let account_id = match query.account_id.as_ref() {
-        Some(id) => id.clone(),
-        None => {
-            // Get default account if no account_id provided
-            let account_service = state.account_service.lock().await;
-            match account_service.get_default_account().await {
-                Ok(Some(account)) => account.email_address,
-                Ok(None) => return Err(ApiError::NotFound("No default account configured".to_string())),
-                Err(e) => return Err(ApiError::InternalError(format!("Failed to get default account: {}", e))),
-            }
-        }
-    };
+    let account_id = resolve_account_or_default(query.account_id.as_deref(), &state).await?;
 
     let account_email = match validate_account_exists(&account_id, &state).await {
         Ok(id) => id,
@@ -4956,8 +9484,8 @@ pub async fn get_cached_emails(
           folder, account_id, limit, offset);
 
     // Dashboard UI needs full content for display
-    match state.cache_service.get_cached_emails_for_account(folder, &account_email, limit, offset, false).await {
-        Ok(emails) => {
+    match state.cache_service.get_cached_emails_page(folder, &account_email, limit, start, false).await {
+        Ok((emails, next_cursor)) => {
             // Get total count for this folder and account
             let total_count = state.cache_service.count_emails_in_folder_for_account(folder, &account_email).await
                 .unwrap_or(0);
@@ -4967,6 +9495,7 @@ pub async fn get_cached_emails(
                 "emails": emails,
                 "folder": folder,
                 "count": total_count,
+                "next_cursor": next_cursor.map(|c| c.encode()),
             })))
         }
         Err(e) => {
@@ -4976,10 +9505,118 @@ pub async fn get_cached_emails(
     }
 }
 
+#[derive(Deserialize)]
+pub struct RenderEmailQueryParams {
+    folder: Option<String>,
+    uid: u32,
+    account_id: Option<String>,
+}
+
+/// Render a cached email's body as sanitized HTML for display. Plain-text
+/// messages are escaped and wrapped in `<pre>`.
+pub async fn render_email(
+    state: Data<DashboardState>,
+    query: web::Query<RenderEmailQueryParams>,
+) -> Result<impl Responder, ApiError> {
+    use crate::dashboard::services::html_sanitizer::{self, HtmlSanitizer};
+
+    let folder = query.folder.as_deref().unwrap_or("INBOX");
+    let account_id = resolve_account_or_default(query.account_id.as_deref(), &state).await?;
+    let account_email = validate_account_exists(&account_id, &state).await?;
+
+    let email = state.cache_service.get_cached_email(folder, query.uid, &account_email).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to read cached email: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("Email {} not found in {}", query.uid, folder)))?;
+
+    let sanitizer = HtmlSanitizer::global();
+    let rendered = match (&email.body_html, &email.body_text) {
+        (Some(html), _) => sanitizer.sanitize(html, Some(&account_email)),
+        (None, text) => {
+            let mut rendered = sanitizer.sanitize("", Some(&account_email));
+            rendered.html = html_sanitizer::text_to_html(text.as_deref().unwrap_or(""));
+            rendered
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "folder": folder,
+        "uid": email.uid,
+        "subject": email.subject,
+        "html": rendered.html,
+        "remote_images_blocked": rendered.remote_images_blocked,
+        "policy": rendered.policy,
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct RawEmailQueryParams {
+    account_id: Option<String>,
+}
+
+/// ApiError for a failed raw fetch; a UID the server doesn't have is a 404.
+fn raw_fetch_error(folder: &str, uid: u32, error: crate::dashboard::services::email::EmailServiceError) -> ApiError {
+    use crate::dashboard::services::email::EmailServiceError;
+    use crate::imap::error::ImapError;
+
+    match error {
+        EmailServiceError::ImapError(ImapError::EmailNotFound(_) | ImapError::MissingData(_)) => {
+            ApiError::NotFound(format!("Email {} not found in {}", uid, folder))
+        }
+        e => ApiError::InternalError(format!("Failed to fetch email {} from {}: {}", uid, folder, e)),
+    }
+}
+
+/// GET /api/emails/{folder}/{uid}/raw: the original RFC822 message, fetched
+/// from the server rather than the cache so every byte and header is as
+/// received, and streamed to the client as it arrives. Folder names
+/// containing `/` must be percent-encoded.
+pub async fn get_raw_email(
+    state: Data<DashboardState>,
+    path: web::Path<(String, u32)>,
+    query: web::Query<RawEmailQueryParams>,
+) -> Result<impl Responder, ApiError> {
+    let (folder, uid) = path.into_inner();
+    let account_id = resolve_account_or_default(query.account_id.as_deref(), &state).await?;
+    let account_email = validate_account_exists(&account_id, &state).await?;
+
+    let (size, chunks) = state.email_service.stream_raw_message_for_account(&folder, uid, &account_email).await
+        .map_err(|e| raw_fetch_error(&folder, uid, e))?;
+
+    info!("Serving raw message {} from {} ({} bytes)", uid, folder, size);
+    Ok(HttpResponse::Ok()
+        .content_type("message/rfc822")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.eml\"", uid)))
+        .no_chunking(size)
+        .streaming(chunks.map(|chunk| chunk.map(web::Bytes::from))))
+}
+
+/// HEAD /api/emails/{folder}/{uid}/raw: the message's RFC822.SIZE as
+/// Content-Length, without downloading it.
+pub async fn head_raw_email(
+    state: Data<DashboardState>,
+    path: web::Path<(String, u32)>,
+    query: web::Query<RawEmailQueryParams>,
+) -> Result<impl Responder, ApiError> {
+    let (folder, uid) = path.into_inner();
+    let account_id = resolve_account_or_default(query.account_id.as_deref(), &state).await?;
+    let account_email = validate_account_exists(&account_id, &state).await?;
+
+    let size = state.email_service.message_size_for_account(&folder, uid, &account_email).await
+        .map_err(|e| raw_fetch_error(&folder, uid, e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("message/rfc822")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.eml\"", uid)))
+        .no_chunking(size)
+        .finish())
+}
+
 /// Send an email via SMTP
 #[derive(serde::Deserialize)]
 pub struct SendEmailQueryParams {
     account_email: Option<String>,
+    /// RFC 3339 time to send at; omitted or past means send now
+    schedule: Option<String>,
 }
 
 pub async fn send_email(
@@ -4987,7 +9624,7 @@ pub async fn send_email(
     query: web::Query<SendEmailQueryParams>,
     body: web::Json<crate::dashboard::services::SendEmailRequest>,
 ) -> Result<impl Responder, ApiError> {
-    use lettre::{Message, message::{header::ContentType, Mailbox, MultiPart, SinglePart, header}};
+    use lettre::{Message, message::Mailbox};
     use chrono::Utc;
 
     // REQUIRE account_email parameter - do NOT fall back to default account
@@ -4996,6 +9633,14 @@ pub async fn send_email(
         .ok_or_else(|| ApiError::BadRequest("account_email query parameter is required".to_string()))?
         .clone();
 
+    let scheduled_at = query.schedule.as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| chrono::DateTime::parse_from_rfc3339(s.trim())
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| ApiError::BadRequest(format!("Invalid schedule '{}', expected an RFC 3339 time: {}", s, e))))
+        .transpose()?
+        .filter(|t| *t > Utc::now());
+
     info!("Queueing email from account: {}", account_email);
 
     let request = body.into_inner();
@@ -5004,6 +9649,7 @@ pub async fn send_email(
     let account_service = state.account_service.lock().await;
     let account = account_service.get_account(&account_email).await
         .map_err(|e| ApiError::InternalError(format!("Account not found: {}", e)))?;
+    let default_reply_to = account_service.get_default_reply_to(&account_email).await.ok().flatten();
     drop(account_service);
 
     // Build from address with properly quoted display name
@@ -5022,6 +9668,10 @@ pub async fn send_email(
 
     // Build email message
     let mut email_builder = Message::builder().from(from_mailbox).subject(&request.subject);
+    if let Some(reply_to) = &default_reply_to {
+        email_builder = email_builder.reply_to(reply_to.parse()
+            .map_err(|e| ApiError::InternalError(format!("Invalid account reply_to address {}: {}", reply_to, e)))?);
+    }
 
     // Add recipients
     for to_addr in &request.to {
@@ -5041,17 +9691,13 @@ pub async fn send_email(
         }
     }
 
-    // Build multipart body
-    let email = if let Some(html_body) = &request.body_html {
-        email_builder.multipart(
-            MultiPart::alternative()
-                .singlepart(SinglePart::builder().header(header::ContentType::TEXT_PLAIN).body(request.body.clone()))
-                .singlepart(SinglePart::builder().header(header::ContentType::TEXT_HTML).body(html_body.clone()))
-        ).map_err(|e| ApiError::InternalError(format!("Failed to build email: {}", e)))?
-    } else {
-        email_builder.header(ContentType::TEXT_PLAIN).body(request.body.clone())
-            .map_err(|e| ApiError::InternalError(format!("Failed to build email: {}", e)))?
-    };
+    // Build the body, with any attachments
+    let email = crate::dashboard::services::smtp::build_message(email_builder, &request).await
+        .map_err(|e| match e {
+            crate::dashboard::services::SmtpError::AttachmentError(_)
+            | crate::dashboard::services::SmtpError::AttachmentsTooLarge { .. } => ApiError::BadRequest(e.to_string()),
+            other => ApiError::InternalError(format!("Failed to build email: {}", other)),
+        })?;
 
     // Get message ID and raw bytes
     let message_id = email.headers().get_raw("Message-ID").map(|v| v.to_string());
@@ -5077,6 +9723,7 @@ pub async fn send_email(
         max_retries: 3,
         last_error: None,
         created_at: Utc::now(),
+        scheduled_at,
         smtp_sent_at: None,
         last_retry_at: None,
         completed_at: None,
@@ -5087,10 +9734,14 @@ pub async fn send_email(
         Ok(queue_id) => {
             info!("Email queued successfully with ID: {} (will be sent asynchronously)", queue_id);
 
+            let message = match scheduled_at {
+                Some(at) => format!("Email scheduled (queue ID: {}). It will be sent at {}.", queue_id, at.to_rfc3339()),
+                None => format!("Email queued successfully (queue ID: {}). Background worker will send it shortly.", queue_id),
+            };
             let response = crate::dashboard::services::SendEmailResponse {
                 success: true,
                 message_id,
-                message: format!("Email queued successfully (queue ID: {}). Background worker will send it shortly.", queue_id),
+                message,
             };
 
             Ok(HttpResponse::Ok().json(response))
@@ -5102,6 +9753,56 @@ pub async fn send_email(
     }
 }
 
+#[derive(serde::Deserialize)]
+pub struct ListOutboxQuery {
+    account_email: Option<String>,
+    /// Also list sent, failed and cancelled items
+    #[serde(default)]
+    include_finished: bool,
+    limit: Option<i64>,
+}
+
+/// Queued and scheduled outgoing messages with their send times
+pub async fn list_outbox(
+    state: Data<DashboardState>,
+    query: web::Query<ListOutboxQuery>,
+) -> Result<impl Responder, ApiError> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let items = state.outbox_queue_service
+        .list(query.account_email.as_deref(), query.include_finished, limit)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to list outbox: {}", e)))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "count": items.len(),
+        "items": items,
+    })))
+}
+
+/// Cancel a queued or scheduled message before the worker starts sending it
+pub async fn cancel_outbox_item(
+    state: Data<DashboardState>,
+    path: web::Path<i64>,
+) -> Result<impl Responder, ApiError> {
+    use crate::dashboard::services::CancelOutcome;
+
+    let queue_id = path.into_inner();
+    let outcome = state.outbox_queue_service.cancel(queue_id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to cancel outbox item: {}", e)))?;
+
+    match outcome {
+        CancelOutcome::Cancelled => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "queue_id": queue_id,
+            "status": "cancelled",
+        }))),
+        CancelOutcome::NotFound => Err(ApiError::NotFound(format!("Outbox item {} not found", queue_id))),
+        CancelOutcome::NotCancellable(status) => Err(ApiError::Conflict(format!(
+            "Outbox item {} is {} and can no longer be cancelled", queue_id, status.as_str()
+        ))),
+    }
+}
+
 /// Delete email(s) from a folder
 #[derive(serde::Deserialize)]
 pub struct DeleteEmailRequest {
@@ -5153,6 +9854,15 @@ pub async fn delete_email(
         warn!("Failed to remove deleted emails from cache: {}", e);
     }
 
+    state.event_bus.publish_mailbox_activity(
+        &request.account_email,
+        crate::dashboard::services::events::MailboxAction::Deleted,
+        Some(request.folder.clone()),
+        request.uids.clone(),
+        None,
+        "dashboard",
+    ).await;
+
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
         "deleted_count": request.uids.len(),
@@ -32,9 +32,12 @@ pub fn configure_health_routes(cfg: &mut web::ServiceConfig) {
                 .route("/report", web::get().to(health_report))
                 .route("/metrics", web::get().to(health_metrics))
         )
-        // Legacy endpoints for compatibility
+        // Orchestrator probes: /healthz is liveness (always 200 while the process
+        // runs), /readyz is readiness (503 until startup gates pass)
         .route("/healthz", web::get().to(liveness))
-        .route("/readyz", web::get().to(readiness));
+        .route("/readyz", web::get().to(readiness))
+        // Prometheus scrape target
+        .route("/metrics", web::get().to(prometheus_metrics));
 }
 
 // Liveness probe endpoint - returns 200 if service is alive
@@ -52,20 +55,21 @@ pub async fn liveness(
     Ok(HttpResponse::Ok().json(response))
 }
 
-// Readiness probe endpoint - returns 200 if service is ready to handle requests
+// Readiness probe endpoint - returns 200 only once startup gates (migrations,
+// account connectivity, pool warm-up) have passed and critical components are healthy
 pub async fn readiness(
     state: web::Data<DashboardState>,
 ) -> Result<HttpResponse> {
     debug!("Readiness check requested");
 
     if let Some(health_service) = &state.health_service {
-        let is_ready = health_service.readiness().await;
+        let report = health_service.readiness_report().await;
 
-        if is_ready {
+        if report.ready {
             let response = HealthCheckResponse {
                 status: "ready".to_string(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
-                details: None,
+                details: Some(serde_json::json!({ "checks": report.checks })),
             };
             Ok(HttpResponse::Ok().json(response))
         } else {
@@ -73,7 +77,8 @@ pub async fn readiness(
                 status: "not_ready".to_string(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
                 details: Some(serde_json::json!({
-                    "message": "One or more critical components are unhealthy"
+                    "message": format!("Waiting on: {}", report.failing().join(", ")),
+                    "checks": report.checks,
                 })),
             };
             Ok(HttpResponse::ServiceUnavailable().json(response))
@@ -120,7 +125,11 @@ pub async fn health_metrics(
 
     if let Some(health_service) = &state.health_service {
         let resources = health_service.get_resource_health().await;
-        Ok(HttpResponse::Ok().json(resources))
+        let mut body = serde_json::to_value(&resources).unwrap_or_default();
+        if let Some(stats) = job_table_stats(&state).await {
+            body["background_jobs"] = serde_json::json!(stats);
+        }
+        Ok(HttpResponse::Ok().json(body))
     } else {
         Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
             "error": "Health monitoring service not available"
@@ -128,18 +137,31 @@ pub async fn health_metrics(
     }
 }
 
-// Prometheus-compatible metrics endpoint (future enhancement)
+// Size of the persisted jobs table, if job persistence is enabled
+async fn job_table_stats(state: &DashboardState) -> Option<crate::dashboard::services::jobs::JobTableStats> {
+    let persistence = state.job_persistence.as_ref()?;
+    match persistence.table_stats().await {
+        Ok(stats) => Some(stats),
+        Err(e) => {
+            debug!("Failed to read job table stats: {}", e);
+            None
+        }
+    }
+}
+
+// Prometheus metrics endpoint: service metrics (pools, sync lag, outbox,
+// AI requests), plus resource and component health when monitoring is on
 pub async fn prometheus_metrics(
     state: web::Data<DashboardState>,
 ) -> Result<HttpResponse> {
     debug!("Prometheus metrics requested");
 
+    // Format metrics in Prometheus text format
+    let mut metrics = state.metrics_service.render_prometheus().await;
+
     if let Some(health_service) = &state.health_service {
         let resources = health_service.get_resource_health().await;
 
-        // Format metrics in Prometheus text format
-        let mut metrics = String::new();
-
         // System metrics
         metrics.push_str(&format!("# HELP rustymail_cpu_usage CPU usage percentage\n"));
         metrics.push_str(&format!("# TYPE rustymail_cpu_usage gauge\n"));
@@ -181,12 +203,18 @@ pub async fn prometheus_metrics(
             metrics.push_str(&format!("rustymail_component_health{{component=\"{}\"}} {}\n", name, value));
         }
 
-        Ok(HttpResponse::Ok()
-            .content_type("text/plain; version=0.0.4")
-            .body(metrics))
-    } else {
-        Ok(HttpResponse::ServiceUnavailable().body("# Health monitoring service not available\n"))
+        if let Some(stats) = job_table_stats(&state).await {
+            metrics.push_str("# HELP rustymail_background_jobs Rows in the background_jobs table\n");
+            metrics.push_str("# TYPE rustymail_background_jobs gauge\n");
+            for (status, count) in &stats.by_status {
+                metrics.push_str(&format!("rustymail_background_jobs{{status=\"{}\"}} {}\n", status, count));
+            }
+        }
     }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics))
 }
 
 #[cfg(test)]
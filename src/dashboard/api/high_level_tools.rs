@@ -6,6 +6,7 @@ use crate::dashboard::DashboardState;
 use serde_json::{json, Value};
 use log::{debug, error, warn};
 use crate::dashboard::services::jobs::{JobRecord, JobStatus};
+use crate::dashboard::services::SendEmailRequest;
 use uuid::Uuid;
 
 /// Get high-level MCP tools in JSON-RPC format
@@ -49,6 +50,11 @@ pub fn get_mcp_high_level_tools_jsonrpc_format() -> Vec<Value> {
                         "type": "string",
                         "description": "Optional instructions for the reply (e.g., 'polite decline', 'confirm meeting')"
                     },
+                    "reply_mode": {
+                        "type": "string",
+                        "enum": ["sender", "all", "list"],
+                        "description": "Optional. 'sender' (default) replies to Reply-To, or From when absent; 'all' also copies the other recipients; 'list' replies to the mailing list's List-Post address"
+                    },
                     "account_id": {
                         "type": "string",
                         "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
@@ -126,6 +132,10 @@ pub fn get_mcp_high_level_tools_jsonrpc_format() -> Vec<Value> {
                         "type": "integer",
                         "description": "Number of emails to skip (default: 0)"
                     },
+                    "cursor": {
+                        "type": "string",
+                        "description": "next_cursor of the previous page, empty for the first page. Returns {emails, next_cursor}; preferred over offset for large folders"
+                    },
                     "account_id": {
                         "type": "string",
                         "description": "REQUIRED. Email address of the account (e.g., user@example.com)"
@@ -790,6 +800,7 @@ pub async fn handle_process_email_instructions(state: &DashboardState, arguments
                     "email_uid": {"type": "integer"},
                     "folder": {"type": "string"},
                     "instruction": {"type": "string"},
+                    "reply_mode": {"type": "string", "enum": ["sender", "all", "list"]},
                     "account_id": {"type": "string"}
                 },
                 "required": ["email_uid", "folder", "account_id"]
@@ -908,6 +919,7 @@ pub async fn handle_process_email_instructions(state: &DashboardState, arguments
 
 async fn handle_draft_reply(state: &DashboardState, arguments: Value) -> Value {
     use crate::dashboard::services::ai::email_drafter::{EmailDrafter, DraftReplyRequest};
+    use crate::dashboard::services::reply::{build_reply, ReplyMode, ReplySource};
 
     let pool = match state.cache_service.db_pool.as_ref() {
         Some(p) => p,
@@ -942,6 +954,16 @@ async fn handle_draft_reply(state: &DashboardState, arguments: Value) -> Value {
     };
 
     let instruction = arguments.get("instruction").and_then(|v| v.as_str()).map(|s| s.to_string());
+    let reply_mode: ReplyMode = match arguments.get("reply_mode").and_then(|v| v.as_str()) {
+        Some(mode) => match mode.parse() {
+            Ok(mode) => mode,
+            Err(e) => return json!({
+                "success": false,
+                "error": e
+            }),
+        },
+        None => ReplyMode::default(),
+    };
 
     // Fetch the original email
     let email_args = json!({
@@ -976,6 +998,32 @@ async fn handle_draft_reply(state: &DashboardState, arguments: Value) -> Value {
     let original_subject = email_data.get("subject").and_then(|v| v.as_str()).unwrap_or("(no subject)");
     let original_body = email_data.get("body_text").and_then(|v| v.as_str()).unwrap_or("");
 
+    let str_list = |key: &str| -> Vec<String> {
+        email_data.get(key)
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default()
+    };
+    // The get_email_by_uid call above leaves the message in the cache
+    let headers = state.cache_service.get_reply_headers(folder, email_uid, account_id).await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let source = ReplySource {
+        from: Some(original_from.to_string()),
+        to: str_list("to_addresses"),
+        cc: str_list("cc_addresses"),
+        subject: Some(original_subject.to_string()),
+        message_id: email_data.get("message_id").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        references: email_data.get("references_header").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        headers,
+    };
+    // The account's aliases and Reply-To count as its own on a reply-all
+    let own_addresses = state.account_service.lock().await
+        .get_own_addresses(account_id).await
+        .unwrap_or_else(|_| vec![account_id.to_string()]);
+    let reply = build_reply(&source, reply_mode, &own_addresses);
+
     let request = DraftReplyRequest {
         original_from: original_from.to_string(),
         original_subject: original_subject.to_string(),
@@ -989,25 +1037,28 @@ async fn handle_draft_reply(state: &DashboardState, arguments: Value) -> Value {
             // Save the draft to the Drafts folder
             let account_email = account_id.to_string();
 
-            // Construct reply subject (add "Re: " if not already present)
-            let reply_subject = if original_subject.starts_with("Re: ") {
-                original_subject.to_string()
-            } else {
-                format!("Re: {}", original_subject)
+            let draft_request = SendEmailRequest {
+                to: reply.to.clone(),
+                cc: Some(reply.cc.clone()).filter(|cc| !cc.is_empty()),
+                bcc: None,
+                subject: reply.subject.clone(),
+                body: draft.clone(),
+                body_html: None,
+                in_reply_to: reply.in_reply_to.clone(),
+                references: reply.references.clone(),
+                attachments: None,
             };
 
-            match state.smtp_service.save_draft(
-                &account_email,
-                &request.original_from,
-                &reply_subject,
-                &draft
-            ).await {
-                Ok(_) => {
+            match state.smtp_service.save_draft(&account_email, &draft_request).await {
+                Ok(saved) => {
                     json!({
                         "success": true,
                         "data": {
                             "draft": draft,
-                            "saved_to": "Drafts folder"
+                            "to": reply.to,
+                            "cc": reply.cc,
+                            "saved_to": saved.folder,
+                            "uid": saved.uid
                         }
                     })
                 }
@@ -1085,18 +1136,29 @@ async fn handle_draft_email(state: &DashboardState, arguments: Value) -> Value {
     match drafter.draft_email(pool, request.clone()).await {
         Ok(draft) => {
             // Save the draft to the Drafts folder
-            match state.smtp_service.save_draft(
-                &account_id,
-                &request.to,
-                &request.subject,
-                &draft
-            ).await {
-                Ok(_) => {
+            let draft_request = SendEmailRequest {
+                to: request.to.split(',')
+                    .map(|addr| addr.trim().to_string())
+                    .filter(|addr| !addr.is_empty())
+                    .collect(),
+                cc: None,
+                bcc: None,
+                subject: request.subject.clone(),
+                body: draft.clone(),
+                body_html: None,
+                in_reply_to: None,
+                references: None,
+                attachments: None,
+            };
+
+            match state.smtp_service.save_draft(&account_id, &draft_request).await {
+                Ok(saved) => {
                     json!({
                         "success": true,
                         "data": {
                             "draft": draft,
-                            "saved_to": "Drafts folder"
+                            "saved_to": saved.folder,
+                            "uid": saved.uid
                         }
                     })
                 }
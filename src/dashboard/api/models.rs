@@ -120,6 +120,22 @@ pub struct ChatbotResponse {
     pub email_data: Option<EmailData>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub followup_suggestions: Option<Vec<String>>,
+    /// Provider and model that produced the answer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answered_by: Option<AnsweredBy>,
+    /// Tokens used and estimated cost of this request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<crate::dashboard::services::ai::usage::RequestUsage>,
+}
+
+/// The provider that answered a chatbot query, and whether it took retries
+/// or a failover to the fallback provider to get there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnsweredBy {
+    pub provider: String,
+    pub model: String,
+    pub retries: u32,
+    pub failover: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,9 +26,12 @@ pub fn configure_routes() -> Scope {
         .route("/chatbot/stream", web::post().to(handlers::stream_chatbot))
         .route("/mcp/tools", web::get().to(handlers::list_mcp_tools))
         .route("/mcp/execute", web::post().to(handlers::execute_mcp_tool))
+        .route("/mcp/dedup", web::get().to(handlers::get_mcp_dedup_stats))
         // AI provider management endpoints
         .route("/ai/providers", web::get().to(handlers::get_ai_providers))
         .route("/ai/providers/set", web::post().to(handlers::set_ai_provider))
+        .route("/ai/status", web::get().to(handlers::get_ai_status))
+        .route("/ai/usage", web::get().to(handlers::get_ai_usage))
         // AI model management endpoints
         .route("/ai/models", web::get().to(handlers::get_ai_models))
         .route("/ai/models/set", web::post().to(handlers::set_ai_model))
@@ -52,8 +55,13 @@ pub fn configure_routes() -> Scope {
         .route("/folders", web::get().to(handlers::list_folders))
         .route("/cached-folders", web::get().to(handlers::list_cached_folders))
         .route("/emails", web::get().to(handlers::get_cached_emails))
+        .route("/emails/render", web::get().to(handlers::render_email))
+        .route("/emails/{folder}/{uid}/raw", web::get().to(handlers::get_raw_email))
+        .route("/emails/{folder}/{uid}/raw", web::head().to(handlers::head_raw_email))
         // SMTP email sending endpoint
         .route("/emails/send", web::post().to(handlers::send_email))
+        .route("/outbox", web::get().to(handlers::list_outbox))
+        .route("/outbox/{id}/cancel", web::post().to(handlers::cancel_outbox_item))
         // Email deletion endpoint
         .route("/emails/delete", web::post().to(handlers::delete_email))
         .route("/events", web::get().to(sse::sse_handler))
@@ -62,12 +70,20 @@ pub fn configure_routes() -> Scope {
         .route("/accounts", web::post().to(accounts::create_account))
         .route("/accounts", web::get().to(accounts::list_accounts))
         .route("/accounts/default", web::get().to(accounts::get_default_account))
+        .route("/accounts/summary", web::get().to(handlers::get_all_accounts_summary))
         .route("/accounts/{id}", web::get().to(accounts::get_account))
         .route("/accounts/{id}", web::put().to(accounts::update_account))
         .route("/accounts/{id}", web::delete().to(accounts::delete_account))
         .route("/accounts/{id}/default", web::post().to(accounts::set_default_account))
         .route("/accounts/{id}/connection-status", web::get().to(accounts::get_connection_status))
+        .route("/accounts/{id}/activity", web::get().to(accounts::get_account_activity))
+        .route("/accounts/{id}/templates", web::get().to(accounts::list_templates))
+        .route("/accounts/{id}/templates", web::post().to(accounts::create_template))
+        .route("/accounts/{id}/templates/{name}", web::get().to(accounts::get_template))
+        .route("/accounts/{id}/templates/{name}", web::put().to(accounts::update_template))
+        .route("/accounts/{id}/templates/{name}", web::delete().to(accounts::delete_template))
         .route("/accounts/{id}/validate", web::post().to(accounts::validate_connection))
+        .route("/accounts/{id}/reconnect", web::post().to(accounts::refresh_connections))
         // Subscription management endpoints
         .route("/events/types", web::get().to(handlers::get_available_event_types))
         .route("/clients/{client_id}/subscriptions", web::get().to(handlers::get_client_subscriptions))
@@ -76,6 +92,7 @@ pub fn configure_routes() -> Scope {
         .route("/clients/{client_id}/unsubscribe", web::post().to(handlers::unsubscribe_from_event))
         // Attachment management endpoints
         .route("/attachments/list", web::get().to(attachments::list_attachments))
+        .route("/attachments/jobs/{job_id}/zip", web::get().to(attachments::download_job_archive))
         .route("/attachments/{message_id}/zip", web::get().to(attachments::download_attachments_zip))
         .route("/attachments/{message_id}/inline/{content_id}", web::get().to(attachments::download_inline_attachment))
         .route("/attachments/{message_id}/{filename}", web::get().to(attachments::download_attachment))
@@ -97,6 +114,10 @@ pub fn configure_routes() -> Scope {
 pub fn configure(cfg: &mut web::ServiceConfig) {
     info!("Configuring dashboard routes (/api/dashboard)");
     cfg.service(configure_routes());
+    cfg.service(
+        web::scope("/api/admin")
+            .route("/reload-config", web::post().to(config::reload_config))
+    );
 
     // Add health check endpoints
     health::configure_health_routes(cfg);
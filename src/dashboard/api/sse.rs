@@ -18,7 +18,7 @@ use uuid::Uuid;
 use log::{info, debug, error, warn};
 use crate::dashboard::services::metrics::MetricsService;
 use crate::dashboard::services::clients::ClientManager;
-use crate::dashboard::services::events::{EventBus, DashboardEvent};
+use crate::dashboard::services::events::{EventBus, DashboardEvent, MailboxAction};
 use chrono::Utc;
 use tokio_stream::wrappers::{ReceiverStream, IntervalStream};
 use crate::dashboard::services::DashboardState;
@@ -33,6 +33,9 @@ pub enum EventType {
     ClientDisconnected,
     SystemAlert,
     ConfigurationUpdated,
+    NewMail,
+    SyncCompleted,
+    SyncFailed,
     DashboardEvent,
 }
 
@@ -45,6 +48,9 @@ impl EventType {
             "client_disconnected" => Some(EventType::ClientDisconnected),
             "system_alert" => Some(EventType::SystemAlert),
             "configuration_updated" => Some(EventType::ConfigurationUpdated),
+            "new_mail" => Some(EventType::NewMail),
+            "sync_completed" => Some(EventType::SyncCompleted),
+            "sync_failed" => Some(EventType::SyncFailed),
             "dashboard_event" => Some(EventType::DashboardEvent),
             _ => None,
         }
@@ -58,9 +64,27 @@ impl EventType {
             EventType::ClientDisconnected => "client_disconnected",
             EventType::SystemAlert => "system_alert",
             EventType::ConfigurationUpdated => "configuration_updated",
+            EventType::NewMail => "new_mail",
+            EventType::SyncCompleted => "sync_completed",
+            EventType::SyncFailed => "sync_failed",
             EventType::DashboardEvent => "dashboard_event",
         }
     }
+
+    /// The type a bus event is delivered as.
+    pub fn of(event: &DashboardEvent) -> Self {
+        match event {
+            DashboardEvent::MetricsUpdated { .. } => EventType::StatsUpdate,
+            DashboardEvent::ClientConnected { .. } => EventType::ClientConnected,
+            DashboardEvent::ClientDisconnected { .. } => EventType::ClientDisconnected,
+            DashboardEvent::SystemAlert { .. } => EventType::SystemAlert,
+            DashboardEvent::ConfigurationUpdated { .. } => EventType::ConfigurationUpdated,
+            DashboardEvent::MailboxActivity { action: MailboxAction::Received, .. } => EventType::NewMail,
+            DashboardEvent::SyncCompleted { .. } => EventType::SyncCompleted,
+            DashboardEvent::SyncFailed { .. } => EventType::SyncFailed,
+            _ => EventType::DashboardEvent,
+        }
+    }
 }
 
 // Default subscription: all events except welcome (which is sent once anyway)
@@ -115,6 +139,9 @@ impl SseClient {
         subscriptions.insert(EventType::ClientDisconnected);
         subscriptions.insert(EventType::SystemAlert);
         subscriptions.insert(EventType::ConfigurationUpdated);
+        subscriptions.insert(EventType::NewMail);
+        subscriptions.insert(EventType::SyncCompleted);
+        subscriptions.insert(EventType::SyncFailed);
         subscriptions.insert(EventType::DashboardEvent);
 
         Self {
@@ -306,6 +333,9 @@ impl SseManager {
                 subs.insert(EventType::ClientDisconnected);
                 subs.insert(EventType::SystemAlert);
                 subs.insert(EventType::ConfigurationUpdated);
+                subs.insert(EventType::NewMail);
+                subs.insert(EventType::SyncCompleted);
+                subs.insert(EventType::SyncFailed);
                 subs.insert(EventType::DashboardEvent);
                 subs
             });
@@ -558,9 +588,10 @@ impl SseManager {
                             )
                         },
                         _ => {
-                            // For other events, use a generic format
+                            // For other events, use a generic format under
+                            // their own type (new_mail, sync_*) or dashboard_event
                             SseEvent::new(
-                                "dashboard_event".to_string(),
+                                EventType::of(&event).to_string().to_string(),
                                 serde_json::to_string(&event).unwrap_or_default()
                             )
                         }
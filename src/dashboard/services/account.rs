@@ -7,10 +7,12 @@ use sqlx::{SqlitePool, Row};
 use log::{info, debug, error, warn};
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
-use super::account_store::{AccountStore, StoredAccount, AccountStoreError};
+use super::account_store::{AccountStore, SentAppendMode, StoredAccount, AccountStoreError};
 use super::connection_status_store::{ConnectionStatusStore, ConnectionStatusStoreError};
 use super::connection_status::AccountConnectionStatus;
 use chrono::Utc;
+use crate::config::TlsConfig;
+use crate::imap::transport::{ConnectionMode, ImapTransport};
 
 #[derive(Error, Debug)]
 pub enum AccountError {
@@ -49,6 +51,12 @@ pub struct Account {
     #[serde(skip_serializing)] // Never serialize passwords
     pub imap_pass: String,
     pub imap_use_tls: bool,
+    /// How the IMAP connection is secured: `implicit`, `starttls` or `plaintext`.
+    #[serde(default)]
+    pub imap_connection_mode: ConnectionMode,
+    /// Permit IMAP credentials over a connection without TLS.
+    #[serde(default)]
+    pub imap_allow_plaintext_auth: bool,
     pub smtp_host: Option<String>,
     pub smtp_port: Option<i64>,
     pub smtp_user: Option<String>,
@@ -72,6 +80,10 @@ pub struct Account {
     pub is_active: bool,
     #[serde(default)]
     pub is_default: bool,
+    /// TLS verification for this account's IMAP connection; unset uses the
+    /// `[tls]` settings.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tls: Option<TlsConfig>,
     // Connection status for IMAP and SMTP (optional, populated from separate store)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub connection_status: Option<super::connection_status::AccountConnectionStatus>,
@@ -82,6 +94,15 @@ impl Account {
     pub fn is_oauth(&self) -> bool {
         self.oauth_provider.is_some()
     }
+
+    /// How to secure this account's IMAP connection.
+    pub fn imap_transport(&self) -> ImapTransport {
+        ImapTransport {
+            mode: self.imap_connection_mode,
+            allow_plaintext_auth: self.imap_allow_plaintext_auth,
+            tls: crate::tls::config_for(self.tls.as_ref()),
+        }
+    }
 }
 
 // Default value function for is_active (defaults to true for new accounts)
@@ -119,6 +140,13 @@ pub struct AutoConfigResult {
     pub smtp_use_starttls: Option<bool>,
     pub supports_oauth: bool,
     pub oauth_provider: Option<String>,
+    /// Login name with the provider's username template filled in
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Where the settings came from: "template", "autoconfig", "ispdb",
+    /// "srv" or "guess"
+    #[serde(default)]
+    pub discovery_source: Option<String>,
 }
 
 pub struct AccountService {
@@ -201,12 +229,20 @@ impl AccountService {
                 username: settings.imap_user.clone(),
                 password: settings.imap_pass.clone(),
                 use_tls: true,
+                mode: ConnectionMode::default(),
+                allow_plaintext_auth: false,
             },
             smtp: None,
             oauth_provider: None,
             oauth_access_token: None,
             oauth_refresh_token: None,
             oauth_token_expiry: None,
+            sieve: None,
+            tls: None,
+            reply_to: None,
+            aliases: Vec::new(),
+            drafts_folder: None,
+            sent_append: SentAppendMode::default(),
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -313,6 +349,8 @@ impl AccountService {
                     username: imap_user,
                     password: imap_pass,
                     use_tls: imap_use_tls != 0,
+                    mode: ConnectionMode::default(),
+                    allow_plaintext_auth: false,
                 },
                 smtp: smtp_host.map(|host| {
                     super::account_store::SmtpConfig {
@@ -328,6 +366,12 @@ impl AccountService {
                 oauth_access_token: None,
                 oauth_refresh_token: None,
                 oauth_token_expiry: None,
+                sieve: None,
+                tls: None,
+                reply_to: None,
+                aliases: Vec::new(),
+                drafts_folder: None,
+                sent_append: SentAppendMode::default(),
                 is_active: is_active != 0,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
@@ -472,6 +516,8 @@ impl AccountService {
             imap_user: stored.imap.username,
             imap_pass: stored.imap.password,
             imap_use_tls: stored.imap.use_tls,
+            imap_connection_mode: stored.imap.mode,
+            imap_allow_plaintext_auth: stored.imap.allow_plaintext_auth,
             smtp_host: stored.smtp.as_ref().map(|s| s.host.clone()),
             smtp_port: stored.smtp.as_ref().map(|s| s.port as i64),
             smtp_user: stored.smtp.as_ref().map(|s| s.username.clone()),
@@ -484,6 +530,7 @@ impl AccountService {
             oauth_token_expiry: stored.oauth_token_expiry,
             is_active: stored.is_active,
             is_default: false, // Will be set based on config default_account_id
+            tls: stored.tls,
             connection_status: None, // Will be populated from ConnectionStatusStore
         }
     }
@@ -515,6 +562,8 @@ impl AccountService {
                     smtp_use_starttls: Some(tmpl.smtp_use_starttls),
                     supports_oauth: tmpl.supports_oauth,
                     oauth_provider: tmpl.oauth_provider,
+                    username: Some(email_address.to_string()),
+                    discovery_source: Some("template".to_string()),
                 })
             }
             None => {
@@ -532,6 +581,8 @@ impl AccountService {
                     smtp_use_starttls: None,
                     supports_oauth: false,
                     oauth_provider: None,
+                    username: None,
+                    discovery_source: None,
                 })
             }
         }
@@ -611,6 +662,8 @@ impl AccountService {
                 username: account.imap_user.clone(),
                 password: account.imap_pass.clone(),
                 use_tls: account.imap_use_tls,
+                mode: account.imap_connection_mode,
+                allow_plaintext_auth: account.imap_allow_plaintext_auth,
             },
             smtp: account.smtp_host.as_ref().map(|host| {
                 super::account_store::SmtpConfig {
@@ -626,6 +679,12 @@ impl AccountService {
             oauth_access_token: account.oauth_access_token.clone(),
             oauth_refresh_token: account.oauth_refresh_token.clone(),
             oauth_token_expiry: account.oauth_token_expiry,
+            sieve: None,
+            tls: account.tls.clone(),
+            reply_to: None,
+            aliases: Vec::new(),
+            drafts_folder: None,
+            sent_append: SentAppendMode::default(),
             is_active: account.is_active,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -646,6 +705,41 @@ impl AccountService {
         Ok(account_email)
     }
 
+    /// ManageSieve host and port for an account (IMAP host and 4190 unless overridden)
+    pub async fn get_sieve_endpoint(&self, account_id: &str) -> Result<(String, u16), AccountError> {
+        let stored = self.account_store.get_account(account_id).await?;
+        Ok(stored.sieve_endpoint())
+    }
+
+    /// Reply-To address configured for mail sent from an account, if any
+    pub async fn get_default_reply_to(&self, account_id: &str) -> Result<Option<String>, AccountError> {
+        let stored = self.account_store.get_account(account_id).await?;
+        Ok(stored.reply_to.filter(|r| !r.trim().is_empty()))
+    }
+
+    /// Addresses that belong to the account: its own, its aliases and its
+    /// Reply-To. A reply-all never copies these.
+    pub async fn get_own_addresses(&self, account_id: &str) -> Result<Vec<String>, AccountError> {
+        let stored = self.account_store.get_account(account_id).await?;
+        Ok(std::iter::once(stored.email_address)
+            .chain(stored.aliases)
+            .chain(stored.reply_to)
+            .filter(|a| !a.trim().is_empty())
+            .collect())
+    }
+
+    /// Drafts folder configured for the account, if any
+    pub async fn get_drafts_folder(&self, account_id: &str) -> Result<Option<String>, AccountError> {
+        let stored = self.account_store.get_account(account_id).await?;
+        Ok(stored.drafts_folder.filter(|f| !f.trim().is_empty()))
+    }
+
+    /// Whether sent mail is always appended to the account's Sent folder
+    pub async fn get_sent_append_mode(&self, account_id: &str) -> Result<SentAppendMode, AccountError> {
+        let stored = self.account_store.get_account(account_id).await?;
+        Ok(stored.sent_append)
+    }
+
     /// Get account by ID
     pub async fn get_account(&self, account_id: &str) -> Result<Account, AccountError> {
         let stored = self.account_store.get_account(account_id).await?;
@@ -718,6 +812,8 @@ impl AccountService {
                 username: account.imap_user.clone(),
                 password: account.imap_pass.clone(),
                 use_tls: account.imap_use_tls,
+                mode: account.imap_connection_mode,
+                allow_plaintext_auth: account.imap_allow_plaintext_auth,
             },
             smtp: account.smtp_host.as_ref().map(|host| {
                 super::account_store::SmtpConfig {
@@ -734,6 +830,12 @@ impl AccountService {
             oauth_access_token: existing.oauth_access_token,
             oauth_refresh_token: existing.oauth_refresh_token,
             oauth_token_expiry: existing.oauth_token_expiry,
+            sieve: existing.sieve,
+            tls: account.tls.clone().or(existing.tls),
+            reply_to: existing.reply_to,
+            aliases: existing.aliases,
+            drafts_folder: existing.drafts_folder,
+            sent_append: existing.sent_append,
             is_active: account.is_active,
             created_at: existing.created_at,
             updated_at: Utc::now(),
@@ -788,16 +890,19 @@ impl AccountService {
     pub async fn validate_connection(&self, account: &Account) -> Result<(), AccountError> {
         debug!("Validating connection for account: {}", account.display_name);
 
+        let transport = account.imap_transport();
+
         // Route OAuth accounts through XOAUTH2
         let connect_result = if account.is_oauth() {
             match &account.oauth_access_token {
                 Some(token) => {
                     debug!("Validating OAuth connection for {}", account.email_address);
-                    crate::imap::client::ImapClient::<crate::imap::session::AsyncImapSessionWrapper>::connect_with_xoauth2(
+                    crate::imap::client::ImapClient::<crate::imap::session::AsyncImapSessionWrapper>::connect_with_xoauth2_transport(
                         &account.imap_host,
                         account.imap_port as u16,
                         &account.imap_user,
                         token,
+                        &transport,
                     ).await
                 }
                 None => {
@@ -815,6 +920,7 @@ impl AccountService {
                 &account.imap_user,
                 &account.imap_pass,
                 timeout,
+                &transport,
             ).await
         };
 
@@ -10,6 +10,8 @@ use chrono::{DateTime, Utc};
 use log::{info, debug, warn};
 use thiserror::Error;
 use super::encryption::CredentialEncryption;
+use crate::config::TlsConfig;
+use crate::imap::transport::{ConnectionMode, ImapTransport};
 
 #[derive(Error, Debug)]
 pub enum AccountStoreError {
@@ -37,6 +39,13 @@ pub struct ImapConfig {
     #[serde(skip_serializing_if = "String::is_empty", default)]
     pub password: String,
     pub use_tls: bool,
+    /// Implicit TLS (993), STARTTLS (143) or plaintext
+    #[serde(default)]
+    pub mode: ConnectionMode,
+    /// Allow LOGIN over a connection that isn't encrypted, including after a
+    /// refused STARTTLS. Off unless set.
+    #[serde(default)]
+    pub allow_plaintext_auth: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -59,6 +68,33 @@ pub struct SmtpConfig {
     pub use_starttls: bool,
 }
 
+/// How the outbox worker puts sent mail in the Sent folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SentAppendMode {
+    /// Skip the APPEND when a message with the same Message-ID is already
+    /// there, as with servers that file what goes through their SMTP
+    #[default]
+    IfMissing,
+    /// Always APPEND, for providers whose own copy shows up too late to be
+    /// found or never at all
+    Always,
+}
+
+/// ManageSieve settings. Credentials are shared with the IMAP config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SieveConfig {
+    /// Defaults to the IMAP host when unset.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub host: Option<String>,
+    #[serde(default = "default_sieve_port")]
+    pub port: u16,
+}
+
+fn default_sieve_port() -> u16 {
+    crate::sieve::DEFAULT_SIEVE_PORT
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredAccount {
     // email_address is the primary identifier - id field removed
@@ -80,6 +116,29 @@ pub struct StoredAccount {
     /// Unix timestamp (seconds) when the OAuth access token expires.
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub oauth_token_expiry: Option<i64>,
+    /// ManageSieve override; when absent the IMAP host and port 4190 are used.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sieve: Option<SieveConfig>,
+    /// IMAP TLS overrides (minimum version, CA bundle, pin); unset uses the
+    /// `[tls]` settings.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tls: Option<TlsConfig>,
+    /// Reply-To header added to mail sent from this account, e.g. a shared
+    /// support address. Unset sends no Reply-To.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reply_to: Option<String>,
+    /// Other addresses the account receives and sends as, left out of
+    /// reply-all recipients along with the account's own address.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub aliases: Vec<String>,
+    /// Folder drafts are saved to. Unset uses the folder marked \Drafts,
+    /// or the provider's usual name for it.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub drafts_folder: Option<String>,
+    /// Whether sent mail is always appended to Sent or only when the server
+    /// hasn't filed a copy itself.
+    #[serde(default)]
+    pub sent_append: SentAppendMode,
     pub is_active: bool,
     #[serde(default = "Utc::now")]
     pub created_at: DateTime<Utc>,
@@ -92,6 +151,26 @@ impl StoredAccount {
     pub fn is_oauth(&self) -> bool {
         self.oauth_provider.is_some()
     }
+
+    /// ManageSieve host and port for this account.
+    pub fn sieve_endpoint(&self) -> (String, u16) {
+        match &self.sieve {
+            Some(config) => (
+                config.host.clone().unwrap_or_else(|| self.imap.host.clone()),
+                config.port,
+            ),
+            None => (self.imap.host.clone(), crate::sieve::DEFAULT_SIEVE_PORT),
+        }
+    }
+
+    /// How to secure this account's IMAP connection.
+    pub fn imap_transport(&self) -> ImapTransport {
+        ImapTransport {
+            mode: self.imap.mode,
+            allow_plaintext_auth: self.imap.allow_plaintext_auth,
+            tls: crate::tls::config_for(self.tls.as_ref()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -379,12 +458,20 @@ mod tests {
                 username: "test@example.com".to_string(),
                 password: "password".to_string(),
                 use_tls: true,
+                mode: ConnectionMode::default(),
+                allow_plaintext_auth: false,
             },
             smtp: None,
             oauth_provider: None,
             oauth_access_token: None,
             oauth_refresh_token: None,
             oauth_token_expiry: None,
+            sieve: None,
+            tls: None,
+            reply_to: None,
+            aliases: Vec::new(),
+            drafts_folder: None,
+            sent_append: SentAppendMode::default(),
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -428,12 +515,20 @@ mod tests {
                 username: "user@outlook.com".to_string(),
                 password: String::new(), // OAuth accounts don't use passwords
                 use_tls: true,
+                mode: ConnectionMode::default(),
+                allow_plaintext_auth: false,
             },
             smtp: None,
             oauth_provider: Some("microsoft".to_string()),
             oauth_access_token: Some("test-access-token".to_string()),
             oauth_refresh_token: Some("test-refresh-token".to_string()),
             oauth_token_expiry: Some(1700000000),
+            sieve: None,
+            tls: None,
+            reply_to: None,
+            aliases: Vec::new(),
+            drafts_folder: None,
+            sent_append: SentAppendMode::default(),
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -463,12 +558,20 @@ mod tests {
                 username: "test".to_string(),
                 password: "pass".to_string(),
                 use_tls: true,
+                mode: ConnectionMode::default(),
+                allow_plaintext_auth: false,
             },
             smtp: None,
             oauth_provider: None,
             oauth_access_token: None,
             oauth_refresh_token: None,
             oauth_token_expiry: None,
+            sieve: None,
+            tls: None,
+            reply_to: None,
+            aliases: Vec::new(),
+            drafts_folder: None,
+            sent_append: SentAppendMode::default(),
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
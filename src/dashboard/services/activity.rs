@@ -0,0 +1,219 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Persisted per-account activity feed.
+//
+// Subscribes to the EventBus and writes MailboxActivity events to the
+// activity_log table, keeping only the newest entries per account. Unlike the
+// in-memory SSE replay buffer this survives restarts.
+
+use std::sync::Arc;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use log::{debug, info, warn};
+use chrono::{DateTime, Utc};
+use super::events::{DashboardEvent, EventBus, MailboxAction};
+
+/// Default number of entries kept per account.
+const DEFAULT_MAX_ENTRIES: i64 = 1000;
+
+/// One entry in an account's activity feed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub account_id: String,
+    pub action: String,
+    pub folder: Option<String>,
+    pub uids: Vec<u32>,
+    pub detail: Option<String>,
+    pub source: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+pub struct ActivityLogService {
+    pool: SqlitePool,
+    max_entries: i64,
+}
+
+impl ActivityLogService {
+    pub fn new(pool: SqlitePool, max_entries: i64) -> Self {
+        Self { pool, max_entries: max_entries.max(1) }
+    }
+
+    /// Create with the cap from `ACTIVITY_LOG_MAX_ENTRIES` (default 1000 per account).
+    pub fn from_env(pool: SqlitePool) -> Self {
+        let max_entries = std::env::var("ACTIVITY_LOG_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_ENTRIES);
+        Self::new(pool, max_entries)
+    }
+
+    /// Record an entry and trim the account's log to the cap.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record(
+        &self,
+        account_id: &str,
+        action: MailboxAction,
+        folder: Option<&str>,
+        uids: &[u32],
+        detail: Option<&str>,
+        source: &str,
+        occurred_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let uids_json = serde_json::to_string(uids)
+            .map_err(|e| format!("Failed to serialize UIDs: {}", e))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO activity_log (account_id, action, folder, uids, detail, source, occurred_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(account_id)
+        .bind(action.as_str())
+        .bind(folder)
+        .bind(&uids_json)
+        .bind(detail)
+        .bind(source)
+        .bind(occurred_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM activity_log
+            WHERE account_id = ? AND id NOT IN (
+                SELECT id FROM activity_log WHERE account_id = ? ORDER BY id DESC LIMIT ?
+            )
+            "#
+        )
+        .bind(account_id)
+        .bind(account_id)
+        .bind(self.max_entries)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Newest-first page of an account's activity, plus the total entry count.
+    pub async fn list(
+        &self,
+        account_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<ActivityEntry>, i64), String> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, account_id, action, folder, uids, detail, source, occurred_at
+            FROM activity_log
+            WHERE account_id = ?
+            ORDER BY id DESC
+            LIMIT ? OFFSET ?
+            "#
+        )
+        .bind(account_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let entries = rows.iter().map(|row| {
+            let uids: String = row.get("uids");
+            let occurred_at: String = row.get("occurred_at");
+            ActivityEntry {
+                id: row.get("id"),
+                account_id: row.get("account_id"),
+                action: row.get("action"),
+                folder: row.get("folder"),
+                uids: serde_json::from_str(&uids).unwrap_or_default(),
+                detail: row.get("detail"),
+                source: row.get("source"),
+                occurred_at: DateTime::parse_from_rfc3339(&occurred_at)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+            }
+        }).collect();
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM activity_log WHERE account_id = ?")
+            .bind(account_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok((entries, total))
+    }
+}
+
+/// Persist MailboxActivity events from the bus for as long as the bus lives.
+pub async fn start_activity_recorder(service: Arc<ActivityLogService>, event_bus: Arc<EventBus>) {
+    let mut subscription = event_bus.subscribe().await;
+
+    tokio::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            if let DashboardEvent::MailboxActivity { account_id, action, folder, uids, detail, source, timestamp } = event {
+                debug!("Recording {} activity for {}", action.as_str(), account_id);
+                if let Err(e) = service.record(
+                    &account_id, action, folder.as_deref(), &uids, detail.as_deref(), &source, timestamp,
+                ).await {
+                    warn!("Failed to record activity for {}: {}", account_id, e);
+                }
+            }
+        }
+        warn!("Activity recorder stopped - subscription ended");
+    });
+
+    info!("Started activity log recorder");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Executor;
+
+    async fn setup(max_entries: i64) -> ActivityLogService {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        pool.execute(include_str!("../../../migrations/016_create_activity_log.sql"))
+            .await
+            .unwrap();
+        ActivityLogService::new(pool, max_entries)
+    }
+
+    #[tokio::test]
+    async fn test_record_and_list_newest_first() {
+        let service = setup(100).await;
+        service.record("a@example.com", MailboxAction::Received, Some("INBOX"), &[1, 2], None, "sync", Utc::now()).await.unwrap();
+        service.record("a@example.com", MailboxAction::Moved, Some("INBOX"), &[2], Some("Archive"), "mcp", Utc::now()).await.unwrap();
+        service.record("b@example.com", MailboxAction::Sent, None, &[], None, "dashboard", Utc::now()).await.unwrap();
+
+        let (entries, total) = service.list("a@example.com", 10, 0).await.unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(entries[0].action, "moved");
+        assert_eq!(entries[0].detail.as_deref(), Some("Archive"));
+        assert_eq!(entries[1].uids, vec![1, 2]);
+
+        let (page, _) = service.list("a@example.com", 1, 1).await.unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].action, "received");
+    }
+
+    #[tokio::test]
+    async fn test_log_is_capped_per_account() {
+        let service = setup(3).await;
+        for uid in 1..=5 {
+            service.record("a@example.com", MailboxAction::Flagged, Some("INBOX"), &[uid], None, "mcp", Utc::now()).await.unwrap();
+        }
+        service.record("b@example.com", MailboxAction::Deleted, Some("INBOX"), &[9], None, "mcp", Utc::now()).await.unwrap();
+
+        let (entries, total) = service.list("a@example.com", 10, 0).await.unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(entries.iter().map(|e| e.uids[0]).collect::<Vec<_>>(), vec![5, 4, 3]);
+        assert_eq!(service.list("b@example.com", 10, 0).await.unwrap().1, 1);
+    }
+}
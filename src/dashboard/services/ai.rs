@@ -11,9 +11,12 @@ pub mod sampler_config;
 pub mod tool_converter;
 pub mod email_drafter;
 pub mod agent_executor;
+pub mod grounding;
+pub mod usage;
+pub mod retry;
 
 use log::{debug, error, info, warn};
-use crate::dashboard::api::models::{ChatbotQuery, ChatbotResponse, EmailData, EmailMessage, EmailFolder};
+use crate::dashboard::api::models::{AnsweredBy, ChatbotQuery, ChatbotResponse, EmailData, EmailMessage, EmailFolder};
 use crate::dashboard::services::ai::provider::{AiProvider, AiChatMessage};
 use crate::dashboard::services::ai::provider_manager::ProviderManager;
 use crate::dashboard::services::ai::nlp_processor::NlpProcessor;
@@ -21,7 +24,8 @@ use std::sync::Arc;
 use crate::api::errors::ApiError;
 use thiserror::Error;
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use futures::stream::StreamExt;
+use tokio::sync::{mpsc, RwLock};
 use uuid::Uuid;
 use reqwest::Client;
 use serde_json::{json, Value};
@@ -40,6 +44,15 @@ struct Conversation {
     last_activity: chrono::DateTime<chrono::Utc>,
 }
 
+/// One agent turn's response and the provider that gave it.
+struct TurnOutcome {
+    text: String,
+    provider: String,
+    model: String,
+    retries: u32,
+    failed_over: bool,
+}
+
 // Helper struct to hold both string context and structured email data
 #[derive(Debug, Clone)]
 struct EmailContextData {
@@ -47,6 +60,77 @@ struct EmailContextData {
     email_data: EmailData,
 }
 
+/// Passes streamed text through while hiding `TOOL_CALL:` lines. A line is
+/// held only until it can no longer turn into a tool call.
+#[derive(Default)]
+struct ToolCallFilter {
+    line: String,
+    /// The current line is a tool call; drop it up to the newline
+    in_tool_call: bool,
+    /// The current line was already forwarded in part
+    passing: bool,
+}
+
+impl ToolCallFilter {
+    const MARKER: &'static str = "TOOL_CALL:";
+
+    fn push(&mut self, chunk: &str) -> String {
+        let mut out = String::new();
+        for c in chunk.chars() {
+            if self.in_tool_call {
+                if c == '\n' {
+                    self.in_tool_call = false;
+                }
+                continue;
+            }
+            if self.passing {
+                out.push(c);
+                if c == '\n' {
+                    self.passing = false;
+                }
+                continue;
+            }
+            self.line.push(c);
+            let start = self.line.trim_start();
+            if start.starts_with(Self::MARKER) {
+                self.line.clear();
+                self.in_tool_call = c != '\n';
+            } else if c == '\n' {
+                out.push_str(&std::mem::take(&mut self.line));
+            } else if !start.is_empty() && !Self::MARKER.starts_with(start) {
+                out.push_str(&std::mem::take(&mut self.line));
+                self.passing = true;
+            }
+        }
+        out
+    }
+
+    /// Text still held when the stream ends.
+    fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.line);
+        if self.in_tool_call || rest.trim_start().starts_with(Self::MARKER) {
+            String::new()
+        } else {
+            rest
+        }
+    }
+}
+
+/// Providers `new` configures from an API key, with the variable holding it.
+const PROVIDER_KEY_VARS: &[(&str, &str)] = &[
+    ("openai", "OPENAI_API_KEY"),
+    ("openrouter", "OPENROUTER_API_KEY"),
+    ("morpheus", "MORPHEUS_API_KEY"),
+];
+
+/// Outcome of `AiService::reload_api_keys`, by environment variable.
+#[derive(Debug, Default)]
+pub struct ApiKeyReload {
+    pub updated: Vec<String>,
+    pub requires_restart: Vec<String>,
+    pub errors: Vec<String>,
+}
+
 pub struct AiService {
     conversations: RwLock<HashMap<String, Conversation>>,
     provider_manager: ProviderManager,
@@ -57,6 +141,8 @@ pub struct AiService {
     mcp_base_url: String,
     api_key: String,
     mcp_tools: RwLock<Vec<Value>>, // Cached MCP tools from API
+    grounding: grounding::GroundingConfig,
+    usage: usage::UsageTracker,
 }
 
 impl std::fmt::Debug for AiService {
@@ -64,6 +150,7 @@ impl std::fmt::Debug for AiService {
         f.debug_struct("AiService")
             .field("conversations_count", &self.conversations.try_read().map(|g| g.len()).unwrap_or(0))
             .field("mock_mode", &self.mock_mode)
+            .field("grounding", &self.grounding)
             .finish()
     }
 }
@@ -99,6 +186,8 @@ impl AiService {
             api_key: std::env::var("RUSTYMAIL_API_KEY")
                 .unwrap_or_else(|_| String::new()),
             mcp_tools: RwLock::new(Vec::new()),
+            grounding: grounding::GroundingConfig::from_env(),
+            usage: usage::UsageTracker::default(),
         }
     }
 
@@ -221,10 +310,25 @@ impl AiService {
                     .expect("RUSTYMAIL_API_KEY environment variable must be set")
             ),
             mcp_tools: RwLock::new(Vec::new()),
+            grounding: grounding::GroundingConfig::from_env(),
+            usage: usage::UsageTracker::default(),
         })
     }
 
     pub async fn process_query(&self, query: ChatbotQuery) -> Result<ChatbotResponse, ApiError> {
+        self.process_query_inner(query, None).await
+    }
+
+    /// Like `process_query`, but sends the reply text to `chunks` as the
+    /// provider generates it. `TOOL_CALL:` lines are held back, and an answer
+    /// that strict grounding may still reject is sent only once accepted.
+    /// Text the model writes before calling tools is streamed too, so the
+    /// returned response (the final answer only) is the one to keep.
+    pub async fn process_query_stream(&self, query: ChatbotQuery, chunks: mpsc::Sender<String>) -> Result<ChatbotResponse, ApiError> {
+        self.process_query_inner(query, Some(chunks)).await
+    }
+
+    async fn process_query_inner(&self, query: ChatbotQuery, chunks: Option<mpsc::Sender<String>>) -> Result<ChatbotResponse, ApiError> {
         let conversation_id = query.conversation_id.unwrap_or_else(|| Uuid::new_v4().to_string());
         let query_text = query.query.clone();
         let provider_override = query.provider_override.clone();
@@ -313,6 +417,10 @@ impl AiService {
                 system_content.push_str(&Self::format_tools_for_prompt(&tools));
             }
 
+            if self.grounding.strict {
+                system_content.push_str(grounding::STRICT_GROUNDING_INSTRUCTION);
+            }
+
             messages_history.insert(0, AiChatMessage {
                 role: "system".to_string(),
                 content: system_content
@@ -341,21 +449,58 @@ impl AiService {
         let max_iterations = 3;
         let mut final_response = String::new();
 
+        // Strict grounding: a data question must be answered from a tool result
+        let requires_data = self.grounding.strict && grounding::is_data_question(&query_text);
+        let mut data_retrieved = false;
+        let mut reminded = false;
+        let mut provider_failed = false;
+
+        // Tokens and cost of every provider call this request makes
+        let mut request_usage = usage::TokenUsage::default();
+        let mut request_cost = 0.0;
+        let mut request_estimated = false;
+        // Provider of the latest successful turn, with retries and failovers so far
+        let mut answered_by: Option<AnsweredBy> = None;
+
+        // Streaming goes through the provider's own model, so a model
+        // override is answered in one piece
+        let chunks = chunks.filter(|_| model_override.is_none());
+        let header = format!("[Provider: {}, Model: {}]\n\n", provider_name, model_name);
+        let mut streamed = String::new();
+        if let Some(tx) = &chunks {
+            let _ = tx.send(header.clone()).await;
+        }
+
         for iteration in 0..max_iterations {
             info!("Agentic loop iteration {}/{}", iteration + 1, max_iterations);
 
             // Get AI response
-            let response_result = if provider_override.is_some() || model_override.is_some() {
-                self.provider_manager.generate_response_with_override(&messages_history, provider_override.clone(), model_override.clone()).await
-            } else {
-                self.provider_manager.generate_response(&messages_history).await
-            };
-
-            let ai_response = match response_result {
-                Ok(text) => text,
+            let hold_back = requires_data && !data_retrieved;
+            let stream_to = chunks.as_ref().filter(|_| !hold_back);
+            let (turn, reported) = usage::capture(self.generate_turn(
+                &messages_history,
+                (&provider_name, &model_name),
+                provider_override.clone(),
+                model_override.clone(),
+                stream_to,
+                &mut streamed,
+            )).await;
+
+            let ai_response = match turn {
+                Ok(turn) => {
+                    let call_usage = reported.unwrap_or_else(|| usage::estimate(&messages_history, &turn.text));
+                    request_estimated |= reported.is_none();
+                    request_cost += self.usage.record(&turn.provider, &turn.model, call_usage, reported.is_none());
+                    request_usage += call_usage;
+                    let retries = answered_by.as_ref().map_or(0, |a| a.retries) + turn.retries;
+                    let failover = answered_by.as_ref().is_some_and(|a| a.failover) || turn.failed_over;
+                    answered_by = Some(AnsweredBy { provider: turn.provider, model: turn.model, retries, failover });
+                    turn.text
+                }
                 Err(e) => {
                     error!("AI Service failed: {}", e);
                     final_response = format!("[Error - Provider: {} failed]\n\n{}", provider_name, e.to_string());
+                    provider_failed = true;
                     break;
                 }
             };
@@ -364,6 +509,21 @@ impl AiService {
             let tool_calls = Self::parse_tool_calls(&ai_response);
 
             if tool_calls.is_empty() {
+                if requires_data && !data_retrieved && !reminded && iteration + 1 < max_iterations {
+                    // Answered a data question from thin air - push it to retrieve first
+                    warn!("Strict grounding: model answered a data question without tool results, asking it to retrieve data");
+                    reminded = true;
+                    messages_history.push(AiChatMessage {
+                        role: "assistant".to_string(),
+                        content: ai_response,
+                    });
+                    messages_history.push(AiChatMessage {
+                        role: "user".to_string(),
+                        content: grounding::RETRIEVE_FIRST_REMINDER.to_string(),
+                    });
+                    continue;
+                }
+
                 // No tool calls - we're done
                 info!("No tool calls found. Final response ready.");
                 final_response = ai_response;
@@ -379,6 +539,7 @@ impl AiService {
 
                 match self.call_mcp_tool(&tool_name, params).await {
                     Ok(result) => {
+                        data_retrieved |= grounding::tool_result_retrieved(&result);
                         tool_results.push(format!("TOOL_RESULT {}: {}", tool_name, serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string())));
                     }
                     Err(e) => {
@@ -401,8 +562,28 @@ impl AiService {
             // Continue loop for next iteration
         }
 
+        if requires_data && !data_retrieved && !provider_failed {
+            warn!("Strict grounding: no data retrieved for data question, refusing to answer");
+            final_response = grounding::NO_DATA_RESPONSE.to_string();
+        }
+
         // Format final response with provider/model info
-        let response_text = format!("[Provider: {}, Model: {}]\n\n{}", provider_name, model_name, final_response);
+        let header = match &answered_by {
+            Some(answer) if answer.failover => format!(
+                "[Provider: {}, Model: {} (failover from {})]\n\n",
+                answer.provider, answer.model, provider_name
+            ),
+            _ => header,
+        };
+        let response_text = format!("{}{}", header, final_response);
+
+        if let Some(tx) = &chunks {
+            // Whatever the client hasn't seen yet: an error, the grounding
+            // refusal, or an answer that was held back
+            if !final_response.is_empty() && !streamed.ends_with(&final_response) {
+                let _ = tx.send(final_response.clone()).await;
+            }
+        }
 
         let assistant_message = AiChatMessage { role: "assistant".to_string(), content: response_text.clone() };
         conversation.entries.push(ConversationEntry {
@@ -425,9 +606,113 @@ impl AiService {
             conversation_id,
             email_data: None, // No longer using hardcoded email context
             followup_suggestions: Some(suggestions),
+            answered_by,
+            usage: Some(usage::RequestUsage {
+                prompt_tokens: request_usage.prompt_tokens,
+                completion_tokens: request_usage.completion_tokens,
+                total_tokens: request_usage.total(),
+                estimated_cost_usd: request_cost,
+                estimated: request_estimated,
+            }),
         })
     }
 
+    /// Get one agent turn's response. Transient failures are retried with
+    /// backoff per `RetryPolicy`; if they persist and `AI_FALLBACK_PROVIDER`
+    /// names another provider, the turn is tried there too. Errors that
+    /// can't be retried, and errors after part of a streamed answer was
+    /// sent, are returned at once.
+    async fn generate_turn(
+        &self,
+        messages: &[AiChatMessage],
+        (primary_provider, primary_model): (&str, &str),
+        provider_override: Option<String>,
+        model_override: Option<String>,
+        stream_to: Option<&mpsc::Sender<String>>,
+        streamed: &mut String,
+    ) -> Result<TurnOutcome, ApiError> {
+        let policy = retry::RetryPolicy::from_env();
+        let mut targets = vec![(provider_override, model_override, primary_provider.to_string(), primary_model.to_string())];
+        if let Some(fallback) = policy.fallback_provider.clone().filter(|f| f != primary_provider) {
+            let fallback_model = self.provider_manager.list_providers().await
+                .into_iter()
+                .find(|c| c.name == fallback)
+                .map(|c| c.model)
+                .unwrap_or_else(|| "unknown".to_string());
+            targets.push((Some(fallback.clone()), None, fallback, fallback_model));
+        }
+
+        let mut retries = 0;
+        let mut last_error = None;
+        for (index, (provider, model, provider_name, model_name)) in targets.into_iter().enumerate() {
+            if let Some(e) = &last_error {
+                warn!("Failing over to provider {} after: {}", provider_name, e);
+            }
+            for attempt in 0..=policy.max_retries {
+                let already_streamed = streamed.len();
+                let result = if let Some(tx) = stream_to {
+                    self.stream_response(messages, provider.clone(), tx, streamed).await
+                } else if provider.is_some() || model.is_some() {
+                    self.provider_manager.generate_response_with_override(messages, provider.clone(), model.clone()).await
+                } else {
+                    self.provider_manager.generate_response(messages).await
+                };
+                let error = match result {
+                    Ok(text) => return Ok(TurnOutcome {
+                        text,
+                        provider: provider_name,
+                        model: model_name,
+                        retries,
+                        failed_over: index > 0,
+                    }),
+                    Err(e) => e,
+                };
+                // Retrying after the client saw part of the answer would repeat it
+                if !retry::is_retryable(&error) || streamed.len() > already_streamed {
+                    return Err(error);
+                }
+                if attempt < policy.max_retries {
+                    let delay = policy.delay(attempt);
+                    warn!("Provider {} failed ({}), retrying in {:?}", provider_name, error, delay);
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                last_error = Some(error);
+            }
+        }
+        Err(last_error.expect("at least one provider attempt was made"))
+    }
+
+    /// One agent turn from the provider's response stream. Text is forwarded
+    /// to `tx` line by line as it arrives, except `TOOL_CALL:` lines; what was
+    /// forwarded is appended to `streamed`. Returns the full response.
+    async fn stream_response(
+        &self,
+        messages: &[AiChatMessage],
+        provider_override: Option<String>,
+        tx: &mpsc::Sender<String>,
+        streamed: &mut String,
+    ) -> Result<String, ApiError> {
+        let mut stream = self.provider_manager.generate_response_stream(messages, provider_override).await?;
+        let mut response = String::new();
+        let mut filter = ToolCallFilter::default();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            response.push_str(&chunk);
+            let visible = filter.push(&chunk);
+            if !visible.is_empty() {
+                streamed.push_str(&visible);
+                let _ = tx.send(visible).await;
+            }
+        }
+        let rest = filter.finish();
+        if !rest.is_empty() {
+            streamed.push_str(&rest);
+            let _ = tx.send(rest).await;
+        }
+        Ok(response)
+    }
+
     // Generate a mock response using MCP tools
     fn generate_mock_response(&self, query: &str, account_id: Option<&str>) -> String {
         let query_lower = query.to_lowercase();
@@ -516,6 +801,20 @@ impl AiService {
         self.provider_manager.get_current_provider_name().await
     }
 
+    pub async fn get_current_model_name(&self) -> Option<String> {
+        self.provider_manager.get_current_model_name().await
+    }
+
+    pub async fn provider_concurrency(&self) -> Vec<crate::dashboard::services::ai::provider_manager::ProviderConcurrency> {
+        self.provider_manager.concurrency_status().await
+    }
+
+    /// Tokens and estimated cost of chatbot requests since startup, per
+    /// provider and model.
+    pub fn usage_report(&self) -> usage::UsageReport {
+        self.usage.report()
+    }
+
     pub async fn set_current_provider(&self, name: String) -> Result<(), String> {
         self.provider_manager.set_current_provider(name)
             .await
@@ -551,6 +850,31 @@ impl AiService {
             .map_err(|e| format!("Failed to update provider config: {:?}", e))
     }
 
+    /// Re-read the provider API keys `new` takes from the environment and
+    /// rebuild every configured provider whose key changed. Returns the
+    /// providers updated, and those whose key appeared or disappeared, since
+    /// adding or removing a provider still needs a restart.
+    pub async fn reload_api_keys(&self) -> ApiKeyReload {
+        let mut reload = ApiKeyReload::default();
+        let configs = self.provider_manager.list_providers().await;
+        for (name, var) in PROVIDER_KEY_VARS {
+            let key = std::env::var(var).ok().filter(|k| !k.is_empty());
+            match (configs.iter().find(|c| c.name == *name), key) {
+                (Some(config), Some(key)) if config.api_key.as_deref() != Some(key.as_str()) => {
+                    let mut config = config.clone();
+                    config.api_key = Some(key);
+                    match self.update_provider_config(name, config).await {
+                        Ok(()) => reload.updated.push(var.to_string()),
+                        Err(e) => reload.errors.push(format!("{}: {}", var, e)),
+                    }
+                }
+                (Some(_), None) | (None, Some(_)) => reload.requires_restart.push(var.to_string()),
+                _ => {}
+            }
+        }
+        reload
+    }
+
     pub async fn get_available_models(&self) -> Result<Vec<String>, ApiError> {
         self.provider_manager.get_available_models().await
     }
@@ -1163,3 +1487,24 @@ impl AiService {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_call_filter_hides_tool_call_lines() {
+        let mut filter = ToolCallFilter::default();
+        let mut out = String::new();
+        for chunk in ["Let me ", "check.\n  TOOL", "_CALL: list_folders {\"account", "_id\": \"a\"}\nT", "here you go", ".\nTO"] {
+            out.push_str(&filter.push(chunk));
+        }
+        // "TO" could still become a tool call until the stream ends
+        assert_eq!(out, "Let me check.\nThere you go.\n");
+        assert_eq!(filter.finish(), "TO");
+
+        let mut filter = ToolCallFilter::default();
+        assert_eq!(filter.push("TOOL_CALL: list_folders"), "");
+        assert_eq!(filter.finish(), "");
+    }
+}
@@ -9,6 +9,7 @@ use sqlx::SqlitePool;
 use crate::api::errors::ApiError;
 use crate::dashboard::services::DashboardState;
 use super::model_config::{get_model_config, ModelConfiguration};
+use super::provider_manager::acquire_provider_slot;
 use super::tool_converter::{mcp_to_ollama_tools, parse_ollama_tool_call};
 use super::sampler_config::{get_sampler_config, SamplerConfig};
 
@@ -220,6 +221,7 @@ impl AgentExecutor {
         tools: &[Value],
         sampler_config: Option<&SamplerConfig>,
     ) -> Result<Value, ApiError> {
+        let _slot = acquire_provider_slot(&config.provider).await;
         match config.provider.as_str() {
             "ollama" => self.call_ollama_with_tools(config, messages, tools, sampler_config).await,
             "llamacpp" => self.call_openai_compatible_with_tools(config, messages, tools, sampler_config, "LLAMACPP_BASE_URL").await,
@@ -8,6 +8,7 @@ use log::{debug, error, warn, info};
 use sqlx::SqlitePool;
 use crate::api::errors::ApiError;
 use super::model_config::{get_model_config, ModelConfiguration};
+use super::provider_manager::acquire_provider_slot;
 use super::sampler_config::{get_sampler_config, SamplerConfig};
 
 /// Providers that support email drafting
@@ -146,6 +147,7 @@ Draft email body:"#,
         prompt: &str,
         sampler_config: Option<&SamplerConfig>,
     ) -> Result<String, ApiError> {
+        let _slot = acquire_provider_slot(&config.provider).await;
         match config.provider.as_str() {
             "ollama" => self.generate_with_ollama(config, prompt, sampler_config).await,
             "openai" => self.generate_with_openai(config, prompt, sampler_config).await,
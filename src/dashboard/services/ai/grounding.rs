@@ -0,0 +1,104 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// src/dashboard/services/ai/grounding.rs
+// Strict grounding for the chatbot: answers about mailbox contents must come
+// from tool results, never from the model's guesses.
+
+use serde_json::Value;
+
+/// System prompt addition used when strict grounding is enabled.
+pub const STRICT_GROUNDING_INSTRUCTION: &str = "\n\nGROUNDING RULES (strict):\n\
+- Only state counts, senders, subjects, dates or other mailbox facts that appear in a TOOL_RESULT in this conversation.\n\
+- Before answering any question about the user's emails or folders, call a tool to retrieve the data.\n\
+- If a tool returns an empty list or a zero count, say that nothing matched. If it returns an error, say plainly that no data is available. Never estimate or invent numbers.";
+
+/// Sent back to the model when it answers a data question without calling a tool.
+pub const RETRIEVE_FIRST_REMINDER: &str = "You answered a question about the mailbox without retrieving any data. \
+Call the appropriate tool first and answer only from its result. If no tool can provide the data, say so.";
+
+/// Final answer when the model never retrieved data for a data question.
+pub const NO_DATA_RESPONSE: &str = "I couldn't retrieve any email data to answer that, so I won't guess. \
+The cache may be empty or the account may not have synced yet. Try syncing the account and ask again.";
+
+/// Chatbot grounding settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GroundingConfig {
+    /// `AI_STRICT_GROUNDING` (default false)
+    pub strict: bool,
+}
+
+impl GroundingConfig {
+    pub fn from_env() -> Self {
+        let strict = std::env::var("AI_STRICT_GROUNDING")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        Self { strict }
+    }
+}
+
+/// Words that indicate the user is asking about mailbox contents rather than,
+/// say, asking for help drafting text. Matched as whole words, a trailing
+/// plural "s" allowed, so "count" doesn't fire on "account".
+const DATA_KEYWORDS: &[&str] = &[
+    "how many", "count", "number of", "unread", "inbox", "folder", "latest", "recent",
+    "newest", "oldest", "last email", "emails from", "messages from", "any email",
+    "any message", "list", "show me", "find", "search", "who sent", "did i get",
+    "did i receive", "attachment", "flagged", "starred",
+];
+
+/// Whether answering `query` requires data from the mailbox.
+pub fn is_data_question(query: &str) -> bool {
+    let lower = query.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    DATA_KEYWORDS.iter().any(|keyword| {
+        let keyword: Vec<&str> = keyword.split(' ').collect();
+        words.windows(keyword.len()).any(|window| {
+            window.iter().zip(&keyword).all(|(word, k)| word == k || word.strip_suffix('s') == Some(*k))
+        })
+    })
+}
+
+/// Whether a tool call retrieved an answer: `success` is not false. An empty
+/// list is an answer too (nothing matched), not a failed retrieval.
+pub fn tool_result_retrieved(result: &Value) -> bool {
+    result.get("success").and_then(|v| v.as_bool()) != Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_is_data_question() {
+        assert!(is_data_question("How many unread emails do I have?"));
+        assert!(is_data_question("Show me the latest message from Alice"));
+        assert!(!is_data_question("Hello!"));
+        assert!(!is_data_question("Help me write a polite decline"));
+        assert!(is_data_question("Which folders have attachments?"));
+    }
+
+    #[test]
+    fn test_is_data_question_matches_whole_words() {
+        assert!(!is_data_question("What account settings should I use?"));
+        assert!(!is_data_question("Can you listen to my concerns?"));
+        assert!(!is_data_question("Help me write a note to my financial advisor"));
+        assert!(is_data_question("Count the messages from Bob"));
+    }
+
+    #[test]
+    fn test_tool_result_retrieved() {
+        assert!(tool_result_retrieved(&json!({"success": true, "data": [{"uid": 1}]})));
+        assert!(tool_result_retrieved(&json!({"success": true, "data": {"count": 0}})));
+        assert!(tool_result_retrieved(&json!({"success": true, "data": []})));
+        assert!(!tool_result_retrieved(&json!({"success": false, "data": [1]})));
+        assert!(!tool_result_retrieved(&json!({"success": false, "error": "Account not found"})));
+    }
+}
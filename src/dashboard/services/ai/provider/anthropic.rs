@@ -9,8 +9,9 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use log::{debug, warn, error};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout}; // Import trait, common message struct, and timeout helper
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, request_error, status_error}; // Import trait, common message struct, and timeout helper
 use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 
 // Get Anthropic API base URL from environment or use default
 fn get_base_url() -> String {
@@ -42,6 +43,14 @@ struct AnthropicMessagesResponse {
     content: Vec<AnthropicContent>,
     #[serde(default)]
     stop_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
 }
 
 #[derive(Deserialize)]
@@ -125,15 +134,13 @@ impl AiProvider for AnthropicAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Anthropic: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Anthropic: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Anthropic API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Anthropic API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Anthropic API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -141,6 +148,10 @@ impl AiProvider for AnthropicAdapter {
             .await
             .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize Anthropic response: {}", e) })?;
 
+        if let Some(usage) = &response_body.usage {
+            usage::report(TokenUsage::new(usage.input_tokens, usage.output_tokens));
+        }
+
         // Extract the first content block's text
         if let Some(content) = response_body.content.first() {
             debug!("Received response from Anthropic API.");
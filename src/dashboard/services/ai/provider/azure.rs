@@ -10,8 +10,9 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use log::{debug, warn, error};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout};
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, request_error, status_error};
 use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 
 const DEFAULT_AZURE_API_VERSION: &str = "2024-10-01";
 const DEFAULT_AZURE_DEPLOYMENT: &str = "gpt-4";
@@ -29,6 +30,7 @@ struct AzureChatRequest {
 #[derive(Deserialize)]
 struct AzureChatResponse {
     choices: Vec<AzureChoice>,
+    usage: Option<TokenUsage>,
 }
 
 #[derive(Deserialize)]
@@ -109,15 +111,13 @@ impl AiProvider for AzureOpenAIAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Azure OpenAI: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Azure OpenAI: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Azure OpenAI API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Azure OpenAI API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Azure OpenAI API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -125,6 +125,10 @@ impl AiProvider for AzureOpenAIAdapter {
             .await
             .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize Azure OpenAI response: {}", e) })?;
 
+        if let Some(usage) = response_body.usage {
+            usage::report(usage);
+        }
+
         if let Some(choice) = response_body.choices.first() {
             debug!("Received response from Azure OpenAI API.");
             Ok(choice.message.content.clone())
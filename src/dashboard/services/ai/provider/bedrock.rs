@@ -0,0 +1,491 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// src/dashboard/services/ai/provider/bedrock.rs
+// AWS Bedrock: SigV4-signed InvokeModel / InvokeModelWithResponseStream calls.
+// Credentials and region come from the default AWS chain (environment,
+// AWS_PROFILE, SSO, instance or task role). Claude (anthropic.*) and Titan
+// (amazon.titan-text-*) request formats are supported.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_credential_types::provider::ProvideCredentials;
+use aws_sigv4::http_request::{sign, SignableBody, SignableRequest, SigningParams, SigningSettings};
+use aws_sigv4::sign::v4;
+use base64::Engine;
+use futures::stream::StreamExt;
+use log::{debug, error};
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::sync::OnceCell;
+
+use super::{AiProvider, AiChatMessage, ResponseStream, get_ai_request_timeout, get_ai_generation_timeout, request_error, status_error};
+use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
+
+const DEFAULT_BEDROCK_MODEL: &str = "anthropic.claude-3-5-sonnet-20240620-v1:0";
+/// Version string Bedrock requires in Claude request bodies
+const BEDROCK_ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+const MAX_TOKENS: usize = 2000;
+const TEMPERATURE: f32 = 0.7;
+
+#[derive(Clone)]
+pub struct BedrockAdapter {
+    http_client: Client,
+    model: String,
+    /// Model ids reported by `get_available_models`
+    models: Vec<String>,
+    /// BEDROCK_REGION, otherwise the region from the AWS config chain
+    region: Option<String>,
+    /// BEDROCK_ENDPOINT, e.g. a VPC endpoint; defaults to the regional endpoint
+    endpoint: Option<String>,
+    /// Loaded on first use, since the credential chain is async
+    aws_config: Arc<OnceCell<SdkConfig>>,
+}
+
+impl BedrockAdapter {
+    pub fn new(http_client: Client) -> Self {
+        let model = std::env::var("BEDROCK_MODEL")
+            .unwrap_or_else(|_| DEFAULT_BEDROCK_MODEL.to_string());
+        let mut models: Vec<String> = std::env::var("BEDROCK_MODELS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty())
+            .collect();
+        if !models.contains(&model) {
+            models.insert(0, model.clone());
+        }
+
+        Self {
+            http_client,
+            model,
+            models,
+            region: std::env::var("BEDROCK_REGION").ok().filter(|r| !r.is_empty()),
+            endpoint: std::env::var("BEDROCK_ENDPOINT").ok().filter(|e| !e.is_empty()),
+            aws_config: Arc::new(OnceCell::new()),
+        }
+    }
+
+    // Allow setting a different model
+    #[allow(dead_code)]
+    pub fn with_model(mut self, model: String) -> Self {
+        if !self.models.contains(&model) {
+            self.models.insert(0, model.clone());
+        }
+        self.model = model;
+        self
+    }
+
+    async fn aws_config(&self) -> &SdkConfig {
+        self.aws_config
+            .get_or_init(|| aws_config::defaults(BehaviorVersion::latest()).load())
+            .await
+    }
+
+    /// POST `body` to `/model/{model}/{action}`, signed with SigV4.
+    async fn signed_request(&self, action: &str, accept: &str, body: Vec<u8>) -> Result<reqwest::RequestBuilder, RestApiError> {
+        let config = self.aws_config().await;
+        let region = self.region.clone()
+            .or_else(|| config.region().map(|r| r.to_string()))
+            .ok_or_else(|| RestApiError::UnprocessableEntity {
+                message: "No AWS region for Bedrock; set BEDROCK_REGION or AWS_REGION".to_string()
+            })?;
+        let credentials = config.credentials_provider()
+            .ok_or_else(|| RestApiError::UnprocessableEntity {
+                message: "No AWS credentials provider configured for Bedrock".to_string()
+            })?
+            .provide_credentials()
+            .await
+            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Bedrock: failed to load AWS credentials: {}", e) })?;
+
+        let endpoint = self.endpoint.clone()
+            .unwrap_or_else(|| format!("https://bedrock-runtime.{}.amazonaws.com", region));
+        // Model ids contain ':'; the signer encodes the path again, as SigV4 expects
+        let url = format!("{}/model/{}/{}", endpoint.trim_end_matches('/'), urlencoding::encode(&self.model), action);
+
+        let identity = credentials.into();
+        let signing_params: SigningParams = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&region)
+            .name("bedrock")
+            .time(SystemTime::now())
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|e| RestApiError::InternalError { message: format!("Bedrock: invalid signing parameters: {}", e) })?
+            .into();
+        let headers = [("content-type", "application/json"), ("accept", accept)];
+        let signable = SignableRequest::new("POST", &url, headers.into_iter(), SignableBody::Bytes(&body))
+            .map_err(|e| RestApiError::InternalError { message: format!("Bedrock: failed to sign request: {}", e) })?;
+        let (instructions, _signature) = sign(signable, &signing_params)
+            .map_err(|e| RestApiError::InternalError { message: format!("Bedrock: failed to sign request: {}", e) })?
+            .into_parts();
+
+        let mut request = self.http_client
+            .post(&url)
+            .header("content-type", "application/json")
+            .header("accept", accept);
+        for (name, value) in instructions.headers() {
+            request = request.header(name, value);
+        }
+        Ok(request.body(body))
+    }
+}
+
+fn is_anthropic(model: &str) -> bool {
+    // Also matches cross-region inference profiles such as "us.anthropic.claude-..."
+    model.starts_with("anthropic.") || model.contains(".anthropic.")
+}
+
+/// InvokeModel body in the format of the model's family.
+fn request_body(model: &str, messages: &[AiChatMessage]) -> Value {
+    if is_anthropic(model) {
+        let system = messages.iter()
+            .filter(|m| m.role == "system")
+            .map(|m| m.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let turns: Vec<Value> = messages.iter()
+            .filter(|m| m.role != "system")
+            .map(|m| json!({"role": m.role, "content": m.content}))
+            .collect();
+        let mut body = json!({
+            "anthropic_version": BEDROCK_ANTHROPIC_VERSION,
+            "max_tokens": MAX_TOKENS,
+            "temperature": TEMPERATURE,
+            "messages": turns,
+        });
+        if !system.is_empty() {
+            body["system"] = Value::String(system);
+        }
+        body
+    } else {
+        // Titan text models take a single prompt
+        let mut prompt = String::new();
+        for message in messages {
+            let speaker = match message.role.as_str() {
+                "assistant" => "Bot",
+                "system" => "System",
+                _ => "User",
+            };
+            prompt.push_str(&format!("{}: {}\n", speaker, message.content));
+        }
+        prompt.push_str("Bot:");
+        json!({
+            "inputText": prompt,
+            "textGenerationConfig": {
+                "maxTokenCount": MAX_TOKENS,
+                "temperature": TEMPERATURE,
+            }
+        })
+    }
+}
+
+/// Generated text of an InvokeModel response.
+fn response_text(model: &str, body: &Value) -> Option<String> {
+    let text = if is_anthropic(model) {
+        body.get("content")?.as_array()?.iter()
+            .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect::<String>()
+    } else {
+        body.pointer("/results/0/outputText")?.as_str()?.to_string()
+    };
+    Some(text).filter(|t| !t.is_empty())
+}
+
+/// Token counts of an InvokeModel response.
+fn response_usage(model: &str, body: &Value) -> Option<TokenUsage> {
+    if is_anthropic(model) {
+        let usage = body.get("usage")?;
+        Some(TokenUsage::new(usage.get("input_tokens")?.as_u64()?, usage.get("output_tokens")?.as_u64()?))
+    } else {
+        Some(TokenUsage::new(
+            body.get("inputTextTokenCount")?.as_u64()?,
+            body.pointer("/results/0/tokenCount")?.as_u64()?,
+        ))
+    }
+}
+
+/// Token counts Bedrock adds to the last chunk of a stream.
+fn stream_chunk_usage(chunk: &Value) -> Option<TokenUsage> {
+    let metrics = chunk.get("amazon-bedrock-invocationMetrics")?;
+    Some(TokenUsage::new(metrics.get("inputTokenCount")?.as_u64()?, metrics.get("outputTokenCount")?.as_u64()?))
+}
+
+/// Text carried by one decoded stream chunk, if any.
+fn stream_chunk_text(model: &str, chunk: &Value) -> Option<String> {
+    let text = if is_anthropic(model) {
+        if chunk.get("type").and_then(|t| t.as_str()) != Some("content_block_delta") {
+            return None;
+        }
+        chunk.pointer("/delta/text")?.as_str()?
+    } else {
+        chunk.get("outputText")?.as_str()?
+    };
+    Some(text.to_string()).filter(|t| !t.is_empty())
+}
+
+/// One message of an `application/vnd.amazon.eventstream` body.
+#[derive(Debug, PartialEq)]
+struct EventMessage {
+    headers: HashMap<String, String>,
+    payload: Vec<u8>,
+}
+
+/// Splits an AWS event stream into messages, keeping a partial message
+/// until the rest of it arrives. CRCs are not checked; TLS already
+/// protects the body.
+#[derive(Default)]
+struct EventStreamDecoder {
+    pending: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    fn push(&mut self, bytes: &[u8]) -> Result<Vec<EventMessage>, String> {
+        self.pending.extend_from_slice(bytes);
+        let mut messages = Vec::new();
+        while self.pending.len() >= 12 {
+            let total = read_u32(&self.pending, 0) as usize;
+            let headers_len = read_u32(&self.pending, 4) as usize;
+            if total < 16 + headers_len {
+                return Err(format!("invalid event stream message length {}", total));
+            }
+            if self.pending.len() < total {
+                break;
+            }
+            let message: Vec<u8> = self.pending.drain(..total).collect();
+            let headers = parse_headers(&message[12..12 + headers_len])?;
+            let payload = message[12 + headers_len..total - 4].to_vec();
+            messages.push(EventMessage { headers, payload });
+        }
+        Ok(messages)
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// String-valued headers of an event stream message; other value types
+/// are skipped.
+fn parse_headers(mut bytes: &[u8]) -> Result<HashMap<String, String>, String> {
+    let truncated = || "truncated event stream header".to_string();
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_len = bytes[0] as usize;
+        let name = bytes.get(1..1 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        bytes = &bytes[1 + name_len..];
+        let value_type = *bytes.first().ok_or_else(truncated)?;
+        bytes = &bytes[1..];
+        let fixed = match value_type {
+            0 | 1 => Some(0),
+            2 => Some(1),
+            3 => Some(2),
+            4 => Some(4),
+            5 | 8 => Some(8),
+            9 => Some(16),
+            6 | 7 => None,
+            other => return Err(format!("unknown event stream header type {}", other)),
+        };
+        let len = match fixed {
+            Some(len) => len,
+            None => {
+                let len = bytes.get(..2).ok_or_else(truncated)?;
+                bytes = &bytes[2..];
+                u16::from_be_bytes([len[0], len[1]]) as usize
+            }
+        };
+        let value = bytes.get(..len).ok_or_else(truncated)?;
+        if value_type == 7 {
+            headers.insert(name, String::from_utf8_lossy(value).to_string());
+        }
+        bytes = &bytes[len..];
+    }
+    Ok(headers)
+}
+
+/// The model's own JSON chunk inside an event stream `chunk` event.
+fn decode_chunk(payload: &[u8]) -> Result<Value, RestApiError> {
+    let parse_error = |e: String| RestApiError::UnprocessableEntity { message: format!("Failed to parse Bedrock stream chunk: {}", e) };
+    let envelope: Value = serde_json::from_slice(payload).map_err(|e| parse_error(e.to_string()))?;
+    let bytes = envelope.get("bytes").and_then(|b| b.as_str())
+        .ok_or_else(|| parse_error("missing bytes".to_string()))?;
+    let decoded = base64::engine::general_purpose::STANDARD.decode(bytes).map_err(|e| parse_error(e.to_string()))?;
+    serde_json::from_slice(&decoded).map_err(|e| parse_error(e.to_string()))
+}
+
+#[async_trait]
+impl AiProvider for BedrockAdapter {
+    async fn get_available_models(&self) -> Result<Vec<String>, RestApiError> {
+        // Bedrock's model list is a separate control-plane API with its own
+        // permissions; report the configured models instead
+        debug!("Returning configured Bedrock models");
+        Ok(self.models.clone())
+    }
+
+    async fn generate_response(&self, messages: &[AiChatMessage]) -> Result<String, RestApiError> {
+        let body = serde_json::to_vec(&request_body(&self.model, messages))
+            .map_err(|e| RestApiError::InternalError { message: format!("Failed to encode Bedrock request: {}", e) })?;
+
+        debug!("Sending InvokeModel request to Bedrock: model={}, messages_count={}", self.model, messages.len());
+
+        let response = self.signed_request("invoke", "application/json", body).await?
+            .timeout(get_ai_request_timeout())
+            .send()
+            .await
+            .map_err(|e| request_error(&e, format!("Bedrock: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
+            error!("Bedrock API request failed with status {}: {}", status, error_body);
+            return Err(status_error(status, format!("Bedrock API returned error status {}: {}", status, error_body)));
+        }
+
+        let response_body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize Bedrock response: {}", e) })?;
+
+        if let Some(tokens) = response_usage(&self.model, &response_body) {
+            usage::report(tokens);
+        }
+
+        response_text(&self.model, &response_body)
+            .ok_or_else(|| RestApiError::UnprocessableEntity { message: "Bedrock response was empty or missing content".to_string() })
+    }
+
+    fn generate_response_stream<'a>(&'a self, messages: &'a [AiChatMessage]) -> ResponseStream<'a> {
+        async_stream::try_stream! {
+            let body = serde_json::to_vec(&request_body(&self.model, messages))
+                .map_err(|e| RestApiError::InternalError { message: format!("Failed to encode Bedrock request: {}", e) })?;
+
+            let response = self.signed_request("invoke-with-response-stream", "application/vnd.amazon.eventstream", body).await?
+                .timeout(get_ai_generation_timeout())
+                .send()
+                .await
+                .map_err(|e| request_error(&e, format!("Bedrock: {}", e)))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
+                error!("Bedrock API request failed with status {}: {}", status, error_body);
+                Err::<(), _>(status_error(status, format!("Bedrock API returned error status {}: {}", status, error_body)))?;
+            }
+
+            let mut body = response.bytes_stream();
+            let mut decoder = EventStreamDecoder::default();
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk
+                    .map_err(|e| request_error(&e, format!("Bedrock stream: {}", e)))?;
+                let events = decoder.push(&chunk)
+                    .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Bedrock stream: {}", e) })?;
+                for event in events {
+                    if event.headers.get(":message-type").map(String::as_str) == Some("exception") {
+                        let kind = event.headers.get(":exception-type").cloned().unwrap_or_default();
+                        let service = format!("Bedrock stream error {}: {}", kind, String::from_utf8_lossy(&event.payload));
+                        Err::<(), _>(match kind.as_str() {
+                            "throttlingException" => RestApiError::RateLimitExceeded { message: service },
+                            "internalServerException" | "serviceUnavailableException" | "modelStreamErrorException" => {
+                                RestApiError::UpstreamUnavailable { service }
+                            }
+                            _ => RestApiError::ServiceUnavailable { service },
+                        })?;
+                    }
+                    if event.headers.get(":event-type").map(String::as_str) != Some("chunk") {
+                        continue;
+                    }
+                    let chunk = decode_chunk(&event.payload)?;
+                    if let Some(tokens) = stream_chunk_usage(&chunk) {
+                        usage::report(tokens);
+                    }
+                    if let Some(text) = stream_chunk_text(&self.model, &chunk) {
+                        yield text;
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let total = 16 + header_bytes.len() + payload.len();
+        let mut message = Vec::new();
+        message.extend_from_slice(&(total as u32).to_be_bytes());
+        message.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        message.extend_from_slice(&[0; 4]);
+        message.extend_from_slice(&header_bytes);
+        message.extend_from_slice(payload);
+        message.extend_from_slice(&[0; 4]);
+        message
+    }
+
+    #[test]
+    fn test_event_stream_chunks_split_across_reads() {
+        let delta = json!({"type": "content_block_delta", "delta": {"type": "text_delta", "text": "Hello"}});
+        let payload = json!({"bytes": base64::engine::general_purpose::STANDARD.encode(delta.to_string())}).to_string();
+        let mut bytes = event(&[(":event-type", "chunk"), (":message-type", "event")], payload.as_bytes());
+        bytes.extend(event(&[(":message-type", "exception"), (":exception-type", "throttlingException")], b"{}"));
+
+        let mut decoder = EventStreamDecoder::default();
+        assert!(decoder.push(&bytes[..20]).unwrap().is_empty());
+        let events = decoder.push(&bytes[20..]).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].headers.get(":event-type").map(String::as_str), Some("chunk"));
+        assert_eq!(events[1].headers.get(":exception-type").map(String::as_str), Some("throttlingException"));
+
+        let model = "us.anthropic.claude-3-5-sonnet-20240620-v1:0";
+        let chunk = decode_chunk(&events[0].payload).unwrap();
+        assert_eq!(stream_chunk_text(model, &chunk), Some("Hello".to_string()));
+        assert_eq!(stream_chunk_text(model, &json!({"type": "message_stop"})), None);
+        assert_eq!(stream_chunk_text("amazon.titan-text-express-v1", &json!({"outputText": "Hi"})), Some("Hi".to_string()));
+    }
+
+    #[test]
+    fn test_request_and_response_formats() {
+        let messages = vec![
+            AiChatMessage { role: "system".to_string(), content: "Be brief".to_string() },
+            AiChatMessage { role: "user".to_string(), content: "Hi".to_string() },
+        ];
+
+        let claude = request_body("anthropic.claude-3-haiku-20240307-v1:0", &messages);
+        assert_eq!(claude["anthropic_version"], BEDROCK_ANTHROPIC_VERSION);
+        assert_eq!(claude["system"], "Be brief");
+        assert_eq!(claude["messages"].as_array().unwrap().len(), 1);
+        let reply = json!({"content": [{"type": "text", "text": "Hello"}]});
+        assert_eq!(response_text("anthropic.claude-3-haiku-20240307-v1:0", &reply), Some("Hello".to_string()));
+
+        let titan = request_body("amazon.titan-text-express-v1", &messages);
+        assert_eq!(titan["inputText"], "System: Be brief\nUser: Hi\nBot:");
+        let reply = json!({"results": [{"outputText": "Hello"}]});
+        assert_eq!(response_text("amazon.titan-text-express-v1", &reply), Some("Hello".to_string()));
+
+        let reply = json!({"content": [], "usage": {"input_tokens": 12, "output_tokens": 3}});
+        assert_eq!(response_usage("anthropic.claude-3-haiku-20240307-v1:0", &reply), Some(TokenUsage::new(12, 3)));
+        let reply = json!({"inputTextTokenCount": 7, "results": [{"tokenCount": 2, "outputText": "Hi"}]});
+        assert_eq!(response_usage("amazon.titan-text-express-v1", &reply), Some(TokenUsage::new(7, 2)));
+        let last = json!({"type": "message_stop", "amazon-bedrock-invocationMetrics": {"inputTokenCount": 9, "outputTokenCount": 4}});
+        assert_eq!(stream_chunk_usage(&last), Some(TokenUsage::new(9, 4)));
+    }
+}
@@ -10,8 +10,9 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use log::{debug, warn, error};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout};
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, request_error, status_error};
 use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 
 // Get Gemini API base URL from environment or use default
 fn get_base_url() -> String {
@@ -47,8 +48,20 @@ struct GeminiGenerationConfig {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiGenerateResponse {
     candidates: Vec<GeminiCandidate>,
+    #[serde(default)]
+    usage_metadata: Option<GeminiUsageMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
 }
 
 #[derive(Deserialize)]
@@ -121,15 +134,13 @@ impl AiProvider for GeminiAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Gemini models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Gemini models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Gemini models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Gemini models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Gemini models API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -180,15 +191,13 @@ impl AiProvider for GeminiAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Gemini: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Gemini: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Gemini API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Gemini API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Gemini API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -196,6 +205,10 @@ impl AiProvider for GeminiAdapter {
             .await
             .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize Gemini response: {}", e) })?;
 
+        if let Some(usage) = &response_body.usage_metadata {
+            usage::report(TokenUsage::new(usage.prompt_token_count, usage.candidates_token_count));
+        }
+
         // Extract the first candidate's first part's text
         if let Some(candidate) = response_body.candidates.first() {
             if let Some(part) = candidate.content.parts.first() {
@@ -10,8 +10,9 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use log::{debug, warn, error, info};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout, get_ai_generation_timeout};
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, get_ai_generation_timeout, request_error, status_error};
 use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 use crate::dashboard::services::ai::sampler_config::SamplerConfig;
 
 // Default sampler settings for tool-calling
@@ -278,15 +279,13 @@ impl AiProvider for LlamaCppAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("llama.cpp models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("llama.cpp models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("llama.cpp models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("llama.cpp models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("llama.cpp models API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -334,15 +333,13 @@ impl AiProvider for LlamaCppAdapter {
             .timeout(get_ai_generation_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("llama.cpp: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("llama.cpp: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("llama.cpp API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("llama.cpp API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("llama.cpp API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -354,6 +351,9 @@ impl AiProvider for LlamaCppAdapter {
             if let Some(usage) = &response_body.usage {
                 info!("llama.cpp response complete. Tokens: prompt={:?}, completion={:?}, total={:?}",
                       usage.prompt_tokens, usage.completion_tokens, usage.total_tokens);
+                if let (Some(prompt), Some(completion)) = (usage.prompt_tokens, usage.completion_tokens) {
+                    usage::report(TokenUsage::new(prompt as u64, completion as u64));
+                }
             }
             Ok(choice.message.content.clone())
         } else {
@@ -417,15 +417,13 @@ impl AiProvider for LlamaCppAdapter {
             .timeout(get_ai_generation_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("llama.cpp: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("llama.cpp: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("llama.cpp API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("llama.cpp API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("llama.cpp API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -437,6 +435,9 @@ impl AiProvider for LlamaCppAdapter {
             if let Some(usage) = &response_body.usage {
                 info!("llama.cpp response complete. Tokens: prompt={:?}, completion={:?}, total={:?}",
                       usage.prompt_tokens, usage.completion_tokens, usage.total_tokens);
+                if let (Some(prompt), Some(completion)) = (usage.prompt_tokens, usage.completion_tokens) {
+                    usage::report(TokenUsage::new(prompt as u64, completion as u64));
+                }
             }
             Ok(choice.message.content.clone())
         } else {
@@ -10,8 +10,9 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use log::{debug, warn, error, info};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout, get_ai_generation_timeout};
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, get_ai_generation_timeout, request_error, status_error};
 use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 use crate::dashboard::services::ai::sampler_config::SamplerConfig;
 
 // Default sampler settings for tool-calling (same as llama.cpp)
@@ -234,15 +235,13 @@ impl AiProvider for LmStudioAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("LM Studio models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("LM Studio models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("LM Studio models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("LM Studio models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("LM Studio models API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -290,15 +289,13 @@ impl AiProvider for LmStudioAdapter {
             .timeout(get_ai_generation_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("LM Studio: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("LM Studio: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("LM Studio API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("LM Studio API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("LM Studio API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -310,6 +307,9 @@ impl AiProvider for LmStudioAdapter {
             if let Some(usage) = &response_body.usage {
                 info!("LM Studio response complete. Tokens: prompt={:?}, completion={:?}, total={:?}",
                       usage.prompt_tokens, usage.completion_tokens, usage.total_tokens);
+                if let (Some(prompt), Some(completion)) = (usage.prompt_tokens, usage.completion_tokens) {
+                    usage::report(TokenUsage::new(prompt as u64, completion as u64));
+                }
             }
             Ok(choice.message.content.clone())
         } else {
@@ -365,15 +365,13 @@ impl AiProvider for LmStudioAdapter {
             .timeout(get_ai_generation_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("LM Studio: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("LM Studio: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("LM Studio API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("LM Studio API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("LM Studio API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -385,6 +383,9 @@ impl AiProvider for LmStudioAdapter {
             if let Some(usage) = &response_body.usage {
                 info!("LM Studio response complete. Tokens: prompt={:?}, completion={:?}, total={:?}",
                       usage.prompt_tokens, usage.completion_tokens, usage.total_tokens);
+                if let (Some(prompt), Some(completion)) = (usage.prompt_tokens, usage.completion_tokens) {
+                    usage::report(TokenUsage::new(prompt as u64, completion as u64));
+                }
             }
             Ok(choice.message.content.clone())
         } else {
@@ -4,9 +4,11 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
 use serde::{Serialize, Deserialize};
 use crate::api::errors::ApiError as RestApiError;
 use crate::dashboard::services::ai::sampler_config::SamplerConfig;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 use std::time::Duration;
 
 /// Get the standard AI request timeout from environment variable or use default (30 seconds)
@@ -29,6 +31,29 @@ pub fn get_ai_generation_timeout() -> Duration {
     )
 }
 
+/// Error for a non-success HTTP status from a provider API. Rate limits,
+/// timeouts and server errors get the variants the retry policy retries.
+pub fn status_error(status: reqwest::StatusCode, service: String) -> RestApiError {
+    match status.as_u16() {
+        429 => RestApiError::RateLimitExceeded { message: service },
+        408 | 504 => RestApiError::GatewayTimeout { service },
+        500..=599 => RestApiError::UpstreamUnavailable { service },
+        _ => RestApiError::ServiceUnavailable { service },
+    }
+}
+
+/// Error for a provider request that got no response: timeouts and
+/// connection failures are transient, a request that couldn't be built is not.
+pub fn request_error(error: &reqwest::Error, service: String) -> RestApiError {
+    if error.is_timeout() {
+        RestApiError::GatewayTimeout { service }
+    } else if error.is_builder() {
+        RestApiError::ServiceUnavailable { service }
+    } else {
+        RestApiError::UpstreamUnavailable { service }
+    }
+}
+
 pub mod openai;
 pub mod openrouter;
 pub mod morpheus;
@@ -42,6 +67,7 @@ pub mod gemini;
 pub mod mistral;
 pub mod together;
 pub mod azure;
+pub mod bedrock;
 
 /// Common message structure for AI chat completion APIs (OpenAI, OpenRouter)
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -50,6 +76,9 @@ pub struct AiChatMessage {
     pub content: String,
 }
 
+/// Response text as it is generated, one chunk at a time.
+pub type ResponseStream<'a> = BoxStream<'a, Result<String, RestApiError>>;
+
 /// Trait defining the interface for an AI chat completion provider.
 #[async_trait]
 pub trait AiProvider: Send + Sync {
@@ -64,6 +93,14 @@ pub trait AiProvider: Send + Sync {
     /// A `Result` containing the AI's response text (`String`) or an `ApiError`.
     async fn generate_response(&self, messages: &[AiChatMessage]) -> Result<String, RestApiError>;
 
+    /// Streams the response as the provider generates it.
+    ///
+    /// Default implementation yields the whole `generate_response` result as
+    /// a single chunk; providers with a streaming API override this.
+    fn generate_response_stream<'a>(&'a self, messages: &'a [AiChatMessage]) -> ResponseStream<'a> {
+        futures::stream::once(self.generate_response(messages)).boxed()
+    }
+
     /// Generates a response with custom sampler configuration.
     /// This allows applying per-model sampler settings from the database.
     ///
@@ -87,6 +124,116 @@ pub trait AiProvider: Send + Sync {
     async fn get_available_models(&self) -> Result<Vec<String>, RestApiError>;
 }
 
+/// Concatenate a response stream, for callers that want the whole text.
+pub async fn collect_response(mut stream: ResponseStream<'_>, provider: &str) -> Result<String, RestApiError> {
+    let mut text = String::new();
+    while let Some(chunk) = stream.next().await {
+        text.push_str(&chunk?);
+    }
+    if text.is_empty() {
+        return Err(RestApiError::UnprocessableEntity { message: format!("{} response was empty", provider) });
+    }
+    Ok(text)
+}
+
+/// `stream_options` asking an OpenAI-compatible API to end the stream with a
+/// chunk carrying the request's token usage.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub(crate) struct StreamOptions {
+    include_usage: bool,
+}
+
+impl StreamOptions {
+    pub(crate) const WITH_USAGE: Self = Self { include_usage: true };
+}
+
+/// Send an OpenAI-compatible chat completion request made with
+/// `"stream": true` and yield each content delta as it arrives. A usage
+/// chunk, if the API sends one, is passed to `usage::report`.
+pub(crate) fn openai_compatible_stream(provider: &'static str, request: reqwest::RequestBuilder) -> ResponseStream<'static> {
+    async_stream::try_stream! {
+        let response = request.send().await
+            .map_err(|e| request_error(&e, format!("{}: {}", provider, e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
+            log::error!("{} API request failed with status {}: {}", provider, status, error_body);
+            Err::<(), _>(status_error(status, format!("{} API returned error status {}: {}", provider, status, error_body)))?;
+        }
+
+        let mut body = response.bytes_stream();
+        let mut lines = SseLines::default();
+        let mut done = false;
+        while !done {
+            let Some(chunk) = body.next().await else { break };
+            let chunk = chunk
+                .map_err(|e| request_error(&e, format!("{} stream: {}", provider, e)))?;
+            for data in lines.push(&chunk) {
+                match parse_stream_delta(provider, &data)? {
+                    StreamDelta::Text(text) => yield text,
+                    StreamDelta::Usage(tokens) => usage::report(tokens),
+                    StreamDelta::Empty => {}
+                    StreamDelta::Done => {
+                        done = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    .boxed()
+}
+
+/// Splits a server-sent event body into the payloads of its `data:` lines,
+/// keeping a partial line until the rest of it arrives.
+#[derive(Default)]
+struct SseLines {
+    pending: Vec<u8>,
+}
+
+impl SseLines {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let mut data = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(payload) = line.trim_end_matches(['\r', '\n']).strip_prefix("data:") {
+                data.push(payload.trim_start().to_string());
+            }
+        }
+        data
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum StreamDelta {
+    Text(String),
+    Usage(TokenUsage),
+    Empty,
+    Done,
+}
+
+/// The content delta of one streamed chat completion chunk.
+fn parse_stream_delta(provider: &str, data: &str) -> Result<StreamDelta, RestApiError> {
+    if data == "[DONE]" {
+        return Ok(StreamDelta::Done);
+    }
+    let chunk: serde_json::Value = serde_json::from_str(data)
+        .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to parse {} stream chunk: {}", provider, e) })?;
+    if let Some(error) = chunk.get("error") {
+        return Err(RestApiError::ServiceUnavailable { service: format!("{} stream error: {}", provider, error) });
+    }
+    match chunk.pointer("/choices/0/delta/content").and_then(|c| c.as_str()) {
+        Some(text) if !text.is_empty() => Ok(StreamDelta::Text(text.to_string())),
+        _ => match chunk.get("usage").and_then(|u| TokenUsage::deserialize(u).ok()) {
+            Some(tokens) => Ok(StreamDelta::Usage(tokens)),
+            None => Ok(StreamDelta::Empty),
+        },
+    }
+}
+
 // Re-export the provider implementations for easier access
 pub use openai::OpenAiAdapter;
 pub use openrouter::OpenRouterAdapter;
@@ -101,6 +248,7 @@ pub use gemini::GeminiAdapter;
 pub use mistral::MistralAdapter;
 pub use together::TogetherAdapter;
 pub use azure::AzureOpenAIAdapter;
+pub use bedrock::BedrockAdapter;
 
 // --- Mock Provider Implementation ---
 #[derive(Debug, Default)]
@@ -158,4 +306,26 @@ impl AiProvider for MockAiProvider {
 
 // --- Comment out mock module as it doesn't exist --- 
 // mod mock; 
-// pub use mock::MockAiProvider; 
\ No newline at end of file
+// pub use mock::MockAiProvider; 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_chunks_split_across_reads() {
+        let mut lines = SseLines::default();
+        assert!(lines.push(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel").is_empty());
+        let data = lines.push(b"lo\"}}]}\r\n\n: keep-alive\ndata: [DONE]\n");
+        assert_eq!(data.len(), 2);
+
+        assert_eq!(parse_stream_delta("Test", &data[0]).unwrap(), StreamDelta::Text("Hello".to_string()));
+        assert_eq!(parse_stream_delta("Test", &data[1]).unwrap(), StreamDelta::Done);
+        assert_eq!(parse_stream_delta("Test", r#"{"choices":[{"delta":{"role":"assistant"}}]}"#).unwrap(), StreamDelta::Empty);
+        assert!(parse_stream_delta("Test", r#"{"error":{"message":"rate limited"}}"#).is_err());
+        assert_eq!(
+            parse_stream_delta("Test", r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":5,"total_tokens":17}}"#).unwrap(),
+            StreamDelta::Usage(TokenUsage::new(12, 5))
+        );
+    }
+}
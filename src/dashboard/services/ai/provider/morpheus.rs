@@ -10,8 +10,9 @@ use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use serde_json;
 use log::{debug, warn, error};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout}; // Import trait, common message struct, and timeout helper
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, request_error, status_error}; // Import trait, common message struct, and timeout helper
 use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 
 // Get Morpheus API base URL from environment or use default
 fn get_base_url() -> String {
@@ -33,7 +34,7 @@ struct MorpheusChatRequest {
 #[derive(Deserialize)]
 struct MorpheusChatResponse {
     choices: Vec<MorpheusChoice>,
-    // Add usage, error fields if needed
+    usage: Option<TokenUsage>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -97,15 +98,13 @@ impl AiProvider for MorpheusAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Morpheus models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Morpheus models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Morpheus models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Morpheus models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Morpheus models API returned error status {}: {}", status, error_body)));
         }
 
         // First get the raw response text to see what format it's in
@@ -163,15 +162,13 @@ impl AiProvider for MorpheusAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Morpheus: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Morpheus: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Morpheus API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Morpheus API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Morpheus API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -180,6 +177,10 @@ impl AiProvider for MorpheusAdapter {
             .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize Morpheus response: {}", e) })?;
 
         // Extract the first choice's message content
+        if let Some(usage) = response_body.usage {
+            usage::report(usage);
+        }
+
         if let Some(choice) = response_body.choices.first() {
             debug!("Received response from Morpheus API.");
             Ok(choice.message.content.clone())
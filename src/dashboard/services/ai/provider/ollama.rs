@@ -10,7 +10,7 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use log::{debug, warn, error, info};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout, get_ai_generation_timeout};
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, get_ai_generation_timeout, request_error, status_error};
 use crate::api::errors::ApiError as RestApiError;
 use crate::dashboard::services::ai::sampler_config::SamplerConfig;
 
@@ -226,15 +226,13 @@ impl AiProvider for OllamaAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Ollama models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Ollama models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Ollama models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Ollama models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Ollama models API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -276,15 +274,13 @@ impl AiProvider for OllamaAdapter {
             .timeout(get_ai_generation_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Ollama: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Ollama: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Ollama API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Ollama API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Ollama API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -342,15 +338,13 @@ impl AiProvider for OllamaAdapter {
             .timeout(get_ai_generation_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Ollama: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Ollama: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Ollama API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Ollama API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Ollama API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
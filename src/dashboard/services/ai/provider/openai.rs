@@ -8,8 +8,11 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
-use log::{debug, warn, error};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout}; // Import trait, common message struct, and timeout helper
+use log::{debug, error};
+use super::{
+    AiProvider, AiChatMessage, ResponseStream, StreamOptions, collect_response, openai_compatible_stream,
+    get_ai_request_timeout, get_ai_generation_timeout, request_error, status_error,
+};
 use crate::api::errors::ApiError as RestApiError;
 
 // Get OpenAI API base URL from environment or use default
@@ -25,21 +28,11 @@ const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
 struct OpenAiChatRequest {
     model: String,
     messages: Vec<AiChatMessage>,
+    stream: bool,
+    stream_options: StreamOptions,
     // Add other parameters like temperature, max_tokens if needed
 }
 
-#[derive(Deserialize)]
-struct OpenAiChatResponse {
-    choices: Vec<OpenAiChoice>,
-    // Add usage, error fields if needed
-}
-
-#[derive(Deserialize, Debug)]
-struct OpenAiChoice {
-    message: AiChatMessage,
-    // Add other fields if needed, like finish_reason
-}
-
 #[derive(Deserialize, Debug)]
 struct OpenAiUsage {
     // Define usage fields if needed
@@ -94,15 +87,13 @@ impl AiProvider for OpenAiAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("OpenAI models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("OpenAI models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("OpenAI models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("OpenAI models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("OpenAI models API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -121,47 +112,28 @@ impl AiProvider for OpenAiAdapter {
     }
 
     async fn generate_response(&self, messages: &[AiChatMessage]) -> Result<String, RestApiError> {
+        collect_response(self.generate_response_stream(messages), "OpenAI").await
+    }
+
+    fn generate_response_stream<'a>(&'a self, messages: &'a [AiChatMessage]) -> ResponseStream<'a> {
         let base_url = get_base_url();
         let chat_url = format!("{}/chat/completions", base_url);
 
         let request_payload = OpenAiChatRequest {
             model: self.model.clone(),
             messages: messages.to_vec(), // Clone messages for the request
+            stream: true,
+            stream_options: StreamOptions::WITH_USAGE,
         };
 
-        debug!("Sending request to OpenAI API: model={}, messages_count={}, url={}",
+        debug!("Sending streaming request to OpenAI API: model={}, messages_count={}, url={}",
                request_payload.model, request_payload.messages.len(), chat_url);
 
-        let response = self.http_client
+        let request = self.http_client
             .post(&chat_url)
             .bearer_auth(&self.api_key)
             .json(&request_payload)
-            .timeout(get_ai_request_timeout())
-            .send()
-            .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("OpenAI: {}", e) })?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
-            error!("OpenAI API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("OpenAI API returned error status {}: {}", status, error_body)
-            });
-        }
-
-        let response_body = response
-            .json::<OpenAiChatResponse>()
-            .await
-            .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize OpenAI response: {}", e) })?;
-
-        // Extract the first choice's message content
-        if let Some(choice) = response_body.choices.first() {
-            debug!("Received response from OpenAI API.");
-            Ok(choice.message.content.clone())
-        } else {
-            warn!("OpenAI API response did not contain any choices.");
-            Err(RestApiError::UnprocessableEntity { message: "OpenAI response was empty or missing choices".to_string() })
-        }
+            .timeout(get_ai_generation_timeout());
+        openai_compatible_stream("OpenAI", request)
     }
-} 
\ No newline at end of file
+}
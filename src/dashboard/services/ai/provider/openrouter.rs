@@ -8,9 +8,12 @@
 use async_trait::async_trait;
 use reqwest::{Client, header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT}};
 use serde::{Serialize, Deserialize};
-use log::{debug, warn, error};
+use log::{debug, error};
 use crate::api::errors::ApiError as RestApiError;
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout, get_ai_generation_timeout}; // Import trait, common message struct, and timeout helpers
+use super::{
+    AiProvider, AiChatMessage, ResponseStream, StreamOptions, collect_response, openai_compatible_stream,
+    get_ai_request_timeout, get_ai_generation_timeout, request_error, status_error,
+};
 
 // Get OpenRouter API base URL from environment or use default
 fn get_base_url() -> String {
@@ -32,20 +35,11 @@ const TITLE_HEADER_VALUE: &str = "RustyMail Dashboard"; // Example value
 struct OpenRouterChatRequest {
     model: String,
     messages: Vec<AiChatMessage>,
+    stream: bool,
+    stream_options: StreamOptions,
     // OpenRouter might support additional parameters, add them here if needed
 }
 
-// Assuming standard OpenAI response format
-#[derive(Deserialize)]
-struct OpenRouterChatResponse {
-    choices: Vec<OpenRouterChoice>,
-}
-
-#[derive(Deserialize)]
-struct OpenRouterChoice {
-    message: AiChatMessage,
-}
-
 #[derive(Deserialize, Debug)]
 struct OpenRouterModelsResponse {
     data: Vec<OpenRouterModel>,
@@ -105,15 +99,13 @@ impl AiProvider for OpenRouterAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("OpenRouter models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("OpenRouter models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("OpenRouter models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("OpenRouter models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("OpenRouter models API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -131,50 +123,33 @@ impl AiProvider for OpenRouterAdapter {
     }
 
     async fn generate_response(&self, messages: &[AiChatMessage]) -> Result<String, RestApiError> {
+        collect_response(self.generate_response_stream(messages), "OpenRouter").await
+    }
+
+    fn generate_response_stream<'a>(&'a self, messages: &'a [AiChatMessage]) -> ResponseStream<'a> {
         let base_url = get_base_url();
         let chat_url = format!("{}/chat/completions", base_url);
 
         let request_payload = OpenRouterChatRequest {
             model: self.model.clone(),
             messages: messages.to_vec(),
+            stream: true,
+            stream_options: StreamOptions::WITH_USAGE,
         };
 
-        debug!("Sending request to OpenRouter API: model={}, messages_count={}, url={}",
+        debug!("Sending streaming request to OpenRouter API: model={}, messages_count={}, url={}",
                request_payload.model, request_payload.messages.len(), chat_url);
 
-        let response = self.http_client
+        let request = self.http_client
             .post(&chat_url)
             // Set common headers (Referer, X-Title, User-Agent)
-            .headers(self.common_headers.clone()) 
+            .headers(self.common_headers.clone())
             // Set authorization header
             .header(AUTHORIZATION, format!("Bearer {}", self.api_key))
             .json(&request_payload)
-            .timeout(get_ai_generation_timeout()) // Longer timeout for generation
-            .send()
-            .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("OpenRouter: {}", e) })?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
-            error!("OpenRouter API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("OpenRouter API returned error status {}: {}", status, error_body)
-            });
-        }
-
-        let response_body = response
-            .json::<OpenRouterChatResponse>() // Use OpenRouter specific response struct
-            .await
-            .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize OpenRouter response: {}", e) })?;
-
-        // Extract the first choice's message content
-        if let Some(choice) = response_body.choices.first() {
-            debug!("Received response from OpenRouter API.");
-            Ok(choice.message.content.clone())
-        } else {
-            warn!("OpenRouter API response did not contain any choices.");
-            Err(RestApiError::UnprocessableEntity { message: "OpenRouter response was empty or missing choices".to_string() })
-        }
+            .timeout(get_ai_generation_timeout()); // Longer timeout for generation
+        // OpenRouter interleaves ": OPENROUTER PROCESSING" comments, which
+        // the SSE reader skips
+        openai_compatible_stream("OpenRouter", request)
     }
-} 
\ No newline at end of file
+}
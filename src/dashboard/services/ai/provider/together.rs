@@ -9,8 +9,9 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
 use log::{debug, warn, error};
-use super::{AiProvider, AiChatMessage, get_ai_request_timeout};
+use super::{AiProvider, AiChatMessage, get_ai_request_timeout, request_error, status_error};
 use crate::api::errors::ApiError as RestApiError;
+use crate::dashboard::services::ai::usage::{self, TokenUsage};
 
 // Get Together AI API base URL from environment or use default
 fn get_base_url() -> String {
@@ -34,6 +35,7 @@ struct TogetherChatRequest {
 #[derive(Deserialize)]
 struct TogetherChatResponse {
     choices: Vec<TogetherChoice>,
+    usage: Option<TokenUsage>,
 }
 
 #[derive(Deserialize)]
@@ -88,15 +90,13 @@ impl AiProvider for TogetherAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Together AI models: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Together AI models: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Together AI models API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Together AI models API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Together AI models API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -134,15 +134,13 @@ impl AiProvider for TogetherAdapter {
             .timeout(get_ai_request_timeout())
             .send()
             .await
-            .map_err(|e| RestApiError::ServiceUnavailable { service: format!("Together AI: {}", e) })?;
+            .map_err(|e| request_error(&e, format!("Together AI: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await.unwrap_or_else(|_| "<failed to read error body>".to_string());
             error!("Together AI API request failed with status {}: {}", status, error_body);
-            return Err(RestApiError::ServiceUnavailable {
-                service: format!("Together AI API returned error status {}: {}", status, error_body)
-            });
+            return Err(status_error(status, format!("Together AI API returned error status {}: {}", status, error_body)));
         }
 
         let response_body = response
@@ -150,6 +148,10 @@ impl AiProvider for TogetherAdapter {
             .await
             .map_err(|e| RestApiError::UnprocessableEntity { message: format!("Failed to deserialize Together AI response: {}", e) })?;
 
+        if let Some(usage) = response_body.usage {
+            usage::report(usage);
+        }
+
         if let Some(choice) = response_body.choices.first() {
             debug!("Received response from Together AI API.");
             Ok(choice.message.content.clone())
@@ -10,15 +10,19 @@ use log::{debug, warn, error, info};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
 use sqlx::SqlitePool;
 use crate::api::errors::ApiError as RestApiError;
 use crate::api::errors::ApiError;  // For pattern matching
+use futures::stream::StreamExt;
 use super::provider::{
-    AiProvider, AiChatMessage,
+    AiProvider, AiChatMessage, ResponseStream,
     OpenAiAdapter, OpenRouterAdapter, MorpheusAdapter, OllamaAdapter, LlamaCppAdapter, LmStudioAdapter, MockAiProvider,
     AnthropicAdapter, DeepSeekAdapter, XAIAdapter, GeminiAdapter,
-    MistralAdapter, TogetherAdapter, AzureOpenAIAdapter
+    MistralAdapter, TogetherAdapter, AzureOpenAIAdapter, BedrockAdapter
 };
 use super::model_config::{get_model_config, set_model_config, ModelConfiguration};
 use reqwest::Client;
@@ -26,6 +30,130 @@ use reqwest::Client;
 /// Role constant for chatbot configuration
 pub const ROLE_CHATBOT: &str = "chatbot";
 
+/// Default cap on concurrent in-flight requests to one provider
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// Concurrent request limit for a provider: `AI_MAX_CONCURRENT_<PROVIDER>`
+/// (e.g. `AI_MAX_CONCURRENT_OPENAI`), then `AI_MAX_CONCURRENT_REQUESTS`.
+fn provider_concurrency_limit(provider: &str) -> usize {
+    let specific = format!("AI_MAX_CONCURRENT_{}", provider.to_uppercase().replace('-', "_"));
+    std::env::var(&specific)
+        .or_else(|_| std::env::var("AI_MAX_CONCURRENT_REQUESTS"))
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n: &usize| *n > 0)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_REQUESTS)
+}
+
+struct ProviderSlots {
+    semaphore: Arc<Semaphore>,
+    limit: usize,
+    waiting: AtomicUsize,
+    /// Requests started since the process began
+    requests: AtomicU64,
+}
+
+lazy_static! {
+    // Process-wide so every caller of a provider (chat, agent tool loops,
+    // drafting) shares one budget, keyed by provider name
+    static ref PROVIDER_SLOTS: DashMap<String, Arc<ProviderSlots>> = DashMap::new();
+}
+
+fn provider_slots(provider: &str) -> Arc<ProviderSlots> {
+    PROVIDER_SLOTS
+        .entry(provider.to_string())
+        .or_insert_with(|| {
+            let limit = provider_concurrency_limit(provider);
+            Arc::new(ProviderSlots {
+                semaphore: Arc::new(Semaphore::new(limit)),
+                limit,
+                waiting: AtomicUsize::new(0),
+                requests: AtomicU64::new(0),
+            })
+        })
+        .clone()
+}
+
+/// Held for the duration of one request to a provider.
+pub struct ProviderPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Decrements the waiting count even if the caller gives up while queued.
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Wait for a free request slot for `provider`. Requests beyond the limit
+/// queue here in FIFO order instead of reaching the provider.
+pub async fn acquire_provider_slot(provider: &str) -> ProviderPermit {
+    let slots = provider_slots(provider);
+    slots.requests.fetch_add(1, Ordering::Relaxed);
+    if let Ok(permit) = slots.semaphore.clone().try_acquire_owned() {
+        return ProviderPermit { _permit: permit };
+    }
+
+    slots.waiting.fetch_add(1, Ordering::SeqCst);
+    let _waiting = WaitingGuard(&slots.waiting);
+    debug!("Provider '{}' at its limit of {} concurrent requests, queuing", provider, slots.limit);
+    let permit = slots.semaphore.clone().acquire_owned().await
+        .expect("provider semaphores are never closed");
+    ProviderPermit { _permit: permit }
+}
+
+/// Wrap a provider's error with `message`, keeping the variants the retry
+/// policy treats as transient.
+fn provider_failure(error: RestApiError, message: String) -> RestApiError {
+    match error {
+        RestApiError::RateLimitExceeded { .. } => RestApiError::RateLimitExceeded { message },
+        RestApiError::GatewayTimeout { .. } => RestApiError::GatewayTimeout { service: message },
+        RestApiError::UpstreamUnavailable { .. } => RestApiError::UpstreamUnavailable { service: message },
+        _ => RestApiError::ServiceUnavailable { service: message },
+    }
+}
+
+/// Request concurrency for one provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderConcurrency {
+    pub provider: String,
+    pub limit: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+    /// Requests sent to the provider since startup
+    pub requests_total: u64,
+}
+
+/// Current concurrency for `provider`.
+pub fn provider_concurrency(provider: &str) -> ProviderConcurrency {
+    match PROVIDER_SLOTS.get(provider) {
+        Some(slots) => ProviderConcurrency {
+            provider: provider.to_string(),
+            limit: slots.limit,
+            in_flight: slots.limit - slots.semaphore.available_permits(),
+            queued: slots.waiting.load(Ordering::SeqCst),
+            requests_total: slots.requests.load(Ordering::Relaxed),
+        },
+        None => ProviderConcurrency {
+            provider: provider.to_string(),
+            limit: provider_concurrency_limit(provider),
+            in_flight: 0,
+            queued: 0,
+            requests_total: 0,
+        },
+    }
+}
+
+/// Concurrency of every provider that has been called since startup.
+pub fn all_provider_concurrency() -> Vec<ProviderConcurrency> {
+    let mut providers: Vec<String> = PROVIDER_SLOTS.iter().map(|entry| entry.key().clone()).collect();
+    providers.sort();
+    providers.iter().map(|provider| provider_concurrency(provider)).collect()
+}
+
 // Provider configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
@@ -54,6 +182,7 @@ pub enum ProviderType {
     Mistral,
     Together,
     Azure,
+    Bedrock,
     Mock,
 }
 
@@ -403,6 +532,25 @@ impl ProviderManager {
             }
         }
 
+        // Check for AWS Bedrock configuration. Credentials come from the AWS
+        // default chain rather than an API key
+        if let Ok(model) = std::env::var("BEDROCK_MODEL") {
+            let config = ProviderConfig {
+                name: "bedrock".to_string(),
+                provider_type: ProviderType::Bedrock,
+                api_key: None,
+                model: model.clone(),
+                max_tokens: None,
+                temperature: None,
+                priority: 13,
+                enabled: true,
+            };
+            configs.push(config);
+            let provider = Arc::new(BedrockAdapter::new(self.http_client.clone()).with_model(model));
+            self.providers.write().await.insert("bedrock".to_string(), provider);
+            info!("Initialized AWS Bedrock provider");
+        }
+
         // Always add mock provider as fallback
         let mock_config = ProviderConfig {
             name: "mock".to_string(),
@@ -539,6 +687,10 @@ impl ProviderManager {
                 AzureOpenAIAdapter::new(api_key.clone(), self.http_client.clone())
                     .map(|adapter| Arc::new(adapter) as Arc<dyn AiProvider>)?
             },
+            ProviderType::Bedrock => {
+                Arc::new(BedrockAdapter::new(self.http_client.clone())
+                    .with_model(config.model.clone()))
+            },
             ProviderType::Mock => {
                 Arc::new(MockAiProvider)
             },
@@ -686,6 +838,7 @@ impl ProviderManager {
             let current_name = self.get_current_provider_name().await.unwrap_or_else(|| "unknown".to_string());
             info!("Using provider: {} with model: {}", current_name, self.get_current_model_name().await.unwrap_or_else(|| "unknown".to_string()));
 
+            let _slot = acquire_provider_slot(&current_name).await;
             match current_provider.generate_response(messages).await {
                 Ok(response) => {
                     info!("Successfully got response from provider: {}", current_name);
@@ -693,9 +846,8 @@ impl ProviderManager {
                 },
                 Err(e) => {
                     error!("Provider {} failed: {}. NO FALLBACK - user must select different provider.", current_name, e);
-                    return Err(RestApiError::ServiceUnavailable {
-                        service: format!("Provider '{}' failed: {}. Please select a different provider.", current_name, e)
-                    });
+                    let message = format!("Provider '{}' failed: {}. Please select a different provider.", current_name, e);
+                    return Err(provider_failure(e, message));
                 }
             }
         }
@@ -742,6 +894,9 @@ impl ProviderManager {
 
                 info!("Using override provider: {} with model: {}", provider_name, model_name.as_deref().unwrap_or("default"));
 
+                // Don't hold the providers map lock while queued for a slot
+                drop(providers);
+                let _slot = acquire_provider_slot(&provider_name).await;
                 match provider_to_use.generate_response(messages).await {
                     Ok(response) => {
                         info!("Successfully got response from override provider: {}", provider_name);
@@ -749,9 +904,8 @@ impl ProviderManager {
                     },
                     Err(e) => {
                         error!("Override provider {} failed: {}", provider_name, e);
-                        return Err(RestApiError::ServiceUnavailable {
-                            service: format!("Provider '{}' failed: {}", provider_name, e)
-                        });
+                        let message = format!("Provider '{}' failed: {}", provider_name, e);
+                        return Err(provider_failure(e, message));
                     }
                 }
             } else {
@@ -765,11 +919,63 @@ impl ProviderManager {
         self.generate_response(messages).await
     }
 
+    /// Stream a response from the current provider, or from `provider_name`
+    /// when given. Like `generate_response`, there is no fallback. The
+    /// provider's request slot is held until the stream is dropped.
+    pub async fn generate_response_stream(
+        &self,
+        messages: &[AiChatMessage],
+        provider_name: Option<String>,
+    ) -> Result<ResponseStream<'static>, RestApiError> {
+        let (name, provider) = match provider_name {
+            Some(name) => {
+                let provider = self.providers.read().await.get(&name).cloned()
+                    .ok_or_else(|| RestApiError::NotFound { resource: format!("Provider '{}' not found", name) })?;
+                (name, provider)
+            }
+            None => {
+                let provider = self.get_current_provider().await
+                    .ok_or_else(|| RestApiError::ServiceUnavailable {
+                        service: "No AI provider selected. Please select a provider first.".to_string()
+                    })?;
+                (self.get_current_provider_name().await.unwrap_or_else(|| "unknown".to_string()), provider)
+            }
+        };
+        info!("Streaming from provider: {}", name);
+
+        let messages = messages.to_vec();
+        let stream = async_stream::stream! {
+            let _slot = acquire_provider_slot(&name).await;
+            let mut chunks = provider.generate_response_stream(&messages);
+            while let Some(chunk) = chunks.next().await {
+                yield chunk.map_err(|e| {
+                    error!("Provider {} failed while streaming: {}", name, e);
+                    let message = format!("Provider '{}' failed: {}", name, e);
+                    provider_failure(e, message)
+                });
+            }
+        };
+        Ok(stream.boxed())
+    }
+
     // List available providers
     pub async fn list_providers(&self) -> Vec<ProviderConfig> {
         self.configs.read().await.clone()
     }
 
+    /// Request concurrency for every configured provider, plus any other
+    /// provider that has been called directly (e.g. by the agent executor).
+    pub async fn concurrency_status(&self) -> Vec<ProviderConcurrency> {
+        let mut names: Vec<String> = self.configs.read().await.iter().map(|c| c.name.clone()).collect();
+        for entry in PROVIDER_SLOTS.iter() {
+            if !names.contains(entry.key()) {
+                names.push(entry.key().clone());
+            }
+        }
+        names.sort();
+        names.iter().map(|name| provider_concurrency(name)).collect()
+    }
+
     // Enable/disable a provider
     pub async fn set_provider_enabled(&self, name: &str, enabled: bool) -> Result<(), RestApiError> {
         let mut configs = self.configs.write().await;
@@ -893,6 +1099,10 @@ impl ProviderManager {
                 AzureOpenAIAdapter::new(api_key.clone(), self.http_client.clone())
                     .map(|adapter| Arc::new(adapter) as Arc<dyn AiProvider>)?
             },
+            ProviderType::Bedrock => {
+                Arc::new(BedrockAdapter::new(self.http_client.clone())
+                    .with_model(config.model.clone()))
+            },
             ProviderType::Mock => {
                 Arc::new(MockAiProvider)
             },
@@ -1159,4 +1369,25 @@ mod tests {
         // Should have at least mock provider
         assert!(providers.iter().any(|p| p.provider_type == ProviderType::Mock));
     }
+
+    #[tokio::test]
+    async fn test_provider_slots_queue_beyond_limit() {
+        std::env::set_var("AI_MAX_CONCURRENT_SLOT_TEST", "2");
+
+        let first = acquire_provider_slot("slot-test").await;
+        let _second = acquire_provider_slot("slot-test").await;
+        let status = provider_concurrency("slot-test");
+        assert_eq!((status.limit, status.in_flight, status.queued), (2, 2, 0));
+
+        // A third request waits until one of the first two finishes
+        let waiter = tokio::spawn(async { acquire_provider_slot("slot-test").await });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(provider_concurrency("slot-test").queued, 1);
+        assert!(!waiter.is_finished());
+
+        drop(first);
+        let _third = waiter.await.unwrap();
+        let status = provider_concurrency("slot-test");
+        assert_eq!((status.in_flight, status.queued), (2, 0));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// src/dashboard/services/ai/retry.rs
+// Retry policy for chatbot provider calls. Providers report rate limits
+// (429), timeouts and server errors or dropped connections as
+// `RateLimitExceeded`, `GatewayTimeout` and `UpstreamUnavailable`; those are
+// retried with exponential backoff and jitter, anything else (400, 401/403,
+// unknown provider) is returned at once. With a fallback provider configured, a call that still
+// fails after its retries is tried again on the fallback.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::api::errors::ApiError;
+
+/// Settings from the environment:
+/// - `AI_MAX_RETRIES` (default 2)
+/// - `AI_RETRY_BASE_DELAY_MS` (default 500), doubled on each retry
+/// - `AI_RETRY_MAX_DELAY_MS` (default 8000)
+/// - `AI_FALLBACK_PROVIDER` (unset = no failover)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub fallback_provider: Option<String>,
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str, default: u64| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            max_retries: env_u64("AI_MAX_RETRIES", 2) as u32,
+            base_delay: Duration::from_millis(env_u64("AI_RETRY_BASE_DELAY_MS", 500)),
+            max_delay: Duration::from_millis(env_u64("AI_RETRY_MAX_DELAY_MS", 8000)),
+            fallback_provider: std::env::var("AI_FALLBACK_PROVIDER").ok().filter(|p| !p.trim().is_empty()),
+        }
+    }
+
+    /// Wait before retry number `retry` (0-based): the base delay doubled per
+    /// retry, capped, then scaled to between 50% and 100% so clients that
+    /// failed together don't retry together.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let ceiling = self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        ceiling.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// Whether another attempt at the call that returned `error` may succeed.
+pub fn is_retryable(error: &ApiError) -> bool {
+    matches!(
+        error,
+        ApiError::RateLimitExceeded { .. } | ApiError::GatewayTimeout { .. } | ApiError::UpstreamUnavailable { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::services::ai::provider::status_error;
+
+    fn status(code: u16) -> ApiError {
+        let status = reqwest::StatusCode::from_u16(code).unwrap();
+        status_error(status, format!("OpenAI API returned error status {}: body", status))
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(is_retryable(&status(429)));
+        assert!(is_retryable(&status(529)));
+        assert!(is_retryable(&status(504)));
+        assert!(!is_retryable(&status(401)));
+        assert!(!is_retryable(&status(400)));
+        assert!(is_retryable(&ApiError::UpstreamUnavailable { service: "Provider 'openai' failed: connection reset".to_string() }));
+        assert!(!is_retryable(&ApiError::ServiceUnavailable { service: "No AI provider selected. Please select a provider first.".to_string() }));
+        assert!(!is_retryable(&ApiError::NotFound { resource: "Provider 'x' not found".to_string() }));
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_millis(3000),
+            fallback_provider: None,
+        };
+        for retry in 0..5 {
+            let ceiling = Duration::from_millis((500u64 << retry).min(3000));
+            let delay = policy.delay(retry);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "retry {}: {:?}", retry, delay);
+        }
+    }
+}
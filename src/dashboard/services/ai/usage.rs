@@ -0,0 +1,324 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// src/dashboard/services/ai/usage.rs
+// Token and cost accounting for AI requests. Providers report the usage
+// block of each response with `report`; the caller wraps a provider call in
+// `capture` to get what was reported, and falls back to `estimate` for
+// providers that return no usage (Ollama, streamed responses).
+//
+// Prices are USD per million tokens, matched by model-id prefix. Entries in
+// `AI_MODEL_PRICES` ("model=input/output,...") take precedence over the
+// built-in table; models with no price count tokens at zero cost.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tiktoken_rs::CoreBPE;
+
+use super::provider::AiChatMessage;
+
+/// Prompt and completion tokens of one or more requests. Deserializes from
+/// the `usage` block of an OpenAI-compatible response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self { prompt_tokens, completion_tokens }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+tokio::task_local! {
+    static REPORTED: Cell<Option<TokenUsage>>;
+}
+
+/// Record usage returned by a provider. Outside `capture` this does nothing.
+pub fn report(usage: TokenUsage) {
+    let _ = REPORTED.try_with(|reported| {
+        let mut total = reported.get().unwrap_or_default();
+        total += usage;
+        reported.set(Some(total));
+    });
+}
+
+/// Run `future` and return its output with the usage providers reported
+/// while it ran, if any.
+pub async fn capture<F: Future>(future: F) -> (F::Output, Option<TokenUsage>) {
+    REPORTED
+        .scope(Cell::new(None), async move {
+            let output = future.await;
+            (output, REPORTED.with(Cell::get))
+        })
+        .await
+}
+
+lazy_static! {
+    // cl100k is not every model's tokenizer, but close enough for an estimate
+    static ref TOKENIZER: Option<CoreBPE> = tiktoken_rs::cl100k_base().ok();
+}
+
+/// Approximate token count of `text`.
+pub fn count_tokens(text: &str) -> u64 {
+    match TOKENIZER.as_ref() {
+        Some(bpe) => bpe.encode_with_special_tokens(text).len() as u64,
+        None => (text.chars().count() as u64).div_ceil(4),
+    }
+}
+
+/// Estimated usage of a chat request, counting the per-message framing
+/// tokens chat models add.
+pub fn estimate(messages: &[AiChatMessage], response: &str) -> TokenUsage {
+    let prompt = messages.iter()
+        .map(|m| count_tokens(&m.role) + count_tokens(&m.content) + 4)
+        .sum::<u64>() + 3;
+    TokenUsage::new(prompt, count_tokens(response))
+}
+
+/// USD per million input and output tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input + usage.completion_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// Built-in list prices by model-id prefix; the longest matching prefix wins.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-4", 30.00, 60.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("o3-mini", 1.10, 4.40),
+    ("o4-mini", 1.10, 4.40),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-haiku-4", 1.00, 5.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-opus-4", 15.00, 75.00),
+    ("deepseek-chat", 0.27, 1.10),
+    ("deepseek-reasoner", 0.55, 2.19),
+    ("gemini-1.5-flash", 0.075, 0.30),
+    ("gemini-1.5-pro", 1.25, 5.00),
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("mistral-small", 0.20, 0.60),
+    ("mistral-large", 2.00, 6.00),
+    ("grok-2", 2.00, 10.00),
+    ("grok-3", 3.00, 15.00),
+    ("amazon.titan-text-lite", 0.15, 0.20),
+    ("amazon.titan-text-express", 0.20, 0.60),
+];
+
+/// `AI_MODEL_PRICES` entries, e.g. `my-model=0.5/1.5,gpt-4o=2.5/10`.
+fn configured_prices() -> Vec<(String, ModelPrice)> {
+    std::env::var("AI_MODEL_PRICES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| {
+            let (model, prices) = entry.split_once('=')?;
+            let (input, output) = prices.split_once('/')?;
+            let price = ModelPrice { input: input.trim().parse().ok()?, output: output.trim().parse().ok()? };
+            Some((model.trim().to_string(), price))
+        })
+        .collect()
+}
+
+/// Price of `model`, ignoring routing prefixes such as `openai/` (OpenRouter)
+/// and `us.anthropic.` (Bedrock).
+pub fn price_for(model: &str) -> Option<ModelPrice> {
+    let bare = model.rsplit('/').next().unwrap_or(model);
+    let bare = bare.rsplit_once("anthropic.").map(|(_, rest)| rest).unwrap_or(bare);
+    let candidates = [model, bare];
+
+    let configured = configured_prices();
+    let from_config = configured.iter()
+        .filter(|(prefix, _)| candidates.iter().any(|m| m.starts_with(prefix.as_str())))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| *price);
+    from_config.or_else(|| {
+        PRICES.iter()
+            .filter(|(prefix, _, _)| candidates.iter().any(|m| m.starts_with(prefix)))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|&(_, input, output)| ModelPrice { input, output })
+    })
+}
+
+/// Token counts and cost of one chatbot request, returned with its response.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RequestUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub estimated_cost_usd: f64,
+    /// Some of the counts were estimated rather than reported by the provider
+    pub estimated: bool,
+}
+
+/// Cumulative usage of one provider and model.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub model: String,
+    pub requests: u64,
+    /// Requests whose counts were estimated
+    pub estimated_requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    /// Whether a price is known for the model; unpriced models cost 0
+    pub priced: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub since: DateTime<Utc>,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub models: Vec<ModelUsage>,
+}
+
+/// Running totals keyed by provider and model, kept since startup.
+#[derive(Debug)]
+pub struct UsageTracker {
+    since: DateTime<Utc>,
+    totals: Mutex<HashMap<(String, String), ModelUsage>>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self { since: Utc::now(), totals: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl UsageTracker {
+    /// Add one provider call and return its estimated cost.
+    pub fn record(&self, provider: &str, model: &str, usage: TokenUsage, estimated: bool) -> f64 {
+        let price = price_for(model);
+        let cost = price.map(|p| p.cost(&usage)).unwrap_or(0.0);
+        let mut totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = totals.entry((provider.to_string(), model.to_string()))
+            .or_insert_with(|| ModelUsage {
+                provider: provider.to_string(),
+                model: model.to_string(),
+                requests: 0,
+                estimated_requests: 0,
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+                cost_usd: 0.0,
+                priced: price.is_some(),
+            });
+        entry.requests += 1;
+        if estimated {
+            entry.estimated_requests += 1;
+        }
+        entry.prompt_tokens += usage.prompt_tokens;
+        entry.completion_tokens += usage.completion_tokens;
+        entry.total_tokens += usage.total();
+        entry.cost_usd += cost;
+        cost
+    }
+
+    pub fn report(&self) -> UsageReport {
+        let mut models: Vec<ModelUsage> = self.totals.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        models.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd).then(b.total_tokens.cmp(&a.total_tokens)));
+        UsageReport {
+            since: self.since,
+            requests: models.iter().map(|m| m.requests).sum(),
+            prompt_tokens: models.iter().map(|m| m.prompt_tokens).sum(),
+            completion_tokens: models.iter().map(|m| m.completion_tokens).sum(),
+            total_tokens: models.iter().map(|m| m.total_tokens).sum(),
+            cost_usd: models.iter().map(|m| m.cost_usd).sum(),
+            models,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_capture_collects_reported_usage() {
+        let (value, usage) = capture(async {
+            report(TokenUsage::new(10, 5));
+            report(TokenUsage::new(3, 2));
+            7
+        }).await;
+        assert_eq!(value, 7);
+        assert_eq!(usage, Some(TokenUsage::new(13, 7)));
+
+        let (_, usage) = capture(async {}).await;
+        assert_eq!(usage, None);
+        // Outside capture, reporting is a no-op
+        report(TokenUsage::new(1, 1));
+    }
+
+    #[test]
+    fn test_prices_and_totals() {
+        assert_eq!(price_for("gpt-4o-mini-2024-07-18"), Some(ModelPrice { input: 0.15, output: 0.60 }));
+        assert_eq!(price_for("openai/gpt-4o"), Some(ModelPrice { input: 2.50, output: 10.00 }));
+        assert_eq!(price_for("us.anthropic.claude-3-5-sonnet-20240620-v1:0"), Some(ModelPrice { input: 3.00, output: 15.00 }));
+        assert_eq!(price_for("llama3.1:8b"), None);
+
+        let tracker = UsageTracker::default();
+        let cost = tracker.record("openai", "gpt-4o", TokenUsage::new(1_000_000, 100_000), false);
+        assert!((cost - 3.5).abs() < 1e-9);
+        tracker.record("ollama", "llama3.1:8b", TokenUsage::new(50, 20), true);
+        tracker.record("openai", "gpt-4o", TokenUsage::new(10, 10), false);
+
+        let report = tracker.report();
+        assert_eq!(report.requests, 3);
+        assert_eq!(report.total_tokens, 1_100_090);
+        assert_eq!(report.models[0].model, "gpt-4o");
+        assert_eq!(report.models[0].requests, 2);
+        assert_eq!(report.models[1].estimated_requests, 1);
+        assert!(!report.models[1].priced);
+
+        let usage = estimate(&[AiChatMessage { role: "user".to_string(), content: "Hello there".to_string() }], "Hi");
+        assert!(usage.prompt_tokens > 4 && usage.completion_tokens >= 1);
+    }
+}
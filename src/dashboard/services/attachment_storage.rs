@@ -30,6 +30,8 @@ pub enum AttachmentError {
     PathTraversal,
     #[error("Invalid filename: {0}")]
     InvalidFilename(String),
+    #[error("Archive too large: {0}")]
+    TooLarge(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -161,6 +163,56 @@ fn validate_path_containment(storage_root: &Path, full_path: &Path) -> Result<Pa
     }
 }
 
+/// Resolve an existing file inside the storage root, e.g. one saved from a
+/// received email that is being attached to an outgoing message. Relative
+/// paths are taken from the storage root; nothing outside it is readable.
+///
+/// Containment is checked before existence, so a path outside the root is
+/// refused the same way whether or not anything is there.
+pub fn stored_file_path(path: &str) -> Result<PathBuf, AttachmentError> {
+    let storage_root = get_storage_root();
+    if !storage_root.exists() {
+        fs::create_dir_all(&storage_root)?;
+    }
+    let canonical_root = fs::canonicalize(&storage_root)?;
+    let requested = Path::new(path);
+    let full_path = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        canonical_root.join(requested)
+    };
+    if !lexically_normalized(&full_path).starts_with(&canonical_root) {
+        warn!("Path traversal attempt: {:?} escapes storage root {:?}", full_path, canonical_root);
+        return Err(AttachmentError::PathTraversal);
+    }
+    // Canonicalizing again catches symlinks that lead out of the root
+    let canonical_path = fs::canonicalize(&full_path)
+        .map_err(|_| AttachmentError::NotFound(path.to_string()))?;
+    if !canonical_path.starts_with(&canonical_root) {
+        warn!("Path traversal attempt: {:?} escapes storage root {:?}", full_path, canonical_root);
+        return Err(AttachmentError::PathTraversal);
+    }
+    if !canonical_path.is_file() {
+        return Err(AttachmentError::NotFound(path.to_string()));
+    }
+    Ok(canonical_path)
+}
+
+/// `path` with `.` and `..` resolved without touching the filesystem.
+fn lexically_normalized(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
 /// Ensure an email has a message-id, generating one if needed
 pub fn ensure_message_id(email: &Email, account: &str) -> String {
     if let Some(envelope) = &email.envelope {
@@ -408,6 +460,68 @@ pub async fn delete_attachments_for_email(
     Ok(())
 }
 
+/// Limits for building attachment ZIP archives, read from the environment.
+#[derive(Debug, Clone, Copy)]
+pub struct ZipConfig {
+    /// `ATTACHMENT_ZIP_MAX_TOTAL_MB` (default 1024): archives whose inputs exceed this are refused
+    pub max_total_bytes: u64,
+    /// `ATTACHMENT_ZIP_CONCURRENCY` (default 4): attachment files checked and opened ahead of the writer
+    pub concurrency: usize,
+    /// `ATTACHMENT_ZIP_JOB_THRESHOLD_MB` (default 50): larger archives are built as background jobs
+    pub job_threshold_bytes: u64,
+}
+
+impl Default for ZipConfig {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: 1024 * 1024 * 1024,
+            concurrency: 4,
+            job_threshold_bytes: 50 * 1024 * 1024,
+        }
+    }
+}
+
+impl ZipConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let mb = |name: &str, default: u64| -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|v| v * 1024 * 1024)
+                .unwrap_or(default)
+        };
+        Self {
+            max_total_bytes: mb("ATTACHMENT_ZIP_MAX_TOTAL_MB", defaults.max_total_bytes),
+            concurrency: std::env::var("ATTACHMENT_ZIP_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v: &usize| *v > 0)
+                .unwrap_or(defaults.concurrency),
+            job_threshold_bytes: mb("ATTACHMENT_ZIP_JOB_THRESHOLD_MB", defaults.job_threshold_bytes),
+        }
+    }
+}
+
+/// Progress of a ZIP build, reported after each file is written.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ZipProgress {
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+/// Total stored size of a message's attachments, from metadata only.
+pub async fn attachments_total_size(
+    pool: &SqlitePool,
+    account: &str,
+    message_id: &str,
+) -> Result<u64, AttachmentError> {
+    let attachments = get_attachments_metadata(pool, account, message_id).await?;
+    Ok(attachments.iter().map(|a| a.size_bytes.max(0) as u64).sum())
+}
+
 /// Create a ZIP archive of all attachments for an email
 pub async fn create_zip_archive(
     pool: &SqlitePool,
@@ -415,14 +529,116 @@ pub async fn create_zip_archive(
     message_id: &str,
     output_path: &Path,
 ) -> Result<PathBuf, AttachmentError> {
-    use zip::write::FileOptions;
-    use zip::ZipWriter;
+    create_zip_archive_with_progress(pool, account, message_id, output_path, &ZipConfig::from_env(), None).await
+}
+
+/// Create a ZIP archive of all attachments for an email, streaming each file
+/// from storage so memory use does not grow with attachment size. Fails
+/// before writing anything if the inputs exceed `config.max_total_bytes`.
+pub async fn create_zip_archive_with_progress(
+    pool: &SqlitePool,
+    account: &str,
+    message_id: &str,
+    output_path: &Path,
+    config: &ZipConfig,
+    progress: Option<tokio::sync::mpsc::UnboundedSender<ZipProgress>>,
+) -> Result<PathBuf, AttachmentError> {
+    let entries = collect_zip_entries(pool, account, message_id, None, config).await?;
+    if entries.is_empty() {
+        return Err(AttachmentError::NotFound("No attachments found".to_string()));
+    }
+    write_zip_archive(entries, output_path, config, progress).await
+}
+
+/// Create one ZIP of the attachments of several emails, each email's files in
+/// its own directory. `messages` pairs a directory name with a message ID;
+/// emails without stored attachments are skipped. The size cap applies to the
+/// archive as a whole.
+pub async fn create_multi_zip_archive(
+    pool: &SqlitePool,
+    account: &str,
+    messages: &[(String, String)],
+    output_path: &Path,
+    config: &ZipConfig,
+    progress: Option<tokio::sync::mpsc::UnboundedSender<ZipProgress>>,
+) -> Result<PathBuf, AttachmentError> {
+    let mut entries = Vec::new();
+    for (index, (directory, message_id)) in messages.iter().enumerate() {
+        let dir = sanitize_message_id(directory).replace("..", "_");
+        let dir = if dir.trim().is_empty() { format!("message_{}", index + 1) } else { dir };
+        entries.extend(collect_zip_entries(pool, account, message_id, Some(&dir), config).await?);
+    }
+    if entries.is_empty() {
+        return Err(AttachmentError::NotFound("No attachments found".to_string()));
+    }
+    write_zip_archive(entries, output_path, config, progress).await
+}
+
+/// Validate and stat a message's stored attachment files, a bounded number at
+/// a time. Returns (entry name, path, size) for each file that exists.
+async fn collect_zip_entries(
+    pool: &SqlitePool,
+    account: &str,
+    message_id: &str,
+    directory: Option<&str>,
+    config: &ZipConfig,
+) -> Result<Vec<(String, PathBuf, u64)>, AttachmentError> {
+    use futures::stream::{self, StreamExt};
 
     let storage_root = get_storage_root();
     let attachments = get_attachments_metadata(pool, account, message_id).await?;
 
-    if attachments.is_empty() {
-        return Err(AttachmentError::NotFound("No attachments found".to_string()));
+    let checked: Vec<Option<(String, PathBuf, u64)>> = stream::iter(attachments.into_iter().enumerate())
+        .map(|(index, attachment)| {
+            let storage_root = storage_root.clone();
+            async move {
+                let path = Path::new(&attachment.storage_path);
+                let validated_path = match validate_path_containment(&storage_root, path) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        warn!("Skipping suspicious attachment path {:?}: {}", path, e);
+                        return None;
+                    }
+                };
+                match tokio::fs::metadata(&validated_path).await {
+                    Ok(meta) => {
+                        // Sanitize filename for ZIP entry (prevent zip slip attacks)
+                        let safe_filename = sanitize_filename(&attachment.filename)
+                            .unwrap_or_else(|_| format!("attachment_{}", index));
+                        let entry_name = match directory {
+                            Some(dir) => format!("{}/{}", dir, safe_filename),
+                            None => safe_filename,
+                        };
+                        Some((entry_name, validated_path, meta.len()))
+                    }
+                    Err(_) => {
+                        warn!("Attachment file not found: {:?}", validated_path);
+                        None
+                    }
+                }
+            }
+        })
+        .buffered(config.concurrency)
+        .collect()
+        .await;
+
+    Ok(checked.into_iter().flatten().collect())
+}
+
+/// Stream `entries` into a ZIP at `output_path`, refusing up front if their
+/// total size exceeds the cap.
+async fn write_zip_archive(
+    entries: Vec<(String, PathBuf, u64)>,
+    output_path: &Path,
+    config: &ZipConfig,
+    progress: Option<tokio::sync::mpsc::UnboundedSender<ZipProgress>>,
+) -> Result<PathBuf, AttachmentError> {
+    let bytes_total: u64 = entries.iter().map(|(_, _, len)| len).sum();
+    if bytes_total > config.max_total_bytes {
+        return Err(AttachmentError::TooLarge(format!(
+            "attachments total {} bytes, limit is {} bytes (ATTACHMENT_ZIP_MAX_TOTAL_MB)",
+            bytes_total, config.max_total_bytes
+        )));
     }
 
     // Create output directory if needed
@@ -430,40 +646,44 @@ pub async fn create_zip_archive(
         fs::create_dir_all(parent)?;
     }
 
-    let file = fs::File::create(output_path)?;
-    let mut zip = ZipWriter::new(file);
-    let options = FileOptions::default()
-        .compression_method(zip::CompressionMethod::Deflated)
-        .unix_permissions(0o644);
-
-    let mut files_added = 0;
-    for attachment in &attachments {
-        let path = Path::new(&attachment.storage_path);
-
-        // Validate path containment before reading
-        match validate_path_containment(&storage_root, path) {
-            Ok(validated_path) => {
-                if validated_path.exists() {
-                    // Sanitize filename for ZIP entry (prevent zip slip attacks)
-                    let safe_filename = sanitize_filename(&attachment.filename)
-                        .unwrap_or_else(|_| format!("attachment_{}", files_added));
-
-                    zip.start_file(&safe_filename, options)?;
-                    let content = fs::read(&validated_path)?;
-                    zip.write_all(&content)?;
-                    debug!("Added {} to ZIP archive", safe_filename);
-                    files_added += 1;
-                } else {
-                    warn!("Attachment file not found: {:?}", validated_path);
-                }
-            }
-            Err(e) => {
-                warn!("Skipping suspicious attachment path {:?}: {}", path, e);
+    // The zip crate is synchronous; keep the copy off the async workers
+    let output = output_path.to_path_buf();
+    let files_added = tokio::task::spawn_blocking(move || -> Result<usize, AttachmentError> {
+        use zip::write::FileOptions;
+        use zip::ZipWriter;
+
+        let file = fs::File::create(&output)?;
+        let mut zip = ZipWriter::new(std::io::BufWriter::new(file));
+        let options = FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(0o644)
+            .large_file(bytes_total > u32::MAX as u64);
+
+        let files_total = entries.len();
+        let mut bytes_done = 0u64;
+        for (files_done, (entry_name, path, len)) in entries.into_iter().enumerate() {
+            zip.start_file(&entry_name, options)?;
+            let mut reader = std::io::BufReader::new(fs::File::open(&path)?);
+            std::io::copy(&mut reader, &mut zip)?;
+            debug!("Added {} to ZIP archive", entry_name);
+
+            bytes_done += len;
+            if let Some(progress) = &progress {
+                let _ = progress.send(ZipProgress {
+                    files_done: files_done + 1,
+                    files_total,
+                    bytes_done,
+                    bytes_total,
+                });
             }
         }
-    }
 
-    zip.finish()?;
+        zip.finish()?.flush()?;
+        Ok(files_total)
+    })
+    .await
+    .map_err(|e| AttachmentError::IoError(std::io::Error::new(std::io::ErrorKind::Other, e)))??;
+
     info!("Created ZIP archive at {:?} with {} files", output_path, files_added);
 
     Ok(output_path.to_path_buf())
@@ -521,6 +741,7 @@ pub async fn search_by_attachment_type(
     // Fine-filter: parse JSON and match content_type against glob patterns.
     let mut results = Vec::new();
     for (uid, folder, subject, parts_json) in rows {
+        let subject = super::cache_crypto::reveal(subject);
         let parts: Vec<serde_json::Value> = match serde_json::from_str(&parts_json) {
             Ok(v) => v,
             Err(_) => continue,
@@ -577,7 +798,7 @@ pub async fn search_by_attachment_type(
                 results.push(AttachmentTypeMatch {
                     uid,
                     folder,
-                    subject,
+                    subject: super::cache_crypto::reveal(subject),
                     filename,
                     content_type: content_type.unwrap_or_else(|| "application/octet-stream".to_string()),
                     size,
@@ -792,6 +1013,15 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_stored_file_path_checks_containment_first() {
+        // Refused the same way whether or not the target exists
+        for outside in ["../Cargo.toml", "../no-such-file", "/etc/passwd", "/no/such/file"] {
+            assert!(matches!(stored_file_path(outside), Err(AttachmentError::PathTraversal)), "{}", outside);
+        }
+        assert!(matches!(stored_file_path("no-such-file.pdf"), Err(AttachmentError::NotFound(_))));
+    }
+
     #[test]
     fn test_sanitize_filename() {
         // Normal filename should pass
@@ -3,11 +3,49 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+//! Server settings for an email address, so the account form can be
+//! pre-filled. Sources are tried in this order:
+//!
+//! 1. The domain's own Mozilla autoconfig file
+//!    (`autoconfig.<domain>/mail/config-v1.1.xml`, then `.well-known`)
+//! 2. The Thunderbird ISPDB (`autoconfig.thunderbird.net`)
+//! 3. RFC 6186 SRV records (`_imaps._tcp`, `_imap._tcp`, `_submission._tcp`)
+//! 4. Guessing common host names (`imap.<domain>`, `mail.<domain>`) and
+//!    keeping the first that accepts a connection
+//!
+//! A domain for which nothing was found is remembered for
+//! `AUTODISCOVERY_NEGATIVE_TTL_SECONDS` (default 300, 0 = off) so a user
+//! retrying the form doesn't repeat every DNS and HTTP lookup.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
 use hickory_resolver::TokioResolver;
-use log::{info, debug, warn, error};
+use lazy_static::lazy_static;
+use log::{info, debug, warn};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use reqwest::Client;
+use tokio::net::TcpStream;
+
+use super::account::AutoConfigResult;
+
+/// How long a guessed host gets to accept a TCP connection.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+lazy_static! {
+    /// Domains with no discoverable settings, and when that was found.
+    static ref NEGATIVE_CACHE: DashMap<String, Instant> = DashMap::new();
+}
+
+fn negative_ttl() -> Duration {
+    Duration::from_secs(
+        std::env::var("AUTODISCOVERY_NEGATIVE_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300),
+    )
+}
 
 #[derive(Error, Debug)]
 pub enum AutodiscoveryError {
@@ -35,6 +73,38 @@ pub struct EmailConfig {
     pub username_pattern: String, // e.g., "%EMAILADDRESS%" or "%EMAILLOCALPART%"
 }
 
+impl EmailConfig {
+    /// Result for the account form, with the username template filled in.
+    fn into_result(self, email: &str, source: &str, display_name: Option<String>) -> AutoConfigResult {
+        AutoConfigResult {
+            provider_found: true,
+            provider_type: Some("Auto-discovered".to_string()),
+            display_name: display_name.or_else(|| Some("Auto-discovered".to_string())),
+            imap_host: Some(self.imap_host),
+            imap_port: Some(self.imap_port as i64),
+            imap_use_tls: Some(self.imap_use_tls),
+            smtp_host: self.smtp_host,
+            smtp_port: self.smtp_port.map(|p| p as i64),
+            smtp_use_tls: self.smtp_use_tls,
+            smtp_use_starttls: self.smtp_use_starttls,
+            supports_oauth: false,
+            oauth_provider: None,
+            username: Some(expand_username(&self.username_pattern, email)),
+            discovery_source: Some(source.to_string()),
+        }
+    }
+}
+
+/// Fill in a Thunderbird username template (`%EMAILADDRESS%`,
+/// `%EMAILLOCALPART%`, `%EMAILDOMAIN%`).
+pub fn expand_username(template: &str, email: &str) -> String {
+    let (local, domain) = email.split_once('@').unwrap_or((email, ""));
+    template
+        .replace("%EMAILADDRESS%", email)
+        .replace("%EMAILLOCALPART%", local)
+        .replace("%EMAILDOMAIN%", domain)
+}
+
 pub struct AutodiscoveryService {
     resolver: TokioResolver,
     http_client: Client,
@@ -50,7 +120,7 @@ impl AutodiscoveryService {
         let http_client = Client::builder()
             .timeout(std::time::Duration::from_secs(10))
             .build()
-            .map_err(|e| AutodiscoveryError::HttpError(e))?;
+            .map_err(AutodiscoveryError::HttpError)?;
 
         Ok(Self {
             resolver,
@@ -59,32 +129,54 @@ impl AutodiscoveryService {
     }
 
     /// Discover email configuration for a given email address
-    pub async fn discover(&self, email: &str) -> Result<EmailConfig, AutodiscoveryError> {
+    pub async fn discover(&self, email: &str) -> Result<AutoConfigResult, AutodiscoveryError> {
         info!("Starting autodiscovery for email: {}", email);
 
         let domain = self.extract_domain(email)?;
         debug!("Extracted domain: {}", domain);
 
-        // Try RFC 6186 DNS SRV records first
+        let ttl = negative_ttl();
+        if let Some(found_at) = NEGATIVE_CACHE.get(&domain).map(|e| *e) {
+            if found_at.elapsed() < ttl {
+                debug!("Skipping autodiscovery for {}: nothing found {:?} ago", domain, found_at.elapsed());
+                return Err(AutodiscoveryError::NoConfigFound(domain));
+            }
+            NEGATIVE_CACHE.remove(&domain);
+        }
+
+        if let Ok((config, name)) = self.try_domain_autoconfig(email, &domain).await {
+            info!("Found configuration via domain autoconfig for {}", domain);
+            return Ok(config.into_result(email, "autoconfig", name));
+        }
+
+        if let Ok((config, name)) = self.try_ispdb(&domain).await {
+            info!("Found configuration via ISPDB for {}", domain);
+            return Ok(config.into_result(email, "ispdb", name));
+        }
+
         if let Ok(config) = self.try_rfc6186(&domain).await {
             info!("Found configuration via RFC 6186 for {}", domain);
-            return Ok(config);
+            return Ok(config.into_result(email, "srv", None));
         }
 
-        // Try Mozilla Autoconfig
-        if let Ok(config) = self.try_mozilla_autoconfig(email, &domain).await {
-            info!("Found configuration via Mozilla Autoconfig for {}", domain);
-            return Ok(config);
+        if let Some(config) = self.try_common_hosts(&domain).await {
+            info!("Found configuration by probing common hosts for {}", domain);
+            return Ok(config.into_result(email, "guess", None));
         }
 
+        if !ttl.is_zero() {
+            NEGATIVE_CACHE.insert(domain.clone(), Instant::now());
+        }
         Err(AutodiscoveryError::NoConfigFound(domain))
     }
 
     fn extract_domain(&self, email: &str) -> Result<String, AutodiscoveryError> {
-        email.split('@')
-            .nth(1)
-            .map(|s| s.to_string())
-            .ok_or_else(|| AutodiscoveryError::InvalidEmail(email.to_string()))
+        match email.trim().split_once('@') {
+            Some((local, domain)) if !local.is_empty() && !domain.is_empty() && !domain.contains('@') => {
+                Ok(domain.to_lowercase())
+            }
+            _ => Err(AutodiscoveryError::InvalidEmail(email.to_string())),
+        }
     }
 
     /// Try RFC 6186 DNS SRV record lookup
@@ -166,54 +258,131 @@ impl AutodiscoveryService {
         Err(AutodiscoveryError::DnsError(format!("No SRV records found for {}", domain)))
     }
 
-    /// Try Mozilla Autoconfig protocol
-    async fn try_mozilla_autoconfig(&self, email: &str, domain: &str) -> Result<EmailConfig, AutodiscoveryError> {
-        debug!("Attempting Mozilla Autoconfig for domain: {}", domain);
+    /// Autoconfig file published by the domain itself. Plain HTTP is tried
+    /// last because some providers serve nothing else.
+    async fn try_domain_autoconfig(&self, email: &str, domain: &str) -> Result<(EmailConfig, Option<String>), AutodiscoveryError> {
+        debug!("Attempting domain autoconfig for: {}", domain);
 
-        // Try autoconfig URLs in order of preference as per Mozilla spec
-        let urls = vec![
-            // 1. Domain-hosted autoconfig (HTTPS)
+        // A '+' or '&' in the local part would otherwise change the query
+        let email = urlencoding::encode(email);
+        let urls = [
             format!("https://autoconfig.{}/mail/config-v1.1.xml?emailaddress={}", domain, email),
-            // 2. Well-known location (HTTPS)
             format!("https://{}/.well-known/autoconfig/mail/config-v1.1.xml?emailaddress={}", domain, email),
-            // 3. Mozilla ISPDB (centralized database that Thunderbird uses)
-            format!("https://autoconfig.thunderbird.net/v1.1/{}", domain),
-            // 4. Fallback to HTTP if HTTPS fails (less secure but some providers only support HTTP)
             format!("http://autoconfig.{}/mail/config-v1.1.xml?emailaddress={}", domain, email),
         ];
+        for url in &urls {
+            if let Some(found) = self.fetch_autoconfig(url).await {
+                return Ok(found);
+            }
+        }
 
-        for url in urls {
-            debug!("Trying Mozilla Autoconfig URL: {}", url);
+        Err(AutodiscoveryError::NoConfigFound(domain.to_string()))
+    }
 
-            match self.http_client.get(&url).send().await {
-                Ok(response) if response.status().is_success() => {
-                    let xml_text = response.text().await?;
-                    debug!("Received autoconfig XML response");
+    /// Thunderbird's central database of provider settings.
+    async fn try_ispdb(&self, domain: &str) -> Result<(EmailConfig, Option<String>), AutodiscoveryError> {
+        debug!("Attempting ISPDB lookup for: {}", domain);
+        self.fetch_autoconfig(&format!("https://autoconfig.thunderbird.net/v1.1/{}", domain))
+            .await
+            .ok_or_else(|| AutodiscoveryError::NoConfigFound(domain.to_string()))
+    }
 
-                    match self.parse_mozilla_autoconfig(&xml_text) {
-                        Ok(config) => return Ok(config),
-                        Err(e) => {
-                            warn!("Failed to parse autoconfig XML from {}: {}", url, e);
-                            continue;
-                        }
-                    }
-                }
-                Ok(response) => {
-                    debug!("Autoconfig URL {} returned status: {}", url, response.status());
-                }
-                Err(e) => {
-                    debug!("Failed to fetch autoconfig from {}: {}", url, e);
-                }
+    /// Fetch and parse one autoconfig URL; failures are logged and skipped.
+    async fn fetch_autoconfig(&self, url: &str) -> Option<(EmailConfig, Option<String>)> {
+        debug!("Trying autoconfig URL: {}", url);
+
+        let response = match self.http_client.get(url).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                debug!("Autoconfig URL {} returned status: {}", url, response.status());
+                return None;
+            }
+            Err(e) => {
+                debug!("Failed to fetch autoconfig from {}: {}", url, e);
+                return None;
+            }
+        };
+        let xml = match response.text().await {
+            Ok(xml) => xml,
+            Err(e) => {
+                debug!("Failed to read autoconfig body from {}: {}", url, e);
+                return None;
+            }
+        };
+        match self.parse_mozilla_autoconfig(&xml) {
+            Ok(found) => Some(found),
+            Err(e) => {
+                warn!("Failed to parse autoconfig XML from {}: {}", url, e);
+                None
             }
         }
+    }
 
-        Err(AutodiscoveryError::NoConfigFound(domain.to_string()))
+    /// Last resort: connect to the usual host names and ports and keep the
+    /// first IMAP and SMTP endpoints that answer.
+    async fn try_common_hosts(&self, domain: &str) -> Option<EmailConfig> {
+        debug!("Probing common mail hosts for: {}", domain);
+
+        let imap_candidates = [
+            (format!("imap.{}", domain), 993, true),
+            (format!("mail.{}", domain), 993, true),
+            (format!("imap.{}", domain), 143, false),
+            (format!("mail.{}", domain), 143, false),
+        ];
+        let mut imap = None;
+        for (host, port, tls) in imap_candidates {
+            if Self::probe(&host, port).await {
+                imap = Some((host, port, tls));
+                break;
+            }
+        }
+        let (imap_host, imap_port, imap_use_tls) = imap?;
+
+        let smtp_candidates = [
+            (format!("smtp.{}", domain), 465),
+            (format!("smtp.{}", domain), 587),
+            (format!("mail.{}", domain), 465),
+            (format!("mail.{}", domain), 587),
+        ];
+        let mut config = EmailConfig {
+            imap_host,
+            imap_port,
+            imap_use_tls,
+            smtp_host: None,
+            smtp_port: None,
+            smtp_use_tls: None,
+            smtp_use_starttls: None,
+            username_pattern: "%EMAILADDRESS%".to_string(),
+        };
+        for (host, port) in smtp_candidates {
+            if Self::probe(&host, port).await {
+                config.smtp_host = Some(host);
+                config.smtp_port = Some(port);
+                config.smtp_use_tls = Some(port == 465);
+                config.smtp_use_starttls = Some(port == 587);
+                break;
+            }
+        }
+
+        Some(config)
     }
 
-    /// Parse Mozilla Autoconfig XML
-    fn parse_mozilla_autoconfig(&self, xml: &str) -> Result<EmailConfig, AutodiscoveryError> {
+    /// Whether `host:port` accepts a TCP connection within [`PROBE_TIMEOUT`].
+    async fn probe(host: &str, port: u16) -> bool {
+        match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port))).await {
+            Ok(Ok(_)) => {
+                debug!("{}:{} accepted a connection", host, port);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse Mozilla Autoconfig XML into the IMAP and SMTP servers it lists,
+    /// plus the provider's display name. Where several IMAP servers are
+    /// offered, implicit TLS beats STARTTLS beats plaintext.
+    fn parse_mozilla_autoconfig(&self, xml: &str) -> Result<(EmailConfig, Option<String>), AutodiscoveryError> {
         use quick_xml::de::from_str;
-        use serde::Deserialize;
 
         #[derive(Debug, Deserialize)]
         struct ClientConfig {
@@ -223,50 +392,53 @@ impl AutodiscoveryService {
 
         #[derive(Debug, Deserialize)]
         struct EmailProvider {
+            #[serde(rename = "displayName", default)]
+            display_name: Option<String>,
             #[serde(rename = "incomingServer", default)]
-            incoming_servers: Vec<IncomingServer>,
+            incoming_servers: Vec<Server>,
             #[serde(rename = "outgoingServer", default)]
-            outgoing_servers: Vec<OutgoingServer>,
+            outgoing_servers: Vec<Server>,
         }
 
         #[derive(Debug, Deserialize)]
-        struct IncomingServer {
+        struct Server {
             #[serde(rename = "@type")]
             server_type: String,
             hostname: String,
             port: u16,
             #[serde(rename = "socketType")]
             socket_type: String,
+            #[serde(default = "default_username")]
             username: String,
         }
 
-        #[derive(Debug, Deserialize)]
-        struct OutgoingServer {
-            #[serde(rename = "@type")]
-            server_type: String,
-            hostname: String,
-            port: u16,
-            #[serde(rename = "socketType")]
-            socket_type: String,
-            username: String,
+        fn default_username() -> String {
+            "%EMAILADDRESS%".to_string()
+        }
+
+        fn tls_rank(socket_type: &str) -> u8 {
+            match socket_type.to_uppercase().as_str() {
+                "SSL" => 0,
+                "STARTTLS" => 1,
+                _ => 2,
+            }
         }
 
         let config: ClientConfig = from_str(xml)
             .map_err(|e| AutodiscoveryError::XmlError(e.to_string()))?;
+        let provider = config.email_provider;
 
-        // Find IMAP server (prefer IMAP over POP3)
-        let imap_server = config.email_provider.incoming_servers
+        // IMAP only; POP3 entries are ignored
+        let imap_server = provider.incoming_servers
             .iter()
-            .find(|s| s.server_type.to_lowercase() == "imap")
+            .filter(|s| s.server_type.eq_ignore_ascii_case("imap"))
+            .min_by_key(|s| tls_rank(&s.socket_type))
             .ok_or_else(|| AutodiscoveryError::XmlError("No IMAP server found".to_string()))?;
 
-        // Parse socket type for TLS settings
-        let imap_use_tls = imap_server.socket_type.to_uppercase() == "SSL";
-
         let mut email_config = EmailConfig {
             imap_host: imap_server.hostname.clone(),
             imap_port: imap_server.port,
-            imap_use_tls,
+            imap_use_tls: imap_server.socket_type.eq_ignore_ascii_case("SSL"),
             smtp_host: None,
             smtp_port: None,
             smtp_use_tls: None,
@@ -274,18 +446,18 @@ impl AutodiscoveryService {
             username_pattern: imap_server.username.clone(),
         };
 
-        // Add SMTP config if available
-        if let Some(smtp_server) = config.email_provider.outgoing_servers.first() {
-            let smtp_use_tls = smtp_server.socket_type.to_uppercase() == "SSL";
-            let smtp_use_starttls = smtp_server.socket_type.to_uppercase() == "STARTTLS";
-
+        if let Some(smtp_server) = provider.outgoing_servers
+            .iter()
+            .filter(|s| s.server_type.eq_ignore_ascii_case("smtp"))
+            .min_by_key(|s| tls_rank(&s.socket_type))
+        {
             email_config.smtp_host = Some(smtp_server.hostname.clone());
             email_config.smtp_port = Some(smtp_server.port);
-            email_config.smtp_use_tls = Some(smtp_use_tls);
-            email_config.smtp_use_starttls = Some(smtp_use_starttls);
+            email_config.smtp_use_tls = Some(smtp_server.socket_type.eq_ignore_ascii_case("SSL"));
+            email_config.smtp_use_starttls = Some(smtp_server.socket_type.eq_ignore_ascii_case("STARTTLS"));
         }
 
-        Ok(email_config)
+        Ok((email_config, provider.display_name))
     }
 }
 
@@ -308,6 +480,19 @@ mod tests {
         let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
 <clientConfig version="1.1">
   <emailProvider id="example.com" type="generic">
+    <displayName>Example Mail</displayName>
+    <incomingServer type="pop3">
+      <hostname>pop.example.com</hostname>
+      <port>995</port>
+      <socketType>SSL</socketType>
+      <username>%EMAILADDRESS%</username>
+    </incomingServer>
+    <incomingServer type="imap">
+      <hostname>imap.example.com</hostname>
+      <port>143</port>
+      <socketType>STARTTLS</socketType>
+      <username>%EMAILLOCALPART%</username>
+    </incomingServer>
     <incomingServer type="imap">
       <hostname>imap.example.com</hostname>
       <port>993</port>
@@ -325,12 +510,24 @@ mod tests {
   </emailProvider>
 </clientConfig>"#;
 
-        let config = service.parse_mozilla_autoconfig(xml).unwrap();
+        let (config, display_name) = service.parse_mozilla_autoconfig(xml).unwrap();
+        assert_eq!(display_name.as_deref(), Some("Example Mail"));
         assert_eq!(config.imap_host, "imap.example.com");
         assert_eq!(config.imap_port, 993);
         assert_eq!(config.imap_use_tls, true);
         assert_eq!(config.smtp_host.unwrap(), "smtp.example.com");
         assert_eq!(config.smtp_port.unwrap(), 587);
         assert_eq!(config.smtp_use_starttls.unwrap(), true);
+
+        let result = config.into_result("jane@example.com", "autoconfig", display_name);
+        assert_eq!(result.username.as_deref(), Some("jane@example.com"));
+        assert_eq!(result.discovery_source.as_deref(), Some("autoconfig"));
+    }
+
+    #[test]
+    fn test_expand_username() {
+        assert_eq!(expand_username("%EMAILLOCALPART%", "jane@example.com"), "jane");
+        assert_eq!(expand_username("%EMAILLOCALPART%@mail.%EMAILDOMAIN%", "jane@example.com"), "jane@mail.example.com");
+        assert_eq!(expand_username("%EMAILADDRESS%", "jane@example.com"), "jane@example.com");
     }
 }
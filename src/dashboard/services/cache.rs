@@ -9,11 +9,13 @@ use tokio::sync::RwLock;
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions, Row};
 use chrono::{DateTime, Utc};
 use lru::LruCache;
+use arc_swap::ArcSwap;
 use std::num::NonZeroUsize;
 use log::{info, error, debug, warn};
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
 use crate::imap::types::{Email, Address};
+use super::cache_crypto::{self, CacheCryptoError, CacheEncryption, ReencryptReport};
 
 // Default account email for backwards compatibility wrapper methods
 // This should match one of the actual accounts in the database
@@ -27,6 +29,8 @@ pub enum CacheError {
     NotInitialized,
     #[error("Cache operation failed: {0}")]
     OperationFailed(String),
+    #[error("Cache encryption error: {0}")]
+    Encryption(#[from] CacheCryptoError),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +70,325 @@ pub struct CachedEmail {
     pub references_header: Option<String>,
     /// JSON array of attachment metadata: [{"filename","content_type","size"},...]
     pub attachment_parts: Option<String>,
+    /// Readable preview for list views; see `utils::plain_text::generate_snippet`
+    pub snippet: Option<String>,
+}
+
+/// A full-text search hit from `search_cached_emails_ranked`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedEmail {
+    pub email: CachedEmail,
+    pub folder: String,
+    /// Relevance, higher is better; 0 when the LIKE fallback was used
+    pub score: f64,
+    /// Body excerpt around the matched terms
+    pub snippet: Option<String>,
+}
+
+/// Turn free text into an FTS5 MATCH expression that can't be a syntax
+/// error: every word becomes a quoted phrase (all must match), and a trailing
+/// `*` keeps prefix matching. `None` when there is nothing to search for.
+pub fn fts_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter_map(|word| {
+            let prefix = word.ends_with('*');
+            let word: String = word.chars().filter(|c| *c != '"' && *c != '*').collect();
+            // Punctuation-only words index to nothing
+            if !word.chars().any(char::is_alphanumeric) {
+                return None;
+            }
+            Some(if prefix { format!("\"{}\"*", word) } else { format!("\"{}\"", word) })
+        })
+        .collect();
+    if terms.is_empty() { None } else { Some(terms.join(" ")) }
+}
+
+/// Message metadata as returned by `get_flagged_emails_for_account` and
+/// `get_recent_emails_for_account`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlaggedEmail {
+    pub folder: String,
+    pub uid: u32,
+    pub message_id: Option<String>,
+    pub subject: Option<String>,
+    pub from_address: Option<String>,
+    pub from_name: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+    pub internal_date: Option<DateTime<Utc>>,
+    pub flags: Vec<String>,
+    pub has_attachments: bool,
+}
+
+/// RECENT count from the last SELECT by sync, and when it was taken.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderRecent {
+    pub folder: String,
+    pub recent_messages: i64,
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+/// Per-account cache totals and sync recency, aggregated across all folders.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountCacheSummary {
+    pub account_id: String,
+    pub folder_count: i64,
+    pub total_messages: i64,
+    pub unread_messages: i64,
+    pub size_bytes: i64,
+    /// Most recent sync activity in any folder
+    pub last_sync: Option<DateTime<Utc>>,
+    /// Least recent sync among folders that have synced at least once
+    pub oldest_folder_sync: Option<DateTime<Utc>>,
+    pub folders_syncing: i64,
+    pub folders_in_error: i64,
+}
+
+/// How current one folder's cache is.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderFreshness {
+    pub folder: String,
+    /// Messages in the cache
+    pub cached_count: i64,
+    /// Message count the server reported at the last sync, or from a live
+    /// STATUS when `live` is set
+    pub server_count: Option<i64>,
+    /// Whether `server_count` came from a live STATUS
+    pub live: bool,
+    /// Most recent full or incremental sync
+    pub last_sync: Option<DateTime<Utc>>,
+    pub age_seconds: Option<i64>,
+    pub syncing: bool,
+    pub sync_error: Option<String>,
+    pub stale: bool,
+    /// Why the folder counts as stale
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_reason: Option<String>,
+}
+
+impl FolderFreshness {
+    /// Fill in `age_seconds`, `stale` and `stale_reason` as of `now`.
+    pub fn evaluate(&mut self, now: DateTime<Utc>, stale_after: chrono::Duration) {
+        self.age_seconds = self.last_sync.map(|t| (now - t).num_seconds().max(0));
+        self.stale_reason = match (self.last_sync, &self.sync_error) {
+            (None, _) => Some("never synced".to_string()),
+            (_, Some(error)) => Some(format!("last sync failed: {}", error)),
+            (Some(t), None) if now - t > stale_after => {
+                Some(format!("last synced more than {}s ago", stale_after.num_seconds()))
+            }
+            _ => match self.server_count {
+                Some(server) if self.live && server != self.cached_count => {
+                    Some(format!("server has {} messages, cache has {}", server, self.cached_count))
+                }
+                _ => None,
+            },
+        };
+        self.stale = self.stale_reason.is_some();
+    }
+}
+
+/// How far one folder's cache trails the server.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FolderLag {
+    pub folder: String,
+    pub cached_messages: i64,
+    pub cached_unseen: i64,
+    /// From a live STATUS; `None` when the server didn't answer for this folder
+    pub server_messages: Option<i64>,
+    pub server_unseen: Option<i64>,
+    /// Server minus cache; positive means the cache is missing messages
+    pub message_delta: Option<i64>,
+    pub unseen_delta: Option<i64>,
+    pub last_sync: Option<DateTime<Utc>>,
+    pub age_seconds: Option<i64>,
+    /// STATUS or last-sync error for this folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FolderLag {
+    /// Messages and unread flags the cache disagrees with the server on.
+    pub fn score(&self) -> i64 {
+        self.message_delta.unwrap_or(0).abs() + self.unseen_delta.unwrap_or(0).abs()
+    }
+}
+
+/// Sync lag of one account, aggregated over its folders.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountLag {
+    pub account_id: String,
+    /// "ok", "behind" (counts differ or a sync is older than the threshold)
+    /// or "failing" (the server or a folder sync reported an error)
+    pub status: &'static str,
+    /// Sum of the folder scores; 0 when the cache matches the server
+    pub lag_score: i64,
+    pub folders_behind: usize,
+    pub oldest_sync_age_seconds: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub folders: Vec<FolderLag>,
+}
+
+impl AccountLag {
+    /// Fill in the folders' deltas and ages as of `now` and aggregate them.
+    /// A folder never synced counts as older than `stale_after`.
+    pub fn summarize(account_id: &str, mut folders: Vec<FolderLag>, error: Option<String>, now: DateTime<Utc>, stale_after: chrono::Duration) -> Self {
+        for folder in &mut folders {
+            folder.message_delta = folder.server_messages.map(|n| n - folder.cached_messages);
+            folder.unseen_delta = folder.server_unseen.map(|n| n - folder.cached_unseen);
+            folder.age_seconds = folder.last_sync.map(|t| (now - t).num_seconds().max(0));
+        }
+
+        let lag_score = folders.iter().map(FolderLag::score).sum();
+        let folders_behind = folders.iter().filter(|f| f.score() > 0).count();
+        let oldest_sync_age_seconds = folders.iter().filter_map(|f| f.age_seconds).max();
+        let out_of_date = folders.iter()
+            .any(|f| f.age_seconds.is_none_or(|age| age > stale_after.num_seconds()));
+
+        let status = if error.is_some() || folders.iter().any(|f| f.error.is_some()) {
+            "failing"
+        } else if lag_score > 0 || out_of_date {
+            "behind"
+        } else {
+            "ok"
+        };
+
+        Self {
+            account_id: account_id.to_string(),
+            status,
+            lag_score,
+            folders_behind,
+            oldest_sync_age_seconds,
+            error,
+            folders,
+        }
+    }
+}
+
+/// Where a page of a folder listing starts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageStart {
+    /// Skip this many emails. Slow deep into large folders, and shifts when
+    /// mail arrives between pages.
+    Offset(usize),
+    /// Continue after the last email of the previous page
+    After(EmailCursor),
+}
+
+/// Keyset position in a folder listing: the sort date and UID of the last
+/// email returned. Listings are ordered by `(COALESCE(date, internal_date),
+/// uid)` descending, so a page that starts after a cursor is unaffected by
+/// newer mail arriving meanwhile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailCursor {
+    /// The sort date as stored; None for emails with no date at all
+    pub sort_key: Option<String>,
+    pub uid: u32,
+}
+
+impl EmailCursor {
+    /// Opaque URL-safe form handed to clients as `next_cursor`.
+    pub fn encode(&self) -> String {
+        use base64::Engine;
+        let raw = match &self.sort_key {
+            Some(key) => format!("{}:{}", self.uid, key),
+            None => self.uid.to_string(),
+        };
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        use base64::Engine;
+        let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(cursor.trim()).ok()?;
+        let raw = String::from_utf8(raw).ok()?;
+        match raw.split_once(':') {
+            Some((uid, key)) => Some(Self { sort_key: Some(key.to_string()), uid: uid.parse().ok()? }),
+            None => Some(Self { sort_key: None, uid: raw.parse().ok()? }),
+        }
+    }
+}
+
+/// Identity of a cached message for comparing folders: its Message-ID, or a
+/// hash of envelope fields when the message has none.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderMessageKey {
+    pub uid: u32,
+    pub key: String,
+    pub message_id: Option<String>,
+    pub subject: Option<String>,
+    pub from_address: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+}
+
+impl FolderMessageKey {
+    fn new(uid: u32, message_id: Option<String>, subject: Option<String>, from_address: Option<String>, date: Option<DateTime<Utc>>, size: Option<i64>) -> Self {
+        let message_id = message_id
+            .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+            .filter(|id| !id.is_empty());
+        let key = match &message_id {
+            Some(id) => format!("message-id:{}", id),
+            None => {
+                // Subject, sender, date and size all survive a COPY/APPEND unchanged
+                use sha2::{Digest, Sha256};
+                let mut hasher = Sha256::new();
+                hasher.update(subject.as_deref().unwrap_or("").as_bytes());
+                hasher.update([0]);
+                hasher.update(from_address.as_deref().unwrap_or("").to_lowercase().as_bytes());
+                hasher.update([0]);
+                hasher.update(date.map(|d| d.timestamp()).unwrap_or(0).to_be_bytes());
+                hasher.update(size.unwrap_or(0).to_be_bytes());
+                format!("content-hash:{}", hex::encode(&hasher.finalize()[..16]))
+            }
+        };
+        Self { uid, key, message_id, subject, from_address, date }
+    }
+}
+
+/// Messages present in only one of two folders, matched by `FolderMessageKey::key`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FolderDiff {
+    pub only_in_a: Vec<FolderMessageKey>,
+    pub only_in_b: Vec<FolderMessageKey>,
+    pub matched: usize,
+    /// Messages in either folder identified by content hash rather than Message-ID
+    pub hashed: usize,
+}
+
+impl FolderDiff {
+    /// Compare two folders' keys. Duplicates are counted, so a folder holding
+    /// two copies of a message the other folder has once reports the extra copy.
+    pub fn compare(a: &[FolderMessageKey], b: &[FolderMessageKey]) -> Self {
+        let mut remaining: HashMap<&str, usize> = HashMap::new();
+        for entry in b {
+            *remaining.entry(entry.key.as_str()).or_insert(0) += 1;
+        }
+
+        let mut diff = FolderDiff {
+            hashed: a.iter().chain(b).filter(|e| e.message_id.is_none()).count(),
+            ..Default::default()
+        };
+        for entry in a {
+            match remaining.get_mut(entry.key.as_str()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    diff.matched += 1;
+                }
+                _ => diff.only_in_a.push(entry.clone()),
+            }
+        }
+
+        // Whatever is left unmatched in `b`, taking the later copies of duplicates
+        for entry in b.iter().rev() {
+            if let Some(count) = remaining.get_mut(entry.key.as_str()) {
+                if *count > 0 {
+                    *count -= 1;
+                    diff.only_in_b.push(entry.clone());
+                }
+            }
+        }
+        diff.only_in_b.reverse();
+        diff
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -78,6 +401,8 @@ pub struct SyncState {
     pub error_message: Option<String>,
     pub emails_synced: i32,
     pub emails_total: i32,
+    /// HIGHESTMODSEQ recorded by the last CONDSTORE sync
+    pub modseq: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -91,7 +416,9 @@ pub struct CacheService {
     pub db_pool: Option<SqlitePool>,
     memory_cache: Arc<RwLock<LruCache<String, CachedEmail>>>,
     folder_cache: Arc<RwLock<LruCache<String, CachedFolder>>>,
-    config: CacheConfig,
+    /// Swapped by `set_limits` on a config reload
+    config: ArcSwap<CacheConfig>,
+    encryption: std::sync::RwLock<CacheEncryption>,
 }
 
 impl std::fmt::Debug for CacheService {
@@ -100,7 +427,7 @@ impl std::fmt::Debug for CacheService {
             .field("db_pool", &self.db_pool.is_some())
             .field("memory_cache", &"<LruCache>")
             .field("folder_cache", &"<LruCache>")
-            .field("config", &self.config)
+            .field("config", &self.config.load())
             .finish()
     }
 }
@@ -113,6 +440,47 @@ pub struct CacheConfig {
     pub max_cache_size_mb: u64,
     pub max_email_age_days: u32,
     pub sync_interval_seconds: u64,
+    /// Passphrase for encrypting cached subjects and bodies; None stores plaintext
+    pub encryption_passphrase: Option<String>,
+    /// Leave \Flagged (starred/pinned) emails alone when pruning
+    pub prune_keep_flagged: bool,
+}
+
+/// The part of `CacheConfig` that can change while running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheLimits {
+    pub max_memory_items: usize,
+    pub max_folder_items: usize,
+    pub max_cache_size_mb: u64,
+    pub max_email_age_days: u32,
+}
+
+impl CacheLimits {
+    /// Read from `CACHE_MAX_MEMORY_ITEMS`, `CACHE_MAX_FOLDER_ITEMS`,
+    /// `CACHE_MAX_SIZE_MB` and `CACHE_MAX_EMAIL_AGE_DAYS`.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name).ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+        }
+        Self {
+            max_memory_items: var("CACHE_MAX_MEMORY_ITEMS", 1000),
+            max_folder_items: var("CACHE_MAX_FOLDER_ITEMS", 100),
+            max_cache_size_mb: var("CACHE_MAX_SIZE_MB", 1000),
+            max_email_age_days: var("CACHE_MAX_EMAIL_AGE_DAYS", 30),
+        }
+    }
+}
+
+/// What one `CacheService::prune` pass removed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    /// Emails older than `max_email_age_days`
+    pub expired: u64,
+    /// Emails removed oldest-first to get under `max_cache_size_mb`
+    pub over_budget: u64,
+    /// Bytes in use before and after, VACUUM included
+    pub bytes_before: i64,
+    pub bytes_after: i64,
 }
 
 impl Default for CacheConfig {
@@ -124,8 +492,79 @@ impl Default for CacheConfig {
             max_cache_size_mb: 500,  // Reduced from 1000
             max_email_age_days: 30,
             sync_interval_seconds: 300,
+            encryption_passphrase: None,
+            prune_keep_flagged: true,
+        }
+    }
+}
+
+/// Emails deleted per statement by `CacheService::prune`.
+const PRUNE_BATCH: i64 = 500;
+
+/// Whether `initialize` snapshots the database before applying pending
+/// migrations (`CACHE_MIGRATION_BACKUP`, default true).
+fn migration_backup_enabled() -> bool {
+    std::env::var("CACHE_MIGRATION_BACKUP")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Number of pre-migration backups to keep (`CACHE_MIGRATION_BACKUP_KEEP`, default 5).
+fn migration_backup_keep() -> usize {
+    std::env::var("CACHE_MIGRATION_BACKUP_KEEP")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5)
+}
+
+/// Write a consistent copy of the database to a timestamped file in
+/// `backup_dir` and delete all but the newest `keep` backups of it.
+///
+/// Uses `VACUUM INTO` rather than a file copy so pages still in the WAL
+/// are included and concurrent writers can't tear the snapshot.
+pub async fn backup_database(
+    pool: &SqlitePool,
+    db_path: &std::path::Path,
+    backup_dir: &std::path::Path,
+    keep: usize,
+) -> Result<std::path::PathBuf, CacheError> {
+    let stem = db_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("email_cache");
+    let prefix = format!("{}.pre-migration-", stem);
+
+    std::fs::create_dir_all(backup_dir).map_err(|e|
+        CacheError::OperationFailed(format!("Failed to create backup directory {}: {}", backup_dir.display(), e))
+    )?;
+
+    let backup_path = backup_dir.join(format!("{}{}.db", prefix, Utc::now().format("%Y%m%dT%H%M%S%.3fZ")));
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup_path.to_string_lossy().to_string())
+        .execute(pool)
+        .await?;
+
+    // Timestamps sort lexically, so the oldest backups come first
+    let mut backups: Vec<std::path::PathBuf> = std::fs::read_dir(backup_dir)
+        .map_err(|e| CacheError::OperationFailed(format!("Failed to read backup directory: {}", e)))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".db"))
+        })
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for old in backups.into_iter().take(excess) {
+        match std::fs::remove_file(&old) {
+            Ok(()) => debug!("Removed old migration backup {}", old.display()),
+            Err(e) => warn!("Failed to remove old migration backup {}: {}", old.display(), e),
         }
     }
+
+    Ok(backup_path)
 }
 
 impl CacheService {
@@ -141,15 +580,17 @@ impl CacheService {
             db_pool: None,
             memory_cache,
             folder_cache,
-            config,
+            config: ArcSwap::from_pointee(config),
+            encryption: std::sync::RwLock::new(CacheEncryption::Off),
         }
     }
 
     pub async fn initialize(&mut self) -> Result<(), CacheError> {
-        info!("Initializing cache service with database: {}", self.config.database_url);
+        let config = self.config.load_full();
+        info!("Initializing cache service with database: {}", config.database_url);
 
         // Extract the file path from the database URL
-        let db_path = self.config.database_url.replace("sqlite:", "");
+        let db_path = config.database_url.replace("sqlite:", "");
         let path = std::path::Path::new(&db_path);
 
         // Create data directory if it doesn't exist
@@ -170,15 +611,37 @@ impl CacheService {
         // Create database connection pool
         let pool = SqlitePoolOptions::new()
             .max_connections(5)
-            .connect(&self.config.database_url)
+            .connect(&config.database_url)
             .await?;
 
+        let migrator = sqlx::migrate!("./migrations");
+
+        // Snapshot the existing database so a bad migration is recoverable
+        if migration_backup_enabled() {
+            let pending = Self::pending_migrations(&pool, &migrator).await?;
+            let has_data = std::fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false);
+            if pending > 0 && has_data {
+                let backup_dir = std::env::var("CACHE_MIGRATION_BACKUP_DIR")
+                    .map(std::path::PathBuf::from)
+                    .unwrap_or_else(|_| path.parent().unwrap_or(std::path::Path::new(".")).join("backups"));
+                let backup = backup_database(&pool, path, &backup_dir, migration_backup_keep()).await
+                    .map_err(|e| CacheError::OperationFailed(format!(
+                        "Failed to back up database before {} pending migration(s), set CACHE_MIGRATION_BACKUP=false to skip: {}",
+                        pending, e
+                    )))?;
+                info!("Backed up database to {} before applying {} migration(s)", backup.display(), pending);
+            }
+        }
+
         // Run migrations to ensure tables exist
-        sqlx::migrate!("./migrations")
+        migrator
             .run(&pool)
             .await
             .map_err(|e| CacheError::OperationFailed(format!("Failed to run migrations: {}", e)))?;
 
+        let encryption = cache_crypto::prepare(&pool, config.encryption_passphrase.as_deref()).await?;
+        self.set_encryption(encryption);
+
         self.db_pool = Some(pool);
 
         // Load folders into cache
@@ -188,6 +651,80 @@ impl CacheService {
         Ok(())
     }
 
+    fn encryption(&self) -> CacheEncryption {
+        self.encryption.read().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    fn set_encryption(&self, encryption: CacheEncryption) {
+        cache_crypto::install(&encryption);
+        if let Ok(mut current) = self.encryption.write() {
+            *current = encryption;
+        }
+    }
+
+    /// Encrypt every plaintext subject and body already in the cache and
+    /// switch a read-only plaintext database over to encrypted caching.
+    pub async fn reencrypt(&self) -> Result<ReencryptReport, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+        let config = self.config.load_full();
+        let passphrase = config.encryption_passphrase.as_deref()
+            .ok_or(CacheCryptoError::PassphraseRequired)?;
+        let (cipher, report) = cache_crypto::reencrypt(pool, passphrase).await?;
+        self.set_encryption(CacheEncryption::On(cipher));
+        Ok(report)
+    }
+
+    /// Decrypt the encrypted columns of emails read from the database, and
+    /// build a snippet for rows cached before snippets were stored.
+    fn reveal_emails(&self, emails: &mut [CachedEmail]) -> Result<(), CacheError> {
+        let encryption = self.encryption();
+        if let Some(cipher) = encryption.reader() {
+            for email in emails.iter_mut() {
+                email.subject = cipher.open_opt(email.subject.take())?;
+                email.body_text = cipher.open_opt(email.body_text.take())?;
+                email.body_html = cipher.open_opt(email.body_html.take())?;
+                email.snippet = cipher.open_opt(email.snippet.take())?;
+            }
+        }
+        for email in emails.iter_mut().filter(|e| e.snippet.is_none()) {
+            email.snippet = Some(crate::utils::plain_text::snippet_from_parts(
+                email.body_text.as_deref(),
+                email.body_html.as_deref(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Decrypt a subject read from the database.
+    fn reveal_subject(&self, subject: Option<String>) -> Result<Option<String>, CacheError> {
+        match self.encryption().reader() {
+            Some(cipher) => Ok(cipher.open_opt(subject)?),
+            None => Ok(subject),
+        }
+    }
+
+    /// Migrations in `migrator` that haven't been applied to this database yet.
+    async fn pending_migrations(pool: &SqlitePool, migrator: &sqlx::migrate::Migrator) -> Result<usize, CacheError> {
+        let table_exists: Option<i64> = sqlx::query_scalar(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'"
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        let applied: Vec<i64> = if table_exists.is_some() {
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+                .fetch_all(pool)
+                .await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(migrator
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+            .count())
+    }
+
     async fn load_folders_to_cache(&self) -> Result<(), CacheError> {
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
 
@@ -367,7 +904,11 @@ impl CacheService {
         let deduped_flags: Vec<&str> = email.flags.iter().map(|s| s.as_str())
             .collect::<std::collections::BTreeSet<_>>().into_iter().collect();
         let flags = serde_json::to_string(&deduped_flags).unwrap_or_else(|_| "[]".to_string());
-        let headers = "{}".to_string(); // Headers not directly available
+        // Only the reply-addressing headers are kept; see `get_reply_headers`
+        let headers = parsed_message.as_ref()
+            .map(super::reply::ReplyHeaders::from_message)
+            .and_then(|h| serde_json::to_string(&h).ok())
+            .unwrap_or_else(|| "{}".to_string());
 
         // Determine if email has attachments from MIME structure
         let has_attachments = !email.attachments.is_empty();
@@ -391,6 +932,20 @@ impl CacheService {
             None
         };
 
+        let snippet = crate::utils::plain_text::generate_snippet(email);
+
+        // Encrypt the private columns when the cache is encrypted at rest
+        let encryption = self.encryption();
+        let (stored_subject, stored_text, stored_html, stored_snippet) = match encryption.writer()? {
+            Some(cipher) => (
+                cipher.seal_opt(subject.as_deref())?,
+                cipher.seal_opt(email.text_body.as_deref())?,
+                cipher.seal_opt(email.html_body.as_deref())?,
+                cipher.seal(&snippet)?,
+            ),
+            None => (subject.clone(), email.text_body.clone(), email.html_body.clone(), snippet.clone()),
+        };
+
         // Insert or update email in database
         let email_id = sqlx::query_scalar::<_, i64>(
             r#"
@@ -398,8 +953,8 @@ impl CacheService {
                 folder_id, uid, message_id, subject, from_address, from_name,
                 to_addresses, cc_addresses, date, internal_date, size, flags,
                 headers, body_text, body_html, has_attachments,
-                in_reply_to, references_header, attachment_parts
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                in_reply_to, references_header, attachment_parts, snippet
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(folder_id, uid) DO UPDATE SET
                 message_id = excluded.message_id,
                 subject = excluded.subject,
@@ -418,6 +973,7 @@ impl CacheService {
                 in_reply_to = excluded.in_reply_to,
                 references_header = excluded.references_header,
                 attachment_parts = excluded.attachment_parts,
+                snippet = excluded.snippet,
                 updated_at = CURRENT_TIMESTAMP
             RETURNING id
             "#
@@ -425,7 +981,7 @@ impl CacheService {
         .bind(folder.id)
         .bind(email.uid as i64)
         .bind(&message_id)
-        .bind(&subject)
+        .bind(&stored_subject)
         .bind(&from)
         .bind(&from_name)
         .bind(to_addresses)
@@ -435,12 +991,13 @@ impl CacheService {
         .bind(email.body.as_ref().map(|b| b.len() as i64))
         .bind(flags)
         .bind(headers)
-        .bind(&email.text_body)
-        .bind(&email.html_body)
+        .bind(&stored_text)
+        .bind(&stored_html)
         .bind(has_attachments)
         .bind(&in_reply_to)
         .bind(&references_header)
         .bind(&attachment_parts)
+        .bind(&stored_snippet)
         .fetch_one(pool)
         .await?;
 
@@ -477,6 +1034,7 @@ impl CacheService {
             in_reply_to: in_reply_to.clone(),
             references_header: references_header.clone(),
             attachment_parts: attachment_parts.clone(),
+            snippet: Some(snippet),
         };
 
         // Add to memory cache with account_id to prevent cross-account data leakage
@@ -484,45 +1042,352 @@ impl CacheService {
         let mut memory_cache = self.memory_cache.write().await;
         memory_cache.put(cache_key, cached_email);
 
-        debug!("Cached email {} in folder {} for account {}", email.uid, folder_name, account_id);
-        Ok(())
+        debug!("Cached email {} in folder {} for account {}", email.uid, folder_name, account_id);
+        Ok(())
+    }
+
+    /// Get all cached UIDs for a folder (used for flag resync).
+    pub async fn get_cached_uids(&self, folder_name: &str, account_id: &str) -> Result<Vec<u32>, CacheError> {
+        let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
+            Some(f) => f,
+            None => return Ok(Vec::new()),
+        };
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+        let rows = sqlx::query_scalar::<_, i64>("SELECT uid FROM emails WHERE folder_id = ?")
+            .bind(folder.id)
+            .fetch_all(pool)
+            .await?;
+        Ok(rows.into_iter().map(|uid| uid as u32).collect())
+    }
+
+    /// Update only the flags for an existing cached email (lightweight flag resync).
+    pub async fn update_email_flags(&self, folder_name: &str, uid: u32, flags: &[String], account_id: &str) -> Result<(), CacheError> {
+        let folder = self.get_or_create_folder_for_account(folder_name, account_id).await?;
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+        let deduped: Vec<&str> = flags.iter().map(|s| s.as_str())
+            .collect::<std::collections::BTreeSet<_>>().into_iter().collect();
+        let flags_json = serde_json::to_string(&deduped).unwrap_or_else(|_| "[]".to_string());
+
+        sqlx::query("UPDATE emails SET flags = ?, updated_at = CURRENT_TIMESTAMP WHERE folder_id = ? AND uid = ?")
+            .bind(&flags_json)
+            .bind(folder.id)
+            .bind(uid as i64)
+            .execute(pool)
+            .await?;
+
+        // Invalidate memory cache entry
+        let cache_key = format!("{}:{}:{}", account_id, folder_name, uid);
+        let mut memory_cache = self.memory_cache.write().await;
+        memory_cache.pop(&cache_key);
+
+        Ok(())
+    }
+
+    /// Add or remove a single flag on cached emails after a successful STORE,
+    /// so the cache matches the server without waiting for the next flag resync.
+    /// `flag` uses the stored form without a backslash (e.g. "Flagged").
+    pub async fn set_cached_flag(&self, folder_name: &str, uids: &[u32], flag: &str, present: bool, account_id: &str) -> Result<(), CacheError> {
+        let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+        let backslashed = format!("\\{}", flag);
+
+        for &uid in uids {
+            let flags_json = sqlx::query_scalar::<_, String>(
+                "SELECT flags FROM emails WHERE folder_id = ? AND uid = ?"
+            )
+            .bind(folder.id)
+            .bind(uid as i64)
+            .fetch_optional(pool)
+            .await?;
+
+            let Some(flags_json) = flags_json else {
+                continue;
+            };
+            let mut flags: Vec<String> = serde_json::from_str(&flags_json).unwrap_or_default();
+            flags.retain(|f| f != flag && f != &backslashed);
+            if present {
+                flags.push(flag.to_string());
+            }
+            self.update_email_flags(folder_name, uid, &flags, account_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Flagged (starred) emails for an account across all cached folders,
+    /// newest first. Metadata only.
+    pub async fn get_flagged_emails_for_account(&self, account_id: &str, limit: usize, offset: usize) -> Result<Vec<FlaggedEmail>, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT f.name AS folder, e.uid, e.message_id, e.subject, e.from_address, e.from_name,
+                   e.date, e.internal_date, e.flags, e.has_attachments
+            FROM emails e
+            JOIN folders f ON e.folder_id = f.id
+            WHERE f.account_id = ?
+              AND e.flags LIKE '%"Flagged"%'
+            ORDER BY COALESCE(e.date, e.internal_date) DESC
+            LIMIT ? OFFSET ?
+            "#
+        )
+        .bind(account_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(|row| -> Result<FlaggedEmail, CacheError> {
+            let flags_json: String = row.get("flags");
+            Ok(FlaggedEmail {
+                folder: row.get("folder"),
+                uid: row.get::<i64, _>("uid") as u32,
+                message_id: row.get("message_id"),
+                subject: self.reveal_subject(row.get("subject"))?,
+                from_address: row.get("from_address"),
+                from_name: row.get("from_name"),
+                date: row.get("date"),
+                internal_date: row.get("internal_date"),
+                flags: serde_json::from_str(&flags_json).unwrap_or_default(),
+                has_attachments: row.get::<i32, _>("has_attachments") != 0,
+            })
+        }).collect()
+    }
+
+    /// Emails whose cached flags include \Recent, newest first, across all
+    /// folders or just `folder_name`. Metadata only.
+    pub async fn get_recent_emails_for_account(&self, account_id: &str, folder_name: Option<&str>, limit: usize) -> Result<Vec<FlaggedEmail>, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT f.name AS folder, e.uid, e.message_id, e.subject, e.from_address, e.from_name,
+                   e.date, e.internal_date, e.flags, e.has_attachments
+            FROM emails e
+            JOIN folders f ON e.folder_id = f.id
+            WHERE f.account_id = ?
+              AND (? IS NULL OR f.name = ?)
+              AND e.flags LIKE '%Recent"%'
+            ORDER BY COALESCE(e.date, e.internal_date) DESC
+            LIMIT ?
+            "#
+        )
+        .bind(account_id)
+        .bind(folder_name)
+        .bind(folder_name)
+        .bind(limit as i64)
+        .fetch_all(pool)
+        .await?;
+
+        let emails = rows.into_iter().map(|row| -> Result<FlaggedEmail, CacheError> {
+            let flags_json: String = row.get("flags");
+            Ok(FlaggedEmail {
+                folder: row.get("folder"),
+                uid: row.get::<i64, _>("uid") as u32,
+                message_id: row.get("message_id"),
+                subject: self.reveal_subject(row.get("subject"))?,
+                from_address: row.get("from_address"),
+                from_name: row.get("from_name"),
+                date: row.get("date"),
+                internal_date: row.get("internal_date"),
+                flags: serde_json::from_str(&flags_json).unwrap_or_default(),
+                has_attachments: row.get::<i32, _>("has_attachments") != 0,
+            })
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(emails.into_iter()
+            .filter(|email| email.flags.iter().any(|f| f.trim_start_matches('\\') == "Recent"))
+            .collect())
+    }
+
+    /// UID of the oldest (or with `newest_first`, newest) unread cached email
+    /// in a folder, and how many unread emails the folder has. None when the
+    /// folder isn't cached or everything is read.
+    pub async fn get_next_unread_uid(&self, folder_name: &str, account_id: &str, newest_first: bool) -> Result<Option<(u32, i64)>, CacheError> {
+        let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
+            Some(f) => f,
+            None => return Ok(None),
+        };
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let order = if newest_first { "DESC" } else { "ASC" };
+        let uid = sqlx::query_scalar::<_, i64>(&format!(
+            r#"SELECT uid FROM emails
+               WHERE folder_id = ? AND flags NOT LIKE '%"Seen"%'
+               ORDER BY COALESCE(date, internal_date) {order}, uid {order}
+               LIMIT 1"#
+        ))
+        .bind(folder.id)
+        .fetch_optional(pool)
+        .await?;
+
+        let Some(uid) = uid else {
+            return Ok(None);
+        };
+
+        let unread = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*) FROM emails
+               WHERE folder_id = ?
+               AND flags NOT LIKE '%"Seen"%'"#
+        )
+        .bind(folder.id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(Some((uid as u32, unread)))
+    }
+
+    /// UIDs in a folder whose cached flags include \Recent. With `seen_only`,
+    /// just those that have also been read.
+    pub async fn get_cached_recent_uids(&self, folder_name: &str, account_id: &str, seen_only: bool) -> Result<Vec<u32>, CacheError> {
+        let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
+            Some(f) => f,
+            None => return Ok(Vec::new()),
+        };
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let rows = sqlx::query_as::<_, (i64, String)>(
+            "SELECT uid, flags FROM emails WHERE folder_id = ? AND flags LIKE '%Recent\"%'"
+        )
+        .bind(folder.id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().filter_map(|(uid, flags_json)| {
+            let flags: Vec<String> = serde_json::from_str(&flags_json).unwrap_or_default();
+            let has = |name: &str| flags.iter().any(|f| f.trim_start_matches('\\') == name);
+            (has("Recent") && (!seen_only || has("Seen"))).then_some(uid as u32)
+        }).collect())
+    }
+
+    /// Store the message, unseen and RECENT counts from a SELECT.
+    pub async fn record_mailbox_counts(&self, folder_name: &str, account_id: &str, exists: u32, unseen: Option<u32>, recent: u32) -> Result<(), CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        sqlx::query(
+            "UPDATE folders SET total_messages = ?, unseen_messages = COALESCE(?, unseen_messages),
+                    recent_messages = ?, recent_checked_at = CURRENT_TIMESTAMP
+             WHERE name = ? AND account_id = ?"
+        )
+        .bind(exists as i64)
+        .bind(unseen.map(|n| n as i64))
+        .bind(recent as i64)
+        .bind(folder_name)
+        .bind(account_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Seconds since the latest sync pass over any folder of each account,
+    /// or None for accounts with no synced folder yet.
+    pub async fn sync_age_by_account(&self) -> Result<Vec<(String, Option<i64>)>, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let rows = sqlx::query_as::<_, (String, Option<i64>)>(
+            r#"
+            SELECT f.account_id,
+                   CAST(strftime('%s', 'now') - strftime('%s', MAX(MAX(COALESCE(s.last_full_sync, s.last_incremental_sync), COALESCE(s.last_incremental_sync, s.last_full_sync)))) AS INTEGER)
+            FROM folders f
+            LEFT JOIN sync_state s ON s.folder_id = f.id
+            GROUP BY f.account_id
+            ORDER BY f.account_id
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Cache and sync-state details for one folder or all of an account's
+    /// folders. `age_seconds` and staleness are left for `FolderFreshness::evaluate`.
+    pub async fn get_folder_freshness(&self, account_id: &str, folder_name: Option<&str>) -> Result<Vec<FolderFreshness>, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let rows = sqlx::query_as::<_, (String, Option<i64>, i64, Option<DateTime<Utc>>, Option<DateTime<Utc>>, Option<String>, Option<String>)>(
+            r#"
+            SELECT f.name, f.total_messages,
+                   (SELECT COUNT(*) FROM emails e WHERE e.folder_id = f.id),
+                   s.last_full_sync, s.last_incremental_sync, s.sync_status, s.error_message
+            FROM folders f
+            LEFT JOIN sync_state s ON s.folder_id = f.id
+            WHERE f.account_id = ? AND (? IS NULL OR f.name = ?)
+            ORDER BY f.name
+            "#
+        )
+        .bind(account_id)
+        .bind(folder_name)
+        .bind(folder_name)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(folder, server_count, cached_count, last_full, last_incremental, status, error)| {
+            let status = status.unwrap_or_default();
+            FolderFreshness {
+                folder,
+                cached_count,
+                server_count,
+                live: false,
+                last_sync: last_full.max(last_incremental),
+                age_seconds: None,
+                syncing: status.eq_ignore_ascii_case("syncing"),
+                sync_error: if status == "error" { Some(error.unwrap_or_else(|| "unknown error".to_string())) } else { None },
+                stale: false,
+                stale_reason: None,
+            }
+        }).collect())
     }
 
-    /// Get all cached UIDs for a folder (used for flag resync).
-    pub async fn get_cached_uids(&self, folder_name: &str, account_id: &str) -> Result<Vec<u32>, CacheError> {
+    /// Comparison keys for every cached message in a folder, in UID order.
+    /// Returns `None` when the folder isn't in the cache at all.
+    pub async fn get_folder_message_keys(&self, folder_name: &str, account_id: &str) -> Result<Option<Vec<FolderMessageKey>>, CacheError> {
         let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
             Some(f) => f,
-            None => return Ok(Vec::new()),
+            None => return Ok(None),
         };
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
-        let rows = sqlx::query_scalar::<_, i64>("SELECT uid FROM emails WHERE folder_id = ?")
-            .bind(folder.id)
-            .fetch_all(pool)
-            .await?;
-        Ok(rows.into_iter().map(|uid| uid as u32).collect())
+
+        let rows = sqlx::query_as::<_, (i64, Option<String>, Option<String>, Option<String>, Option<DateTime<Utc>>, Option<i64>)>(
+            "SELECT uid, message_id, subject, from_address, date, size FROM emails WHERE folder_id = ? ORDER BY uid"
+        )
+        .bind(folder.id)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(uid, message_id, subject, from_address, date, size)| -> Result<FolderMessageKey, CacheError> {
+                let subject = self.reveal_subject(subject)?;
+                Ok(FolderMessageKey::new(uid as u32, message_id, subject, from_address, date, size))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
     }
 
-    /// Update only the flags for an existing cached email (lightweight flag resync).
-    pub async fn update_email_flags(&self, folder_name: &str, uid: u32, flags: &[String], account_id: &str) -> Result<(), CacheError> {
-        let folder = self.get_or_create_folder_for_account(folder_name, account_id).await?;
+    /// RECENT counts recorded by sync, for one folder or all of an account's folders.
+    pub async fn get_folder_recent_counts(&self, account_id: &str, folder_name: Option<&str>) -> Result<Vec<FolderRecent>, CacheError> {
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
-        let deduped: Vec<&str> = flags.iter().map(|s| s.as_str())
-            .collect::<std::collections::BTreeSet<_>>().into_iter().collect();
-        let flags_json = serde_json::to_string(&deduped).unwrap_or_else(|_| "[]".to_string());
-
-        sqlx::query("UPDATE emails SET flags = ?, updated_at = CURRENT_TIMESTAMP WHERE folder_id = ? AND uid = ?")
-            .bind(&flags_json)
-            .bind(folder.id)
-            .bind(uid as i64)
-            .execute(pool)
-            .await?;
 
-        // Invalidate memory cache entry
-        let cache_key = format!("{}:{}:{}", account_id, folder_name, uid);
-        let mut memory_cache = self.memory_cache.write().await;
-        memory_cache.pop(&cache_key);
+        let rows = sqlx::query_as::<_, (String, Option<i64>, Option<DateTime<Utc>>)>(
+            "SELECT name, recent_messages, recent_checked_at FROM folders
+             WHERE account_id = ? AND (? IS NULL OR name = ?)
+             ORDER BY name"
+        )
+        .bind(account_id)
+        .bind(folder_name)
+        .bind(folder_name)
+        .fetch_all(pool)
+        .await?;
 
-        Ok(())
+        Ok(rows.into_iter().map(|(folder, recent, checked_at)| FolderRecent {
+            folder,
+            recent_messages: recent.unwrap_or(0),
+            checked_at,
+        }).collect())
     }
 
     pub async fn get_cached_email(&self, folder_name: &str, uid: u32, account_id: &str) -> Result<Option<CachedEmail>, CacheError> {
@@ -549,7 +1414,7 @@ impl CacheService {
             SELECT id, folder_id, uid, message_id, subject, from_address, from_name,
                    to_addresses, cc_addresses, date, internal_date, size,
                    flags, body_text, body_html, cached_at, has_attachments,
-                   in_reply_to, references_header, attachment_parts
+                   in_reply_to, references_header, attachment_parts, snippet
             FROM emails
             WHERE folder_id = ? AND uid = ?
             "#
@@ -564,7 +1429,7 @@ impl CacheService {
             let cc_json: String = row.get("cc_addresses");
             let flags_json: String = row.get("flags");
 
-            let cached_email = CachedEmail {
+            let mut cached_email = CachedEmail {
                 id: row.get("id"),
                 folder_id: row.get("folder_id"),
                 uid: row.get::<i64, _>("uid") as u32,
@@ -585,7 +1450,9 @@ impl CacheService {
                 in_reply_to: row.get("in_reply_to"),
                 references_header: row.get("references_header"),
                 attachment_parts: row.get("attachment_parts"),
+                snippet: row.get("snippet"),
             };
+            self.reveal_emails(std::slice::from_mut(&mut cached_email))?;
 
             // Add to memory cache for future access
             let mut memory_cache = self.memory_cache.write().await;
@@ -600,47 +1467,88 @@ impl CacheService {
 
     /// Get cached emails with pagination support for a specific account
     pub async fn get_cached_emails_for_account(&self, folder_name: &str, account_id: &str, limit: usize, offset: usize, preview_mode: bool) -> Result<Vec<CachedEmail>, CacheError> {
+        let (emails, _) = self.get_cached_emails_page(folder_name, account_id, limit, PageStart::Offset(offset), preview_mode).await?;
+        Ok(emails)
+    }
+
+    /// A page of a folder, newest first, and the cursor for the next page
+    /// when this one is full. Prefer `PageStart::After` over offsets when
+    /// walking a large folder.
+    pub async fn get_cached_emails_page(
+        &self,
+        folder_name: &str,
+        account_id: &str,
+        limit: usize,
+        start: PageStart,
+        preview_mode: bool,
+    ) -> Result<(Vec<CachedEmail>, Option<EmailCursor>), CacheError> {
         // Get folder from cache or database (don't create if it doesn't exist)
         let folder = match self.get_or_create_folder_for_account(folder_name, account_id).await {
             Ok(f) => f,
-            Err(_) => return Ok(Vec::new()), // Folder doesn't exist, return empty list
+            Err(_) => return Ok((Vec::new(), None)), // Folder doesn't exist, return empty list
         };
 
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
 
-        // When in preview mode, truncate body text/html to 200 characters to save tokens
-        let query = if preview_mode {
-            r#"
-            SELECT id, folder_id, uid, message_id, subject, from_address, from_name,
-                   to_addresses, cc_addresses, date, internal_date, size,
-                   flags,
-                   CASE WHEN body_text IS NOT NULL THEN SUBSTR(body_text, 1, 200) || '...' ELSE NULL END as body_text,
-                   CASE WHEN body_html IS NOT NULL THEN SUBSTR(body_html, 1, 200) || '...' ELSE NULL END as body_html,
-                   cached_at, has_attachments, in_reply_to, references_header, attachment_parts
-            FROM emails
-            WHERE folder_id = ?
-            ORDER BY COALESCE(date, internal_date) DESC
-            LIMIT ? OFFSET ?
-            "#
+        // When in preview mode, truncate body text/html to 200 characters to save tokens.
+        // Encrypted bodies can't be cut in SQL, so they are truncated after decryption.
+        let encrypted = self.encryption().is_on();
+        let bodies = if preview_mode && !encrypted {
+            "CASE WHEN body_text IS NOT NULL THEN SUBSTR(body_text, 1, 200) || '...' ELSE NULL END as body_text,
+                   CASE WHEN body_html IS NOT NULL THEN SUBSTR(body_html, 1, 200) || '...' ELSE NULL END as body_html"
         } else {
+            "body_text, body_html"
+        };
+        let (keyset, paging) = match &start {
+            PageStart::Offset(_) => ("", "LIMIT ? OFFSET ?"),
+            // Undated emails sort last, so every page may end in them
+            PageStart::After(EmailCursor { sort_key: Some(_), .. }) => (
+                "AND (COALESCE(date, internal_date) < ? OR (COALESCE(date, internal_date) = ? AND uid < ?) OR COALESCE(date, internal_date) IS NULL)",
+                "LIMIT ?",
+            ),
+            PageStart::After(EmailCursor { sort_key: None, .. }) => (
+                "AND COALESCE(date, internal_date) IS NULL AND uid < ?",
+                "LIMIT ?",
+            ),
+        };
+        let query = format!(
             r#"
             SELECT id, folder_id, uid, message_id, subject, from_address, from_name,
                    to_addresses, cc_addresses, date, internal_date, size,
-                   flags, body_text, body_html, cached_at, has_attachments,
-                   in_reply_to, references_header, attachment_parts
+                   flags,
+                   {bodies},
+                   cached_at, has_attachments, in_reply_to, references_header, attachment_parts, snippet,
+                   COALESCE(date, internal_date) AS sort_key
             FROM emails
-            WHERE folder_id = ?
-            ORDER BY COALESCE(date, internal_date) DESC
-            LIMIT ? OFFSET ?
+            WHERE folder_id = ? {keyset}
+            ORDER BY sort_key DESC, uid DESC
+            {paging}
             "#
-        };
+        );
 
-        let rows = sqlx::query(query)
-            .bind(folder.id)
-            .bind(limit as i64)
-            .bind(offset as i64)
-            .fetch_all(pool)
-            .await?;
+        let mut rows_query = sqlx::query(&query).bind(folder.id);
+        match &start {
+            PageStart::Offset(_) => {}
+            PageStart::After(EmailCursor { sort_key: Some(key), uid }) => {
+                rows_query = rows_query.bind(key).bind(key).bind(*uid as i64);
+            }
+            PageStart::After(EmailCursor { sort_key: None, uid }) => {
+                rows_query = rows_query.bind(*uid as i64);
+            }
+        }
+        rows_query = rows_query.bind(limit as i64);
+        if let PageStart::Offset(offset) = start {
+            rows_query = rows_query.bind(offset as i64);
+        }
+        let rows = rows_query.fetch_all(pool).await?;
+
+        let next_cursor = match rows.last() {
+            Some(last) if limit > 0 && rows.len() == limit => Some(EmailCursor {
+                sort_key: last.get("sort_key"),
+                uid: last.get::<i64, _>("uid") as u32,
+            }),
+            _ => None,
+        };
 
         let mut cached_emails = Vec::new();
         for row in rows {
@@ -687,10 +1595,24 @@ impl CacheService {
                 in_reply_to: row.get("in_reply_to"),
                 references_header: row.get("references_header"),
                 attachment_parts: row.get("attachment_parts"),
+                snippet: row.get("snippet"),
             });
         }
+        self.reveal_emails(&mut cached_emails)?;
 
-        Ok(cached_emails)
+        if preview_mode && encrypted {
+            let preview = |body: &mut Option<String>| {
+                if let Some(b) = body.as_mut() {
+                    *b = format!("{}...", b.chars().take(200).collect::<String>());
+                }
+            };
+            for email in &mut cached_emails {
+                preview(&mut email.body_text);
+                preview(&mut email.body_html);
+            }
+        }
+
+        Ok((cached_emails, next_cursor))
     }
 
     /// Get cached emails filtered by flags for a specific account.
@@ -724,7 +1646,7 @@ impl CacheService {
             r#"SELECT id, folder_id, uid, message_id, subject, from_address, from_name,
                    to_addresses, cc_addresses, date, internal_date, size,
                    flags, body_text, body_html, cached_at, has_attachments,
-                   in_reply_to, references_header, attachment_parts
+                   in_reply_to, references_header, attachment_parts, snippet
             FROM emails
             WHERE {}
             ORDER BY COALESCE(date, internal_date) DESC
@@ -767,8 +1689,10 @@ impl CacheService {
                 in_reply_to: row.get("in_reply_to"),
                 references_header: row.get("references_header"),
                 attachment_parts: row.get("attachment_parts"),
+                snippet: row.get("snippet"),
             });
         }
+        self.reveal_emails(&mut cached_emails)?;
 
         Ok(cached_emails)
     }
@@ -894,6 +1818,21 @@ impl CacheService {
         Ok(())
     }
 
+    /// Record the folder's HIGHESTMODSEQ after a sync pass; None clears it so
+    /// the next pass does not trust a stale value.
+    pub async fn update_sync_modseq(&self, folder_name: &str, modseq: Option<u64>, account_id: &str) -> Result<(), CacheError> {
+        let folder = self.get_or_create_folder_for_account(folder_name, account_id).await?;
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        sqlx::query("UPDATE sync_state SET modseq = ? WHERE folder_id = ?")
+            .bind(modseq.map(|m| m as i64))
+            .bind(folder.id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_sync_state(&self, folder_name: &str, account_id: &str) -> Result<Option<SyncState>, CacheError> {
         let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
             Some(f) => f,
@@ -902,15 +1841,15 @@ impl CacheService {
 
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
 
-        let state = sqlx::query_as::<_, (i64, Option<i64>, Option<DateTime<Utc>>, Option<DateTime<Utc>>, String, Option<String>, Option<i32>, Option<i32>)>(
-            "SELECT folder_id, last_uid_synced, last_full_sync, last_incremental_sync, sync_status, error_message, emails_synced, emails_total
+        let state = sqlx::query_as::<_, (i64, Option<i64>, Option<DateTime<Utc>>, Option<DateTime<Utc>>, String, Option<String>, Option<i32>, Option<i32>, Option<i64>)>(
+            "SELECT folder_id, last_uid_synced, last_full_sync, last_incremental_sync, sync_status, error_message, emails_synced, emails_total, modseq
              FROM sync_state WHERE folder_id = ?"
         )
         .bind(folder.id)
         .fetch_optional(pool)
         .await?;
 
-        if let Some((folder_id, last_uid, last_full, last_inc, status_str, error_msg, synced, total)) = state {
+        if let Some((folder_id, last_uid, last_full, last_inc, status_str, error_msg, synced, total, modseq)) = state {
             let sync_status = match status_str.as_str() {
                 "syncing" | "Syncing" => SyncStatus::Syncing,
                 "error" => SyncStatus::Error,
@@ -926,6 +1865,7 @@ impl CacheService {
                 error_message: error_msg,
                 emails_synced: synced.unwrap_or(0),
                 emails_total: total.unwrap_or(0),
+                modseq: modseq.map(|m| m as u64),
             }))
         } else {
             Ok(None)
@@ -978,7 +1918,7 @@ impl CacheService {
         let keys_to_remove: Vec<String> = memory_cache
             .iter()
             .filter_map(|(k, _)| {
-                if k.starts_with(&format!("{}:", folder_name)) {
+                if k.starts_with(&format!("{}:{}:", account_id, folder_name)) {
                     Some(k.clone())
                 } else {
                     None
@@ -994,6 +1934,139 @@ impl CacheService {
         Ok(())
     }
 
+    /// Close the database pool at shutdown, once connections in use are
+    /// returned.
+    pub async fn close(&self) {
+        if let Some(pool) = &self.db_pool {
+            pool.close().await;
+        }
+    }
+
+    /// Replace the size and age limits while running. The in-memory caches
+    /// are resized at once, dropping their least recently used entries if
+    /// they shrink; the age and size budgets apply from the next prune.
+    pub async fn set_limits(&self, limits: CacheLimits) {
+        let mut config = CacheConfig::clone(&self.config.load());
+        config.max_memory_items = limits.max_memory_items.max(1);
+        config.max_folder_items = limits.max_folder_items.max(1);
+        config.max_cache_size_mb = limits.max_cache_size_mb;
+        config.max_email_age_days = limits.max_email_age_days;
+
+        self.memory_cache.write().await.resize(NonZeroUsize::new(config.max_memory_items).unwrap());
+        self.folder_cache.write().await.resize(NonZeroUsize::new(config.max_folder_items).unwrap());
+        info!(
+            "Cache limits set: {} emails and {} folders in memory, {} MB, {} days",
+            config.max_memory_items, config.max_folder_items, config.max_cache_size_mb, config.max_email_age_days
+        );
+        self.config.store(Arc::new(config));
+    }
+
+    pub fn limits(&self) -> CacheLimits {
+        let config = self.config.load();
+        CacheLimits {
+            max_memory_items: config.max_memory_items,
+            max_folder_items: config.max_folder_items,
+            max_cache_size_mb: config.max_cache_size_mb,
+            max_email_age_days: config.max_email_age_days,
+        }
+    }
+
+    /// Enforce `max_email_age_days` and `max_cache_size_mb`: delete emails
+    /// older than the age limit, then the oldest remaining ones until the
+    /// database fits the size budget, then VACUUM. A limit of 0 is off.
+    /// With `prune_keep_flagged`, flagged emails are never deleted, so the
+    /// budget can stay exceeded if they alone fill it.
+    pub async fn prune(&self) -> Result<PruneReport, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+        let config = self.config.load_full();
+        let keep = if config.prune_keep_flagged {
+            // Matches both "Flagged" and "\\Flagged" in the JSON array
+            r#" AND e.flags NOT LIKE '%Flagged"%'"#
+        } else {
+            ""
+        };
+        let mut report = PruneReport {
+            bytes_before: Self::used_bytes(pool).await?,
+            ..Default::default()
+        };
+
+        if config.max_email_age_days > 0 {
+            let cutoff = Utc::now() - chrono::Duration::days(config.max_email_age_days as i64);
+            loop {
+                let batch = sqlx::query_as::<_, (i64, i64, String, String)>(&format!(
+                    r#"SELECT e.id, e.uid, f.name, f.account_id FROM emails e
+                       JOIN folders f ON e.folder_id = f.id
+                       WHERE datetime(COALESCE(e.date, e.internal_date, e.cached_at)) < datetime(?){keep}
+                       LIMIT ?"#
+                ))
+                .bind(cutoff.to_rfc3339())
+                .bind(PRUNE_BATCH)
+                .fetch_all(pool)
+                .await?;
+                if batch.is_empty() {
+                    break;
+                }
+                report.expired += self.delete_pruned(pool, &batch).await?;
+            }
+        }
+
+        let budget = config.max_cache_size_mb as i64 * 1024 * 1024;
+        if budget > 0 {
+            while Self::used_bytes(pool).await? > budget {
+                let batch = sqlx::query_as::<_, (i64, i64, String, String)>(&format!(
+                    r#"SELECT e.id, e.uid, f.name, f.account_id FROM emails e
+                       JOIN folders f ON e.folder_id = f.id
+                       WHERE 1 = 1{keep}
+                       ORDER BY datetime(COALESCE(e.date, e.internal_date, e.cached_at)) ASC, e.id ASC
+                       LIMIT ?"#
+                ))
+                .bind(PRUNE_BATCH)
+                .fetch_all(pool)
+                .await?;
+                if batch.is_empty() {
+                    warn!("Cache is over its {} MB budget but nothing is left that may be pruned", config.max_cache_size_mb);
+                    break;
+                }
+                report.over_budget += self.delete_pruned(pool, &batch).await?;
+            }
+        }
+
+        if report.expired + report.over_budget > 0 {
+            sqlx::query("VACUUM").execute(pool).await?;
+            info!("Pruned {} expired and {} over-budget emails from the cache", report.expired, report.over_budget);
+        }
+        report.bytes_after = Self::used_bytes(pool).await?;
+        Ok(report)
+    }
+
+    /// Bytes of the database file in use, not counting free pages left by deletes.
+    async fn used_bytes(pool: &SqlitePool) -> Result<i64, CacheError> {
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count").fetch_one(pool).await?;
+        let freelist: i64 = sqlx::query_scalar("PRAGMA freelist_count").fetch_one(pool).await?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size").fetch_one(pool).await?;
+        Ok((page_count - freelist) * page_size)
+    }
+
+    /// Delete one batch of `(id, uid, folder, account)` rows picked by `prune`
+    /// and drop them from the memory caches.
+    async fn delete_pruned(&self, pool: &SqlitePool, batch: &[(i64, i64, String, String)]) -> Result<u64, CacheError> {
+        let mut qb = sqlx::QueryBuilder::new("DELETE FROM emails WHERE id IN (");
+        let mut ids = qb.separated(", ");
+        for (id, ..) in batch {
+            ids.push_bind(*id);
+        }
+        qb.push(")");
+        let deleted = qb.build().execute(pool).await?.rows_affected();
+
+        let mut memory_cache = self.memory_cache.write().await;
+        let mut folder_cache = self.folder_cache.write().await;
+        for (_, uid, folder, account_id) in batch {
+            memory_cache.pop(&format!("{}:{}:{}", account_id, folder, uid));
+            folder_cache.pop(&format!("{}:{}", account_id, folder));
+        }
+        Ok(deleted)
+    }
+
     pub async fn get_cache_stats(&self) -> Result<HashMap<String, serde_json::Value>, CacheError> {
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
 
@@ -1021,13 +2094,40 @@ impl CacheService {
         stats.insert("cache_size_bytes".to_string(), serde_json::json!(cache_size));
         stats.insert("cache_size_mb".to_string(), serde_json::json!(cache_size / (1024 * 1024)));
         stats.insert("memory_cache_items".to_string(), serde_json::json!(memory_cache_size));
-        stats.insert("max_memory_items".to_string(), serde_json::json!(self.config.max_memory_items));
+        stats.insert("max_memory_items".to_string(), serde_json::json!(self.config.load().max_memory_items));
+        stats.insert("encryption".to_string(), serde_json::json!(match self.encryption() {
+            CacheEncryption::Off => "off",
+            CacheEncryption::On(_) => "on",
+            CacheEncryption::MigrationRequired(_) => "migration_required",
+        }));
 
         Ok(stats)
     }
 
     /// Get a specific email by UID
     /// Get an email by UID for a specific account
+    /// Reply-To and List-Post of a cached email. Emails cached before these
+    /// headers were recorded come back with both empty.
+    pub async fn get_reply_headers(&self, folder_name: &str, uid: u32, account_id: &str) -> Result<Option<super::reply::ReplyHeaders>, CacheError> {
+        let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
+            Some(f) => f,
+            None => return Ok(None),
+        };
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let headers: Option<Option<String>> = sqlx::query_scalar(
+            "SELECT headers FROM emails WHERE folder_id = ? AND uid = ?"
+        )
+        .bind(folder.id)
+        .bind(uid as i64)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(headers.map(|json| {
+            json.and_then(|j| serde_json::from_str(&j).ok()).unwrap_or_default()
+        }))
+    }
+
     pub async fn get_email_by_uid_for_account(&self, folder_name: &str, uid: u32, account_id: &str) -> Result<Option<CachedEmail>, CacheError> {
         let folder = match self.get_folder_from_cache_for_account(folder_name, account_id).await {
             Some(f) => f,
@@ -1041,7 +2141,7 @@ impl CacheService {
             SELECT id, folder_id, uid, message_id, subject, from_address, from_name,
                    to_addresses, cc_addresses, date, internal_date, size,
                    flags, body_text, body_html, cached_at, has_attachments,
-                   in_reply_to, references_header, attachment_parts
+                   in_reply_to, references_header, attachment_parts, snippet
             FROM emails
             WHERE folder_id = ? AND uid = ?
             "#
@@ -1056,7 +2156,7 @@ impl CacheService {
             let cc_json: String = row.get("cc_addresses");
             let flags_json: String = row.get("flags");
 
-            Ok(Some(CachedEmail {
+            let mut email = CachedEmail {
                 id: row.get("id"),
                 folder_id: row.get("folder_id"),
                 uid: row.get::<i64, _>("uid") as u32,
@@ -1077,7 +2177,10 @@ impl CacheService {
                 in_reply_to: row.get("in_reply_to"),
                 references_header: row.get("references_header"),
                 attachment_parts: row.get("attachment_parts"),
-            }))
+                snippet: row.get("snippet"),
+            };
+            self.reveal_emails(std::slice::from_mut(&mut email))?;
+            Ok(Some(email))
         } else {
             Ok(None)
         }
@@ -1157,8 +2260,93 @@ impl CacheService {
     }
 
 
+    /// Unread (not \Seen) cached emails per folder of an account, keyed by
+    /// folder name. Folders with no cached emails are included with 0.
+    pub async fn get_unread_counts_for_account(&self, account_id: &str) -> Result<HashMap<String, i64>, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT f.name,
+                   COALESCE(SUM(CASE WHEN e.flags NOT LIKE '%"Seen"%' THEN 1 ELSE 0 END), 0)
+            FROM folders f
+            LEFT JOIN emails e ON e.folder_id = f.id
+            WHERE f.account_id = ?
+            GROUP BY f.id, f.name
+            "#
+        )
+        .bind(account_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Aggregate cache totals and sync recency for every account with cached folders.
+    pub async fn get_account_cache_summaries(&self) -> Result<Vec<AccountCacheSummary>, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+
+        let totals = sqlx::query_as::<_, (String, i64, i64, i64, i64)>(
+            r#"
+            SELECT f.account_id,
+                   COUNT(DISTINCT f.id),
+                   COUNT(e.id),
+                   COALESCE(SUM(CASE WHEN e.flags NOT LIKE '%"Seen"%' THEN 1 ELSE 0 END), 0),
+                   COALESCE(SUM(e.size), 0)
+            FROM folders f
+            LEFT JOIN emails e ON e.folder_id = f.id
+            GROUP BY f.account_id
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let sync_rows = sqlx::query_as::<_, (String, Option<DateTime<Utc>>, Option<DateTime<Utc>>, i64, i64)>(
+            r#"
+            SELECT f.account_id,
+                   MAX(s.last_incremental_sync),
+                   MIN(s.last_incremental_sync),
+                   COALESCE(SUM(CASE WHEN s.sync_status IN ('syncing', 'Syncing') THEN 1 ELSE 0 END), 0),
+                   COALESCE(SUM(CASE WHEN s.sync_status = 'error' THEN 1 ELSE 0 END), 0)
+            FROM folders f
+            JOIN sync_state s ON s.folder_id = f.id
+            GROUP BY f.account_id
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let sync_by_account: HashMap<String, (Option<DateTime<Utc>>, Option<DateTime<Utc>>, i64, i64)> = sync_rows
+            .into_iter()
+            .map(|(account, last, oldest, syncing, errors)| (account, (last, oldest, syncing, errors)))
+            .collect();
+
+        Ok(totals
+            .into_iter()
+            .map(|(account_id, folder_count, total_messages, unread_messages, size_bytes)| {
+                let (last_sync, oldest_folder_sync, folders_syncing, folders_in_error) =
+                    sync_by_account.get(&account_id).cloned().unwrap_or((None, None, 0, 0));
+                AccountCacheSummary {
+                    account_id,
+                    folder_count,
+                    total_messages,
+                    unread_messages,
+                    size_bytes,
+                    last_sync,
+                    oldest_folder_sync,
+                    folders_syncing,
+                    folders_in_error,
+                }
+            })
+            .collect())
+    }
+
+
     /// Search cached emails for a specific account
     pub async fn search_cached_emails_for_account(&self, folder_name: &str, query: &str, limit: usize, account_id: &str) -> Result<Vec<CachedEmail>, CacheError> {
+        if self.encryption().is_on() {
+            return self.search_encrypted_emails(folder_name, query, limit, account_id).await;
+        }
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
         let search_pattern = format!("%{}%", query);
 
@@ -1167,7 +2355,7 @@ impl CacheService {
             SELECT DISTINCT e.id, e.folder_id, e.uid, e.message_id, e.subject, e.from_address, e.from_name,
                    e.to_addresses, e.cc_addresses, e.date, e.internal_date, e.size,
                    e.flags, e.body_text, e.body_html, e.cached_at, e.has_attachments,
-                   e.in_reply_to, e.references_header, e.attachment_parts
+                   e.in_reply_to, e.references_header, e.attachment_parts, e.snippet
             FROM emails e
             LEFT JOIN attachment_metadata a ON e.message_id = a.message_id AND a.account_email =
             "#
@@ -1194,6 +2382,11 @@ impl CacheService {
             };
             qb.push(" AND e.folder_id = ");
             qb.push_bind(folder.id);
+        } else {
+            // Every folder, but only this account's
+            qb.push(" AND e.folder_id IN (SELECT id FROM folders WHERE account_id = ");
+            qb.push_bind(account_id);
+            qb.push(")");
         }
 
         qb.push(r#" ORDER BY COALESCE(e.date, e.internal_date) DESC LIMIT "#);
@@ -1228,13 +2421,197 @@ impl CacheService {
                 in_reply_to: row.get("in_reply_to"),
                 references_header: row.get("references_header"),
                 attachment_parts: row.get("attachment_parts"),
+                snippet: row.get("snippet"),
             };
             cached_emails.push(cached_email);
         }
+        self.reveal_emails(&mut cached_emails)?;
+
+        Ok(cached_emails)
+    }
+
+    /// `search_cached_emails_for_account` for an encrypted cache. Subjects
+    /// and bodies can't be matched in SQL, so the account's emails are read
+    /// newest first, decrypted and matched here until `limit` are found.
+    async fn search_encrypted_emails(&self, folder_name: &str, query: &str, limit: usize, account_id: &str) -> Result<Vec<CachedEmail>, CacheError> {
+        use futures::TryStreamExt;
+
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+        let search_pattern = format!("%{}%", query);
+        let needle = query.to_lowercase();
+
+        let mut qb = sqlx::QueryBuilder::new(
+            r#"
+            SELECT e.id, e.folder_id, e.uid, e.message_id, e.subject, e.from_address, e.from_name,
+                   e.to_addresses, e.cc_addresses, e.date, e.internal_date, e.size,
+                   e.flags, e.body_text, e.body_html, e.cached_at, e.has_attachments,
+                   e.in_reply_to, e.references_header, e.attachment_parts, e.snippet,
+                   (e.from_address LIKE "#
+        );
+        qb.push_bind(&search_pattern);
+        qb.push(" OR e.from_name LIKE ");
+        qb.push_bind(&search_pattern);
+        qb.push(" OR EXISTS (SELECT 1 FROM attachment_metadata a WHERE a.message_id = e.message_id AND a.account_email = ");
+        qb.push_bind(account_id);
+        qb.push(" AND a.filename LIKE ");
+        qb.push_bind(&search_pattern);
+        qb.push(")) AS plain_match FROM emails e JOIN folders f ON e.folder_id = f.id WHERE f.account_id = ");
+        qb.push_bind(account_id);
+        if !folder_name.is_empty() {
+            qb.push(" AND f.name = ");
+            qb.push_bind(folder_name);
+        }
+        qb.push(" ORDER BY COALESCE(e.date, e.internal_date) DESC");
+
+        let query = qb.build();
+        let mut rows = query.fetch(pool);
+        let mut cached_emails = Vec::new();
+        while cached_emails.len() < limit {
+            let Some(row) = rows.try_next().await? else {
+                break;
+            };
+            let to_addresses_str: String = row.get("to_addresses");
+            let cc_addresses_str: String = row.get("cc_addresses");
+            let flags_str: String = row.get("flags");
+            let plain_match = row.get::<i32, _>("plain_match") != 0;
+
+            let mut cached_email = CachedEmail {
+                id: row.get("id"),
+                folder_id: row.get("folder_id"),
+                uid: row.get::<i64, _>("uid") as u32,
+                message_id: row.get("message_id"),
+                subject: row.get("subject"),
+                from_address: row.get("from_address"),
+                from_name: row.get("from_name"),
+                to_addresses: serde_json::from_str(&to_addresses_str).unwrap_or_default(),
+                cc_addresses: serde_json::from_str(&cc_addresses_str).unwrap_or_default(),
+                date: row.get("date"),
+                internal_date: row.get("internal_date"),
+                size: row.get("size"),
+                flags: serde_json::from_str(&flags_str).unwrap_or_default(),
+                body_text: row.get("body_text"),
+                body_html: row.get("body_html"),
+                cached_at: row.get("cached_at"),
+                has_attachments: row.get::<i32, _>("has_attachments") != 0,
+                in_reply_to: row.get("in_reply_to"),
+                references_header: row.get("references_header"),
+                attachment_parts: row.get("attachment_parts"),
+                snippet: row.get("snippet"),
+            };
+            self.reveal_emails(std::slice::from_mut(&mut cached_email))?;
+
+            let text_match = [&cached_email.subject, &cached_email.body_text, &cached_email.body_html]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&needle));
+            if plain_match || text_match {
+                cached_emails.push(cached_email);
+            }
+        }
 
         Ok(cached_emails)
     }
 
+    /// Full-text search over subject, sender name and body, best matches
+    /// first (bm25, with subject hits weighted above sender and body). Each
+    /// hit carries a short body snippet with the matched terms in [brackets].
+    /// Falls back to the unranked LIKE search when the FTS index is missing
+    /// or the cache is encrypted.
+    pub async fn search_cached_emails_ranked(&self, folder_name: &str, query: &str, limit: usize, account_id: &str) -> Result<Vec<RankedEmail>, CacheError> {
+        let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
+        let Some(match_query) = fts_match_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        let has_index: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'emails_fts'"
+        )
+        .fetch_one(pool)
+        .await?;
+        // The index holds ciphertext when the cache is encrypted
+        let encrypted = self.encryption().is_on();
+        if has_index == 0 || encrypted {
+            if !encrypted {
+                warn!("emails_fts index not found; falling back to LIKE search");
+            }
+            let emails = self.search_cached_emails_for_account(folder_name, query, limit, account_id).await?;
+            return Ok(emails.into_iter().map(|email| RankedEmail {
+                email,
+                folder: folder_name.to_string(),
+                score: 0.0,
+                snippet: None,
+            }).collect());
+        }
+
+        let mut qb = sqlx::QueryBuilder::new(
+            r#"
+            SELECT e.id, e.folder_id, e.uid, e.message_id, e.subject, e.from_address, e.from_name,
+                   e.to_addresses, e.cc_addresses, e.date, e.internal_date, e.size,
+                   e.flags, e.body_text, e.body_html, e.cached_at, e.has_attachments,
+                   e.in_reply_to, e.references_header, e.attachment_parts, e.snippet,
+                   f.name AS folder_name,
+                   bm25(emails_fts, 5.0, 2.0, 1.0) AS score,
+                   snippet(emails_fts, 2, '[', ']', '...', 16) AS excerpt
+            FROM emails_fts
+            JOIN emails e ON e.id = emails_fts.rowid
+            JOIN folders f ON f.id = e.folder_id
+            WHERE emails_fts MATCH "#
+        );
+        qb.push_bind(&match_query);
+        qb.push(" AND f.account_id = ");
+        qb.push_bind(account_id);
+        if !folder_name.is_empty() {
+            qb.push(" AND f.name = ");
+            qb.push_bind(folder_name);
+        }
+        // bm25() is lower for better matches
+        qb.push(" ORDER BY score ASC LIMIT ");
+        qb.push_bind(limit as i64);
+
+        let rows = qb.build().fetch_all(pool).await?;
+
+        let mut results = Vec::with_capacity(rows.len());
+        for row in rows {
+            let to_addresses_str: String = row.get("to_addresses");
+            let cc_addresses_str: String = row.get("cc_addresses");
+            let flags_str: String = row.get("flags");
+            let score: f64 = row.get("score");
+
+            results.push(RankedEmail {
+                email: CachedEmail {
+                    id: row.get("id"),
+                    folder_id: row.get("folder_id"),
+                    uid: row.get::<i64, _>("uid") as u32,
+                    message_id: row.get("message_id"),
+                    subject: row.get("subject"),
+                    from_address: row.get("from_address"),
+                    from_name: row.get("from_name"),
+                    to_addresses: serde_json::from_str(&to_addresses_str).unwrap_or_default(),
+                    cc_addresses: serde_json::from_str(&cc_addresses_str).unwrap_or_default(),
+                    date: row.get("date"),
+                    internal_date: row.get("internal_date"),
+                    size: row.get("size"),
+                    flags: serde_json::from_str(&flags_str).unwrap_or_default(),
+                    body_text: row.get("body_text"),
+                    body_html: row.get("body_html"),
+                    cached_at: row.get("cached_at"),
+                    has_attachments: row.get::<i32, _>("has_attachments") != 0,
+                    in_reply_to: row.get("in_reply_to"),
+                    references_header: row.get("references_header"),
+                    attachment_parts: row.get("attachment_parts"),
+                    snippet: row.get("snippet"),
+                },
+                folder: row.get("folder_name"),
+                // Report higher-is-better so callers needn't know bm25's sign
+                score: -score,
+                snippet: row.get("excerpt"),
+            });
+        }
+
+        debug!("FTS search '{}' for {} returned {} results", match_query, account_id, results.len());
+        Ok(results)
+    }
+
     /// Get all emails in the same thread as the given message_id
     pub async fn get_thread_emails(&self, message_id: &str, account_id: &str) -> Result<Vec<CachedEmail>, CacheError> {
         let pool = self.db_pool.as_ref().ok_or(CacheError::NotInitialized)?;
@@ -1275,7 +2652,7 @@ impl CacheService {
             "SELECT e.id, e.folder_id, e.uid, e.message_id, e.subject, e.from_address, e.from_name,
                     e.to_addresses, e.cc_addresses, e.date, e.internal_date, e.size,
                     e.flags, e.body_text, e.body_html, e.cached_at, e.has_attachments,
-                    e.in_reply_to, e.references_header, e.attachment_parts
+                    e.in_reply_to, e.references_header, e.attachment_parts, e.snippet
              FROM emails e
              JOIN folders f ON e.folder_id = f.id
              WHERE f.account_id = ? AND (e.message_id IN ({ph}) OR e.in_reply_to IN ({ph}))
@@ -1328,8 +2705,10 @@ impl CacheService {
                 in_reply_to: row.get("in_reply_to"),
                 references_header: row.get("references_header"),
                 attachment_parts: row.get("attachment_parts"),
+                snippet: row.get("snippet"),
             });
         }
+        self.reveal_emails(&mut emails)?;
         Ok(emails)
     }
 
@@ -1356,7 +2735,7 @@ impl CacheService {
             "SELECT e.id, e.folder_id, e.uid, e.message_id, e.subject, e.from_address, e.from_name,
                     e.to_addresses, e.cc_addresses, e.date, e.internal_date, e.size,
                     e.flags, e.body_text, e.body_html, e.cached_at, e.has_attachments,
-                    e.in_reply_to, e.references_header, e.attachment_parts
+                    e.in_reply_to, e.references_header, e.attachment_parts, e.snippet
              FROM emails e
              JOIN folders f ON e.folder_id = f.id
              WHERE f.account_id = ? AND ({})
@@ -1398,8 +2777,10 @@ impl CacheService {
                 in_reply_to: row.get("in_reply_to"),
                 references_header: row.get("references_header"),
                 attachment_parts: row.get("attachment_parts"),
+                snippet: row.get("snippet"),
             });
         }
+        self.reveal_emails(&mut emails)?;
         Ok(emails)
     }
 
@@ -0,0 +1,373 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! At-rest encryption of the cached `subject`, `body_text` and `body_html`
+//! columns.
+//!
+//! With a passphrase configured (`[cache_encryption] passphrase` or
+//! `CACHE_ENCRYPTION_PASSPHRASE`) each of those values is stored as
+//! `ENC:c1:<base64 nonce||ciphertext>` under AES-256-GCM. The key is derived
+//! from the passphrase with Argon2id and a random salt kept in the
+//! `cache_encryption` table, together with a key-check value so a wrong
+//! passphrase is reported at startup rather than as garbled mail.
+//!
+//! Key derivation needs the `cache-encryption` feature. Values without the
+//! prefix are plaintext and are read as-is, so a database that was only
+//! partly converted keeps working.
+//!
+//! A database that already holds plaintext emails is not converted on the
+//! fly: with a passphrase set it opens read-only and caching fails with
+//! [`CacheCryptoError::MigrationRequired`] until [`reencrypt`] has run
+//! (`rustymail-sync --reencrypt`, or `CacheService::reencrypt`).
+
+use std::sync::{Arc, RwLock};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use aes_gcm::aead::rand_core::RngCore;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use thiserror::Error;
+
+/// Marks a column value written by [`CacheCipher::seal`].
+pub const SEALED_PREFIX: &str = "ENC:c1:";
+
+/// Plaintext sealed into `cache_encryption.key_check` when the salt is created.
+const KEY_CHECK: &str = "rustymail-cache-key-check";
+
+/// Rows converted per transaction by [`reencrypt`].
+const REENCRYPT_BATCH: i64 = 500;
+
+#[derive(Error, Debug)]
+pub enum CacheCryptoError {
+    #[error("The cache database is encrypted; set CACHE_ENCRYPTION_PASSPHRASE to open it")]
+    PassphraseRequired,
+    #[error("Wrong cache encryption passphrase for this database")]
+    WrongPassphrase,
+    #[error("Cache encryption is configured but this build lacks the cache-encryption feature")]
+    NotCompiled,
+    #[error("The cache database holds unencrypted emails and is read-only until converted; run `rustymail-sync --reencrypt` and restart")]
+    MigrationRequired,
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+    #[error("Encryption failed: {0}")]
+    EncryptionFailed(String),
+    #[error("Decryption failed: {0}")]
+    DecryptionFailed(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// AES-256-GCM cipher for cached columns.
+#[derive(Clone)]
+pub struct CacheCipher {
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for CacheCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CacheCipher(<key>)")
+    }
+}
+
+impl CacheCipher {
+    /// Derive the key from `passphrase` and `salt` with Argon2id.
+    #[cfg(feature = "cache-encryption")]
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self, CacheCryptoError> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| CacheCryptoError::KeyDerivation(e.to_string()))?;
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|e| CacheCryptoError::KeyDerivation(e.to_string()))?;
+        Ok(Self { cipher })
+    }
+
+    #[cfg(not(feature = "cache-encryption"))]
+    pub fn derive(_passphrase: &str, _salt: &[u8]) -> Result<Self, CacheCryptoError> {
+        Err(CacheCryptoError::NotCompiled)
+    }
+
+    /// Encrypt `plaintext` under a fresh random nonce.
+    pub fn seal(&self, plaintext: &str) -> Result<String, CacheCryptoError> {
+        let mut nonce_bytes = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let ciphertext = self.cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_bytes())
+            .map_err(|e| CacheCryptoError::EncryptionFailed(e.to_string()))?;
+
+        let mut packed = nonce_bytes.to_vec();
+        packed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(packed)))
+    }
+
+    /// Decrypt a value from [`seal`](Self::seal). Values without the prefix
+    /// are returned unchanged.
+    pub fn open(&self, stored: &str) -> Result<String, CacheCryptoError> {
+        let Some(encoded) = stored.strip_prefix(SEALED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let packed = BASE64.decode(encoded)
+            .map_err(|e| CacheCryptoError::DecryptionFailed(format!("base64 decode: {}", e)))?;
+        if packed.len() < 12 {
+            return Err(CacheCryptoError::DecryptionFailed("value too short".to_string()));
+        }
+        let (nonce, ciphertext) = packed.split_at(12);
+        let plaintext = self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|e| CacheCryptoError::DecryptionFailed(e.to_string()))?;
+        String::from_utf8(plaintext)
+            .map_err(|e| CacheCryptoError::DecryptionFailed(format!("invalid UTF-8: {}", e)))
+    }
+
+    pub fn seal_opt(&self, plaintext: Option<&str>) -> Result<Option<String>, CacheCryptoError> {
+        plaintext.map(|p| self.seal(p)).transpose()
+    }
+
+    pub fn open_opt(&self, stored: Option<String>) -> Result<Option<String>, CacheCryptoError> {
+        match stored {
+            Some(s) if is_sealed(&s) => self.open(&s).map(Some),
+            other => Ok(other),
+        }
+    }
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+/// How the cache database is to be read and written.
+#[derive(Debug, Clone, Default)]
+pub enum CacheEncryption {
+    /// No passphrase and no encrypted data
+    #[default]
+    Off,
+    /// Encrypted columns; new values are sealed
+    On(Arc<CacheCipher>),
+    /// Passphrase set over a plaintext database: reads work, caching is
+    /// refused until [`reencrypt`] has converted it
+    MigrationRequired(Arc<CacheCipher>),
+}
+
+impl CacheEncryption {
+    /// Cipher for opening values read from the database.
+    pub fn reader(&self) -> Option<&CacheCipher> {
+        match self {
+            CacheEncryption::Off => None,
+            CacheEncryption::On(c) | CacheEncryption::MigrationRequired(c) => Some(c),
+        }
+    }
+
+    /// Cipher for sealing values about to be written, or the error that
+    /// keeps a plaintext database read-only.
+    pub fn writer(&self) -> Result<Option<&CacheCipher>, CacheCryptoError> {
+        match self {
+            CacheEncryption::Off => Ok(None),
+            CacheEncryption::On(c) => Ok(Some(c)),
+            CacheEncryption::MigrationRequired(_) => Err(CacheCryptoError::MigrationRequired),
+        }
+    }
+
+    /// Whether stored columns may be ciphertext, so SQL can't match on them.
+    pub fn is_on(&self) -> bool {
+        matches!(self, CacheEncryption::On(_))
+    }
+}
+
+/// Passphrase from `CACHE_ENCRYPTION_PASSPHRASE`, falling back to the one in
+/// `Settings`. Blank values count as unset.
+pub fn passphrase(configured: Option<&str>) -> Option<String> {
+    std::env::var("CACHE_ENCRYPTION_PASSPHRASE").ok()
+        .or_else(|| configured.map(str::to_string))
+        .filter(|p| !p.is_empty())
+}
+
+/// Salt and key-check value, if the database has been set up for encryption.
+async fn load_meta(pool: &SqlitePool) -> Result<Option<(Vec<u8>, String)>, CacheCryptoError> {
+    let row = sqlx::query("SELECT salt, key_check FROM cache_encryption WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| (r.get("salt"), r.get("key_check"))))
+}
+
+/// Derive the cipher for a database that has a salt, checking the passphrase.
+fn unlock(passphrase: &str, salt: &[u8], key_check: &str) -> Result<CacheCipher, CacheCryptoError> {
+    let cipher = CacheCipher::derive(passphrase, salt)?;
+    match cipher.open(key_check) {
+        Ok(check) if check == KEY_CHECK => Ok(cipher),
+        _ => Err(CacheCryptoError::WrongPassphrase),
+    }
+}
+
+/// Create the salt and key-check row and return the new cipher.
+async fn initialize_meta(pool: &SqlitePool, passphrase: &str) -> Result<CacheCipher, CacheCryptoError> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = CacheCipher::derive(passphrase, &salt)?;
+    sqlx::query("INSERT INTO cache_encryption (id, salt, key_check) VALUES (1, ?, ?)")
+        .bind(salt.to_vec())
+        .bind(cipher.seal(KEY_CHECK)?)
+        .execute(pool)
+        .await?;
+    Ok(cipher)
+}
+
+/// Work out the encryption mode of a migrated cache database.
+pub async fn prepare(pool: &SqlitePool, passphrase: Option<&str>) -> Result<CacheEncryption, CacheCryptoError> {
+    let meta = load_meta(pool).await?;
+    match (meta, passphrase) {
+        (None, None) => Ok(CacheEncryption::Off),
+        (Some(_), None) => Err(CacheCryptoError::PassphraseRequired),
+        (Some((salt, key_check)), Some(passphrase)) => {
+            Ok(CacheEncryption::On(Arc::new(unlock(passphrase, &salt, &key_check)?)))
+        }
+        (None, Some(passphrase)) => {
+            let plaintext_rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM emails")
+                .fetch_one(pool)
+                .await?;
+            if plaintext_rows > 0 {
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                warn!("Cache encryption is configured but the database holds {} unencrypted emails; opening read-only", plaintext_rows);
+                return Ok(CacheEncryption::MigrationRequired(Arc::new(CacheCipher::derive(passphrase, &salt)?)));
+            }
+            info!("Enabling encryption for the empty cache database");
+            Ok(CacheEncryption::On(Arc::new(initialize_meta(pool, passphrase).await?)))
+        }
+    }
+}
+
+/// Outcome of [`reencrypt`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReencryptReport {
+    pub rows_scanned: u64,
+    pub rows_encrypted: u64,
+}
+
+/// Encrypt every plaintext subject and body in the database, creating the
+/// salt first if needed. Safe to run again after an interruption: sealed
+/// values are left alone. Ends with a VACUUM so freed pages holding the old
+/// plaintext (including the full-text index's copy) are not left in the file.
+pub async fn reencrypt(pool: &SqlitePool, passphrase: &str) -> Result<(Arc<CacheCipher>, ReencryptReport), CacheCryptoError> {
+    let cipher = match load_meta(pool).await? {
+        Some((salt, key_check)) => unlock(passphrase, &salt, &key_check)?,
+        None => initialize_meta(pool, passphrase).await?,
+    };
+
+    let mut report = ReencryptReport::default();
+    let mut last_id = 0i64;
+    loop {
+        let rows = sqlx::query(
+            "SELECT id, subject, body_text, body_html, snippet FROM emails WHERE id > ? ORDER BY id LIMIT ?"
+        )
+        .bind(last_id)
+        .bind(REENCRYPT_BATCH)
+        .fetch_all(pool)
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        last_id = last.get("id");
+
+        let mut tx = pool.begin().await?;
+        for row in &rows {
+            report.rows_scanned += 1;
+            let columns: [Option<String>; 4] = [row.get("subject"), row.get("body_text"), row.get("body_html"), row.get("snippet")];
+            if columns.iter().flatten().all(|v| is_sealed(v)) {
+                continue;
+            }
+            let mut sealed = Vec::with_capacity(columns.len());
+            for value in &columns {
+                sealed.push(match value {
+                    Some(v) if !is_sealed(v) => Some(cipher.seal(v)?),
+                    other => other.clone(),
+                });
+            }
+            sqlx::query("UPDATE emails SET subject = ?, body_text = ?, body_html = ?, snippet = ? WHERE id = ?")
+                .bind(&sealed[0])
+                .bind(&sealed[1])
+                .bind(&sealed[2])
+                .bind(&sealed[3])
+                .bind(row.get::<i64, _>("id"))
+                .execute(&mut *tx)
+                .await?;
+            report.rows_encrypted += 1;
+        }
+        tx.commit().await?;
+    }
+
+    sqlx::query("VACUUM").execute(pool).await?;
+    info!("Cache re-encryption done: {} of {} emails encrypted", report.rows_encrypted, report.rows_scanned);
+    Ok((Arc::new(cipher), report))
+}
+
+lazy_static! {
+    /// Cipher of the running cache, for modules that query `emails` directly.
+    static ref ACTIVE: RwLock<Option<Arc<CacheCipher>>> = RwLock::new(None);
+}
+
+/// Make `encryption` the process-wide mode used by [`reveal`].
+pub fn install(encryption: &CacheEncryption) {
+    let cipher = encryption.reader().map(|c| Arc::new(c.clone()));
+    if let Ok(mut active) = ACTIVE.write() {
+        *active = cipher;
+    }
+}
+
+/// Whether the installed mode may have ciphertext in the database.
+pub fn active() -> bool {
+    ACTIVE.read().map(|a| a.is_some()).unwrap_or(false)
+}
+
+/// Plaintext of a subject or body read straight from the `emails` table.
+/// A value that can't be decrypted comes back as None.
+pub fn reveal(stored: Option<String>) -> Option<String> {
+    let stored = stored?;
+    if !is_sealed(&stored) {
+        return Some(stored);
+    }
+    let active = ACTIVE.read().ok()?;
+    match active.as_ref().map(|c| c.open(&stored)) {
+        Some(Ok(plaintext)) => Some(plaintext),
+        Some(Err(e)) => {
+            warn!("Failed to decrypt cached value: {}", e);
+            None
+        }
+        None => None,
+    }
+}
+
+#[cfg(all(test, feature = "cache-encryption"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = CacheCipher::derive("correct horse", b"0123456789abcdef").unwrap();
+        let sealed = cipher.seal("Quarterly report").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("Quarterly"));
+        assert_ne!(sealed, cipher.seal("Quarterly report").unwrap());
+        assert_eq!(cipher.open(&sealed).unwrap(), "Quarterly report");
+
+        // Plaintext from before encryption passes through
+        assert_eq!(cipher.open("plain subject").unwrap(), "plain subject");
+
+        // Another passphrase or salt can't open it
+        let other = CacheCipher::derive("wrong horse", b"0123456789abcdef").unwrap();
+        assert!(other.open(&sealed).is_err());
+        let salted = CacheCipher::derive("correct horse", b"fedcba9876543210").unwrap();
+        assert!(salted.open(&sealed).is_err());
+
+        let check = cipher.seal(KEY_CHECK).unwrap();
+        assert!(unlock("correct horse", b"0123456789abcdef", &check).is_ok());
+        assert!(matches!(unlock("wrong horse", b"0123456789abcdef", &check), Err(CacheCryptoError::WrongPassphrase)));
+    }
+}
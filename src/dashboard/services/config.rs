@@ -11,6 +11,7 @@ use crate::config::Settings;
 use sysinfo;
 use serde::{Serialize, Deserialize};
 use std::sync::Arc;
+use arc_swap::ArcSwap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -24,7 +25,8 @@ pub struct ConfigData {
 
 pub struct ConfigService {
     config: RwLock<ConfigData>,
-    current_config: Arc<RwLock<Settings>>,
+    /// Settings in effect; swapped whole by the update methods and on reload
+    current_config: ArcSwap<Settings>,
     config_path: Option<PathBuf>,
 }
 
@@ -62,14 +64,14 @@ impl ConfigService {
 
         Self {
             config: RwLock::new(config_data),
-            current_config: Arc::new(RwLock::new(settings)),
+            current_config: ArcSwap::from_pointee(settings),
             config_path,
         }
     }
 
     // Get the current server configuration relevant to the dashboard
     pub async fn get_configuration(&self) -> ServerConfig {
-        let settings = self.current_config.read().await;
+        let settings = self.current_config.load();
         let version = env!("CARGO_PKG_VERSION").to_string();
         // Use sysinfo for system uptime
         let uptime = sysinfo::System::uptime();
@@ -178,7 +180,27 @@ impl ConfigService {
 
     // Get current settings
     pub async fn get_settings(&self) -> Settings {
-        self.current_config.read().await.clone()
+        Settings::clone(&self.current_config.load())
+    }
+
+    /// The settings in effect, without cloning them.
+    pub fn settings(&self) -> Arc<Settings> {
+        self.current_config.load_full()
+    }
+
+    /// Read the settings again from the config file and environment,
+    /// re-reading `.env` first so edits to it are seen. As at startup, the
+    /// process environment wins over `.env`. Nothing is stored; see
+    /// `replace_settings`.
+    pub fn load_settings(&self) -> Result<Settings, String> {
+        super::config_reload::reload_dotenv()?;
+        Settings::new(self.config_path.as_deref().and_then(|p| p.to_str()))
+            .map_err(|e| format!("Failed to load settings: {}", e))
+    }
+
+    /// Swap in new settings, returning the ones they replace.
+    pub fn replace_settings(&self, settings: Settings) -> Arc<Settings> {
+        self.current_config.swap(Arc::new(settings))
     }
 
     // Update IMAP configuration at runtime
@@ -200,15 +222,16 @@ impl ConfigService {
         }
 
         // Update settings
-        let mut settings = self.current_config.write().await;
-        settings.imap_host = host.clone();
-        settings.imap_port = port;
-        settings.imap_user = user.clone();
-        settings.imap_pass = pass;
+        let settings = self.update_settings(|settings| {
+            settings.imap_host = host.clone();
+            settings.imap_port = port;
+            settings.imap_user = user.clone();
+            settings.imap_pass = pass.clone();
+        });
 
         // Persist if we have a config path
         if let Some(config_path) = &self.config_path {
-            if let Err(e) = self.persist_settings(&*settings, config_path).await {
+            if let Err(e) = self.persist_settings(&settings, config_path).await {
                 error!("Failed to persist configuration: {}", e);
                 return Err(format!("Failed to save configuration: {}", e));
             }
@@ -224,16 +247,17 @@ impl ConfigService {
             return Err("Invalid port number".to_string());
         }
 
-        let mut settings = self.current_config.write().await;
-        settings.rest = Some(crate::config::RestConfig {
-            enabled,
-            host: host.clone(),
-            port,
+        let settings = self.update_settings(|settings| {
+            settings.rest = Some(crate::config::RestConfig {
+                enabled,
+                host: host.clone(),
+                port,
+            });
         });
 
         // Persist if we have a config path
         if let Some(config_path) = &self.config_path {
-            if let Err(e) = self.persist_settings(&*settings, config_path).await {
+            if let Err(e) = self.persist_settings(&settings, config_path).await {
                 error!("Failed to persist configuration: {}", e);
                 return Err(format!("Failed to save configuration: {}", e));
             }
@@ -257,16 +281,17 @@ impl ConfigService {
             }
         }
 
-        let mut settings = self.current_config.write().await;
-        settings.dashboard = Some(crate::config::DashboardConfig {
-            enabled,
-            port,
-            path: path.clone(),
+        let settings = self.update_settings(|settings| {
+            settings.dashboard = Some(crate::config::DashboardConfig {
+                enabled,
+                port,
+                path: path.clone(),
+            });
         });
 
         // Persist if we have a config path
         if let Some(config_path) = &self.config_path {
-            if let Err(e) = self.persist_settings(&*settings, config_path).await {
+            if let Err(e) = self.persist_settings(&settings, config_path).await {
                 error!("Failed to persist configuration: {}", e);
                 return Err(format!("Failed to save configuration: {}", e));
             }
@@ -276,6 +301,17 @@ impl ConfigService {
         Ok(())
     }
 
+    // Apply a change to a copy of the current settings and swap it in,
+    // retrying if another update got there first
+    fn update_settings(&self, change: impl Fn(&mut Settings)) -> Arc<Settings> {
+        self.current_config.rcu(|current| {
+            let mut settings = Settings::clone(current);
+            change(&mut settings);
+            settings
+        });
+        self.current_config.load_full()
+    }
+
     // Persist settings to file
     async fn persist_settings(&self, settings: &Settings, path: &PathBuf) -> Result<(), String> {
         let toml_string = toml::to_string_pretty(settings)
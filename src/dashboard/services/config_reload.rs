@@ -0,0 +1,313 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Reloading settings without a restart, on SIGHUP or from
+//! `POST /api/admin/reload-config`.
+//!
+//! The config file, `.env` and the environment are read again and compared
+//! with what is running. As at startup, a variable set in the process
+//! environment wins over `.env`; only variables that came from the file pick
+//! up edits to it. These changes take effect at once:
+//!
+//! - `log.level`, which replaces the `RUST_LOG` filter
+//! - `require_explicit_account`
+//! - `SYNC_INTERVAL_SECONDS`
+//! - `CACHE_MAX_MEMORY_ITEMS`, `CACHE_MAX_FOLDER_ITEMS`, `CACHE_MAX_SIZE_MB`
+//!   and `CACHE_MAX_EMAIL_AGE_DAYS`
+//! - the API keys of AI providers configured at startup
+//!
+//! Anything else, such as bind addresses, ports or auth, is reported as
+//! requiring a restart and keeps its running value until then.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::OnceLock;
+
+use log::{error, info, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::config::Settings;
+use super::cache::CacheLimits;
+use super::sync::sync_interval_from_env;
+use super::DashboardState;
+
+/// Shown in place of secret values.
+const REDACTED: &str = "***";
+
+/// Variables set in the process environment before `.env` was first read.
+static PROCESS_ENV_KEYS: OnceLock<HashSet<String>> = OnceLock::new();
+
+/// Read `.env` at startup without replacing variables that are already set,
+/// remembering which those were so `reload_dotenv` leaves them alone too.
+pub fn load_dotenv() -> Result<(), dotenvy::Error> {
+    PROCESS_ENV_KEYS.get_or_init(|| std::env::vars_os().filter_map(|(key, _)| key.into_string().ok()).collect());
+    dotenvy::dotenv().map(|_| ())
+}
+
+/// Read `.env` again, applying every value except those of variables the
+/// process environment set before startup. A missing file is not an error.
+pub fn reload_dotenv() -> Result<(), String> {
+    let entries = match dotenvy::dotenv_iter() {
+        Ok(entries) => entries,
+        Err(e) if e.not_found() => return Ok(()),
+        Err(e) => return Err(format!("Failed to read .env: {}", e)),
+    };
+    let entries = entries
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read .env: {}", e))?;
+    apply_dotenv(entries, PROCESS_ENV_KEYS.get());
+    Ok(())
+}
+
+/// Set each entry unless the process environment owns the variable. Without
+/// a startup snapshot, any variable already set counts as the process's.
+fn apply_dotenv(entries: Vec<(String, String)>, process_keys: Option<&HashSet<String>>) {
+    for (key, value) in entries {
+        let from_process = match process_keys {
+            Some(keys) => keys.contains(&key),
+            None => std::env::var_os(&key).is_some(),
+        };
+        if !from_process {
+            std::env::set_var(key, value);
+        }
+    }
+}
+
+/// One setting whose value differs between the running and the reloaded
+/// config. Keys are dotted paths into `Settings`, or environment variable
+/// names for settings read straight from the environment.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    pub key: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl ConfigChange {
+    fn new(key: impl Into<String>, old: Value, new: Value) -> Self {
+        Self { key: key.into(), old, new }
+    }
+}
+
+/// What a reload did.
+#[derive(Debug, Default, Serialize)]
+pub struct ReloadReport {
+    pub applied: Vec<ConfigChange>,
+    pub requires_restart: Vec<ConfigChange>,
+    pub errors: Vec<String>,
+}
+
+/// Reload settings and apply whatever can be applied live.
+pub async fn reload(state: &DashboardState) -> ReloadReport {
+    let mut report = ReloadReport::default();
+    let reloaded = match state.config_service.load_settings() {
+        Ok(settings) => settings,
+        Err(e) => {
+            error!("Config reload failed: {}", e);
+            report.errors.push(e);
+            return report;
+        }
+    };
+
+    // Only live changes go into the running settings, so they keep showing
+    // what is actually in effect
+    let running = state.config_service.settings();
+    let mut next = Settings::clone(&running);
+    for change in diff_settings(&running, &reloaded) {
+        match change.key.as_str() {
+            "log.level" => match crate::logging::set_level(&reloaded.log.level) {
+                Ok(()) => {
+                    next.log = reloaded.log.clone();
+                    report.applied.push(change);
+                }
+                Err(e) => report.errors.push(format!("log.level: {}", e)),
+            },
+            "require_explicit_account" => {
+                next.require_explicit_account = reloaded.require_explicit_account;
+                report.applied.push(change);
+            }
+            _ => report.requires_restart.push(change),
+        }
+    }
+    state.config_service.replace_settings(next);
+
+    let interval = sync_interval_from_env().max(1);
+    let running_interval = state.sync_service.sync_interval().as_secs();
+    if interval != running_interval {
+        state.sync_service.set_sync_interval(interval);
+        report.applied.push(ConfigChange::new("SYNC_INTERVAL_SECONDS", json!(running_interval), json!(interval)));
+    }
+
+    let limits = CacheLimits::from_env();
+    let running_limits = state.cache_service.limits();
+    if limits != running_limits {
+        let pairs = [
+            ("CACHE_MAX_MEMORY_ITEMS", json!(running_limits.max_memory_items), json!(limits.max_memory_items)),
+            ("CACHE_MAX_FOLDER_ITEMS", json!(running_limits.max_folder_items), json!(limits.max_folder_items)),
+            ("CACHE_MAX_SIZE_MB", json!(running_limits.max_cache_size_mb), json!(limits.max_cache_size_mb)),
+            ("CACHE_MAX_EMAIL_AGE_DAYS", json!(running_limits.max_email_age_days), json!(limits.max_email_age_days)),
+        ];
+        state.cache_service.set_limits(limits).await;
+        report.applied.extend(pairs.into_iter()
+            .filter(|(_, old, new)| old != new)
+            .map(|(key, old, new)| ConfigChange::new(key, old, new)));
+    }
+
+    let keys = state.ai_service.reload_api_keys().await;
+    let redacted = |var: String| ConfigChange::new(var, json!(REDACTED), json!(REDACTED));
+    report.applied.extend(keys.updated.into_iter().map(redacted));
+    report.requires_restart.extend(keys.requires_restart.into_iter().map(redacted));
+    report.errors.extend(keys.errors);
+
+    info!(
+        "Config reloaded: {} change(s) applied, {} requiring restart, {} error(s)",
+        report.applied.len(), report.requires_restart.len(), report.errors.len()
+    );
+    for change in &report.requires_restart {
+        warn!("Config change to {} requires a restart to take effect", change.key);
+    }
+    report
+}
+
+/// Reload whenever the process receives SIGHUP.
+#[cfg(unix)]
+pub fn start_sighup_listener(state: std::sync::Arc<DashboardState>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            error!("Failed to listen for SIGHUP; config reload is only available over HTTP: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("SIGHUP received, reloading config");
+            reload(&state).await;
+        }
+    });
+}
+
+/// Settings that differ between `old` and `new`, with secrets redacted.
+pub fn diff_settings(old: &Settings, new: &Settings) -> Vec<ConfigChange> {
+    let old = flatten_settings(old);
+    let new = flatten_settings(new);
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let before = old.get(key).cloned().unwrap_or(Value::Null);
+            let after = new.get(key).cloned().unwrap_or(Value::Null);
+            if before == after {
+                return None;
+            }
+            Some(if is_secret(key) {
+                ConfigChange::new(key.clone(), redact(before), redact(after))
+            } else {
+                ConfigChange::new(key.clone(), before, after)
+            })
+        })
+        .collect()
+}
+
+fn flatten_settings(settings: &Settings) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    match serde_json::to_value(settings) {
+        Ok(value) => flatten("", value, &mut fields),
+        Err(e) => error!("Failed to serialize settings for diffing: {}", e),
+    }
+    fields
+}
+
+fn flatten(prefix: &str, value: Value, fields: &mut BTreeMap<String, Value>) {
+    let key = |name: &str| if prefix.is_empty() { name.to_string() } else { format!("{}.{}", prefix, name) };
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                flatten(&key(&name), value, fields);
+            }
+        }
+        // Lists of sections (DKIM domains) are compared entry by entry
+        Value::Array(items) if items.iter().any(Value::is_object) => {
+            for (index, value) in items.into_iter().enumerate() {
+                flatten(&key(&index.to_string()), value, fields);
+            }
+        }
+        value => {
+            fields.insert(prefix.to_string(), value);
+        }
+    }
+}
+
+fn is_secret(key: &str) -> bool {
+    let field = key.rsplit('.').next().unwrap_or(key);
+    field.contains("pass") || field.contains("secret") || field.ends_with("key")
+}
+
+fn redact(value: Value) -> Value {
+    if value.is_null() { value } else { json!(REDACTED) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(value: Value) -> Settings {
+        let mut base = json!({
+            "interface": "rest",
+            "log": { "level": "info" },
+            "imap_host": "imap.example.com",
+            "imap_port": 993,
+            "imap_user": "me@example.com",
+            "imap_pass": "hunter2",
+            "rest": { "enabled": true, "host": "127.0.0.1", "port": 9437 }
+        });
+        for (key, value) in value.as_object().unwrap() {
+            base[key] = value.clone();
+        }
+        serde_json::from_value(base).unwrap()
+    }
+
+    #[test]
+    fn test_diff_settings() {
+        let old = settings(json!({}));
+        assert!(diff_settings(&old, &old).is_empty());
+
+        let new = settings(json!({
+            "log": { "level": "debug" },
+            "imap_pass": "correct horse",
+            "rest": { "enabled": true, "host": "0.0.0.0", "port": 9437 },
+            "api_key": "new-key"
+        }));
+        let changes = diff_settings(&old, &new);
+        let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, vec!["api_key", "imap_pass", "log.level", "rest.host"]);
+
+        assert_eq!(changes[0].old, Value::Null);
+        assert_eq!(changes[0].new, json!(REDACTED));
+        assert_eq!(changes[1].old, json!(REDACTED), "secrets never appear in the report");
+        assert_eq!(changes[2], ConfigChange::new("log.level", json!("info"), json!("debug")));
+        assert_eq!(changes[3].new, json!("0.0.0.0"));
+    }
+
+    #[test]
+    fn test_apply_dotenv_keeps_process_environment() {
+        std::env::set_var("RELOAD_TEST_FROM_PROCESS", "process");
+        std::env::set_var("RELOAD_TEST_FROM_FILE", "old file value");
+        let process_keys: HashSet<String> = ["RELOAD_TEST_FROM_PROCESS".to_string()].into();
+
+        apply_dotenv(vec![
+            ("RELOAD_TEST_FROM_PROCESS".to_string(), "file".to_string()),
+            ("RELOAD_TEST_FROM_FILE".to_string(), "new file value".to_string()),
+        ], Some(&process_keys));
+
+        assert_eq!(std::env::var("RELOAD_TEST_FROM_PROCESS").unwrap(), "process");
+        assert_eq!(std::env::var("RELOAD_TEST_FROM_FILE").unwrap(), "new file value");
+
+        // Without a snapshot nothing already set is replaced
+        apply_dotenv(vec![("RELOAD_TEST_FROM_FILE".to_string(), "newer".to_string())], None);
+        assert_eq!(std::env::var("RELOAD_TEST_FROM_FILE").unwrap(), "new file value");
+    }
+}
@@ -0,0 +1,405 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! DKIM signing (RFC 6376) of outgoing messages.
+//!
+//! Keys are configured per sending domain in the `[dkim]` section, or for a
+//! single domain with `DKIM_DOMAIN`, `DKIM_SELECTOR` and `DKIM_PRIVATE_KEY`.
+//! Messages are signed rsa-sha256 with relaxed/relaxed canonicalization over
+//! the exact bytes handed to the SMTP transport, and the `DKIM-Signature`
+//! header is prepended to them. The key, and `d=`, belong to the domain of
+//! the From header, so the signature aligns for DMARC. A message whose From
+//! domain has no key is sent unsigned.
+
+use std::collections::HashMap;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use log::{debug, info, warn};
+use rsa::RsaPrivateKey;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::config::{DkimConfig, DkimDomainConfig, Settings};
+
+#[derive(Error, Debug)]
+pub enum DkimError {
+    #[error("DKIM configuration error: {0}")]
+    Config(String),
+    #[error("Invalid DKIM private key for {domain}: {message}")]
+    Key { domain: String, message: String },
+}
+
+/// Headers signed when `signed_headers` isn't configured.
+pub const DEFAULT_SIGNED_HEADERS: &[&str] = &[
+    "From", "Reply-To", "Subject", "Date", "To", "Cc", "Message-ID",
+    "In-Reply-To", "References", "MIME-Version", "Content-Type",
+];
+
+struct DomainKey {
+    selector: String,
+    key: SigningKey<Sha256>,
+}
+
+/// Signing keys by domain, and the headers to sign.
+pub struct DkimSigner {
+    keys: HashMap<String, DomainKey>,
+    signed_headers: Vec<String>,
+}
+
+impl std::fmt::Debug for DkimSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DkimSigner")
+            .field("domains", &self.keys.keys().collect::<Vec<_>>())
+            .field("signed_headers", &self.signed_headers)
+            .finish()
+    }
+}
+
+impl DkimSigner {
+    /// Signer from the `[dkim]` section plus the `DKIM_*` variables.
+    /// Returns None when no key is configured at all.
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>, DkimError> {
+        let mut config = settings.dkim.clone().unwrap_or_default();
+
+        if let Ok(domain) = std::env::var("DKIM_DOMAIN") {
+            let selector = std::env::var("DKIM_SELECTOR")
+                .map_err(|_| DkimError::Config("DKIM_DOMAIN is set but DKIM_SELECTOR is not".to_string()))?;
+            let private_key = std::env::var("DKIM_PRIVATE_KEY")
+                .map_err(|_| DkimError::Config("DKIM_DOMAIN is set but DKIM_PRIVATE_KEY is not".to_string()))?;
+            config.domains.retain(|d| !d.domain.eq_ignore_ascii_case(&domain));
+            config.domains.push(DkimDomainConfig { domain, selector, private_key });
+        }
+        if let Ok(headers) = std::env::var("DKIM_SIGNED_HEADERS") {
+            config.signed_headers = headers.split(',')
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+                .collect();
+        }
+
+        if config.domains.is_empty() {
+            return Ok(None);
+        }
+        Self::new(&config).map(Some)
+    }
+
+    pub fn new(config: &DkimConfig) -> Result<Self, DkimError> {
+        let mut keys = HashMap::new();
+        for domain in &config.domains {
+            let name = domain.domain.trim().to_lowercase();
+            let key = load_private_key(&domain.private_key).map_err(|message| DkimError::Key {
+                domain: name.clone(),
+                message,
+            })?;
+            info!("DKIM signing enabled for {} (selector {})", name, domain.selector);
+            keys.insert(name, DomainKey {
+                selector: domain.selector.clone(),
+                key: SigningKey::<Sha256>::new(key),
+            });
+        }
+
+        let mut signed_headers: Vec<String> = if config.signed_headers.is_empty() {
+            DEFAULT_SIGNED_HEADERS.iter().map(|h| h.to_string()).collect()
+        } else {
+            config.signed_headers.clone()
+        };
+        // RFC 6376 section 5.4: From must always be signed
+        if !signed_headers.iter().any(|h| h.eq_ignore_ascii_case("from")) {
+            signed_headers.insert(0, "From".to_string());
+        }
+
+        Ok(Self { keys, signed_headers })
+    }
+
+    pub fn has_key_for(&self, domain: &str) -> bool {
+        self.keys.contains_key(&domain.to_lowercase())
+    }
+
+    /// The message with a `DKIM-Signature` header for the domain of its
+    /// From header prepended, or None if that domain has no key. `sender`'s
+    /// domain is used only for a message without a From header.
+    pub fn sign(&self, sender: &str, message: &[u8]) -> Option<Vec<u8>> {
+        let domain = signing_domain(sender, message);
+        let domain_key = self.keys.get(&domain)?;
+        let timestamp = chrono::Utc::now().timestamp();
+        Some(sign_message(message, &domain, domain_key, &self.signed_headers, timestamp))
+    }
+
+    /// Sign if possible, otherwise log why not and send as-is.
+    pub fn sign_or_passthrough(&self, sender: &str, message: &[u8]) -> Vec<u8> {
+        match self.sign(sender, message) {
+            Some(signed) => {
+                debug!("DKIM-signed message from {}", sender);
+                signed
+            }
+            None => {
+                warn!("No DKIM key configured for {} (message from {}); sending unsigned",
+                      signing_domain(sender, message), sender);
+                message.to_vec()
+            }
+        }
+    }
+}
+
+/// Domain of the first address in the From header, lowercased, or of
+/// `sender` when there is none.
+fn signing_domain(sender: &str, message: &[u8]) -> String {
+    let (header_block, _) = split_message(message);
+    let from = parse_headers(header_block)
+        .into_iter()
+        .find(|(name, _)| name == "from")
+        .and_then(|(_, raw)| address_domain(&String::from_utf8_lossy(&raw)));
+    from.unwrap_or_else(|| sender.rsplit_once('@').map(|(_, d)| d).unwrap_or(sender).to_lowercase())
+}
+
+/// Domain of the first address in a From header line.
+fn address_domain(header: &str) -> Option<String> {
+    let value = header.split_once(':').map(|(_, v)| v)?;
+    let address = match value.split_once('<') {
+        Some((_, rest)) => rest.split('>').next()?,
+        None => value.split(',').next()?,
+    };
+    let (_, domain) = address.trim().rsplit_once('@')?;
+    let domain = domain.trim().to_lowercase();
+    (!domain.is_empty()).then_some(domain)
+}
+
+/// Key from PEM text (PKCS#8 or PKCS#1) or a path to a PEM file.
+fn load_private_key(source: &str) -> Result<RsaPrivateKey, String> {
+    let pem = if source.trim_start().starts_with("-----BEGIN") {
+        source.to_string()
+    } else {
+        std::fs::read_to_string(source.trim()).map_err(|e| format!("failed to read {}: {}", source, e))?
+    };
+    RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+        .map_err(|e| e.to_string())
+}
+
+fn sign_message(message: &[u8], domain: &str, domain_key: &DomainKey, signed_headers: &[String], timestamp: i64) -> Vec<u8> {
+    let (header_block, body) = split_message(message);
+    let headers = parse_headers(header_block);
+
+    let body_hash = BASE64.encode(Sha256::digest(canonicalize_body_relaxed(body)));
+
+    // Each listed name takes the next instance from the bottom, so repeated
+    // headers are signed as often as they are listed
+    let mut used: HashMap<String, usize> = HashMap::new();
+    let mut names = Vec::new();
+    let mut signed = Vec::new();
+    for name in signed_headers {
+        let lower = name.to_lowercase();
+        let skip = used.entry(lower.clone()).or_insert(0);
+        if let Some((_, raw)) = headers.iter().rev().filter(|(n, _)| *n == lower).nth(*skip) {
+            *skip += 1;
+            names.push(lower);
+            signed.push(canonicalize_header_relaxed(raw));
+        }
+    }
+
+    let tags = format!(
+        "v=1; a=rsa-sha256; c=relaxed/relaxed; d={}; s={}; t={}; h={}; bh={}; b=",
+        domain, domain_key.selector, timestamp, names.join(":"), body_hash
+    );
+
+    let mut data = Vec::new();
+    for header in &signed {
+        data.extend_from_slice(header);
+        data.extend_from_slice(b"\r\n");
+    }
+    // The signature header itself, empty b= and no trailing CRLF
+    data.extend_from_slice(&canonicalize_header_relaxed(format!("DKIM-Signature: {}", tags).as_bytes()));
+    let signature = BASE64.encode(domain_key.key.sign(&data).to_bytes());
+
+    // Fold at the tag separators and inside b=; relaxed canonicalization
+    // turns both back into what was signed
+    let mut header = format!("DKIM-Signature: {}", tags.replace("; ", ";\r\n\t"));
+    for (i, chunk) in signature.as_bytes().chunks(72).enumerate() {
+        if i > 0 {
+            header.push_str("\r\n\t");
+        }
+        header.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+    }
+    header.push_str("\r\n");
+
+    let mut out = header.into_bytes();
+    out.extend_from_slice(message);
+    out
+}
+
+/// Split at the blank line ending the header block.
+fn split_message(message: &[u8]) -> (&[u8], &[u8]) {
+    match message.windows(4).position(|w| w == b"\r\n\r\n") {
+        Some(pos) => (&message[..pos + 2], &message[pos + 4..]),
+        None => (message, &[]),
+    }
+}
+
+/// (lowercased name, full header bytes with folding) in message order.
+/// Header bytes are kept as sent; 8-bit text must not be altered before it
+/// is hashed.
+fn parse_headers(block: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut headers: Vec<(String, Vec<u8>)> = Vec::new();
+    for line in split_lines(block) {
+        if line.is_empty() {
+            continue;
+        }
+        if line[0] == b' ' || line[0] == b'\t' {
+            if let Some((_, raw)) = headers.last_mut() {
+                raw.extend_from_slice(b"\r\n");
+                raw.extend_from_slice(line);
+            }
+            continue;
+        }
+        if let Some(colon) = line.iter().position(|&b| b == b':') {
+            let name = String::from_utf8_lossy(&line[..colon]).trim().to_lowercase();
+            headers.push((name, line.to_vec()));
+        }
+    }
+    headers
+}
+
+/// Lines separated by CRLF, without it.
+fn split_lines(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while let Some(pos) = bytes[start..].windows(2).position(|w| w == b"\r\n") {
+        lines.push(&bytes[start..start + pos]);
+        start += pos + 2;
+    }
+    lines.push(&bytes[start..]);
+    lines
+}
+
+/// Relaxed header canonicalization (RFC 6376 section 3.4.2), without the
+/// trailing CRLF.
+fn canonicalize_header_relaxed(raw: &[u8]) -> Vec<u8> {
+    let colon = raw.iter().position(|&b| b == b':').unwrap_or(raw.len());
+    let name = trim_wsp(&raw[..colon]).to_ascii_lowercase();
+    let value = raw.get(colon + 1..).unwrap_or_default();
+    let unfolded: Vec<u8> = value.iter().copied().filter(|&b| b != b'\r' && b != b'\n').collect();
+    let mut out = name;
+    out.push(b':');
+    out.extend_from_slice(trim_wsp(&collapse_whitespace(&unfolded)));
+    out
+}
+
+/// Relaxed body canonicalization (RFC 6376 section 3.4.4).
+fn canonicalize_body_relaxed(body: &[u8]) -> Vec<u8> {
+    let mut lines: Vec<Vec<u8>> = split_lines(body)
+        .into_iter()
+        .map(|line| {
+            let mut line = collapse_whitespace(line);
+            while line.last() == Some(&b' ') {
+                line.pop();
+            }
+            line
+        })
+        .collect();
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    let mut out = Vec::new();
+    for line in lines {
+        out.extend_from_slice(&line);
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+/// Runs of spaces and tabs to a single space.
+fn collapse_whitespace(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut in_ws = false;
+    for &b in bytes {
+        if b == b' ' || b == b'\t' {
+            if !in_ws {
+                out.push(b' ');
+            }
+            in_ws = true;
+        } else {
+            out.push(b);
+            in_ws = false;
+        }
+    }
+    out
+}
+
+/// Without leading and trailing spaces and tabs.
+fn trim_wsp(bytes: &[u8]) -> &[u8] {
+    let is_wsp = |b: &u8| *b == b' ' || *b == b'\t';
+    let start = bytes.iter().position(|b| !is_wsp(b)).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| !is_wsp(b)).map_or(start, |i| i + 1);
+    &bytes[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::pkcs1v15::{Signature, VerifyingKey};
+    use rsa::signature::Verifier;
+
+    #[test]
+    fn test_relaxed_canonicalization() {
+        assert_eq!(canonicalize_header_relaxed(b"SubJect :  Hello \t world \r\n  again  "), b"subject:Hello world again");
+        assert_eq!(canonicalize_body_relaxed(b"a  b \t\r\n\r\nc\r\n\r\n\r\n"), b"a b\r\n\r\nc\r\n");
+        assert_eq!(canonicalize_body_relaxed(b"\r\n\r\n"), b"");
+    }
+
+    #[test]
+    fn test_canonicalization_keeps_8bit_bytes() {
+        // Latin-1 "café", not valid UTF-8
+        assert_eq!(canonicalize_header_relaxed(b"Subject: caf\xe9  au lait"), b"subject:caf\xe9 au lait");
+        assert_eq!(canonicalize_body_relaxed(b"caf\xe9 \t au lait \r\n"), b"caf\xe9 au lait\r\n");
+    }
+
+    #[test]
+    fn test_signing_domain_follows_from_header() {
+        let message = b"From: \"Doe, Jane\" <jane@Example.COM>\r\nSubject: Hi\r\n\r\nBody\r\n";
+        assert_eq!(signing_domain("account@mail.example.net", message), "example.com");
+        assert_eq!(signing_domain("bob@example.org", b"From: bob@Example.org\r\n\r\n"), "example.org");
+        assert_eq!(signing_domain("bob@example.org", b"Subject: no from\r\n\r\n"), "example.org");
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let private = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let verifying = VerifyingKey::<Sha256>::new(private.to_public_key());
+        let domain_key = DomainKey { selector: "mail".to_string(), key: SigningKey::<Sha256>::new(private) };
+        let headers: Vec<String> = ["From", "Subject", "X-Missing"].iter().map(|h| h.to_string()).collect();
+
+        let message = b"From: Jane <jane@example.com>\r\nTo: bob@example.org\r\nSubject: Hi\r\n there\r\n\r\nHello  Bob\r\n\r\n";
+        let signed = sign_message(message, "example.com", &domain_key, &headers, 1700000000);
+        let text = String::from_utf8(signed.clone()).unwrap();
+        assert!(text.starts_with("DKIM-Signature: v=1;\r\n\ta=rsa-sha256;\r\n\tc=relaxed/relaxed;\r\n\td=example.com;"));
+        assert!(text.contains("h=from:subject;"));
+        assert!(signed.ends_with(message));
+
+        // Verify the way a receiver would: canonicalize the received
+        // signature header with b= emptied
+        let (block, body) = split_message(&signed);
+        let parsed = parse_headers(block);
+        let (_, dkim) = &parsed[0];
+        let canonical = String::from_utf8(canonicalize_header_relaxed(dkim)).unwrap();
+        let (head, b) = canonical.rsplit_once("; b=").unwrap();
+        let unsigned = format!("{}; b=", head);
+        let b = b.replace(' ', "");
+        let bh = BASE64.encode(Sha256::digest(canonicalize_body_relaxed(body)));
+        assert!(unsigned.contains(&format!("bh={}", bh)));
+
+        let mut data = Vec::new();
+        for name in ["from", "subject"] {
+            let (_, raw) = parsed.iter().rev().find(|(n, _)| n == name).unwrap();
+            data.extend_from_slice(&canonicalize_header_relaxed(raw));
+            data.extend_from_slice(b"\r\n");
+        }
+        data.extend_from_slice(unsigned.as_bytes());
+        let signature = Signature::try_from(BASE64.decode(&b).unwrap().as_slice()).unwrap();
+        assert!(verifying.verify(&data, &signature).is_ok());
+    }
+}
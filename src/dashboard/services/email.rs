@@ -3,16 +3,31 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use log::{info, error, debug, warn};
+use tracing::{info, error, debug, warn};
+use serde::{Deserialize, Serialize};
+use crate::imap::capabilities::{self, ServerCapabilities};
 use crate::imap::error::ImapError;
-use crate::imap::types::Email;
+use crate::imap::namespace;
+use crate::imap::provider_profile::ProviderProfile;
+use crate::imap::sort::SortKey;
+use crate::imap::special_use::{self, SpecialUse};
+use crate::imap::threading::{ThreadAlgorithm, ThreadNode};
+use crate::imap::types::{build_folder_tree, imap_flag_syntax, CopyOutcome, FlagOperation, FolderNode, Email, FetchFailure, FolderState, PartialBody, QuotaInfo, SearchCriteria};
 use crate::prelude::CloneableImapSessionFactory;
-use crate::connection_pool::ConnectionPool;
+use crate::connection_pool::{
+    AccountConnectionFactory, AcquirePriority, ConnectionFactory, ConnectionPool, MultiAccountPool, PoolConfig, PoolDrain, PoolError, PoolStats,
+    SessionHandle,
+};
+use crate::imap::client::ImapClient;
+use crate::imap::session::AsyncImapSessionWrapper;
 use crate::dashboard::services::cache::{CacheService, CachedEmail};
 use crate::dashboard::services::account::{AccountService, Account, AccountError};
 use crate::dashboard::services::attachment_storage::{self, AttachmentInfo, AttachmentError};
+use crate::sieve::{ManageSieveClient, SieveAuth, SieveError};
 use tokio::sync::Mutex as TokioMutex;
+use dashmap::DashMap;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -31,13 +46,208 @@ pub enum EmailServiceError {
     AttachmentError(#[from] AttachmentError),
     #[error("Cache service not available")]
     CacheServiceNotAvailable,
+    #[error(transparent)]
+    SieveError(#[from] crate::sieve::SieveError),
+}
+
+/// Outcome of a move or delete. UIDs that were no longer in the folder,
+/// typically because a concurrent call already moved or deleted them, are
+/// skipped rather than reported as a failure.
+#[derive(Debug, Default, Serialize)]
+pub struct MutationOutcome {
+    /// UIDs the operation was applied to
+    pub applied: Vec<u32>,
+    /// UIDs already gone from the folder when the operation ran
+    pub already_gone: Vec<u32>,
+}
+
+/// Message and unread totals of one folder, for unread badges.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadCount {
+    pub total: u32,
+    pub unread: u32,
+    /// "cache" or "live" (STATUS)
+    pub source: &'static str,
+    pub last_sync: Option<chrono::DateTime<chrono::Utc>>,
+    /// Why live counts couldn't be had; the cached ones are shown instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// UIDs of one folder for `bulk_flag_for_account`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlagGroup {
+    pub folder: String,
+    pub uids: Vec<u32>,
+}
+
+/// Outcome of one folder in a bulk flag change.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlagGroupResult {
+    pub folder: String,
+    pub uids: Vec<u32>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+type MessageKey = (String, String, u32);
+
+/// Per-message mutation locks held by one operation; dropping them wakes the
+/// next queued operation and forgets locks nobody else is waiting on.
+struct MessageLocks {
+    locks: Arc<DashMap<MessageKey, Arc<TokioMutex<()>>>>,
+    held: Vec<(MessageKey, tokio::sync::OwnedMutexGuard<()>)>,
+}
+
+impl Drop for MessageLocks {
+    fn drop(&mut self) {
+        for (key, guard) in self.held.drain(..) {
+            drop(guard);
+            // Only the map's reference left: no one holds or awaits this lock
+            self.locks.remove_if(&key, |_, lock| Arc::strong_count(lock) == 1);
+        }
+    }
+}
+
+/// Result of `refresh_connections_for_account`: a fresh login with the
+/// account's stored credentials.
+#[derive(Debug, Serialize)]
+pub struct ConnectionRefresh {
+    pub account_id: String,
+    pub imap_host: String,
+    pub connected: bool,
+    pub login_ms: u64,
+    /// Capabilities the server advertised after login
+    pub capabilities: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of a server-side search capped at a result limit.
+#[derive(Debug)]
+pub struct ServerSearch {
+    /// Every message the SEARCH matched; may exceed `uids.len()`
+    pub total_matches: usize,
+    /// The newest matches within the limit, highest UID first
+    pub uids: Vec<u32>,
+    /// Envelope-only fetches of `uids`, in the same order
+    pub previews: Vec<Email>,
+}
+
+/// The conversation a message belongs to, from `get_thread`.
+#[derive(Debug)]
+pub struct Conversation {
+    /// Tree of the whole conversation, starting at its first message
+    pub thread: ThreadNode,
+    /// Envelope-only fetches of every message in `thread`
+    pub messages: Vec<Email>,
+}
+
+/// Threads computed for one folder, reused until the folder's MODSEQ moves.
+struct CachedThreads {
+    uid_validity: Option<u32>,
+    modseq: u64,
+    threads: Arc<Vec<ThreadNode>>,
+}
+
+/// Whether moves and deletes on the same message are queued behind each
+/// other (`SERIALIZE_MESSAGE_MUTATIONS`, default true).
+fn serialize_message_mutations() -> bool {
+    std::env::var("SERIALIZE_MESSAGE_MUTATIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Outcome of a cross-account message transfer
+#[derive(Debug, Serialize)]
+pub struct TransferResult {
+    /// Source UIDs whose copy was verified in the destination folder
+    pub transferred: Vec<u32>,
+    /// Source UIDs that could not be transferred, with the reason
+    pub failed: Vec<FetchFailure>,
+    /// Whether the transferred messages were removed from the source folder
+    pub deleted_from_source: bool,
+    /// Error from the source-side delete, if it was requested and failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete_error: Option<String>,
+}
+
+/// IMAP session for one account operation: borrowed from the account's
+/// pool, or a one-off login when pooling is off or the pool is exhausted.
+/// Derefs to the client either way.
+pub enum AccountSession {
+    Pooled(SessionHandle),
+    Direct(ImapClient<AsyncImapSessionWrapper>),
+}
+
+impl std::ops::Deref for AccountSession {
+    type Target = ImapClient<AsyncImapSessionWrapper>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            AccountSession::Pooled(handle) => handle.client(),
+            AccountSession::Direct(client) => client,
+        }
+    }
+}
+
+impl AccountSession {
+    /// Finish with the session. A pooled connection stays logged in and
+    /// goes back to its pool; a one-off session is logged out.
+    pub async fn logout(self) -> Result<(), ImapError> {
+        match self {
+            AccountSession::Pooled(_) => Ok(()),
+            AccountSession::Direct(client) => client.logout().await,
+        }
+    }
 }
 
 pub struct EmailService {
     imap_factory: CloneableImapSessionFactory,
     connection_pool: Arc<ConnectionPool>,
+    /// Connections kept open per account, keyed by email address
+    account_pools: Arc<MultiAccountPool>,
     cache_service: Option<Arc<CacheService>>,
     account_service: Option<Arc<TokioMutex<AccountService>>>,
+    /// One lock per (account, folder, uid) being backfilled, so concurrent
+    /// cache misses for the same message share a single IMAP fetch
+    backfills: Arc<DashMap<(String, String, u32), Arc<TokioMutex<()>>>>,
+    /// One lock per (account, folder, uid) being moved or deleted, so
+    /// concurrent mutations of the same message run one after the other
+    message_locks: Arc<DashMap<MessageKey, Arc<TokioMutex<()>>>>,
+    /// Threads per (account, folder, algorithm), valid for one MODSEQ
+    thread_cache: Arc<DashMap<(String, String, ThreadAlgorithm), CachedThreads>>,
+}
+
+/// Whether a cache miss in `get_email_by_uid_with_backfill` falls through to
+/// IMAP (`CACHE_BACKFILL_ON_MISS`, default true).
+fn cache_backfill_on_miss() -> bool {
+    std::env::var("CACHE_BACKFILL_ON_MISS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Bytes per partial FETCH in `stream_raw_message_for_account`.
+const RAW_STREAM_CHUNK: u32 = 256 * 1024;
+
+/// Message-IDs looked up per `UID SEARCH` in `find_message_ids_for_account`.
+const MESSAGE_ID_SEARCH_BATCH: usize = 50;
+
+/// SEARCH criteria matching any of `ids` in the Message-ID header. IMAP's
+/// OR takes two keys, so n ids need n-1 ORs in front.
+fn message_id_criteria(ids: &[String]) -> String {
+    let keys: Vec<String> = ids.iter()
+        .map(|id| format!("HEADER Message-ID \"{}\"", id.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("{}{}", "OR ".repeat(keys.len().saturating_sub(1)), keys.join(" "))
+}
+
+/// A Message-ID without surrounding whitespace and angle brackets.
+fn bare_message_id(id: &str) -> &str {
+    id.trim().trim_start_matches('<').trim_end_matches('>')
 }
 
 impl EmailService {
@@ -45,8 +255,12 @@ impl EmailService {
         Self {
             imap_factory,
             connection_pool,
+            account_pools: Arc::new(MultiAccountPool::new(PoolConfig::per_account())),
             cache_service: None,
             account_service: None,
+            backfills: Arc::new(DashMap::new()),
+            message_locks: Arc::new(DashMap::new()),
+            thread_cache: Arc::new(DashMap::new()),
         }
     }
 
@@ -70,13 +284,85 @@ impl EmailService {
         Ok(account)
     }
 
-    /// Create an IMAP session for an account and record connection status (success or failure)
+    /// Get an IMAP session for an account from its connection pool. A pooled
+    /// connection that fails a NOOP is discarded, and when pooling is off or
+    /// the pool is exhausted a one-off session is opened instead. While the
+    /// pool's circuit breaker is open this fails fast with `CircuitOpen`.
     async fn create_session_with_status(
         &self,
         account: &Account,
         account_id: &str,
         operation: &str,
-    ) -> Result<crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>, EmailServiceError> {
+    ) -> Result<AccountSession, EmailServiceError> {
+        self.session_with_priority(account, account_id, operation, AcquirePriority::Interactive).await
+    }
+
+    /// `create_session_with_status` for bulk work. The connection is borrowed
+    /// at background priority, and an exhausted pool is an error rather than
+    /// a reason to log in around it, so interactive requests keep their
+    /// reserved connections.
+    async fn create_background_session(
+        &self,
+        account: &Account,
+        account_id: &str,
+        operation: &str,
+    ) -> Result<AccountSession, EmailServiceError> {
+        self.session_with_priority(account, account_id, operation, AcquirePriority::Background).await
+    }
+
+    async fn session_with_priority(
+        &self,
+        account: &Account,
+        account_id: &str,
+        operation: &str,
+        priority: AcquirePriority,
+    ) -> Result<AccountSession, EmailServiceError> {
+        if MultiAccountPool::enabled() {
+            let acquired = self.account_pools.acquire(&account.email_address, priority, || self.pool_factory(account)).await;
+            match acquired {
+                Ok(handle) => {
+                    if handle.client().noop().await.is_ok() {
+                        debug!("Using pooled connection for {} ({})", account.email_address, operation);
+                        return Ok(AccountSession::Pooled(handle));
+                    }
+                    debug!("Pooled connection for {} failed NOOP; replacing it", account.email_address);
+                    handle.discard();
+                }
+                Err(e @ PoolError::CircuitOpen(_)) => {
+                    // The server kept refusing connections; a direct login would too
+                    warn!("Not connecting {} for {}: {}", account.email_address, operation, e);
+                    return Err(EmailServiceError::ImapError(e.into()));
+                }
+                Err(e @ PoolError::PoolExhausted) if priority == AcquirePriority::Background => {
+                    warn!("No connection free for {} ({}) outside those reserved for interactive use", account.email_address, operation);
+                    return Err(EmailServiceError::ImapError(e.into()));
+                }
+                Err(PoolError::PoolExhausted) | Err(PoolError::ShuttingDown) => {
+                    debug!("No pooled connection for {} ({}); opening a direct session", account.email_address, operation);
+                }
+                Err(e) => {
+                    // Login failures surface through the direct attempt below,
+                    // which records them as the account's connection status
+                    debug!("Pool could not connect {}: {}", account.email_address, e);
+                }
+            }
+        }
+
+        self.connect_with_status(account, account_id, operation).await.map(AccountSession::Direct)
+    }
+
+    fn pool_factory(&self, account: &Account) -> Arc<dyn ConnectionFactory> {
+        Arc::new(AccountConnectionFactory::new(self.imap_factory.clone(), account.clone()))
+    }
+
+    /// Log in to an account outside the pool and record connection status
+    /// (success or failure)
+    async fn connect_with_status(
+        &self,
+        account: &Account,
+        account_id: &str,
+        operation: &str,
+    ) -> Result<ImapClient<AsyncImapSessionWrapper>, EmailServiceError> {
         match self.imap_factory.create_session_for_account(account).await {
             Ok(s) => {
                 if let Some(account_service) = &self.account_service {
@@ -106,6 +392,53 @@ impl EmailService {
         }
     }
 
+    /// Take the mutation lock of every message in `uids`, in UID order so
+    /// overlapping batches can't deadlock. A no-op when
+    /// `SERIALIZE_MESSAGE_MUTATIONS=false`.
+    async fn lock_messages(&self, account_key: &str, folder: &str, uids: &[u32]) -> MessageLocks {
+        let mut locks = MessageLocks { locks: Arc::clone(&self.message_locks), held: Vec::new() };
+        if !serialize_message_mutations() {
+            return locks;
+        }
+
+        let mut sorted = uids.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        for uid in sorted {
+            let key = (account_key.to_lowercase(), folder.to_string(), uid);
+            let lock = self.message_locks.entry(key.clone()).or_default().clone();
+            let guard = lock.lock_owned().await;
+            locks.held.push((key, guard));
+        }
+        locks
+    }
+
+    /// Account key for the mutation locks of default-account operations.
+    async fn default_account_key(&self) -> String {
+        if let Some(account_service) = &self.account_service {
+            if let Ok(Some(account)) = account_service.lock().await.get_default_account().await {
+                return account.email_address;
+            }
+        }
+        "default".to_string()
+    }
+
+    /// Split `uids` into those still present in the selected folder and those
+    /// that are gone, with one UID FETCH FLAGS. Both lists are sorted.
+    async fn partition_existing(
+        client: &crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>,
+        uids: &[u32],
+    ) -> Result<(Vec<u32>, Vec<u32>), ImapError> {
+        let present: std::collections::HashSet<u32> = client.fetch_flags(uids).await?
+            .into_iter()
+            .map(|(uid, _)| uid)
+            .collect();
+        let mut requested = uids.to_vec();
+        requested.sort_unstable();
+        requested.dedup();
+        Ok(requested.into_iter().partition(|uid| present.contains(uid)))
+    }
+
     /// List all folders for a specific account
     pub async fn list_folders_for_account(&self, account_id: &str) -> Result<Vec<String>, EmailServiceError> {
         debug!("Listing email folders for account: {}", account_id);
@@ -128,6 +461,476 @@ impl EmailService {
         Ok(folders)
     }
 
+    /// List folders with their selectability (\Noselect) and subscription state
+    pub async fn list_folder_states_for_account(&self, account_id: &str) -> Result<Vec<FolderState>, EmailServiceError> {
+        debug!("Listing folder states for account: {}", account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "folder state listing").await?;
+
+        let states = session.list_folder_states().await?;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        info!("Listed state of {} folders for account {}", states.len(), account_id);
+        Ok(states)
+    }
+
+    /// The account's folders as a nested tree, built with the delimiter of
+    /// its personal namespace. Unread counts come from the cache, so folders
+    /// that were never synced show 0.
+    pub async fn list_folders_tree_for_account(&self, account_id: &str) -> Result<Vec<FolderNode>, EmailServiceError> {
+        debug!("Listing folder tree for account: {}", account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "folder tree listing").await?;
+
+        let listed = async {
+            let states = session.list_folder_states().await?;
+            let namespaces = namespace::resolve(&session, &account.email_address).await?;
+            Ok::<_, ImapError>((states, namespaces))
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        let (states, namespaces) = listed?;
+        let unread = match &self.cache_service {
+            Some(cache) => cache.get_unread_counts_for_account(account_id).await.unwrap_or_else(|e| {
+                warn!("Unread counts unavailable for folder tree of {}: {}", account_id, e);
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+        Ok(build_folder_tree(&states, namespaces.delimiter(), &unread))
+    }
+
+    /// Start over with an account's connections: re-read its credentials,
+    /// clear any connection-limit backoff, and log in once to prove the new
+    /// settings work. Once that login succeeds the account's pool is created
+    /// again, pre-warming its `min_connections`, so the next request doesn't
+    /// wait for a login. A failed login is reported in the result, not as an
+    /// error, and is recorded as the account's connection status.
+    pub async fn refresh_connections_for_account(&self, account_id: &str) -> Result<ConnectionRefresh, EmailServiceError> {
+        info!("Refreshing IMAP connections for account: {}", account_id);
+
+        let account = self.get_account(account_id).await?;
+        self.account_pools.evict(&account.email_address).await;
+        crate::imap::connection_limits::ConnectionLimiter::global().reset(&account.email_address);
+
+        let started = std::time::Instant::now();
+        let mut refresh = ConnectionRefresh {
+            account_id: account_id.to_string(),
+            imap_host: account.imap_host.clone(),
+            connected: false,
+            login_ms: 0,
+            capabilities: Vec::new(),
+            error: None,
+        };
+        match self.connect_with_status(&account, account_id, "connection refresh").await {
+            Ok(session) => {
+                refresh.login_ms = started.elapsed().as_millis() as u64;
+                refresh.connected = true;
+                match session.server_capabilities().await {
+                    Ok(capabilities) => refresh.capabilities = capabilities.names().map(str::to_string).collect(),
+                    Err(e) => warn!("Could not read capabilities of {}: {}", account.imap_host, e),
+                }
+
+                // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+                if let Err(e) = session.logout().await {
+                    warn!("Failed to logout IMAP session: {}", e);
+                }
+
+                if MultiAccountPool::enabled() {
+                    self.account_pools.pool_for(&account.email_address, || self.pool_factory(&account));
+                }
+            }
+            Err(e) => {
+                refresh.login_ms = started.elapsed().as_millis() as u64;
+                refresh.error = Some(e.to_string());
+            }
+        }
+        Ok(refresh)
+    }
+
+    /// The per-account pools, for services that borrow from them too.
+    pub fn account_pools(&self) -> Arc<MultiAccountPool> {
+        Arc::clone(&self.account_pools)
+    }
+
+    /// Close an account's pooled connections, e.g. once it is deleted or its
+    /// server settings change. The next operation starts a fresh pool.
+    pub async fn evict_account_connections(&self, email_address: &str) -> bool {
+        self.account_pools.evict(email_address).await
+    }
+
+    /// Log out every pooled connection, per-account and shared, at shutdown.
+    pub async fn drain_connections(&self, limit: std::time::Duration) -> PoolDrain {
+        let (accounts, shared) = tokio::join!(self.account_pools.drain(limit), self.connection_pool.drain(limit));
+        accounts + shared
+    }
+
+    /// Connection pool statistics per account.
+    pub async fn account_pool_stats(&self) -> Vec<(String, PoolStats)> {
+        self.account_pools.stats().await
+    }
+
+    /// Folder conventions of the account's mail provider.
+    pub async fn provider_profile_for_account(&self, account_id: &str) -> Result<&'static ProviderProfile, EmailServiceError> {
+        let account = self.get_account(account_id).await?;
+        Ok(ProviderProfile::detect(account.provider_type.as_deref(), &account.imap_host))
+    }
+
+    /// The account's Sent, Drafts, Trash, Junk and Archive folders, from
+    /// the per-account store unless `refresh` asks for a new LIST.
+    pub async fn special_folders_for_account(
+        &self,
+        account_id: &str,
+        refresh: bool,
+    ) -> Result<HashMap<SpecialUse, String>, EmailServiceError> {
+        let account = self.get_account(account_id).await?;
+        if refresh {
+            special_use::invalidate(&account.email_address);
+        } else if let Some(folders) = special_use::cached(&account.email_address) {
+            return Ok(folders);
+        }
+
+        let profile = ProviderProfile::detect(account.provider_type.as_deref(), &account.imap_host);
+        let session = self.create_session_with_status(&account, account_id, "special-use folders").await?;
+
+        let result = special_use::resolve(&session, &account.email_address, profile).await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
+    /// What the account's server advertises after login, from the set kept
+    /// for the account unless `refresh` asks for a new CAPABILITY.
+    pub async fn server_capabilities_for_account(
+        &self,
+        account_id: &str,
+        refresh: bool,
+    ) -> Result<ServerCapabilities, EmailServiceError> {
+        let account = self.get_account(account_id).await?;
+        if refresh {
+            capabilities::invalidate(&account.email_address);
+        } else if let Some(capabilities) = capabilities::cached(&account.email_address) {
+            return Ok(capabilities);
+        }
+
+        let session = self.create_session_with_status(&account, account_id, "capabilities").await?;
+
+        // Sessions ask after login; a pooled one may have asked long ago
+        let result = if refresh {
+            session.refresh_capabilities().await
+        } else {
+            session.server_capabilities().await
+        };
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        let capabilities = result?;
+        capabilities::remember(&account.email_address, &capabilities);
+        Ok(capabilities)
+    }
+
+    /// `folder` unchanged, or the account's folder for it when it is written
+    /// as a special-use attribute such as `\Trash`.
+    pub async fn resolve_folder_for_account(&self, folder: &str, account_id: &str) -> Result<String, EmailServiceError> {
+        let Some(role) = SpecialUse::from_folder_alias(folder) else {
+            return Ok(folder.to_string());
+        };
+        self.special_folders_for_account(account_id, false).await?
+            .remove(&role)
+            .ok_or_else(|| ImapError::FolderNotFound(format!("{} has no {} folder", account_id, role)).into())
+    }
+
+    /// STATUS the given folders without selecting them. A folder the server
+    /// refuses carries its error instead of failing the call.
+    pub async fn folder_statuses_for_account(
+        &self,
+        folders: &[String],
+        account_id: &str,
+    ) -> Result<Vec<crate::imap::pipeline::FolderStatus>, EmailServiceError> {
+        debug!("STATUS of {} folders for account: {}", folders.len(), account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "folder status").await?;
+
+        let result = session.folder_statuses(folders).await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
+    /// Total and unread messages of every cached folder of the account.
+    /// Folders synced within `max_age` are answered from the cache; the rest
+    /// are STATUSed over one session. Folders whose STATUS fails keep their
+    /// cached counts, with the reason in `error`.
+    pub async fn unread_counts_for_account(
+        &self,
+        account_id: &str,
+        max_age: chrono::Duration,
+    ) -> Result<BTreeMap<String, UnreadCount>, EmailServiceError> {
+        let cache = self.cache_service.as_ref().ok_or(EmailServiceError::CacheServiceNotAvailable)?;
+        let folders = cache.get_all_cached_folders_for_account(account_id).await
+            .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to read cached folders: {}", e)))?;
+        let unread = cache.get_unread_counts_for_account(account_id).await
+            .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to read cached unread counts: {}", e)))?;
+
+        let now = chrono::Utc::now();
+        let mut stale = Vec::new();
+        let mut counts = BTreeMap::new();
+        for folder in folders {
+            if folder.attributes.iter().any(|a| crate::imap::types::is_noselect_attribute(a)) {
+                continue;
+            }
+            if !folder.last_sync.is_some_and(|t| now - t <= max_age) {
+                stale.push(folder.name.clone());
+            }
+            counts.insert(folder.name.clone(), UnreadCount {
+                total: folder.total_messages.max(folder.cached_count).max(0) as u32,
+                unread: unread.get(&folder.name).copied().unwrap_or(0).max(0) as u32,
+                source: "cache",
+                last_sync: folder.last_sync,
+                error: None,
+            });
+        }
+        debug!("Unread counts for {}: {} folders from cache, {} by STATUS", account_id, counts.len() - stale.len(), stale.len());
+
+        if !stale.is_empty() {
+            match self.folder_statuses_for_account(&stale, account_id).await {
+                Ok(statuses) => {
+                    for status in statuses {
+                        let Some(entry) = counts.get_mut(&status.name) else { continue };
+                        match (status.error, status.messages, status.unseen) {
+                            (None, Some(messages), Some(unseen)) => {
+                                entry.total = messages;
+                                entry.unread = unseen;
+                                entry.source = "live";
+                            }
+                            (Some(e), _, _) => entry.error = Some(format!("STATUS failed: {}", e)),
+                            _ => entry.error = Some("STATUS response lacked MESSAGES or UNSEEN".to_string()),
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Live unread counts for {} failed, using cached counts: {}", account_id, e);
+                    for name in &stale {
+                        if let Some(entry) = counts.get_mut(name) {
+                            entry.error = Some(format!("Live STATUS failed: {}", e));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(counts)
+    }
+
+    /// Quota usage of the quota root containing `mailbox`. Servers without
+    /// QUOTA give `ImapError::Unsupported`.
+    pub async fn get_quota_for_account(
+        &self,
+        mailbox: &str,
+        account_id: &str,
+    ) -> Result<QuotaInfo, EmailServiceError> {
+        debug!("GETQUOTAROOT {} for account: {}", mailbox, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "quota").await?;
+
+        let result = session.get_quota(mailbox).await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
+    /// Up to `length` bytes of message `uid`, or of its MIME part `section`,
+    /// starting at `offset`. Only that range crosses the wire.
+    pub async fn fetch_email_partial_for_account(
+        &self,
+        folder: &str,
+        uid: u32,
+        section: Option<&str>,
+        offset: u32,
+        length: u32,
+        account_id: &str,
+    ) -> Result<PartialBody, EmailServiceError> {
+        debug!("Partial fetch of UID {} in {} (section {:?}, {}+{}) for account {}",
+               uid, folder, section, offset, length, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "partial fetch").await?;
+
+        let result = session.fetch_section_partial(folder, uid, section.unwrap_or(""), offset, length).await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
+    /// Message `uid` in `folder` exactly as the server stores it (`BODY[]`),
+    /// headers included. Always read from the server, never the cache.
+    pub async fn fetch_raw_message_for_account(&self, folder: &str, uid: u32, account_id: &str) -> Result<Vec<u8>, EmailServiceError> {
+        debug!("Raw fetch of UID {} in {} for account {}", uid, folder, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "raw fetch").await?;
+
+        let result = match session.select_folder(folder).await {
+            Ok(_) => session.fetch_raw_message(uid).await,
+            Err(e) => Err(e),
+        };
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
+    /// Message `uid` in `folder` exactly as the server stores it, read from
+    /// the server in `RAW_STREAM_CHUNK` pieces with partial FETCHes so the
+    /// whole message is never held in memory. Returns its RFC822.SIZE and
+    /// the chunks; the first chunk is fetched before returning, so a missing
+    /// message fails here rather than partway through a response.
+    pub async fn stream_raw_message_for_account(
+        &self,
+        folder: &str,
+        uid: u32,
+        account_id: &str,
+    ) -> Result<(u64, futures::stream::BoxStream<'static, Result<Vec<u8>, EmailServiceError>>), EmailServiceError> {
+        debug!("Streaming raw UID {} in {} for account {}", uid, folder, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "raw fetch").await?;
+
+        let first = match session.fetch_section_partial(folder, uid, "", 0, RAW_STREAM_CHUNK).await {
+            Ok(first) => first,
+            Err(e) => {
+                if let Err(e) = session.logout().await {
+                    warn!("Failed to logout IMAP session: {}", e);
+                }
+                return Err(e.into());
+            }
+        };
+        let size = first.message_size.map(u64::from).unwrap_or(first.data.len() as u64);
+
+        let folder = folder.to_string();
+        let stream: futures::stream::BoxStream<'static, Result<Vec<u8>, EmailServiceError>> = Box::pin(async_stream::try_stream! {
+            let mut offset = first.data.len() as u32;
+            let mut more = first.truncated;
+            yield first.data;
+            while more {
+                let chunk = session.fetch_section_partial(&folder, uid, "", offset, RAW_STREAM_CHUNK).await?;
+                // An empty chunk would never advance the offset
+                more = chunk.truncated && !chunk.data.is_empty();
+                offset += chunk.data.len() as u32;
+                yield chunk.data;
+            }
+
+            // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+            if let Err(e) = session.logout().await {
+                warn!("Failed to logout IMAP session: {}", e);
+            }
+        });
+        Ok((size, stream))
+    }
+
+    /// RFC822.SIZE of message `uid` in `folder`, without downloading it.
+    pub async fn message_size_for_account(&self, folder: &str, uid: u32, account_id: &str) -> Result<u64, EmailServiceError> {
+        let partial = self.fetch_email_partial_for_account(folder, uid, None, 0, 0, account_id).await?;
+        partial.message_size
+            .map(u64::from)
+            .ok_or_else(|| EmailServiceError::ImapError(ImapError::EmailNotFound(vec![uid])))
+    }
+
+    /// The conversation containing `uid` in `folder`, or `None` when the
+    /// folder has no such message. The folder's threads are kept and reused
+    /// while its HIGHESTMODSEQ is unchanged; servers without CONDSTORE get
+    /// them recomputed on every call.
+    pub async fn get_thread(
+        &self,
+        folder: &str,
+        uid: u32,
+        algorithm: ThreadAlgorithm,
+        account_id: &str,
+    ) -> Result<Option<Conversation>, EmailServiceError> {
+        debug!("Threading {} ({}) for uid {} in account {}", folder, algorithm.as_imap(), uid, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "thread").await?;
+        let key = (account.email_address.to_lowercase(), folder.to_string(), algorithm);
+
+        let result: Result<Option<Conversation>, ImapError> = async {
+            let mailbox = session.select_folder(folder).await?;
+            let cached = mailbox.highest_modseq.and_then(|modseq| {
+                self.thread_cache.get(&key)
+                    .filter(|c| c.modseq == modseq && c.uid_validity == mailbox.uid_validity)
+                    .map(|c| Arc::clone(&c.threads))
+            });
+            let threads = match cached {
+                Some(threads) => threads,
+                None => {
+                    let threads = Arc::new(session.thread(algorithm, &SearchCriteria::All).await?);
+                    match mailbox.highest_modseq {
+                        Some(modseq) => {
+                            self.thread_cache.insert(key.clone(), CachedThreads {
+                                uid_validity: mailbox.uid_validity,
+                                modseq,
+                                threads: Arc::clone(&threads),
+                            });
+                        }
+                        None => {
+                            self.thread_cache.remove(&key);
+                        }
+                    }
+                    threads
+                }
+            };
+
+            let Some(thread) = threads.iter().find(|t| t.contains(uid)).cloned() else {
+                return Ok(None);
+            };
+            let uids = thread.uids();
+            let mut messages = session.fetch_envelopes(&uids).await?;
+            messages.sort_by_key(|m| uids.iter().position(|&u| u == m.uid));
+            Ok(Some(Conversation { thread, messages }))
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
     /// List all folders in the email account (uses default account)
     pub async fn list_folders(&self) -> Result<Vec<String>, EmailServiceError> {
         debug!("Listing email folders (default account)");
@@ -148,6 +951,88 @@ impl EmailService {
         Ok(folders)
     }
 
+    /// Run a structured UID SEARCH on the server and fetch envelopes (no
+    /// bodies) for only the `limit` newest matches, so a broad search never
+    /// materialises the whole folder. `total_matches` counts every match.
+    pub async fn search_uids_for_account(
+        &self,
+        folder: &str,
+        criteria: &SearchCriteria,
+        limit: usize,
+        account_id: &str,
+    ) -> Result<ServerSearch, EmailServiceError> {
+        debug!("Server search in '{}' with criteria: {} for account {}", folder, criteria, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "server search").await?;
+
+        let result = async {
+            session.select_folder(folder).await?;
+            let mut matches = session.search_emails(&criteria.to_string()).await?;
+            // A server may repeat a UID in its SEARCH reply; count each once
+            matches.sort_unstable();
+            matches.dedup();
+            let uids = crate::imap::types::newest_uids(&matches, 0, limit);
+            let total_matches = matches.len();
+
+            let mut previews = if uids.is_empty() {
+                Vec::new()
+            } else {
+                session.fetch_envelopes(&uids).await?
+            };
+            previews.sort_by(|a, b| b.uid.cmp(&a.uid));
+            Ok::<_, ImapError>(ServerSearch { total_matches, uids, previews })
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        let search = result?;
+        info!("Server search in {} matched {} emails for account {}", folder, search.total_matches, account_id);
+        Ok(search)
+    }
+
+    /// Like `search_uids_for_account`, but the matches are ordered by `keys`
+    /// (server SORT, or a local sort when unsupported) and the first `limit`
+    /// of that order are returned.
+    pub async fn list_folder_sorted(
+        &self,
+        folder: &str,
+        criteria: &SearchCriteria,
+        keys: &[SortKey],
+        limit: usize,
+        account_id: &str,
+    ) -> Result<ServerSearch, EmailServiceError> {
+        debug!("Sorted listing of '{}' by {:?} with criteria: {} for account {}", folder, keys, criteria, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let session = self.create_session_with_status(&account, account_id, "sorted listing").await?;
+
+        let result = async {
+            session.select_folder(folder).await?;
+            let sorted = session.sort(keys, criteria).await?;
+            let total_matches = sorted.len();
+            let uids: Vec<u32> = sorted.into_iter().take(limit).collect();
+
+            let mut previews = if uids.is_empty() {
+                Vec::new()
+            } else {
+                session.fetch_envelopes(&uids).await?
+            };
+            previews.sort_by_key(|email| uids.iter().position(|&u| u == email.uid));
+            Ok::<_, ImapError>(ServerSearch { total_matches, uids, previews })
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
     /// Search for emails in a specific folder for a specific account
     pub async fn search_emails_for_account(&self, folder: &str, criteria: &str, account_id: &str) -> Result<Vec<u32>, EmailServiceError> {
         debug!("Searching emails in folder '{}' with criteria: {} for account {}", folder, criteria, account_id);
@@ -262,11 +1147,84 @@ impl EmailService {
             }
         }
 
-        info!("Fetched {} emails for account {} ({} from cache, {} from IMAP)",
-              emails.len(), account_id,
-              uids.len() - uids_to_fetch.len(),
-              uids_to_fetch.len());
-        Ok(emails)
+        info!("Fetched {} emails for account {} ({} from cache, {} from IMAP)",
+              emails.len(), account_id,
+              uids.len() - uids_to_fetch.len(),
+              uids_to_fetch.len());
+        Ok(emails)
+    }
+
+    /// Re-fetch one email's full message from IMAP and overwrite its cache
+    /// entry, ignoring whatever is cached. Returns the refreshed cache row, or
+    /// None if the UID no longer exists on the server.
+    pub async fn refresh_email_for_account(&self, folder: &str, uid: u32, account_id: &str) -> Result<Option<CachedEmail>, EmailServiceError> {
+        let cache = self.cache_service.as_ref().ok_or(EmailServiceError::CacheServiceNotAvailable)?;
+        let account = self.get_account(account_id).await?;
+        let account_email = &account.email_address;
+
+        let session = self.create_session_with_status(&account, account_id, "refresh").await?;
+        let fetched = match session.select_folder(folder).await {
+            Ok(_) => session.fetch_emails(&[uid]).await,
+            Err(e) => Err(e),
+        };
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        let Some(email) = fetched?.into_iter().find(|e| e.uid == uid) else {
+            info!("Email {} not found in {} on server for account {}", uid, folder, account_id);
+            return Ok(None);
+        };
+
+        cache.cache_email(folder, &email, account_email).await
+            .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to update cache: {}", e)))?;
+        info!("Refreshed cached email {} in {} for account {}", uid, folder, account_id);
+
+        cache.get_email_by_uid_for_account(folder, uid, account_email).await
+            .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to read refreshed email: {}", e)))
+    }
+
+    /// Get an email from the cache, fetching and caching it from IMAP on a miss
+    /// unless `cache_only` is set or backfill is disabled. Concurrent misses for
+    /// the same message wait on one fetch instead of each going to the server.
+    pub async fn get_email_by_uid_with_backfill(
+        &self,
+        folder: &str,
+        uid: u32,
+        account_id: &str,
+        cache_only: bool,
+    ) -> Result<Option<CachedEmail>, EmailServiceError> {
+        let cache = self.cache_service.as_ref().ok_or(EmailServiceError::CacheServiceNotAvailable)?;
+        let lookup = || async move {
+            cache.get_email_by_uid_for_account(folder, uid, account_id).await
+                .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to read cache: {}", e)))
+        };
+
+        if let Some(email) = lookup().await? {
+            return Ok(Some(email));
+        }
+        if cache_only || !cache_backfill_on_miss() {
+            return Ok(None);
+        }
+
+        let key = (account_id.to_string(), folder.to_string(), uid);
+        let flight = self.backfills.entry(key.clone()).or_default().clone();
+        let _guard = flight.lock().await;
+
+        // Another caller may have finished the fetch while we waited
+        let result = match lookup().await {
+            Ok(Some(email)) => Ok(Some(email)),
+            Ok(None) => {
+                debug!("Cache miss for UID {} in {} ({}), backfilling from IMAP", uid, folder, account_id);
+                self.refresh_email_for_account(folder, uid, account_id).await
+            }
+            Err(e) => Err(e),
+        };
+
+        self.backfills.remove_if(&key, |_, v| Arc::ptr_eq(v, &flight));
+        result
     }
 
     /// Fetch emails by their UIDs (uses default account)
@@ -420,48 +1378,132 @@ impl EmailService {
         }
     }
 
-    /// Atomically move a single email from one folder to another
-    pub async fn atomic_move_message(&self, uid: u32, from_folder: &str, to_folder: &str) -> Result<(), EmailServiceError> {
-        debug!("Atomically moving email {} from {} to {}", uid, from_folder, to_folder);
+    /// Atomically move a single email from one folder to another. A message
+    /// already gone from `from_folder` is reported in `already_gone`.
+    pub async fn atomic_move_message(&self, uid: u32, from_folder: &str, to_folder: &str) -> Result<MutationOutcome, EmailServiceError> {
+        self.atomic_batch_move(&[uid], from_folder, to_folder).await
+    }
+
+    /// Atomically move multiple emails from one folder to another. Waits for
+    /// other moves or deletes of the same messages, then moves only those
+    /// still in `from_folder`.
+    pub async fn atomic_batch_move(&self, uids: &[u32], from_folder: &str, to_folder: &str) -> Result<MutationOutcome, EmailServiceError> {
+        debug!("Atomically moving {} emails from {} to {}", uids.len(), from_folder, to_folder);
+
+        let account_key = self.default_account_key().await;
+        let _locks = self.lock_messages(&account_key, from_folder, uids).await;
 
         let client = self.imap_factory.create_session().await
             .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to create session: {}", e)))?;
 
-        // Use atomic operations - extract the session from the client
-        let session = client.session_arc();
-        let atomic_ops = crate::imap::atomic::AtomicImapOperations::new((*session).clone());
-        atomic_ops.atomic_move(uid, from_folder, to_folder).await?;
+        let result = async {
+            client.select_folder(from_folder).await?;
+            let (present, already_gone) = Self::partition_existing(&client, uids).await?;
+            if !present.is_empty() {
+                let moving = present.as_slice();
+                client.with_session("atomic move", client.timeouts().fetch, |session| async move {
+                    let atomic_ops = crate::imap::atomic::AtomicImapOperations::new((*session).clone());
+                    match moving {
+                        [uid] => atomic_ops.atomic_move(*uid, from_folder, to_folder).await?,
+                        _ => atomic_ops.atomic_batch_move(moving, from_folder, to_folder).await?,
+                    }
+                    Ok(())
+                }).await?;
+            }
+            Ok::<_, ImapError>(MutationOutcome { applied: present, already_gone })
+        }.await;
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
         if let Err(e) = client.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
+        let outcome = result?;
 
         // Note: Cache will be invalidated naturally on next access
-        info!("Successfully moved email {} from {} to {}", uid, from_folder, to_folder);
-        Ok(())
+        if !outcome.already_gone.is_empty() {
+            info!("Skipped {} emails no longer in {}: {:?}", outcome.already_gone.len(), from_folder, outcome.already_gone);
+        }
+        info!("Successfully moved {} emails from {} to {}", outcome.applied.len(), from_folder, to_folder);
+        Ok(outcome)
     }
 
-    /// Atomically move multiple emails from one folder to another
-    pub async fn atomic_batch_move(&self, uids: &[u32], from_folder: &str, to_folder: &str) -> Result<(), EmailServiceError> {
-        debug!("Atomically moving {} emails from {} to {}", uids.len(), from_folder, to_folder);
+    /// Copy messages of an account to another folder, keeping the originals.
+    /// UIDs no longer in `from_folder` are reported in `missing`; the new
+    /// UIDs are included when the server supports UIDPLUS.
+    pub async fn copy_messages(
+        &self,
+        uids: &[u32],
+        from_folder: &str,
+        to_folder: &str,
+        account_id: &str,
+    ) -> Result<CopyOutcome, EmailServiceError> {
+        debug!("Copying {} emails from {} to {} for account {}", uids.len(), from_folder, to_folder, account_id);
 
-        let client = self.imap_factory.create_session().await
-            .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to create session: {}", e)))?;
+        let account = self.get_account(account_id).await?;
+        let client = self.create_session_with_status(&account, account_id, "copy").await?;
 
-        // Use atomic operations - extract the session from the client
-        let session = client.session_arc();
-        let atomic_ops = crate::imap::atomic::AtomicImapOperations::new((*session).clone());
-        atomic_ops.atomic_batch_move(uids, from_folder, to_folder).await?;
+        let result = async {
+            client.select_folder(from_folder).await?;
+            client.with_session("copy", client.timeouts().fetch, |session| async move {
+                crate::imap::atomic::AtomicImapOperations::new((*session).clone()).copy_messages(uids, to_folder).await
+            }).await
+        }.await;
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
         if let Err(e) = client.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
+        let outcome = result?;
 
-        // Note: Cache will be invalidated naturally on next access
-        info!("Successfully moved {} emails from {} to {}", uids.len(), from_folder, to_folder);
-        Ok(())
+        if !outcome.missing.is_empty() {
+            info!("Skipped {} emails no longer in {}: {:?}", outcome.missing.len(), from_folder, outcome.missing);
+        }
+        info!("Copied {} emails from {} to {}", outcome.copied.len(), from_folder, to_folder);
+        Ok(outcome)
+    }
+
+    /// Copy a large set of messages in chunks of `COPY_BATCH_SIZE` UIDs
+    /// (default 500) over one session, so no single command line grows
+    /// unbounded. Each chunk is copied as a unit; the outcomes are merged.
+    pub async fn batch_copy(
+        &self,
+        uids: &[u32],
+        from_folder: &str,
+        to_folder: &str,
+        account_id: &str,
+    ) -> Result<CopyOutcome, EmailServiceError> {
+        let chunk_size = std::env::var("COPY_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(500);
+        debug!("Batch copying {} emails from {} to {} in chunks of {}", uids.len(), from_folder, to_folder, chunk_size);
+
+        let account = self.get_account(account_id).await?;
+        let client = self.create_session_with_status(&account, account_id, "batch copy").await?;
+
+        let result = async {
+            client.select_folder(from_folder).await?;
+            let mut outcome = CopyOutcome::default();
+            for chunk in uids.chunks(chunk_size) {
+                let part = client.with_session("copy", client.timeouts().fetch, |session| async move {
+                    crate::imap::atomic::AtomicImapOperations::new((*session).clone()).copy_messages(chunk, to_folder).await
+                }).await?;
+                outcome.copied.extend(part.copied);
+                outcome.missing.extend(part.missing);
+                outcome.target_uid_validity = part.target_uid_validity.or(outcome.target_uid_validity);
+                outcome.new_uids.extend(part.new_uids);
+            }
+            Ok::<_, ImapError>(outcome)
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+        let outcome = result?;
+        info!("Copied {} emails from {} to {}", outcome.copied.len(), from_folder, to_folder);
+        Ok(outcome)
     }
 
     /// Mark email(s) as read (adds \Seen flag)
@@ -473,7 +1515,6 @@ impl EmailService {
 
         client.select_folder(folder).await?;
 
-        use crate::imap::types::FlagOperation;
         client.store_flags(uids, FlagOperation::Add, &vec!["\\Seen".to_string()]).await?;
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
@@ -495,7 +1536,6 @@ impl EmailService {
 
         client.select_folder(folder).await?;
 
-        use crate::imap::types::FlagOperation;
         client.store_flags(uids, FlagOperation::Remove, &vec!["\\Seen".to_string()]).await?;
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
@@ -508,38 +1548,318 @@ impl EmailService {
         Ok(())
     }
 
+    /// Open an authenticated ManageSieve session for an account, using its
+    /// IMAP credentials (or OAuth token).
+    pub async fn connect_sieve(&self, account_id: &str) -> Result<ManageSieveClient, EmailServiceError> {
+        let account = self.get_account(account_id).await?;
+        let account_service = self.account_service.as_ref()
+            .ok_or_else(|| EmailServiceError::AccountNotFound("Account service not available".to_string()))?;
+        let (host, port) = account_service.lock().await.get_sieve_endpoint(account_id).await?;
+
+        let auth = if account.is_oauth() {
+            let access_token = account.oauth_access_token.as_deref()
+                .ok_or_else(|| SieveError::Auth(format!("{} has no OAuth access token", account_id)))?;
+            SieveAuth::XOAuth2 { username: &account.email_address, access_token }
+        } else {
+            SieveAuth::Plain { username: &account.imap_user, password: &account.imap_pass }
+        };
+
+        Ok(ManageSieveClient::connect(&host, port, auth).await?)
+    }
+
+    /// Flag (star) or unflag email(s) for an account by adding or removing
+    /// \Flagged, then mirror the change into the cache.
+    pub async fn set_flagged_for_account(
+        &self,
+        folder: &str,
+        uids: &[u32],
+        flagged: bool,
+        account_id: &str,
+    ) -> Result<(), EmailServiceError> {
+        debug!("Setting \\Flagged={} on {} emails in {} for account {}", flagged, uids.len(), folder, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let client = self.create_session_with_status(&account, account_id, "flag").await?;
+
+        let operation = if flagged { FlagOperation::Add } else { FlagOperation::Remove };
+        let result = async {
+            client.select_folder(folder).await?;
+            client.store_flags(uids, operation, &["\\Flagged".to_string()]).await
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+        result?;
+
+        if let Some(cache) = &self.cache_service {
+            if let Err(e) = cache.set_cached_flag(folder, uids, "Flagged", flagged, &account.email_address).await {
+                warn!("Failed to update cached flags for {} emails in {}: {}", uids.len(), folder, e);
+            }
+        }
+
+        info!("Successfully {} {} emails in {}", if flagged { "flagged" } else { "unflagged" }, uids.len(), folder);
+        Ok(())
+    }
+
+    /// Add, remove or set `flags` on messages in several folders over one
+    /// session. Groups naming the same folder are merged so each folder is
+    /// selected once. A folder that fails is reported in its result and
+    /// the rest still go ahead; changes that succeed are mirrored into the
+    /// cache.
+    pub async fn bulk_flag_for_account(
+        &self,
+        groups: &[FlagGroup],
+        operation: FlagOperation,
+        flags: &[String],
+        account_id: &str,
+    ) -> Result<Vec<FlagGroupResult>, EmailServiceError> {
+        let flags: Vec<String> = flags.iter().filter_map(|f| imap_flag_syntax(f)).collect();
+
+        let mut merged: Vec<FlagGroupResult> = Vec::new();
+        for group in groups.iter().filter(|g| !g.uids.is_empty()) {
+            match merged.iter_mut().find(|m| m.folder == group.folder) {
+                Some(existing) => existing.uids.extend(&group.uids),
+                None => merged.push(FlagGroupResult {
+                    folder: group.folder.clone(),
+                    uids: group.uids.clone(),
+                    success: false,
+                    error: None,
+                }),
+            }
+        }
+        for group in &mut merged {
+            group.uids.sort_unstable();
+            group.uids.dedup();
+        }
+        debug!("{:?} {:?} on {} folders for account {}", operation, flags, merged.len(), account_id);
+
+        let account = self.get_account(account_id).await?;
+        let client = self.create_background_session(&account, account_id, "bulk flag").await?;
+
+        for group in &mut merged {
+            let result = async {
+                client.select_folder(&group.folder).await?;
+                client.store_flags(&group.uids, operation.clone(), &flags).await
+            }.await;
+            match result {
+                Ok(()) => group.success = true,
+                Err(e) => {
+                    warn!("Failed to change flags in {} for account {}: {}", group.folder, account_id, e);
+                    group.error = Some(e.to_string());
+                }
+            }
+        }
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        if let Some(cache) = &self.cache_service {
+            for group in merged.iter().filter(|g| g.success) {
+                let mirrored = match operation {
+                    FlagOperation::Add | FlagOperation::Remove => {
+                        let present = operation == FlagOperation::Add;
+                        let mut mirrored = Ok(());
+                        for flag in &flags {
+                            mirrored = cache.set_cached_flag(&group.folder, &group.uids, flag.trim_start_matches('\\'), present, &account.email_address).await;
+                            if mirrored.is_err() {
+                                break;
+                            }
+                        }
+                        mirrored
+                    }
+                    FlagOperation::Set => {
+                        let cached: Vec<String> = flags.iter().map(|f| f.trim_start_matches('\\').to_string()).collect();
+                        let mut mirrored = Ok(());
+                        for &uid in &group.uids {
+                            mirrored = cache.update_email_flags(&group.folder, uid, &cached, &account.email_address).await;
+                            if mirrored.is_err() {
+                                break;
+                            }
+                        }
+                        mirrored
+                    }
+                };
+                if let Err(e) = mirrored {
+                    warn!("Failed to update cached flags for {} emails in {}: {}", group.uids.len(), group.folder, e);
+                }
+            }
+        }
+
+        info!(
+            "Bulk flag change for account {}: {} of {} folders succeeded",
+            account_id, merged.iter().filter(|g| g.success).count(), merged.len()
+        );
+        Ok(merged)
+    }
+
+    /// Run a server-side SEARCH in `folder` and set \Seen on every match in
+    /// one STORE, mirroring the change into the cache. Returns the matched
+    /// UIDs, ascending; with `dry_run` nothing is stored.
+    pub async fn mark_read_matching_for_account(
+        &self,
+        folder: &str,
+        criteria: &SearchCriteria,
+        dry_run: bool,
+        account_id: &str,
+    ) -> Result<Vec<u32>, EmailServiceError> {
+        debug!("Marking emails matching {} in {} as read for account {} (dry_run={})", criteria, folder, account_id, dry_run);
+
+        let account = self.get_account(account_id).await?;
+        let client = self.create_background_session(&account, account_id, "mark_read_matching").await?;
+
+        let result = async {
+            client.select_folder(folder).await?;
+            let mut uids = client.search_emails(&criteria.to_string()).await?;
+            uids.sort_unstable();
+            uids.dedup();
+            if !dry_run && !uids.is_empty() {
+                client.store_flags(&uids, FlagOperation::Add, &["\\Seen".to_string()]).await?;
+            }
+            Ok::<_, ImapError>(uids)
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+        let uids = result?;
+
+        if !dry_run && !uids.is_empty() {
+            if let Some(cache) = &self.cache_service {
+                if let Err(e) = cache.set_cached_flag(folder, &uids, "Seen", true, &account.email_address).await {
+                    warn!("Failed to update cached flags for {} emails in {}: {}", uids.len(), folder, e);
+                }
+            }
+        }
+
+        info!("{} {} emails matching search in {} as read", if dry_run { "Would mark" } else { "Marked" }, uids.len(), folder);
+        Ok(uids)
+    }
+
+    /// Split `uids` into those still on the server and those that are gone,
+    /// using one UID FETCH FLAGS (the server silently skips missing UIDs).
+    /// Also returns the folder's UIDVALIDITY, since UIDs from an older
+    /// validity epoch may match unrelated messages.
+    pub async fn check_uids_exist_for_account(
+        &self,
+        folder: &str,
+        uids: &[u32],
+        account_id: &str,
+    ) -> Result<(Vec<u32>, Vec<u32>, Option<u32>), EmailServiceError> {
+        debug!("Checking {} UIDs in {} for account {}", uids.len(), folder, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let client = self.create_session_with_status(&account, account_id, "check_uids_exist").await?;
+
+        let result = async {
+            let mailbox = client.select_folder(folder).await?;
+            let (existing, missing) = Self::partition_existing(&client, uids).await?;
+            Ok::<_, ImapError>((existing, missing, mailbox.uid_validity))
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+        Ok(result?)
+    }
+
+    /// Which of `message_ids` the server has in `folder`. The ids are ORed
+    /// together `MESSAGE_ID_SEARCH_BATCH` at a time, one `UID SEARCH` per
+    /// batch over a single session, and the envelopes of the hits tell which
+    /// ids matched. Used to confirm messages the cache doesn't know about yet.
+    pub async fn find_message_ids_for_account(
+        &self,
+        folder: &str,
+        message_ids: &[String],
+        account_id: &str,
+    ) -> Result<Vec<String>, EmailServiceError> {
+        debug!("Searching {} for {} Message-IDs for account {}", folder, message_ids.len(), account_id);
+
+        let account = self.get_account(account_id).await?;
+        let client = self.create_background_session(&account, account_id, "find_message_ids").await?;
+
+        let result = async {
+            client.select_folder(folder).await?;
+            let mut found = Vec::new();
+            for batch in message_ids.chunks(MESSAGE_ID_SEARCH_BATCH) {
+                let uids = client.search_emails(&message_id_criteria(batch)).await?;
+                if uids.is_empty() {
+                    continue;
+                }
+                // HEADER matches substrings, so keep only exact Message-IDs
+                let present: std::collections::HashSet<String> = client.fetch_envelopes(&uids).await?
+                    .into_iter()
+                    .filter_map(|email| email.envelope.and_then(|envelope| envelope.message_id))
+                    .map(|id| bare_message_id(&id).to_string())
+                    .collect();
+                found.extend(batch.iter().filter(|id| present.contains(bare_message_id(id))).cloned());
+            }
+            Ok::<_, ImapError>(found)
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+        Ok(result?)
+    }
+
     /// Mark email(s) as deleted (sets \Deleted flag)
-    pub async fn mark_as_deleted(&self, folder: &str, uids: &[u32]) -> Result<(), EmailServiceError> {
+    pub async fn mark_as_deleted(&self, folder: &str, uids: &[u32]) -> Result<MutationOutcome, EmailServiceError> {
         debug!("Marking {} emails as deleted in {}", uids.len(), folder);
 
+        let account_key = self.default_account_key().await;
+        let _locks = self.lock_messages(&account_key, folder, uids).await;
+        let outcome = self.mark_as_deleted_locked(folder, uids).await?;
+
+        // Note: Cache will be invalidated naturally on next access
+        info!("Successfully marked {} emails as deleted ({} already gone)", outcome.applied.len(), outcome.already_gone.len());
+        Ok(outcome)
+    }
+
+    /// `mark_as_deleted` body; the caller holds the messages' mutation locks.
+    async fn mark_as_deleted_locked(&self, folder: &str, uids: &[u32]) -> Result<MutationOutcome, EmailServiceError> {
         let client = self.imap_factory.create_session().await
             .map_err(|e| EmailServiceError::ConnectionError(format!("Failed to create session: {}", e)))?;
 
-        client.select_folder(folder).await?;
-        client.mark_as_deleted(uids).await?;
+        let result = async {
+            client.select_folder(folder).await?;
+            let (present, already_gone) = Self::partition_existing(&client, uids).await?;
+            if !present.is_empty() {
+                client.mark_as_deleted(&present).await?;
+            }
+            Ok::<_, ImapError>(MutationOutcome { applied: present, already_gone })
+        }.await;
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
         if let Err(e) = client.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
-
-        // Note: Cache will be invalidated naturally on next access
-        info!("Successfully marked {} emails as deleted", uids.len());
-        Ok(())
+        Ok(result?)
     }
 
     /// Permanently delete messages (mark as deleted and expunge)
-    pub async fn delete_messages(&self, folder: &str, uids: &[u32]) -> Result<(), EmailServiceError> {
+    pub async fn delete_messages(&self, folder: &str, uids: &[u32]) -> Result<MutationOutcome, EmailServiceError> {
         debug!("Deleting {} messages in {}", uids.len(), folder);
 
+        let account_key = self.default_account_key().await;
+        let _locks = self.lock_messages(&account_key, folder, uids).await;
+
         // First mark as deleted
-        self.mark_as_deleted(folder, uids).await?;
+        let outcome = self.mark_as_deleted_locked(folder, uids).await?;
 
         // Then expunge
-        self.expunge(folder).await?;
+        if !outcome.applied.is_empty() {
+            self.expunge(folder).await?;
+        }
 
-        info!("Successfully deleted {} messages", uids.len());
-        Ok(())
+        info!("Successfully deleted {} messages ({} already gone)", outcome.applied.len(), outcome.already_gone.len());
+        Ok(outcome)
     }
 
     /// Permanently delete messages for a specific account with attachment cleanup
@@ -549,17 +1869,18 @@ impl EmailService {
         folder: &str,
         uids: &[u32],
         account_id: &str,
-    ) -> Result<(), EmailServiceError> {
+    ) -> Result<MutationOutcome, EmailServiceError> {
         debug!("Deleting {} messages in {} for account {} with attachment cleanup",
                uids.len(), folder, account_id);
 
         if uids.is_empty() {
-            return Ok(());
+            return Ok(MutationOutcome::default());
         }
 
         // Get account
         let account = self.get_account(account_id).await?;
         let account_email = &account.email_address;
+        let _locks = self.lock_messages(account_email, folder, uids).await;
 
         // Get database pool for attachment cleanup
         let db_pool = self.cache_service.as_ref()
@@ -591,17 +1912,178 @@ impl EmailService {
         // Delete emails from IMAP with connection status recording
         let client = self.create_session_with_status(&account, account_id, "delete").await?;
 
-        client.select_folder(folder).await?;
-        client.mark_as_deleted(uids).await?;
-        client.expunge().await?;
+        let result = async {
+            client.select_folder(folder).await?;
+            let (present, already_gone) = Self::partition_existing(&client, uids).await?;
+            if !present.is_empty() {
+                client.mark_as_deleted(&present).await?;
+                client.expunge().await?;
+            }
+            Ok::<_, ImapError>(MutationOutcome { applied: present, already_gone })
+        }.await;
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
         if let Err(e) = client.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
+        let outcome = result?;
 
-        info!("Successfully deleted {} messages with attachments for account {}", uids.len(), account_id);
-        Ok(())
+        info!("Successfully deleted {} messages with attachments for account {} ({} already gone)",
+              outcome.applied.len(), account_id, outcome.already_gone.len());
+        Ok(outcome)
+    }
+
+    /// Move messages to the account's Trash folder, found by special-use
+    /// attribute or provider naming. Without a Trash folder the messages are
+    /// only flagged \Deleted, left for an expunge or undelete. Returns the
+    /// Trash folder used, if any.
+    pub async fn move_to_trash_for_account(
+        &self,
+        folder: &str,
+        uids: &[u32],
+        account_id: &str,
+    ) -> Result<(Option<String>, MutationOutcome), EmailServiceError> {
+        debug!("Moving {} messages in {} to Trash for account {}", uids.len(), folder, account_id);
+
+        let account = self.get_account(account_id).await?;
+        let trash = self.special_folders_for_account(account_id, false).await?
+            .remove(&SpecialUse::Trash);
+        if trash.as_deref().is_some_and(|trash| trash.eq_ignore_ascii_case(folder)) {
+            return Err(ImapError::Validation(format!(
+                "Messages are already in {}; delete them to remove them permanently", folder
+            )).into());
+        }
+
+        let _locks = self.lock_messages(&account.email_address, folder, uids).await;
+        let client = self.create_session_with_status(&account, account_id, "move to trash").await?;
+
+        let result = async {
+            client.select_folder(folder).await?;
+            let (present, already_gone) = Self::partition_existing(&client, uids).await?;
+            if !present.is_empty() {
+                match trash.as_deref() {
+                    Some(trash) => {
+                        let moving = present.as_slice();
+                        client.with_session("move to trash", client.timeouts().fetch, |session| async move {
+                            let atomic_ops = crate::imap::atomic::AtomicImapOperations::new((*session).clone());
+                            atomic_ops.atomic_batch_move(moving, folder, trash).await?;
+                            Ok(())
+                        }).await?;
+                    }
+                    None => client.mark_as_deleted(&present).await?,
+                }
+            }
+            Ok::<_, ImapError>(MutationOutcome { applied: present, already_gone })
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+        let outcome = result?;
+
+        if trash.is_some() && !outcome.applied.is_empty() {
+            if let Some(cache) = &self.cache_service {
+                if let Err(e) = cache.delete_emails_by_uids(folder, &outcome.applied, &account.email_address).await {
+                    warn!("Failed to drop trashed emails from the {} cache: {}", folder, e);
+                }
+            }
+        }
+
+        match &trash {
+            Some(trash) => info!("Moved {} messages from {} to {} for account {}", outcome.applied.len(), folder, trash, account_id),
+            None => warn!("No Trash folder for account {}; flagged {} messages in {} as deleted", account_id, outcome.applied.len(), folder),
+        }
+        Ok((trash, outcome))
+    }
+
+    /// Permanently delete every message in a folder, or only those received
+    /// more than `older_than_days` ago, then bring the cache in line.
+    /// Returns the purged UIDs and the number of messages left.
+    pub async fn empty_folder_for_account(
+        &self,
+        folder: &str,
+        older_than_days: Option<u32>,
+        account_id: &str,
+    ) -> Result<(Vec<u32>, u32), EmailServiceError> {
+        info!("Emptying folder {} for account {} (older than: {:?} days)", folder, account_id, older_than_days);
+
+        let account = self.get_account(account_id).await?;
+        let client = self.create_background_session(&account, account_id, "empty_folder").await?;
+
+        // BEFORE compares dates only, so use the cutoff's own date: nothing
+        // newer than the cutoff is ever purged
+        let criteria = match older_than_days {
+            Some(days) => format!("BEFORE {}", crate::imap::dates::format_imap_date(
+                (chrono::Utc::now() - chrono::Duration::days(days as i64)).date_naive()
+            )),
+            None => "ALL".to_string(),
+        };
+
+        let result = async {
+            client.select_folder(folder).await?;
+            let uids = client.search_emails(&criteria).await?;
+            if uids.is_empty() {
+                let mailbox = client.select_folder(folder).await?;
+                return Ok::<_, ImapError>((uids, mailbox));
+            }
+            let _locks = self.lock_messages(&account.email_address, folder, &uids).await;
+            if older_than_days.is_some() {
+                // Only the matched messages: a folder-wide EXPUNGE would also
+                // remove newer ones someone else flagged \Deleted
+                if !client.capabilities().is_none_or(|caps| caps.supports_uidplus()) {
+                    return Err(ImapError::Unsupported("UID EXPUNGE (UIDPLUS), needed to purge only older messages".to_string()));
+                }
+                client.mark_as_deleted(&uids).await?;
+                client.uid_expunge(&uids).await?;
+            } else {
+                client.mark_as_deleted(&uids).await?;
+                client.expunge().await?;
+            }
+            // Re-select for the counts left after the expunge
+            let mailbox = client.select_folder(folder).await?;
+            Ok((uids, mailbox))
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = client.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+        let (purged, mailbox) = result?;
+
+        if let Some(cache) = &self.cache_service {
+            if !purged.is_empty() {
+                // Attachment rows are keyed by Message-ID, which only the cache still has
+                if let Some(pool) = cache.db_pool.as_ref() {
+                    for uid in &purged {
+                        let message_id = match cache.get_email_by_uid_for_account(folder, *uid, &account.email_address).await {
+                            Ok(Some(email)) => email.message_id,
+                            _ => None,
+                        };
+                        if let Some(message_id) = message_id {
+                            if let Err(e) = attachment_storage::delete_attachments_for_email(pool, &message_id, &account.email_address).await {
+                                warn!("Failed to delete attachments for purged UID {}: {}", uid, e);
+                            }
+                        }
+                    }
+                }
+            }
+
+            let cache_result = if older_than_days.is_none() {
+                cache.clear_folder_cache(folder, &account.email_address).await
+            } else {
+                cache.delete_emails_by_uids(folder, &purged, &account.email_address).await
+            };
+            if let Err(e) = cache_result {
+                warn!("Failed to update cache after emptying {}: {}", folder, e);
+            }
+            if let Err(e) = cache.record_mailbox_counts(folder, &account.email_address, mailbox.exists, mailbox.unseen, mailbox.recent).await {
+                warn!("Failed to record counts for {}: {}", folder, e);
+            }
+        }
+
+        info!("Purged {} messages from {} for account {}, {} left", purged.len(), folder, account_id, mailbox.exists);
+        Ok((purged, mailbox.exists))
     }
 
     /// Remove \Deleted flag from messages
@@ -642,9 +2124,12 @@ impl EmailService {
         Ok(())
     }
 
-    /// Create a new folder for a specific account
-    pub async fn create_folder_for_account(&self, name: &str, account_id: &str) -> Result<(), EmailServiceError> {
-        debug!("Creating folder '{}' for account {}", name, account_id);
+    /// Create a folder for a specific account, inside `parent` if given.
+    /// The name is built with the account's namespace delimiter, and a
+    /// top-level name goes under the personal namespace prefix (`INBOX.` on
+    /// some servers). Returns the full name created.
+    pub async fn create_folder_for_account(&self, name: &str, parent: Option<&str>, account_id: &str) -> Result<String, EmailServiceError> {
+        debug!("Creating folder '{}' (parent {:?}) for account {}", name, parent, account_id);
 
         // Get account credentials
         let account = self.get_account(account_id).await?;
@@ -652,16 +2137,24 @@ impl EmailService {
         // Create session with account-specific credentials and record connection status
         let session = self.create_session_with_status(&account, account_id, "create folder").await?;
 
-        // Create the folder
-        session.create_folder(name).await?;
+        let result = async {
+            let namespaces = namespace::resolve(&session, &account.email_address).await?;
+            let full_name = match parent {
+                Some(parent) => namespaces.child_name(parent, name)?,
+                None => namespaces.qualify(name),
+            };
+            session.create_folder(&full_name).await?;
+            Ok::<_, ImapError>(full_name)
+        }.await;
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
         if let Err(e) = session.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
 
-        info!("Successfully created folder '{}' for account {}", name, account_id);
-        Ok(())
+        let full_name = result?;
+        info!("Successfully created folder '{}' for account {}", full_name, account_id);
+        Ok(full_name)
     }
 
     /// Delete a folder for a specific account
@@ -676,6 +2169,7 @@ impl EmailService {
 
         // Delete the folder
         session.delete_folder(name).await?;
+        special_use::invalidate(&account.email_address);
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
         if let Err(e) = session.logout().await {
@@ -686,8 +2180,10 @@ impl EmailService {
         Ok(())
     }
 
-    /// Rename a folder for a specific account
-    pub async fn rename_folder_for_account(&self, old_name: &str, new_name: &str, account_id: &str) -> Result<(), EmailServiceError> {
+    /// Rename a folder for a specific account. A `new_name` without the
+    /// account's namespace delimiter keeps the folder under its current
+    /// parent; one with it is used as the full name. Returns the new full name.
+    pub async fn rename_folder_for_account(&self, old_name: &str, new_name: &str, account_id: &str) -> Result<String, EmailServiceError> {
         debug!("Renaming folder '{}' to '{}' for account {}", old_name, new_name, account_id);
 
         // Get account credentials
@@ -696,16 +2192,22 @@ impl EmailService {
         // Create session with account-specific credentials and record connection status
         let session = self.create_session_with_status(&account, account_id, "rename folder").await?;
 
-        // Rename the folder
-        session.rename_folder(old_name, new_name).await?;
+        let result = async {
+            let namespaces = namespace::resolve(&session, &account.email_address).await?;
+            let full_name = namespaces.rename_target(old_name, new_name)?;
+            session.rename_folder(old_name, &full_name).await?;
+            Ok::<_, ImapError>(full_name)
+        }.await;
+        special_use::invalidate(&account.email_address);
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
         if let Err(e) = session.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
 
-        info!("Successfully renamed folder '{}' to '{}' for account {}", old_name, new_name, account_id);
-        Ok(())
+        let full_name = result?;
+        info!("Successfully renamed folder '{}' to '{}' for account {}", old_name, full_name, account_id);
+        Ok(full_name)
     }
 
     /// Fetch a single email with full body and save its attachments
@@ -811,4 +2313,133 @@ impl EmailService {
         info!("Fetched email {} with {} attachments for account {}", uid, attachment_infos.len(), account_id);
         Ok((email, attachment_infos))
     }
+
+    /// Transfer messages from one account's folder to another account's folder.
+    ///
+    /// Each message is fetched raw (BODY.PEEK[]) from the source and APPENDed to
+    /// the destination together with its flags and INTERNALDATE. A message
+    /// counts as transferred once the destination has acknowledged the APPEND.
+    /// With `delete_source`, only transferred messages are removed from the
+    /// source, by UID EXPUNGE, so other messages already flagged \Deleted
+    /// there stay; a server without UIDPLUS keeps every source message.
+    pub async fn transfer_messages(
+        &self,
+        source_account_id: &str,
+        source_folder: &str,
+        uids: &[u32],
+        dest_account_id: &str,
+        dest_folder: &str,
+        delete_source: bool,
+    ) -> Result<TransferResult, EmailServiceError> {
+        info!("Transferring {} messages from {}:{} to {}:{} (delete_source: {})",
+              uids.len(), source_account_id, source_folder, dest_account_id, dest_folder, delete_source);
+
+        let source_account = self.get_account(source_account_id).await?;
+        let dest_account = self.get_account(dest_account_id).await?;
+
+        let source = self.create_background_session(&source_account, source_account_id, "transfer").await?;
+        let dest = match self.create_background_session(&dest_account, dest_account_id, "transfer").await {
+            Ok(session) => session,
+            Err(e) => {
+                if let Err(e) = source.logout().await {
+                    warn!("Failed to logout IMAP session: {}", e);
+                }
+                return Err(e);
+            }
+        };
+
+        let result = Self::transfer_between_sessions(&source, &dest, source_folder, uids, dest_folder, delete_source).await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        for session in [&source, &dest] {
+            if let Err(e) = session.logout().await {
+                warn!("Failed to logout IMAP session: {}", e);
+            }
+        }
+
+        let result = result?;
+
+        if result.deleted_from_source {
+            if let Some(cache) = &self.cache_service {
+                if let Err(e) = cache.delete_emails_by_uids(source_folder, &result.transferred, &source_account.email_address).await {
+                    warn!("Failed to remove transferred emails from cache: {}", e);
+                }
+            }
+        }
+
+        info!("Transferred {} of {} messages ({} failed)", result.transferred.len(), uids.len(), result.failed.len());
+        Ok(result)
+    }
+
+    async fn transfer_between_sessions(
+        source: &crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>,
+        dest: &crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>,
+        source_folder: &str,
+        uids: &[u32],
+        dest_folder: &str,
+        delete_source: bool,
+    ) -> Result<TransferResult, EmailServiceError> {
+        source.select_folder(source_folder).await?;
+        // Flags and INTERNALDATE go along with each message, set by the APPEND itself
+        let metadata: HashMap<u32, (Vec<String>, Option<chrono::DateTime<chrono::Utc>>)> = source.fetch_envelopes(uids).await?
+            .into_iter()
+            .map(|email| (email.uid, (email.flags, email.internal_date)))
+            .collect();
+
+        let mut result = TransferResult {
+            transferred: Vec::new(),
+            failed: Vec::new(),
+            deleted_from_source: false,
+            delete_error: None,
+        };
+
+        for &uid in uids {
+            let raw = match source.fetch_raw_message(uid).await {
+                Ok(raw) => raw,
+                Err(e) => {
+                    result.failed.push(FetchFailure { uid, reason: format!("Fetch from source failed: {}", e) });
+                    continue;
+                }
+            };
+            let (flags, internal_date) = match metadata.get(&uid) {
+                Some((flags, internal_date)) => (
+                    flags.iter().filter_map(|f| imap_flag_syntax(f)).collect::<Vec<String>>(),
+                    *internal_date,
+                ),
+                None => (Vec::new(), None),
+            };
+
+            // A tagged OK means the server has stored the message, so the
+            // source copy may go
+            if let Err(e) = dest.append_with_flags(dest_folder, &raw, &flags, internal_date).await {
+                result.failed.push(FetchFailure { uid, reason: format!("Append to destination failed: {}", e) });
+                continue;
+            }
+
+            result.transferred.push(uid);
+        }
+
+        if delete_source && !result.transferred.is_empty() {
+            let delete: Result<(), ImapError> = async {
+                // A folder-wide EXPUNGE would also remove unrelated \Deleted mail
+                if !source.capabilities().is_none_or(|caps| caps.supports_uidplus()) {
+                    return Err(ImapError::Unsupported(
+                        "UID EXPUNGE (UIDPLUS); transferred messages were left in the source".to_string()
+                    ));
+                }
+                source.select_folder(source_folder).await?;
+                source.mark_as_deleted(&result.transferred).await?;
+                source.uid_expunge(&result.transferred).await
+            }.await;
+            match delete {
+                Ok(()) => result.deleted_from_source = true,
+                Err(e) => {
+                    error!("Transferred messages could not be removed from source {}: {}", source_folder, e);
+                    result.delete_error = Some(e.to_string());
+                }
+            }
+        }
+
+        Ok(result)
+    }
 }
\ No newline at end of file
@@ -77,6 +77,32 @@ pub enum DashboardEvent {
         timestamp: DateTime<Utc>,
     },
 
+    // Mailbox events (persisted to the per-account activity log)
+    MailboxActivity {
+        account_id: String,
+        action: MailboxAction,
+        folder: Option<String>,
+        uids: Vec<u32>,
+        detail: Option<String>,
+        /// What triggered the change: "sync", "mcp", "dashboard", ...
+        source: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    // Sync events, one per account per sync pass
+    SyncCompleted {
+        account_id: String,
+        folders_synced: usize,
+        /// Folders that failed while the rest of the account synced
+        failed_folders: Vec<String>,
+        timestamp: DateTime<Utc>,
+    },
+    SyncFailed {
+        account_id: String,
+        error: String,
+        timestamp: DateTime<Utc>,
+    },
+
     // System events
     SystemAlert {
         level: AlertLevel,
@@ -119,6 +145,28 @@ pub enum ConfigSection {
     Mcp,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailboxAction {
+    Received,
+    Sent,
+    Moved,
+    Deleted,
+    Flagged,
+}
+
+impl MailboxAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MailboxAction::Received => "received",
+            MailboxAction::Sent => "sent",
+            MailboxAction::Moved => "moved",
+            MailboxAction::Deleted => "deleted",
+            MailboxAction::Flagged => "flagged",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AlertLevel {
@@ -305,6 +353,43 @@ impl EventBus {
         }).await;
     }
 
+    pub async fn publish_mailbox_activity(
+        &self,
+        account_id: &str,
+        action: MailboxAction,
+        folder: Option<String>,
+        uids: Vec<u32>,
+        detail: Option<String>,
+        source: &str,
+    ) {
+        self.publish(DashboardEvent::MailboxActivity {
+            account_id: account_id.to_string(),
+            action,
+            folder,
+            uids,
+            detail,
+            source: source.to_string(),
+            timestamp: Utc::now(),
+        }).await;
+    }
+
+    pub async fn publish_sync_completed(&self, account_id: &str, folders_synced: usize, failed_folders: Vec<String>) {
+        self.publish(DashboardEvent::SyncCompleted {
+            account_id: account_id.to_string(),
+            folders_synced,
+            failed_folders,
+            timestamp: Utc::now(),
+        }).await;
+    }
+
+    pub async fn publish_sync_failed(&self, account_id: &str, error: String) {
+        self.publish(DashboardEvent::SyncFailed {
+            account_id: account_id.to_string(),
+            error,
+            timestamp: Utc::now(),
+        }).await;
+    }
+
     pub async fn publish_configuration_updated(
         &self,
         section: ConfigSection,
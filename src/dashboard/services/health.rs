@@ -9,6 +9,7 @@
 // resource monitoring, and alerting capabilities.
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::RwLock;
 use tokio::time::{interval, Duration, Instant};
 use sysinfo::{System, RefreshKind, CpuRefreshKind, MemoryRefreshKind};
@@ -19,10 +20,12 @@ use std::collections::HashMap;
 use crate::dashboard::services::{EventBus, DashboardEvent};
 use crate::dashboard::services::events::{AlertLevel, ConfigSection};
 use crate::dashboard::api::models::{SystemHealth, SystemStatus};
-use crate::connection_pool::{ConnectionPool, PoolStats};
+use crate::connection_pool::{CircuitState, ConnectionPool, PoolStats};
+use crate::dashboard::services::account::{Account, AccountService};
 use crate::session_manager::SessionManager;
 use crate::config::Settings;
 use reqwest::Client;
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
 
 // Health check result for individual components
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,8 +54,47 @@ pub struct HealthReport {
     pub uptime_seconds: u64,
     pub last_updated: DateTime<Utc>,
     pub alerts: Vec<HealthAlert>,
+    #[serde(default)]
+    pub smtp: Vec<SmtpProbeStatus>,
 }
 
+// Result of the latest SMTP probe of one account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpProbeStatus {
+    pub account: String,
+    pub host: String,
+    pub port: u16,
+    // Whether the server answered at all
+    pub reachable: bool,
+    // "starttls" or "implicit"
+    pub tls_mode: String,
+    // Whether TLS was negotiated; None when the probe failed before it could tell
+    pub tls_negotiated: Option<bool>,
+    pub error: Option<String>,
+    pub response_time_ms: u64,
+    pub last_check: DateTime<Utc>,
+    pub last_success: Option<DateTime<Utc>>,
+}
+
+impl SmtpProbeStatus {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+// Seconds between SMTP probes (HEALTH_SMTP_PROBE_INTERVAL_SECONDS, default
+// 300; 0 turns probing off)
+fn smtp_probe_interval() -> Duration {
+    Duration::from_secs(
+        std::env::var("HEALTH_SMTP_PROBE_INTERVAL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300)
+    )
+}
+
+const SMTP_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
 // Resource health metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceHealth {
@@ -116,6 +158,33 @@ impl Default for HealthThresholds {
     }
 }
 
+// Result of a single startup readiness gate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessCheck {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+// Readiness report returned by the /readyz probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+impl ReadinessReport {
+    fn from_checks(checks: Vec<ReadinessCheck>) -> Self {
+        let ready = checks.iter().all(|c| c.passed);
+        Self { ready, checks }
+    }
+
+    // Names of the gates that have not passed yet
+    pub fn failing(&self) -> Vec<&str> {
+        self.checks.iter().filter(|c| !c.passed).map(|c| c.name.as_str()).collect()
+    }
+}
+
 // Main health monitoring service
 pub struct HealthService {
     components: Arc<RwLock<HashMap<String, ComponentHealth>>>,
@@ -125,8 +194,13 @@ pub struct HealthService {
     event_bus: Option<Arc<EventBus>>,
     connection_pool: Option<Arc<ConnectionPool>>,
     session_manager: Option<Arc<SessionManager>>,
+    account_service: Option<Arc<tokio::sync::Mutex<AccountService>>>,
+    smtp_probes: Arc<RwLock<HashMap<String, SmtpProbeStatus>>>,
+    smtp_probe_interval: Duration,
     http_client: Client,
     last_alerts: Arc<RwLock<Vec<HealthAlert>>>,
+    migrations_complete: AtomicBool,
+    allow_unhealthy_accounts: bool,
 }
 
 impl HealthService {
@@ -148,11 +222,19 @@ impl HealthService {
             event_bus: None,
             connection_pool: None,
             session_manager: None,
+            account_service: None,
+            smtp_probes: Arc::new(RwLock::new(HashMap::new())),
+            smtp_probe_interval: smtp_probe_interval(),
             http_client: Client::builder()
                 .timeout(Duration::from_secs(5))
                 .build()
                 .unwrap_or_default(),
             last_alerts: Arc::new(RwLock::new(Vec::new())),
+            migrations_complete: AtomicBool::new(false),
+            allow_unhealthy_accounts: std::env::var("READINESS_ALLOW_UNHEALTHY_ACCOUNTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
         }
     }
 
@@ -176,6 +258,22 @@ impl HealthService {
         self
     }
 
+    // Accounts whose SMTP servers are probed
+    pub fn with_account_service(mut self, account_service: Arc<tokio::sync::Mutex<AccountService>>) -> Self {
+        self.account_service = Some(account_service);
+        self
+    }
+
+    pub fn with_smtp_probe_interval(mut self, interval: Duration) -> Self {
+        self.smtp_probe_interval = interval;
+        self
+    }
+
+    // Record that database migrations have been applied; gates readiness
+    pub fn mark_migrations_complete(&self) {
+        self.migrations_complete.store(true, Ordering::SeqCst);
+    }
+
     // Start background health monitoring
     pub async fn start_monitoring(self: Arc<Self>) {
         let health_service = Arc::clone(&self);
@@ -194,9 +292,99 @@ impl HealthService {
             }
         });
 
+        if self.account_service.is_some() && !self.smtp_probe_interval.is_zero() {
+            let health_service = Arc::clone(&self);
+            tokio::spawn(async move {
+                let mut interval = interval(health_service.smtp_probe_interval);
+                loop {
+                    interval.tick().await;
+                    health_service.probe_smtp_accounts().await;
+                }
+            });
+            info!("SMTP probes every {:?}", self.smtp_probe_interval);
+        }
+
         info!("Started health monitoring service");
     }
 
+    // Probe every account's SMTP server and forget accounts that are gone
+    pub async fn probe_smtp_accounts(&self) {
+        let Some(account_service) = &self.account_service else {
+            return;
+        };
+        let accounts = match account_service.lock().await.list_accounts().await {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                warn!("SMTP probe could not list accounts: {}", e);
+                return;
+            }
+        };
+        let accounts: Vec<Account> = accounts.into_iter().filter(|a| a.smtp_host.is_some()).collect();
+
+        {
+            let mut probes = self.smtp_probes.write().await;
+            probes.retain(|email, _| accounts.iter().any(|a| &a.email_address == email));
+            let mut components = self.components.write().await;
+            components.retain(|name, _| match name.strip_prefix("smtp:") {
+                Some(email) => accounts.iter().any(|a| a.email_address == email),
+                None => true,
+            });
+        }
+
+        for account in &accounts {
+            let status = probe_smtp(account).await;
+            self.record_smtp_probe(status).await;
+        }
+    }
+
+    // Store a probe result as the account's `smtp:<account>` component. A
+    // failure marks it Degraded and raises a SystemAlert when the previous
+    // probe had passed (or there was none), so a server that stays down
+    // alerts once rather than on every probe.
+    async fn record_smtp_probe(&self, mut status: SmtpProbeStatus) {
+        let previous = self.smtp_probes.read().await.get(&status.account).cloned();
+        if status.is_ok() {
+            status.last_success = Some(status.last_check);
+        } else {
+            status.last_success = previous.as_ref().and_then(|p| p.last_success);
+        }
+
+        let message = match &status.error {
+            None => format!("SMTP {}:{} reachable ({})", status.host, status.port, status.tls_mode),
+            Some(error) => format!("SMTP {}:{} failed: {}", status.host, status.port, error),
+        };
+        let component = format!("smtp:{}", status.account);
+        self.components.write().await.insert(component.clone(), ComponentHealth {
+            name: component.clone(),
+            status: if status.is_ok() { HealthStatus::Healthy } else { HealthStatus::Degraded },
+            message: Some(message.clone()),
+            last_check: status.last_check,
+            response_time_ms: Some(status.response_time_ms),
+        });
+
+        let newly_failing = !status.is_ok() && !previous.as_ref().is_some_and(|p| !p.is_ok());
+        if newly_failing {
+            warn!("{} ({})", message, status.account);
+            if let Some(event_bus) = &self.event_bus {
+                event_bus.publish_system_alert(
+                    AlertLevel::Warning,
+                    format!("Outgoing mail for {} may not be delivered: {}", status.account, message),
+                    Some(serde_json::json!({
+                        "component": component,
+                        "account": status.account,
+                        "reachable": status.reachable,
+                        "tls_negotiated": status.tls_negotiated,
+                        "last_success": status.last_success,
+                    })),
+                ).await;
+            }
+        } else if status.is_ok() && previous.as_ref().is_some_and(|p| !p.is_ok()) {
+            info!("{} ({}), recovered", message, status.account);
+        }
+
+        self.smtp_probes.write().await.insert(status.account.clone(), status);
+    }
+
     // Check all system components
     async fn check_all_components(&self) {
         // Check IMAP connection pool
@@ -226,23 +414,35 @@ impl HealthService {
     async fn check_connection_pool(&self, pool: &Arc<ConnectionPool>) {
         let start = Instant::now();
         let stats = pool.stats().await;
+        let pool_status = pool.pool_status();
         let response_time = start.elapsed().as_millis() as u64;
 
-        let status = if stats.acquire_timeouts > 10 {
+        let status = if pool_status.circuit == CircuitState::Open || stats.acquire_timeouts > 10 {
             HealthStatus::Unhealthy
-        } else if stats.active_connections > self.thresholds.connection_pool_warning {
+        } else if pool_status.circuit == CircuitState::HalfOpen
+            || stats.active_connections > self.thresholds.connection_pool_warning {
             HealthStatus::Degraded
         } else {
             HealthStatus::Healthy
         };
 
+        let mut message = format!(
+            "Active: {}, Available: {}, Timeouts: {}",
+            stats.active_connections, stats.available_connections, stats.acquire_timeouts
+        );
+        match pool_status.circuit {
+            CircuitState::Open => message.push_str(&format!(
+                ", circuit open after {} failures (retry in {}s)",
+                pool_status.consecutive_failures, pool_status.retry_after_seconds.unwrap_or(0)
+            )),
+            CircuitState::HalfOpen => message.push_str(", circuit half-open (probing)"),
+            CircuitState::Closed => {}
+        }
+
         let health = ComponentHealth {
             name: "connection_pool".to_string(),
             status,
-            message: Some(format!(
-                "Active: {}, Available: {}, Timeouts: {}",
-                stats.active_connections, stats.available_connections, stats.acquire_timeouts
-            )),
+            message: Some(message),
             last_check: Utc::now(),
             response_time_ms: Some(response_time),
         };
@@ -461,6 +661,8 @@ impl HealthService {
         let components = self.components.read().await.clone();
         let resources = self.get_resource_health().await;
         let alerts = self.last_alerts.read().await.clone();
+        let mut smtp: Vec<SmtpProbeStatus> = self.smtp_probes.read().await.values().cloned().collect();
+        smtp.sort_by(|a, b| a.account.cmp(&b.account));
 
         // Determine overall status based on components
         let overall_status = if components.values().any(|c| c.status == HealthStatus::Unhealthy) {
@@ -478,6 +680,7 @@ impl HealthService {
             uptime_seconds: self.start_time.elapsed().as_secs(),
             last_updated: Utc::now(),
             alerts,
+            smtp,
         }
     }
 
@@ -486,22 +689,111 @@ impl HealthService {
         true
     }
 
-    // Readiness check (checks if all critical components are healthy)
+    // Readiness check (startup gates passed and no critical component unhealthy)
     pub async fn readiness(&self) -> bool {
-        let components = self.components.read().await;
+        self.readiness_report().await.ready
+    }
 
-        // Check critical components
-        let critical = ["connection_pool", "session_manager"];
+    // Evaluate each readiness gate individually so probes can report what is missing
+    pub async fn readiness_report(&self) -> ReadinessReport {
+        let mut checks = Vec::new();
 
-        for name in &critical {
-            if let Some(component) = components.get(*name) {
+        let migrated = self.migrations_complete.load(Ordering::SeqCst);
+        checks.push(ReadinessCheck {
+            name: "migrations".to_string(),
+            passed: migrated,
+            message: if migrated { "Database migrations applied" } else { "Database migrations not yet applied" }.to_string(),
+        });
+
+        match &self.connection_pool {
+            Some(pool) => {
+                let stats = pool.stats().await;
+                let connected = stats.total_created > 0;
+                let (passed, message) = if connected {
+                    (true, "At least one account connection established".to_string())
+                } else if self.allow_unhealthy_accounts {
+                    (true, "No account connected (allowed by READINESS_ALLOW_UNHEALTHY_ACCOUNTS)".to_string())
+                } else {
+                    (false, format!("No account connection established ({} failed attempts)", stats.creation_failures))
+                };
+                checks.push(ReadinessCheck { name: "account_connected".to_string(), passed, message });
+
+                let warmed = pool.is_warmed();
+                checks.push(ReadinessCheck {
+                    name: "pools_warmed".to_string(),
+                    passed: warmed,
+                    message: if warmed { "Connection pool pre-warm complete" } else { "Connection pool still warming" }.to_string(),
+                });
+            }
+            None => {
+                checks.push(ReadinessCheck {
+                    name: "pools_warmed".to_string(),
+                    passed: true,
+                    message: "No connection pool configured".to_string(),
+                });
+            }
+        }
+
+        let components = self.components.read().await;
+        for name in ["connection_pool", "session_manager"] {
+            if let Some(component) = components.get(name) {
                 if component.status == HealthStatus::Unhealthy {
-                    return false;
+                    checks.push(ReadinessCheck {
+                        name: name.to_string(),
+                        passed: false,
+                        message: component.message.clone().unwrap_or_else(|| "Component unhealthy".to_string()),
+                    });
                 }
             }
         }
 
-        true
+        ReadinessReport::from_checks(checks)
+    }
+}
+
+// Connect to the account's SMTP server, negotiate TLS and EHLO, then NOOP
+// and QUIT. Nothing is authenticated or sent.
+async fn probe_smtp(account: &Account) -> SmtpProbeStatus {
+    let host = account.smtp_host.clone().unwrap_or_default();
+    let port = account.smtp_port.unwrap_or(587) as u16;
+    let use_starttls = account.smtp_use_starttls.unwrap_or(true);
+    let start = Instant::now();
+
+    let result = match super::smtp_auth::relay_builder(&host, use_starttls) {
+        Ok(builder) => {
+            let transport: AsyncSmtpTransport<Tokio1Executor> = builder
+                .port(port)
+                .timeout(Some(SMTP_PROBE_TIMEOUT))
+                .build();
+            Ok(transport.test_connection().await)
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    let (reachable, tls_negotiated, error) = match result {
+        Ok(Ok(true)) => (true, Some(true), None),
+        Ok(Ok(false)) => (true, Some(true), Some("server did not answer NOOP".to_string())),
+        Ok(Err(e)) if e.is_tls() => (true, Some(false), Some(crate::tls::handshake_error(&host, &e))),
+        // The server answered, but with an error
+        Ok(Err(e)) if e.is_response() || e.is_client() => (true, None, Some(e.to_string())),
+        Ok(Err(e)) => (false, None, Some(e.to_string())),
+        Err(e) => (false, None, Some(e)),
+    };
+    if error.is_none() {
+        crate::tls::log_established("SMTP", &host);
+    }
+
+    SmtpProbeStatus {
+        account: account.email_address.clone(),
+        host,
+        port,
+        reachable,
+        tls_mode: if use_starttls { "starttls" } else { "implicit" }.to_string(),
+        tls_negotiated,
+        error,
+        response_time_ms: start.elapsed().as_millis() as u64,
+        last_check: Utc::now(),
+        last_success: None,
     }
 }
 
@@ -537,6 +829,74 @@ mod tests {
         assert!(report.components.is_empty() || !report.components.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_readiness_waits_for_migrations() {
+        let service = HealthService::new();
+        let report = service.readiness_report().await;
+        assert!(!report.ready);
+        assert_eq!(report.failing(), vec!["migrations"]);
+
+        service.mark_migrations_complete();
+        assert!(service.readiness().await);
+    }
+
+    #[tokio::test]
+    async fn test_readiness_fails_on_unhealthy_critical_component() {
+        let service = HealthService::new();
+        service.mark_migrations_complete();
+        service.components.write().await.insert("connection_pool".to_string(), ComponentHealth {
+            name: "connection_pool".to_string(),
+            status: HealthStatus::Unhealthy,
+            message: Some("Pool exhausted".to_string()),
+            last_check: Utc::now(),
+            response_time_ms: None,
+        });
+
+        let report = service.readiness_report().await;
+        assert!(!report.ready);
+        assert_eq!(report.failing(), vec!["connection_pool"]);
+        assert!(service.liveness().await);
+    }
+
+    fn smtp_status(account: &str, error: Option<&str>) -> SmtpProbeStatus {
+        SmtpProbeStatus {
+            account: account.to_string(),
+            host: "smtp.example.com".to_string(),
+            port: 587,
+            reachable: error.is_none(),
+            tls_mode: "starttls".to_string(),
+            tls_negotiated: error.is_none().then_some(true),
+            error: error.map(str::to_string),
+            response_time_ms: 12,
+            last_check: Utc::now(),
+            last_success: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_smtp_probe_degrades_account_and_alerts_once() {
+        let event_bus = Arc::new(EventBus::new());
+        let service = HealthService::new().with_event_bus(Arc::clone(&event_bus));
+
+        service.record_smtp_probe(smtp_status("me@example.com", None)).await;
+        let report = service.get_health_report().await;
+        assert_eq!(report.components["smtp:me@example.com"].status, HealthStatus::Healthy);
+        let last_success = report.smtp[0].last_success.expect("success recorded");
+
+        service.record_smtp_probe(smtp_status("me@example.com", Some("connection refused"))).await;
+        service.record_smtp_probe(smtp_status("me@example.com", Some("connection refused"))).await;
+        let report = service.get_health_report().await;
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.components["smtp:me@example.com"].status, HealthStatus::Degraded);
+        assert_eq!(report.smtp[0].last_success, Some(last_success));
+        assert!(!report.smtp[0].reachable);
+
+        let alerts = event_bus.get_history(10).await.into_iter()
+            .filter(|e| matches!(e, DashboardEvent::SystemAlert { .. }))
+            .count();
+        assert_eq!(alerts, 1);
+    }
+
     #[tokio::test]
     async fn test_thresholds() {
         let thresholds = HealthThresholds::default();
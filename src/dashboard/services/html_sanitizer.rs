@@ -0,0 +1,322 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Sanitizing of email HTML for display.
+//!
+//! The default policy is ammonia's conservative allowlist with `style`
+//! attributes and remote images stripped. Deployments can loosen or tighten
+//! it from a JSON file (`HTML_SANITIZER_CONFIG`, default
+//! `config/html_sanitizer.json`), globally and per account. The file is
+//! re-read whenever its modification time changes, so edits apply to the
+//! next render without a restart:
+//!
+//! ```json
+//! {
+//!   "allow_style": false,
+//!   "allow_remote_images": false,
+//!   "extra_tags": ["font"],
+//!   "extra_attributes": ["color"],
+//!   "url_schemes": ["http", "https", "mailto"],
+//!   "accounts": {
+//!     "newsletters@example.com": { "allow_remote_images": true, "allow_style": true }
+//!   }
+//! }
+//! ```
+//!
+//! Active content can never be enabled: tags such as `script`, `iframe` and
+//! `form`, event-handler attributes and `srcdoc` are dropped from the config
+//! with a warning.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::SystemTime;
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+/// Tags that stay blocked whatever the config says.
+const NEVER_ALLOWED_TAGS: &[&str] = &[
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "applet",
+    "base", "meta", "link", "form", "input", "button", "textarea", "select", "svg", "math",
+];
+
+const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+fn default_url_schemes() -> Vec<String> {
+    DEFAULT_URL_SCHEMES.iter().map(|s| s.to_string()).collect()
+}
+
+/// Effective sanitizer settings for one render.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SanitizerPolicy {
+    /// Keep inline `style` attributes. Their CSS is not filtered.
+    pub allow_style: bool,
+    /// Keep `img` sources that load from the network (tracking pixels included)
+    pub allow_remote_images: bool,
+    /// Tags allowed on top of the default allowlist
+    pub extra_tags: Vec<String>,
+    /// Attributes allowed on any tag on top of the defaults
+    pub extra_attributes: Vec<String>,
+    /// URL schemes allowed in links and image sources; replaces the default
+    pub url_schemes: Vec<String>,
+}
+
+impl Default for SanitizerPolicy {
+    fn default() -> Self {
+        Self {
+            allow_style: false,
+            allow_remote_images: false,
+            extra_tags: Vec::new(),
+            extra_attributes: Vec::new(),
+            url_schemes: default_url_schemes(),
+        }
+    }
+}
+
+/// Per-account changes to the global policy; unset fields inherit it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PolicyOverride {
+    pub allow_style: Option<bool>,
+    pub allow_remote_images: Option<bool>,
+    pub extra_tags: Option<Vec<String>>,
+    pub extra_attributes: Option<Vec<String>>,
+    pub url_schemes: Option<Vec<String>>,
+}
+
+/// Contents of the sanitizer config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SanitizerConfig {
+    #[serde(flatten)]
+    pub global: SanitizerPolicy,
+    pub accounts: HashMap<String, PolicyOverride>,
+}
+
+impl SanitizerConfig {
+    pub fn policy_for(&self, account_id: Option<&str>) -> SanitizerPolicy {
+        let mut policy = self.global.clone();
+        let Some(overrides) = account_id.and_then(|id| {
+            self.accounts.iter().find(|(k, _)| k.eq_ignore_ascii_case(id)).map(|(_, v)| v)
+        }) else {
+            return policy;
+        };
+        if let Some(v) = overrides.allow_style {
+            policy.allow_style = v;
+        }
+        if let Some(v) = overrides.allow_remote_images {
+            policy.allow_remote_images = v;
+        }
+        if let Some(v) = &overrides.extra_tags {
+            policy.extra_tags = v.clone();
+        }
+        if let Some(v) = &overrides.extra_attributes {
+            policy.extra_attributes = v.clone();
+        }
+        if let Some(v) = &overrides.url_schemes {
+            policy.url_schemes = v.clone();
+        }
+        policy
+    }
+}
+
+/// Result of sanitizing one message body.
+#[derive(Debug, Clone, Serialize)]
+pub struct SanitizedHtml {
+    pub html: String,
+    pub remote_images_blocked: usize,
+    pub policy: SanitizerPolicy,
+}
+
+fn is_remote_url(value: &str) -> bool {
+    let lower = value.trim().to_ascii_lowercase();
+    lower.starts_with("http:") || lower.starts_with("https:") || lower.starts_with("//")
+}
+
+/// Sanitize `html` under `policy`. Returns the cleaned markup and how many
+/// remote image sources were removed.
+pub fn sanitize_with(policy: &SanitizerPolicy, html: &str) -> (String, usize) {
+    let extra_tags: Vec<String> = policy.extra_tags.iter()
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| {
+            let blocked = NEVER_ALLOWED_TAGS.contains(&t.as_str());
+            if blocked {
+                warn!("HTML sanitizer: ignoring disallowed tag '{}' in extra_tags", t);
+            }
+            !t.is_empty() && !blocked
+        })
+        .collect();
+    let mut extra_attributes: Vec<String> = policy.extra_attributes.iter()
+        .map(|a| a.trim().to_ascii_lowercase())
+        .filter(|a| {
+            // `rel` is managed by ammonia's link_rel and `style` by allow_style
+            let blocked = a.starts_with("on") || a == "srcdoc" || a == "rel" || a == "style";
+            if blocked {
+                warn!("HTML sanitizer: ignoring disallowed attribute '{}' in extra_attributes", a);
+            }
+            !a.is_empty() && !blocked
+        })
+        .collect();
+    if policy.allow_style {
+        extra_attributes.push("style".to_string());
+    }
+    let schemes: HashSet<&str> = policy.url_schemes.iter()
+        .map(|s| s.as_str())
+        .filter(|s| !s.eq_ignore_ascii_case("javascript") && !s.eq_ignore_ascii_case("vbscript"))
+        .collect();
+
+    let blocked = Arc::new(AtomicUsize::new(0));
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tags(extra_tags.iter().map(String::as_str))
+        .add_generic_attributes(extra_attributes.iter().map(String::as_str))
+        .url_schemes(schemes);
+    if !policy.allow_remote_images {
+        let blocked = Arc::clone(&blocked);
+        builder.attribute_filter(move |element, attribute, value| {
+            if element == "img" && matches!(attribute, "src" | "srcset") && is_remote_url(value) {
+                blocked.fetch_add(1, Ordering::Relaxed);
+                None
+            } else {
+                Some(Cow::Borrowed(value))
+            }
+        });
+    }
+
+    let cleaned = builder.clean(html).to_string();
+    (cleaned, blocked.load(Ordering::Relaxed))
+}
+
+/// Escape a plain-text body for display as HTML.
+pub fn text_to_html(text: &str) -> String {
+    format!("<pre>{}</pre>", ammonia::clean_text(text))
+}
+
+struct LoadedConfig {
+    modified: Option<SystemTime>,
+    config: SanitizerConfig,
+}
+
+/// Sanitizer whose config file is reloaded when it changes on disk.
+pub struct HtmlSanitizer {
+    path: PathBuf,
+    loaded: RwLock<Option<LoadedConfig>>,
+}
+
+impl HtmlSanitizer {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), loaded: RwLock::new(None) }
+    }
+
+    /// Process-wide sanitizer reading `HTML_SANITIZER_CONFIG`.
+    pub fn global() -> &'static HtmlSanitizer {
+        static SANITIZER: OnceLock<HtmlSanitizer> = OnceLock::new();
+        SANITIZER.get_or_init(|| {
+            let path = std::env::var("HTML_SANITIZER_CONFIG")
+                .unwrap_or_else(|_| "config/html_sanitizer.json".to_string());
+            HtmlSanitizer::new(path)
+        })
+    }
+
+    /// Current config, re-read if the file's modification time changed. A
+    /// missing file means the built-in defaults; an invalid one keeps the
+    /// last good config.
+    pub fn config(&self) -> SanitizerConfig {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if let Some(loaded) = self.loaded.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            if loaded.modified == modified {
+                return loaded.config.clone();
+            }
+        }
+
+        let mut guard = self.loaded.write().unwrap_or_else(|e| e.into_inner());
+        let config = match modified {
+            None => SanitizerConfig::default(),
+            Some(_) => match std::fs::read_to_string(&self.path)
+                .map_err(|e| e.to_string())
+                .and_then(|raw| serde_json::from_str::<SanitizerConfig>(&raw).map_err(|e| e.to_string()))
+            {
+                Ok(config) => {
+                    debug!("Loaded HTML sanitizer config from {}", self.path.display());
+                    config
+                }
+                Err(e) => {
+                    warn!("Invalid HTML sanitizer config {}: {}; keeping previous settings", self.path.display(), e);
+                    guard.as_ref().map(|l| l.config.clone()).unwrap_or_default()
+                }
+            },
+        };
+        *guard = Some(LoadedConfig { modified, config: config.clone() });
+        config
+    }
+
+    pub fn policy_for(&self, account_id: Option<&str>) -> SanitizerPolicy {
+        self.config().policy_for(account_id)
+    }
+
+    /// Sanitize a message body for `account_id` under the current config.
+    pub fn sanitize(&self, html: &str, account_id: Option<&str>) -> SanitizedHtml {
+        let policy = self.policy_for(account_id);
+        let (html, remote_images_blocked) = sanitize_with(&policy, html);
+        SanitizedHtml { html, remote_images_blocked, policy }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_strips_active_content_and_remote_images() {
+        let html = r#"<p style="color:red" onclick="x()">Hi<script>alert(1)</script></p>
+<img src="https://tracker.example/p.gif"><a href="javascript:alert(1)">x</a>"#;
+        let (clean, blocked) = sanitize_with(&SanitizerPolicy::default(), html);
+        assert!(!clean.contains("script"));
+        assert!(!clean.contains("onclick"));
+        assert!(!clean.contains("style"));
+        assert!(!clean.contains("tracker.example"));
+        assert!(!clean.contains("javascript"));
+        assert_eq!(blocked, 1);
+    }
+
+    #[test]
+    fn test_opt_in_style_and_remote_images() {
+        let policy = SanitizerPolicy { allow_style: true, allow_remote_images: true, ..Default::default() };
+        let (clean, blocked) = sanitize_with(&policy, r#"<p style="color:red"><img src="https://cdn.example/a.png"></p>"#);
+        assert!(clean.contains("color:red"));
+        assert!(clean.contains("cdn.example"));
+        assert_eq!(blocked, 0);
+    }
+
+    #[test]
+    fn test_dangerous_extras_are_ignored() {
+        let policy = SanitizerPolicy {
+            extra_tags: vec!["script".into(), "font".into()],
+            extra_attributes: vec!["onload".into(), "color".into()],
+            ..Default::default()
+        };
+        let (clean, _) = sanitize_with(&policy, r#"<font color="red" onload="x()">a</font><script>b</script>"#);
+        assert!(clean.contains(r#"<font color="red">"#));
+        assert!(!clean.contains("onload"));
+        assert!(!clean.contains("<script"));
+    }
+
+    #[test]
+    fn test_account_override_merges_over_global() {
+        let config: SanitizerConfig = serde_json::from_str(r#"{
+            "allow_style": true,
+            "accounts": { "News@Example.com": { "allow_remote_images": true } }
+        }"#).unwrap();
+        let policy = config.policy_for(Some("news@example.com"));
+        assert!(policy.allow_style);
+        assert!(policy.allow_remote_images);
+        assert_eq!(policy.url_schemes, default_url_schemes());
+        assert!(!config.policy_for(Some("other@example.com")).allow_remote_images);
+    }
+}
@@ -5,11 +5,14 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use sqlx::SqlitePool;
 use log::{debug, error, info, warn};
 use chrono::{DateTime, NaiveDateTime, Utc};
 
+use super::cache::CacheService;
+
 /// Parse a datetime string from SQLite, trying RFC3339 first, then SQLite's format.
 /// SQLite stores timestamps as "YYYY-MM-DD HH:MM:SS" (no timezone), which we treat as UTC.
 fn parse_sqlite_datetime(s: &str) -> DateTime<Utc> {
@@ -121,6 +124,46 @@ pub struct JobPersistenceService {
     pool: SqlitePool,
 }
 
+/// How much finished-job history to keep. Running and paused jobs are never
+/// removed by retention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobRetention {
+    /// Finished jobs older than this are deleted (`JOB_RETENTION_DAYS`, default 30)
+    pub max_age_days: i64,
+    /// Cap on total rows; the oldest finished jobs go first
+    /// (`JOB_RETENTION_MAX_ROWS`, default 10000, 0 = no cap)
+    pub max_rows: Option<i64>,
+}
+
+impl Default for JobRetention {
+    fn default() -> Self {
+        Self { max_age_days: 30, max_rows: Some(10_000) }
+    }
+}
+
+impl JobRetention {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let max_age_days = std::env::var("JOB_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.max_age_days);
+        let max_rows = match std::env::var("JOB_RETENTION_MAX_ROWS").ok().and_then(|v| v.parse::<i64>().ok()) {
+            Some(n) if n <= 0 => None,
+            Some(n) => Some(n),
+            None => defaults.max_rows,
+        };
+        Self { max_age_days, max_rows }
+    }
+}
+
+/// Row counts for the background_jobs table.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobTableStats {
+    pub total: i64,
+    pub by_status: std::collections::BTreeMap<String, i64>,
+}
+
 impl JobPersistenceService {
     pub fn new(pool: SqlitePool) -> Self {
         Self { pool }
@@ -417,6 +460,71 @@ impl JobPersistenceService {
         Ok(deleted)
     }
 
+    /// Delete the oldest finished jobs until at most `max_rows` rows remain
+    /// (or no finished jobs are left)
+    pub async fn cap_job_rows(&self, max_rows: i64) -> Result<u64, String> {
+        let (total,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM background_jobs")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+        let excess = total - max_rows;
+        if excess <= 0 {
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM background_jobs
+            WHERE job_id IN (
+                SELECT job_id FROM background_jobs
+                WHERE status IN ('completed', 'failed', 'cancelled')
+                ORDER BY COALESCE(completed_at, started_at) ASC
+                LIMIT ?
+            )
+            "#
+        )
+        .bind(excess)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let deleted = result.rows_affected();
+        if deleted > 0 {
+            info!("Trimmed {} background jobs to stay within {} rows", deleted, max_rows);
+        }
+        if (deleted as i64) < excess {
+            warn!("background_jobs still has {} rows over the {} cap; the rest are unfinished", excess - deleted as i64, max_rows);
+        }
+
+        Ok(deleted)
+    }
+
+    /// Apply age and row-count retention. Returns the number of rows deleted.
+    pub async fn apply_retention(&self, retention: &JobRetention) -> Result<u64, String> {
+        let mut deleted = self.cleanup_old_jobs(retention.max_age_days).await?;
+        if let Some(max_rows) = retention.max_rows {
+            deleted += self.cap_job_rows(max_rows).await?;
+        }
+        Ok(deleted)
+    }
+
+    /// Current size of the jobs table, total and per status
+    pub async fn table_stats(&self) -> Result<JobTableStats, String> {
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            "SELECT status, COUNT(*) FROM background_jobs GROUP BY status"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let mut stats = JobTableStats::default();
+        for (status, count) in rows {
+            stats.total += count;
+            stats.by_status.insert(status, count);
+        }
+        Ok(stats)
+    }
+
     /// Mark interrupted jobs as failed on startup (non-resumable ones)
     pub async fn mark_interrupted_jobs_failed(&self) -> Result<u64, String> {
         let result = sqlx::query(
@@ -594,3 +702,90 @@ impl JobPersistenceService {
         Ok(updated)
     }
 }
+
+/// How often the cache is pruned (`CACHE_PRUNE_INTERVAL_SECONDS`, default
+/// 3600). None when set to 0.
+pub fn cache_prune_interval() -> Option<Duration> {
+    let seconds: u64 = std::env::var("CACHE_PRUNE_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+    (seconds > 0).then_some(Duration::from_secs(seconds))
+}
+
+/// Background job that enforces the cache's age and size limits with
+/// `CacheService::prune` every `interval`. The first pass runs one interval
+/// after startup so it doesn't compete with the initial sync.
+pub fn spawn_cache_prune_job(cache_service: Arc<CacheService>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match cache_service.prune().await {
+                Ok(report) => debug!(
+                    "Cache prune: {} expired, {} over budget, {} -> {} bytes",
+                    report.expired, report.over_budget, report.bytes_before, report.bytes_after
+                ),
+                Err(e) => error!("Cache prune failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Executor;
+
+    async fn service_with_jobs(finished: usize, running: usize) -> JobPersistenceService {
+        // One connection, so every query sees the same in-memory database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        pool.execute(include_str!("../../../migrations/006_create_background_jobs.sql"))
+            .await
+            .unwrap();
+        pool.execute(include_str!("../../../migrations/011_add_account_id_to_jobs.sql"))
+            .await
+            .unwrap();
+        let service = JobPersistenceService::new(pool);
+
+        for i in 0..finished {
+            let job = PersistedJob::new(format!("done-{}", i), None, None);
+            service.create_job(&job).await.unwrap();
+            service.complete_job(&job.job_id, &serde_json::json!({})).await.unwrap();
+        }
+        for i in 0..running {
+            service.create_job(&PersistedJob::new(format!("run-{}", i), None, None)).await.unwrap();
+        }
+        service
+    }
+
+    #[tokio::test]
+    async fn test_cap_job_rows_keeps_unfinished_jobs() {
+        let service = service_with_jobs(8, 3).await;
+
+        assert_eq!(service.cap_job_rows(5).await.unwrap(), 6);
+        let stats = service.table_stats().await.unwrap();
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.by_status.get("running"), Some(&3));
+
+        // Only running jobs would be left to delete; they are kept
+        assert_eq!(service.cap_job_rows(1).await.unwrap(), 2);
+        assert_eq!(service.table_stats().await.unwrap().total, 3);
+    }
+
+    #[tokio::test]
+    async fn test_apply_retention_without_row_cap() {
+        let service = service_with_jobs(4, 1).await;
+        let retention = JobRetention { max_age_days: 30, max_rows: None };
+
+        // Everything is fresh, so nothing is old enough to go
+        assert_eq!(service.apply_retention(&retention).await.unwrap(), 0);
+        assert_eq!(service.table_stats().await.unwrap().total, 5);
+    }
+}
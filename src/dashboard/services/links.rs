@@ -0,0 +1,166 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Link extraction from cached email bodies.
+//!
+//! HTML bodies contribute their `<a href>` anchors with the visible text;
+//! plain-text bodies contribute bare `http(s)://` and `www.` URLs. Links are
+//! merged by target URL. An anchor whose visible text names a different
+//! domain than its href (`<a href="https://evil.example">paypal.com</a>`) is
+//! flagged as a mismatch, the classic phishing pattern.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::utils::plain_text::decode_entities;
+
+lazy_static! {
+    static ref ANCHOR: Regex = Regex::new(
+        r#"(?is)<a\b[^>]*?\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))[^>]*>(.*?)</a\s*>"#
+    ).unwrap();
+    static ref TAG: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+    static ref WHITESPACE: Regex = Regex::new(r"\s+").unwrap();
+    static ref PLAIN_URL: Regex = Regex::new(r#"(?i)\b(?:https?://|www\.)[^\s<>"'\]\)]+"#).unwrap();
+    // Visible text that reads as a web address: optional scheme, dotted host, optional path
+    static ref DOMAIN_TEXT: Regex = Regex::new(
+        r"(?i)^(?:https?://)?((?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+[a-z]{2,})(?::\d+)?(?:[/?#]\S*)?$"
+    ).unwrap();
+}
+
+/// One distinct link target found in an email.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExtractedLink {
+    pub url: String,
+    /// Host of `url`, lowercased, when it has one
+    pub domain: Option<String>,
+    /// Distinct visible texts of the anchors pointing at `url`
+    pub texts: Vec<String>,
+    pub in_plain: bool,
+    pub in_html: bool,
+    /// Visible text names a different domain than the link goes to
+    pub domain_mismatch: bool,
+    /// The domain shown in the mismatching text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_domain: Option<String>,
+}
+
+/// Links from a message's plain and HTML bodies, in order of first appearance.
+pub fn extract_links(body_text: Option<&str>, body_html: Option<&str>) -> Vec<ExtractedLink> {
+    let mut links: Vec<ExtractedLink> = Vec::new();
+
+    if let Some(html) = body_html {
+        for caps in ANCHOR.captures_iter(html) {
+            let href = caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3))
+                .map(|m| decode_entities(m.as_str().trim()))
+                .unwrap_or_default();
+            if href.is_empty() || href.starts_with('#') {
+                continue;
+            }
+            let text = caps.get(4).map(|m| anchor_text(m.as_str())).unwrap_or_default();
+            let link = entry(&mut links, &href);
+            link.in_html = true;
+            if !text.is_empty() && !link.texts.contains(&text) {
+                link.texts.push(text);
+            }
+        }
+    }
+
+    if let Some(text) = body_text {
+        for m in PLAIN_URL.find_iter(text) {
+            let url = m.as_str().trim_end_matches(|c: char| ".,;:!?".contains(c));
+            entry(&mut links, url).in_plain = true;
+        }
+    }
+
+    for link in &mut links {
+        link.text_domain = link.texts.iter()
+            .filter_map(|t| text_domain(t))
+            .find(|shown| link.domain.as_deref().is_none_or(|actual| !same_site(shown, actual)));
+        link.domain_mismatch = link.text_domain.is_some();
+    }
+    links
+}
+
+fn entry<'a>(links: &'a mut Vec<ExtractedLink>, url: &str) -> &'a mut ExtractedLink {
+    let index = match links.iter().position(|l| l.url == url) {
+        Some(index) => index,
+        None => {
+            links.push(ExtractedLink {
+                url: url.to_string(),
+                domain: url_domain(url),
+                texts: Vec::new(),
+                in_plain: false,
+                in_html: false,
+                domain_mismatch: false,
+                text_domain: None,
+            });
+            links.len() - 1
+        }
+    };
+    &mut links[index]
+}
+
+fn url_domain(url: &str) -> Option<String> {
+    let with_scheme = if url.to_ascii_lowercase().starts_with("www.") {
+        format!("http://{}", url)
+    } else {
+        url.to_string()
+    };
+    url::Url::parse(&with_scheme).ok()?.host_str().map(|h| h.to_ascii_lowercase())
+}
+
+fn anchor_text(inner: &str) -> String {
+    let text = TAG.replace_all(inner, " ");
+    decode_entities(WHITESPACE.replace_all(&text, " ").trim())
+}
+
+/// Domain named by anchor text, if the text is a web address.
+fn text_domain(text: &str) -> Option<String> {
+    DOMAIN_TEXT.captures(text.trim()).map(|c| c[1].to_ascii_lowercase())
+}
+
+/// Hosts match ignoring `www.`, or one is a subdomain of the other.
+fn same_site(a: &str, b: &str) -> bool {
+    let a = a.strip_prefix("www.").unwrap_or(a);
+    let b = b.strip_prefix("www.").unwrap_or(b);
+    a == b || a.ends_with(&format!(".{}", b)) || b.ends_with(&format!(".{}", a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links_merges_parts_and_flags_mismatch() {
+        let html = r##"<p>Pay <a href="https://evil.example/login">www.paypal.com</a>
+            or see <a class="x" href='https://news.example.com/a?b=1&amp;c=2'><b>our</b> news</a>
+            and <A HREF="https://shop.example.com/">https://example.com/deals</A>
+            <a href="#top">top</a></p>"##;
+        let text = "Read https://news.example.com/a?b=1&c=2. Or www.other.org/page, thanks";
+
+        let links = extract_links(Some(text), Some(html));
+        let urls: Vec<&str> = links.iter().map(|l| l.url.as_str()).collect();
+        assert_eq!(urls, vec![
+            "https://evil.example/login",
+            "https://news.example.com/a?b=1&c=2",
+            "https://shop.example.com/",
+            "www.other.org/page",
+        ]);
+
+        assert!(links[0].domain_mismatch);
+        assert_eq!(links[0].text_domain.as_deref(), Some("www.paypal.com"));
+
+        assert_eq!(links[1].texts, vec!["our news"]);
+        assert!(links[1].in_html && links[1].in_plain);
+        assert!(!links[1].domain_mismatch);
+
+        // Subdomains of the shown domain aren't a mismatch
+        assert!(!links[2].domain_mismatch);
+
+        assert_eq!(links[3].domain.as_deref(), Some("www.other.org"));
+        assert!(links[3].in_plain && !links[3].in_html);
+    }
+}
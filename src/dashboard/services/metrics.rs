@@ -5,13 +5,20 @@
 
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use std::fmt::Write as _;
 use chrono::Utc;
 use tokio::sync::RwLock;
 use sysinfo::{System, RefreshKind, CpuRefreshKind, MemoryRefreshKind};
-use log::{debug, info};
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use crate::connection_pool::{ConnectionPool, PoolStats};
 use crate::dashboard::api::models::{DashboardStats, SystemHealth, SystemStatus};
+use crate::dashboard::services::{CacheService, EmailService, OutboxQueueService};
 use std::collections::VecDeque;
 
+/// Outbox statuses still waiting on the worker; sent items are history.
+const OUTBOX_QUEUED_STATUSES: &[&str] = &["pending", "sending", "failed"];
+
 // Store for metrics data
 #[derive(Debug)]
 struct MetricsStore {
@@ -25,6 +32,18 @@ struct MetricsStore {
     request_timestamps: VecDeque<Instant>,
     // Store response times for requests within the last minute
     response_times_ms: VecDeque<u128>,
+    // Gauges for the Prometheus export, refreshed by the background collector
+    pool_stats: Option<PoolStats>,
+    account_pool_stats: Vec<(String, PoolStats)>,
+    outbox_counts: Vec<(String, i64)>,
+    sync_age_seconds: Vec<(String, Option<i64>)>,
+}
+
+/// Services the background collector reads for the Prometheus export.
+pub struct MetricsSources {
+    pub cache_service: Arc<CacheService>,
+    pub email_service: Arc<EmailService>,
+    pub outbox_queue_service: Arc<OutboxQueueService>,
 }
 
 impl Default for MetricsStore {
@@ -37,6 +56,10 @@ impl Default for MetricsStore {
             last_updated: Utc::now(),
             request_timestamps: VecDeque::with_capacity(1000), // Estimate capacity
             response_times_ms: VecDeque::with_capacity(1000),
+            pool_stats: None,
+            account_pool_stats: Vec::new(),
+            outbox_counts: Vec::new(),
+            sync_age_seconds: Vec::new(),
         }
     }
 }
@@ -67,8 +90,22 @@ impl MetricsService {
     async fn collect_metrics(
         sys: &mut System,
         store: Arc<RwLock<MetricsStore>>,
-        connection_pool: Arc<crate::connection_pool::ConnectionPool>
+        connection_pool: Arc<ConnectionPool>,
+        sources: &MetricsSources,
     ) {
+        // Read everything that awaits before taking the write lock
+        let account_pool_stats = sources.email_service.account_pool_stats().await;
+        let outbox_counts = sources.outbox_queue_service.status_counts().await
+            .unwrap_or_else(|e| {
+                warn!("Failed to count outbox items for metrics: {}", e);
+                Vec::new()
+            });
+        let sync_age_seconds = sources.cache_service.sync_age_by_account().await
+            .unwrap_or_else(|e| {
+                warn!("Failed to read sync ages for metrics: {}", e);
+                Vec::new()
+            });
+
         sys.refresh_specifics(
             RefreshKind::new()
                 .with_cpu(CpuRefreshKind::everything())
@@ -86,6 +123,10 @@ impl MetricsService {
         store_guard.active_imap_connections = pool_stats.active_connections;
         debug!("Collected IMAP connection pool stats: active={}, total={}, available={}",
                pool_stats.active_connections, pool_stats.total_connections, pool_stats.available_connections);
+        store_guard.pool_stats = Some(pool_stats);
+        store_guard.account_pool_stats = account_pool_stats;
+        store_guard.outbox_counts = outbox_counts;
+        store_guard.sync_age_seconds = sync_age_seconds;
         // --- End connection count --- 
 
         // TODO: Update request_rate_points (needs tracking mechanism)
@@ -180,8 +221,79 @@ impl MetricsService {
         }
     }
 
-    // Start background collection task with only the connection pool (breaks circular reference)
-    pub fn start_background_collection(&self, connection_pool: Arc<crate::connection_pool::ConnectionPool>) {
+    /// Metrics in the Prometheus text exposition format (version 0.0.4).
+    ///
+    /// Pool, outbox and sync figures are the background collector's latest
+    /// snapshot, so a scrape never touches IMAP or runs queries. Accounts
+    /// are labelled by `account_label`, never by address.
+    pub async fn render_prometheus(&self) -> String {
+        let store = self.metrics_store.read().await;
+        let mut out = String::new();
+
+        if let Some(pool) = &store.pool_stats {
+            metric_header(&mut out, "rustymail_imap_pool_connections", "Connections in the shared IMAP pool by state", "gauge");
+            let _ = writeln!(out, "rustymail_imap_pool_connections{{state=\"active\"}} {}", pool.active_connections);
+            let _ = writeln!(out, "rustymail_imap_pool_connections{{state=\"available\"}} {}", pool.available_connections);
+            metric_header(&mut out, "rustymail_imap_pool_max_connections", "Size limit of the shared IMAP pool", "gauge");
+            let _ = writeln!(out, "rustymail_imap_pool_max_connections {}", pool.max_connections);
+            metric_header(&mut out, "rustymail_imap_pool_acquire_timeouts_total", "Pool checkouts that timed out", "counter");
+            let _ = writeln!(out, "rustymail_imap_pool_acquire_timeouts_total {}", pool.acquire_timeouts);
+            metric_header(&mut out, "rustymail_imap_pool_creation_failures_total", "Pool connections that failed to open", "counter");
+            let _ = writeln!(out, "rustymail_imap_pool_creation_failures_total {}", pool.creation_failures);
+        }
+
+        if !store.account_pool_stats.is_empty() {
+            metric_header(&mut out, "rustymail_imap_account_sessions", "Sessions in each account's IMAP pool by state", "gauge");
+            for (account, stats) in &store.account_pool_stats {
+                let label = account_label(account);
+                let _ = writeln!(out, "rustymail_imap_account_sessions{{account=\"{}\",state=\"active\"}} {}", label, stats.active_connections);
+                let _ = writeln!(out, "rustymail_imap_account_sessions{{account=\"{}\",state=\"available\"}} {}", label, stats.available_connections);
+            }
+        }
+
+        if !store.sync_age_seconds.is_empty() {
+            metric_header(&mut out, "rustymail_sync_lag_seconds", "Seconds since the latest sync pass over the account's folders", "gauge");
+            for (account, age) in &store.sync_age_seconds {
+                // Accounts that never synced have no sample rather than a fake age
+                if let Some(age) = age {
+                    let _ = writeln!(out, "rustymail_sync_lag_seconds{{account=\"{}\"}} {}", account_label(account), (*age).max(0));
+                }
+            }
+        }
+
+        metric_header(&mut out, "rustymail_outbox_queue_depth", "Outgoing messages waiting in the outbox queue by status", "gauge");
+        for status in OUTBOX_QUEUED_STATUSES {
+            let count = store.outbox_counts.iter()
+                .find(|(s, _)| s == status)
+                .map(|(_, n)| *n)
+                .unwrap_or(0);
+            let _ = writeln!(out, "rustymail_outbox_queue_depth{{status=\"{}\"}} {}", status, count);
+        }
+        drop(store);
+
+        let providers = crate::dashboard::services::ai::provider_manager::all_provider_concurrency();
+        if !providers.is_empty() {
+            metric_header(&mut out, "rustymail_ai_requests_total", "Requests sent to each AI provider", "counter");
+            for p in &providers {
+                let _ = writeln!(out, "rustymail_ai_requests_total{{provider=\"{}\"}} {}", label_value(&p.provider), p.requests_total);
+            }
+            metric_header(&mut out, "rustymail_ai_requests_in_flight", "AI requests currently running per provider", "gauge");
+            for p in &providers {
+                let _ = writeln!(out, "rustymail_ai_requests_in_flight{{provider=\"{}\"}} {}", label_value(&p.provider), p.in_flight);
+            }
+            metric_header(&mut out, "rustymail_ai_requests_queued", "AI requests waiting for a provider slot", "gauge");
+            for p in &providers {
+                let _ = writeln!(out, "rustymail_ai_requests_queued{{provider=\"{}\"}} {}", label_value(&p.provider), p.queued);
+            }
+        }
+
+        out
+    }
+
+    // Start background collection task with the connection pool and the
+    // services the Prometheus export reads (not DashboardState, which would
+    // be a circular reference)
+    pub fn start_background_collection(&self, connection_pool: Arc<ConnectionPool>, sources: MetricsSources) {
         let metrics_store_clone = Arc::clone(&self.metrics_store);
         let collection_interval = self.collection_interval;
 
@@ -195,9 +307,56 @@ impl MetricsService {
             loop {
                 interval.tick().await;
                 // Only pass the connection pool, not the entire DashboardState
-                MetricsService::collect_metrics(&mut sys, metrics_store_clone.clone(), connection_pool.clone()).await;
+                MetricsService::collect_metrics(&mut sys, metrics_store_clone.clone(), connection_pool.clone(), &sources).await;
             }
         });
         info!("Started background metrics collection task");
     }
 }
+
+fn metric_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Stable, fixed-size label for an account: the first 12 hex digits of the
+/// SHA-256 of its lower-cased address. Keeps one series per account without
+/// sending addresses to the metrics backend.
+pub fn account_label(email: &str) -> String {
+    let digest = Sha256::digest(email.trim().to_lowercase().as_bytes());
+    hex::encode(digest)[..12].to_string()
+}
+
+/// Escape a label value per the exposition format.
+fn label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_render_prometheus_labels_accounts_by_hash() {
+        let service = MetricsService::new(Duration::from_secs(60));
+        {
+            let mut store = service.metrics_store.write().await;
+            store.sync_age_seconds = vec![
+                ("Alice@Example.com".to_string(), Some(42)),
+                ("new@example.com".to_string(), None),
+            ];
+            store.outbox_counts = vec![("pending".to_string(), 3), ("sent".to_string(), 120)];
+        }
+
+        let text = service.render_prometheus().await;
+        let alice = account_label("alice@example.com");
+        assert_eq!(alice.len(), 12);
+        assert!(text.contains(&format!("rustymail_sync_lag_seconds{{account=\"{}\"}} 42", alice)));
+        assert!(!text.contains("example.com"));
+        assert!(!text.contains(&account_label("new@example.com")));
+        assert!(text.contains("rustymail_outbox_queue_depth{status=\"pending\"} 3"));
+        assert!(text.contains("rustymail_outbox_queue_depth{status=\"failed\"} 0"));
+        assert!(!text.contains("status=\"sent\""));
+        assert!(text.contains("# TYPE rustymail_outbox_queue_depth gauge"));
+    }
+}
@@ -23,6 +23,7 @@ use crate::connection_pool::ConnectionPool;
 use sqlx::SqlitePool;
 
 pub mod account;
+pub mod activity;
 pub mod account_store;
 pub mod ai;
 pub mod encryption;
@@ -31,22 +32,34 @@ pub mod oauth_service;
 pub mod attachment_storage;
 pub mod autodiscovery;
 pub mod cache;
+pub mod cache_crypto;
 pub mod clients;
 pub mod config;
+pub mod config_reload;
 pub mod connection_status;
 pub mod connection_status_store;
 pub mod email;
 pub mod events;
 pub mod event_integration;
 pub mod health;
+pub mod html_sanitizer;
+pub mod links;
 pub mod metrics;
 pub mod outbox_queue;
 pub mod outbox_worker;
+pub mod recipient_verify;
+pub mod reply;
 pub mod smtp;
+pub mod dkim;
 pub mod smtp_auth;
+pub mod shutdown;
 pub mod sync;
+pub mod templates;
+pub mod threads;
+pub mod tool_dedup;
 pub mod token_refresh_worker;
 pub mod jobs;
+pub mod webhooks;
 
 // Define or import error types if they exist
 #[derive(Error, Debug)] pub enum MetricsError { #[error("Metrics collection failed: {0}")] CollectionFailed(String), #[error("Metrics storage error: {0}")] StorageError(String) }
@@ -70,10 +83,10 @@ pub use ai::{AiService};
 pub use email::{EmailService};
 pub use events::{EventBus, DashboardEvent};
 pub use health::{HealthService, HealthReport, HealthStatus};
-pub use outbox_queue::{OutboxQueueService, OutboxQueueItem, OutboxStatus};
+pub use outbox_queue::{OutboxQueueService, OutboxQueueItem, OutboxStatus, OutboxListEntry, CancelOutcome};
 pub use outbox_worker::{OutboxWorker};
 pub use token_refresh_worker::TokenRefreshWorker;
-pub use smtp::{SmtpService, SendEmailRequest, SendEmailResponse, SmtpError};
+pub use smtp::{SmtpService, SendEmailRequest, SendEmailResponse, SavedDraft, SmtpError, OutgoingAttachment};
 pub use sync::{SyncService};
 pub use jobs::{JobRecord, JobStatus};
 pub use encryption::{CredentialEncryption, EncryptionError};
@@ -115,6 +128,8 @@ pub struct DashboardState {
     pub sse_manager: Arc<SseManager>,
     pub event_bus: Arc<EventBus>,
     pub health_service: Option<Arc<HealthService>>,
+    /// Settings as loaded at startup; `config_service.settings()` has any
+    /// changes applied by a reload since
     pub config: web::Data<Settings>,
     pub imap_session_factory: CloneableImapSessionFactory,
     pub connection_pool: Arc<ConnectionPool>,
@@ -140,36 +155,36 @@ pub async fn init(
     let config_service = Arc::new(ConfigService::new());
 
     // Initialize Cache Service
+    let cache_limits = cache::CacheLimits::from_env();
     let cache_config = CacheConfig {
         database_url: std::env::var("CACHE_DATABASE_URL")
             .unwrap_or_else(|_| "sqlite:data/email_cache.db".to_string()),
-        max_memory_items: std::env::var("CACHE_MAX_MEMORY_ITEMS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(1000),
-        max_folder_items: std::env::var("CACHE_MAX_FOLDER_ITEMS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(100),
-        max_cache_size_mb: std::env::var("CACHE_MAX_SIZE_MB")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(1000),
-        max_email_age_days: std::env::var("CACHE_MAX_EMAIL_AGE_DAYS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(30),
+        max_memory_items: cache_limits.max_memory_items,
+        max_folder_items: cache_limits.max_folder_items,
+        max_cache_size_mb: cache_limits.max_cache_size_mb,
+        max_email_age_days: cache_limits.max_email_age_days,
         sync_interval_seconds: std::env::var("CACHE_SYNC_INTERVAL_SECONDS")
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(300),
+        encryption_passphrase: cache_crypto::passphrase(
+            config.cache_encryption.as_ref().and_then(|c| c.passphrase.as_deref())
+        ),
+        prune_keep_flagged: std::env::var("CACHE_PRUNE_KEEP_FLAGGED")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(true),
     };
 
     // Initialize Cache Service (this runs database migrations)
     let mut cache_service = CacheService::new(cache_config);
-    if let Err(e) = cache_service.initialize().await {
-        warn!("Failed to initialize cache service: {}. Running without cache.", e);
-    }
+    let migrations_complete = match cache_service.initialize().await {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to initialize cache service: {}. Running without cache.", e);
+            false
+        }
+    };
     let cache_service = Arc::new(cache_service);
 
     // Initialize Account Service with file-based storage
@@ -216,23 +231,50 @@ pub async fn init(
     let outbox_queue_service = Arc::new(OutboxQueueService::new(queue_pool));
 
     // Initialize SMTP Service
-    let smtp_service = Arc::new(SmtpService::new(
+    let mut smtp_service = SmtpService::new(
         account_service.clone(),
         imap_session_factory.clone(),
-    ));
+    );
+    match dkim::DkimSigner::from_settings(&config) {
+        Ok(Some(signer)) => smtp_service = smtp_service.with_dkim(Arc::new(signer)),
+        Ok(None) => info!("No DKIM keys configured; outgoing mail is not signed"),
+        Err(e) => error!("DKIM signing disabled: {}", e),
+    }
+    let smtp_service = Arc::new(smtp_service);
 
     // Initialize Sync Service
-    let sync_interval = std::env::var("SYNC_INTERVAL_SECONDS")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(300); // Default 5 minutes
+    let sync_interval = sync::sync_interval_from_env();
 
-    let sync_service = Arc::new(SyncService::new(
-        imap_session_factory.clone(),
-        cache_service.clone(),
-        account_service.clone(),
-        sync_interval,
-    ));
+    // Create event bus
+    let event_bus = Arc::new(EventBus::new());
+
+    let sync_service = Arc::new(
+        SyncService::new(
+            imap_session_factory.clone(),
+            cache_service.clone(),
+            account_service.clone(),
+            sync_interval,
+        )
+        .with_event_bus(Arc::clone(&event_bus))
+        .with_account_pools(email_service.account_pools())
+    );
+
+    // Sync watched folders as soon as mail arrives on servers with IDLE
+    if crate::imap::idle::idle_enabled() {
+        Arc::clone(&sync_service).start_idle_watchers();
+    }
+
+    // Persist mailbox activity events for the per-account activity feed
+    activity::start_activity_recorder(
+        Arc::new(activity::ActivityLogService::from_env(account_db_pool.clone())),
+        Arc::clone(&event_bus),
+    ).await;
+
+    // POST new mail and sync events to the configured webhooks
+    match webhooks::WebhookDispatcher::from_settings(&config, account_db_pool.clone()) {
+        Some(dispatcher) => webhooks::start_webhook_dispatcher(Arc::new(dispatcher), Arc::clone(&event_bus)).await,
+        None => debug!("No webhooks configured"),
+    }
 
     // Initialize AI Service with environment variables
     let openai_api_key = std::env::var("OPENAI_API_KEY").ok();
@@ -273,9 +315,6 @@ pub async fn init(
     let oauth_config = OAuthConfig::from_env();
     let oauth_service = Arc::new(OAuthService::new(oauth_config));
 
-    // Create event bus
-    let event_bus = Arc::new(EventBus::new());
-
     // Create SSE manager and configure it with event bus
     let mut sse_manager = SseManager::new(
         metrics_service.clone(),
@@ -289,7 +328,11 @@ pub async fn init(
         HealthService::new()
             .with_event_bus(Arc::clone(&event_bus))
             .with_connection_pool(Arc::clone(&connection_pool))
+            .with_account_service(Arc::clone(&account_service))
     );
+    if migrations_complete {
+        health_service.mark_migrations_complete();
+    }
 
     // Initialize job persistence service
     let job_persistence = Arc::new(jobs::JobPersistenceService::new(account_db_pool.clone()));
@@ -298,9 +341,32 @@ pub async fn init(
     if let Err(e) = job_persistence.mark_interrupted_jobs_failed().await {
         warn!("Failed to mark interrupted jobs as failed: {}", e);
     }
-    if let Err(e) = job_persistence.cleanup_old_jobs(30).await {
+    let job_retention = jobs::JobRetention::from_env();
+    info!("Job retention: {} days, max rows {:?}", job_retention.max_age_days, job_retention.max_rows);
+    if let Err(e) = job_persistence.apply_retention(&job_retention).await {
         warn!("Failed to clean up old jobs: {}", e);
     }
+    {
+        // Busy servers can outgrow the cap between restarts, so re-apply hourly
+        let job_persistence = Arc::clone(&job_persistence);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = job_persistence.apply_retention(&job_retention).await {
+                    warn!("Failed to clean up old jobs: {}", e);
+                }
+            }
+        });
+    }
+
+    if migrations_complete {
+        if let Some(interval) = jobs::cache_prune_interval() {
+            info!("Cache pruning every {} seconds", interval.as_secs());
+            jobs::spawn_cache_prune_job(Arc::clone(&cache_service), interval);
+        }
+    }
 
     // Load resumable jobs into memory
     let jobs_map = Arc::new(DashMap::new());
@@ -3,7 +3,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use sqlx::{Row, SqlitePool};
 use chrono::{DateTime, Utc, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use log::{info, warn};
@@ -26,6 +27,9 @@ pub struct OutboxQueueItem {
     pub subject: String,
     pub body_text: String,
     pub body_html: Option<String>,
+    /// Fully assembled MIME message, attachments included, built once at
+    /// enqueue time. The worker sends and appends these bytes as-is, so
+    /// retries never need the original attachment sources.
     pub raw_email_bytes: Vec<u8>,
 
     // Status tracking
@@ -41,11 +45,24 @@ pub struct OutboxQueueItem {
 
     // Timestamps
     pub created_at: DateTime<Utc>,
+    /// Earliest time the worker may send this item; None sends right away
+    pub scheduled_at: Option<DateTime<Utc>>,
     pub smtp_sent_at: Option<DateTime<Utc>>,
     pub last_retry_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
 }
 
+impl OutboxQueueItem {
+    /// Every SMTP recipient: To, Cc and Bcc.
+    pub fn envelope_recipients(&self) -> Vec<String> {
+        self.to_addresses.iter()
+            .chain(self.cc_addresses.iter().flatten())
+            .chain(self.bcc_addresses.iter().flatten())
+            .cloned()
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum OutboxStatus {
@@ -53,6 +70,7 @@ pub enum OutboxStatus {
     Sending,
     Sent,
     Failed,
+    Cancelled,
 }
 
 impl OutboxStatus {
@@ -62,6 +80,7 @@ impl OutboxStatus {
             OutboxStatus::Sending => "sending",
             OutboxStatus::Sent => "sent",
             OutboxStatus::Failed => "failed",
+            OutboxStatus::Cancelled => "cancelled",
         }
     }
 
@@ -70,26 +89,81 @@ impl OutboxStatus {
             "sending" => OutboxStatus::Sending,
             "sent" => OutboxStatus::Sent,
             "failed" => OutboxStatus::Failed,
+            "cancelled" => OutboxStatus::Cancelled,
             _ => OutboxStatus::Pending,
         }
     }
 }
 
+/// Why [`OutboxQueueService::cancel`] left an item alone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CancelOutcome {
+    Cancelled,
+    NotFound,
+    /// The item is already being sent, or is finished
+    NotCancellable(OutboxStatus),
+}
+
+/// Queue entry without the message bytes, for listing.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxListEntry {
+    pub id: i64,
+    pub account_email: String,
+    pub message_id: Option<String>,
+    pub to_addresses: Vec<String>,
+    pub subject: String,
+    pub status: OutboxStatus,
+    pub retry_count: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub scheduled_at: Option<DateTime<Utc>>,
+    /// When the worker will (or did) pick the item up: the scheduled time,
+    /// or the queue time for items sent right away
+    pub send_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
 pub struct OutboxQueueService {
     pool: SqlitePool,
+    /// Cleared at shutdown so nothing is queued that the worker won't send
+    accepting: AtomicBool,
 }
 
 impl OutboxQueueService {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self { pool, accepting: AtomicBool::new(true) }
+    }
+
+    /// Refuse new items from now on; items already queued are unaffected.
+    pub fn stop_accepting(&self) {
+        self.accepting.store(false, Ordering::SeqCst);
+    }
+
+    /// Close the database pool, which is shared with the account and job
+    /// stores. Waits for connections in use to be returned.
+    pub async fn close(&self) {
+        self.pool.close().await;
     }
 
     /// Add a new email to the outbox queue
     pub async fn enqueue(&self, item: OutboxQueueItem) -> Result<i64, sqlx::Error> {
+        if !self.accepting.load(Ordering::SeqCst) {
+            return Err(sqlx::Error::Protocol(
+                "The outbox is not accepting new mail while the server shuts down".to_string(),
+            ));
+        }
+        if item.raw_email_bytes.is_empty() {
+            return Err(sqlx::Error::Protocol(
+                "Outbox items must carry the fully assembled message in raw_email_bytes".to_string(),
+            ));
+        }
+
         let to_json = serde_json::to_string(&item.to_addresses).unwrap_or_default();
         let cc_json = item.cc_addresses.as_ref().map(|cc| serde_json::to_string(cc).unwrap_or_default());
         let bcc_json = item.bcc_addresses.as_ref().map(|bcc| serde_json::to_string(bcc).unwrap_or_default());
         let status_str = item.status.as_str().to_string();
+        // Stored like CURRENT_TIMESTAMP (naive UTC) so SQL can compare them
+        let scheduled_at = item.scheduled_at.map(|t| t.naive_utc());
 
         let result = sqlx::query!(
             r#"
@@ -97,8 +171,8 @@ impl OutboxQueueService {
                 account_email, message_id, to_addresses, cc_addresses, bcc_addresses,
                 subject, body_text, body_html, raw_email_bytes,
                 status, smtp_sent, outbox_saved, sent_folder_saved,
-                retry_count, max_retries
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                retry_count, max_retries, scheduled_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             item.account_email,
             item.message_id,
@@ -114,16 +188,21 @@ impl OutboxQueueService {
             item.outbox_saved,
             item.sent_folder_saved,
             item.retry_count,
-            item.max_retries
+            item.max_retries,
+            scheduled_at
         )
         .execute(&self.pool)
         .await?;
 
-        info!("Enqueued email for {} (subject: {})", item.account_email, item.subject);
+        match item.scheduled_at {
+            Some(at) => info!("Enqueued email for {} (subject: {}), scheduled for {}", item.account_email, item.subject, at),
+            None => info!("Enqueued email for {} (subject: {})", item.account_email, item.subject),
+        }
         Ok(result.last_insert_rowid())
     }
 
-    /// Get next pending email to process
+    /// Get next pending email to process. Items scheduled for later are
+    /// skipped until their time has come.
     pub async fn get_next_pending(&self) -> Result<Option<OutboxQueueItem>, sqlx::Error> {
         let record = sqlx::query!(
             r#"
@@ -131,10 +210,11 @@ impl OutboxQueueService {
                    subject, body_text, body_html, raw_email_bytes,
                    status, smtp_sent, outbox_saved, sent_folder_saved,
                    retry_count, max_retries, last_error,
-                   created_at, smtp_sent_at, last_retry_at, completed_at
+                   created_at, smtp_sent_at, last_retry_at, completed_at, scheduled_at
             FROM outbox_queue
             WHERE status = 'pending'
-            ORDER BY created_at ASC
+              AND (scheduled_at IS NULL OR datetime(scheduled_at) <= datetime('now'))
+            ORDER BY COALESCE(scheduled_at, created_at) ASC
             LIMIT 1
             "#
         )
@@ -163,19 +243,93 @@ impl OutboxQueueService {
             smtp_sent_at: r.smtp_sent_at.map(naive_to_utc),
             last_retry_at: r.last_retry_at.map(naive_to_utc),
             completed_at: r.completed_at.map(naive_to_utc),
+            scheduled_at: r.scheduled_at.map(naive_to_utc),
         }))
     }
 
-    /// Update status to sending
-    pub async fn mark_sending(&self, id: i64) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            r#"UPDATE outbox_queue SET status = 'sending', last_retry_at = CURRENT_TIMESTAMP WHERE id = ?"#,
+    /// Claim a pending item for sending. Returns false if it is no longer
+    /// pending, e.g. because it was cancelled after being picked up.
+    pub async fn mark_sending(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"UPDATE outbox_queue SET status = 'sending', last_retry_at = CURRENT_TIMESTAMP WHERE id = ? AND status = 'pending'"#,
             id
         )
         .execute(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Cancel an item that is still waiting to be sent. Once the worker has
+    /// claimed it the SMTP send may be under way, so it can't be cancelled.
+    pub async fn cancel(&self, id: i64) -> Result<CancelOutcome, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"UPDATE outbox_queue SET status = 'cancelled', completed_at = CURRENT_TIMESTAMP WHERE id = ? AND status = 'pending' AND smtp_sent = FALSE"#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            info!("Cancelled outbox queue item {}", id);
+            return Ok(CancelOutcome::Cancelled);
+        }
+
+        let status: Option<String> = sqlx::query_scalar("SELECT status FROM outbox_queue WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(match status {
+            Some(status) => CancelOutcome::NotCancellable(OutboxStatus::from_str(&status)),
+            None => CancelOutcome::NotFound,
+        })
+    }
+
+    /// Queued and scheduled items (pending or being sent), soonest first.
+    /// With `include_finished`, sent, failed and cancelled items follow,
+    /// most recent first.
+    pub async fn list(
+        &self,
+        account_email: Option<&str>,
+        include_finished: bool,
+        limit: i64,
+    ) -> Result<Vec<OutboxListEntry>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, account_email, message_id, to_addresses, subject, status,
+                   retry_count, last_error, created_at, scheduled_at, completed_at
+            FROM outbox_queue
+            WHERE (?1 IS NULL OR account_email = ?1)
+              AND (?2 OR status IN ('pending', 'sending'))
+            ORDER BY status NOT IN ('pending', 'sending'),
+                     CASE WHEN status IN ('pending', 'sending') THEN COALESCE(scheduled_at, created_at) END ASC,
+                     COALESCE(completed_at, created_at) DESC
+            LIMIT ?3
+            "#
+        )
+        .bind(account_email)
+        .bind(include_finished)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|r| {
+            let created_at = r.get::<Option<NaiveDateTime>, _>("created_at").map(naive_to_utc).unwrap_or_else(Utc::now);
+            let scheduled_at = r.get::<Option<NaiveDateTime>, _>("scheduled_at").map(naive_to_utc);
+            OutboxListEntry {
+                id: r.get("id"),
+                account_email: r.get("account_email"),
+                message_id: r.get("message_id"),
+                to_addresses: serde_json::from_str(&r.get::<String, _>("to_addresses")).unwrap_or_default(),
+                subject: r.get("subject"),
+                status: OutboxStatus::from_str(&r.get::<String, _>("status")),
+                retry_count: r.get::<i64, _>("retry_count") as i32,
+                last_error: r.get("last_error"),
+                created_at,
+                scheduled_at,
+                send_at: scheduled_at.unwrap_or(created_at),
+                completed_at: r.get::<Option<NaiveDateTime>, _>("completed_at").map(naive_to_utc),
+            }
+        }).collect())
     }
 
     /// Mark SMTP as sent successfully
@@ -203,14 +357,13 @@ impl OutboxQueueService {
         Ok(())
     }
 
-    /// Mark sent folder save complete
-    pub async fn mark_sent_folder_saved(&self, id: i64) -> Result<(), sqlx::Error> {
-        sqlx::query!(
-            r#"UPDATE outbox_queue SET sent_folder_saved = TRUE WHERE id = ?"#,
-            id
-        )
-        .execute(&self.pool)
-        .await?;
+    /// Mark sent folder save complete, with the UID of the Sent copy if known
+    pub async fn mark_sent_folder_saved(&self, id: i64, sent_uid: Option<u32>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE outbox_queue SET sent_folder_saved = TRUE, sent_uid = ? WHERE id = ?")
+            .bind(sent_uid.map(i64::from))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
 
         Ok(())
     }
@@ -270,6 +423,34 @@ impl OutboxQueueService {
         }
     }
 
+    /// Put items left `sending` by a previous run back to `pending`. Run
+    /// once at startup, before the worker polls: nothing is in flight then,
+    /// so these were cut off by a crash or the shutdown timeout. Their
+    /// `smtp_sent` and `*_saved` flags keep completed steps from repeating.
+    pub async fn recover_interrupted(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE outbox_queue SET status = 'pending' WHERE status = 'sending'")
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() > 0 {
+            info!("Requeued {} outbox item(s) interrupted while sending", result.rows_affected());
+        }
+        Ok(result.rows_affected())
+    }
+
+    /// Items not yet sent or given up on: pending, scheduled and in flight.
+    pub async fn unfinished_count(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM outbox_queue WHERE status IN ('pending', 'sending')")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Number of items in each status, for monitoring.
+    pub async fn status_counts(&self) -> Result<Vec<(String, i64)>, sqlx::Error> {
+        sqlx::query_as::<_, (String, i64)>("SELECT status, COUNT(*) FROM outbox_queue GROUP BY status")
+            .fetch_all(&self.pool)
+            .await
+    }
+
     /// Get all items for an account
     pub async fn get_by_account(&self, account_email: &str) -> Result<Vec<OutboxQueueItem>, sqlx::Error> {
         let records = sqlx::query!(
@@ -278,7 +459,7 @@ impl OutboxQueueService {
                    subject, body_text, body_html, raw_email_bytes,
                    status, smtp_sent, outbox_saved, sent_folder_saved,
                    retry_count, max_retries, last_error,
-                   created_at, smtp_sent_at, last_retry_at, completed_at
+                   created_at, smtp_sent_at, last_retry_at, completed_at, scheduled_at
             FROM outbox_queue
             WHERE account_email = ?
             ORDER BY created_at DESC
@@ -310,17 +491,18 @@ impl OutboxQueueService {
             smtp_sent_at: r.smtp_sent_at.map(naive_to_utc),
             last_retry_at: r.last_retry_at.map(naive_to_utc),
             completed_at: r.completed_at.map(naive_to_utc),
+            scheduled_at: r.scheduled_at.map(naive_to_utc),
         }).collect())
     }
 
-    /// Get subjects of all completed or failed items for an account
+    /// Get subjects of all sent, failed or cancelled items for an account
     /// Used for orphan cleanup to identify which emails should be removed from Outbox
     pub async fn get_completed_subjects(&self, account_email: &str) -> Result<Vec<String>, sqlx::Error> {
         let records = sqlx::query!(
             r#"
             SELECT subject
             FROM outbox_queue
-            WHERE account_email = ? AND (status = 'sent' OR status = 'failed')
+            WHERE account_email = ? AND status IN ('sent', 'failed', 'cancelled')
             "#,
             account_email
         )
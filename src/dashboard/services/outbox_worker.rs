@@ -4,11 +4,17 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tokio::time::sleep;
+use tokio::time::{sleep, timeout, Instant};
 use tokio::sync::Mutex as TokioMutex;
 use log::{info, error, warn};
 use crate::dashboard::services::{OutboxQueueService, SmtpService, AccountService, CacheService};
+use crate::dashboard::services::account_store::SentAppendMode;
+use crate::dashboard::services::events::{EventBus, MailboxAction};
+use crate::imap::error::ImapError;
+use crate::imap::provider_profile::ProviderProfile;
+use crate::imap::special_use::{self, SpecialUse};
 use crate::prelude::CloneableImapSessionFactory;
 
 /// Background worker that processes the outbox queue
@@ -18,7 +24,29 @@ pub struct OutboxWorker {
     imap_factory: CloneableImapSessionFactory,
     account_service: Arc<TokioMutex<AccountService>>,
     cache_service: Arc<CacheService>,
+    event_bus: Option<Arc<EventBus>>,
     poll_interval: Duration,
+    /// Set by `drain`; the polling loop exits before its next item
+    stopping: AtomicBool,
+    /// Held while an item is processed, so `drain` can wait for it
+    busy: TokioMutex<()>,
+}
+
+/// What `OutboxWorker::drain` got through before shutdown.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutboxDrain {
+    /// Items taken off the queue, whether they went out or failed
+    pub processed: usize,
+    /// An item was still being processed when the timeout fired
+    pub interrupted: bool,
+}
+
+/// How a sent message got into the Sent folder.
+enum SentCopy {
+    /// Appended by the worker, with its UID if the server sent APPENDUID
+    Appended(Option<u32>),
+    /// Already there, filed by the server when the message went out
+    AlreadyFiled(u32),
 }
 
 // SAFETY: All fields are Send: Arc<T> is Send if T is Send+Sync, CloneableImapSessionFactory is Send+Sync, Duration is Send
@@ -46,20 +74,39 @@ impl OutboxWorker {
             imap_factory,
             account_service,
             cache_service,
+            event_bus: None,
             poll_interval: Duration::from_secs(poll_interval),
+            stopping: AtomicBool::new(false),
+            busy: TokioMutex::new(()),
         }
     }
 
+    /// Publish a `sent` activity event for each delivered message.
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
     /// Start the background worker loop
     pub async fn start(self: Arc<Self>) {
         info!("Starting outbox worker with {} second poll interval", self.poll_interval.as_secs());
 
+        if let Err(e) = self.queue_service.recover_interrupted().await {
+            error!("Failed to requeue interrupted outbox items: {}", e);
+        }
+
         let mut iteration_count = 0u64;
         let cleanup_interval = 12; // Run cleanup every 12 iterations (60 seconds with 5-second poll)
 
         loop {
-            if let Err(e) = self.process_next().await {
-                error!("Error processing outbox queue: {}", e);
+            {
+                let _busy = self.busy.lock().await;
+                if self.stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Err(e) = self.process_next().await {
+                    error!("Error processing outbox queue: {}", e);
+                }
             }
 
             // Periodically clean up orphaned emails in Outbox folders
@@ -72,24 +119,65 @@ impl OutboxWorker {
 
             sleep(self.poll_interval).await;
         }
+        info!("Outbox worker stopped");
     }
 
-    /// Process next item in the queue
-    async fn process_next(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// Stop the polling loop once its current item is done, then send
+    /// whatever is due until the queue is empty or `limit` has passed.
+    /// Takes at most as many items as were unfinished once the loop stopped,
+    /// so a failing item isn't retried back to back. An item cut off by the
+    /// timeout keeps the steps it completed and is resumed from there on the
+    /// next start.
+    pub async fn drain(&self, limit: Duration) -> OutboxDrain {
+        let deadline = Instant::now() + limit;
+        let mut drain = OutboxDrain::default();
+        self.stopping.store(true, Ordering::SeqCst);
+
+        let Ok(_busy) = timeout(limit, self.busy.lock()).await else {
+            drain.interrupted = true;
+            return drain;
+        };
+        let budget = self.queue_service.unfinished_count().await.unwrap_or(0).max(0) as usize;
+        while drain.processed < budget && Instant::now() < deadline {
+            match timeout(deadline - Instant::now(), self.process_next()).await {
+                Ok(Ok(true)) => drain.processed += 1,
+                Ok(Ok(false)) => break,
+                Ok(Err(e)) => {
+                    error!("Error draining outbox queue: {}", e);
+                    break;
+                }
+                Err(_) => {
+                    drain.interrupted = true;
+                    break;
+                }
+            }
+        }
+        drain
+    }
+
+    /// Process next item in the queue. Returns whether there was one.
+    async fn process_next(&self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
         // Get next pending item
         let item = match self.queue_service.get_next_pending().await? {
             Some(item) => item,
-            None => return Ok(()), // No pending items
+            None => return Ok(false), // No pending items
         };
 
         let id = item.id.ok_or("Queue item missing ID")?;
 
         info!("Processing outbox queue item {} for account {}", id, item.account_email);
 
-        // Mark as sending
-        if let Err(e) = self.queue_service.mark_sending(id).await {
-            error!("Failed to mark item {} as sending: {}", id, e);
-            return Ok(());
+        // Mark as sending; an item cancelled since it was fetched is dropped
+        match self.queue_service.mark_sending(id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("Outbox queue item {} is no longer pending, skipping", id);
+                return Ok(true);
+            }
+            Err(e) => {
+                error!("Failed to mark item {} as sending: {}", id, e);
+                return Ok(true);
+            }
         }
 
         // Step 1: Save to IMAP Outbox folder FIRST (so user can see it in their email client)
@@ -123,21 +211,41 @@ impl OutboxWorker {
                     if let Err(e) = self.queue_service.mark_smtp_sent(id).await {
                         error!("Failed to mark SMTP sent for item {}: {}", id, e);
                     }
+                    if let Some(event_bus) = &self.event_bus {
+                        event_bus.publish_mailbox_activity(
+                            &item.account_email,
+                            MailboxAction::Sent,
+                            None,
+                            Vec::new(),
+                            Some(item.subject.clone()),
+                            "outbox",
+                        ).await;
+                    }
                 }
                 Err(e) => {
                     error!("SMTP send failed for item {}: {}", id, e);
                     self.handle_failure(id, format!("SMTP send failed: {}", e)).await;
-                    return Ok(());
+                    return Ok(true);
                 }
             }
         }
 
         // Step 3: Save to Sent folder and remove from Outbox (completing the "move" operation)
         if !item.sent_folder_saved {
-            match self.save_to_folder(&item, "INBOX.Sent").await {
-                Ok(_) => {
-                    info!("Email saved to Sent folder");
-                    if let Err(e) = self.queue_service.mark_sent_folder_saved(id).await {
+            let sent_folder = self.sent_folder(&item.account_email).await;
+            match self.save_to_sent(&item, &sent_folder).await {
+                Ok(copy) => {
+                    let sent_uid = match copy {
+                        SentCopy::Appended(uid) => {
+                            info!("Email saved to Sent folder{}", uid.map(|u| format!(" as UID {}", u)).unwrap_or_default());
+                            uid
+                        }
+                        SentCopy::AlreadyFiled(uid) => {
+                            info!("Server already filed the email in {} as UID {}, not appending a second copy", sent_folder, uid);
+                            Some(uid)
+                        }
+                    };
+                    if let Err(e) = self.queue_service.mark_sent_folder_saved(id, sent_uid).await {
                         warn!("Failed to mark sent folder saved for item {}: {}", id, e);
                     }
 
@@ -155,7 +263,7 @@ impl OutboxWorker {
                     }
 
                     // Invalidate both Sent and Outbox caches so UI reflects the move
-                    if let Err(e) = self.cache_service.clear_folder_cache("INBOX.Sent", &item.account_email).await {
+                    if let Err(e) = self.cache_service.clear_folder_cache(&sent_folder, &item.account_email).await {
                         warn!("Failed to invalidate Sent cache for {}: {}", item.account_email, e);
                     }
                     if let Err(e) = self.cache_service.clear_folder_cache("INBOX.Outbox", &item.account_email).await {
@@ -176,12 +284,23 @@ impl OutboxWorker {
             info!("Successfully processed outbox queue item {}", id);
         }
 
-        Ok(())
+        Ok(true)
     }
 
     /// Send email via SMTP
     async fn send_via_smtp(&self, item: &crate::dashboard::services::OutboxQueueItem) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Rebuild the send request from the queue item
+        // Send the message exactly as queued, so a retry (even after a
+        // restart) delivers the same attachments and Message-ID that were
+        // appended to the Outbox
+        if !item.raw_email_bytes.is_empty() {
+            self.smtp_service
+                .send_raw_smtp_only(&item.account_email, &item.envelope_recipients(), &item.raw_email_bytes)
+                .await?;
+            return Ok(());
+        }
+
+        // Items queued without a stored message: rebuild from the fields
+        warn!("Outbox item {:?} has no stored message; rebuilding it from its fields", item.id);
         let request = crate::dashboard::services::SendEmailRequest {
             to: item.to_addresses.clone(),
             cc: item.cc_addresses.clone(),
@@ -189,6 +308,9 @@ impl OutboxWorker {
             subject: item.subject.clone(),
             body: item.body_text.clone(),
             body_html: item.body_html.clone(),
+            in_reply_to: None,
+            references: None,
+            attachments: None,
         };
 
         // Send using SMTP-only method (no IMAP operations)
@@ -198,6 +320,47 @@ impl OutboxWorker {
         Ok(())
     }
 
+    /// The account's Sent folder by special-use or provider naming, falling
+    /// back to INBOX.Sent when it can't be determined.
+    async fn sent_folder(&self, account_email: &str) -> String {
+        const FALLBACK: &str = "INBOX.Sent";
+
+        if let Some(folders) = special_use::cached(account_email) {
+            return folders.get(&SpecialUse::Sent).cloned().unwrap_or_else(|| FALLBACK.to_string());
+        }
+
+        let account = match self.account_service.lock().await.get_account(account_email).await {
+            Ok(account) => account,
+            Err(e) => {
+                warn!("Failed to get account {} to find its Sent folder: {}", account_email, e);
+                return FALLBACK.to_string();
+            }
+        };
+        let session = match self.imap_factory.create_session_for_account(&account).await {
+            Ok(session) => session,
+            Err(e) => {
+                warn!("Failed to connect to find the Sent folder for {}: {}", account_email, e);
+                return FALLBACK.to_string();
+            }
+        };
+
+        let profile = ProviderProfile::detect(account.provider_type.as_deref(), &account.imap_host);
+        let folders = special_use::resolve(&session, &account.email_address, profile).await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        match folders {
+            Ok(folders) => folders.get(&SpecialUse::Sent).cloned().unwrap_or_else(|| FALLBACK.to_string()),
+            Err(e) => {
+                warn!("Failed to list folders for {}: {}", account_email, e);
+                FALLBACK.to_string()
+            }
+        }
+    }
+
     /// Save email to IMAP folder (Outbox or Sent)
     async fn save_to_folder(&self, item: &crate::dashboard::services::OutboxQueueItem, folder: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         // Get the account for this email
@@ -227,6 +390,50 @@ impl OutboxWorker {
         Ok(())
     }
 
+    /// Put a sent message in the Sent folder. Unless the account is set to
+    /// always append, the folder is first searched for the message's
+    /// Message-ID, and a copy the server filed on its own is kept instead.
+    async fn save_to_sent(&self, item: &crate::dashboard::services::OutboxQueueItem, folder: &str) -> Result<SentCopy, Box<dyn std::error::Error + Send + Sync>> {
+        let account_service = self.account_service.lock().await;
+        let account = account_service
+            .get_account(&item.account_email)
+            .await
+            .map_err(|e| format!("Failed to get account {}: {}", item.account_email, e))?;
+        let mode = account_service.get_sent_append_mode(&item.account_email).await.unwrap_or_else(|e| {
+            warn!("Failed to read Sent append setting for {}, checking for a filed copy: {}", item.account_email, e);
+            SentAppendMode::default()
+        });
+        drop(account_service);
+
+        let session = self.imap_factory.create_session_for_account(&account).await?;
+
+        let result = async {
+            session.select_folder(folder).await?;
+
+            if let (SentAppendMode::IfMissing, Some(message_id)) = (mode, item.message_id.as_deref()) {
+                let criteria = format!("HEADER Message-ID \"{}\"", message_id.replace('\\', "\\\\").replace('"', "\\\""));
+                match session.search_emails(&criteria).await {
+                    Ok(uids) => if let Some(&uid) = uids.iter().max() {
+                        return Ok(SentCopy::AlreadyFiled(uid));
+                    },
+                    // Better a duplicate than no copy at all
+                    Err(e) => warn!("Failed to search {} for {}, appending anyway: {}", folder, message_id, e),
+                }
+            }
+
+            let flags = vec!["\\Seen".to_string()];
+            let uid = session.append_with_flags(folder, &item.raw_email_bytes, &flags, None).await?;
+            Ok::<_, ImapError>(SentCopy::Appended(uid))
+        }.await;
+
+        // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
+        if let Err(e) = session.logout().await {
+            warn!("Failed to logout IMAP session: {}", e);
+        }
+
+        Ok(result?)
+    }
+
     /// Remove email from Outbox folder after successful send
     async fn remove_from_outbox(&self, item: &crate::dashboard::services::OutboxQueueItem) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         use crate::imap::session::AsyncImapOps;
@@ -0,0 +1,315 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Pre-send recipient checks: address syntax, the domain's mail exchangers,
+//! and optionally an SMTP `RCPT TO` probe against the best MX.
+//!
+//! The probe connects to port 25, walks EHLO / MAIL FROM / RCPT TO and quits
+//! without sending DATA. Some receivers treat that as address harvesting, so
+//! it only runs when `RECIPIENT_SMTP_PROBE=true`.
+
+use std::time::Duration;
+
+use hickory_resolver::TokioResolver;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::extension::ClientId;
+use log::{debug, warn};
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Overall verdict for one address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Deliverability {
+    Deliverable,
+    Risky,
+    Undeliverable,
+    Unknown,
+}
+
+/// Outcome of the optional SMTP probe.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ProbeOutcome {
+    Accepted { code: u16 },
+    Rejected { code: u16, message: String },
+    /// 4xx (greylisting, rate limits) or a connection problem
+    Inconclusive { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecipientCheck {
+    pub address: String,
+    pub syntax_valid: bool,
+    pub domain: Option<String>,
+    pub mx_hosts: Vec<String>,
+    pub smtp_probe: Option<ProbeOutcome>,
+    pub verdict: Deliverability,
+    /// 0.0-1.0, how sure we are of `verdict`
+    pub confidence: f32,
+    pub reason: String,
+}
+
+impl RecipientCheck {
+    fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            syntax_valid: false,
+            domain: None,
+            mx_hosts: Vec::new(),
+            smtp_probe: None,
+            verdict: Deliverability::Unknown,
+            confidence: 0.0,
+            reason: String::new(),
+        }
+    }
+
+    fn conclude(mut self, verdict: Deliverability, confidence: f32, reason: impl Into<String>) -> Self {
+        self.verdict = verdict;
+        self.confidence = confidence;
+        self.reason = reason.into();
+        self
+    }
+}
+
+/// Whether the SMTP RCPT probe runs (`RECIPIENT_SMTP_PROBE`, default false).
+pub fn smtp_probe_enabled() -> bool {
+    std::env::var("RECIPIENT_SMTP_PROBE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+fn probe_timeout() -> Duration {
+    let secs = std::env::var("RECIPIENT_PROBE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10);
+    Duration::from_secs(secs)
+}
+
+/// Split an address (bare or `Name <addr>`) into its canonical form and
+/// domain, using the same parser that `send_email` builds messages with.
+pub fn parse_recipient(address: &str) -> Result<(String, String), String> {
+    let mailbox: Mailbox = address
+        .trim()
+        .parse()
+        .map_err(|e| format!("'{}' is not a valid email address: {}", address, e))?;
+    let domain = mailbox.email.domain().trim_end_matches('.').to_ascii_lowercase();
+    if domain.starts_with('[') {
+        return Err(format!("'{}' uses an address literal; only domain names can be checked", address));
+    }
+    if !domain.contains('.') {
+        return Err(format!("'{}' has no top-level domain", address));
+    }
+    Ok((mailbox.email.to_string(), domain))
+}
+
+/// Check one recipient. `probe_from` is the envelope sender used for the
+/// SMTP probe, normally the sending account's address.
+pub async fn verify_recipient(address: &str, probe_from: &str) -> RecipientCheck {
+    let mut check = RecipientCheck::new(address);
+
+    let (canonical, domain) = match parse_recipient(address) {
+        Ok(parts) => parts,
+        Err(e) => return check.conclude(Deliverability::Undeliverable, 1.0, e),
+    };
+    check.syntax_valid = true;
+    check.domain = Some(domain.clone());
+
+    let resolver = match TokioResolver::builder_tokio() {
+        Ok(builder) => builder.build(),
+        Err(e) => return check.conclude(Deliverability::Unknown, 0.0, format!("DNS resolver unavailable: {}", e)),
+    };
+
+    match resolver.mx_lookup(format!("{}.", domain)).await {
+        Ok(lookup) => {
+            let mut records: Vec<(u16, String)> = lookup
+                .iter()
+                .map(|mx| (mx.preference(), mx.exchange().to_string().trim_end_matches('.').to_string()))
+                .collect();
+            records.sort();
+            // RFC 7505 null MX: the domain explicitly accepts no mail
+            if records.len() == 1 && records[0].1.is_empty() {
+                return check.conclude(
+                    Deliverability::Undeliverable,
+                    0.95,
+                    format!("{} publishes a null MX record and accepts no mail", domain),
+                );
+            }
+            check.mx_hosts = records.into_iter().map(|(_, host)| host).filter(|h| !h.is_empty()).collect();
+        }
+        Err(e) if e.is_nx_domain() => {
+            return check.conclude(Deliverability::Undeliverable, 0.95, format!("Domain {} does not exist", domain));
+        }
+        Err(e) if e.is_no_records_found() => {
+            // RFC 5321 section 5.1: with no MX, mail goes to the domain's own address
+            return match resolver.lookup_ip(format!("{}.", domain)).await {
+                Ok(ips) if ips.iter().next().is_some() => check.conclude(
+                    Deliverability::Risky,
+                    0.5,
+                    format!("{} has no MX record; delivery would fall back to its A/AAAA record", domain),
+                ),
+                _ => check.conclude(
+                    Deliverability::Undeliverable,
+                    0.9,
+                    format!("{} has no MX or address records", domain),
+                ),
+            };
+        }
+        Err(e) => {
+            return check.conclude(Deliverability::Unknown, 0.0, format!("MX lookup for {} failed: {}", domain, e));
+        }
+    }
+
+    if !smtp_probe_enabled() {
+        return check.conclude(
+            Deliverability::Deliverable,
+            0.7,
+            format!("{} accepts mail via {}", domain, check.mx_hosts[0]),
+        );
+    }
+
+    let outcome = probe_mailbox(&check.mx_hosts[0], &canonical, probe_from).await;
+    check.smtp_probe = Some(outcome.clone());
+    match outcome {
+        // Catch-all domains accept every RCPT, so acceptance isn't proof
+        ProbeOutcome::Accepted { .. } => {
+            check.conclude(Deliverability::Deliverable, 0.9, format!("{} accepted the recipient", check.mx_hosts[0]))
+        }
+        ProbeOutcome::Rejected { code, message } => check.conclude(
+            Deliverability::Undeliverable,
+            0.9,
+            format!("{} rejected the recipient: {} {}", check.mx_hosts[0], code, message),
+        ),
+        ProbeOutcome::Inconclusive { reason } => check.conclude(
+            Deliverability::Deliverable,
+            0.6,
+            format!("{} accepts mail; SMTP probe inconclusive: {}", domain, reason),
+        ),
+    }
+}
+
+/// Check several recipients concurrently, keeping input order.
+pub async fn verify_recipients(addresses: &[String], probe_from: &str) -> Vec<RecipientCheck> {
+    futures::future::join_all(addresses.iter().map(|a| verify_recipient(a, probe_from))).await
+}
+
+/// Read one (possibly multi-line) SMTP reply and return its code and text.
+async fn read_reply<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<(u16, String), String> {
+    let mut text = Vec::new();
+    loop {
+        let mut line = String::new();
+        let n = reader.read_line(&mut line).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed by server".to_string());
+        }
+        let line = line.trim_end();
+        let code = line
+            .get(..3)
+            .and_then(|c| c.parse::<u16>().ok())
+            .ok_or_else(|| format!("malformed SMTP reply: {}", line))?;
+        text.push(line.get(4..).unwrap_or("").to_string());
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, text.join(" ")));
+        }
+    }
+}
+
+/// Map the RCPT TO reply to a probe outcome.
+fn classify_rcpt_reply(code: u16, message: String) -> ProbeOutcome {
+    match code {
+        250 | 251 => ProbeOutcome::Accepted { code },
+        500..=599 => ProbeOutcome::Rejected { code, message },
+        _ => ProbeOutcome::Inconclusive { reason: format!("{} {}", code, message) },
+    }
+}
+
+async fn probe_mailbox(mx_host: &str, recipient: &str, probe_from: &str) -> ProbeOutcome {
+    let timeout = probe_timeout();
+    match tokio::time::timeout(timeout, run_probe(mx_host, recipient, probe_from)).await {
+        Ok(Ok(outcome)) => outcome,
+        Ok(Err(e)) => {
+            warn!("SMTP probe of {} via {} failed: {}", recipient, mx_host, e);
+            ProbeOutcome::Inconclusive { reason: e }
+        }
+        Err(_) => ProbeOutcome::Inconclusive { reason: format!("timed out after {:?}", timeout) },
+    }
+}
+
+async fn run_probe(mx_host: &str, recipient: &str, probe_from: &str) -> Result<ProbeOutcome, String> {
+    debug!("Probing {} via {}:25", recipient, mx_host);
+    let stream = TcpStream::connect((mx_host, 25)).await.map_err(|e| e.to_string())?;
+    let mut stream = BufReader::new(stream);
+
+    let expect_ok = |(code, text): (u16, String), step: &str| -> Result<(), String> {
+        if (200..400).contains(&code) {
+            Ok(())
+        } else {
+            Err(format!("{} refused: {} {}", step, code, text))
+        }
+    };
+
+    expect_ok(read_reply(&mut stream).await?, "greeting")?;
+    let commands = [
+        (format!("EHLO {}\r\n", ClientId::default()), "EHLO"),
+        (format!("MAIL FROM:<{}>\r\n", probe_from), "MAIL FROM"),
+    ];
+    for (command, step) in commands {
+        stream.get_mut().write_all(command.as_bytes()).await.map_err(|e| e.to_string())?;
+        expect_ok(read_reply(&mut stream).await?, step)?;
+    }
+
+    stream
+        .get_mut()
+        .write_all(format!("RCPT TO:<{}>\r\n", recipient).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    let (code, message) = read_reply(&mut stream).await?;
+
+    // Best effort; the verdict is already known
+    let _ = stream.get_mut().write_all(b"QUIT\r\n").await;
+    Ok(classify_rcpt_reply(code, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recipient() {
+        assert_eq!(
+            parse_recipient("Jane Doe <Jane.Doe@Example.COM>").unwrap(),
+            ("Jane.Doe@Example.COM".to_string(), "example.com".to_string())
+        );
+        assert!(parse_recipient("jane.doe@example").is_err());
+        assert!(parse_recipient("jane.doe.example.com").is_err());
+        assert!(parse_recipient("jane@[192.0.2.1]").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_syntax_is_undeliverable() {
+        let check = verify_recipient("not an address", "me@example.com").await;
+        assert!(!check.syntax_valid);
+        assert_eq!(check.verdict, Deliverability::Undeliverable);
+    }
+
+    #[tokio::test]
+    async fn test_read_multiline_reply() {
+        let data: &[u8] = b"250-mx.example.com\r\n250-PIPELINING\r\n250 SIZE 1000\r\n";
+        let mut reader = BufReader::new(data);
+        let (code, text) = read_reply(&mut reader).await.unwrap();
+        assert_eq!(code, 250);
+        assert_eq!(text, "mx.example.com PIPELINING SIZE 1000");
+    }
+
+    #[test]
+    fn test_classify_rcpt_reply() {
+        assert_eq!(classify_rcpt_reply(250, "OK".into()), ProbeOutcome::Accepted { code: 250 });
+        assert!(matches!(classify_rcpt_reply(550, "No such user".into()), ProbeOutcome::Rejected { code: 550, .. }));
+        assert!(matches!(classify_rcpt_reply(451, "Greylisted".into()), ProbeOutcome::Inconclusive { .. }));
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Addressing for replies.
+//!
+//! A reply goes to the original's `Reply-To` when it has one and to `From`
+//! otherwise (RFC 5322 §3.6.2). Mailing-list replies go to the `List-Post`
+//! address (RFC 2369), which lists often leave out of `Reply-To`.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    static ref ADDRESS: Regex = Regex::new(r"[A-Za-z0-9.!#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?)+").unwrap();
+    static ref MAILTO: Regex = Regex::new(r"(?i)<\s*mailto:([^>?\s]+)").unwrap();
+}
+
+/// Reply-related headers of a cached message. The IMAP envelope can't be
+/// used for `Reply-To` since servers fill it in from `From` when absent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplyHeaders {
+    pub reply_to: Vec<String>,
+    /// Posting address from `List-Post`; `None` also when the list says `NO`
+    pub list_post: Option<String>,
+}
+
+impl ReplyHeaders {
+    pub fn from_message(message: &mail_parser::Message) -> Self {
+        Self {
+            reply_to: message.header_raw("Reply-To").map(header_addresses).unwrap_or_default(),
+            list_post: message.header_raw("List-Post").and_then(list_post_address),
+        }
+    }
+}
+
+/// Addresses in a raw address-list header, ignoring display names.
+pub fn header_addresses(raw: &str) -> Vec<String> {
+    let mut addresses: Vec<String> = Vec::new();
+    for m in ADDRESS.find_iter(raw) {
+        let address = m.as_str().to_string();
+        if !addresses.iter().any(|a| a.eq_ignore_ascii_case(&address)) {
+            addresses.push(address);
+        }
+    }
+    addresses
+}
+
+/// First `mailto:` address of a `List-Post` header.
+pub fn list_post_address(raw: &str) -> Option<String> {
+    MAILTO.captures(raw).map(|c| c[1].trim().to_string())
+}
+
+/// Who a reply is addressed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplyMode {
+    /// Reply-To, or From when there is no Reply-To
+    #[default]
+    Sender,
+    /// The sender plus every other To/Cc recipient
+    All,
+    /// The list's List-Post address, or the sender for non-list mail
+    List,
+}
+
+impl std::str::FromStr for ReplyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sender" => Ok(ReplyMode::Sender),
+            "all" => Ok(ReplyMode::All),
+            "list" => Ok(ReplyMode::List),
+            other => Err(format!("Unknown reply mode '{}'; expected sender, all or list", other)),
+        }
+    }
+}
+
+/// The message being replied to.
+#[derive(Debug, Clone, Default)]
+pub struct ReplySource {
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub subject: Option<String>,
+    pub message_id: Option<String>,
+    pub references: Option<String>,
+    pub headers: ReplyHeaders,
+}
+
+/// Recipients and threading headers for a reply.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplyEnvelope {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub subject: String,
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+}
+
+/// Address a reply to `source`. `own_addresses` (the replying account and
+/// its aliases) are never copied on a reply-all.
+pub fn build_reply(source: &ReplySource, mode: ReplyMode, own_addresses: &[String]) -> ReplyEnvelope {
+    let sender: Vec<String> = if source.headers.reply_to.is_empty() {
+        source.from.iter().flat_map(|f| header_addresses(f)).collect()
+    } else {
+        source.headers.reply_to.clone()
+    };
+
+    let to = match (mode, &source.headers.list_post) {
+        (ReplyMode::List, Some(list)) => vec![list.clone()],
+        _ => sender,
+    };
+
+    let mut cc: Vec<String> = Vec::new();
+    if mode == ReplyMode::All {
+        let is_own = |a: &str| own_addresses.iter().any(|o| o.eq_ignore_ascii_case(a));
+        for address in source.to.iter().chain(&source.cc).flat_map(|a| header_addresses(a)) {
+            let seen = to.iter().chain(&cc).any(|a| a.eq_ignore_ascii_case(&address));
+            if !seen && !is_own(&address) {
+                cc.push(address);
+            }
+        }
+    }
+
+    let subject = source.subject.as_deref().unwrap_or("").trim();
+    let subject = if subject.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("re:")) {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    };
+
+    let references = match (&source.references, &source.message_id) {
+        (Some(refs), Some(id)) if !refs.contains(id.as_str()) => Some(format!("{} {}", refs.trim(), id)),
+        (Some(refs), _) => Some(refs.trim().to_string()),
+        (None, id) => id.clone(),
+    };
+
+    ReplyEnvelope {
+        to,
+        cc,
+        subject,
+        in_reply_to: source.message_id.clone(),
+        references,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_message() -> ReplySource {
+        ReplySource {
+            from: Some("Alice <alice@example.com>".to_string()),
+            to: vec!["dev@lists.example.org".to_string()],
+            cc: vec!["Bob <bob@example.com>".to_string(), "me@example.com".to_string()],
+            subject: Some("RE: Release plan".to_string()),
+            message_id: Some("<m2@example.com>".to_string()),
+            references: Some("<m1@example.com>".to_string()),
+            headers: ReplyHeaders {
+                reply_to: vec!["alice.private@example.com".to_string()],
+                list_post: list_post_address("<mailto:dev@lists.example.org>"),
+            },
+        }
+    }
+
+    #[test]
+    fn test_build_reply_prefers_reply_to_and_list_post() {
+        let own = vec!["ME@example.com".to_string()];
+        let source = list_message();
+
+        let sender = build_reply(&source, ReplyMode::Sender, &own);
+        assert_eq!(sender.to, vec!["alice.private@example.com"]);
+        assert!(sender.cc.is_empty());
+        assert_eq!(sender.subject, "RE: Release plan");
+        assert_eq!(sender.references.as_deref(), Some("<m1@example.com> <m2@example.com>"));
+
+        let list = build_reply(&source, ReplyMode::List, &own);
+        assert_eq!(list.to, vec!["dev@lists.example.org"]);
+
+        let all = build_reply(&source, ReplyMode::All, &own);
+        assert_eq!(all.cc, vec!["dev@lists.example.org", "bob@example.com"]);
+
+        // Without Reply-To or List-Post everything falls back to From
+        let plain = ReplySource { headers: ReplyHeaders::default(), ..list_message() };
+        assert_eq!(build_reply(&plain, ReplyMode::List, &own).to, vec!["alice@example.com"]);
+    }
+
+    #[test]
+    fn test_reply_headers_from_message() {
+        let raw = b"From: a@example.com\r\nReply-To: \"Team, Support\" <support@example.com>, b@example.com\r\nList-Post: NO\r\nSubject: x\r\n\r\nbody";
+        let message = mail_parser::Message::parse(raw).unwrap();
+        let headers = ReplyHeaders::from_message(&message);
+        assert_eq!(headers.reply_to, vec!["support@example.com", "b@example.com"]);
+        assert_eq!(headers.list_post, None);
+    }
+}
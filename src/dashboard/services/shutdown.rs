@@ -0,0 +1,118 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Graceful shutdown on SIGTERM or Ctrl-C.
+//!
+//! The outbox stops taking new mail and the HTTP server finishes the
+//! requests it has. Once it has stopped, the outbox worker finishes the
+//! item it's on and sends what is due, the IMAP pools log out, and the
+//! SQLite pools are closed. The outbox and the pools each get
+//! `SHUTDOWN_DRAIN_TIMEOUT_SECONDS` (default 30).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::connection_pool::PoolDrain;
+use super::outbox_worker::{OutboxDrain, OutboxWorker};
+use super::{CacheService, DashboardState, EmailService, OutboxQueueService};
+
+/// How long the outbox and the connection pools each get to drain
+/// (`SHUTDOWN_DRAIN_TIMEOUT_SECONDS`, default 30).
+pub fn drain_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("SHUTDOWN_DRAIN_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30),
+    )
+}
+
+/// Resolves on SIGTERM or Ctrl-C.
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => info!("SIGTERM received"),
+                    _ = tokio::signal::ctrl_c() => info!("Ctrl-C received"),
+                }
+                return;
+            }
+            Err(e) => warn!("Failed to listen for SIGTERM, only Ctrl-C will shut down gracefully: {}", e),
+        }
+    }
+    if tokio::signal::ctrl_c().await.is_ok() {
+        info!("Ctrl-C received");
+    }
+}
+
+/// What was drained at shutdown and what was left.
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    pub outbox: OutboxDrain,
+    /// Outbox items still pending or in flight, sent on the next start
+    pub outbox_left: i64,
+    pub connections: PoolDrain,
+}
+
+pub struct ShutdownCoordinator {
+    outbox_queue: Arc<OutboxQueueService>,
+    outbox_worker: Arc<OutboxWorker>,
+    email_service: Arc<EmailService>,
+    cache_service: Arc<CacheService>,
+    timeout: Duration,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(state: &DashboardState, outbox_worker: Arc<OutboxWorker>) -> Self {
+        Self {
+            outbox_queue: Arc::clone(&state.outbox_queue_service),
+            outbox_worker,
+            email_service: Arc::clone(&state.email_service),
+            cache_service: Arc::clone(&state.cache_service),
+            timeout: drain_timeout(),
+        }
+    }
+
+    /// First step, as soon as the signal arrives: refuse new outbox items
+    /// while the HTTP server finishes its requests.
+    pub fn begin(&self) {
+        info!("Shutting down: no longer accepting outbox items");
+        self.outbox_queue.stop_accepting();
+    }
+
+    /// Everything after the HTTP server has stopped.
+    pub async fn drain(&self) -> ShutdownReport {
+        self.begin();
+        let mut report = ShutdownReport {
+            outbox: self.outbox_worker.drain(self.timeout).await,
+            ..Default::default()
+        };
+        report.outbox_left = self.outbox_queue.unfinished_count().await.unwrap_or_else(|e| {
+            warn!("Failed to count unsent outbox items: {}", e);
+            0
+        });
+        report.connections = self.email_service.drain_connections(self.timeout).await;
+
+        self.outbox_queue.close().await;
+        self.cache_service.close().await;
+
+        info!(
+            "Shutdown drain: {} outbox item(s) processed{}, {} left for the next start; \
+             {} IMAP connection(s) logged out, {} failed to log out, {} abandoned",
+            report.outbox.processed,
+            if report.outbox.interrupted { ", one cut off by the timeout" } else { "" },
+            report.outbox_left,
+            report.connections.logged_out,
+            report.connections.logout_failed,
+            report.connections.abandoned,
+        );
+        report
+    }
+}
@@ -3,8 +3,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use base64::Engine;
 use lettre::{
-    message::{header::ContentType, Mailbox, MultiPart, SinglePart},
+    message::{header::ContentType, Attachment, Mailbox, MessageBuilder, MultiPart, SinglePart},
     transport::smtp::authentication::Credentials,
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
@@ -17,12 +18,18 @@ use tokio::sync::Mutex as TokioMutex;
 use tokio::time::timeout;
 use chrono;
 
-use super::account::{AccountService};
+use super::account::{Account, AccountService};
+use super::dkim::DkimSigner;
+use crate::imap::error::ImapError;
+use crate::imap::provider_profile::ProviderProfile;
+use crate::imap::special_use::{self, SpecialUse};
 use crate::prelude::CloneableImapSessionFactory;
 
 // Folder name constants (can be configured via environment or config file in the future)
 const OUTBOX_FOLDER: &str = "INBOX.Outbox";
 const SENT_FOLDER: &str = "INBOX.Sent";
+/// Drafts folder created when the account has none
+const DEFAULT_DRAFTS_FOLDER: &str = "Drafts";
 
 #[derive(Error, Debug)]
 pub enum SmtpError {
@@ -40,6 +47,41 @@ pub enum SmtpError {
 
     #[error("SMTP credentials not configured for account: {0}")]
     MissingCredentials(String),
+
+    #[error("Attachment error: {0}")]
+    AttachmentError(String),
+
+    #[error("Attachments total {total} bytes, over the {limit} byte limit (SMTP_MAX_ATTACHMENT_MB)")]
+    AttachmentsTooLarge { total: u64, limit: u64 },
+}
+
+impl SmtpError {
+    /// Wrap a transport error, spelling out TLS failures (including servers
+    /// that can't meet `TLS_MIN_VERSION`) with the host they came from.
+    fn from_transport(host: &str, err: lettre::transport::smtp::Error) -> Self {
+        if err.is_tls() {
+            SmtpError::ConfigError(crate::tls::handshake_error(host, &err))
+        } else {
+            SmtpError::SendError(err)
+        }
+    }
+}
+
+/// SMTP envelope for a pre-built message. Recipients may be bare addresses
+/// or `Name <addr>` mailboxes; duplicates are sent once.
+pub fn envelope_for(from: &str, recipients: &[String]) -> Result<lettre::address::Envelope, SmtpError> {
+    let from: Mailbox = from.parse()
+        .map_err(|e| SmtpError::ConfigError(format!("Invalid from address {}: {}", from, e)))?;
+    let mut to: Vec<lettre::Address> = Vec::with_capacity(recipients.len());
+    for recipient in recipients {
+        let mailbox: Mailbox = recipient.parse()
+            .map_err(|e| SmtpError::ConfigError(format!("Invalid recipient address {}: {}", recipient, e)))?;
+        if !to.contains(&mailbox.email) {
+            to.push(mailbox.email);
+        }
+    }
+    lettre::address::Envelope::new(Some(from.email), to)
+        .map_err(|e| SmtpError::ConfigError(format!("Invalid envelope: {}", e)))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -50,6 +92,237 @@ pub struct SendEmailRequest {
     pub subject: String,
     pub body: String,
     pub body_html: Option<String>,
+    /// Message-ID of the message this one replies to
+    #[serde(default)]
+    pub in_reply_to: Option<String>,
+    /// Message-IDs of the thread so far, oldest first, space-separated
+    #[serde(default)]
+    pub references: Option<String>,
+    #[serde(default)]
+    pub attachments: Option<Vec<OutgoingAttachment>>,
+}
+
+/// A file to attach to an outgoing message, given either inline as base64
+/// `data` or as a `path` inside the attachment storage directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutgoingAttachment {
+    pub filename: String,
+    /// MIME type, `application/octet-stream` when not given
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Base64-encoded content
+    #[serde(default)]
+    pub data: Option<String>,
+    /// File under ATTACHMENTS_STORAGE_PATH, absolute or relative to it
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Makes this an inline part the HTML body can reference as `cid:<content_id>`
+    #[serde(default)]
+    pub content_id: Option<String>,
+}
+
+/// An attachment's content, read and checked.
+struct LoadedAttachment {
+    filename: String,
+    content_type: ContentType,
+    content_id: Option<String>,
+    bytes: Vec<u8>,
+}
+
+impl LoadedAttachment {
+    /// `Content-Disposition: attachment` part
+    fn attachment_part(self) -> SinglePart {
+        Attachment::new(self.filename).body(self.bytes, self.content_type)
+    }
+
+    /// `Content-Disposition: inline` part with a Content-ID
+    fn inline_part(self) -> SinglePart {
+        let content_id = self.content_id.unwrap_or_default();
+        Attachment::new_inline(content_id).body(self.bytes, self.content_type)
+    }
+}
+
+/// Largest total size of one message's attachments, after decoding
+/// (`SMTP_MAX_ATTACHMENT_MB`, default 25).
+pub fn max_attachment_bytes() -> u64 {
+    std::env::var("SMTP_MAX_ATTACHMENT_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(25u64)
+        * 1024 * 1024
+}
+
+/// Read and decode every attachment, refusing the lot once their total size
+/// passes `limit` (file sizes are checked before reading).
+async fn load_attachments(attachments: &[OutgoingAttachment], limit: u64) -> Result<Vec<LoadedAttachment>, SmtpError> {
+    let mut loaded = Vec::with_capacity(attachments.len());
+    let mut total: u64 = 0;
+    for attachment in attachments {
+        let bytes = match (&attachment.data, &attachment.path) {
+            (Some(data), None) => {
+                // Base64 is 4 characters per 3 bytes
+                let estimate = data.len() as u64 / 4 * 3;
+                if total + estimate > limit {
+                    return Err(SmtpError::AttachmentsTooLarge { total: total + estimate, limit });
+                }
+                base64::engine::general_purpose::STANDARD
+                    .decode(data.trim())
+                    .map_err(|e| SmtpError::AttachmentError(format!("{}: invalid base64 data: {}", attachment.filename, e)))?
+            }
+            (None, Some(path)) => {
+                let full_path = super::attachment_storage::stored_file_path(path)
+                    .map_err(|e| SmtpError::AttachmentError(format!("{}: {}", attachment.filename, e)))?;
+                let size = tokio::fs::metadata(&full_path).await
+                    .map_err(|e| SmtpError::AttachmentError(format!("{}: {}", attachment.filename, e)))?
+                    .len();
+                if total + size > limit {
+                    return Err(SmtpError::AttachmentsTooLarge { total: total + size, limit });
+                }
+                tokio::fs::read(&full_path).await
+                    .map_err(|e| SmtpError::AttachmentError(format!("{}: {}", attachment.filename, e)))?
+            }
+            _ => {
+                return Err(SmtpError::AttachmentError(format!(
+                    "{}: give exactly one of 'data' or 'path'", attachment.filename
+                )));
+            }
+        };
+        total += bytes.len() as u64;
+        if total > limit {
+            return Err(SmtpError::AttachmentsTooLarge { total, limit });
+        }
+
+        let content_type = attachment.content_type.as_deref().unwrap_or("application/octet-stream");
+        let content_type = ContentType::parse(content_type)
+            .map_err(|e| SmtpError::AttachmentError(format!("{}: invalid content type {}: {}", attachment.filename, content_type, e)))?;
+        loaded.push(LoadedAttachment {
+            filename: attachment.filename.clone(),
+            content_type,
+            content_id: attachment.content_id.as_deref()
+                .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+                .filter(|id| !id.is_empty()),
+            bytes,
+        });
+    }
+    Ok(loaded)
+}
+
+/// Finish `builder` with the request's body: plain text, an HTML alternative
+/// when given (with its `content_id` images as multipart/related), and a
+/// multipart/mixed wrapper when there are file attachments. Inline images
+/// without an HTML body are attached as ordinary files.
+pub async fn build_message(builder: MessageBuilder, request: &SendEmailRequest) -> Result<Message, SmtpError> {
+    let attachments = load_attachments(request.attachments.as_deref().unwrap_or(&[]), max_attachment_bytes()).await?;
+    let has_html = request.body_html.is_some();
+    let (inline, files): (Vec<_>, Vec<_>) = attachments.into_iter()
+        .partition(|a| has_html && a.content_id.is_some());
+
+    let text = || SinglePart::builder()
+        .header(header::ContentType::TEXT_PLAIN)
+        .body(request.body.clone());
+    let alternative = request.body_html.as_ref().map(|html| {
+        let html = SinglePart::builder()
+            .header(header::ContentType::TEXT_HTML)
+            .body(html.clone());
+        if inline.is_empty() {
+            MultiPart::alternative().singlepart(text()).singlepart(html)
+        } else {
+            let related = inline.into_iter()
+                .fold(MultiPart::related().singlepart(html), |related, a| related.singlepart(a.inline_part()));
+            MultiPart::alternative().singlepart(text()).multipart(related)
+        }
+    });
+
+    if files.is_empty() {
+        return Ok(match alternative {
+            Some(alternative) => builder.multipart(alternative)?,
+            None => builder.header(ContentType::TEXT_PLAIN).body(request.body.clone())?,
+        });
+    }
+    let mixed = match alternative {
+        Some(alternative) => MultiPart::mixed().multipart(alternative),
+        None => MultiPart::mixed().singlepart(text()),
+    };
+    let mixed = files.into_iter().fold(mixed, |mixed, a| mixed.singlepart(a.attachment_part()));
+    Ok(builder.multipart(mixed)?)
+}
+
+/// Headers of an outgoing message from `account`: From with the display
+/// name, the account's Reply-To, Subject and the request's recipients.
+fn message_builder(account: &Account, reply_to: Option<&str>, request: &SendEmailRequest) -> Result<MessageBuilder, SmtpError> {
+    // Build from address with properly quoted display name
+    let from_mailbox: Mailbox = if account.display_name.is_empty() {
+        // Just use the email address if no display name
+        account.email_address
+            .parse()
+            .map_err(|e| SmtpError::ConfigError(format!("Invalid from address: {}", e)))?
+    } else {
+        // Quote the display name if it contains special characters
+        let quoted_name = if account.display_name.contains(|c: char| "()<>[]:;@\\,\"".contains(c)) {
+            format!("\"{}\"", account.display_name.replace('\"', "\\\""))
+        } else {
+            account.display_name.clone()
+        };
+        format!("{} <{}>", quoted_name, account.email_address)
+            .parse()
+            .map_err(|e| SmtpError::ConfigError(format!("Invalid from address: {}", e)))?
+    };
+
+    // Build email message
+    let mut email_builder = Message::builder()
+        .from(from_mailbox)
+        .subject(&request.subject);
+
+    // Account-level Reply-To, e.g. a shared support address
+    if let Some(reply_to) = reply_to {
+        email_builder = email_builder.reply_to(reply_to.parse().map_err(|e| {
+            SmtpError::ConfigError(format!("Invalid reply_to address {}: {}", reply_to, e))
+        })?);
+    }
+
+    // Threading headers, so a reply joins the original's conversation
+    if let Some(in_reply_to) = &request.in_reply_to {
+        email_builder = email_builder.in_reply_to(in_reply_to.clone());
+    }
+    if let Some(references) = &request.references {
+        email_builder = email_builder.references(references.clone());
+    }
+
+    // Add To recipients
+    for to_addr in &request.to {
+        email_builder = email_builder.to(to_addr.parse().map_err(|e| {
+            SmtpError::ConfigError(format!("Invalid to address {}: {}", to_addr, e))
+        })?);
+    }
+
+    // Add CC recipients
+    if let Some(cc_addrs) = &request.cc {
+        for cc_addr in cc_addrs {
+            email_builder = email_builder.cc(cc_addr.parse().map_err(|e| {
+                SmtpError::ConfigError(format!("Invalid cc address {}: {}", cc_addr, e))
+            })?);
+        }
+    }
+
+    // Add BCC recipients
+    if let Some(bcc_addrs) = &request.bcc {
+        for bcc_addr in bcc_addrs {
+            email_builder = email_builder.bcc(bcc_addr.parse().map_err(|e| {
+                SmtpError::ConfigError(format!("Invalid bcc address {}: {}", bcc_addr, e))
+            })?);
+        }
+    }
+
+    Ok(email_builder)
+}
+
+/// Where a draft was saved.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedDraft {
+    pub folder: String,
+    /// Set when the server reports APPENDUID
+    pub uid: Option<u32>,
+    pub message_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -62,6 +335,7 @@ pub struct SendEmailResponse {
 pub struct SmtpService {
     account_service: Arc<TokioMutex<AccountService>>,
     imap_session_factory: CloneableImapSessionFactory,
+    dkim: Option<Arc<DkimSigner>>,
 }
 
 impl SmtpService {
@@ -72,6 +346,22 @@ impl SmtpService {
         Self {
             account_service,
             imap_session_factory,
+            dkim: None,
+        }
+    }
+
+    /// DKIM-sign everything handed to the SMTP transport.
+    pub fn with_dkim(mut self, signer: Arc<DkimSigner>) -> Self {
+        self.dkim = Some(signer);
+        self
+    }
+
+    /// Bytes to hand to the transport: signed when DKIM is set up, and sent
+    /// unsigned (with a warning) if the sender's domain has no key.
+    fn outgoing_bytes(&self, sender: &str, message: &[u8]) -> Vec<u8> {
+        match &self.dkim {
+            Some(signer) => signer.sign_or_passthrough(sender, message),
+            None => message.to_vec(),
         }
     }
 
@@ -86,6 +376,11 @@ impl SmtpService {
             .get_account(account_email)
             .await
             .map_err(|_| SmtpError::AccountNotFound(account_email.to_string()))?;
+        let default_reply_to = account_service
+            .get_default_reply_to(account_email)
+            .await
+            .ok()
+            .flatten();
 
         // Validate SMTP configuration
         let smtp_host = account
@@ -104,72 +399,10 @@ impl SmtpService {
         let smtp_port = account.smtp_port.unwrap_or(587) as u16;
         let use_starttls = account.smtp_use_starttls.unwrap_or(true);
 
-        // Build from address with properly quoted display name
-        let from_mailbox: Mailbox = if account.display_name.is_empty() {
-            // Just use the email address if no display name
-            account.email_address
-                .parse()
-                .map_err(|e| SmtpError::ConfigError(format!("Invalid from address: {}", e)))?
-        } else {
-            // Quote the display name if it contains special characters
-            let quoted_name = if account.display_name.contains(|c: char| "()<>[]:;@\\,\"".contains(c)) {
-                format!("\"{}\"", account.display_name.replace('\"', "\\\""))
-            } else {
-                account.display_name.clone()
-            };
-            format!("{} <{}>", quoted_name, account.email_address)
-                .parse()
-                .map_err(|e| SmtpError::ConfigError(format!("Invalid from address: {}", e)))?
-        };
-
-        // Build email message
-        let mut email_builder = Message::builder()
-            .from(from_mailbox)
-            .subject(&request.subject);
+        let email_builder = message_builder(&account, default_reply_to.as_deref(), &request)?;
 
-        // Add To recipients
-        for to_addr in &request.to {
-            email_builder = email_builder.to(to_addr.parse().map_err(|e| {
-                SmtpError::ConfigError(format!("Invalid to address {}: {}", to_addr, e))
-            })?);
-        }
-
-        // Add CC recipients
-        if let Some(cc_addrs) = &request.cc {
-            for cc_addr in cc_addrs {
-                email_builder = email_builder.cc(cc_addr.parse().map_err(|e| {
-                    SmtpError::ConfigError(format!("Invalid cc address {}: {}", cc_addr, e))
-                })?);
-            }
-        }
-
-        // Add BCC recipients
-        if let Some(bcc_addrs) = &request.bcc {
-            for bcc_addr in bcc_addrs {
-                email_builder = email_builder.bcc(bcc_addr.parse().map_err(|e| {
-                    SmtpError::ConfigError(format!("Invalid bcc address {}: {}", bcc_addr, e))
-                })?);
-            }
-        }
-
-        // Build multipart body (plain text + optional HTML)
-        let email = if let Some(html_body) = &request.body_html {
-            email_builder.multipart(
-                MultiPart::alternative()
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(header::ContentType::TEXT_PLAIN)
-                            .body(request.body.clone()),
-                    )
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(header::ContentType::TEXT_HTML)
-                            .body(html_body.clone()),
-                    ),
-            )?
-        } else {
-            email_builder.header(ContentType::TEXT_PLAIN).body(request.body.clone())?
-        };
+        // Build the body (plain text, optional HTML, attachments)
+        let email = build_message(email_builder, &request).await?;
 
         // Get message ID before sending
         let message_id = email
@@ -180,19 +413,10 @@ impl SmtpService {
         // Build SMTP transport
         let creds = Credentials::new(smtp_user.clone(), smtp_pass.clone());
 
-        let mailer = if use_starttls {
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        } else {
-            AsyncSmtpTransport::<Tokio1Executor>::relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        };
+        let mailer = super::smtp_auth::relay_builder(smtp_host, use_starttls)?
+            .port(smtp_port)
+            .credentials(creds)
+            .build();
 
         // Convert email to RFC822 format for IMAP operations
         let email_bytes = email.formatted();
@@ -212,7 +436,8 @@ impl SmtpService {
 
         // Step 2: Now try to send via SMTP (Outbox has the email, so user can see it)
         log::info!("Attempting to send email via SMTP...");
-        match mailer.send(email.clone()).await {
+        let outgoing = self.outgoing_bytes(&account.email_address, &email_bytes);
+        match mailer.send_raw(email.envelope(), &outgoing).await {
             Ok(_) => {
                 log::info!("Email sent successfully via SMTP");
 
@@ -244,7 +469,7 @@ impl SmtpService {
             }
             Err(e) => {
                 log::error!("SMTP send failed: {}. Email remains in Outbox - please check Outbox folder to retry.", e);
-                Err(SmtpError::SendError(e))
+                Err(SmtpError::from_transport(smtp_host, e))
             }
         }
     }
@@ -598,6 +823,11 @@ impl SmtpService {
             .get_account(account_email)
             .await
             .map_err(|_| SmtpError::AccountNotFound(account_email.to_string()))?;
+        let default_reply_to = account_service
+            .get_default_reply_to(account_email)
+            .await
+            .ok()
+            .flatten();
 
         // Validate SMTP configuration
         let smtp_host = account
@@ -616,70 +846,10 @@ impl SmtpService {
         let smtp_port = account.smtp_port.unwrap_or(587) as u16;
         let use_starttls = account.smtp_use_starttls.unwrap_or(true);
 
-        // Build from address with properly quoted display name
-        let from_mailbox: Mailbox = if account.display_name.is_empty() {
-            account.email_address
-                .parse()
-                .map_err(|e| SmtpError::ConfigError(format!("Invalid from address: {}", e)))?
-        } else {
-            let quoted_name = if account.display_name.contains(|c: char| "()<>[]:;@\\,\"".contains(c)) {
-                format!("\"{}\"", account.display_name.replace('\"', "\\\""))
-            } else {
-                account.display_name.clone()
-            };
-            format!("{} <{}>", quoted_name, account.email_address)
-                .parse()
-                .map_err(|e| SmtpError::ConfigError(format!("Invalid from address: {}", e)))?
-        };
+        let email_builder = message_builder(&account, default_reply_to.as_deref(), &request)?;
 
-        // Build email message
-        let mut email_builder = Message::builder()
-            .from(from_mailbox)
-            .subject(&request.subject);
-
-        // Add To recipients
-        for to_addr in &request.to {
-            email_builder = email_builder.to(to_addr.parse().map_err(|e| {
-                SmtpError::ConfigError(format!("Invalid to address {}: {}", to_addr, e))
-            })?);
-        }
-
-        // Add CC recipients
-        if let Some(cc_addrs) = &request.cc {
-            for cc_addr in cc_addrs {
-                email_builder = email_builder.cc(cc_addr.parse().map_err(|e| {
-                    SmtpError::ConfigError(format!("Invalid cc address {}: {}", cc_addr, e))
-                })?);
-            }
-        }
-
-        // Add BCC recipients
-        if let Some(bcc_addrs) = &request.bcc {
-            for bcc_addr in bcc_addrs {
-                email_builder = email_builder.bcc(bcc_addr.parse().map_err(|e| {
-                    SmtpError::ConfigError(format!("Invalid bcc address {}: {}", bcc_addr, e))
-                })?);
-            }
-        }
-
-        // Build multipart body (plain text + optional HTML)
-        let email = if let Some(html_body) = &request.body_html {
-            email_builder.multipart(
-                MultiPart::alternative()
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(header::ContentType::TEXT_PLAIN)
-                            .body(request.body.clone()),
-                    )
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(header::ContentType::TEXT_HTML)
-                            .body(html_body.clone()),
-                    ),
-            )?
-        } else {
-            email_builder.header(ContentType::TEXT_PLAIN).body(request.body.clone())?
-        };
+        // Build the body (plain text, optional HTML, attachments)
+        let email = build_message(email_builder, &request).await?;
 
         // Get message ID before sending
         let message_id = email
@@ -691,28 +861,56 @@ impl SmtpService {
         // Build SMTP transport
         let creds = Credentials::new(smtp_user.clone(), smtp_pass.clone());
 
-        let mailer: AsyncSmtpTransport<Tokio1Executor> = if use_starttls {
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        } else {
-            AsyncSmtpTransport::<Tokio1Executor>::relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        };
+        let mailer: AsyncSmtpTransport<Tokio1Executor> = super::smtp_auth::relay_builder(smtp_host, use_starttls)?
+            .port(smtp_port)
+            .credentials(creds)
+            .build();
 
         // Send via SMTP (no IMAP operations)
         log::info!("Sending email via SMTP only (no IMAP operations)...");
-        mailer.send(email).await?;
+        let outgoing = self.outgoing_bytes(&account.email_address, &email.formatted());
+        mailer.send_raw(email.envelope(), &outgoing).await.map_err(|e| SmtpError::from_transport(smtp_host, e))?;
         log::info!("Email sent successfully via SMTP");
 
         Ok(message_id)
     }
 
+    /// Send an already-assembled RFC 5322 message via SMTP only, used by the
+    /// outbox worker so retries deliver exactly the bytes that were queued
+    /// (attachments and Message-ID included) instead of rebuilding them.
+    /// `recipients` must list every envelope recipient, Bcc included, since
+    /// the queued bytes carry no Bcc header.
+    pub async fn send_raw_smtp_only(
+        &self,
+        account_email: &str,
+        recipients: &[String],
+        raw_email: &[u8],
+    ) -> Result<(), SmtpError> {
+        let account_service = self.account_service.lock().await;
+        let account = account_service
+            .get_account(account_email)
+            .await
+            .map_err(|_| SmtpError::AccountNotFound(account_email.to_string()))?;
+        drop(account_service);
+
+        let smtp_host = account
+            .smtp_host
+            .as_ref()
+            .ok_or_else(|| SmtpError::MissingCredentials(account_email.to_string()))?;
+
+        let envelope = envelope_for(&account.email_address, recipients)?;
+
+        // XOAUTH2 for OAuth accounts, password auth otherwise
+        let mailer = super::smtp_auth::build_smtp_transport(&account)?;
+
+        log::info!("Sending queued message ({} bytes) via SMTP only...", raw_email.len());
+        let outgoing = self.outgoing_bytes(&account.email_address, raw_email);
+        mailer.send_raw(&envelope, &outgoing).await.map_err(|e| SmtpError::from_transport(smtp_host, e))?;
+        log::info!("Email sent successfully via SMTP");
+
+        Ok(())
+    }
+
     pub async fn test_smtp_connection(&self, account_email: &str) -> Result<(), SmtpError> {
         // Get account details
         let account_service = self.account_service.lock().await;
@@ -741,56 +939,54 @@ impl SmtpService {
         // Build SMTP transport
         let creds = Credentials::new(smtp_user.clone(), smtp_pass.clone());
 
-        let mailer: AsyncSmtpTransport<Tokio1Executor> = if use_starttls {
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        } else {
-            AsyncSmtpTransport::<Tokio1Executor>::relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        };
+        let mailer: AsyncSmtpTransport<Tokio1Executor> = super::smtp_auth::relay_builder(smtp_host, use_starttls)?
+            .port(smtp_port)
+            .credentials(creds)
+            .build();
 
         // Test connection
-        mailer.test_connection().await?;
+        mailer.test_connection().await.map_err(|e| SmtpError::from_transport(smtp_host, e))?;
+        crate::tls::log_established("SMTP", smtp_host);
 
         Ok(())
     }
 
-    /// Save a draft email to the Drafts folder with the \Draft flag
+    /// Save a draft to the account's Drafts folder with the \Draft flag.
+    /// The folder is the account's `drafts_folder` when set, otherwise the
+    /// one marked \Drafts (or the provider's usual name), otherwise
+    /// "Drafts", created if missing.
     pub async fn save_draft(
         &self,
         account_email: &str,
-        to: &str,
-        subject: &str,
-        body: &str,
-    ) -> Result<(), SmtpError> {
-        // Get account to get the from address
+        request: &SendEmailRequest,
+    ) -> Result<SavedDraft, SmtpError> {
         let account_service = self.account_service.lock().await;
         let account = account_service
             .get_account(account_email)
             .await
             .map_err(|_| SmtpError::AccountNotFound(account_email.to_string()))?;
+        let default_reply_to = account_service
+            .get_default_reply_to(account_email)
+            .await
+            .ok()
+            .flatten();
+        let configured_folder = account_service
+            .get_drafts_folder(account_email)
+            .await
+            .ok()
+            .flatten();
         drop(account_service);
 
-        // Construct RFC822 message
-        let from_address = account_email;
-        let date = chrono::Utc::now().to_rfc2822();
-
-        let rfc822_message = format!(
-            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
-            from_address, to, subject, date, body
-        );
-
-        let email_bytes = rfc822_message.as_bytes();
+        let email_builder = message_builder(&account, default_reply_to.as_deref(), request)?;
+        let email = build_message(email_builder, request).await?;
+        let message_id = email
+            .headers()
+            .get_raw("Message-ID")
+            .map(|v| v.to_string());
+        let email_bytes = email.formatted();
 
-        // Append to Drafts folder with \Draft flag
         let operation_timeout = Duration::from_secs(40);
-        log::info!("Saving draft to Drafts folder with {}s timeout", operation_timeout.as_secs());
+        log::info!("Saving draft with {}s timeout", operation_timeout.as_secs());
 
         let result = timeout(operation_timeout, async {
             let session = self.imap_session_factory
@@ -798,44 +994,34 @@ impl SmtpService {
                 .await
                 .map_err(|e| SmtpError::ConfigError(format!("Failed to create IMAP session: {}", e)))?;
 
-            let drafts_folder = "INBOX.Drafts";
-            let flags = vec!["\\Draft".to_string()];
-
-            let result = match session.append(drafts_folder, email_bytes, &flags).await {
-                Ok(_) => {
-                    log::info!("Successfully saved draft to {}", drafts_folder);
-                    Ok(())
-                }
-                Err(append_err) => {
-                    let err_str = append_err.to_string().to_lowercase();
-                    let folder_not_found = err_str.contains("no such") ||
-                                          err_str.contains("not found") ||
-                                          err_str.contains("nonexistent") ||
-                                          err_str.contains("does not exist");
-
-                    if folder_not_found {
-                        log::warn!("Drafts folder does not exist, attempting to create...");
-                        match session.create_folder(drafts_folder).await {
-                            Ok(_) => {
-                                log::info!("Created Drafts folder");
-                                match session.append(drafts_folder, email_bytes, &flags).await {
-                                    Ok(_) => Ok(()),
-                                    Err(e) => Err(SmtpError::ConfigError(format!(
-                                        "Failed to append draft after creating folder: {}", e
-                                    )))
-                                }
-                            }
-                            Err(e) => Err(SmtpError::ConfigError(format!(
-                                "Failed to create Drafts folder: {}", e
-                            )))
-                        }
-                    } else {
-                        Err(SmtpError::ConfigError(format!(
-                            "Failed to save draft: {}", append_err
-                        )))
+            let result = async {
+                let folder = match configured_folder {
+                    Some(folder) => folder,
+                    None => {
+                        let profile = ProviderProfile::detect(account.provider_type.as_deref(), &account.imap_host);
+                        special_use::resolve(&session, &account.email_address, profile).await
+                            .map_err(|e| SmtpError::ConfigError(format!("Failed to list folders: {}", e)))?
+                            .remove(&SpecialUse::Drafts)
+                            .unwrap_or_else(|| DEFAULT_DRAFTS_FOLDER.to_string())
                     }
-                }
-            };
+                };
+                let flags = vec!["\\Draft".to_string()];
+
+                let uid = match session.append_with_flags(&folder, &email_bytes, &flags, None).await {
+                    Ok(uid) => uid,
+                    Err(e) if is_missing_folder(&e) => {
+                        log::warn!("Drafts folder '{}' does not exist, attempting to create...", folder);
+                        session.create_folder(&folder).await
+                            .map_err(|e| SmtpError::ConfigError(format!("Failed to create Drafts folder: {}", e)))?;
+                        session.append_with_flags(&folder, &email_bytes, &flags, None).await
+                            .map_err(|e| SmtpError::ConfigError(format!("Failed to append draft after creating folder: {}", e)))?
+                    }
+                    Err(e) => return Err(SmtpError::ConfigError(format!("Failed to save draft: {}", e))),
+                };
+
+                log::info!("Saved draft to {} (UID {:?})", folder, uid);
+                Ok(SavedDraft { folder, uid, message_id })
+            }.await;
 
             // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
             if let Err(e) = session.logout().await {
@@ -846,11 +1032,22 @@ impl SmtpService {
         }).await;
 
         match result {
-            Ok(Ok(())) => Ok(()),
-            Ok(Err(e)) => Err(e),
+            Ok(result) => result,
             Err(_) => Err(SmtpError::ConfigError(format!(
                 "Save draft operation timed out after {}s", operation_timeout.as_secs()
             )))
         }
     }
 }
+
+/// Whether an APPEND failed because the folder doesn't exist.
+fn is_missing_folder(err: &ImapError) -> bool {
+    if matches!(err, ImapError::FolderNotFound(_)) {
+        return true;
+    }
+    let message = err.to_string().to_lowercase();
+    message.contains("no such")
+        || message.contains("not found")
+        || message.contains("nonexistent")
+        || message.contains("does not exist")
+}
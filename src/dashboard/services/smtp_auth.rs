@@ -11,13 +11,36 @@
 
 use lettre::{
     transport::smtp::authentication::{Credentials, Mechanism},
+    transport::smtp::client::{Tls, TlsParameters},
+    transport::smtp::AsyncSmtpTransportBuilder,
     AsyncSmtpTransport, Tokio1Executor,
 };
-use log::info;
+use log::{debug, info};
 
 use super::account::Account;
 use super::smtp::SmtpError;
 
+/// Transport builder for `host`, implicit TLS or STARTTLS, with the
+/// minimum version from `TLS_MIN_VERSION`. Port and credentials are left to
+/// the caller.
+pub fn relay_builder(
+    host: &str,
+    use_starttls: bool,
+) -> Result<AsyncSmtpTransportBuilder, SmtpError> {
+    let tls_parameters = TlsParameters::builder(host.to_string())
+        .set_min_tls_version(crate::tls::smtp_min_tls_version())
+        .build()
+        .map_err(|e| SmtpError::ConfigError(format!("SMTP TLS error: {}", e)))?;
+    debug!("SMTP transport for {} requires {:?} or newer", host, crate::tls::smtp_min_tls_version());
+
+    let tls = if use_starttls {
+        Tls::Required(tls_parameters)
+    } else {
+        Tls::Wrapper(tls_parameters)
+    };
+    Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host).tls(tls))
+}
+
 /// Build an async SMTP transport for the given account.
 ///
 /// If the account has `oauth_provider` set and an `oauth_access_token`,
@@ -45,21 +68,11 @@ pub fn build_smtp_transport(
 
         info!("Building SMTP transport with XOAUTH2 for {}", account.email_address);
 
-        let mailer = if use_starttls {
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .authentication(vec![Mechanism::Xoauth2])
-                .build()
-        } else {
-            AsyncSmtpTransport::<Tokio1Executor>::relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .authentication(vec![Mechanism::Xoauth2])
-                .build()
-        };
+        let mailer = relay_builder(smtp_host, use_starttls)?
+            .port(smtp_port)
+            .credentials(creds)
+            .authentication(vec![Mechanism::Xoauth2])
+            .build();
 
         Ok(mailer)
     } else {
@@ -74,19 +87,10 @@ pub fn build_smtp_transport(
 
         let creds = Credentials::new(smtp_user.clone(), smtp_pass.clone());
 
-        let mailer = if use_starttls {
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        } else {
-            AsyncSmtpTransport::<Tokio1Executor>::relay(smtp_host)
-                .map_err(|e| SmtpError::ConfigError(format!("SMTP relay error: {}", e)))?
-                .port(smtp_port)
-                .credentials(creds)
-                .build()
-        };
+        let mailer = relay_builder(smtp_host, use_starttls)?
+            .port(smtp_port)
+            .credentials(creds)
+            .build();
 
         Ok(mailer)
     }
@@ -95,6 +99,7 @@ pub fn build_smtp_transport(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::imap::ConnectionMode;
 
     fn make_test_account(oauth: bool) -> Account {
         Account {
@@ -107,6 +112,8 @@ mod tests {
             imap_user: "user@outlook.com".to_string(),
             imap_pass: String::new(),
             imap_use_tls: true,
+            imap_connection_mode: ConnectionMode::default(),
+            imap_allow_plaintext_auth: false,
             smtp_host: Some("smtp.office365.com".to_string()),
             smtp_port: Some(587),
             smtp_user: if oauth { None } else { Some("user@outlook.com".to_string()) },
@@ -119,6 +126,7 @@ mod tests {
             oauth_token_expiry: if oauth { Some(9999999999) } else { None },
             is_active: true,
             is_default: false,
+            tls: None,
             connection_status: None,
         }
     }
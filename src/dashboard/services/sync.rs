@@ -4,14 +4,22 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::time;
 use tokio::sync::Mutex as TokioMutex;
+use tokio::sync::Notify;
+use dashmap::DashMap;
 use log::{info, error, debug, warn};
 use crate::imap::error::ImapError;
+use crate::imap::provider_profile::ProviderProfile;
+use crate::imap::types::MailboxInfo;
 use crate::prelude::CloneableImapSessionFactory;
 use crate::dashboard::services::cache::{CacheService, SyncStatus};
-use crate::dashboard::services::account::AccountService;
+use crate::connection_pool::{AccountConnectionFactory, AcquirePriority, ConnectionFactory, MultiAccountPool, PoolError};
+use crate::dashboard::services::account::{Account, AccountService};
+use crate::dashboard::services::email::AccountSession;
+use crate::dashboard::services::events::{EventBus, MailboxAction};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,11 +34,67 @@ pub enum SyncError {
     AccountError(String),
 }
 
+/// How long the \Recent marker on cached messages lasts (`IMAP_RECENT_MODE`).
+///
+/// \Recent is per-session: the first session to SELECT a folder after a
+/// message arrives sees it, and every later session does not. Sync opens
+/// folders with EXAMINE (`IMAP_AUTO_EXAMINE`), which reports \Recent without
+/// claiming it, so a message stays recent until some client SELECTs the
+/// folder. With EXAMINE disabled, sync claims the flag itself and a message
+/// is "recent" when it arrived between two sync passes, unless another
+/// client selected the folder first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecentMode {
+    /// Keep only what the latest sync pass saw (default)
+    Sync,
+    /// Keep the marker until the message is read
+    UntilSeen,
+}
+
+impl RecentMode {
+    pub fn from_env() -> Self {
+        match std::env::var("IMAP_RECENT_MODE").ok().as_deref().map(str::trim) {
+            Some("until_seen") => Self::UntilSeen,
+            Some("sync") | None => Self::Sync,
+            Some(other) => {
+                warn!("Unknown IMAP_RECENT_MODE '{}'; using 'sync'", other);
+                Self::Sync
+            }
+        }
+    }
+}
+
+/// Whether sync uses CONDSTORE to pick up flag changes when the server
+/// supports it (`IMAP_CONDSTORE_SYNC`, default true).
+fn condstore_sync_enabled() -> bool {
+    std::env::var("IMAP_CONDSTORE_SYNC")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Seconds between background syncs (`SYNC_INTERVAL_SECONDS`, default 300).
+pub fn sync_interval_from_env() -> u64 {
+    std::env::var("SYNC_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(300)
+}
+
 pub struct SyncService {
     imap_factory: CloneableImapSessionFactory,
     cache_service: Arc<CacheService>,
     account_service: Arc<TokioMutex<AccountService>>,
-    sync_interval: Duration,
+    /// Seconds between background syncs; changed by a config reload
+    sync_interval_seconds: AtomicU64,
+    event_bus: Option<Arc<EventBus>>,
+    /// Per-account signal that makes IDLE watchers drop their session and
+    /// log in again with freshly read credentials
+    idle_reconnects: Arc<DashMap<String, Arc<Notify>>>,
+    /// Running IDLE watcher tasks, one per watched folder, by account
+    idle_watchers: Arc<DashMap<String, Vec<tokio::task::JoinHandle<()>>>>,
+    /// Per-account pools sync borrows from, at background priority
+    account_pools: Option<Arc<MultiAccountPool>>,
 }
 
 impl SyncService {
@@ -44,18 +108,83 @@ impl SyncService {
             imap_factory,
             cache_service,
             account_service,
-            sync_interval: Duration::from_secs(sync_interval_seconds),
+            sync_interval_seconds: AtomicU64::new(sync_interval_seconds.max(1)),
+            event_bus: None,
+            idle_reconnects: Arc::new(DashMap::new()),
+            idle_watchers: Arc::new(DashMap::new()),
+            account_pools: None,
+        }
+    }
+
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// Borrow sync sessions from the account pools instead of logging in
+    /// each time. They are acquired at background priority, so a sync waits
+    /// rather than take the connections kept free for interactive requests.
+    pub fn with_account_pools(mut self, account_pools: Arc<MultiAccountPool>) -> Self {
+        self.account_pools = Some(account_pools);
+        self
+    }
+
+    /// Session for sync work: pooled at background priority when account
+    /// pools are attached and enabled, otherwise (or when the pool can't
+    /// connect) a one-off login whose outcome is recorded as the account's
+    /// connection status.
+    async fn sync_session(&self, account_id: &str, account: &Account, operation: &str) -> Result<AccountSession, SyncError> {
+        if let Some(pools) = self.account_pools.as_ref().filter(|_| MultiAccountPool::enabled()) {
+            let acquired = pools.acquire(&account.email_address, AcquirePriority::Background, || {
+                Arc::new(AccountConnectionFactory::new(self.imap_factory.clone(), account.clone()))
+                    as Arc<dyn ConnectionFactory>
+            }).await;
+            match acquired {
+                Ok(handle) => {
+                    if handle.client().noop().await.is_ok() {
+                        return Ok(AccountSession::Pooled(handle));
+                    }
+                    debug!("Pooled connection for {} failed NOOP; replacing it", account.email_address);
+                    handle.discard();
+                }
+                // Logging in around the pool would use the reserved connections after all
+                Err(e @ (PoolError::PoolExhausted | PoolError::CircuitOpen(_))) => {
+                    return Err(SyncError::ImapError(e.into()));
+                }
+                Err(e) => debug!("Pool could not connect {} for {}: {}", account.email_address, operation, e),
+            }
         }
+
+        let result = self.imap_factory.create_session_for_account(account).await;
+        let (connected, message) = match &result {
+            Ok(_) => (true, format!("Successfully connected to {} for {}", account.imap_host, operation)),
+            Err(e) => (false, e.to_string()),
+        };
+        let account_service = self.account_service.lock().await;
+        if let Err(e) = account_service.update_imap_status(account_id, connected, message).await {
+            warn!("Failed to update IMAP connection status: {}", e);
+        }
+        drop(account_service);
+        Ok(AccountSession::Direct(result?))
+    }
+
+    pub fn sync_interval(&self) -> Duration {
+        Duration::from_secs(self.sync_interval_seconds.load(Ordering::Relaxed))
+    }
+
+    /// Change the time between background syncs. The wait already under
+    /// way finishes on the old interval.
+    pub fn set_sync_interval(&self, seconds: u64) {
+        self.sync_interval_seconds.store(seconds.max(1), Ordering::Relaxed);
+        info!("Sync interval set to {} seconds", seconds.max(1));
     }
 
     /// Start the background sync task
     pub fn start_background_sync(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
-            let mut interval = time::interval(self.sync_interval);
-            interval.tick().await; // Skip the first immediate tick
-
             loop {
-                interval.tick().await;
+                // Re-read every round so a reloaded interval takes effect
+                time::sleep(self.sync_interval()).await;
 
                 // Get all accounts for background sync
                 let account_service = self.account_service.lock().await;
@@ -89,6 +218,19 @@ impl SyncService {
 
     /// Sync all folders for a specific account
     pub async fn sync_all_folders(&self, account_id: &str) -> Result<(), SyncError> {
+        let result = self.sync_account_folders(account_id).await;
+        if let Some(event_bus) = &self.event_bus {
+            match &result {
+                Ok((synced, failed)) => event_bus.publish_sync_completed(account_id, *synced, failed.clone()).await,
+                Err(e) => event_bus.publish_sync_failed(account_id, e.to_string()).await,
+            }
+        }
+        result.map(|_| ())
+    }
+
+    /// Sync every folder the provider profile allows over one session.
+    /// Returns how many folders synced and which ones failed.
+    async fn sync_account_folders(&self, account_id: &str) -> Result<(usize, Vec<String>), SyncError> {
         info!("Starting email sync for all folders for account: {}", account_id);
 
         // Get account credentials
@@ -97,36 +239,28 @@ impl SyncService {
             .map_err(|e| SyncError::AccountError(format!("Failed to get account: {}", e)))?;
         drop(account_service); // Release lock before creating session
 
-        // Try to create session and record connection status
-        let session = match self.imap_factory.create_session_for_account(&account).await {
-            Ok(s) => {
-                // Record successful IMAP connection
-                let account_service = self.account_service.lock().await;
-                if let Err(e) = account_service.update_imap_status(account_id, true, format!("Successfully connected to {}", account.imap_host)).await {
-                    warn!("Failed to update IMAP connection status: {}", e);
-                }
-                drop(account_service);
-                s
-            }
-            Err(e) => {
-                // Record failed IMAP connection
-                let account_service = self.account_service.lock().await;
-                if let Err(status_err) = account_service.update_imap_status(account_id, false, e.to_string()).await {
-                    warn!("Failed to update IMAP connection status: {}", status_err);
-                }
-                drop(account_service);
-                return Err(SyncError::ImapError(e));
-            }
-        };
+        let session = self.sync_session(account_id, &account, "sync").await?;
 
-        let folders = session.list_folders().await?;
+        let profile = ProviderProfile::detect(account.provider_type.as_deref(), &account.imap_host);
+        let folders: Vec<String> = session.list_folders().await?
+            .into_iter()
+            .filter(|folder| {
+                let sync = profile.should_sync(folder);
+                if !sync {
+                    debug!("Skipping folder {} for account {} ({} profile)", folder, account_id, profile.id);
+                }
+                sync
+            })
+            .collect();
 
         // IMPORTANT: Reuse the same session for all folders to prevent memory leak
         // Previously, each folder created its own session with separate BytePools
-        for folder in folders {
-            if let Err(e) = self.sync_folder_with_session(account_id, &folder, &session).await {
+        let mut failed_folders = Vec::new();
+        for folder in &folders {
+            if let Err(e) = self.sync_folder_with_session(account_id, folder, &session).await {
                 warn!("Failed to sync folder {} for account {}: {}", folder, account_id, e);
                 // Continue with other folders even if one fails
+                failed_folders.push(folder.clone());
             }
         }
 
@@ -136,7 +270,7 @@ impl SyncService {
         }
 
         info!("Email sync completed for all folders for account: {}", account_id);
-        Ok(())
+        Ok((folders.len() - failed_folders.len(), failed_folders))
     }
 
     /// Sync a specific folder for a specific account
@@ -183,36 +317,21 @@ impl SyncService {
     /// Inner sync logic for sync_folder_with_limit. Extracted so that the
     /// caller can reset sync status to Idle on any error path.
     async fn do_sync_folder(&self, account_id: &str, account: &crate::dashboard::services::account::Account, folder_name: &str, account_email: &str, limit: Option<usize>) -> Result<(), SyncError> {
-        // Try to create session and record connection status
-        let session = match self.imap_factory.create_session_for_account(account).await {
-            Ok(s) => {
-                let account_service = self.account_service.lock().await;
-                if let Err(e) = account_service.update_imap_status(account_id, true, format!("Successfully connected to {} for sync", account.imap_host)).await {
-                    warn!("Failed to update IMAP connection status: {}", e);
-                }
-                drop(account_service);
-                s
-            }
-            Err(e) => {
-                let account_service = self.account_service.lock().await;
-                if let Err(status_err) = account_service.update_imap_status(account_id, false, e.to_string()).await {
-                    warn!("Failed to update IMAP connection status: {}", status_err);
-                }
-                drop(account_service);
-                return Err(SyncError::ImapError(e));
-            }
-        };
+        let session = self.sync_session(account_id, account, "sync").await?;
 
-        session.select_folder(folder_name).await?;
+        let mailbox = session.select_folder(folder_name).await?;
 
         if let Err(e) = self.cache_service.get_or_create_folder_for_account(folder_name, account_email).await {
             error!("Failed to create folder {} for account {}: {}", folder_name, account_email, e);
             return Err(SyncError::CacheError(format!("Failed to create folder: {}", e)));
         }
+        self.record_recent(folder_name, account_email, &mailbox).await;
 
         let sync_state = self.cache_service.get_sync_state(folder_name, account_email).await
             .map_err(|e| SyncError::CacheError(e.to_string()))?;
-        let last_uid_synced = sync_state.and_then(|s| s.last_uid_synced).unwrap_or(0);
+        let last_uid_synced = sync_state.as_ref().and_then(|s| s.last_uid_synced).unwrap_or(0);
+        let stored_modseq = sync_state.and_then(|s| s.modseq);
+        let modseq = self.sync_changed_flags(&session, folder_name, account_email, &mailbox, stored_modseq, last_uid_synced).await;
 
         let search_criteria = if last_uid_synced > 0 {
             format!("UID {}:*", last_uid_synced + 1)
@@ -227,6 +346,7 @@ impl SyncService {
             if let Err(e) = self.cache_service.update_sync_state(folder_name, last_uid_synced, SyncStatus::Idle, account_email).await {
                 warn!("Failed to update sync state: {}", e);
             }
+            self.record_modseq(folder_name, account_email, modseq).await;
             return Ok(());
         }
 
@@ -248,7 +368,12 @@ impl SyncService {
 
         for chunk in uids_to_sync.chunks(FETCH_BATCH_SIZE) {
             debug!("Fetching batch of {} emails", chunk.len());
-            let emails = session.fetch_emails(chunk).await?;
+            let batch = session.fetch_batch(chunk).await?;
+            if !batch.errors.is_empty() {
+                warn!("{} of {} emails in folder {} could not be fetched: {:?}",
+                      batch.errors.len(), chunk.len(), folder_name, batch.errors);
+            }
+            let emails = batch.emails;
 
             let total_size: usize = emails.iter()
                 .map(|e| {
@@ -308,15 +433,162 @@ impl SyncService {
         if let Err(e) = self.cache_service.update_sync_state(folder_name, last_uid, SyncStatus::Idle, account_email).await {
             warn!("Failed to update sync state: {}", e);
         }
+        self.record_modseq(folder_name, account_email, modseq).await;
 
         if let Err(e) = session.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
 
+        self.index_embeddings(account_email, folder_name).await;
+        self.publish_received(account_email, folder_name, last_uid_synced, &uids_to_sync).await;
+
         info!("Successfully synced {} emails in folder {}", uids_to_sync.len(), folder_name);
         Ok(())
     }
 
+    /// Store the SELECT counts and expire \Recent markers left by earlier
+    /// passes, according to `IMAP_RECENT_MODE`. New messages fetched in this
+    /// pass bring their own \Recent flag.
+    async fn record_recent(&self, folder_name: &str, account_email: &str, mailbox: &MailboxInfo) {
+        if let Err(e) = self.cache_service.record_mailbox_counts(
+            folder_name, account_email, mailbox.exists, mailbox.unseen, mailbox.recent,
+        ).await {
+            warn!("Failed to record mailbox counts for {}: {}", folder_name, e);
+        }
+
+        let seen_only = RecentMode::from_env() == RecentMode::UntilSeen;
+        let stale = match self.cache_service.get_cached_recent_uids(folder_name, account_email, seen_only).await {
+            Ok(uids) => uids,
+            Err(e) => {
+                warn!("Failed to read cached \\Recent flags for {}: {}", folder_name, e);
+                return;
+            }
+        };
+        if !stale.is_empty() {
+            debug!("Clearing \\Recent on {} cached emails in {}", stale.len(), folder_name);
+            if let Err(e) = self.cache_service.set_cached_flag(folder_name, &stale, "Recent", false, account_email).await {
+                warn!("Failed to clear cached \\Recent flags for {}: {}", folder_name, e);
+            }
+        }
+    }
+
+    /// CONDSTORE pass (RFC 7162): update the cached flags of messages changed
+    /// since the stored HIGHESTMODSEQ, which the UID-based pass never looks
+    /// at again. Returns the MODSEQ to keep for the next pass, or None when
+    /// the server reports no HIGHESTMODSEQ for the folder; sync is then
+    /// UID-based only, as before.
+    ///
+    /// One CHANGEDSINCE fetch lists every changed UID with its flags, so
+    /// SEARCH MODSEQ is not needed (imap-proto also rejects the `(MODSEQ n)`
+    /// suffix of its response). New messages show up as UIDs past
+    /// `last_uid_synced` and are left to the `UID n:*` pass. Expunges are not
+    /// reported without QRESYNC.
+    async fn sync_changed_flags(
+        &self,
+        session: &crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>,
+        folder_name: &str,
+        account_email: &str,
+        mailbox: &MailboxInfo,
+        stored_modseq: Option<u64>,
+        last_uid_synced: u32,
+    ) -> Option<u64> {
+        if !condstore_sync_enabled() {
+            return None;
+        }
+        // Skip servers known to lack it rather than trying CHANGEDSINCE
+        if session.capabilities().is_some_and(|caps| !caps.supports_condstore()) {
+            return None;
+        }
+        let server_modseq = mailbox.highest_modseq?;
+        let since = match stored_modseq {
+            // Nothing to compare against yet; start tracking from here
+            None => return Some(server_modseq),
+            Some(_) if last_uid_synced == 0 => return Some(server_modseq),
+            Some(stored) if stored == server_modseq => {
+                debug!("No changes in {} since MODSEQ {}", folder_name, stored);
+                return Some(server_modseq);
+            }
+            Some(stored) if stored > server_modseq => {
+                warn!("HIGHESTMODSEQ of {} went back from {} to {}; cached flags may be stale until the next flag resync",
+                      folder_name, stored, server_modseq);
+                return Some(server_modseq);
+            }
+            Some(stored) => stored,
+        };
+
+        let changed = match session.get_changed_since(since).await {
+            Ok(changed) => changed,
+            Err(e) => {
+                // Keep the old value so the next pass asks again
+                warn!("CHANGEDSINCE {} failed for {}: {}", since, folder_name, e);
+                return Some(since);
+            }
+        };
+
+        // This session can't see \Recent claimed by an earlier one; keep the recorded markers
+        let recent_uids: std::collections::HashSet<u32> = self.cache_service
+            .get_cached_recent_uids(folder_name, account_email, false).await
+            .map(|uids| uids.into_iter().collect())
+            .unwrap_or_default();
+
+        let mut updated = 0;
+        for (uid, mut flags) in changed.changes {
+            if uid > last_uid_synced {
+                continue;
+            }
+            if recent_uids.contains(&uid) && !flags.iter().any(|f| f == "Recent") {
+                flags.push("Recent".to_string());
+            }
+            match self.cache_service.update_email_flags(folder_name, uid, &flags, account_email).await {
+                Ok(_) => updated += 1,
+                Err(e) => warn!("Failed to update flags for UID {}: {}", uid, e),
+            }
+        }
+        debug!("Applied {} flag change(s) in {} since MODSEQ {}", updated, folder_name, since);
+
+        Some(server_modseq.max(changed.highest_modseq.unwrap_or(0)))
+    }
+
+    async fn record_modseq(&self, folder_name: &str, account_email: &str, modseq: Option<u64>) {
+        if let Err(e) = self.cache_service.update_sync_modseq(folder_name, modseq, account_email).await {
+            warn!("Failed to record MODSEQ for {}: {}", folder_name, e);
+        }
+    }
+
+    /// Publish a `received` activity event for mail that arrived since the
+    /// previous sync. The initial backfill of a folder is not reported.
+    async fn publish_received(&self, account_email: &str, folder_name: &str, last_uid_synced: u32, uids: &[u32]) {
+        let Some(event_bus) = &self.event_bus else {
+            return;
+        };
+        if last_uid_synced == 0 {
+            return;
+        }
+        // "UID n:*" always matches the highest UID, even when it is below n
+        let new_uids: Vec<u32> = uids.iter().copied().filter(|uid| *uid > last_uid_synced).collect();
+        if new_uids.is_empty() {
+            return;
+        }
+        let detail = format!("{} new message(s)", new_uids.len());
+        event_bus.publish_mailbox_activity(
+            account_email, MailboxAction::Received, Some(folder_name.to_string()), new_uids, Some(detail), "sync",
+        ).await;
+    }
+
+    /// Embed newly cached emails for semantic search. No-op unless
+    /// SEMANTIC_SEARCH_ENABLED is set; failures are logged and never fail the sync.
+    async fn index_embeddings(&self, account_email: &str, folder_name: &str) {
+        let Some(pool) = self.cache_service.db_pool.as_ref() else {
+            return;
+        };
+        let Some(index) = crate::semantic_search::SemanticIndex::from_env(pool.clone()) else {
+            return;
+        };
+        if let Err(e) = index.index_pending(account_email, folder_name).await {
+            warn!("Failed to embed emails in folder {} for semantic search: {}", folder_name, e);
+        }
+    }
+
     /// Sync a specific folder with a provided session and optional limit
     /// This is used internally to reuse the same IMAP session across folders
     async fn sync_folder_with_session_and_limit(&self, account_id: &str, folder_name: &str, session: &crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>, limit: Option<usize>) -> Result<(), SyncError> {
@@ -352,16 +624,19 @@ impl SyncService {
     /// Inner sync logic for sync_folder_with_session_and_limit. Extracted so
     /// the caller can reset sync status to Idle on any error path.
     async fn do_sync_folder_with_session(&self, folder_name: &str, account_email: &str, session: &crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>, limit: Option<usize>) -> Result<(), SyncError> {
-        session.select_folder(folder_name).await?;
+        let mailbox = session.select_folder(folder_name).await?;
 
         if let Err(e) = self.cache_service.get_or_create_folder_for_account(folder_name, account_email).await {
             error!("Failed to create folder {} for account {}: {}", folder_name, account_email, e);
             return Err(SyncError::CacheError(format!("Failed to create folder: {}", e)));
         }
+        self.record_recent(folder_name, account_email, &mailbox).await;
 
         let sync_state = self.cache_service.get_sync_state(folder_name, account_email).await
             .map_err(|e| SyncError::CacheError(e.to_string()))?;
-        let last_uid_synced = sync_state.and_then(|s| s.last_uid_synced).unwrap_or(0);
+        let last_uid_synced = sync_state.as_ref().and_then(|s| s.last_uid_synced).unwrap_or(0);
+        let stored_modseq = sync_state.and_then(|s| s.modseq);
+        let modseq = self.sync_changed_flags(session, folder_name, account_email, &mailbox, stored_modseq, last_uid_synced).await;
 
         let search_criteria = if last_uid_synced > 0 {
             format!("UID {}:*", last_uid_synced + 1)
@@ -376,6 +651,7 @@ impl SyncService {
             if let Err(e) = self.cache_service.update_sync_state(folder_name, last_uid_synced, SyncStatus::Idle, account_email).await {
                 warn!("Failed to update sync state: {}", e);
             }
+            self.record_modseq(folder_name, account_email, modseq).await;
             return Ok(());
         }
 
@@ -397,7 +673,12 @@ impl SyncService {
 
         for chunk in uids_to_sync.chunks(FETCH_BATCH_SIZE) {
             debug!("Fetching batch of {} emails", chunk.len());
-            let emails = session.fetch_emails(chunk).await?;
+            let batch = session.fetch_batch(chunk).await?;
+            if !batch.errors.is_empty() {
+                warn!("{} of {} emails in folder {} could not be fetched: {:?}",
+                      batch.errors.len(), chunk.len(), folder_name, batch.errors);
+            }
+            let emails = batch.emails;
 
             let total_size: usize = emails.iter()
                 .map(|e| {
@@ -457,6 +738,10 @@ impl SyncService {
         if let Err(e) = self.cache_service.update_sync_state(folder_name, last_uid, SyncStatus::Idle, account_email).await {
             warn!("Failed to update sync state: {}", e);
         }
+        self.record_modseq(folder_name, account_email, modseq).await;
+
+        self.index_embeddings(account_email, folder_name).await;
+        self.publish_received(account_email, folder_name, last_uid_synced, &uids_to_sync).await;
 
         info!("Successfully synced {} emails in folder {}", uids_to_sync.len(), folder_name);
         Ok(())
@@ -484,17 +769,26 @@ impl SyncService {
             return Ok(());
         }
 
-        // Create IMAP session
-        let session = self.imap_factory.create_session_for_account(&account).await?;
+        let session = self.sync_session(account_id, &account, "flag resync").await?;
         session.select_folder(folder_name).await?;
 
+        // This session can't see \Recent on messages an earlier session already
+        // selected, so keep the markers sync recorded rather than wiping them.
+        let recent_uids: std::collections::HashSet<u32> = self.cache_service
+            .get_cached_recent_uids(folder_name, account_email, false).await
+            .map(|uids| uids.into_iter().collect())
+            .unwrap_or_default();
+
         // Fetch flags in batches of 500 (FLAGS-only is very lightweight)
         const FLAG_BATCH_SIZE: usize = 500;
         let mut updated = 0;
 
         for chunk in cached_uids.chunks(FLAG_BATCH_SIZE) {
             let flag_results = session.fetch_flags(chunk).await?;
-            for (uid, flags) in flag_results {
+            for (uid, mut flags) in flag_results {
+                if recent_uids.contains(&uid) && !flags.iter().any(|f| f == "Recent") {
+                    flags.push("Recent".to_string());
+                }
                 if let Err(e) = self.cache_service.update_email_flags(folder_name, uid, &flags, account_email).await {
                     warn!("Failed to update flags for UID {}: {}", uid, e);
                 } else {
@@ -519,8 +813,110 @@ impl SyncService {
         self.sync_folder_with_limit(account_id, folder_name, None).await
     }
 
-    /// Handle IMAP IDLE for real-time updates for a specific account
-    pub async fn start_idle_monitoring(&self, account_id: &str, folder_name: &str) -> Result<(), SyncError> {
+    /// Watch `IMAP_IDLE_FOLDERS` (default INBOX) of every account with IDLE,
+    /// syncing a folder as soon as the server reports a change. Accounts
+    /// whose server doesn't advertise IDLE are left to the periodic sync; a
+    /// watcher that loses its connection retries after a growing delay.
+    pub fn start_idle_watchers(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let accounts = match self.account_service.lock().await.list_accounts().await {
+                Ok(accounts) => accounts,
+                Err(e) => {
+                    error!("Failed to list accounts for IDLE: {}", e);
+                    return;
+                }
+            };
+            for account in accounts.into_iter().filter(|a| a.is_active) {
+                self.watch_idle(&account.email_address);
+            }
+        })
+    }
+
+    /// Start IDLE watchers for one account, e.g. one just added. Does
+    /// nothing when IDLE is off or the account is already watched.
+    pub fn watch_idle(self: &Arc<Self>, account_id: &str) {
+        if !crate::imap::idle::idle_enabled() {
+            return;
+        }
+        let key = account_id.to_lowercase();
+        let mut watchers = self.idle_watchers.entry(key).or_default();
+        if watchers.iter().any(|watcher| !watcher.is_finished()) {
+            return;
+        }
+
+        let folders = std::env::var("IMAP_IDLE_FOLDERS")
+            .unwrap_or_else(|_| "INBOX".to_string())
+            .split(',')
+            .map(|f| f.trim().to_string())
+            .filter(|f| !f.is_empty())
+            .collect::<Vec<_>>();
+        *watchers = folders.into_iter()
+            .map(|folder| {
+                let service = Arc::clone(self);
+                let account_id = account_id.to_string();
+                tokio::spawn(async move {
+                    let reconnect = service.idle_reconnect_signal(&account_id);
+                    let mut retry = Duration::from_secs(5);
+                    loop {
+                        match service.start_idle_monitoring(&account_id, &folder).await {
+                            Ok(false) => {
+                                info!("{} does not support IDLE; {} stays on periodic sync", account_id, folder);
+                                return;
+                            }
+                            // Only a reconnect request ends a healthy IDLE
+                            Ok(true) => {
+                                retry = Duration::from_secs(5);
+                                continue;
+                            }
+                            Err(e) => {
+                                warn!("IDLE on {} for {} stopped: {}; retrying in {:?}", folder, account_id, e, retry);
+                                retry = (retry * 2).min(Duration::from_secs(600));
+                            }
+                        }
+                        // A reconnect request skips the remaining backoff
+                        tokio::select! {
+                            _ = time::sleep(retry) => {}
+                            _ = reconnect.notified() => retry = Duration::from_secs(5),
+                        }
+                    }
+                })
+            })
+            .collect();
+    }
+
+    /// Stop an account's IDLE watchers, e.g. once it is deleted. Their
+    /// sessions are dropped without waiting for the server.
+    pub fn stop_idle(&self, account_id: &str) {
+        let key = account_id.to_lowercase();
+        if let Some((_, watchers)) = self.idle_watchers.remove(&key) {
+            info!("Stopping {} IDLE watcher(s) for {}", watchers.len(), account_id);
+            for watcher in watchers {
+                watcher.abort();
+            }
+        }
+        self.idle_reconnects.remove(&key);
+    }
+
+    fn idle_reconnect_signal(&self, account_id: &str) -> Arc<Notify> {
+        self.idle_reconnects.entry(account_id.to_lowercase()).or_default().clone()
+    }
+
+    /// Make the account's IDLE watchers log out and reconnect now, reading
+    /// the account's credentials again.
+    pub fn reconnect_idle(&self, account_id: &str) {
+        if let Some(signal) = self.idle_reconnects.get(&account_id.to_lowercase()) {
+            signal.notify_waiters();
+        }
+    }
+
+    /// IDLE on one folder of an account, syncing it whenever the server
+    /// reports new, removed or changed messages. Returns `Ok(false)` straight
+    /// away when the server lacks IDLE (or `IMAP_IDLE_ENABLED=false`), so the
+    /// caller can keep polling; otherwise runs until the connection fails.
+    pub async fn start_idle_monitoring(&self, account_id: &str, folder_name: &str) -> Result<bool, SyncError> {
+        if !crate::imap::idle::idle_enabled() {
+            return Ok(false);
+        }
         debug!("Starting IDLE monitoring for folder: {} for account: {}", folder_name, account_id);
 
         // Get account credentials
@@ -551,20 +947,54 @@ impl SyncService {
             }
         };
 
-        // Select the folder
-        session.select_folder(folder_name).await?;
-
-        // Note: IMAP IDLE implementation would go here
-        // This requires keeping a persistent connection and handling IDLE responses
-        // For now, we'll rely on periodic sync
+        let supported = match session.server_capabilities().await {
+            Ok(capabilities) => capabilities.supports_idle(),
+            Err(e) => {
+                warn!("Could not read capabilities of {} for IDLE: {}", account.imap_host, e);
+                false
+            }
+        };
 
-        warn!("IDLE monitoring not yet implemented, using periodic sync");
+        let result = if supported {
+            info!("IDLE monitoring {} for account {}", folder_name, account_id);
+            let reconnect = self.idle_reconnect_signal(account_id);
+            tokio::select! {
+                result = self.idle_loop(account_id, folder_name, &session) => result.map(|_| true),
+                _ = reconnect.notified() => {
+                    info!("Reconnecting IDLE on {} for {} on request", folder_name, account_id);
+                    Ok(true)
+                }
+            }
+        } else {
+            Ok(false)
+        };
 
-        // IMPORTANT: Explicitly logout since we're not actually using IDLE
+        // IMPORTANT: Explicitly logout to ensure the session and its BytePool are freed
         if let Err(e) = session.logout().await {
             warn!("Failed to logout IMAP session: {}", e);
         }
 
-        Ok(())
+        result
+    }
+
+    async fn idle_loop(&self, account_id: &str, folder_name: &str, session: &crate::imap::client::ImapClient<crate::imap::session::AsyncImapSessionWrapper>) -> Result<(), SyncError> {
+        use crate::imap::idle::{wait_timeout, IdleEvent};
+
+        loop {
+            let events = session.idle(folder_name, wait_timeout()).await?;
+            if events.is_empty() {
+                continue;
+            }
+            debug!("IDLE on {} for {}: {:?}", folder_name, account_id, events);
+
+            if events.iter().any(|e| matches!(e, IdleEvent::Exists(_) | IdleEvent::Recent(_))) {
+                self.sync_folder_with_session(account_id, folder_name, session).await?;
+            }
+            if events.iter().any(|e| matches!(e, IdleEvent::Expunge(_) | IdleEvent::Fetch { .. })) {
+                if let Err(e) = self.sync_flags_for_folder(account_id, folder_name).await {
+                    warn!("Flag resync after IDLE failed for {}: {}", folder_name, e);
+                }
+            }
+        }
     }
 }
\ No newline at end of file
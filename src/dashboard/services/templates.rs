@@ -0,0 +1,381 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Per-account email templates.
+//
+// Templates are a subject, a plain-text body and an optional HTML body with
+// `{{name}}` placeholders. Rendering is plain string substitution: there are
+// no expressions, loops or includes, every placeholder must be supplied, and
+// values are HTML-escaped when they go into the HTML body.
+
+use std::collections::{BTreeSet, HashMap};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use thiserror::Error;
+
+const MAX_NAME_LEN: usize = 100;
+
+#[derive(Error, Debug)]
+pub enum TemplateError {
+    #[error("Template '{0}' not found")]
+    NotFound(String),
+    #[error("Template '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("Invalid template: {0}")]
+    Invalid(String),
+    #[error("Missing template variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl From<sqlx::Error> for TemplateError {
+    fn from(e: sqlx::Error) -> Self {
+        TemplateError::Database(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailTemplate {
+    pub account_id: String,
+    pub name: String,
+    pub subject: String,
+    pub body: String,
+    pub body_html: Option<String>,
+    /// Placeholders used anywhere in the template, sorted
+    pub variables: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fields supplied when creating or replacing a template.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateInput {
+    pub name: String,
+    pub subject: String,
+    pub body: String,
+    pub body_html: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub body: String,
+    pub body_html: Option<String>,
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+/// Split template text into literal text and placeholder names.
+fn parse(text: &str) -> Result<Vec<Segment<'_>>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        segments.push(Segment::Text(&rest[..start]));
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| TemplateError::Invalid("unclosed '{{' placeholder".to_string()))?;
+        let name = after[..end].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-') {
+            return Err(TemplateError::Invalid(format!(
+                "'{{{{{}}}}}' is not a valid placeholder; use letters, digits, '_', '.' or '-'",
+                &after[..end]
+            )));
+        }
+        segments.push(Segment::Variable(name));
+        rest = &after[end + 2..];
+    }
+    segments.push(Segment::Text(rest));
+    Ok(segments)
+}
+
+fn escape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Substitute `vars` into `text`. Values are inserted as-is (HTML-escaped
+/// when `html` is set) and never re-scanned for placeholders.
+pub fn render(text: &str, vars: &HashMap<String, String>, html: bool) -> Result<String, TemplateError> {
+    let segments = parse(text)?;
+    let missing: BTreeSet<String> = segments
+        .iter()
+        .filter_map(|s| match s {
+            Segment::Variable(name) if !vars.contains_key(*name) => Some(name.to_string()),
+            _ => None,
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(TemplateError::MissingVariables(missing.into_iter().collect()));
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for segment in segments {
+        match segment {
+            Segment::Text(t) => out.push_str(t),
+            Segment::Variable(name) if html => out.push_str(&escape_html(&vars[name])),
+            Segment::Variable(name) => out.push_str(&vars[name]),
+        }
+    }
+    Ok(out)
+}
+
+/// Placeholder names used in `text`, validating its syntax.
+pub fn variables(text: &str) -> Result<BTreeSet<String>, TemplateError> {
+    Ok(parse(text)?
+        .into_iter()
+        .filter_map(|s| match s {
+            Segment::Variable(name) => Some(name.to_string()),
+            Segment::Text(_) => None,
+        })
+        .collect())
+}
+
+impl TemplateInput {
+    fn validate(&self) -> Result<Vec<String>, TemplateError> {
+        let name = self.name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(TemplateError::Invalid(format!("name must be 1-{} characters", MAX_NAME_LEN)));
+        }
+        if self.subject.contains(['\r', '\n']) {
+            return Err(TemplateError::Invalid("subject must be a single line".to_string()));
+        }
+        let mut names = variables(&self.subject)?;
+        names.extend(variables(&self.body)?);
+        if let Some(html) = &self.body_html {
+            names.extend(variables(html)?);
+        }
+        Ok(names.into_iter().collect())
+    }
+}
+
+impl EmailTemplate {
+    /// Render subject and bodies with `vars`.
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<RenderedEmail, TemplateError> {
+        let subject = render(&self.subject, vars, false)?;
+        // A newline from a variable would otherwise start a new header line
+        if subject.contains(['\r', '\n']) {
+            return Err(TemplateError::Invalid("subject variables must not contain line breaks".to_string()));
+        }
+        Ok(RenderedEmail {
+            subject,
+            body: render(&self.body, vars, false)?,
+            body_html: self.body_html.as_deref().map(|html| render(html, vars, true)).transpose()?,
+        })
+    }
+}
+
+pub struct TemplateService {
+    pool: SqlitePool,
+}
+
+impl TemplateService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> EmailTemplate {
+        let parse_time = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now())
+        };
+        let subject: String = row.get("subject");
+        let body: String = row.get("body");
+        let body_html: Option<String> = row.get("body_html");
+        let mut names = variables(&subject).unwrap_or_default();
+        names.extend(variables(&body).unwrap_or_default());
+        if let Some(html) = &body_html {
+            names.extend(variables(html).unwrap_or_default());
+        }
+        EmailTemplate {
+            account_id: row.get("account_id"),
+            name: row.get("name"),
+            subject,
+            body,
+            body_html,
+            variables: names.into_iter().collect(),
+            created_at: parse_time(row.get("created_at")),
+            updated_at: parse_time(row.get("updated_at")),
+        }
+    }
+
+    pub async fn list(&self, account_id: &str) -> Result<Vec<EmailTemplate>, TemplateError> {
+        let rows = sqlx::query("SELECT * FROM email_templates WHERE account_id = ? ORDER BY name")
+            .bind(account_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(Self::from_row).collect())
+    }
+
+    pub async fn get(&self, account_id: &str, name: &str) -> Result<EmailTemplate, TemplateError> {
+        sqlx::query("SELECT * FROM email_templates WHERE account_id = ? AND name = ?")
+            .bind(account_id)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| Self::from_row(&row))
+            .ok_or_else(|| TemplateError::NotFound(name.to_string()))
+    }
+
+    pub async fn create(&self, account_id: &str, input: &TemplateInput) -> Result<EmailTemplate, TemplateError> {
+        input.validate()?;
+        let name = input.name.trim();
+        let now = Utc::now().to_rfc3339();
+        let result = sqlx::query(
+            r#"
+            INSERT INTO email_templates (account_id, name, subject, body, body_html, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(account_id)
+        .bind(name)
+        .bind(&input.subject)
+        .bind(&input.body)
+        .bind(&input.body_html)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                Err(TemplateError::AlreadyExists(name.to_string()))
+            }
+            Err(e) => Err(e.into()),
+            Ok(_) => self.get(account_id, name).await,
+        }
+    }
+
+    /// Replace the template called `name`; `input.name` may rename it.
+    pub async fn update(&self, account_id: &str, name: &str, input: &TemplateInput) -> Result<EmailTemplate, TemplateError> {
+        input.validate()?;
+        let new_name = input.name.trim();
+        let result = sqlx::query(
+            r#"
+            UPDATE email_templates
+            SET name = ?, subject = ?, body = ?, body_html = ?, updated_at = ?
+            WHERE account_id = ? AND name = ?
+            "#
+        )
+        .bind(new_name)
+        .bind(&input.subject)
+        .bind(&input.body)
+        .bind(&input.body_html)
+        .bind(Utc::now().to_rfc3339())
+        .bind(account_id)
+        .bind(name)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                Err(TemplateError::AlreadyExists(new_name.to_string()))
+            }
+            Err(e) => Err(e.into()),
+            Ok(r) if r.rows_affected() == 0 => Err(TemplateError::NotFound(name.to_string())),
+            Ok(_) => self.get(account_id, new_name).await,
+        }
+    }
+
+    pub async fn delete(&self, account_id: &str, name: &str) -> Result<(), TemplateError> {
+        let result = sqlx::query("DELETE FROM email_templates WHERE account_id = ? AND name = ?")
+            .bind(account_id)
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(TemplateError::NotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Executor;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    async fn setup() -> TemplateService {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        pool.execute(include_str!("../../../migrations/017_create_email_templates.sql"))
+            .await
+            .unwrap();
+        TemplateService::new(pool)
+    }
+
+    #[test]
+    fn test_render_substitutes_without_rescanning() {
+        let out = render("Hi {{ name }}, invoice {{invoice.id}}", &vars(&[("name", "{{invoice.id}}"), ("invoice.id", "42")]), false).unwrap();
+        assert_eq!(out, "Hi {{invoice.id}}, invoice 42");
+    }
+
+    #[test]
+    fn test_render_reports_every_missing_variable() {
+        match render("{{a}} {{b}} {{a}}", &vars(&[]), false) {
+            Err(TemplateError::MissingVariables(names)) => assert_eq!(names, vec!["a", "b"]),
+            other => panic!("expected MissingVariables, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_render_rejects_malformed_placeholders() {
+        assert!(matches!(render("Hi {{name", &vars(&[]), false), Err(TemplateError::Invalid(_))));
+        assert!(matches!(render("Hi {{ na me }}", &vars(&[]), false), Err(TemplateError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_html_values_are_escaped() {
+        let out = render("<p>{{note}}</p>", &vars(&[("note", "<script>&</script>")]), true).unwrap();
+        assert_eq!(out, "<p>&lt;script&gt;&amp;&lt;/script&gt;</p>");
+    }
+
+    #[tokio::test]
+    async fn test_template_crud_and_render() {
+        let service = setup().await;
+        let input = TemplateInput {
+            name: "invoice".to_string(),
+            subject: "Invoice {{number}}".to_string(),
+            body: "Dear {{name}},\nplease find invoice {{number}} attached.".to_string(),
+            body_html: None,
+        };
+        let created = service.create("me@example.com", &input).await.unwrap();
+        assert_eq!(created.variables, vec!["name", "number"]);
+        assert!(matches!(service.create("me@example.com", &input).await, Err(TemplateError::AlreadyExists(_))));
+
+        let rendered = created.render(&vars(&[("name", "Ana"), ("number", "7")])).unwrap();
+        assert_eq!(rendered.subject, "Invoice 7");
+        assert!(matches!(created.render(&vars(&[("name", "Ana"), ("number", "7\nBcc: x")])), Err(TemplateError::Invalid(_))));
+
+        let renamed = TemplateInput { name: "invoice-v2".to_string(), ..input };
+        service.update("me@example.com", "invoice", &renamed).await.unwrap();
+        assert!(matches!(service.get("me@example.com", "invoice").await, Err(TemplateError::NotFound(_))));
+        assert_eq!(service.list("me@example.com").await.unwrap().len(), 1);
+        assert!(service.list("other@example.com").await.unwrap().is_empty());
+
+        service.delete("me@example.com", "invoice-v2").await.unwrap();
+        assert!(service.list("me@example.com").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversation grouping for a folder's cached messages.
+//!
+//! Messages are linked by `imap::threading` with the REFERENCES algorithm:
+//! Message-ID, In-Reply-To and References. Replies whose parents were never
+//! cached still carry their subject, so a thread also takes in a message with
+//! the same subject (`Re:`/`Fwd:`-style prefixes removed) when the two share
+//! a participant or were sent within `SUBJECT_MATCH_WINDOW_DAYS` of each
+//! other; a common subject alone doesn't make a conversation.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::dashboard::services::cache::CachedEmail;
+use crate::imap::threading::{build_threads_where, parse_message_ids, ThreadAlgorithm, ThreadMessage};
+
+/// How far apart two messages with the same subject and no common
+/// participant may be sent and still count as one conversation.
+const SUBJECT_MATCH_WINDOW_DAYS: i64 = 3;
+
+/// Reply and forward prefixes stripped from subjects, in lowercase. Covers the
+/// English forms plus the common German, Scandinavian and Dutch ones.
+const SUBJECT_PREFIXES: &[&str] = &["re", "fw", "fwd", "aw", "wg", "sv", "vs", "antw"];
+
+/// Subject used to match messages of one conversation: reply/forward
+/// prefixes (including counted ones like `Re[2]:`) removed, whitespace
+/// collapsed, lowercased.
+pub fn normalize_subject(subject: &str) -> String {
+    let mut rest = subject.trim();
+    loop {
+        let Some(colon) = rest.find(':') else { break };
+        let head = rest[..colon].trim_end();
+        let word = head.split(['[', '(']).next().unwrap_or(head).trim();
+        let counter = &head[word.len()..];
+        let counter_ok = counter.is_empty()
+            || ((counter.starts_with('[') && counter.ends_with(']'))
+                || (counter.starts_with('(') && counter.ends_with(')')))
+                && counter[1..counter.len() - 1].chars().all(|c| c.is_ascii_digit());
+        if counter_ok && SUBJECT_PREFIXES.contains(&word.to_ascii_lowercase().as_str()) {
+            rest = rest[colon + 1..].trim_start();
+        } else {
+            break;
+        }
+    }
+    rest.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn normalize_id(id: &str) -> &str {
+    id.trim().trim_matches(|c| c == '<' || c == '>')
+}
+
+fn is_unread(email: &CachedEmail) -> bool {
+    !email.flags.iter().any(|f| f.trim_start_matches('\\').eq_ignore_ascii_case("seen"))
+}
+
+/// A sender and how many messages they sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Participant {
+    pub address: String,
+    pub name: Option<String>,
+    pub message_count: usize,
+}
+
+/// One conversation within a folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadSummary {
+    /// Message-ID of the earliest message, or `uid:<n>` when it has none
+    pub thread_id: String,
+    pub subject: Option<String>,
+    pub message_count: usize,
+    pub unread_count: usize,
+    pub participants: Vec<String>,
+    pub first_date: Option<DateTime<Utc>>,
+    pub last_date: Option<DateTime<Utc>>,
+    /// UIDs in date order
+    pub uids: Vec<u32>,
+}
+
+/// Thread statistics for one folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderThreads {
+    pub message_count: usize,
+    pub thread_count: usize,
+    pub largest_thread: Option<ThreadSummary>,
+    pub top_participants: Vec<Participant>,
+    /// Most recently active first
+    pub threads: Vec<ThreadSummary>,
+}
+
+fn sent(email: &CachedEmail) -> Option<DateTime<Utc>> {
+    email.date.or(email.internal_date)
+}
+
+/// Sender and recipients, as bare lowercase addresses.
+fn participants(email: &CachedEmail) -> Vec<String> {
+    email.from_address.iter()
+        .chain(&email.to_addresses)
+        .chain(&email.cc_addresses)
+        .map(|address| {
+            let bare = address.rsplit('<').next().unwrap_or(address);
+            bare.trim().trim_end_matches('>').to_lowercase()
+        })
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+/// Whether two messages with the same subject but no reference between them
+/// belong to one conversation.
+fn same_conversation(a: &CachedEmail, b: &CachedEmail) -> bool {
+    let close = match (sent(a), sent(b)) {
+        (Some(x), Some(y)) => (x - y).abs() <= Duration::days(SUBJECT_MATCH_WINDOW_DAYS),
+        _ => false,
+    };
+    close || {
+        let theirs = participants(b);
+        participants(a).iter().any(|address| theirs.contains(address))
+    }
+}
+
+/// Group messages into conversations. Each group lists indices into `emails`.
+pub fn group_threads(emails: &[CachedEmail]) -> Vec<Vec<usize>> {
+    let messages: Vec<ThreadMessage> = emails.iter()
+        .map(|email| ThreadMessage {
+            uid: email.uid,
+            message_id: email.message_id.clone(),
+            in_reply_to: email.in_reply_to.clone(),
+            references: email.references_header.as_deref().map(parse_message_ids).unwrap_or_default(),
+            subject: email.subject.clone(),
+            date: sent(email),
+        })
+        .collect();
+    let index: HashMap<u32, usize> = emails.iter().enumerate().map(|(i, email)| (email.uid, i)).collect();
+
+    build_threads_where(ThreadAlgorithm::References, &messages, |a, b| same_conversation(&emails[a], &emails[b]))
+        .iter()
+        .map(|root| root.uids().into_iter().filter_map(|uid| index.get(&uid).copied()).collect())
+        .collect()
+}
+
+fn summarize(emails: &[CachedEmail], mut members: Vec<usize>) -> ThreadSummary {
+    let date = |i: usize| sent(&emails[i]);
+    members.sort_by_key(|&i| (date(i), emails[i].uid));
+
+    let first = &emails[members[0]];
+    let mut participants: Vec<String> = Vec::new();
+    for &i in &members {
+        if let Some(address) = emails[i].from_address.as_deref().map(str::to_lowercase) {
+            if !participants.contains(&address) {
+                participants.push(address);
+            }
+        }
+    }
+
+    ThreadSummary {
+        thread_id: first.message_id.as_deref()
+            .map(|id| normalize_id(id).to_string())
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("uid:{}", first.uid)),
+        subject: first.subject.clone(),
+        message_count: members.len(),
+        unread_count: members.iter().filter(|&&i| is_unread(&emails[i])).count(),
+        participants,
+        first_date: members.iter().filter_map(|&i| date(i)).min(),
+        last_date: members.iter().filter_map(|&i| date(i)).max(),
+        uids: members.iter().map(|&i| emails[i].uid).collect(),
+    }
+}
+
+/// Group `emails` into threads and compute folder-level statistics, keeping
+/// the `top_participants` most frequent senders.
+pub fn folder_threads(emails: &[CachedEmail], top_participants: usize) -> FolderThreads {
+    let mut threads: Vec<ThreadSummary> = group_threads(emails)
+        .into_iter()
+        .map(|members| summarize(emails, members))
+        .collect();
+    threads.sort_by(|a, b| b.last_date.cmp(&a.last_date).then_with(|| a.thread_id.cmp(&b.thread_id)));
+
+    let largest_thread = threads.iter()
+        .max_by(|a, b| a.message_count.cmp(&b.message_count).then_with(|| b.last_date.cmp(&a.last_date)))
+        .cloned();
+
+    let mut senders: HashMap<String, Participant> = HashMap::new();
+    for email in emails {
+        let Some(address) = email.from_address.as_deref().map(str::to_lowercase) else { continue };
+        let entry = senders.entry(address.clone()).or_insert_with(|| Participant {
+            address,
+            name: None,
+            message_count: 0,
+        });
+        entry.message_count += 1;
+        if entry.name.is_none() {
+            entry.name = email.from_name.clone().filter(|n| !n.is_empty());
+        }
+    }
+    let mut top: Vec<Participant> = senders.into_values().collect();
+    top.sort_by(|a, b| b.message_count.cmp(&a.message_count).then_with(|| a.address.cmp(&b.address)));
+    top.truncate(top_participants);
+
+    FolderThreads {
+        message_count: emails.len(),
+        thread_count: threads.len(),
+        largest_thread,
+        top_participants: top,
+        threads,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn email(uid: u32, message_id: &str, subject: &str, from: &str, in_reply_to: Option<&str>, refs: Option<&str>) -> CachedEmail {
+        CachedEmail {
+            id: uid as i64,
+            folder_id: 1,
+            uid,
+            message_id: Some(message_id.to_string()),
+            subject: Some(subject.to_string()),
+            from_address: Some(from.to_string()),
+            from_name: None,
+            to_addresses: vec![],
+            cc_addresses: vec![],
+            date: Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, uid).unwrap()),
+            internal_date: None,
+            size: None,
+            flags: vec![],
+            body_text: None,
+            body_html: None,
+            cached_at: Utc::now(),
+            has_attachments: false,
+            in_reply_to: in_reply_to.map(String::from),
+            references_header: refs.map(String::from),
+            attachment_parts: None,
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_normalize_subject() {
+        assert_eq!(normalize_subject("Re: Fwd:  Quarterly   Report"), "quarterly report");
+        assert_eq!(normalize_subject("RE[2]: AW: Budget"), "budget");
+        assert_eq!(normalize_subject("Note: re: this"), "note: re: this");
+        assert_eq!(normalize_subject("Re:"), "");
+    }
+
+    #[test]
+    fn test_groups_by_references_and_subject() {
+        let mut emails = vec![
+            email(1, "<a@x>", "Launch plan", "ann@x.com", None, None),
+            email(2, "<b@x>", "Something else entirely", "bob@x.com", Some("<a@x>"), None),
+            email(3, "<c@x>", "Re: Something else entirely", "ann@x.com", None, Some("<a@x> <b@x>")),
+            email(4, "<d@x>", "Re: Lunch", "cat@x.com", Some("<missing@x>"), None),
+            email(5, "<e@x>", "Lunch", "bob@x.com", None, None),
+            email(6, "<f@x>", "Unrelated", "dan@x.com", None, None),
+        ];
+        emails[0].flags = vec!["\\Seen".to_string()];
+
+        let stats = folder_threads(&emails, 2);
+        assert_eq!(stats.message_count, 6);
+        assert_eq!(stats.thread_count, 3);
+
+        let largest = stats.largest_thread.unwrap();
+        assert_eq!(largest.thread_id, "a@x");
+        assert_eq!(largest.uids, vec![1, 2, 3]);
+        assert_eq!(largest.unread_count, 2);
+        assert_eq!(largest.participants, vec!["ann@x.com", "bob@x.com"]);
+
+        assert_eq!(stats.threads[0].thread_id, "f@x");
+        assert_eq!(stats.threads[1].uids, vec![4, 5]);
+
+        assert_eq!(stats.top_participants.len(), 2);
+        assert_eq!(stats.top_participants[0].address, "ann@x.com");
+        assert_eq!(stats.top_participants[0].message_count, 2);
+        assert_eq!(stats.top_participants[1].address, "bob@x.com");
+    }
+
+    #[test]
+    fn test_subject_alone_does_not_join_threads() {
+        let mut emails = vec![
+            email(1, "<a@x>", "Invoice", "billing@shop.com", None, None),
+            email(2, "<b@x>", "Invoice", "accounts@other.com", None, None),
+            email(3, "<c@x>", "Re: Invoice", "me@x.com", Some("<gone@x>"), None),
+        ];
+        emails[1].date = Some(Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap());
+        emails[2].date = Some(Utc.with_ymd_and_hms(2025, 3, 2, 0, 0, 0).unwrap());
+        emails[2].to_addresses = vec!["Billing <billing@shop.com>".to_string()];
+
+        let mut groups: Vec<Vec<u32>> = group_threads(&emails).into_iter()
+            .map(|group| group.into_iter().map(|i| emails[i].uid).collect())
+            .collect();
+        groups.sort();
+        // Months apart and no common participant: separate. The reply
+        // shares a participant with the first, so joins it
+        assert_eq!(groups, vec![vec![1, 3], vec![2]]);
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Short-lived reuse of results for repeated read-only MCP tool calls.
+//!
+//! Agents often issue the same lookup several times in a row (list the
+//! folders, then list them again before each step). With deduplication on,
+//! a successful result from a read-only tool is kept for a few seconds under
+//! a key of tool name, account and a hash of the parameters, and identical
+//! calls inside that window get the stored result. Tools that change mail,
+//! folders or settings are never cached, and a successful call to one of
+//! them drops every stored result.
+//!
+//! - `MCP_TOOL_DEDUP_ENABLED` (default false)
+//! - `MCP_TOOL_DEDUP_TTL_SECONDS` (default 5)
+//! - `MCP_TOOL_DEDUP_MAX_ENTRIES` (default 500)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Tools whose result depends only on their parameters and the mailbox
+/// state. Anything that writes, talks to SMTP or reports job/sync progress
+/// is left out.
+const CACHEABLE_TOOLS: &[&str] = &[
+    "list_folders",
+    "list_folders_hierarchical",
+    "hierarchical_unread",
+    "get_unread_counts",
+    "list_cached_emails",
+    "get_email_by_uid",
+    "get_email_by_index",
+    "get_email_text",
+    "fetch_email_partial",
+    "count_emails_in_folder",
+    "get_folder_stats",
+    "search_cached_emails",
+    "search_cached_emails_fts",
+    "search_server",
+    "search_by_domain",
+    "search_by_attachment_type",
+    "filter_emails_by_subject",
+    "semantic_search",
+    "list_emails_by_flag",
+    "list_flagged",
+    "list_recent",
+    "list_email_attachments",
+    "get_email_synopsis",
+    "batch_get_synopsis",
+    "get_email_thread",
+    "folder_threads",
+    "get_thread",
+    "get_address_report",
+    "extract_links",
+    "check_uids_exist",
+    "compare_folders",
+    "get_server_info",
+    "get_special_folders",
+    "get_server_capabilities",
+    "list_accounts",
+    "all_accounts_summary",
+    "sieve_list_scripts",
+    "sieve_get_script",
+];
+
+/// Tools that neither change state nor are worth caching, so calling them
+/// leaves stored results alone.
+const PASSIVE_TOOLS: &[&str] = &[
+    "list_jobs",
+    "get_job_status",
+    "cache_status",
+    "sync_lag",
+    "next_unread",
+    "verify_recipient",
+    "fetch_emails_with_mime",
+    "get_attachment_content",
+    "export_folder_metadata",
+    "refresh_account_connections",
+    "get_quota",
+];
+
+struct Entry {
+    result: serde_json::Value,
+    stored_at: Instant,
+}
+
+lazy_static! {
+    static ref CACHE: DashMap<String, Entry> = DashMap::new();
+}
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static INVALIDATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Counters for the dashboard.
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupStats {
+    pub enabled: bool,
+    pub ttl_seconds: u64,
+    pub max_entries: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+    /// Hits over lookups, 0 before the first lookup
+    pub hit_rate: f64,
+}
+
+pub fn dedup_enabled() -> bool {
+    std::env::var("MCP_TOOL_DEDUP_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+fn ttl() -> Duration {
+    Duration::from_secs(
+        std::env::var("MCP_TOOL_DEDUP_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5),
+    )
+}
+
+fn max_entries() -> usize {
+    std::env::var("MCP_TOOL_DEDUP_MAX_ENTRIES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(500)
+}
+
+pub fn is_cacheable(tool_name: &str) -> bool {
+    CACHEABLE_TOOLS.contains(&tool_name)
+}
+
+/// Whether a call to this tool may have changed what cached tools return.
+pub fn is_mutating(tool_name: &str) -> bool {
+    !is_cacheable(tool_name) && !PASSIVE_TOOLS.contains(&tool_name)
+}
+
+/// Cache key for a call. serde_json orders object keys, so parameter order
+/// in the request does not matter.
+pub fn cache_key(tool_name: &str, params: &serde_json::Value) -> String {
+    let account = params.get("account_id").and_then(|v| v.as_str()).unwrap_or("");
+    let mut hasher = Sha256::new();
+    hasher.update(params.to_string().as_bytes());
+    format!("{}:{}:{}", tool_name, account, hex::encode(hasher.finalize()))
+}
+
+/// Stored result for this call, if one is still fresh.
+pub fn lookup(tool_name: &str, params: &serde_json::Value) -> Option<serde_json::Value> {
+    let key = cache_key(tool_name, params);
+    let ttl = ttl();
+    let hit = match CACHE.get(&key) {
+        Some(entry) if entry.stored_at.elapsed() < ttl => Some(entry.result.clone()),
+        _ => None,
+    };
+    match hit {
+        Some(result) => {
+            HITS.fetch_add(1, Ordering::Relaxed);
+            Some(result)
+        }
+        None => {
+            CACHE.remove_if(&key, |_, entry| entry.stored_at.elapsed() >= ttl);
+            MISSES.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+/// Keep a successful result. Errors are never stored so a retry goes to the
+/// server again.
+pub fn store(tool_name: &str, params: &serde_json::Value, result: &serde_json::Value) {
+    if result.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        return;
+    }
+    let max = max_entries();
+    if max == 0 {
+        return;
+    }
+    if CACHE.len() >= max {
+        let ttl = ttl();
+        CACHE.retain(|_, entry| entry.stored_at.elapsed() < ttl);
+    }
+    if CACHE.len() >= max {
+        let oldest = CACHE.iter()
+            .min_by_key(|entry| entry.stored_at)
+            .map(|entry| entry.key().clone());
+        if let Some(key) = oldest {
+            CACHE.remove(&key);
+        }
+    }
+    CACHE.insert(cache_key(tool_name, params), Entry {
+        result: result.clone(),
+        stored_at: Instant::now(),
+    });
+}
+
+/// Drop every stored result. Called after a mutating tool succeeds; a
+/// mutation can show up in other accounts' results too (moves between
+/// accounts, the all-accounts summary), so nothing is kept.
+pub fn invalidate_all() {
+    if !CACHE.is_empty() {
+        CACHE.clear();
+        INVALIDATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn stats() -> DedupStats {
+    let hits = HITS.load(Ordering::Relaxed);
+    let misses = MISSES.load(Ordering::Relaxed);
+    let lookups = hits + misses;
+    DedupStats {
+        enabled: dedup_enabled(),
+        ttl_seconds: ttl().as_secs(),
+        max_entries: max_entries(),
+        entries: CACHE.len(),
+        hits,
+        misses,
+        invalidations: INVALIDATIONS.load(Ordering::Relaxed),
+        hit_rate: if lookups > 0 { hits as f64 / lookups as f64 } else { 0.0 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_dedup_key_and_tool_classes() {
+        // Key ignores parameter order but not values or account
+        let a = cache_key("list_cached_emails", &json!({"account_id": "a@x.com", "folder": "INBOX", "limit": 10}));
+        let b = cache_key("list_cached_emails", &json!({"limit": 10, "folder": "INBOX", "account_id": "a@x.com"}));
+        let c = cache_key("list_cached_emails", &json!({"account_id": "b@x.com", "folder": "INBOX", "limit": 10}));
+        let d = cache_key("list_cached_emails", &json!({"account_id": "a@x.com", "folder": "INBOX", "limit": 20}));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+        assert_ne!(a, cache_key("search_cached_emails", &json!({"account_id": "a@x.com", "folder": "INBOX", "limit": 10})));
+
+        assert!(is_cacheable("list_folders"));
+        assert!(!is_cacheable("mark_as_read"));
+        assert!(is_mutating("mark_as_read"));
+        assert!(is_mutating("send_email"));
+        assert!(!is_mutating("list_folders"));
+        assert!(!is_mutating("get_job_status"));
+    }
+
+    #[test]
+    fn test_dedup_store_and_lookup() {
+        let params = json!({"account_id": "dedup@x.com", "folder": "Dedup"});
+        let ok = json!({"success": true, "data": [1, 2, 3]});
+
+        store("count_emails_in_folder", &params, &json!({"success": false, "error": "boom"}));
+        assert!(lookup("count_emails_in_folder", &params).is_none());
+
+        store("count_emails_in_folder", &params, &ok);
+        assert_eq!(lookup("count_emails_in_folder", &params), Some(ok));
+
+        invalidate_all();
+        assert!(lookup("count_emails_in_folder", &params).is_none());
+    }
+}
@@ -0,0 +1,409 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Webhook notifications for new mail and sync events.
+//!
+//! Endpoints are configured as `[[webhooks]]` entries, or for a single
+//! endpoint with `WEBHOOK_URL`, `WEBHOOK_SECRET` and `WEBHOOK_EVENTS`. Each
+//! endpoint names the SSE event types it wants (`new_mail`, `sync_completed`,
+//! `sync_failed`, ...). Matching events are POSTed as JSON with
+//! `X-Signature: sha256=<hex>`, the HMAC-SHA256 of the body under the
+//! endpoint's secret. A failed delivery is retried with exponential backoff;
+//! when the attempts run out it goes to the `webhook_dead_letters` table.
+//!
+//! Events reach the endpoints from two places:
+//! - the server's `EventBus`: `new_mail` from IDLE watchers, dashboard and
+//!   MCP syncs, and `sync_completed`/`sync_failed` from `SyncService`;
+//! - the `rustymail-sync` process, which runs the scheduled syncs and has no
+//!   bus. It calls [`WebhookDispatcher::dispatch`] itself for `new_mail` (new
+//!   UIDs in a folder that had been synced before) and for
+//!   `sync_completed`/`sync_failed` once per account.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{Row, SqlitePool};
+use uuid::Uuid;
+
+use crate::config::{Settings, WebhookConfig};
+use crate::dashboard::api::sse::EventType;
+use super::events::{DashboardEvent, EventBus};
+
+/// Event types delivered to an endpoint that doesn't list any.
+const DEFAULT_EVENTS: [EventType; 3] = [EventType::NewMail, EventType::SyncCompleted, EventType::SyncFailed];
+
+/// Longest wait between two attempts, however many have failed.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// `X-Signature` value for `body`: `sha256=` and the hex HMAC-SHA256 under `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC takes keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// A configured endpoint and the event types it receives.
+#[derive(Debug, Clone)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    secret: String,
+    events: HashSet<EventType>,
+}
+
+impl WebhookEndpoint {
+    pub fn new(config: &WebhookConfig) -> Self {
+        let events = if config.events.is_empty() {
+            DEFAULT_EVENTS.into_iter().collect()
+        } else {
+            config.events.iter()
+                .filter_map(|name| match EventType::from_string(name.trim()) {
+                    Some(EventType::Welcome) | None => {
+                        warn!("Ignoring unknown event type '{}' for webhook {}", name, config.url);
+                        None
+                    }
+                    Some(event_type) => Some(event_type),
+                })
+                .collect()
+        };
+        Self { url: config.url.clone(), secret: config.secret.clone(), events }
+    }
+
+    pub fn wants(&self, event_type: &EventType) -> bool {
+        self.events.contains(event_type)
+    }
+}
+
+/// How often and how far apart a delivery is attempted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// From `WEBHOOK_MAX_ATTEMPTS` (default 5) and `WEBHOOK_RETRY_BASE_MS`
+    /// (default 1000).
+    pub fn from_env() -> Self {
+        let max_attempts = std::env::var("WEBHOOK_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5u32);
+        let base_ms = std::env::var("WEBHOOK_RETRY_BASE_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1000);
+        Self { max_attempts: max_attempts.max(1), base_delay: Duration::from_millis(base_ms) }
+    }
+
+    /// Wait after failed attempt number `attempt` (from 1): the base delay,
+    /// doubled for each earlier failure, up to five minutes.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+}
+
+/// A delivery that was given up on.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadLetter {
+    pub id: i64,
+    pub url: String,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// The `webhook_dead_letters` table.
+pub struct DeadLetterLog {
+    pool: SqlitePool,
+}
+
+impl DeadLetterLog {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(&self, url: &str, event_type: &str, payload: &str, attempts: u32, last_error: &str) -> Result<(), String> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_dead_letters (url, event_type, payload, attempts, last_error, failed_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(url)
+        .bind(event_type)
+        .bind(payload)
+        .bind(attempts as i64)
+        .bind(last_error)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+        Ok(())
+    }
+
+    /// Newest first.
+    pub async fn list(&self, limit: i64) -> Result<Vec<DeadLetter>, String> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, url, event_type, payload, attempts, last_error, failed_at
+            FROM webhook_dead_letters
+            ORDER BY id DESC
+            LIMIT ?
+            "#
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(rows.iter().map(|row| {
+            let attempts: i64 = row.get("attempts");
+            let failed_at: String = row.get("failed_at");
+            DeadLetter {
+                id: row.get("id"),
+                url: row.get("url"),
+                event_type: row.get("event_type"),
+                payload: row.get("payload"),
+                attempts: attempts as u32,
+                last_error: row.get("last_error"),
+                failed_at: DateTime::parse_from_rfc3339(&failed_at)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+            }
+        }).collect())
+    }
+}
+
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    endpoints: Vec<WebhookEndpoint>,
+    retry: RetryPolicy,
+    dead_letters: DeadLetterLog,
+}
+
+impl WebhookDispatcher {
+    pub fn new(endpoints: Vec<WebhookEndpoint>, retry: RetryPolicy, dead_letters: DeadLetterLog) -> Self {
+        let timeout = std::env::var("WEBHOOK_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout))
+            .build()
+            .unwrap_or_default();
+        Self { client, endpoints, retry, dead_letters }
+    }
+
+    /// Dispatcher for the `[[webhooks]]` entries plus the `WEBHOOK_*`
+    /// variables. Returns None when no endpoint is configured.
+    pub fn from_settings(settings: &Settings, pool: SqlitePool) -> Option<Self> {
+        let mut configs = settings.webhooks.clone();
+        if let Ok(url) = std::env::var("WEBHOOK_URL") {
+            match std::env::var("WEBHOOK_SECRET") {
+                Ok(secret) => {
+                    let events = std::env::var("WEBHOOK_EVENTS")
+                        .map(|v| v.split(',').map(|e| e.trim().to_string()).filter(|e| !e.is_empty()).collect())
+                        .unwrap_or_default();
+                    configs.push(WebhookConfig { url, secret, events });
+                }
+                Err(_) => warn!("WEBHOOK_URL is set but WEBHOOK_SECRET is not; ignoring it"),
+            }
+        }
+        if configs.is_empty() {
+            return None;
+        }
+        let endpoints = configs.iter().map(WebhookEndpoint::new).collect();
+        Some(Self::new(endpoints, RetryPolicy::from_env(), DeadLetterLog::new(pool)))
+    }
+
+    /// Body POSTed for `event`.
+    pub fn payload(event_type: &EventType, event: &DashboardEvent) -> Value {
+        json!({
+            "id": Uuid::new_v4().to_string(),
+            "event": event_type.to_string(),
+            "timestamp": Utc::now().to_rfc3339(),
+            "data": event,
+        })
+    }
+
+    /// Deliver `event` to every endpoint subscribed to its type and wait
+    /// until each delivery has succeeded or been dead-lettered.
+    pub async fn dispatch(&self, event: &DashboardEvent) {
+        let event_type = EventType::of(event);
+        let endpoints: Vec<&WebhookEndpoint> = self.endpoints.iter().filter(|e| e.wants(&event_type)).collect();
+        if endpoints.is_empty() {
+            return;
+        }
+        // Built once, so every endpoint gets the same delivery id
+        let body = Self::payload(&event_type, event).to_string();
+        futures::future::join_all(
+            endpoints.into_iter().map(|endpoint| self.deliver(endpoint, &event_type, body.clone())),
+        ).await;
+    }
+
+    /// POST `body` until the endpoint accepts it or the attempts run out,
+    /// then record it as a dead letter.
+    async fn deliver(&self, endpoint: &WebhookEndpoint, event_type: &EventType, body: String) {
+        let signature = sign(&endpoint.secret, body.as_bytes());
+        let mut attempt = 0;
+        let last_error = loop {
+            attempt += 1;
+            let error = match self.client.post(&endpoint.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header("X-Signature", &signature)
+                .header("X-Webhook-Event", event_type.to_string())
+                .body(body.clone())
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => {
+                    debug!("Delivered {} webhook to {}", event_type.to_string(), endpoint.url);
+                    return;
+                }
+                Ok(response) => format!("HTTP {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+            if attempt >= self.retry.max_attempts {
+                break error;
+            }
+            let delay = self.retry.delay(attempt);
+            debug!("Webhook {} failed ({}), retrying in {:?}", endpoint.url, error, delay);
+            tokio::time::sleep(delay).await;
+        };
+
+        warn!("Giving up on {} webhook to {} after {} attempt(s): {}", event_type.to_string(), endpoint.url, attempt, last_error);
+        if let Err(e) = self.dead_letters.record(&endpoint.url, event_type.to_string(), &body, attempt, &last_error).await {
+            warn!("Failed to record webhook dead letter for {}: {}", endpoint.url, e);
+        }
+    }
+}
+
+/// Forward bus events to the endpoints subscribed to their type. Each
+/// event is delivered on its own task and to all its endpoints at once, so a
+/// slow endpoint doesn't hold up others.
+pub async fn start_webhook_dispatcher(dispatcher: Arc<WebhookDispatcher>, event_bus: Arc<EventBus>) {
+    let mut subscription = event_bus.subscribe().await;
+    let endpoint_count = dispatcher.endpoints.len();
+
+    tokio::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            let dispatcher = Arc::clone(&dispatcher);
+            tokio::spawn(async move {
+                dispatcher.dispatch(&event).await;
+            });
+        }
+        warn!("Webhook dispatcher stopped - subscription ended");
+    });
+
+    info!("Started webhook dispatcher for {} endpoint(s)", endpoint_count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Executor;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_endpoint_event_filter() {
+        let config = |events: &[&str]| WebhookConfig {
+            url: "https://hooks.example.com/mail".to_string(),
+            secret: "s3cret".to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+        };
+
+        let defaults = WebhookEndpoint::new(&config(&[]));
+        assert!(defaults.wants(&EventType::NewMail));
+        assert!(defaults.wants(&EventType::SyncFailed));
+        assert!(!defaults.wants(&EventType::StatsUpdate));
+
+        let new_mail_only = WebhookEndpoint::new(&config(&["new_mail", "welcome", "bogus"]));
+        assert!(new_mail_only.wants(&EventType::NewMail));
+        assert!(!new_mail_only.wants(&EventType::SyncCompleted));
+        assert!(!new_mail_only.wants(&EventType::Welcome));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        let retry = RetryPolicy { max_attempts: 5, base_delay: Duration::from_millis(500) };
+        assert_eq!(retry.delay(1), Duration::from_millis(500));
+        assert_eq!(retry.delay(3), Duration::from_secs(2));
+        assert_eq!(retry.delay(40), MAX_RETRY_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_endpoint_is_dead_lettered() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        pool.execute(include_str!("../../../migrations/025_create_webhook_dead_letters.sql"))
+            .await
+            .unwrap();
+        let endpoint = WebhookEndpoint::new(&WebhookConfig {
+            url: "http://127.0.0.1:1/hook".to_string(),
+            secret: "s3cret".to_string(),
+            events: vec![],
+        });
+        let dispatcher = WebhookDispatcher::new(
+            vec![endpoint.clone()],
+            RetryPolicy { max_attempts: 2, base_delay: Duration::from_millis(1) },
+            DeadLetterLog::new(pool),
+        );
+
+        dispatcher.deliver(&endpoint, &EventType::SyncFailed, "{}".to_string()).await;
+
+        let letters = dispatcher.dead_letters.list(10).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].event_type, "sync_failed");
+        assert_eq!(letters[0].attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_waits_for_subscribed_endpoints_only() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        pool.execute(include_str!("../../../migrations/025_create_webhook_dead_letters.sql"))
+            .await
+            .unwrap();
+        let endpoint = |events: &[&str]| WebhookEndpoint::new(&WebhookConfig {
+            url: "http://127.0.0.1:1/hook".to_string(),
+            secret: "s3cret".to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+        });
+        let dispatcher = WebhookDispatcher::new(
+            vec![endpoint(&["sync_failed"]), endpoint(&["new_mail"])],
+            RetryPolicy { max_attempts: 1, base_delay: Duration::from_millis(1) },
+            DeadLetterLog::new(pool),
+        );
+
+        dispatcher.dispatch(&DashboardEvent::SyncFailed {
+            account_id: "a@example.com".to_string(),
+            error: "login failed".to_string(),
+            timestamp: Utc::now(),
+        }).await;
+
+        // Returns only once the delivery has been given up on
+        let letters = dispatcher.dead_letters.list(10).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].event_type, "sync_failed");
+        assert!(letters[0].payload.contains("login failed"));
+    }
+}
@@ -83,9 +83,10 @@ impl ErrorMapper {
     pub fn imap_to_error_code(err: &ImapError) -> ErrorCode {
         match err {
             ImapError::Connection(_) => ErrorCode::ImapConnectionError,
-            ImapError::Tls(_) => ErrorCode::ImapConnectionError,
+            ImapError::Tls(_) | ImapError::TlsVerification(_) => ErrorCode::ImapConnectionError,
+            ImapError::CircuitOpen(_) => ErrorCode::ImapConnectionError,
             ImapError::Auth(_) => ErrorCode::ImapAuthError,
-            ImapError::InvalidMailbox(_) => ErrorCode::ImapInvalidMailbox,
+            ImapError::InvalidMailbox(_) | ImapError::NotSelectable(_) => ErrorCode::ImapInvalidMailbox,
             ImapError::FolderNotFound(_) => ErrorCode::ImapFolderNotFound,
             ImapError::FolderExists(_) => ErrorCode::ImapFolderExists,
             ImapError::EmailNotFound(_) => ErrorCode::ImapEmailNotFound,
@@ -105,6 +106,7 @@ impl ErrorMapper {
             ImapError::Io(_) => ErrorCode::ImapConnectionError,
             ImapError::Encoding(_) => ErrorCode::ParseError,
             ImapError::Validation(_) => ErrorCode::InvalidParams,
+            ImapError::Unsupported(_) => ErrorCode::ImapCommandError,
             ImapError::Other(_) | ImapError::Unknown(_) => ErrorCode::UnknownError,
         }
     }
@@ -126,6 +128,12 @@ impl ErrorMapper {
                     "folder": folder
                 }));
             },
+            ImapError::NotSelectable(folder) => {
+                details.context = Some(serde_json::json!({
+                    "folder": folder,
+                    "selectable": false
+                }));
+            },
             ImapError::FolderExists(folder) => {
                 details.context = Some(serde_json::json!({
                     "folder": folder
@@ -146,6 +154,11 @@ impl ErrorMapper {
                     "criteria": criteria
                 }));
             },
+            ImapError::TlsVerification(reason) => {
+                details.context = Some(serde_json::json!({
+                    "tls_verification": reason
+                }));
+            },
             _ => {}
         }
 
@@ -12,6 +12,9 @@ use log::info;
 use serde::Serialize;
 use sqlx::SqlitePool;
 
+use crate::dashboard::services::cache_crypto;
+use crate::imap::dates;
+
 /// Default maximum results if not specified by the caller.
 const DEFAULT_MAX_RESULTS: usize = 500;
 
@@ -56,8 +59,9 @@ impl SubjectFilter {
     /// - `match_mode`: "any" (default) or "all"
     /// - `sender_filter`: optional sender address/domain substring
     /// - `recipient_filter`: optional recipient address/domain substring
-    /// - `date_after`: optional lower bound (inclusive)
-    /// - `date_before`: optional upper bound (inclusive)
+    /// - `date_after`: optional lower bound (inclusive), converted to UTC
+    /// - `date_before`: optional upper bound (inclusive), converted to UTC; a
+    ///   bare date (YYYY-MM-DD) includes that whole day
     /// - `max_results`: optional cap (default: 500)
     #[allow(clippy::too_many_arguments)]
     pub async fn filter(
@@ -78,6 +82,8 @@ impl SubjectFilter {
 
         let mode = match_mode.unwrap_or("any");
         let limit = max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+        let date_after = date_after.map(dates::parse_timestamp).transpose()?;
+        let date_before = date_before.map(dates::parse_range_end).transpose()?;
 
         // 1. Resolve folder_id
         let folder_id = self.resolve_folder_id(account_id, folder).await?;
@@ -137,8 +143,8 @@ impl SubjectFilter {
         match_mode: &str,
         sender_filter: Option<&str>,
         recipient_filter: Option<&str>,
-        date_after: Option<&str>,
-        date_before: Option<&str>,
+        date_after: Option<DateTime<Utc>>,
+        date_before: Option<DateTime<Utc>>,
         limit: usize,
     ) -> Result<Vec<RawFilterRow>, Box<dyn std::error::Error>> {
         // We build the query dynamically because the number of pattern
@@ -153,12 +159,16 @@ impl SubjectFilter {
              WHERE e.folder_id = ?"
         );
 
-        // Subject pattern conditions
-        let joiner = if match_mode == "all" { " AND " } else { " OR " };
-        let pattern_clauses: Vec<String> = patterns.iter()
-            .map(|_| "e.subject LIKE ? COLLATE NOCASE".to_string())
-            .collect();
-        sql.push_str(&format!(" AND ({})", pattern_clauses.join(joiner)));
+        // Subject pattern conditions. Encrypted subjects are matched after
+        // decryption instead, which also rules out the SQL LIMIT.
+        let encrypted = cache_crypto::active();
+        if !encrypted {
+            let joiner = if match_mode == "all" { " AND " } else { " OR " };
+            let pattern_clauses: Vec<String> = patterns.iter()
+                .map(|_| "e.subject LIKE ? COLLATE NOCASE".to_string())
+                .collect();
+            sql.push_str(&format!(" AND ({})", pattern_clauses.join(joiner)));
+        }
 
         // Optional filters
         if sender_filter.is_some() {
@@ -167,22 +177,27 @@ impl SubjectFilter {
         if recipient_filter.is_some() {
             sql.push_str(" AND e.to_addresses LIKE ? COLLATE NOCASE");
         }
+        // datetime() normalises both sides to UTC so 'Z' and '+00:00' suffixes compare equal
         if date_after.is_some() {
-            sql.push_str(" AND e.date >= ?");
+            sql.push_str(" AND datetime(e.date) >= datetime(?)");
         }
         if date_before.is_some() {
-            sql.push_str(" AND e.date <= ?");
+            sql.push_str(" AND datetime(e.date) <= datetime(?)");
         }
 
         sql.push_str(" ORDER BY e.date DESC");
-        sql.push_str(&format!(" LIMIT {}", limit));
+        if !encrypted {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
 
         // Bind parameters in order
         let mut query = sqlx::query_as::<_, RawFilterRow>(&sql)
             .bind(folder_id);
 
-        for pattern in patterns {
-            query = query.bind(format!("%{}%", pattern));
+        if !encrypted {
+            for pattern in patterns {
+                query = query.bind(format!("%{}%", pattern));
+            }
         }
         if let Some(sender) = sender_filter {
             query = query.bind(format!("%{}%", sender));
@@ -191,14 +206,32 @@ impl SubjectFilter {
             query = query.bind(format!("%{}%", recipient));
         }
         if let Some(after) = date_after {
-            query = query.bind(after.to_string());
+            query = query.bind(after.to_rfc3339());
         }
         if let Some(before) = date_before {
-            query = query.bind(before.to_string());
+            query = query.bind(before.to_rfc3339());
         }
 
         let rows = query.fetch_all(&self.db_pool).await?;
-        Ok(rows)
+        if !encrypted {
+            return Ok(rows);
+        }
+
+        let patterns_lower: Vec<String> = patterns.iter().map(|p| p.to_lowercase()).collect();
+        Ok(rows.into_iter()
+            .filter_map(|mut row| {
+                row.subject = cache_crypto::reveal(row.subject);
+                let subject = row.subject.as_deref().unwrap_or("").to_lowercase();
+                let hit = |p: &String| subject.contains(p.as_str());
+                let matched = if match_mode == "all" {
+                    patterns_lower.iter().all(hit)
+                } else {
+                    patterns_lower.iter().any(hit)
+                };
+                matched.then_some(row)
+            })
+            .take(limit)
+            .collect())
     }
 }
 
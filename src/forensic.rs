@@ -18,6 +18,8 @@ use std::path::PathBuf;
 use zip::write::FileOptions;
 use zip::ZipWriter;
 
+use crate::dashboard::services::cache_crypto::reveal;
+
 /// Default directory for forensic archives when env var is not set.
 const DEFAULT_ARCHIVE_DIR: &str = "data/forensic_archives";
 
@@ -91,14 +93,14 @@ pub async fn create_forensic_archive(
         let email_json = json!({
             "uid": row.uid,
             "message_id": row.message_id,
-            "subject": row.subject,
+            "subject": reveal(row.subject.clone()),
             "from_address": row.from_address,
             "from_name": row.from_name,
             "to_addresses": row.to_addresses,
             "cc_addresses": row.cc_addresses,
             "date": row.date,
-            "body_text": row.body_text,
-            "body_html": row.body_html,
+            "body_text": reveal(row.body_text.clone()),
+            "body_html": reveal(row.body_html.clone()),
             "flags": row.flags,
             "size": row.size,
             "has_attachments": row.has_attachments,
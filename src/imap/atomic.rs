@@ -5,15 +5,17 @@
 
 // Atomic IMAP operations with ACID properties
 use async_trait::async_trait;
-use log::{debug, error, info, warn};
+use tracing::{debug, error, info, warn};
+use serde::Serialize;
 use std::collections::HashSet;
+use std::fmt;
 use tokio::sync::RwLock;
 use std::sync::Arc;
 
 use super::{
     error::ImapError,
     session::{AsyncImapSessionWrapper, AsyncImapOps},
-    types::FlagOperation,
+    types::{uid_set, CopiedUid, CopyOutcome, FlagOperation},
 };
 
 /// Represents a transaction log entry for rollback support
@@ -183,30 +185,376 @@ impl AtomicImapOperations {
         Ok(())
     }
 
-    /// Perform atomic batch move of multiple messages
-    pub async fn atomic_batch_move(&self, uids: &[u32], from_folder: &str, to_folder: &str) -> Result<(), ImapError> {
+    /// Copy messages from the selected folder to `target`, leaving the
+    /// originals in place.
+    ///
+    /// Like a move, the source UIDs are verified first; ones no longer in the
+    /// folder are reported in `missing` instead of failing the copy. A single
+    /// UID COPY is issued, which servers apply all-or-nothing, and nothing is
+    /// flagged or expunged. With UIDPLUS the new UIDs come back in `new_uids`.
+    pub async fn copy_messages(&self, uids: &[u32], target: &str) -> Result<CopyOutcome, ImapError> {
+        info!("Starting copy of {} messages to {}", uids.len(), target);
+
+        let present: HashSet<u32> = if uids.is_empty() {
+            HashSet::new()
+        } else {
+            self.session.search_emails(&format!("UID {}", uid_set(uids))).await?.into_iter().collect()
+        };
+        let mut requested = uids.to_vec();
+        requested.sort_unstable();
+        requested.dedup();
+        let (copied, missing): (Vec<u32>, Vec<u32>) = requested.into_iter().partition(|uid| present.contains(uid));
+
+        let mut outcome = CopyOutcome { copied, missing, ..CopyOutcome::default() };
+        if outcome.copied.is_empty() {
+            return Ok(outcome);
+        }
+
+        if let Some((uid_validity, new_uids)) = self.session.uid_copy_with_uids(&outcome.copied, target).await? {
+            if new_uids.len() != outcome.copied.len() {
+                warn!("COPYUID reported {} of {} copied messages", new_uids.len(), outcome.copied.len());
+            }
+            outcome.target_uid_validity = Some(uid_validity);
+            outcome.new_uids = new_uids;
+        } else {
+            debug!("Server sent no COPYUID; new UIDs in {} are unknown", target);
+        }
+
+        info!("Copied {} messages to {}", outcome.copied.len(), target);
+        Ok(outcome)
+    }
+
+    /// Move `uids` from `from_folder` to `to_folder` as one transaction:
+    /// COPY them all, check the COPYUID count, then flag the originals
+    /// \Deleted and expunge them. A failure after the COPY removes the new
+    /// copies from the target again (only those whose original is still in
+    /// the source, so nothing is lost) and is reported with the phase it
+    /// happened in and where each message ended up.
+    pub async fn atomic_batch_move(&self, uids: &[u32], from_folder: &str, to_folder: &str) -> Result<(), AtomicMoveError> {
         info!("Starting atomic batch move of {} messages from {} to {}", uids.len(), from_folder, to_folder);
+        batch_move(&self.session, uids, from_folder, to_folder).await?;
+        info!("Atomic batch move to {} completed", to_folder);
+        Ok(())
+    }
+}
 
-        let mut failed_uids = Vec::new();
+/// Step of a batch move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovePhase {
+    /// Selecting the source folder and checking the messages are there
+    Verify,
+    /// COPY to the target, including the COPYUID count check
+    Copy,
+    /// Flagging the originals \Deleted
+    MarkDeleted,
+    Expunge,
+}
 
-        for &uid in uids {
-            if let Err(e) = self.atomic_move(uid, from_folder, to_folder).await {
-                error!("Failed to move UID {}: {:?}", uid, e);
-                failed_uids.push(uid);
-            }
+impl fmt::Display for MovePhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MovePhase::Verify => "verify",
+            MovePhase::Copy => "copy",
+            MovePhase::MarkDeleted => "mark deleted",
+            MovePhase::Expunge => "expunge",
+        })
+    }
+}
+
+/// A failed batch move and the state it left the messages in. The counts
+/// add up to `total`.
+#[derive(Debug, Clone)]
+pub struct AtomicMoveError {
+    pub phase: MovePhase,
+    pub cause: ImapError,
+    pub total: usize,
+    /// Only in the target folder: these were moved before the failure
+    pub moved: usize,
+    /// Only in the source folder, as before the move
+    pub in_source: usize,
+    /// In both folders, because their copies could not be removed
+    pub duplicated: usize,
+    /// Why the rollback didn't finish, when it didn't
+    pub rollback_error: Option<String>,
+}
+
+impl fmt::Display for AtomicMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Batch move failed during {}: {} ({} of {} moved, {} still in source, {} in both folders)",
+            self.phase, self.cause, self.moved, self.total, self.in_source, self.duplicated
+        )?;
+        if let Some(rollback_error) = &self.rollback_error {
+            write!(f, "; rollback failed: {}", rollback_error)?;
         }
+        Ok(())
+    }
+}
 
-        if !failed_uids.is_empty() {
-            Err(ImapError::Other(format!("Failed to move UIDs: {:?}", failed_uids)))
-        } else {
-            Ok(())
+impl std::error::Error for AtomicMoveError {}
+
+impl From<AtomicMoveError> for ImapError {
+    fn from(err: AtomicMoveError) -> Self {
+        match err.cause {
+            // Keep the type callers match on when nothing was touched
+            ImapError::MissingData(_) | ImapError::FolderNotFound(_) if err.in_source == err.total => err.cause,
+            _ => ImapError::Other(err.to_string()),
+        }
+    }
+}
+
+/// The session operations a batch move needs, so it can run against a mock.
+#[async_trait]
+trait MoveSession: Send + Sync {
+    async fn select(&self, folder: &str) -> Result<(), ImapError>;
+    /// Which of `uids` exist in the selected folder
+    async fn existing(&self, uids: &[u32]) -> Result<Vec<u32>, ImapError>;
+    async fn copy(&self, uids: &[u32], to_folder: &str) -> Result<Option<(u32, Vec<CopiedUid>)>, ImapError>;
+    async fn store_deleted(&self, uids: &[u32], operation: FlagOperation) -> Result<(), ImapError>;
+    async fn expunge(&self) -> Result<(), ImapError>;
+    async fn uid_expunge(&self, uids: &[u32]) -> Result<(), ImapError>;
+}
+
+#[async_trait]
+impl MoveSession for AsyncImapSessionWrapper {
+    async fn select(&self, folder: &str) -> Result<(), ImapError> {
+        self.ensure_folder_selected(folder).await
+    }
+
+    async fn existing(&self, uids: &[u32]) -> Result<Vec<u32>, ImapError> {
+        self.search_emails(&format!("UID {}", uid_set(uids))).await
+    }
+
+    async fn copy(&self, uids: &[u32], to_folder: &str) -> Result<Option<(u32, Vec<CopiedUid>)>, ImapError> {
+        self.uid_copy_with_uids(uids, to_folder).await
+    }
+
+    async fn store_deleted(&self, uids: &[u32], operation: FlagOperation) -> Result<(), ImapError> {
+        self.store_flags(uids, operation, &[String::from("\\Deleted")]).await
+    }
+
+    async fn expunge(&self) -> Result<(), ImapError> {
+        AsyncImapOps::expunge(self).await
+    }
+
+    async fn uid_expunge(&self, uids: &[u32]) -> Result<(), ImapError> {
+        AsyncImapSessionWrapper::uid_expunge(self, uids).await
+    }
+}
+
+async fn batch_move<S: MoveSession + ?Sized>(session: &S, uids: &[u32], from_folder: &str, to_folder: &str) -> Result<(), AtomicMoveError> {
+    let mut uids = uids.to_vec();
+    uids.sort_unstable();
+    uids.dedup();
+    let total = uids.len();
+    if total == 0 {
+        return Ok(());
+    }
+    let untouched = |phase, cause| AtomicMoveError {
+        phase, cause, total, moved: 0, in_source: total, duplicated: 0, rollback_error: None,
+    };
+
+    // Verify: nothing has changed yet
+    session.select(from_folder).await.map_err(|e| untouched(MovePhase::Verify, e))?;
+    let present: HashSet<u32> = session.existing(&uids).await
+        .map_err(|e| untouched(MovePhase::Verify, e))?
+        .into_iter().collect();
+    let missing: Vec<u32> = uids.iter().copied().filter(|uid| !present.contains(uid)).collect();
+    if !missing.is_empty() {
+        return Err(untouched(MovePhase::Verify, ImapError::MissingData(format!("UIDs {:?} not found in {}", missing, from_folder))));
+    }
+
+    // Copy: servers apply UID COPY all-or-nothing, so a failure leaves no copies
+    let copied = session.copy(&uids, to_folder).await.map_err(|e| untouched(MovePhase::Copy, e))?;
+    let new_uids = match copied {
+        Some((_, new_uids)) if new_uids.len() != total => {
+            let cause = ImapError::Other(format!("COPYUID reported {} of {} copied messages", new_uids.len(), total));
+            return Err(roll_back(session, MovePhase::Copy, cause, &uids, Some(&new_uids), from_folder, to_folder).await);
         }
+        Some((_, new_uids)) => Some(new_uids),
+        None => {
+            warn!("Server sent no COPYUID; copies in {} cannot be removed if the move fails", to_folder);
+            None
+        }
+    };
+
+    if let Err(e) = session.store_deleted(&uids, FlagOperation::Add).await {
+        return Err(roll_back(session, MovePhase::MarkDeleted, e, &uids, new_uids.as_deref(), from_folder, to_folder).await);
+    }
+
+    // COPYUID means UIDPLUS, so only our messages need expunging
+    let expunged = match &new_uids {
+        Some(_) => session.uid_expunge(&uids).await,
+        None => session.expunge().await,
+    };
+    if let Err(e) = expunged {
+        return Err(roll_back(session, MovePhase::Expunge, e, &uids, new_uids.as_deref(), from_folder, to_folder).await);
+    }
+    Ok(())
+}
+
+/// Undo a batch move that failed after COPY. Messages already expunged from
+/// the source count as moved and keep their copy; the rest are unflagged and
+/// their copies removed from the target.
+async fn roll_back<S: MoveSession + ?Sized>(
+    session: &S,
+    phase: MovePhase,
+    cause: ImapError,
+    uids: &[u32],
+    new_uids: Option<&[CopiedUid]>,
+    from_folder: &str,
+    to_folder: &str,
+) -> AtomicMoveError {
+    error!("Batch move to {} failed during {}: {}. Rolling back...", to_folder, phase, cause);
+    let total = uids.len();
+    let mut err = AtomicMoveError {
+        phase, cause, total, moved: 0, in_source: 0, duplicated: total, rollback_error: None,
+    };
+
+    let still_in_source: HashSet<u32> = match async {
+        session.select(from_folder).await?;
+        session.existing(uids).await
+    }.await {
+        Ok(present) => present.into_iter().collect(),
+        Err(e) => {
+            // Unknown which originals survived; assume all did
+            err.rollback_error = Some(format!("could not check {}: {}", from_folder, e));
+            return err;
+        }
+    };
+    err.moved = total - still_in_source.len();
+    err.duplicated = still_in_source.len();
+    if still_in_source.is_empty() {
+        return err;
+    }
+
+    let originals: Vec<u32> = uids.iter().copied().filter(|uid| still_in_source.contains(uid)).collect();
+    if let Err(e) = session.store_deleted(&originals, FlagOperation::Remove).await {
+        warn!("Rollback: failed to clear \\Deleted in {}: {}", from_folder, e);
+        err.rollback_error = Some(format!("originals in {} are still flagged \\Deleted: {}", from_folder, e));
+    }
+
+    let Some(new_uids) = new_uids else {
+        err.rollback_error = Some(format!("no COPYUID from the server, so the copies in {} are unknown", to_folder));
+        return err;
+    };
+    let copies: Vec<u32> = new_uids.iter()
+        .filter(|c| still_in_source.contains(&c.source_uid))
+        .map(|c| c.target_uid)
+        .collect();
+    let removed = async {
+        session.select(to_folder).await?;
+        session.store_deleted(&copies, FlagOperation::Add).await?;
+        session.uid_expunge(&copies).await?;
+        session.select(from_folder).await
+    }.await;
+    match removed {
+        Ok(()) => {
+            warn!("Rollback: removed {} copies from {}", copies.len(), to_folder);
+            // Originals whose copy COPYUID didn't report are still in both
+            err.duplicated = still_in_source.len() - copies.len();
+            err.in_source = copies.len();
+        }
+        Err(e) => err.rollback_error = Some(format!("could not remove copies from {}: {}", to_folder, e)),
     }
+    err
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Folders of UID -> \Deleted flag, with one phase set up to fail.
+    #[derive(Default)]
+    struct MockMoveSession {
+        folders: Mutex<HashMap<String, HashMap<u32, bool>>>,
+        selected: Mutex<String>,
+        next_uid: Mutex<u32>,
+        fail_on: Option<MovePhase>,
+    }
+
+    impl MockMoveSession {
+        fn new(source: &[u32], fail_on: Option<MovePhase>) -> Self {
+            let mut folders = HashMap::new();
+            folders.insert("INBOX".to_string(), source.iter().map(|&uid| (uid, false)).collect());
+            folders.insert("Archive".to_string(), HashMap::new());
+            Self { folders: Mutex::new(folders), next_uid: Mutex::new(100), fail_on, ..Default::default() }
+        }
+
+        fn uids(&self, folder: &str) -> Vec<u32> {
+            let mut uids: Vec<u32> = self.folders.lock().unwrap()[folder].keys().copied().collect();
+            uids.sort_unstable();
+            uids
+        }
+
+        fn deleted(&self, folder: &str) -> usize {
+            self.folders.lock().unwrap()[folder].values().filter(|&&deleted| deleted).count()
+        }
+
+        fn fail(&self, phase: MovePhase) -> Result<(), ImapError> {
+            match self.fail_on {
+                Some(failing) if failing == phase => Err(ImapError::Connection(format!("injected {} failure", phase))),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl MoveSession for MockMoveSession {
+        async fn select(&self, folder: &str) -> Result<(), ImapError> {
+            *self.selected.lock().unwrap() = folder.to_string();
+            Ok(())
+        }
+
+        async fn existing(&self, uids: &[u32]) -> Result<Vec<u32>, ImapError> {
+            let folders = self.folders.lock().unwrap();
+            let folder = &folders[&*self.selected.lock().unwrap()];
+            Ok(uids.iter().copied().filter(|uid| folder.contains_key(uid)).collect())
+        }
+
+        async fn copy(&self, uids: &[u32], to_folder: &str) -> Result<Option<(u32, Vec<CopiedUid>)>, ImapError> {
+            self.fail(MovePhase::Copy)?;
+            let mut folders = self.folders.lock().unwrap();
+            let mut next_uid = self.next_uid.lock().unwrap();
+            let mut copied = Vec::new();
+            for &uid in uids {
+                *next_uid += 1;
+                folders.get_mut(to_folder).unwrap().insert(*next_uid, false);
+                copied.push(CopiedUid { source_uid: uid, target_uid: *next_uid });
+            }
+            Ok(Some((1, copied)))
+        }
+
+        async fn store_deleted(&self, uids: &[u32], operation: FlagOperation) -> Result<(), ImapError> {
+            self.fail(MovePhase::MarkDeleted)?;
+            let mut folders = self.folders.lock().unwrap();
+            let folder = folders.get_mut(&*self.selected.lock().unwrap()).unwrap();
+            for uid in uids {
+                if let Some(deleted) = folder.get_mut(uid) {
+                    *deleted = operation == FlagOperation::Add;
+                }
+            }
+            Ok(())
+        }
+
+        async fn expunge(&self) -> Result<(), ImapError> {
+            unreachable!("UID EXPUNGE is used when COPYUID was returned")
+        }
+
+        async fn uid_expunge(&self, uids: &[u32]) -> Result<(), ImapError> {
+            let selected = self.selected.lock().unwrap().clone();
+            if selected == "INBOX" {
+                self.fail(MovePhase::Expunge)?;
+            }
+            let mut folders = self.folders.lock().unwrap();
+            folders.get_mut(&selected).unwrap().retain(|uid, deleted| !(*deleted && uids.contains(uid)));
+            Ok(())
+        }
+    }
 
     // Tests will be implemented when we have mock IMAP support
     #[tokio::test]
@@ -214,8 +562,33 @@ mod tests {
         // TODO: Implement with mock IMAP server
     }
 
+    #[tokio::test]
+    async fn test_batch_move() {
+        let session = MockMoveSession::new(&[1, 2, 3], None);
+        batch_move(&session, &[3, 1, 2, 1], "INBOX", "Archive").await.unwrap();
+        assert!(session.uids("INBOX").is_empty());
+        assert_eq!(session.uids("Archive"), vec![101, 102, 103]);
+    }
+
     #[tokio::test]
     async fn test_rollback() {
-        // TODO: Implement with mock IMAP server
+        // COPY succeeds and the originals are flagged, then EXPUNGE fails
+        let session = MockMoveSession::new(&[1, 2, 3, 4], Some(MovePhase::Expunge));
+        let err = batch_move(&session, &[1, 2, 3], "INBOX", "Archive").await.unwrap_err();
+
+        assert_eq!(err.phase, MovePhase::Expunge);
+        assert_eq!((err.total, err.moved, err.in_source, err.duplicated), (3, 0, 3, 0));
+        assert!(err.rollback_error.is_none());
+        assert_eq!(session.uids("INBOX"), vec![1, 2, 3, 4]);
+        assert_eq!(session.deleted("INBOX"), 0);
+        assert!(session.uids("Archive").is_empty());
+        assert!(err.to_string().contains("during expunge"));
+
+        // Nothing copied when the messages aren't all there
+        let session = MockMoveSession::new(&[1], None);
+        let err = batch_move(&session, &[1, 2], "INBOX", "Archive").await.unwrap_err();
+        assert_eq!(err.phase, MovePhase::Verify);
+        assert!(matches!(ImapError::from(err), ImapError::MissingData(_)));
+        assert!(session.uids("Archive").is_empty());
     }
-}
\ No newline at end of file
+}
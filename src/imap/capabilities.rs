@@ -0,0 +1,150 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Post-authentication server capabilities.
+//!
+//! Many servers advertise a reduced set before login and only reveal MOVE,
+//! CONDSTORE and friends afterwards, often without sending them unsolicited.
+//! Each session therefore asks for CAPABILITY once after LOGIN/AUTHENTICATE
+//! and keeps the answer; that set decides which optimizations are used.
+//! `IMAP_CAPABILITY_AFTER_LOGIN=false` skips the extra round-trip, leaving
+//! the set unknown so every optional command is tried with its fallback.
+//!
+//! The last set seen for each account is also kept here, so callers without
+//! a session (the `get_server_capabilities` tool) can answer from it.
+
+use std::collections::BTreeSet;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+/// Whether sessions issue CAPABILITY after login (`IMAP_CAPABILITY_AFTER_LOGIN`, default true).
+pub fn capability_after_login_enabled() -> bool {
+    std::env::var("IMAP_CAPABILITY_AFTER_LOGIN")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Capability names as advertised, upper-cased (`MOVE`, `AUTH=PLAIN`, ...).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ServerCapabilities {
+    names: BTreeSet<String>,
+}
+
+impl ServerCapabilities {
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            names: names.into_iter().map(|n| n.as_ref().to_ascii_uppercase()).collect(),
+        }
+    }
+
+    pub fn from_async_imap(caps: &async_imap::types::Capabilities) -> Self {
+        use async_imap::types::Capability;
+
+        Self::new(caps.iter().map(|cap| match cap {
+            Capability::Imap4rev1 => "IMAP4rev1".to_string(),
+            Capability::Auth(mechanism) => format!("AUTH={}", mechanism),
+            Capability::Atom(atom) => atom.to_string(),
+        }))
+    }
+
+    /// Case-insensitive check for one capability name.
+    pub fn has(&self, name: &str) -> bool {
+        self.names.contains(&name.to_ascii_uppercase())
+    }
+
+    pub fn supports_move(&self) -> bool {
+        self.has("MOVE")
+    }
+
+    pub fn supports_idle(&self) -> bool {
+        self.has("IDLE")
+    }
+
+    /// QRESYNC implies CONDSTORE (RFC 7162 section 3.2.3).
+    pub fn supports_condstore(&self) -> bool {
+        self.has("CONDSTORE") || self.has("QRESYNC")
+    }
+
+    pub fn supports_uidplus(&self) -> bool {
+        self.has("UIDPLUS")
+    }
+
+    pub fn supports_sort(&self) -> bool {
+        self.has("SORT")
+    }
+
+    pub fn supports_quota(&self) -> bool {
+        self.has("QUOTA")
+    }
+
+    /// Algorithms offered for THREAD, from `THREAD=<name>` (`REFERENCES`, ...).
+    pub fn thread_algorithms(&self) -> Vec<&str> {
+        self.names().filter_map(|name| name.strip_prefix("THREAD=")).collect()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+lazy_static! {
+    static ref ACCOUNT_CAPABILITIES: DashMap<String, ServerCapabilities> = DashMap::new();
+}
+
+/// The capabilities last seen on one of the account's sessions.
+pub fn cached(account: &str) -> Option<ServerCapabilities> {
+    ACCOUNT_CAPABILITIES.get(account).map(|entry| entry.clone())
+}
+
+/// Keep `capabilities` as the account's current set.
+pub fn remember(account: &str, capabilities: &ServerCapabilities) {
+    ACCOUNT_CAPABILITIES.insert(account.to_string(), capabilities.clone());
+}
+
+/// Forget an account's capabilities, e.g. after its server settings changed.
+pub fn invalidate(account: &str) {
+    ACCOUNT_CAPABILITIES.remove(account);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_lookup_is_case_insensitive() {
+        let caps = ServerCapabilities::new(["IMAP4rev1", "move", "Idle", "AUTH=PLAIN", "QRESYNC"]);
+        assert!(caps.supports_move());
+        assert!(caps.supports_idle());
+        assert!(caps.supports_condstore());
+        assert!(!caps.supports_uidplus());
+        assert!(caps.has("auth=plain"));
+    }
+
+    #[test]
+    fn test_pre_auth_set_lacks_extensions() {
+        let caps = ServerCapabilities::new(["IMAP4rev1", "STARTTLS", "AUTH=PLAIN"]);
+        assert!(!caps.supports_move());
+        assert!(!caps.supports_condstore());
+    }
+
+    #[test]
+    fn test_thread_algorithms() {
+        let caps = ServerCapabilities::new(["IMAP4rev1", "SORT", "THREAD=REFERENCES", "thread=orderedsubject"]);
+        assert_eq!(caps.thread_algorithms(), vec!["ORDEREDSUBJECT", "REFERENCES"]);
+        assert!(caps.supports_sort());
+        assert!(ServerCapabilities::default().thread_algorithms().is_empty());
+    }
+}
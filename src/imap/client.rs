@@ -6,23 +6,23 @@
 // Standard library imports
 use std::{
     fmt::Debug,
+    future::Future,
     // borrow::Cow, // Unused
     net::ToSocketAddrs,
+    sync::atomic::{AtomicBool, Ordering},
     sync::Arc,
     time::Duration,
 };
 
 // Async runtime and utilities
-use tokio_native_tls::TlsConnector as TokioTlsConnector;
 // use async_imap::error::Error as AsyncImapNativeError; // Unused
 // use async_trait::async_trait; // Unused
 // use futures_util::stream::StreamExt; // Not directly used here, but used by async_imap::Client::connect
 // use chrono::{DateTime, Utc}; // Unused
-use log::{info}; // Keep used logs
+use tracing::{info, warn};
 
 // TLS and crypto
 // use rustls::{ClientConfig, RootCertStore}; // Unused
-use native_tls::TlsConnector;
 use tokio::net::TcpStream as TokioTcpStream;
 use tokio_util::compat::TokioAsyncReadCompatExt;
 
@@ -30,9 +30,12 @@ use tokio_util::compat::TokioAsyncReadCompatExt;
 
 // Local types
 use crate::imap::{
+    compress::CompressibleStream,
+    connection_limits::{self, ConnectionLimiter, ConnectionSlot},
     error::ImapError,
     session::{AsyncImapOps, AsyncImapSessionWrapper, TlsImapSession},
-    types::MailboxInfo,
+    transport::ImapTransport,
+    types::{body_section, MailboxInfo, PartialBody},
 };
 
 // Async IMAP types (async-imap crate)
@@ -50,16 +53,114 @@ use async_imap::{
     Client as AsyncImapInternalClient, // Renamed to avoid clash
 };
 
+/// How long one command may wait for the server before the connection is
+/// given up on: `IMAP_COMMAND_TIMEOUT_SECONDS` (default 60), or
+/// `IMAP_FETCH_TIMEOUT_SECONDS` (default 300) for commands that move message
+/// bodies (body FETCH, APPEND).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandTimeouts {
+    pub command: Duration,
+    pub fetch: Duration,
+}
+
+impl CommandTimeouts {
+    pub fn from_env() -> Self {
+        let seconds = |var: &str, default: u64| {
+            Duration::from_secs(std::env::var(var).ok().and_then(|v| v.parse().ok()).unwrap_or(default))
+        };
+        Self {
+            command: seconds("IMAP_COMMAND_TIMEOUT_SECONDS", 60),
+            fetch: seconds("IMAP_FETCH_TIMEOUT_SECONDS", 300),
+        }
+    }
+
+    /// The same limit for every command.
+    pub fn uniform(timeout: Duration) -> Self {
+        Self { command: timeout, fetch: timeout }
+    }
+}
+
 /// High-level IMAP client providing a simplified interface for common operations.
 #[derive(Debug, Clone)]
 pub struct ImapClient<T: AsyncImapOps + Send + Sync + Debug + 'static> {
     session: Arc<T>,
+    /// Per-account connection slot, released when the last clone is dropped
+    slot: Option<Arc<ConnectionSlot>>,
+    timeouts: CommandTimeouts,
+    /// Set once a command has timed out or its response broke off; shared
+    /// by every handle on the session
+    invalid: Arc<AtomicBool>,
 }
 
 impl<T: AsyncImapOps + Send + Sync + Debug + 'static> ImapClient<T> {
     /// Creates a new `ImapClient` wrapping an existing session.
     pub fn new(session: T) -> Self {
-        Self { session: Arc::new(session) }
+        Self {
+            session: Arc::new(session),
+            slot: None,
+            timeouts: CommandTimeouts::from_env(),
+            invalid: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// A handle on the same session whose commands wait up to `timeout`,
+    /// for operations known to run long, such as fetching a large batch.
+    pub fn with_command_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            session: Arc::clone(&self.session),
+            slot: self.slot.clone(),
+            timeouts: CommandTimeouts::uniform(timeout),
+            invalid: Arc::clone(&self.invalid),
+        }
+    }
+
+    /// Whether a command timed out or a FETCH broke off on this session. The
+    /// connection is then mid-response and unusable; every later command
+    /// fails at once and the pool discards it rather than handing it out again.
+    pub fn is_invalid(&self) -> bool {
+        self.invalid.load(Ordering::SeqCst)
+    }
+
+    /// Run one command, failing with `Timeout` and invalidating the session
+    /// if the server hasn't answered within `limit`.
+    async fn timed<R>(&self, command: &str, limit: Duration, op: impl Future<Output = Result<R, ImapError>>) -> Result<R, ImapError> {
+        if self.is_invalid() {
+            return Err(ImapError::Connection(format!(
+                "{} not sent: an earlier command failed mid-response and the connection was abandoned", command
+            )));
+        }
+        match tokio::time::timeout(limit, op).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => Err(self.check_bye(command, e).await),
+            Err(_) => {
+                self.invalid.store(true, Ordering::SeqCst);
+                warn!("IMAP {} got no answer within {:?}; abandoning the connection", command, limit);
+                Err(ImapError::Timeout(format!("{} got no answer within {:?}", command, limit)))
+            }
+        }
+    }
+
+    /// After a failed command, look for a BYE the server sent this session.
+    /// The connection is then gone; a BYE for too many connections counts
+    /// against the account that holds this session's slot.
+    async fn check_bye(&self, command: &str, error: ImapError) -> ImapError {
+        let Some(bye) = self.session.take_bye().await else {
+            return error;
+        };
+        self.invalid.store(true, Ordering::SeqCst);
+        warn!("IMAP {} failed: server closed the session: {}", command, bye);
+        if connection_limits::is_connection_limit_error(&bye) {
+            if let Some(slot) = &self.slot {
+                ConnectionLimiter::global().record_limit_hit(slot.account());
+            }
+        }
+        ImapError::Connection(format!("Server closed the session: {}", bye))
+    }
+
+    /// Tie a per-account connection slot to this client's lifetime.
+    pub fn with_connection_slot(mut self, slot: ConnectionSlot) -> Self {
+        self.slot = Some(Arc::new(slot));
+        self
     }
 
     /// Establishes a new IMAP connection with the given server, port, and credentials
@@ -86,6 +187,20 @@ impl<T: AsyncImapOps + Send + Sync + Debug + 'static> ImapClient<T> {
         Ok(ImapClient::new(session))
     }
 
+    /// Like `connect`, secured as `transport` says (implicit TLS, STARTTLS
+    /// or plaintext) rather than implicit TLS under the process-wide settings
+    pub async fn connect_with_transport(server: &str, port: u16, username: &str, password: &str, transport: &ImapTransport) -> Result<ImapClient<AsyncImapSessionWrapper>, ImapError> {
+        let session = AsyncImapSessionWrapper::connect_with_transport(
+            server,
+            port,
+            Arc::new(username.to_string()),
+            Arc::new(password.to_string()),
+            Duration::from_secs(35),
+            transport,
+        ).await?;
+        Ok(ImapClient::new(session))
+    }
+
     /// Establishes a new IMAP connection using XOAUTH2 authentication (for OAuth2 providers)
     pub async fn connect_with_xoauth2(
         server: &str,
@@ -96,6 +211,19 @@ impl<T: AsyncImapOps + Send + Sync + Debug + 'static> ImapClient<T> {
         Self::connect_with_xoauth2_and_timeout(server, port, username, access_token, Duration::from_secs(35)).await
     }
 
+    /// XOAUTH2 counterpart of `connect_with_transport`
+    pub async fn connect_with_xoauth2_transport(server: &str, port: u16, username: &str, access_token: &str, transport: &ImapTransport) -> Result<ImapClient<AsyncImapSessionWrapper>, ImapError> {
+        let session = AsyncImapSessionWrapper::connect_with_xoauth2_transport(
+            server,
+            port,
+            Arc::new(username.to_string()),
+            Arc::new(access_token.to_string()),
+            Duration::from_secs(35),
+            transport,
+        ).await?;
+        Ok(ImapClient::new(session))
+    }
+
     /// Establishes a new IMAP connection using XOAUTH2 with custom timeout
     pub async fn connect_with_xoauth2_and_timeout(
         server: &str,
@@ -119,82 +247,243 @@ impl<T: AsyncImapOps + Send + Sync + Debug + 'static> ImapClient<T> {
         &self.session
     }
 
-    /// Returns the Arc-wrapped session for sharing across threads/tasks
-    pub fn session_arc(&self) -> Arc<T> {
-        self.session.clone()
+    /// The limits this handle's commands run under.
+    pub fn timeouts(&self) -> CommandTimeouts {
+        self.timeouts
+    }
+
+    /// Run `op` on the shared session under `limit`, for code that drives
+    /// the session itself (atomic moves, legacy MCP tools). A timeout
+    /// invalidates the session like any other command's.
+    pub async fn with_session<R, Fut>(&self, command: &str, limit: Duration, op: impl FnOnce(Arc<T>) -> Fut) -> Result<R, ImapError>
+    where
+        Fut: Future<Output = Result<R, ImapError>>,
+    {
+        self.timed(command, limit, op(Arc::clone(&self.session))).await
     }
 
     // Add convenience methods here that delegate to self.session
     pub async fn list_folders(&self) -> Result<Vec<String>, ImapError> {
-        self.session.list_folders().await
+        self.timed("list_folders", self.timeouts.command, self.session.list_folders()).await
+    }
+
+    pub async fn list_folder_states(&self) -> Result<Vec<crate::imap::types::FolderState>, ImapError> {
+        self.timed("list_folder_states", self.timeouts.command, self.session.list_folder_states()).await
+    }
+
+    /// Every folder with its LIST attributes in wire form and the
+    /// special-use role they declare.
+    pub async fn list_folders_with_attributes(&self) -> Result<Vec<crate::imap::special_use::FolderAttributes>, ImapError> {
+        let folders = self.timed("list_folder_states", self.timeouts.command, self.session.list_folder_states()).await?;
+        Ok(folders.iter().map(Into::into).collect())
+    }
+
+    /// Which folder is Sent, Drafts, Trash, Junk and Archive, by RFC 6154
+    /// attribute and then by `profile`'s folder names.
+    pub async fn special_use_map(
+        &self,
+        profile: &crate::imap::provider_profile::ProviderProfile,
+    ) -> Result<std::collections::HashMap<crate::imap::special_use::SpecialUse, String>, ImapError> {
+        let folders = self.timed("list_folder_states", self.timeouts.command, self.session.list_folder_states()).await?;
+        Ok(crate::imap::special_use::special_use_map(profile, &folders))
     }
 
     pub async fn create_folder(&self, name: &str) -> Result<(), ImapError> {
-        self.session.create_folder(name).await
+        self.timed("create_folder", self.timeouts.command, self.session.create_folder(name)).await
     }
 
     pub async fn delete_folder(&self, name: &str) -> Result<(), ImapError> {
-        self.session.delete_folder(name).await
+        self.timed("delete_folder", self.timeouts.command, self.session.delete_folder(name)).await
     }
 
     pub async fn rename_folder(&self, old_name: &str, new_name: &str) -> Result<(), ImapError> {
-        self.session.rename_folder(old_name, new_name).await
+        self.timed("rename_folder", self.timeouts.command, self.session.rename_folder(old_name, new_name)).await
     }
 
     pub async fn select_folder(&self, name: &str) -> Result<MailboxInfo, ImapError> {
-        self.session.select_folder(name).await
+        self.timed("select_folder", self.timeouts.command, self.session.select_folder(name)).await
     }
 
     pub async fn search_emails(&self, criteria: &str) -> Result<Vec<u32>, ImapError> {
-        self.session.search_emails(criteria).await
+        self.timed("search_emails", self.timeouts.command, self.session.search_emails(criteria)).await
     }
 
     pub async fn fetch_emails(&self, uids: &[u32]) -> Result<Vec<crate::imap::types::Email>, ImapError> {
-        self.session.fetch_emails(uids).await
+        let batch = self.fetch_batch(uids).await?;
+        if !batch.errors.is_empty() {
+            warn!("Skipped {} of {} requested UIDs: {:?}", batch.errors.len(), uids.len(), batch.errors);
+        }
+        Ok(batch.emails)
+    }
+
+    /// Fetch `uids` under `IMAP_FETCH_TOLERATE_PARTIAL`: messages that fail
+    /// are reported in `errors` when it is on (the default) and fail the
+    /// whole call when it is off.
+    pub async fn fetch_batch(&self, uids: &[u32]) -> Result<crate::imap::types::FetchBatch, ImapError> {
+        let batch = self.fetch_emails_tolerant(uids).await?;
+        match batch.errors.first() {
+            Some(first) if !crate::imap::session::tolerate_partial_fetch() => {
+                Err(ImapError::Fetch(format!("UID {}: {}", first.uid, first.reason)))
+            }
+            _ => Ok(batch),
+        }
+    }
+
+    /// Fetch `uids`, reporting messages that fail rather than failing the
+    /// call. A response that breaks off partway abandons the connection.
+    pub async fn fetch_emails_tolerant(&self, uids: &[u32]) -> Result<crate::imap::types::FetchBatch, ImapError> {
+        let batch = self.timed("fetch_emails_tolerant", self.timeouts.fetch, self.session.fetch_emails_tolerant(uids)).await?;
+        if batch.aborted {
+            self.invalid.store(true, Ordering::SeqCst);
+            warn!("IMAP FETCH broke off mid-response; abandoning the connection");
+        }
+        Ok(batch)
     }
 
     pub async fn fetch_flags(&self, uids: &[u32]) -> Result<Vec<(u32, Vec<String>)>, ImapError> {
-        self.session.fetch_flags(uids).await
+        self.timed("fetch_flags", self.timeouts.command, self.session.fetch_flags(uids)).await
+    }
+
+    pub async fn fetch_envelopes(&self, uids: &[u32]) -> Result<Vec<crate::imap::types::Email>, ImapError> {
+        self.timed("fetch_envelopes", self.timeouts.command, self.session.fetch_envelopes(uids)).await
     }
 
     pub async fn move_email(&self, uid: u32, from_folder: &str, to_folder: &str) -> Result<(), ImapError> {
-        self.session.move_email(uid, from_folder, to_folder).await
+        self.timed("move_email", self.timeouts.command, self.session.move_email(uid, from_folder, to_folder)).await
     }
 
     pub async fn store_flags(&self, uids: &[u32], operation: crate::imap::types::FlagOperation, flags: &[String]) -> Result<(), ImapError> {
-        self.session.store_flags(uids, operation, flags).await
+        self.timed("store_flags", self.timeouts.command, self.session.store_flags(uids, operation, flags)).await
     }
 
     pub async fn append(&self, folder: &str, content: &[u8], flags: &[String]) -> Result<(), ImapError> {
-        self.session.append(folder, content, flags).await
+        self.timed("append", self.timeouts.fetch, self.session.append(folder, content, flags)).await
+    }
+
+    pub async fn append_with_flags(
+        &self,
+        folder: &str,
+        content: &[u8],
+        flags: &[String],
+        internal_date: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Option<u32>, ImapError> {
+        self.timed(
+            "append_with_flags",
+            self.timeouts.fetch,
+            self.session.append_with_flags(folder, content, flags, internal_date),
+        ).await
     }
 
     pub async fn fetch_raw_message(&self, uid: u32) -> Result<Vec<u8>, ImapError> {
-        self.session.fetch_raw_message(uid).await
+        self.timed("fetch_raw_message", self.timeouts.fetch, self.session.fetch_raw_message(uid)).await
     }
 
     pub async fn expunge(&self) -> Result<(), ImapError> {
-        self.session.expunge().await
+        self.timed("expunge", self.timeouts.command, self.session.expunge()).await
     }
 
     pub async fn mark_as_deleted(&self, uids: &[u32]) -> Result<(), ImapError> {
-        self.session.mark_as_deleted(uids).await
+        self.timed("mark_as_deleted", self.timeouts.command, self.session.mark_as_deleted(uids)).await
     }
 
     pub async fn delete_messages(&self, uids: &[u32]) -> Result<(), ImapError> {
-        self.session.delete_messages(uids).await
+        self.timed("delete_messages", self.timeouts.command, self.session.delete_messages(uids)).await
     }
 
     pub async fn undelete_messages(&self, uids: &[u32]) -> Result<(), ImapError> {
-        self.session.undelete_messages(uids).await
+        self.timed("undelete_messages", self.timeouts.command, self.session.undelete_messages(uids)).await
     }
 
     pub async fn noop(&self) -> Result<(), ImapError> {
-        self.session.noop().await
+        self.timed("noop", self.timeouts.command, self.session.noop()).await
+    }
+
+    pub async fn folder_statuses(&self, folders: &[String]) -> Result<Vec<crate::imap::pipeline::FolderStatus>, ImapError> {
+        self.timed("folder_statuses", self.timeouts.command, self.session.folder_statuses(folders)).await
+    }
+
+    pub async fn server_capabilities(&self) -> Result<crate::imap::capabilities::ServerCapabilities, ImapError> {
+        self.timed("server_capabilities", self.timeouts.command, self.session.server_capabilities()).await
+    }
+
+    /// The wait itself doesn't count against the command timeout, only
+    /// entering and leaving IDLE does.
+    pub async fn idle(&self, folder: &str, timeout: Duration) -> Result<Vec<crate::imap::idle::IdleEvent>, ImapError> {
+        self.timed("idle", timeout + self.timeouts.command, self.session.idle(folder, timeout)).await
+    }
+
+    pub async fn thread(
+        &self,
+        algorithm: crate::imap::threading::ThreadAlgorithm,
+        criteria: &crate::imap::types::SearchCriteria,
+    ) -> Result<Vec<crate::imap::threading::ThreadNode>, ImapError> {
+        self.timed("thread", self.timeouts.command, self.session.thread(algorithm, criteria)).await
+    }
+
+    pub async fn sort(
+        &self,
+        keys: &[crate::imap::sort::SortKey],
+        criteria: &crate::imap::types::SearchCriteria,
+    ) -> Result<Vec<u32>, ImapError> {
+        self.timed("sort", self.timeouts.command, self.session.sort(keys, criteria)).await
+    }
+
+    pub async fn get_quota(&self, mailbox: &str) -> Result<crate::imap::types::QuotaInfo, ImapError> {
+        self.timed("get_quota", self.timeouts.command, self.session.get_quota(mailbox)).await
+    }
+
+    pub async fn namespace(&self) -> Result<crate::imap::namespace::Namespaces, ImapError> {
+        self.timed("namespace", self.timeouts.command, self.session.namespace()).await
     }
 
     pub async fn logout(&self) -> Result<(), ImapError> {
-        self.session.logout().await
+        self.timed("logout", self.timeouts.command, self.session.logout()).await
+    }
+}
+
+impl ImapClient<AsyncImapSessionWrapper> {
+    /// Capabilities cached after login, without a round-trip; None when
+    /// they are unknown (see `server_capabilities`).
+    pub fn capabilities(&self) -> Option<std::sync::Arc<crate::imap::capabilities::ServerCapabilities>> {
+        self.session.capabilities()
+    }
+
+    /// Ask the server again, replacing the cached set.
+    pub async fn refresh_capabilities(&self) -> Result<crate::imap::capabilities::ServerCapabilities, ImapError> {
+        self.timed("refresh_capabilities", self.timeouts.command, self.session.refresh_capabilities()).await
+    }
+
+    /// Switch the connection to COMPRESS=DEFLATE if the server supports it.
+    /// Returns false, with the connection unchanged, otherwise.
+    pub async fn enable_compression(&self) -> bool {
+        let enable = async { Ok(self.session.enable_compression().await) };
+        self.timed("enable_compression", self.timeouts.command, enable).await.unwrap_or(false)
+    }
+
+    /// UID EXPUNGE (UIDPLUS): remove just `uids` from the selected folder,
+    /// leaving other messages flagged \Deleted alone.
+    pub async fn uid_expunge(&self, uids: &[u32]) -> Result<(), ImapError> {
+        self.timed("uid_expunge", self.timeouts.command, self.session.uid_expunge(uids)).await
+    }
+
+    /// Flags changed in the selected folder since `modseq`. Only valid on
+    /// servers with CONDSTORE; see `MailboxInfo::highest_modseq`.
+    pub async fn get_changed_since(&self, modseq: u64) -> Result<crate::imap::types::ChangedSince, ImapError> {
+        self.timed("fetch_changed_since", self.timeouts.command, self.session.fetch_changed_since(modseq)).await
+    }
+
+    /// Up to `length` bytes of message `uid` in `folder` starting at
+    /// `offset`, without downloading the rest of it.
+    pub async fn fetch_body_partial(&self, folder: &str, uid: u32, offset: u32, length: u32) -> Result<PartialBody, ImapError> {
+        self.fetch_section_partial(folder, uid, "", offset, length).await
+    }
+
+    /// Like `fetch_body_partial`, for one MIME part (`1.2`) or section
+    /// (`HEADER`, `2.TEXT`); an empty `section` is the whole message.
+    pub async fn fetch_section_partial(&self, folder: &str, uid: u32, section: &str, offset: u32, length: u32) -> Result<PartialBody, ImapError> {
+        let section = body_section(section).map_err(ImapError::Validation)?;
+        self.timed("select_folder", self.timeouts.command, self.session.select_folder(folder)).await?;
+        self.timed("fetch_partial", self.timeouts.fetch, self.session.fetch_partial(uid, section.as_deref(), offset, length)).await
     }
 }
 
@@ -205,6 +494,7 @@ pub async fn connect(
     username: &str,
     password: &str,
     timeout: Duration,
+    transport: &ImapTransport,
 ) -> Result<ImapClient<AsyncImapSessionWrapper>, ImapError> {
     let addr = (server, port)
         .to_socket_addrs()?
@@ -243,23 +533,19 @@ pub async fn connect(
     let tcp_stream = TokioTcpStream::from_std(std_stream)
         .map_err(|e| ImapError::Connection(format!("Failed to convert back to tokio stream: {}", e)))?; 
 
-    // Setup TLS connector
-    let tls_builder = TlsConnector::builder();
-    let native_tls_connector = tls_builder.build()
-        .map_err(|e| ImapError::Tls(format!("Failed to build TLS connector: {}", e)))?;
-    let tls_connector = TokioTlsConnector::from(native_tls_connector);
-
-    // Perform TLS handshake with timeout (tokio_native_tls works with tokio's AsyncRead/Write)
-    let tls_stream = tokio::time::timeout(timeout, tls_connector.connect(server, tcp_stream))
+    // Secure the connection with timeout (implicit TLS, STARTTLS or plaintext;
+    // minimum version, CA bundle and pin from `transport.tls`)
+    let (imap_stream, _) = tokio::time::timeout(timeout, crate::imap::transport::open(server, tcp_stream, transport))
         .await
-        .map_err(|_| ImapError::Timeout("Operation timed out".to_string()))?
-        .map_err(|e| ImapError::Tls(e.to_string()))?;
+        .map_err(|_| ImapError::Timeout("Operation timed out".to_string()))??;
 
-    info!("TLS connection established");
+    info!("Connection secured ({})", transport.mode);
 
-    // Build IMAP client with the TLS stream wrapped in compat for async-imap
+    // Build IMAP client with the stream wrapped in compat for async-imap
     // The client itself is the unauthenticated session - no need to call connect
-    let unauthenticated_session = AsyncImapInternalClient::new(tls_stream.compat());
+    let stream = CompressibleStream::new(imap_stream.compat());
+    let compress = stream.switch();
+    let unauthenticated_session = AsyncImapInternalClient::new(stream);
     
     info!("IMAP session established");
 
@@ -272,7 +558,9 @@ pub async fn connect(
     info!("IMAP login successful for user: {}", username);
 
     // Wrap the authenticated session in our mutex wrapper with append timeout
-    let wrapped_session = AsyncImapSessionWrapper::with_append_timeout(authenticated_session, append_timeout);
+    let wrapped_session = AsyncImapSessionWrapper::with_append_timeout(authenticated_session, append_timeout)
+        .with_compress_switch(compress);
+    wrapped_session.load_post_auth_capabilities().await;
 
     // Create our client using the wrapped session
     Ok(ImapClient::new(wrapped_session))
@@ -288,6 +576,7 @@ pub async fn connect_with_oauth(
     email: &str,
     access_token: &str,
     timeout: Duration,
+    transport: &ImapTransport,
 ) -> Result<ImapClient<AsyncImapSessionWrapper>, ImapError> {
     let addr = (server, port)
         .to_socket_addrs()?
@@ -318,19 +607,15 @@ pub async fn connect_with_oauth(
     let tcp_stream = TokioTcpStream::from_std(std_stream)
         .map_err(|e| ImapError::Connection(format!("Failed to convert back: {}", e)))?;
 
-    let tls_builder = TlsConnector::builder();
-    let native_tls_connector = tls_builder.build()
-        .map_err(|e| ImapError::Tls(format!("Failed to build TLS connector: {}", e)))?;
-    let tls_connector = TokioTlsConnector::from(native_tls_connector);
-
-    let tls_stream = tokio::time::timeout(timeout, tls_connector.connect(server, tcp_stream))
+    let (imap_stream, _) = tokio::time::timeout(timeout, crate::imap::transport::open(server, tcp_stream, transport))
         .await
-        .map_err(|_| ImapError::Timeout("TLS handshake timed out".to_string()))?
-        .map_err(|e| ImapError::Tls(e.to_string()))?;
+        .map_err(|_| ImapError::Timeout("TLS handshake timed out".to_string()))??;
 
-    info!("TLS connection established (XOAUTH2)");
+    info!("Connection secured ({}, XOAUTH2)", transport.mode);
 
-    let unauthenticated_client = AsyncImapInternalClient::new(tls_stream.compat());
+    let stream = CompressibleStream::new(imap_stream.compat());
+    let compress = stream.switch();
+    let unauthenticated_client = AsyncImapInternalClient::new(stream);
 
     let authenticator = crate::imap::xoauth2::XOAuth2Authenticator::new(email, access_token);
 
@@ -346,7 +631,8 @@ pub async fn connect_with_oauth(
 
     let wrapped_session = AsyncImapSessionWrapper::with_append_timeout(
         authenticated_session, append_timeout,
-    );
+    ).with_compress_switch(compress);
+    wrapped_session.load_post_auth_capabilities().await;
     Ok(ImapClient::new(wrapped_session))
 }
 
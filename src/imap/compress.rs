@@ -0,0 +1,257 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! COMPRESS=DEFLATE (RFC 4978) for IMAP sessions.
+//!
+//! Every session's TLS stream is wrapped in a [`CompressibleStream`] that
+//! passes bytes through until its [`CompressSwitch`] is turned on. The
+//! session turns it on right after the server answers OK to
+//! `COMPRESS DEFLATE`; from then on both directions are raw deflate, each
+//! write ending in a sync flush so commands (and IDLE's DONE) reach the
+//! server at once. A refused command never touches the switch, so the
+//! session carries on uncompressed.
+//!
+//! Off unless `IMAP_COMPRESS_DEFLATE=true`, since some servers mishandle it.
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use futures::io::{AsyncRead, AsyncWrite};
+
+/// Compressed bytes read from the server per poll.
+const READ_BUFFER: usize = 16 * 1024;
+
+/// Whether new sessions should negotiate compression (`IMAP_COMPRESS_DEFLATE`, default false).
+pub fn deflate_requested() -> bool {
+    std::env::var("IMAP_COMPRESS_DEFLATE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Turns compression on for the stream it came from. Shared with the stream
+/// because async-imap owns the stream once the session exists.
+#[derive(Debug, Clone, Default)]
+pub struct CompressSwitch(Arc<AtomicBool>);
+
+impl CompressSwitch {
+    pub fn enable(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Codec state once compression is on.
+struct Deflate {
+    compress: Compress,
+    decompress: Decompress,
+    /// Compressed output not yet written to the inner stream
+    pending: Vec<u8>,
+    pending_pos: usize,
+    /// Compressed input not yet inflated, `input[input_start..input_end]`
+    input: Box<[u8]>,
+    input_start: usize,
+    input_end: usize,
+}
+
+impl Deflate {
+    fn new() -> Self {
+        Self {
+            // RFC 4978: raw deflate, no zlib header
+            compress: Compress::new(Compression::default(), false),
+            decompress: Decompress::new(false),
+            pending: Vec::new(),
+            pending_pos: 0,
+            input: vec![0; READ_BUFFER].into_boxed_slice(),
+            input_start: 0,
+            input_end: 0,
+        }
+    }
+
+    fn poll_read<S: AsyncRead + Unpin>(&mut self, inner: &mut S, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            // Inflate first, even without new input: output may be held back
+            // from a previous call whose buffer was full
+            let (before_in, before_out) = (self.decompress.total_in(), self.decompress.total_out());
+            self.decompress
+                .decompress(&self.input[self.input_start..self.input_end], buf, FlushDecompress::None)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("IMAP deflate stream: {}", e)))?;
+            let consumed = (self.decompress.total_in() - before_in) as usize;
+            let produced = (self.decompress.total_out() - before_out) as usize;
+            self.input_start += consumed;
+            if produced > 0 {
+                return Poll::Ready(Ok(produced));
+            }
+            if consumed > 0 {
+                continue;
+            }
+
+            // No progress: more compressed input is needed
+            if self.input_start > 0 {
+                self.input.copy_within(self.input_start..self.input_end, 0);
+                self.input_end -= self.input_start;
+                self.input_start = 0;
+            }
+            if self.input_end == self.input.len() {
+                return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, "IMAP deflate stream made no progress")));
+            }
+            let n = ready!(Pin::new(&mut *inner).poll_read(cx, &mut self.input[self.input_end..]))?;
+            if n == 0 {
+                return Poll::Ready(Ok(0));
+            }
+            self.input_end += n;
+        }
+    }
+
+    /// Write out compressed bytes left over from earlier writes.
+    fn poll_drain<S: AsyncWrite + Unpin>(&mut self, inner: &mut S, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.pending_pos < self.pending.len() {
+            let n = ready!(Pin::new(&mut *inner).poll_write(cx, &self.pending[self.pending_pos..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending_pos += n;
+        }
+        self.pending.clear();
+        self.pending_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_write<S: AsyncWrite + Unpin>(&mut self, inner: &mut S, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        ready!(self.poll_drain(inner, cx))?;
+
+        let start = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.pending.reserve(data.len() - consumed + 64);
+            self.compress
+                .compress_vec(&data[consumed..], &mut self.pending, FlushCompress::Sync)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("IMAP deflate: {}", e)))?;
+            // Done once all input is taken and the flush fit in the spare room
+            let consumed = (self.compress.total_in() - start) as usize;
+            if consumed == data.len() && self.pending.len() < self.pending.capacity() {
+                break;
+            }
+        }
+
+        // The data is accepted; whatever doesn't go out now goes on the next
+        // write or flush
+        if let Poll::Ready(Err(e)) = self.poll_drain(inner, cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(data.len()))
+    }
+}
+
+/// An IMAP transport that can switch to deflate mid-session.
+pub struct CompressibleStream<S> {
+    inner: S,
+    switch: CompressSwitch,
+    deflate: Option<Box<Deflate>>,
+}
+
+impl<S> CompressibleStream<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, switch: CompressSwitch::default(), deflate: None }
+    }
+
+    pub fn switch(&self) -> CompressSwitch {
+        self.switch.clone()
+    }
+
+    /// Split into the inner stream and the codec, creating the codec the
+    /// first time the switch is found on.
+    fn parts(&mut self) -> (&mut S, Option<&mut Deflate>) {
+        if self.deflate.is_none() && self.switch.is_enabled() {
+            self.deflate = Some(Box::new(Deflate::new()));
+        }
+        (&mut self.inner, self.deflate.as_deref_mut())
+    }
+}
+
+impl<S> fmt::Debug for CompressibleStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressibleStream")
+            .field("compressed", &self.deflate.is_some())
+            .finish()
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CompressibleStream<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut().parts() {
+            (inner, Some(deflate)) => deflate.poll_read(inner, cx, buf),
+            (inner, None) => Pin::new(inner).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CompressibleStream<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut().parts() {
+            (inner, Some(deflate)) => deflate.poll_write(inner, cx, buf),
+            (inner, None) => Pin::new(inner).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let (inner, deflate) = self.get_mut().parts();
+        if let Some(deflate) = deflate {
+            ready!(deflate.poll_drain(inner, cx))?;
+        }
+        Pin::new(inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let (inner, deflate) = self.get_mut().parts();
+        if let Some(deflate) = deflate {
+            ready!(deflate.poll_drain(inner, cx))?;
+        }
+        Pin::new(inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::{AsyncReadExt, AsyncWriteExt, Cursor};
+
+    #[tokio::test]
+    async fn test_switch_mid_stream_round_trip() {
+        let mut writer = CompressibleStream::new(Cursor::new(Vec::new()));
+        let switch = writer.switch();
+        writer.write_all(b"A1 COMPRESS DEFLATE\r\n").await.unwrap();
+        switch.enable();
+        let command = b"A2 UID FETCH 1:* (FLAGS)\r\n".repeat(50);
+        writer.write_all(&command).await.unwrap();
+        writer.write_all(b"DONE\r\n").await.unwrap();
+        writer.flush().await.unwrap();
+
+        let wire = writer.inner.into_inner();
+        assert!(wire.starts_with(b"A1 COMPRESS DEFLATE\r\n"), "bytes before the switch pass through");
+        assert!(wire.len() < 21 + command.len(), "repetitive commands should shrink");
+
+        // The reader sees the plain prefix, then switches at the same point
+        let mut reader = CompressibleStream::new(Cursor::new(wire));
+        let mut prefix = [0u8; 21];
+        reader.read_exact(&mut prefix).await.unwrap();
+        assert_eq!(&prefix, b"A1 COMPRESS DEFLATE\r\n");
+        reader.switch().enable();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, [command, b"DONE\r\n".to_vec()].concat());
+    }
+}
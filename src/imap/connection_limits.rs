@@ -0,0 +1,318 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Per-account cap on simultaneous IMAP connections.
+//!
+//! Providers limit concurrent sessions per user (Gmail at 15, many hosted
+//! Dovecot setups at 10 or fewer) and drop or refuse the excess. Every
+//! session opened for an account holds one of that account's slots until the
+//! last clone of its `ImapClient` is dropped.
+//!
+//! - `IMAP_MAX_CONNECTIONS_PER_ACCOUNT` (default 5) applies to every account.
+//! - `IMAP_ACCOUNT_MAX_CONNECTIONS` overrides it per account, e.g.
+//!   `me@gmail.com=10,me@fastmail.com=3`.
+//! - `IMAP_CONNECTION_SLOT_TIMEOUT_SECONDS` (default 30) bounds the wait for a slot.
+//!
+//! When the server refuses a login because of its own limit, or drops an
+//! open session with a BYE saying so, the account backs off (2s, 4s, ... up
+//! to a minute) and gives up one slot, down to a single connection, so later
+//! attempts stay under what the server allows.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use log::{debug, info, warn};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::imap::client::ImapClient;
+use crate::imap::error::ImapError;
+use crate::imap::session::AsyncImapOps;
+
+const DEFAULT_MAX_CONNECTIONS_PER_ACCOUNT: usize = 5;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Connections allowed per account unless overridden (`IMAP_MAX_CONNECTIONS_PER_ACCOUNT`).
+pub fn default_limit() -> usize {
+    std::env::var("IMAP_MAX_CONNECTIONS_PER_ACCOUNT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_CONNECTIONS_PER_ACCOUNT)
+}
+
+/// Parse `IMAP_ACCOUNT_MAX_CONNECTIONS` (`user@host=N,...`). Keys are
+/// lowercased; malformed entries are skipped with a warning.
+pub fn parse_overrides(raw: &str) -> HashMap<String, usize> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.rsplit_once('=')
+                .and_then(|(account, n)| Some((account.trim().to_lowercase(), n.trim().parse::<usize>().ok()?)))
+                .filter(|(account, n)| !account.is_empty() && *n > 0);
+            if parsed.is_none() {
+                warn!("Ignoring malformed IMAP_ACCOUNT_MAX_CONNECTIONS entry '{}'", entry);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Configured connection limit for `account` (the login user or email address).
+pub fn limit_for(account: &str) -> usize {
+    std::env::var("IMAP_ACCOUNT_MAX_CONNECTIONS")
+        .ok()
+        .and_then(|raw| parse_overrides(&raw).get(&account.to_lowercase()).copied())
+        .unwrap_or_else(default_limit)
+}
+
+fn slot_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("IMAP_CONNECTION_SLOT_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30),
+    )
+}
+
+/// Whether a server response means the account has too many sessions open.
+pub fn is_connection_limit_error(message: &str) -> bool {
+    let lower = message.to_ascii_lowercase();
+    [
+        "too many simultaneous",
+        "too many connections",
+        "too many concurrent",
+        "maximum number of connections",
+        "max_userip_connections",
+        "connection limit",
+        "[limit]",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+}
+
+/// One held connection slot; released when dropped.
+#[derive(Debug)]
+pub struct ConnectionSlot {
+    account: String,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl ConnectionSlot {
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+}
+
+#[derive(Debug)]
+struct AccountSlots {
+    semaphore: Arc<Semaphore>,
+    limit: Mutex<usize>,
+    backoff: Mutex<Backoff>,
+}
+
+#[derive(Debug, Default)]
+struct Backoff {
+    consecutive_hits: u32,
+    until: Option<Instant>,
+}
+
+/// Tracks connection slots and server-limit backoff for each account.
+#[derive(Debug, Default)]
+pub struct ConnectionLimiter {
+    accounts: DashMap<String, Arc<AccountSlots>>,
+}
+
+impl ConnectionLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide limiter shared by every session factory.
+    pub fn global() -> &'static ConnectionLimiter {
+        static LIMITER: OnceLock<ConnectionLimiter> = OnceLock::new();
+        LIMITER.get_or_init(ConnectionLimiter::new)
+    }
+
+    fn slots(&self, account: &str, limit: usize) -> Arc<AccountSlots> {
+        self.accounts
+            .entry(account.to_lowercase())
+            .or_insert_with(|| Arc::new(AccountSlots {
+                semaphore: Arc::new(Semaphore::new(limit)),
+                limit: Mutex::new(limit),
+                backoff: Mutex::new(Backoff::default()),
+            }))
+            .clone()
+    }
+
+    /// Wait out any server-limit backoff, then take one of the account's
+    /// slots, waiting up to `IMAP_CONNECTION_SLOT_TIMEOUT_SECONDS`.
+    pub async fn acquire(&self, account: &str) -> Result<ConnectionSlot, ImapError> {
+        self.acquire_with(account, limit_for(account), slot_timeout()).await
+    }
+
+    /// Open a session for `account` with `connect` while holding one of its
+    /// slots. The slot stays with the returned client; a login refused for
+    /// too many connections counts against the account.
+    pub async fn connect<T, F>(&self, account: &str, connect: F) -> Result<ImapClient<T>, ImapError>
+    where
+        T: AsyncImapOps + Send + Sync + std::fmt::Debug + 'static,
+        F: Future<Output = Result<ImapClient<T>, ImapError>>,
+    {
+        let slot = self.acquire(account).await?;
+        match connect.await {
+            Ok(client) => {
+                self.record_success(account);
+                Ok(client.with_connection_slot(slot))
+            }
+            Err(e) => {
+                if is_connection_limit_error(&e.to_string()) {
+                    self.record_limit_hit(account);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn acquire_with(&self, account: &str, limit: usize, timeout: Duration) -> Result<ConnectionSlot, ImapError> {
+        let slots = self.slots(account, limit);
+
+        let wait = slots.backoff.lock().unwrap_or_else(|e| e.into_inner())
+            .until
+            .and_then(|until| until.checked_duration_since(Instant::now()));
+        if let Some(wait) = wait {
+            debug!("Backing off {:?} before connecting {} (server connection limit)", wait, account);
+            tokio::time::sleep(wait).await;
+        }
+
+        let permit = tokio::time::timeout(timeout, Arc::clone(&slots.semaphore).acquire_owned())
+            .await
+            .map_err(|_| {
+                let limit = *slots.limit.lock().unwrap_or_else(|e| e.into_inner());
+                ImapError::Connection(format!(
+                    "All {} connections for {} are in use (IMAP_ACCOUNT_MAX_CONNECTIONS); timed out after {:?}",
+                    limit, account, timeout
+                ))
+            })?
+            .map_err(|_| ImapError::Connection(format!("Connection slots for {} are closed", account)))?;
+
+        Ok(ConnectionSlot { account: account.to_string(), _permit: permit })
+    }
+
+    /// Record a login the server refused for exceeding its connection limit:
+    /// back off and lower this account's limit by one (never below one).
+    pub fn record_limit_hit(&self, account: &str) {
+        let Some(slots) = self.accounts.get(&account.to_lowercase()).map(|s| s.clone()) else {
+            return;
+        };
+
+        let delay = {
+            let mut backoff = slots.backoff.lock().unwrap_or_else(|e| e.into_inner());
+            backoff.consecutive_hits += 1;
+            let delay = Duration::from_secs(1u64 << backoff.consecutive_hits.min(6)).min(MAX_BACKOFF);
+            backoff.until = Some(Instant::now() + delay);
+            delay
+        };
+
+        let mut limit = slots.limit.lock().unwrap_or_else(|e| e.into_inner());
+        if *limit > 1 && slots.semaphore.forget_permits(1) == 1 {
+            *limit -= 1;
+        }
+        warn!(
+            "Server refused a connection for {} (too many connections); backing off {:?}, limit now {}",
+            account, delay, *limit
+        );
+    }
+
+    /// Clear the backoff after a successful login.
+    pub fn record_success(&self, account: &str) {
+        if let Some(slots) = self.accounts.get(&account.to_lowercase()) {
+            let mut backoff = slots.backoff.lock().unwrap_or_else(|e| e.into_inner());
+            if backoff.consecutive_hits > 0 {
+                info!("Connected {} after server connection-limit backoff", account);
+            }
+            *backoff = Backoff::default();
+        }
+    }
+
+    /// Forget the account's backoff and restore its configured limit, e.g.
+    /// after its credentials or server settings were fixed.
+    pub fn reset(&self, account: &str) {
+        self.reset_with(account, limit_for(account));
+    }
+
+    fn reset_with(&self, account: &str, configured: usize) {
+        let Some(slots) = self.accounts.get(&account.to_lowercase()).map(|s| s.clone()) else {
+            return;
+        };
+        *slots.backoff.lock().unwrap_or_else(|e| e.into_inner()) = Backoff::default();
+
+        let mut limit = slots.limit.lock().unwrap_or_else(|e| e.into_inner());
+        if *limit < configured {
+            slots.semaphore.add_permits(configured - *limit);
+            info!("Restored connection limit for {} from {} to {}", account, *limit, configured);
+            *limit = configured;
+        }
+    }
+
+    /// Current (possibly lowered) limit and free slots for an account.
+    pub fn usage(&self, account: &str) -> Option<(usize, usize)> {
+        self.accounts.get(&account.to_lowercase()).map(|slots| {
+            let limit = *slots.limit.lock().unwrap_or_else(|e| e.into_inner());
+            (limit, slots.semaphore.available_permits())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_overrides() {
+        let parsed = parse_overrides("Me@Gmail.com=10, other@x.org = 2,bad,zero@x.org=0,=3");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["me@gmail.com"], 10);
+        assert_eq!(parsed["other@x.org"], 2);
+    }
+
+    #[test]
+    fn test_detects_server_limit_responses() {
+        assert!(is_connection_limit_error("NO [ALERT] Too many simultaneous connections. (Failure)"));
+        assert!(is_connection_limit_error(
+            "BYE Maximum number of connections from user+IP exceeded (mail_max_userip_connections=10)"
+        ));
+        assert!(!is_connection_limit_error("NO [AUTHENTICATIONFAILED] Invalid credentials"));
+    }
+
+    #[tokio::test]
+    async fn test_slots_are_limited_and_released() {
+        let limiter = ConnectionLimiter::new();
+        let first = limiter.acquire_with("a@x.org", 2, Duration::from_millis(20)).await.unwrap();
+        let _second = limiter.acquire_with("a@x.org", 2, Duration::from_millis(20)).await.unwrap();
+        assert!(limiter.acquire_with("a@x.org", 2, Duration::from_millis(20)).await.is_err());
+        // Other accounts are independent
+        assert!(limiter.acquire_with("b@x.org", 2, Duration::from_millis(20)).await.is_ok());
+
+        drop(first);
+        assert!(limiter.acquire_with("A@X.org", 2, Duration::from_millis(20)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_limit_hit_lowers_limit() {
+        let limiter = ConnectionLimiter::new();
+        drop(limiter.acquire_with("a@x.org", 3, Duration::from_millis(20)).await.unwrap());
+        limiter.record_limit_hit("a@x.org");
+        assert_eq!(limiter.usage("a@x.org"), Some((2, 2)));
+        limiter.record_success("a@x.org");
+
+        limiter.record_limit_hit("a@x.org");
+        limiter.reset_with("a@x.org", 3);
+        assert_eq!(limiter.usage("a@x.org"), Some((3, 3)));
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversion of user-supplied timestamps to IMAP search dates.
+//!
+//! IMAP `SINCE`/`BEFORE`/`ON` take a date with no time or timezone
+//! (RFC 3501 `date-text`, e.g. `15-Jan-2024`) and compare it against each
+//! message's INTERNALDATE as the server sees it. To keep results predictable
+//! every timestamp is first normalised to UTC, then rounded *outwards* to a
+//! whole UTC day so a date search never drops a message that falls inside the
+//! requested instant range:
+//!
+//! - `SINCE t`  → `SINCE <UTC date of t>` (floor)
+//! - `BEFORE t` → `BEFORE <UTC date of t>` when t is exactly midnight UTC,
+//!   otherwise `BEFORE <UTC date of t + 1 day>` (ceiling)
+//! - `ON t`     → `ON <UTC date of t>`
+//!
+//! Callers that need exact instants must filter the returned messages by
+//! INTERNALDATE afterwards.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+/// IMAP `date-text` format (`15-Jan-2024`). chrono's `%b` is locale-independent.
+pub const IMAP_DATE_FORMAT: &str = "%d-%b-%Y";
+
+/// IMAP `date-time` format for APPEND (`15-Jan-2024 09:30:00 +0000`).
+pub const IMAP_DATE_TIME_FORMAT: &str = "%d-%b-%Y %H:%M:%S %z";
+
+/// Human-readable summary of the rules above, exposed through `server_info`.
+pub const DATE_HANDLING_SUMMARY: &str = "Timestamps are converted to UTC. IMAP date searches are date-only: \
+SINCE uses the UTC calendar date of the timestamp (inclusive), BEFORE rounds up to the next UTC date unless the \
+timestamp is exactly midnight UTC (exclusive). Date-only inputs (YYYY-MM-DD) are treated as midnight UTC; as an \
+upper bound they include the whole day.";
+
+/// Parse a user-supplied timestamp into UTC.
+///
+/// Accepts RFC 3339 with an offset (`2024-01-15T23:30:00-05:00`), a naive
+/// date-time taken as UTC (`2024-01-15T23:30:00` or `2024-01-15 23:30:00`) and
+/// a bare date taken as midnight UTC (`2024-01-15`).
+pub fn parse_timestamp(input: &str) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(input, format) {
+            return Ok(Utc.from_utc_datetime(&naive));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)));
+    }
+    Err(format!(
+        "Invalid date '{}': expected RFC 3339 (2024-01-15T09:00:00Z), YYYY-MM-DDTHH:MM:SS or YYYY-MM-DD",
+        input
+    ))
+}
+
+/// Parse the inclusive upper bound of a date range. A bare date covers the
+/// whole UTC day, so `2024-01-15` becomes `2024-01-15T23:59:59.999999999Z`.
+pub fn parse_range_end(input: &str) -> Result<DateTime<Utc>, String> {
+    let trimmed = input.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d") {
+        let end_of_day = NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999)
+            .expect("valid end-of-day time");
+        return Ok(Utc.from_utc_datetime(&date.and_time(end_of_day)));
+    }
+    parse_timestamp(trimmed)
+}
+
+/// UTC date to use with `SINCE` so messages at or after `ts` are included.
+pub fn since_date(ts: DateTime<Utc>) -> NaiveDate {
+    ts.date_naive()
+}
+
+/// UTC date to use with `BEFORE` so messages before `ts` are included.
+pub fn before_date(ts: DateTime<Utc>) -> NaiveDate {
+    let date = ts.date_naive();
+    if ts.time() == NaiveTime::MIN {
+        date
+    } else {
+        date + Duration::days(1)
+    }
+}
+
+/// Format a date as IMAP `date-text`.
+pub fn format_imap_date(date: NaiveDate) -> String {
+    date.format(IMAP_DATE_FORMAT).to_string()
+}
+
+/// Format an instant as IMAP `date-time`, for the INTERNALDATE of an APPEND.
+pub fn format_imap_date_time(instant: DateTime<Utc>) -> String {
+    instant.format(IMAP_DATE_TIME_FORMAT).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_format_imap_date_time() {
+        assert_eq!(format_imap_date_time(utc(2024, 1, 5, 9, 30)), "05-Jan-2024 09:30:00 +0000");
+    }
+
+    #[test]
+    fn test_parse_timestamp_formats() {
+        assert_eq!(parse_timestamp("2024-01-15T09:00:00Z").unwrap(), utc(2024, 1, 15, 9, 0));
+        assert_eq!(parse_timestamp("2024-01-15T09:00:00").unwrap(), utc(2024, 1, 15, 9, 0));
+        assert_eq!(parse_timestamp("2024-01-15 09:00:00").unwrap(), utc(2024, 1, 15, 9, 0));
+        assert_eq!(parse_timestamp("2024-01-15").unwrap(), utc(2024, 1, 15, 0, 0));
+        assert!(parse_timestamp("15/01/2024").is_err());
+    }
+
+    #[test]
+    fn test_offset_is_converted_to_utc() {
+        // 23:30 in New York is already the next day in UTC
+        assert_eq!(parse_timestamp("2024-03-10T23:30:00-05:00").unwrap(), utc(2024, 3, 11, 4, 30));
+        // 00:30 in Berlin (summer) is still the previous day in UTC
+        assert_eq!(parse_timestamp("2024-07-11T00:30:00+02:00").unwrap(), utc(2024, 7, 10, 22, 30));
+    }
+
+    #[test]
+    fn test_since_today_near_midnight_across_timezones() {
+        // "Since today" for a user in UTC-5 starts at their local midnight,
+        // which is 05:00 UTC the same calendar day
+        let ny = FixedOffset::west_opt(5 * 3600).unwrap();
+        let start_of_day_ny = ny.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap().with_timezone(&Utc);
+        assert_eq!(format_imap_date(since_date(start_of_day_ny)), "10-Mar-2024");
+
+        // For a user in UTC+9 local midnight is 15:00 UTC on the previous day
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let start_of_day_tokyo = tokyo.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap().with_timezone(&Utc);
+        assert_eq!(format_imap_date(since_date(start_of_day_tokyo)), "09-Mar-2024");
+    }
+
+    #[test]
+    fn test_before_rounds_up_unless_midnight() {
+        assert_eq!(format_imap_date(before_date(utc(2024, 1, 15, 0, 0))), "15-Jan-2024");
+        assert_eq!(format_imap_date(before_date(utc(2024, 1, 15, 0, 1))), "16-Jan-2024");
+        assert_eq!(format_imap_date(before_date(utc(2024, 12, 31, 23, 59))), "01-Jan-2025");
+    }
+
+    #[test]
+    fn test_range_end_covers_whole_day() {
+        let end = parse_range_end("2024-02-29").unwrap();
+        assert_eq!(end.date_naive(), NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert!(end > utc(2024, 2, 29, 23, 59));
+        assert_eq!(parse_range_end("2024-02-29T12:00:00Z").unwrap(), utc(2024, 2, 29, 12, 0));
+    }
+}
@@ -18,6 +18,10 @@ pub enum ImapError {
     
     #[error("TLS error: {0}")]
     Tls(String),
+
+    /// The server certificate failed verification or its pin
+    #[error("TLS certificate verification failed: {0}")]
+    TlsVerification(String),
     
     #[error("Authentication error: {0}")]
     Auth(String),
@@ -28,6 +32,9 @@ pub enum ImapError {
     #[error("Folder not found: {0}")]
     FolderNotFound(String),
     
+    #[error("Folder '{0}' is a container (\\Noselect) and cannot hold messages; select one of its subfolders")]
+    NotSelectable(String),
+
     #[error("Folder already exists: {0}")]
     FolderExists(String),
     
@@ -82,6 +89,12 @@ pub enum ImapError {
     #[error("Operation timed out: {0}")]
     Timeout(String),
 
+    #[error("Server unavailable, circuit open: {0}")]
+    CircuitOpen(String),
+
+    #[error("Not supported by the server: {0}")]
+    Unsupported(String),
+
     #[error("I/O error: {0}")]
     Io(String),
 
@@ -117,6 +130,15 @@ impl From<tokio_native_tls::native_tls::Error> for ImapError {
     }
 }
 
+impl From<crate::tls::TlsError> for ImapError {
+    fn from(err: crate::tls::TlsError) -> Self {
+        match err {
+            crate::tls::TlsError::Verification(reason) => ImapError::TlsVerification(reason),
+            other => ImapError::Tls(other.to_string()),
+        }
+    }
+}
+
 impl From<std::io::Error> for ImapError {
     fn from(err: std::io::Error) -> Self {
         ImapError::Connection(err.to_string())
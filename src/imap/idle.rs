@@ -0,0 +1,135 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! IMAP IDLE (RFC 2177) events and settings.
+//!
+//! Servers may drop a client that idles for 30 minutes, so an IDLE longer
+//! than `IMAP_IDLE_RENEW_SECONDS` (default 25 minutes, capped at 29) is ended
+//! with DONE and re-issued.
+//!
+//! - `IMAP_IDLE_ENABLED` (default true): let sync wait on IDLE when the
+//!   server advertises it instead of only polling.
+//! - `IMAP_IDLE_WAIT_SECONDS` (default 300): how long sync idles on a folder
+//!   before falling back to a regular pass.
+
+use std::time::Duration;
+
+use async_imap::imap_proto::{AttributeValue, MailboxDatum, Response};
+use serde::Serialize;
+
+/// Longest an IDLE may run before it has to be renewed.
+const MAX_RENEW: Duration = Duration::from_secs(29 * 60);
+
+/// A mailbox change reported while idling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IdleEvent {
+    /// The mailbox now holds this many messages; growth means new mail
+    Exists(u32),
+    Recent(u32),
+    /// The message at this sequence number was removed
+    Expunge(u32),
+    /// A message's data changed, usually its flags
+    Fetch {
+        seq: u32,
+        uid: Option<u32>,
+        /// Flags in the same form as `fetch_flags` (`Seen`, `Custom("x")`)
+        flags: Option<Vec<String>>,
+    },
+}
+
+impl IdleEvent {
+    /// The event carried by an untagged response, if it is one IDLE reports.
+    pub fn from_response(response: &Response) -> Option<Self> {
+        match response {
+            Response::MailboxData(MailboxDatum::Exists(n)) => Some(IdleEvent::Exists(*n)),
+            Response::MailboxData(MailboxDatum::Recent(n)) => Some(IdleEvent::Recent(*n)),
+            Response::Expunge(seq) => Some(IdleEvent::Expunge(*seq)),
+            Response::Fetch(seq, attributes) => {
+                let mut uid = None;
+                let mut flags = None;
+                for attribute in attributes {
+                    match attribute {
+                        AttributeValue::Uid(u) => uid = Some(*u),
+                        AttributeValue::Flags(raw) => {
+                            flags = Some(raw.iter().map(|f| flag_name(f)).collect());
+                        }
+                        _ => {}
+                    }
+                }
+                Some(IdleEvent::Fetch { seq: *seq, uid, flags })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Wire flag (`\Seen`, `$Label`) in the Debug form used for cached flags.
+pub(crate) fn flag_name(raw: &str) -> String {
+    match raw.to_ascii_lowercase().as_str() {
+        "\\seen" => "Seen".to_string(),
+        "\\answered" => "Answered".to_string(),
+        "\\flagged" => "Flagged".to_string(),
+        "\\deleted" => "Deleted".to_string(),
+        "\\draft" => "Draft".to_string(),
+        "\\recent" => "Recent".to_string(),
+        "\\*" => "MayCreate".to_string(),
+        _ => format!("Custom({:?})", raw),
+    }
+}
+
+pub fn idle_enabled() -> bool {
+    std::env::var("IMAP_IDLE_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// How long one IDLE command runs before DONE and a fresh IDLE.
+pub fn renew_interval() -> Duration {
+    std::env::var("IMAP_IDLE_RENEW_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &u64| n > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(25 * 60))
+        .min(MAX_RENEW)
+}
+
+/// How long sync waits in IDLE on a folder per call.
+pub fn wait_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("IMAP_IDLE_WAIT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &u64| n > 0)
+            .unwrap_or(300),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_events_from_responses() {
+        let parse = |line: &[u8]| {
+            let (_, response) = Response::from_bytes(line).unwrap();
+            IdleEvent::from_response(&response)
+        };
+
+        assert_eq!(parse(b"* 23 EXISTS\r\n"), Some(IdleEvent::Exists(23)));
+        assert_eq!(parse(b"* 4 EXPUNGE\r\n"), Some(IdleEvent::Expunge(4)));
+        assert_eq!(
+            parse(b"* 7 FETCH (UID 120 FLAGS (\\Seen $Work))\r\n"),
+            Some(IdleEvent::Fetch {
+                seq: 7,
+                uid: Some(120),
+                flags: Some(vec!["Seen".to_string(), "Custom(\"$Work\")".to_string()]),
+            })
+        );
+        assert_eq!(parse(b"* OK Still here\r\n"), None);
+    }
+}
@@ -9,11 +9,24 @@
 // Public Interface for the IMAP module
 
 pub mod atomic;
+pub mod capabilities;
 pub mod client;
+pub mod compress;
+pub mod connection_limits;
+pub mod dates;
 pub mod error;
+pub mod idle;
+pub mod namespace;
 pub mod oauth2;
+pub mod pipeline;
+pub mod provider_profile;
 pub mod session;
+pub mod sort;
+pub mod special_use;
+pub mod threading;
+pub mod transport;
 pub mod types;
+pub mod utf7;
 pub mod xoauth2;
 
 // --- Re-exports ---
@@ -22,9 +35,11 @@ pub mod xoauth2;
 pub use client::ImapClient;
 pub use error::ImapError;
 pub use oauth2::{MicrosoftOAuth2Client, MicrosoftOAuth2Config, OAuth2Error, StoredToken, TokenResponse};
+pub use pipeline::{FolderStatus, PipelineConfig};
 pub use session::{AsyncImapOps, AsyncImapSessionWrapper};
+pub use transport::{ConnectionMode, ImapTransport};
 pub use types::{
-    Address, Email, Envelope, FlagOperation, Flags, Folder, MailboxInfo, SearchCriteria,
+    Address, Email, Envelope, FetchBatch, FetchFailure, FlagOperation, Flags, Folder, MailboxInfo, SearchCriteria,
     // Re-export necessary payload types if they are part of the public API
     AppendEmailPayload, ModifyFlagsPayload,
 };
@@ -42,6 +57,7 @@ use std::fmt;
 
 // Import ImapClientFactory from session module
 use crate::imap::session::ImapClientFactory;
+use crate::imap::connection_limits::ConnectionLimiter;
 
 // Result type for the factory
 pub type ImapSessionFactoryResult = Result<ImapClient<AsyncImapSessionWrapper>, ImapError>;
@@ -67,25 +83,47 @@ impl CloneableImapSessionFactory {
         (self.factory)()
     }
 
-    /// Create a session for a specific account (using account's credentials)
+    /// Create a session for a specific account (using account's credentials).
+    /// The session holds one of the account's connection slots (see
+    /// `connection_limits`) for as long as the client is alive.
     pub async fn create_session_for_account(
         &self,
         account: &crate::dashboard::services::account::Account,
     ) -> ImapSessionFactoryResult {
-        use crate::imap::client::ImapClient;
         use log::debug;
 
         debug!("Creating IMAP session for account: {} ({})", account.email_address, account.imap_host);
 
+        let client = ConnectionLimiter::global()
+            .connect(&account.email_address, Self::connect_account(account))
+            .await?;
+        if compress::deflate_requested() {
+            client.enable_compression().await;
+        }
+        if let Some(capabilities) = client.capabilities() {
+            capabilities::remember(&account.email_address, &capabilities);
+        }
+        Ok(client)
+    }
+
+    async fn connect_account(
+        account: &crate::dashboard::services::account::Account,
+    ) -> ImapSessionFactoryResult {
+        use crate::imap::client::ImapClient;
+        use log::debug;
+
+        let transport = account.imap_transport();
+
         // Route to XOAUTH2 if account is configured for OAuth and has an access token
         if account.is_oauth() {
             if let Some(ref token) = account.oauth_access_token {
                 debug!("Using XOAUTH2 authentication for {}", account.email_address);
-                let client = ImapClient::<AsyncImapSessionWrapper>::connect_with_xoauth2(
+                let client = ImapClient::<AsyncImapSessionWrapper>::connect_with_xoauth2_transport(
                     &account.imap_host,
                     account.imap_port as u16,
                     &account.imap_user,
                     token,
+                    &transport,
                 ).await?;
                 return Ok(client);
             }
@@ -93,11 +131,12 @@ impl CloneableImapSessionFactory {
         }
 
         // Password-based authentication
-        let client = ImapClient::<AsyncImapSessionWrapper>::connect(
+        let client = ImapClient::<AsyncImapSessionWrapper>::connect_with_transport(
             &account.imap_host,
             account.imap_port as u16,
             &account.imap_user,
             &account.imap_pass,
+            &transport,
         ).await?;
 
         Ok(client)
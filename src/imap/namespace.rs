@@ -0,0 +1,327 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Where an account's folders live and how their names nest (RFC 2342).
+//!
+//! Servers differ: Gmail and most Dovecot setups keep personal folders at
+//! the top level separated by `/`, while Courier and some cPanel hosts put
+//! them under `INBOX.` and refuse to create anything outside it. Folder
+//! names built here follow the server's personal namespace rather than a
+//! fixed delimiter. Namespaces don't change while an account exists, so
+//! they are kept per account until invalidated.
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use serde::Serialize;
+
+use crate::imap::client::ImapClient;
+use crate::imap::error::ImapError;
+use crate::imap::session::AsyncImapSessionWrapper;
+
+/// One namespace: folders named `prefix` + the rest, nested by `delimiter`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Namespace {
+    pub prefix: String,
+    /// `None` when the namespace is flat
+    pub delimiter: Option<String>,
+}
+
+/// The personal, other users' and shared namespaces a server reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Namespaces {
+    pub personal: Vec<Namespace>,
+    pub other_users: Vec<Namespace>,
+    pub shared: Vec<Namespace>,
+}
+
+impl Namespaces {
+    /// Namespaces of a server without NAMESPACE: one personal namespace at
+    /// the top level, with the delimiter from `LIST "" ""`.
+    pub fn from_delimiter(delimiter: Option<String>) -> Self {
+        Self {
+            personal: vec![Namespace { prefix: String::new(), delimiter }],
+            ..Default::default()
+        }
+    }
+
+    /// Parse the untagged `* NAMESPACE` response found in `response`.
+    pub fn parse(response: &str) -> Result<Self, ImapError> {
+        let line = response
+            .lines()
+            .find_map(|line| {
+                let rest = line.trim_start().strip_prefix("* ")?;
+                let (keyword, args) = rest.split_once(' ')?;
+                keyword.eq_ignore_ascii_case("NAMESPACE").then_some(args)
+            })
+            .ok_or_else(|| ImapError::Parse("No NAMESPACE response".to_string()))?;
+
+        let mut tokens = Tokens::new(line);
+        let personal = tokens.namespace_list()?;
+        let other_users = tokens.namespace_list()?;
+        let shared = tokens.namespace_list()?;
+        Ok(Self { personal, other_users, shared })
+    }
+
+    /// The hierarchy delimiter of the first personal namespace.
+    pub fn delimiter(&self) -> Option<&str> {
+        self.personal.first().and_then(|ns| ns.delimiter.as_deref())
+    }
+
+    /// Prefix new top-level personal folders go under ("" or "INBOX.").
+    pub fn personal_prefix(&self) -> &str {
+        self.personal.first().map(|ns| ns.prefix.as_str()).unwrap_or("")
+    }
+
+    /// `name` placed in the personal namespace, unless it is INBOX or
+    /// already starts with one of the server's namespace prefixes.
+    pub fn qualify(&self, name: &str) -> String {
+        let prefix = self.personal_prefix();
+        let in_namespace = self.personal.iter()
+            .chain(&self.other_users)
+            .chain(&self.shared)
+            .any(|ns| !ns.prefix.is_empty() && starts_with_prefix(name, &ns.prefix));
+        if prefix.is_empty() || in_namespace || name.eq_ignore_ascii_case("INBOX") {
+            name.to_string()
+        } else {
+            format!("{}{}", prefix, name)
+        }
+    }
+
+    /// Full name of folder `name` inside `parent`. Fails on a flat namespace,
+    /// where folders have no children.
+    pub fn child_name(&self, parent: &str, name: &str) -> Result<String, ImapError> {
+        let delimiter = self.delimiter().ok_or_else(|| {
+            ImapError::Unsupported("Server has a flat folder namespace; folders cannot be nested".to_string())
+        })?;
+        let parent = parent.strip_suffix(delimiter).unwrap_or(parent);
+        Ok(format!("{}{}{}", parent, delimiter, name))
+    }
+
+    /// The folder `name` sits in, if it is nested.
+    pub fn parent_of<'a>(&self, name: &'a str) -> Option<&'a str> {
+        let delimiter = self.delimiter()?;
+        name.rsplit_once(delimiter).map(|(parent, _)| parent).filter(|p| !p.is_empty())
+    }
+
+    /// New full name for a folder being renamed from `old_name`. A bare
+    /// `new_name` keeps the folder where it is; one containing the delimiter
+    /// is taken as a full name.
+    pub fn rename_target(&self, old_name: &str, new_name: &str) -> Result<String, ImapError> {
+        match self.delimiter() {
+            Some(delimiter) if new_name.contains(delimiter) => Ok(new_name.to_string()),
+            _ => match self.parent_of(old_name) {
+                Some(parent) => self.child_name(parent, new_name),
+                None => Ok(self.qualify(new_name)),
+            },
+        }
+    }
+}
+
+/// INBOX is case-insensitive, so `inbox.` matches an `INBOX.` prefix.
+fn starts_with_prefix(name: &str, prefix: &str) -> bool {
+    match (name.get(..5), prefix.get(..5)) {
+        (Some(head), Some(p)) if p.eq_ignore_ascii_case("INBOX") => {
+            head.eq_ignore_ascii_case("INBOX") && name[5..].starts_with(&prefix[5..])
+        }
+        _ => name.starts_with(prefix),
+    }
+}
+
+/// Reader over the arguments of a NAMESPACE response: NIL or a
+/// parenthesized list of `("prefix" "delimiter" extensions...)`.
+struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { rest: input }
+    }
+
+    fn skip_space(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_space();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn nil(&mut self) -> bool {
+        self.skip_space();
+        match self.rest.get(..3) {
+            Some(word) if word.eq_ignore_ascii_case("NIL") => {
+                self.rest = &self.rest[3..];
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn error(&self, expected: &str) -> ImapError {
+        ImapError::Parse(format!("Malformed NAMESPACE response: expected {} at '{}'", expected, self.rest))
+    }
+
+    fn quoted(&mut self) -> Result<String, ImapError> {
+        if !self.eat('"') {
+            return Err(self.error("a quoted string"));
+        }
+        let mut value = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[i + 1..];
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err(self.error("a closing quote"))
+    }
+
+    fn nstring(&mut self) -> Result<Option<String>, ImapError> {
+        if self.nil() { Ok(None) } else { self.quoted().map(Some) }
+    }
+
+    /// Skip a response extension: a string, an atom or a nested list.
+    fn skip_value(&mut self) -> Result<(), ImapError> {
+        self.skip_space();
+        if self.rest.starts_with('"') {
+            return self.quoted().map(|_| ());
+        }
+        if self.eat('(') {
+            while !self.eat(')') {
+                if self.rest.is_empty() {
+                    return Err(self.error("')'"));
+                }
+                self.skip_value()?;
+            }
+            return Ok(());
+        }
+        let end = self.rest.find(|c: char| c.is_whitespace() || c == '(' || c == ')').unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err(self.error("a value"));
+        }
+        self.rest = &self.rest[end..];
+        Ok(())
+    }
+
+    fn namespace_list(&mut self) -> Result<Vec<Namespace>, ImapError> {
+        if self.nil() {
+            return Ok(Vec::new());
+        }
+        if !self.eat('(') {
+            return Err(self.error("NIL or '('"));
+        }
+        let mut namespaces = Vec::new();
+        while !self.eat(')') {
+            if !self.eat('(') {
+                return Err(self.error("'('"));
+            }
+            let prefix = self.quoted()?;
+            let delimiter = self.nstring()?;
+            while !self.eat(')') {
+                if self.rest.is_empty() {
+                    return Err(self.error("')'"));
+                }
+                self.skip_value()?;
+            }
+            namespaces.push(Namespace { prefix, delimiter });
+        }
+        Ok(namespaces)
+    }
+}
+
+lazy_static! {
+    static ref ACCOUNT_NAMESPACES: DashMap<String, Namespaces> = DashMap::new();
+}
+
+/// The account's namespaces if they were discovered before.
+pub fn cached(account: &str) -> Option<Namespaces> {
+    ACCOUNT_NAMESPACES.get(account).map(|entry| entry.clone())
+}
+
+/// Forget an account's namespaces, e.g. after its server settings changed.
+pub fn invalidate(account: &str) {
+    ACCOUNT_NAMESPACES.remove(account);
+}
+
+/// The account's namespaces, from the per-account store or else asked of
+/// the server on `client`.
+pub async fn resolve(
+    client: &ImapClient<AsyncImapSessionWrapper>,
+    account: &str,
+) -> Result<Namespaces, ImapError> {
+    if let Some(namespaces) = cached(account) {
+        return Ok(namespaces);
+    }
+    let namespaces = client.namespace().await?;
+    debug!("Namespaces for {}: {:?}", account, namespaces);
+    ACCOUNT_NAMESPACES.insert(account.to_string(), namespaces.clone());
+    Ok(namespaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ns(prefix: &str, delimiter: Option<&str>) -> Namespace {
+        Namespace { prefix: prefix.to_string(), delimiter: delimiter.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_parse_namespace_response() {
+        let gmail = Namespaces::parse("* NAMESPACE ((\"\" \"/\")) NIL NIL\r\nA3 OK Success\r\n").unwrap();
+        assert_eq!(gmail.personal, vec![ns("", Some("/"))]);
+        assert!(gmail.other_users.is_empty() && gmail.shared.is_empty());
+
+        let courier = Namespaces::parse(
+            "* NAMESPACE ((\"INBOX.\" \".\")) ((\"#Users.\" \".\")) ((\"#Public.\" \".\" \"X-PARAM\" (\"a\" \"b\")))",
+        ).unwrap();
+        assert_eq!(courier.personal, vec![ns("INBOX.", Some("."))]);
+        assert_eq!(courier.other_users, vec![ns("#Users.", Some("."))]);
+        assert_eq!(courier.shared, vec![ns("#Public.", Some("."))]);
+
+        let flat = Namespaces::parse("* namespace ((\"\" NIL)) nil NIL").unwrap();
+        assert_eq!(flat.delimiter(), None);
+
+        assert!(Namespaces::parse("* NAMESPACE ((\"\" \"/\")").is_err());
+        assert!(Namespaces::parse("A1 OK done").is_err());
+    }
+
+    #[test]
+    fn test_folder_names_follow_namespace() {
+        let courier = Namespaces::parse("* NAMESPACE ((\"INBOX.\" \".\")) NIL ((\"#Public.\" \".\"))").unwrap();
+        assert_eq!(courier.qualify("Projects"), "INBOX.Projects");
+        assert_eq!(courier.qualify("inbox.Projects"), "inbox.Projects");
+        assert_eq!(courier.qualify("#Public.Team"), "#Public.Team");
+        assert_eq!(courier.qualify("INBOX"), "INBOX");
+        assert_eq!(courier.child_name("INBOX.Projects", "2025").unwrap(), "INBOX.Projects.2025");
+        assert_eq!(courier.rename_target("INBOX.Projects.Old", "New").unwrap(), "INBOX.Projects.New");
+        assert_eq!(courier.rename_target("INBOX.Temp", "INBOX.Archive.Temp").unwrap(), "INBOX.Archive.Temp");
+
+        let gmail = Namespaces::from_delimiter(Some("/".to_string()));
+        assert_eq!(gmail.qualify("Projects"), "Projects");
+        assert_eq!(gmail.child_name("Work/", "Q3").unwrap(), "Work/Q3");
+        assert_eq!(gmail.rename_target("Work/Old", "Q3.final").unwrap(), "Work/Q3.final");
+        assert_eq!(gmail.rename_target("Old", "New").unwrap(), "New");
+
+        let flat = Namespaces::from_delimiter(None);
+        assert!(matches!(flat.child_name("Work", "Q3"), Err(ImapError::Unsupported(_))));
+        assert_eq!(flat.rename_target("Old", "New").unwrap(), "New");
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! IMAP command pipelining (RFC 3501 section 5.5).
+//!
+//! A client may send several tagged commands before reading any responses,
+//! which turns N round-trips into roughly one on high-latency links. This is
+//! only safe for commands that neither depend on nor change the selected
+//! mailbox, so pipelining is restricted to the allowlist in
+//! [`PIPELINEABLE_COMMANDS`] and disabled unless `IMAP_PIPELINING_ENABLED=true`.
+
+use async_imap::imap_proto::StatusAttribute;
+use serde::Serialize;
+
+/// Commands that are independent of the selected mailbox and of each other.
+pub const PIPELINEABLE_COMMANDS: &[&str] = &["STATUS", "NOOP", "CAPABILITY", "LIST", "LSUB"];
+
+/// Data items requested for each folder by `folder_statuses`.
+pub const STATUS_ITEMS: &str = "(MESSAGES RECENT UIDNEXT UIDVALIDITY UNSEEN)";
+
+/// Default number of commands sent before waiting for their responses.
+const DEFAULT_MAX_IN_FLIGHT: usize = 16;
+
+/// Pipelining settings, read from the environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineConfig {
+    /// `IMAP_PIPELINING_ENABLED` (default false)
+    pub enabled: bool,
+    /// `IMAP_PIPELINE_MAX_IN_FLIGHT` (default 16)
+    pub max_in_flight: usize,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self { enabled: false, max_in_flight: DEFAULT_MAX_IN_FLIGHT }
+    }
+}
+
+impl PipelineConfig {
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("IMAP_PIPELINING_ENABLED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        let max_in_flight = std::env::var("IMAP_PIPELINE_MAX_IN_FLIGHT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &usize| *v > 0)
+            .unwrap_or(DEFAULT_MAX_IN_FLIGHT);
+        Self { enabled, max_in_flight }
+    }
+}
+
+/// Whether `command` may be sent without waiting for earlier responses.
+pub fn is_pipelineable(command: &str) -> bool {
+    let verb = command.split_whitespace().next().unwrap_or("").to_ascii_uppercase();
+    PIPELINEABLE_COMMANDS.contains(&verb.as_str())
+}
+
+/// Quote a mailbox name as an IMAP quoted string.
+pub fn quote_mailbox(name: &str) -> String {
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('"');
+    for c in name.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// `STATUS "<folder>" (MESSAGES RECENT UIDNEXT UIDVALIDITY UNSEEN)`
+pub fn status_command(folder: &str) -> String {
+    format!("STATUS {} {}", quote_mailbox(folder), STATUS_ITEMS)
+}
+
+/// Whether a mailbox name echoed in a STATUS response refers to `requested`.
+/// INBOX is case-insensitive (RFC 3501 section 5.1); other names are exact.
+pub fn mailbox_matches(requested: &str, returned: &str) -> bool {
+    requested == returned
+        || (requested.eq_ignore_ascii_case("INBOX") && returned.eq_ignore_ascii_case("INBOX"))
+}
+
+/// Counters for one folder as returned by STATUS.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FolderStatus {
+    pub name: String,
+    pub messages: Option<u32>,
+    pub recent: Option<u32>,
+    pub unseen: Option<u32>,
+    pub uid_next: Option<u32>,
+    pub uid_validity: Option<u32>,
+    /// Server error for this folder (e.g. NO for a nonexistent mailbox)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FolderStatus {
+    pub fn from_attributes(name: &str, attributes: &[StatusAttribute]) -> Self {
+        let mut status = Self { name: name.to_string(), ..Default::default() };
+        for attribute in attributes {
+            match attribute {
+                StatusAttribute::Messages(n) => status.messages = Some(*n),
+                StatusAttribute::Recent(n) => status.recent = Some(*n),
+                StatusAttribute::Unseen(n) => status.unseen = Some(*n),
+                StatusAttribute::UidNext(n) => status.uid_next = Some(*n),
+                StatusAttribute::UidValidity(n) => status.uid_validity = Some(*n),
+                _ => {}
+            }
+        }
+        status
+    }
+
+    pub fn failed(name: &str, error: String) -> Self {
+        Self { name: name.to_string(), error: Some(error), ..Default::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_allowlisted_commands_pipeline() {
+        assert!(is_pipelineable("STATUS \"INBOX\" (MESSAGES)"));
+        assert!(is_pipelineable("noop"));
+        assert!(!is_pipelineable("SELECT INBOX"));
+        assert!(!is_pipelineable("UID FETCH 1:* FLAGS"));
+        assert!(!is_pipelineable("EXPUNGE"));
+        assert!(!is_pipelineable(""));
+    }
+
+    #[test]
+    fn test_status_command_quotes_mailbox() {
+        assert_eq!(status_command("INBOX"), format!("STATUS \"INBOX\" {}", STATUS_ITEMS));
+        assert_eq!(quote_mailbox(r#"My "Stuff"\Old"#), r#""My \"Stuff\"\\Old""#);
+        assert!(is_pipelineable(&status_command("Sent Items")));
+    }
+
+    #[test]
+    fn test_mailbox_matches() {
+        assert!(mailbox_matches("INBOX", "inbox"));
+        assert!(mailbox_matches("Archive/2024", "Archive/2024"));
+        assert!(!mailbox_matches("Archive", "archive"));
+    }
+
+    #[test]
+    fn test_folder_status_from_attributes() {
+        let status = FolderStatus::from_attributes("INBOX", &[
+            StatusAttribute::Messages(42),
+            StatusAttribute::Unseen(3),
+            StatusAttribute::UidNext(100),
+            StatusAttribute::HighestModSeq(7),
+        ]);
+        assert_eq!(status.messages, Some(42));
+        assert_eq!(status.unseen, Some(3));
+        assert_eq!(status.uid_next, Some(100));
+        assert_eq!(status.recent, None);
+        assert!(status.error.is_none());
+    }
+}
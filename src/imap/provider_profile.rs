@@ -0,0 +1,254 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Provider-specific folder quirks, kept in one place.
+//!
+//! A profile is picked from the account's `provider_type` when it names a
+//! known provider, and otherwise from the IMAP host. It supplies the folder
+//! names a provider uses for special-use roles (tried before the generic
+//! names in `types::find_special_use`) and the folders background sync skips
+//! by default. The hierarchy delimiter always comes from the server.
+//!
+//! - `IMAP_PROVIDER_PROFILES=false` turns detection off; every account then
+//!   gets the generic profile.
+//! - `SYNC_EXCLUDE_FOLDERS` adds folders to skip for every account.
+//! - `SYNC_INCLUDE_FOLDERS` syncs folders a profile would skip, e.g.
+//!   `[Gmail]/All Mail` for a search archive.
+
+use log::debug;
+
+use crate::imap::types::{find_special_use, normalize_role, FolderState};
+
+/// Folder conventions of one mail provider.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProviderProfile {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// `provider_type` values that select this profile
+    aliases: &'static [&'static str],
+    /// IMAP hosts, matched exactly or as a domain suffix
+    hosts: &'static [&'static str],
+    /// Full folder paths for special-use roles, in order of preference
+    pub special_folders: &'static [(&'static str, &'static [&'static str])],
+    /// Folders background sync skips unless listed in SYNC_INCLUDE_FOLDERS
+    pub sync_exclude: &'static [&'static str],
+}
+
+/// Gmail exposes every label as a folder, so the virtual folders repeat
+/// messages that are already synced from their labels.
+static GMAIL: ProviderProfile = ProviderProfile {
+    id: "gmail",
+    name: "Gmail",
+    aliases: &["gmail", "google", "googlemail", "gsuite", "google_workspace"],
+    hosts: &["imap.gmail.com", "imap.googlemail.com"],
+    special_folders: &[
+        ("trash", &["[Gmail]/Trash", "[Google Mail]/Trash", "[Gmail]/Bin", "[Google Mail]/Bin"]),
+        ("junk", &["[Gmail]/Spam", "[Google Mail]/Spam"]),
+        ("sent", &["[Gmail]/Sent Mail", "[Google Mail]/Sent Mail"]),
+        ("drafts", &["[Gmail]/Drafts", "[Google Mail]/Drafts"]),
+        ("all", &["[Gmail]/All Mail", "[Google Mail]/All Mail"]),
+        ("archive", &["[Gmail]/All Mail", "[Google Mail]/All Mail"]),
+        ("flagged", &["[Gmail]/Starred", "[Google Mail]/Starred"]),
+    ],
+    sync_exclude: &[
+        "[Gmail]/All Mail", "[Gmail]/Important", "[Gmail]/Starred",
+        "[Google Mail]/All Mail", "[Google Mail]/Important", "[Google Mail]/Starred",
+    ],
+};
+
+/// Exchange lists non-mail stores (calendar, contacts, sync logs) as folders.
+static OUTLOOK: ProviderProfile = ProviderProfile {
+    id: "outlook",
+    name: "Outlook / Office 365",
+    aliases: &["outlook", "office365", "microsoft", "microsoft365", "hotmail", "live", "exchange"],
+    hosts: &["outlook.office365.com", "outlook.office.com", "imap-mail.outlook.com", "imap.outlook.com"],
+    special_folders: &[
+        ("trash", &["Deleted Items"]),
+        ("junk", &["Junk Email", "Junk E-mail"]),
+        ("sent", &["Sent Items"]),
+        ("drafts", &["Drafts"]),
+        ("archive", &["Archive"]),
+    ],
+    sync_exclude: &[
+        "Calendar", "Contacts", "Tasks", "Notes", "Journal", "Outbox", "Conversation History",
+        "Sync Issues", "Sync Issues/Conflicts", "Sync Issues/Local Failures", "Sync Issues/Server Failures",
+    ],
+};
+
+static YAHOO: ProviderProfile = ProviderProfile {
+    id: "yahoo",
+    name: "Yahoo Mail",
+    aliases: &["yahoo", "ymail", "aol"],
+    hosts: &["imap.mail.yahoo.com", "imap.aol.com"],
+    special_folders: &[
+        ("trash", &["Trash"]),
+        // Yahoo files spam under "Bulk"
+        ("junk", &["Bulk", "Bulk Mail", "Spam"]),
+        ("sent", &["Sent"]),
+        ("drafts", &["Draft", "Drafts"]),
+        ("archive", &["Archive"]),
+    ],
+    sync_exclude: &[],
+};
+
+static ICLOUD: ProviderProfile = ProviderProfile {
+    id: "icloud",
+    name: "iCloud Mail",
+    aliases: &["icloud", "apple", "me", "mac"],
+    hosts: &["imap.mail.me.com"],
+    special_folders: &[
+        ("trash", &["Deleted Messages", "Trash"]),
+        ("junk", &["Junk"]),
+        ("sent", &["Sent Messages", "Sent"]),
+        ("drafts", &["Drafts"]),
+        ("archive", &["Archive"]),
+    ],
+    sync_exclude: &["Notes"],
+};
+
+/// Dovecot's defaults, which most self-hosted servers keep. Also the
+/// profile for anything unrecognised.
+static DOVECOT: ProviderProfile = ProviderProfile {
+    id: "dovecot",
+    name: "Generic (Dovecot)",
+    aliases: &["dovecot", "generic", "custom", "imap"],
+    hosts: &[],
+    special_folders: &[
+        ("trash", &["Trash", "INBOX.Trash"]),
+        ("junk", &["Junk", "INBOX.Junk", "Spam", "INBOX.Spam"]),
+        ("sent", &["Sent", "INBOX.Sent"]),
+        ("drafts", &["Drafts", "INBOX.Drafts"]),
+        ("archive", &["Archive", "INBOX.Archive"]),
+    ],
+    sync_exclude: &[],
+};
+
+static PROFILES: [&ProviderProfile; 5] = [&GMAIL, &OUTLOOK, &YAHOO, &ICLOUD, &DOVECOT];
+
+impl ProviderProfile {
+    /// All shipped profiles.
+    pub fn all() -> &'static [&'static ProviderProfile] {
+        &PROFILES
+    }
+
+    pub fn generic() -> &'static ProviderProfile {
+        &DOVECOT
+    }
+
+    /// Profile for an account: `provider_type` when it names a known
+    /// provider, otherwise the IMAP host, otherwise the generic profile.
+    pub fn detect(provider_type: Option<&str>, imap_host: &str) -> &'static ProviderProfile {
+        if !detection_enabled() {
+            return Self::generic();
+        }
+
+        let by_type = provider_type
+            .map(|t| t.trim().to_ascii_lowercase())
+            .and_then(|t| PROFILES.iter().copied().find(|p| p.id == t || p.aliases.contains(&t.as_str())))
+            // "custom" and friends say nothing about the server; let the host decide
+            .filter(|p| p.id != DOVECOT.id);
+
+        let host = imap_host.trim().trim_end_matches('.').to_ascii_lowercase();
+        let by_host = || PROFILES.iter().copied().find(|p| {
+            p.hosts.iter().any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+        });
+
+        let profile = by_type.or_else(by_host).unwrap_or(&DOVECOT);
+        debug!("Provider profile for {} ({:?}): {}", imap_host, provider_type, profile.id);
+        profile
+    }
+
+    /// Folder for a special-use role: LIST attributes first, then this
+    /// provider's folder names, then the generic names.
+    pub fn find_special_use<'a>(&self, folders: &'a [FolderState], role: &str) -> Option<&'a FolderState> {
+        let role = normalize_role(role)?;
+        if let Some(folder) = folders.iter().find(|f| f.selectable && f.special_use() == Some(role)) {
+            return Some(folder);
+        }
+
+        let by_name = self.special_folders.iter()
+            .filter(|(r, _)| *r == role)
+            .flat_map(|(_, names)| names.iter())
+            .find_map(|name| folders.iter().find(|f| f.selectable && f.name.eq_ignore_ascii_case(name)));
+        by_name.or_else(|| find_special_use(folders, role))
+    }
+
+    /// Whether background sync should fetch `folder`, after the
+    /// SYNC_EXCLUDE_FOLDERS / SYNC_INCLUDE_FOLDERS overrides.
+    pub fn should_sync(&self, folder: &str) -> bool {
+        self.should_sync_with(folder, &folder_list("SYNC_EXCLUDE_FOLDERS"), &folder_list("SYNC_INCLUDE_FOLDERS"))
+    }
+
+    fn should_sync_with(&self, folder: &str, exclude: &[String], include: &[String]) -> bool {
+        if include.iter().any(|f| f.eq_ignore_ascii_case(folder)) {
+            return true;
+        }
+        !self.sync_exclude.iter().any(|f| f.eq_ignore_ascii_case(folder))
+            && !exclude.iter().any(|f| f.eq_ignore_ascii_case(folder))
+    }
+}
+
+fn detection_enabled() -> bool {
+    std::env::var("IMAP_PROVIDER_PROFILES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+fn folder_list(var: &str) -> Vec<String> {
+    std::env::var(var)
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str, attributes: &[&str]) -> FolderState {
+        FolderState {
+            name: name.to_string(),
+            delimiter: Some("/".to_string()),
+            selectable: true,
+            subscribed: true,
+            attributes: attributes.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_detect_profile() {
+        assert_eq!(ProviderProfile::detect(None, "imap.gmail.com").id, "gmail");
+        assert_eq!(ProviderProfile::detect(Some("custom"), "outlook.office365.com").id, "outlook");
+        assert_eq!(ProviderProfile::detect(Some("Office365"), "mail.example.com").id, "outlook");
+        assert_eq!(ProviderProfile::detect(None, "export.imap.mail.yahoo.com").id, "yahoo");
+        assert_eq!(ProviderProfile::detect(None, "imap.mail.me.com").id, "icloud");
+        assert_eq!(ProviderProfile::detect(None, "mail.example.org").id, "dovecot");
+        // Suffix match needs a label boundary
+        assert_eq!(ProviderProfile::detect(None, "notimap.gmail.com.evil.org").id, "dovecot");
+    }
+
+    #[test]
+    fn test_profile_special_use_and_sync_exclusions() {
+        let folders = vec![
+            state("INBOX", &[]),
+            state("Bulk", &[]),
+            state("Spam", &[]),
+            state("[Gmail]/All Mail", &[]),
+        ];
+        // Yahoo's Bulk wins over the generic "Spam" name
+        let yahoo = ProviderProfile::detect(Some("yahoo"), "");
+        assert_eq!(yahoo.find_special_use(&folders, "junk").map(|f| f.name.as_str()), Some("Bulk"));
+        assert_eq!(ProviderProfile::generic().find_special_use(&folders, "junk").map(|f| f.name.as_str()), Some("Spam"));
+
+        let gmail = ProviderProfile::detect(None, "imap.gmail.com");
+        assert!(!gmail.should_sync_with("[Gmail]/All Mail", &[], &[]));
+        assert!(gmail.should_sync_with("[Gmail]/All Mail", &[], &["[gmail]/all mail".to_string()]));
+        assert!(!gmail.should_sync_with("Receipts", &["Receipts".to_string()], &[]));
+        assert!(gmail.should_sync_with("INBOX", &[], &[]));
+    }
+}
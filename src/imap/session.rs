@@ -8,43 +8,74 @@ use std::{
     pin::Pin,
     future::Future,
     fmt::Debug,
-    sync::Arc,
+    sync::{atomic::{AtomicBool, Ordering}, Arc},
     time::Duration,
 };
 
 // Async runtime and utilities
+use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use futures_util::stream::TryStreamExt;
-use log::{debug, error, info, warn};
+use tracing::{debug, error, info, warn};
 
 // IMAP types and client
 use async_imap::{
     types::{
-        Fetch, Flag, Name as AsyncImapName, Mailbox as AsyncImapMailbox,
+        Fetch, Flag, Name as AsyncImapName, Mailbox as AsyncImapMailbox, UnsolicitedResponse,
     },
+    imap_proto::{MailboxDatum, NameAttribute, Response, ResponseCode, Status as ResponseStatus, StatusAttribute},
     Session as AsyncImapSession,
 };
 
 // Local types
 use crate::imap::{
-    types::{Email, FlagOperation, MailboxInfo, SearchCriteria},
+    capabilities::{capability_after_login_enabled, ServerCapabilities},
+    types::{appended_uid, copyuid_pairs, imap_flag_syntax, is_noselect_attribute, uid_set, ChangedSince, CopiedUid, Email, FetchBatch, FetchFailure, FlagOperation, FolderState, MailboxInfo, PartialBody, QuotaInfo, SearchCriteria},
     error::ImapError,
+    idle::{self as idle_settings, IdleEvent},
+    namespace::Namespaces,
+    pipeline::{self, FolderStatus, PipelineConfig},
+    sort::{self, SortItem, SortKey},
+    threading::{self, ThreadAlgorithm, ThreadMessage, ThreadNode},
+    utf7,
 };
 
 // TLS Stream types
 use tokio::net::TcpStream as TokioTcpStream;
 use tokio_util::compat::TokioAsyncReadCompatExt;
-use tokio_native_tls::{native_tls, TlsConnector};
-use tokio::sync::Mutex as TokioMutex;
+use crate::imap::transport::{ImapStream, ImapTransport};
+use crate::imap::compress::{CompressSwitch, CompressibleStream};
+use tokio::sync::{Mutex as TokioMutex, OwnedMutexGuard};
 
 // Type aliases
-pub type TlsCompatibleStream = tokio_util::compat::Compat<tokio_native_tls::TlsStream<TokioTcpStream>>;
+pub type TlsCompatibleStream = CompressibleStream<tokio_util::compat::Compat<ImapStream>>;
 pub type TlsImapSession = async_imap::Session<TlsCompatibleStream>;
 pub type ImapClientFactory = fn(TlsCompatibleStream) -> async_imap::Client<TlsCompatibleStream>;
 
 // Define a constant for the delimiter
 pub const DEFAULT_MAILBOX_DELIMITER: &str = "/";
 
+/// Whether `fetch_emails` skips messages that fail to parse or are missing
+/// data (`IMAP_FETCH_TOLERATE_PARTIAL`, default true) instead of failing the batch.
+pub(crate) fn tolerate_partial_fetch() -> bool {
+    std::env::var("IMAP_FETCH_TOLERATE_PARTIAL")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Whether `select_folder` opens folders read-only with EXAMINE
+/// (`IMAP_AUTO_EXAMINE`, default true). Reads then leave \Recent alone, and
+/// the session re-SELECTs the folder read-write before the first command
+/// that modifies it. Set to false for servers that mishandle EXAMINE.
+fn auto_examine_enabled() -> bool {
+    std::env::var("IMAP_AUTO_EXAMINE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
 /// Trait defining asynchronous IMAP operations
 #[async_trait]
 pub trait AsyncImapOps: Send + Sync + Debug {
@@ -52,6 +83,9 @@ pub trait AsyncImapOps: Send + Sync + Debug {
     async fn logout(&self) -> Result<(), ImapError>;
     async fn list_folders(&self) -> Result<Vec<String>, ImapError>;
     async fn list_folders_hierarchical(&self) -> Result<Vec<crate::imap::types::Folder>, ImapError>;
+    /// LIST and LSUB combined: every folder with its selectability and
+    /// subscription state.
+    async fn list_folder_states(&self) -> Result<Vec<FolderState>, ImapError>;
     async fn create_folder(&self, name: &str) -> Result<(), ImapError>;
     async fn delete_folder(&self, name: &str) -> Result<(), ImapError>;
     async fn rename_folder(&self, old_name: &str, new_name: &str) -> Result<(), ImapError>;
@@ -59,11 +93,26 @@ pub trait AsyncImapOps: Send + Sync + Debug {
     async fn search_emails(&self, criteria: &str) -> Result<Vec<u32>, ImapError>;
     async fn search_emails_structured(&self, criteria: &SearchCriteria) -> Result<Vec<u32>, ImapError>;
     async fn fetch_emails(&self, uids: &[u32]) -> Result<Vec<Email>, ImapError>;
+    /// Fetch emails, skipping and reporting messages that fail to parse or are missing data.
+    async fn fetch_emails_tolerant(&self, uids: &[u32]) -> Result<FetchBatch, ImapError>;
     /// Fetch only FLAGS for the given UIDs (lightweight, no body download).
     async fn fetch_flags(&self, uids: &[u32]) -> Result<Vec<(u32, Vec<String>)>, ImapError>;
+    /// Flags, envelope and internal date of `uids`, without the body.
+    async fn fetch_envelopes(&self, uids: &[u32]) -> Result<Vec<Email>, ImapError>;
     async fn move_email(&self, uid: u32, from_folder: &str, to_folder: &str) -> Result<(), ImapError>;
     async fn store_flags(&self, uids: &[u32], operation: FlagOperation, flags: &[String]) -> Result<(), ImapError>;
     async fn append(&self, folder: &str, content: &[u8], flags: &[String]) -> Result<(), ImapError>;
+    /// APPEND with `flags` set on the new message and, when given,
+    /// `internal_date` as its INTERNALDATE. Returns its UID when the server
+    /// answers with APPENDUID (UIDPLUS). A missing folder fails with
+    /// `FolderNotFound` when the server says TRYCREATE.
+    async fn append_with_flags(
+        &self,
+        folder: &str,
+        content: &[u8],
+        flags: &[String],
+        internal_date: Option<DateTime<Utc>>,
+    ) -> Result<Option<u32>, ImapError>;
     async fn fetch_raw_message(&self, uid: u32) -> Result<Vec<u8>, ImapError>;
     async fn expunge(&self) -> Result<(), ImapError>;
     async fn copy_messages(&self, uids: &[u32], to_folder: &str) -> Result<(), ImapError>;
@@ -72,6 +121,37 @@ pub trait AsyncImapOps: Send + Sync + Debug {
     async fn delete_messages(&self, uids: &[u32]) -> Result<(), ImapError>;
     async fn undelete_messages(&self, uids: &[u32]) -> Result<(), ImapError>;
     async fn noop(&self) -> Result<(), ImapError>;
+    /// STATUS several folders without selecting them. Pipelined when
+    /// `IMAP_PIPELINING_ENABLED` is set; a NO for one folder is reported in
+    /// that folder's `error` rather than failing the call.
+    async fn folder_statuses(&self, folders: &[String]) -> Result<Vec<FolderStatus>, ImapError>;
+    /// Post-authentication CAPABILITY set, fetched on first use if it wasn't
+    /// loaded at login.
+    async fn server_capabilities(&self) -> Result<ServerCapabilities, ImapError>;
+    /// Select `folder` and IDLE until the server reports changes or `timeout`
+    /// passes (returning no events). Long waits are renewed before the
+    /// server's 30-minute cutoff; the session is usable again afterwards,
+    /// even if the call is cancelled.
+    async fn idle(&self, folder: &str, timeout: Duration) -> Result<Vec<IdleEvent>, ImapError>;
+    /// GETQUOTAROOT for `mailbox`. Fails with `Unsupported` when the server
+    /// does not advertise QUOTA.
+    async fn get_quota(&self, mailbox: &str) -> Result<QuotaInfo, ImapError>;
+    /// Personal, other users' and shared namespaces (RFC 2342). Servers
+    /// without NAMESPACE get a single top-level personal namespace with the
+    /// delimiter from `LIST "" ""`.
+    async fn namespace(&self) -> Result<Namespaces, ImapError>;
+    /// Conversation trees of the selected folder's messages matching
+    /// `criteria`. Built from each message's headers (see `imap::threading`).
+    async fn thread(&self, algorithm: ThreadAlgorithm, criteria: &SearchCriteria) -> Result<Vec<ThreadNode>, ImapError>;
+    /// UIDs of the selected folder's messages matching `criteria`, ordered
+    /// by `keys`. Uses UID SORT when the server advertises SORT and sorts
+    /// envelopes locally otherwise.
+    async fn sort(&self, keys: &[SortKey], criteria: &SearchCriteria) -> Result<Vec<u32>, ImapError>;
+    /// Text of a BYE the server sent before closing the connection, if one
+    /// is among the unsolicited responses. Drains them.
+    async fn take_bye(&self) -> Option<String> {
+        None
+    }
 }
 
 // Wrapper definition using Arc<Mutex<...>>
@@ -79,7 +159,12 @@ pub trait AsyncImapOps: Send + Sync + Debug {
 pub struct AsyncImapSessionWrapper {
     session: Arc<TokioMutex<TlsImapSession>>,
     current_folder: Arc<TokioMutex<Option<String>>>,
+    /// Whether `current_folder` was opened with EXAMINE
+    read_only: Arc<AtomicBool>,
     append_timeout: Duration,
+    capabilities: Arc<ArcSwapOption<ServerCapabilities>>,
+    /// Turns on deflate for the session's stream, see `enable_compression`
+    compression: Option<CompressSwitch>,
 }
 
 impl AsyncImapSessionWrapper {
@@ -91,10 +176,91 @@ impl AsyncImapSessionWrapper {
         Self {
             session: Arc::new(TokioMutex::new(session)),
             current_folder: Arc::new(TokioMutex::new(None)),
+            read_only: Arc::new(AtomicBool::new(false)),
             append_timeout,
+            capabilities: Arc::new(ArcSwapOption::empty()),
+            compression: None,
+        }
+    }
+
+    pub fn with_compress_switch(mut self, switch: CompressSwitch) -> Self {
+        self.compression = Some(switch);
+        self
+    }
+
+    /// Negotiate COMPRESS=DEFLATE (RFC 4978) if the server offers it.
+    /// Returns whether the session is compressed; a server that lacks the
+    /// extension or refuses the command leaves the session as it was.
+    pub async fn enable_compression(&self) -> bool {
+        let Some(switch) = &self.compression else {
+            return false;
+        };
+        if switch.is_enabled() {
+            return true;
+        }
+        match self.server_capabilities().await {
+            Ok(caps) if caps.has("COMPRESS=DEFLATE") => {}
+            Ok(_) => {
+                debug!("Server does not offer COMPRESS=DEFLATE");
+                return false;
+            }
+            Err(e) => {
+                warn!("Could not check for COMPRESS=DEFLATE, continuing uncompressed: {}", e);
+                return false;
+            }
+        }
+
+        let mut session_guard = self.session.lock().await;
+        match session_guard.run_command_and_check_ok("COMPRESS DEFLATE").await {
+            Ok(()) => {
+                // The server compresses from the byte after its OK
+                switch.enable();
+                info!("IMAP COMPRESS=DEFLATE active");
+                true
+            }
+            Err(e) => {
+                warn!("COMPRESS DEFLATE failed, continuing uncompressed: {:?}", e);
+                false
+            }
+        }
+    }
+
+    /// Issue CAPABILITY now that the session is authenticated and cache the
+    /// answer, unless `IMAP_CAPABILITY_AFTER_LOGIN=false`. A failure only
+    /// leaves the set unknown.
+    pub async fn load_post_auth_capabilities(&self) {
+        if !capability_after_login_enabled() {
+            return;
+        }
+        if let Err(e) = self.refresh_capabilities().await {
+            warn!("CAPABILITY after login failed, optional extensions will be probed per command: {}", e);
         }
     }
 
+    /// Issue CAPABILITY and replace the cached set with the answer.
+    pub async fn refresh_capabilities(&self) -> Result<ServerCapabilities, ImapError> {
+        let capabilities = {
+            let mut session_guard = self.session.lock().await;
+            let response = session_guard.capabilities().await.map_err(ImapError::from)?;
+            ServerCapabilities::from_async_imap(&response)
+        };
+        debug!("Post-auth capabilities: {}", capabilities.names().collect::<Vec<_>>().join(" "));
+        self.capabilities.store(Some(Arc::new(capabilities.clone())));
+        Ok(capabilities)
+    }
+
+    /// The capabilities cached after login, or None if they weren't asked
+    /// for yet. Replaced whenever CAPABILITY is issued again.
+    pub fn capabilities(&self) -> Option<Arc<ServerCapabilities>> {
+        self.capabilities.load_full()
+    }
+
+    /// Whether to attempt MOVE: yes if the server advertised it, or if we
+    /// don't know its capabilities.
+    async fn should_try_move(&self) -> bool {
+        self.capabilities().is_none_or(|caps| caps.supports_move())
+    }
+
     pub async fn connect(
         server: &str,
         port: u16,
@@ -102,24 +268,23 @@ impl AsyncImapSessionWrapper {
         password: Arc<String>,
         append_timeout: Duration,
     ) -> Result<Self, ImapError> {
-        let tls_builder = native_tls::TlsConnector::builder();
-        let tls = tls_builder.build().map_err(|e| ImapError::Tls(e.to_string()))?;
-        let tls_connector = TlsConnector::from(tls);
-
-        let addr = format!("{}:{}", server, port);
-        let tcp_stream = TokioTcpStream::connect(&addr).await.map_err(|e| ImapError::Connection(e.to_string()))?;
-
-        info!("Setting socket timeouts: read={:?}, write={:?}", append_timeout, append_timeout);
-
-        let std_stream = tcp_stream.into_std().map_err(|e| ImapError::Connection(format!("Failed to convert to std stream: {}", e)))?;
-        std_stream.set_read_timeout(Some(append_timeout)).map_err(|e| ImapError::Connection(format!("Failed to set read timeout: {}", e)))?;
-        std_stream.set_write_timeout(Some(append_timeout)).map_err(|e| ImapError::Connection(format!("Failed to set write timeout: {}", e)))?;
-        let tcp_stream = TokioTcpStream::from_std(std_stream).map_err(|e| ImapError::Connection(format!("Failed to convert back to tokio stream: {}", e)))?;
+        Self::connect_with_transport(server, port, username, password, append_timeout, &ImapTransport::implicit(crate::tls::default_config())).await
+    }
 
-        let tls_stream = tls_connector.connect(server, tcp_stream).await.map_err(|e| ImapError::Tls(e.to_string()))?;
-        let compat_stream = tls_stream.compat();
+    /// `connect` secured as `transport` says (implicit TLS, STARTTLS or
+    /// plaintext) instead of implicit TLS under the default settings.
+    pub async fn connect_with_transport(
+        server: &str,
+        port: u16,
+        username: Arc<String>,
+        password: Arc<String>,
+        append_timeout: Duration,
+        transport: &ImapTransport,
+    ) -> Result<Self, ImapError> {
+        let (stream, _) = Self::open_stream(server, port, append_timeout, transport).await?;
+        let compress = stream.switch();
 
-        let client = async_imap::Client::new(compat_stream);
+        let client = async_imap::Client::new(stream);
         let session = client.login(&*username, &*password).await.map_err(|(err, _client)| {
             match err {
                 async_imap::error::Error::No(msg) | async_imap::error::Error::Bad(msg) => ImapError::Auth(format!("Login failed: {}", msg)),
@@ -127,7 +292,9 @@ impl AsyncImapSessionWrapper {
             }
         })?;
 
-        Ok(Self::with_append_timeout(session, append_timeout))
+        let wrapper = Self::with_append_timeout(session, append_timeout).with_compress_switch(compress);
+        wrapper.load_post_auth_capabilities().await;
+        Ok(wrapper)
     }
 
     /// Connect using XOAUTH2 authentication (for OAuth2 providers like Microsoft 365 and Gmail)
@@ -138,31 +305,32 @@ impl AsyncImapSessionWrapper {
         access_token: Arc<String>,
         append_timeout: Duration,
     ) -> Result<Self, ImapError> {
-        use crate::imap::xoauth2::XOAuth2Authenticator;
-
-        let tls_builder = native_tls::TlsConnector::builder();
-        let tls = tls_builder.build().map_err(|e| ImapError::Tls(e.to_string()))?;
-        let tls_connector = TlsConnector::from(tls);
-
-        let addr = format!("{}:{}", server, port);
-        let tcp_stream = TokioTcpStream::connect(&addr).await.map_err(|e| ImapError::Connection(e.to_string()))?;
-
-        info!("Setting socket timeouts: read={:?}, write={:?}", append_timeout, append_timeout);
+        Self::connect_with_xoauth2_transport(server, port, username, access_token, append_timeout, &ImapTransport::implicit(crate::tls::default_config())).await
+    }
 
-        let std_stream = tcp_stream.into_std().map_err(|e| ImapError::Connection(format!("Failed to convert to std stream: {}", e)))?;
-        std_stream.set_read_timeout(Some(append_timeout)).map_err(|e| ImapError::Connection(format!("Failed to set read timeout: {}", e)))?;
-        std_stream.set_write_timeout(Some(append_timeout)).map_err(|e| ImapError::Connection(format!("Failed to set write timeout: {}", e)))?;
-        let tcp_stream = TokioTcpStream::from_std(std_stream).map_err(|e| ImapError::Connection(format!("Failed to convert back to tokio stream: {}", e)))?;
+    /// `connect_with_xoauth2` secured as `transport` says.
+    pub async fn connect_with_xoauth2_transport(
+        server: &str,
+        port: u16,
+        username: Arc<String>,
+        access_token: Arc<String>,
+        append_timeout: Duration,
+        transport: &ImapTransport,
+    ) -> Result<Self, ImapError> {
+        use crate::imap::xoauth2::XOAuth2Authenticator;
 
-        let tls_stream = tls_connector.connect(server, tcp_stream).await.map_err(|e| ImapError::Tls(e.to_string()))?;
-        let compat_stream = tls_stream.compat();
+        let (stream, greeted) = Self::open_stream(server, port, append_timeout, transport).await?;
+        let compress = stream.switch();
 
-        let mut client = async_imap::Client::new(compat_stream);
+        let mut client = async_imap::Client::new(stream);
 
         // Consume the IMAP server greeting before AUTHENTICATE.
         // async-imap's login() handles this internally, but authenticate()
-        // expects the greeting to have been read already.
-        let _greeting = client.read_response().await;
+        // expects the greeting to have been read already. After STARTTLS
+        // there is no second greeting to wait for.
+        if !greeted {
+            let _greeting = client.read_response().await;
+        }
 
         // Use XOAUTH2 authentication
         let authenticator = XOAuth2Authenticator::new(&username, &access_token);
@@ -174,7 +342,26 @@ impl AsyncImapSessionWrapper {
         })?;
 
         info!("XOAUTH2 authentication successful for user: {}", username);
-        Ok(Self::with_append_timeout(session, append_timeout))
+        let wrapper = Self::with_append_timeout(session, append_timeout).with_compress_switch(compress);
+        wrapper.load_post_auth_capabilities().await;
+        Ok(wrapper)
+    }
+
+    /// TCP connection with socket timeouts, secured as `transport` says.
+    /// Also returns whether the greeting has been read (see `transport::open`).
+    async fn open_stream(server: &str, port: u16, append_timeout: Duration, transport: &ImapTransport) -> Result<(TlsCompatibleStream, bool), ImapError> {
+        let addr = format!("{}:{}", server, port);
+        let tcp_stream = TokioTcpStream::connect(&addr).await.map_err(|e| ImapError::Connection(e.to_string()))?;
+
+        info!("Setting socket timeouts: read={:?}, write={:?}", append_timeout, append_timeout);
+
+        let std_stream = tcp_stream.into_std().map_err(|e| ImapError::Connection(format!("Failed to convert to std stream: {}", e)))?;
+        std_stream.set_read_timeout(Some(append_timeout)).map_err(|e| ImapError::Connection(format!("Failed to set read timeout: {}", e)))?;
+        std_stream.set_write_timeout(Some(append_timeout)).map_err(|e| ImapError::Connection(format!("Failed to set write timeout: {}", e)))?;
+        let tcp_stream = TokioTcpStream::from_std(std_stream).map_err(|e| ImapError::Connection(format!("Failed to convert back to tokio stream: {}", e)))?;
+
+        let (stream, greeted) = crate::imap::transport::open(server, tcp_stream, transport).await?;
+        Ok((CompressibleStream::new(stream.compat()), greeted))
     }
 
     pub async fn current_folder(&self) -> Option<String> {
@@ -185,13 +372,54 @@ impl AsyncImapSessionWrapper {
         let current = self.current_folder().await;
         if current.as_deref() != Some(folder) {
             let mut session_guard = self.session.lock().await;
-            session_guard.select(folder).await.map_err(ImapError::from)?;
+            session_guard.select(utf7::to_imap(folder)).await.map_err(ImapError::from)?;
             drop(session_guard);
+            self.read_only.store(false, Ordering::SeqCst);
             let mut folder_guard = self.current_folder.lock().await;
             *folder_guard = Some(folder.to_string());
         }
         Ok(())
     }
+
+    /// Whether the selected folder was opened read-only with EXAMINE.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
+    }
+
+    /// Re-SELECT the current folder read-write if it was opened with
+    /// EXAMINE. Takes the already-locked session so mutating commands can
+    /// upgrade and run under one lock.
+    async fn upgrade_to_read_write(&self, session: &mut TlsImapSession) -> Result<(), ImapError> {
+        if !self.read_only.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let Some(folder) = self.current_folder().await else {
+            return Ok(());
+        };
+        debug!("Upgrading '{}' from EXAMINE to SELECT before a modifying command", folder);
+        session.select(utf7::to_imap(&folder)).await.map_err(ImapError::from)?;
+        self.read_only.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Whether LIST reports `name` as \Noselect. Any failure counts as "no" so the
+/// caller falls back to the original error.
+async fn is_noselect_folder(session: &mut TlsImapSession, name: &str) -> bool {
+    let mut folders_stream = match session.list(None, Some(&utf7::to_imap(name))).await {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+    // Drain the whole response so nothing is left unread on the connection
+    let mut noselect = false;
+    while let Ok(Some(folder_result)) = folders_stream.try_next().await {
+        if pipeline::mailbox_matches(name, &utf7::from_imap(folder_result.name()))
+            && folder_result.attributes().iter().any(|attr| matches!(attr, NameAttribute::NoSelect))
+        {
+            noselect = true;
+        }
+    }
+    noselect
 }
 
 #[async_trait]
@@ -213,7 +441,7 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
         let mut folders_stream = session_guard.list(None, Some("*")).await.map_err(ImapError::from)?;
         let mut folder_names = Vec::new();
         while let Some(folder_result) = folders_stream.try_next().await.map_err(ImapError::from)? {
-            folder_names.push(folder_result.name().to_string());
+            folder_names.push(utf7::from_imap(folder_result.name()));
         }
         Ok(folder_names)
     }
@@ -223,7 +451,7 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
         let mut folders_stream = session_guard.list(None, Some("*")).await.map_err(ImapError::from)?;
         let mut folder_data = Vec::new();
         while let Some(folder_result) = folders_stream.try_next().await.map_err(ImapError::from)? {
-            let name = folder_result.name().to_string();
+            let name = utf7::from_imap(folder_result.name());
             let delimiter = folder_result.delimiter().map(|d| d.to_string());
             let attributes: Vec<String> = folder_result.attributes().iter().map(|attr| format!("{:?}", attr)).collect();
             folder_data.push((name, delimiter, attributes));
@@ -232,24 +460,75 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
         Ok(hierarchy)
     }
 
+    async fn list_folder_states(&self) -> Result<Vec<FolderState>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+
+        let mut listed = Vec::new();
+        {
+            let mut folders_stream = session_guard.list(None, Some("*")).await.map_err(ImapError::from)?;
+            while let Some(folder_result) = folders_stream.try_next().await.map_err(ImapError::from)? {
+                let name = utf7::from_imap(folder_result.name());
+                let delimiter = folder_result.delimiter().map(|d| d.to_string());
+                let attributes: Vec<String> = folder_result.attributes().iter().map(|attr| format!("{:?}", attr)).collect();
+                listed.push((name, delimiter, attributes));
+            }
+        }
+
+        let mut subscribed = Vec::new();
+        {
+            let mut lsub_stream = session_guard.lsub(None, Some("*")).await.map_err(ImapError::from)?;
+            while let Some(folder_result) = lsub_stream.try_next().await.map_err(ImapError::from)? {
+                subscribed.push(utf7::from_imap(folder_result.name()));
+            }
+        }
+
+        Ok(listed
+            .into_iter()
+            .map(|(name, delimiter, attributes)| FolderState {
+                selectable: !attributes.iter().any(|a| is_noselect_attribute(a)),
+                subscribed: subscribed.iter().any(|s| pipeline::mailbox_matches(&name, s)),
+                name,
+                delimiter,
+                attributes,
+            })
+            .collect())
+    }
+
     async fn create_folder(&self, name: &str) -> Result<(), ImapError> {
         let mut session_guard = self.session.lock().await;
-        session_guard.create(name).await.map_err(ImapError::from)
+        session_guard.create(utf7::to_imap(name)).await.map_err(ImapError::from)
     }
 
     async fn delete_folder(&self, name: &str) -> Result<(), ImapError> {
         let mut session_guard = self.session.lock().await;
-        session_guard.delete(name).await.map_err(ImapError::from)
+        session_guard.delete(utf7::to_imap(name)).await.map_err(ImapError::from)
     }
 
     async fn rename_folder(&self, old_name: &str, new_name: &str) -> Result<(), ImapError> {
         let mut session_guard = self.session.lock().await;
-        session_guard.rename(old_name, new_name).await.map_err(ImapError::from)
+        session_guard.rename(utf7::to_imap(old_name), utf7::to_imap(new_name)).await.map_err(ImapError::from)
     }
 
     async fn select_folder(&self, name: &str) -> Result<MailboxInfo, ImapError> {
+        let read_only = auto_examine_enabled();
         let mut session_guard = self.session.lock().await;
-        let mailbox = session_guard.select(name).await.map_err(ImapError::from)?;
+        let opened = if read_only {
+            session_guard.examine(utf7::to_imap(name)).await
+        } else {
+            session_guard.select(utf7::to_imap(name)).await
+        };
+        let mailbox = match opened {
+            Ok(mailbox) => mailbox,
+            Err(e) => {
+                // A NO on SELECT is usually a missing folder, but for \Noselect
+                // containers the server's text is rarely helpful, so say why.
+                if is_noselect_folder(&mut session_guard, name).await {
+                    return Err(ImapError::NotSelectable(name.to_string()));
+                }
+                return Err(ImapError::from(e));
+            }
+        };
+        self.read_only.store(read_only, Ordering::SeqCst);
         let mut folder_guard = self.current_folder.lock().await;
         *folder_guard = Some(name.to_string());
         let mut info = MailboxInfo::from(mailbox);
@@ -280,28 +559,62 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
     }
 
     async fn fetch_emails(&self, uids: &[u32]) -> Result<Vec<Email>, ImapError> {
+        let batch = self.fetch_emails_tolerant(uids).await?;
+        if let Some(first) = batch.errors.first() {
+            if !tolerate_partial_fetch() {
+                return Err(ImapError::Fetch(format!("UID {}: {}", first.uid, first.reason)));
+            }
+            warn!("Skipped {} of {} requested UIDs: {:?}", batch.errors.len(), uids.len(), batch.errors);
+        }
+        Ok(batch.emails)
+    }
+
+    async fn fetch_emails_tolerant(&self, uids: &[u32]) -> Result<FetchBatch, ImapError> {
         let mut session_guard = self.session.lock().await;
-        let sequence = uids.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(",");
+        let sequence = uid_set(uids);
         debug!("Fetching {} UIDs: {:?}", uids.len(), uids);
         let mut fetch_stream = session_guard.uid_fetch(&sequence, "(FLAGS ENVELOPE INTERNALDATE BODY.PEEK[])").await.map_err(ImapError::from)?;
-        let mut emails = Vec::new();
-        while let Some(fetch_result) = fetch_stream.try_next().await.map_err(ImapError::from)? {
-            let email = Email::from_fetch(&fetch_result)?;
-            debug!("Fetched email UID: {}", email.uid);
-            emails.push(email);
-        }
-        debug!("Fetch complete: requested {} UIDs, received {} emails", uids.len(), emails.len());
-        if emails.len() != uids.len() {
-            warn!("UID mismatch: requested {}, received {}. Missing UIDs: {:?}",
-                  uids.len(), emails.len(),
-                  uids.iter().filter(|uid| !emails.iter().any(|e| e.uid == **uid)).collect::<Vec<_>>());
+        let mut batch = FetchBatch::default();
+        loop {
+            let fetch_result = match fetch_stream.try_next().await {
+                Ok(Some(fetch_result)) => fetch_result,
+                Ok(None) => break,
+                Err(e) => {
+                    // The stream cannot be resumed after a protocol error; keep what we have
+                    warn!("FETCH stream aborted after {} emails: {}", batch.emails.len(), e);
+                    batch.record_missing(uids, &format!("Fetch aborted: {}", e));
+                    batch.aborted = true;
+                    break;
+                }
+            };
+            let Some(uid) = fetch_result.uid else {
+                warn!("Ignoring FETCH response without UID");
+                continue;
+            };
+            if fetch_result.body().is_none() {
+                batch.errors.push(FetchFailure { uid, reason: "Server response missing BODY[]".to_string() });
+                continue;
+            }
+            match Email::from_fetch(&fetch_result) {
+                Ok(email) => {
+                    debug!("Fetched email UID: {}", email.uid);
+                    batch.emails.push(email);
+                }
+                Err(e) => {
+                    warn!("Failed to parse email UID {}: {}", uid, e);
+                    batch.errors.push(FetchFailure { uid, reason: e.to_string() });
+                }
+            }
         }
-        Ok(emails)
+        batch.record_missing(uids, "Not returned by server");
+        debug!("Fetch complete: requested {} UIDs, received {} emails, {} errors",
+               uids.len(), batch.emails.len(), batch.errors.len());
+        Ok(batch)
     }
 
     async fn fetch_flags(&self, uids: &[u32]) -> Result<Vec<(u32, Vec<String>)>, ImapError> {
         let mut session_guard = self.session.lock().await;
-        let sequence = uids.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(",");
+        let sequence = uid_set(uids);
         let mut fetch_stream = session_guard.uid_fetch(&sequence, "FLAGS").await.map_err(ImapError::from)?;
         let mut results = Vec::new();
         while let Some(fetch_result) = fetch_stream.try_next().await.map_err(ImapError::from)? {
@@ -317,23 +630,43 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
         Ok(results)
     }
 
+    async fn fetch_envelopes(&self, uids: &[u32]) -> Result<Vec<Email>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let sequence = uid_set(uids);
+        let mut fetch_stream = session_guard.uid_fetch(&sequence, "(FLAGS ENVELOPE INTERNALDATE)").await.map_err(ImapError::from)?;
+        let mut emails = Vec::new();
+        while let Some(fetch_result) = fetch_stream.try_next().await.map_err(ImapError::from)? {
+            if fetch_result.uid.is_none() {
+                continue;
+            }
+            emails.push(Email::from_fetch(&fetch_result)?);
+        }
+        Ok(emails)
+    }
+
     async fn move_email(&self, uid: u32, from_folder: &str, to_folder: &str) -> Result<(), ImapError> {
+        let try_move = self.should_try_move().await;
         let mut session_guard = self.session.lock().await;
-        session_guard.select(from_folder).await.map_err(ImapError::from)?;
+        session_guard.select(utf7::to_imap(from_folder)).await.map_err(ImapError::from)?;
+        self.read_only.store(false, Ordering::SeqCst);
         {
             let mut folder_guard = self.current_folder.lock().await;
             *folder_guard = Some(from_folder.to_string());
         }
         let sequence = uid.to_string();
+        let to_folder = utf7::to_imap(to_folder);
 
-        let move_result = session_guard.uid_mv(&sequence, to_folder).await;
-        if move_result.is_ok() {
-            return Ok(());
+        if try_move {
+            let move_result = session_guard.uid_mv(&sequence, &to_folder).await;
+            if move_result.is_ok() {
+                return Ok(());
+            }
+            debug!("MOVE command failed, falling back to COPY+DELETE");
+        } else {
+            debug!("Server does not advertise MOVE, using COPY+DELETE");
         }
 
-        debug!("MOVE command failed, falling back to COPY+DELETE");
-
-        session_guard.uid_copy(&sequence, to_folder).await.map_err(|e| ImapError::Other(format!("Failed to copy message: {}", e)))?;
+        session_guard.uid_copy(&sequence, &to_folder).await.map_err(|e| ImapError::Other(format!("Failed to copy message: {}", e)))?;
         let store_stream = session_guard.uid_store(&sequence, r#"+FLAGS (\Deleted)"#).await.map_err(|e| ImapError::Other(format!("Failed to mark as deleted: {}", e)))?;
         store_stream.try_collect::<Vec<_>>().await.map_err(|e| ImapError::Other(format!("Failed to process store results: {}", e)))?;
 
@@ -345,7 +678,8 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
 
     async fn store_flags(&self, uids: &[u32], operation: FlagOperation, flags: &[String]) -> Result<(), ImapError> {
         let mut session_guard = self.session.lock().await;
-        let sequence = uids.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(",");
+        self.upgrade_to_read_write(&mut session_guard).await?;
+        let sequence = uid_set(uids);
         let flags_str = flags.join(" ");
         let op_str = match operation {
             FlagOperation::Add => format!("+FLAGS ({})", flags_str),
@@ -369,7 +703,7 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
             let runtime_handle = tokio::runtime::Handle::current();
             let mut session_guard = runtime_handle.block_on(session_arc.lock());
             debug!("Executing IMAP APPEND in blocking thread for folder '{}'", folder_str);
-            runtime_handle.block_on(session_guard.append(folder_str, &content))
+            runtime_handle.block_on(session_guard.append(utf7::to_imap(&folder_str), &content))
         });
 
         match tokio::time::timeout(append_timeout, blocking_task).await {
@@ -405,32 +739,39 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
 
     async fn expunge(&self) -> Result<(), ImapError> {
         let mut session_guard = self.session.lock().await;
+        self.upgrade_to_read_write(&mut session_guard).await?;
         let stream = session_guard.expunge().await?;
         stream.try_collect::<Vec<_>>().await.map(|_| ()).map_err(ImapError::from)
     }
 
     async fn copy_messages(&self, uids: &[u32], to_folder: &str) -> Result<(), ImapError> {
         let mut session_guard = self.session.lock().await;
-        let sequence = uids.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(",");
-        session_guard.uid_copy(&sequence, to_folder).await.map_err(|e| ImapError::Other(format!("Failed to copy messages: {}", e)))?;
+        let sequence = uid_set(uids);
+        session_guard.uid_copy(&sequence, utf7::to_imap(to_folder)).await.map_err(|e| ImapError::Other(format!("Failed to copy messages: {}", e)))?;
         Ok(())
     }
 
     async fn move_messages(&self, uids: &[u32], from_folder: &str, to_folder: &str) -> Result<(), ImapError> {
         if uids.is_empty() { return Ok(()); }
         self.ensure_folder_selected(from_folder).await?;
+        let try_move = self.should_try_move().await;
         let mut session_guard = self.session.lock().await;
-        let sequence = uids.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(",");
-
-        let move_result = session_guard.uid_mv(&sequence, to_folder).await;
-        if move_result.is_ok() {
-            debug!("Batch MOVE command succeeded for {} messages", uids.len());
-            return Ok(());
+        self.upgrade_to_read_write(&mut session_guard).await?;
+        let sequence = uid_set(uids);
+        let imap_to_folder = utf7::to_imap(to_folder);
+
+        if try_move {
+            let move_result = session_guard.uid_mv(&sequence, &imap_to_folder).await;
+            if move_result.is_ok() {
+                debug!("Batch MOVE command succeeded for {} messages", uids.len());
+                return Ok(());
+            }
+            debug!("Batch MOVE command failed, falling back to COPY+DELETE+EXPUNGE.");
+        } else {
+            debug!("Server does not advertise MOVE, using COPY+DELETE+EXPUNGE for {} messages", uids.len());
         }
 
-        debug!("Batch MOVE command failed, falling back to COPY+DELETE+EXPUNGE.");
-
-        session_guard.uid_copy(&sequence, to_folder).await.map_err(|e| ImapError::Other(format!("Failed to copy messages: {}", e)))?;
+        session_guard.uid_copy(&sequence, &imap_to_folder).await.map_err(|e| ImapError::Other(format!("Failed to copy messages: {}", e)))?;
 
         let store_stream = session_guard.uid_store(&sequence, r#"+FLAGS (\Deleted)"#).await.map_err(|e| ImapError::Other(format!("Failed to mark messages as deleted: {}", e)))?;
         store_stream.try_collect::<Vec<_>>().await.map_err(|e| ImapError::Other(format!("Failed to process store results: {}", e)))?;
@@ -474,4 +815,706 @@ impl AsyncImapOps for AsyncImapSessionWrapper {
         debug!("Successfully sent NOOP keepalive command");
         Ok(())
     }
+
+    async fn take_bye(&self) -> Option<String> {
+        let session_guard = self.session.lock().await;
+        let mut bye = None;
+        while let Ok(response) = session_guard.unsolicited_responses.try_recv() {
+            if let UnsolicitedResponse::Other(data) = response {
+                if let Response::Data { status: ResponseStatus::Bye, information, .. } = data.parsed() {
+                    bye = Some(information.as_ref().map(|i| i.to_string()).unwrap_or_default());
+                }
+            }
+        }
+        bye
+    }
+
+    async fn server_capabilities(&self) -> Result<ServerCapabilities, ImapError> {
+        if let Some(capabilities) = self.capabilities() {
+            return Ok(ServerCapabilities::clone(&capabilities));
+        }
+        self.refresh_capabilities().await
+    }
+
+    async fn folder_statuses(&self, folders: &[String]) -> Result<Vec<FolderStatus>, ImapError> {
+        let config = PipelineConfig::from_env();
+        if config.enabled && folders.len() > 1 {
+            self.folder_statuses_pipelined(folders, config.max_in_flight).await
+        } else {
+            self.folder_statuses_sequential(folders).await
+        }
+    }
+
+    async fn idle(&self, folder: &str, timeout: Duration) -> Result<Vec<IdleEvent>, ImapError> {
+        if !self.server_capabilities().await?.supports_idle() {
+            return Err(ImapError::Command("Server does not support IDLE".to_string()));
+        }
+        self.select_folder(folder).await?;
+
+        let session = Arc::clone(&self.session).lock_owned().await;
+        let mut idle = IdleCommand::start(session).await?;
+        let deadline = tokio::time::Instant::now() + timeout;
+        let renew_every = idle_settings::renew_interval();
+        let mut events = std::mem::take(&mut idle.pending);
+
+        while events.is_empty() {
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let renew_at = idle.started + renew_every;
+            if now >= renew_at {
+                debug!("Renewing IDLE on {}", folder);
+                events.extend(idle.renew().await?);
+                continue;
+            }
+
+            // Reading a response is cancel-safe: partial data stays buffered
+            match tokio::time::timeout_at(deadline.min(renew_at), idle.next_event()).await {
+                Ok(Ok(Some(event))) => {
+                    events.push(event);
+                    // Changes tend to arrive in bursts (EXISTS + RECENT, several EXPUNGEs)
+                    while let Ok(Ok(Some(event))) = tokio::time::timeout(IDLE_COALESCE, idle.next_event()).await {
+                        events.push(event);
+                    }
+                }
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        events.extend(idle.done().await?);
+        debug!("IDLE on {} returned {} events", folder, events.len());
+        Ok(events)
+    }
+
+    async fn thread(&self, algorithm: ThreadAlgorithm, criteria: &SearchCriteria) -> Result<Vec<ThreadNode>, ImapError> {
+        let uids = self.search_emails_structured(criteria).await?;
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let messages = self.fetch_thread_messages(&uids).await?;
+        let threads = threading::build_threads(algorithm, &messages);
+        debug!("{} messages form {} threads ({})", messages.len(), threads.len(), algorithm.as_imap());
+        Ok(threads)
+    }
+
+    async fn sort(&self, keys: &[SortKey], criteria: &SearchCriteria) -> Result<Vec<u32>, ImapError> {
+        if keys.is_empty() {
+            return Err(ImapError::InvalidCriteria("At least one sort key is required".to_string()));
+        }
+
+        if self.server_capabilities().await?.has("SORT") {
+            // UTF-8 is the charset every SORT server must accept, but some
+            // reject it anyway; US-ASCII is the only other one guaranteed
+            return match self.uid_sort(keys, "UTF-8", criteria).await {
+                Err(ImapError::BadResponse(reason)) => {
+                    debug!("SORT with UTF-8 refused ({}); retrying with US-ASCII", reason);
+                    self.uid_sort(keys, "US-ASCII", criteria).await
+                }
+                other => other,
+            };
+        }
+
+        debug!("Server lacks SORT; sorting envelopes locally");
+        let uids = self.search_emails_structured(criteria).await?;
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let items = self.fetch_sort_items(&uids).await?;
+        Ok(sort::sort_locally(keys, items))
+    }
+
+    async fn append_with_flags(
+        &self,
+        folder: &str,
+        content: &[u8],
+        flags: &[String],
+        internal_date: Option<DateTime<Utc>>,
+    ) -> Result<Option<u32>, ImapError> {
+        // The literal is sent with run_command_untagged, which takes text.
+        // Messages built for sending are 7-bit; anything else goes through
+        // the plain APPEND, which takes bytes but no flags or date, and has
+        // its flags stored afterwards.
+        let message = match std::str::from_utf8(content) {
+            Ok(message) => message,
+            Err(_) => {
+                warn!("Message for '{}' is not UTF-8; appending it without its date and setting flags afterwards", folder);
+                return self.append_then_flag(folder, content, flags).await;
+            }
+        };
+        let flag_list = flags.iter().filter_map(|f| imap_flag_syntax(f)).collect::<Vec<_>>().join(" ");
+        let date = internal_date
+            .map(|d| format!(" \"{}\"", crate::imap::dates::format_imap_date_time(d)))
+            .unwrap_or_default();
+        let command = format!(
+            "APPEND {} ({}){} {{{}}}",
+            pipeline::quote_mailbox(&utf7::to_imap(folder)),
+            flag_list,
+            date,
+            message.len()
+        );
+
+        let append = async {
+            let mut session_guard = self.session.lock().await;
+            let tag = session_guard.run_command(&command).await.map_err(ImapError::from)?.0;
+            let mut literal_sent = false;
+            loop {
+                let response = match session_guard.read_response().await {
+                    Some(Ok(response)) => response,
+                    Some(Err(e)) => return Err(ImapError::from(e)),
+                    None => return Err(ImapError::Connection("Connection closed while waiting for APPEND".to_string())),
+                };
+                match response.parsed() {
+                    Response::Continue { .. } if !literal_sent => {
+                        // The CRLF after the literal ends the command
+                        session_guard.run_command_untagged(message).await.map_err(ImapError::from)?;
+                        literal_sent = true;
+                    }
+                    Response::Done { tag: done_tag, status, code, information } if done_tag.0 == tag => {
+                        return match (status, code) {
+                            (ResponseStatus::Ok, Some(ResponseCode::AppendUid(_, uids))) => Ok(appended_uid(uids)),
+                            (ResponseStatus::Ok, _) => Ok(None),
+                            (_, Some(ResponseCode::TryCreate)) => Err(ImapError::FolderNotFound(folder.to_string())),
+                            (other, _) => Err(ImapError::Command(format!(
+                                "APPEND to {} failed: {}",
+                                folder,
+                                information.as_ref().map(|i| i.to_string()).unwrap_or_else(|| format!("{:?}", other)),
+                            ))),
+                        };
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        match tokio::time::timeout(self.append_timeout, append).await {
+            Ok(Ok(uid)) => {
+                info!("APPEND to folder '{}' completed (UID {:?})", folder, uid);
+                Ok(uid)
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_elapsed) => Err(ImapError::Timeout(format!("APPEND operation timed out after {:?}.", self.append_timeout))),
+        }
+    }
+
+    async fn namespace(&self) -> Result<Namespaces, ImapError> {
+        if !self.server_capabilities().await?.has("NAMESPACE") {
+            let mut session_guard = self.session.lock().await;
+            let mut root = session_guard.list(Some(""), Some("")).await.map_err(ImapError::from)?;
+            let mut delimiter = None;
+            while let Some(name) = root.try_next().await.map_err(ImapError::from)? {
+                delimiter = delimiter.or_else(|| name.delimiter().map(|d| d.to_string()));
+            }
+            return Ok(Namespaces::from_delimiter(delimiter));
+        }
+
+        // imap-proto has no NAMESPACE grammar; read the raw reply instead
+        let mut session_guard = self.session.lock().await;
+        let response = session_guard.run_command_and_read_response("NAMESPACE").await.map_err(ImapError::from)?;
+        Namespaces::parse(&String::from_utf8_lossy(&response))
+    }
+
+    async fn get_quota(&self, mailbox: &str) -> Result<QuotaInfo, ImapError> {
+        if !self.server_capabilities().await?.has("QUOTA") {
+            return Err(ImapError::Unsupported("Server does not advertise QUOTA".to_string()));
+        }
+
+        let mut session_guard = self.session.lock().await;
+        let command = format!("GETQUOTAROOT {}", pipeline::quote_mailbox(&utf7::to_imap(mailbox)));
+        let tag = session_guard.run_command(&command).await.map_err(ImapError::from)?.0;
+
+        let mut quota = QuotaInfo::default();
+        loop {
+            let response = match session_guard.read_response().await {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => return Err(ImapError::from(e)),
+                None => return Err(ImapError::Connection("Connection closed while waiting for GETQUOTAROOT".to_string())),
+            };
+            match response.parsed() {
+                Response::Done { tag: done_tag, status, information, .. } if done_tag.0 == tag => {
+                    return match status {
+                        ResponseStatus::Ok => {
+                            debug!("Quota for {}: {:?}", mailbox, quota);
+                            Ok(quota)
+                        }
+                        other => Err(ImapError::Command(format!(
+                            "GETQUOTAROOT {} failed: {}",
+                            mailbox,
+                            information.as_ref().map(|i| i.to_string()).unwrap_or_else(|| format!("{:?}", other)),
+                        ))),
+                    };
+                }
+                parsed => quota.record(parsed),
+            }
+        }
+    }
+}
+
+/// How long to keep collecting after the first IDLE event.
+const IDLE_COALESCE: Duration = Duration::from_millis(200);
+
+/// An IDLE command running on a locked session. If it is dropped before
+/// `done()` (the caller's future was cancelled), DONE is sent from a
+/// background task that holds the lock until the server ends the command, so
+/// the next user of the session never sees a half-finished IDLE.
+struct IdleCommand {
+    session: Option<OwnedMutexGuard<TlsImapSession>>,
+    tag: String,
+    started: tokio::time::Instant,
+    /// Events that arrived while starting or renewing
+    pending: Vec<IdleEvent>,
+}
+
+impl IdleCommand {
+    async fn start(mut session: OwnedMutexGuard<TlsImapSession>) -> Result<Self, ImapError> {
+        let (tag, pending) = Self::begin(&mut session).await?;
+        Ok(Self { session: Some(session), tag, started: tokio::time::Instant::now(), pending })
+    }
+
+    /// Send IDLE and wait for the server's continuation.
+    async fn begin(session: &mut TlsImapSession) -> Result<(String, Vec<IdleEvent>), ImapError> {
+        let tag = session.run_command("IDLE").await.map_err(ImapError::from)?.0;
+        let mut pending = Vec::new();
+        loop {
+            let response = match session.read_response().await {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => return Err(ImapError::from(e)),
+                None => return Err(ImapError::Connection("Connection closed while starting IDLE".to_string())),
+            };
+            match response.parsed() {
+                Response::Continue { .. } => return Ok((tag, pending)),
+                Response::Done { tag: done, information, .. } if done.0 == tag => {
+                    return Err(ImapError::Command(format!(
+                        "IDLE rejected: {}",
+                        information.as_ref().map(|i| i.to_string()).unwrap_or_default()
+                    )));
+                }
+                other => pending.extend(IdleEvent::from_response(other)),
+            }
+        }
+    }
+
+    /// Send DONE and read up to the IDLE's tagged completion, keeping any
+    /// events that arrive on the way.
+    async fn finish(session: &mut TlsImapSession, tag: &str) -> Result<Vec<IdleEvent>, ImapError> {
+        session.run_command_untagged("DONE").await.map_err(ImapError::from)?;
+        let mut events = Vec::new();
+        loop {
+            let response = match session.read_response().await {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => return Err(ImapError::from(e)),
+                None => return Err(ImapError::Connection("Connection closed while ending IDLE".to_string())),
+            };
+            match response.parsed() {
+                Response::Done { tag: done, .. } if done.0 == tag => return Ok(events),
+                other => events.extend(IdleEvent::from_response(other)),
+            }
+        }
+    }
+
+    /// Next event, or `None` for untagged data that isn't one. A tagged
+    /// completion means the server ended the IDLE itself.
+    async fn next_event(&mut self) -> Result<Option<IdleEvent>, ImapError> {
+        let session = self.session.as_mut()
+            .ok_or_else(|| ImapError::Command("IDLE already finished".to_string()))?;
+        let response = match session.read_response().await {
+            Some(Ok(response)) => response,
+            Some(Err(e)) => return Err(ImapError::from(e)),
+            None => {
+                self.session = None;
+                return Err(ImapError::Connection("Connection closed during IDLE".to_string()));
+            }
+        };
+        match response.parsed() {
+            Response::Done { tag, information, .. } if tag.0 == self.tag => {
+                // Nothing to send DONE for
+                self.session = None;
+                Err(ImapError::Connection(format!(
+                    "Server ended IDLE: {}",
+                    information.as_ref().map(|i| i.to_string()).unwrap_or_default()
+                )))
+            }
+            other => Ok(IdleEvent::from_response(other)),
+        }
+    }
+
+    /// End the current IDLE and start a new one.
+    async fn renew(&mut self) -> Result<Vec<IdleEvent>, ImapError> {
+        let mut session = self.session.take()
+            .ok_or_else(|| ImapError::Command("IDLE already finished".to_string()))?;
+        let mut events = Self::finish(&mut session, &self.tag).await?;
+        let (tag, pending) = Self::begin(&mut session).await?;
+        events.extend(pending);
+        self.tag = tag;
+        self.started = tokio::time::Instant::now();
+        self.session = Some(session);
+        Ok(events)
+    }
+
+    async fn done(mut self) -> Result<Vec<IdleEvent>, ImapError> {
+        match self.session.take() {
+            Some(mut session) => Self::finish(&mut session, &self.tag).await,
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+impl Drop for IdleCommand {
+    fn drop(&mut self) {
+        let Some(mut session) = self.session.take() else {
+            return;
+        };
+        let tag = std::mem::take(&mut self.tag);
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(async move {
+                    if let Err(e) = IdleCommand::finish(&mut session, &tag).await {
+                        warn!("Failed to end cancelled IDLE cleanly: {}", e);
+                    }
+                });
+            }
+            Err(_) => warn!("IDLE dropped outside a runtime; session left idling"),
+        }
+    }
+}
+
+impl AsyncImapSessionWrapper {
+    /// Plain APPEND, then STORE `flags` on the message it created, found as
+    /// the only one at or above the folder's UIDNEXT from before the APPEND.
+    /// Leaves the previously selected folder selected again.
+    async fn append_then_flag(&self, folder: &str, content: &[u8], flags: &[String]) -> Result<Option<u32>, ImapError> {
+        let uid_next = self.folder_statuses(&[folder.to_string()]).await?
+            .first()
+            .and_then(|status| status.uid_next);
+        self.append(folder, content, flags).await?;
+        let Some(uid_next) = uid_next else {
+            warn!("'{}' reported no UIDNEXT; the appended message was left without flags", folder);
+            return Ok(None);
+        };
+
+        let previous = self.current_folder().await;
+        self.ensure_folder_selected(folder).await?;
+        // "n:*" always matches the highest UID, even when it is below n
+        let appended: Vec<u32> = self.search_emails(&format!("UID {}:*", uid_next)).await?
+            .into_iter()
+            .filter(|uid| *uid >= uid_next)
+            .collect();
+        let uid = match appended.as_slice() {
+            [uid] => {
+                if !flags.is_empty() {
+                    self.store_flags(&[*uid], FlagOperation::Add, flags).await?;
+                }
+                Some(*uid)
+            }
+            _ => {
+                warn!("Cannot tell which of {:?} in '{}' was just appended; left without flags", appended, folder);
+                None
+            }
+        };
+        if let Some(previous) = previous.filter(|previous| previous != folder) {
+            self.ensure_folder_selected(&previous).await?;
+        }
+        Ok(uid)
+    }
+
+    /// UID EXPUNGE (UIDPLUS): permanently remove just `uids` from the
+    /// selected folder, leaving other messages flagged \Deleted alone.
+    pub async fn uid_expunge(&self, uids: &[u32]) -> Result<(), ImapError> {
+        if uids.is_empty() {
+            return Ok(());
+        }
+        let mut session_guard = self.session.lock().await;
+        self.upgrade_to_read_write(&mut session_guard).await?;
+        let stream = session_guard.uid_expunge(uid_set(uids)).await?;
+        stream.try_collect::<Vec<_>>().await.map(|_| ()).map_err(ImapError::from)
+    }
+
+    /// UID COPY `uids` from the selected folder to `to_folder`, returning the
+    /// target's UIDVALIDITY and the new UIDs when the server answers with
+    /// COPYUID (UIDPLUS). async-imap's `uid_copy` drops the response code, so
+    /// the command is issued directly.
+    pub async fn uid_copy_with_uids(&self, uids: &[u32], to_folder: &str) -> Result<Option<(u32, Vec<CopiedUid>)>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let command = format!("UID COPY {} {}", uid_set(uids), pipeline::quote_mailbox(&utf7::to_imap(to_folder)));
+        let tag = session_guard.run_command(&command).await.map_err(ImapError::from)?.0;
+
+        loop {
+            let response = match session_guard.read_response().await {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => return Err(ImapError::from(e)),
+                None => return Err(ImapError::Connection("Connection closed while waiting for UID COPY".to_string())),
+            };
+            if let Response::Done { tag: done_tag, status, code, information } = response.parsed() {
+                if done_tag.0 != tag {
+                    continue;
+                }
+                return match status {
+                    ResponseStatus::Ok => Ok(match code {
+                        Some(ResponseCode::CopyUid(uid_validity, source, target)) => {
+                            Some((*uid_validity, copyuid_pairs(source, target)))
+                        }
+                        _ => None,
+                    }),
+                    other => Err(ImapError::Other(format!(
+                        "Failed to copy messages: {}",
+                        information.as_ref().map(|i| i.to_string()).unwrap_or_else(|| format!("{:?}", other)),
+                    ))),
+                };
+            }
+        }
+    }
+
+    /// UID and flags of every message in the selected folder whose MODSEQ is
+    /// above `modseq` (RFC 7162 CHANGEDSINCE). The command is issued
+    /// directly so the MODSEQ of each response can be read.
+    pub async fn fetch_changed_since(&self, modseq: u64) -> Result<ChangedSince, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let command = format!("UID FETCH 1:* (UID FLAGS) (CHANGEDSINCE {})", modseq);
+        let tag = session_guard.run_command(&command).await.map_err(ImapError::from)?.0;
+
+        let mut changed = ChangedSince::default();
+        loop {
+            let response = match session_guard.read_response().await {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => return Err(ImapError::from(e)),
+                None => return Err(ImapError::Connection("Connection closed while waiting for CHANGEDSINCE fetch".to_string())),
+            };
+            match response.parsed() {
+                Response::Done { tag: done_tag, status, information, .. } if done_tag.0 == tag => {
+                    return match status {
+                        ResponseStatus::Ok => Ok(changed),
+                        other => Err(ImapError::Fetch(format!(
+                            "CHANGEDSINCE {} failed: {}",
+                            modseq,
+                            information.as_ref().map(|i| i.to_string()).unwrap_or_else(|| format!("{:?}", other)),
+                        ))),
+                    };
+                }
+                parsed => changed.record(parsed),
+            }
+        }
+    }
+
+    /// Up to `length` bytes of message `uid` in the selected folder, starting
+    /// at `offset`, from the whole message or from `section` (already checked
+    /// with `types::body_section`). Issued directly because async-imap's
+    /// `Fetch` accessors ignore partial (`<offset>`) body sections.
+    pub async fn fetch_partial(&self, uid: u32, section: Option<&str>, offset: u32, length: u32) -> Result<PartialBody, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let command = format!(
+            "UID FETCH {} (UID RFC822.SIZE BODY.PEEK[{}]<{}.{}>)",
+            uid,
+            section.unwrap_or(""),
+            offset,
+            length.saturating_add(1),
+        );
+        let tag = session_guard.run_command(&command).await.map_err(ImapError::from)?.0;
+
+        let mut partial = PartialBody::new(uid, section.map(str::to_string), offset);
+        loop {
+            let response = match session_guard.read_response().await {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => return Err(ImapError::from(e)),
+                None => return Err(ImapError::Connection("Connection closed while waiting for partial fetch".to_string())),
+            };
+            match response.parsed() {
+                Response::Done { tag: done_tag, status, information, .. } if done_tag.0 == tag => {
+                    return match status {
+                        // RFC822.SIZE comes with every FETCH of the message
+                        ResponseStatus::Ok if partial.message_size.is_some() => Ok(partial),
+                        ResponseStatus::Ok => Err(ImapError::EmailNotFound(vec![uid])),
+                        other => Err(ImapError::Fetch(format!(
+                            "Partial fetch of UID {} failed: {}",
+                            uid,
+                            information.as_ref().map(|i| i.to_string()).unwrap_or_else(|| format!("{:?}", other)),
+                        ))),
+                    };
+                }
+                parsed => partial.record(parsed, length),
+            }
+        }
+    }
+
+    /// Issue UID SORT with `charset`. A BAD reply, or NO [BADCHARSET], comes
+    /// back as `ImapError::BadResponse` so the caller can retry with another
+    /// charset.
+    async fn uid_sort(&self, keys: &[SortKey], charset: &str, criteria: &SearchCriteria) -> Result<Vec<u32>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let command = sort::sort_command(keys, charset, criteria);
+        debug!("Executing {}", command);
+        let tag = session_guard.run_command(&command).await.map_err(ImapError::from)?.0;
+
+        let mut uids = Vec::new();
+        loop {
+            let response = match session_guard.read_response().await {
+                Some(Ok(response)) => response,
+                Some(Err(e)) => return Err(ImapError::from(e)),
+                None => return Err(ImapError::Connection("Connection closed while waiting for SORT".to_string())),
+            };
+            match response.parsed() {
+                Response::MailboxData(MailboxDatum::Sort(sorted)) => uids.extend(sorted.iter().copied()),
+                Response::Done { tag: done_tag, status, code, information } if done_tag.0 == tag => {
+                    let reason = information.as_ref().map(|i| i.to_string()).unwrap_or_else(|| format!("{:?}", status));
+                    return match (status, code) {
+                        (ResponseStatus::Ok, _) => Ok(uids),
+                        (ResponseStatus::Bad, _) | (_, Some(ResponseCode::BadCharset(_))) => Err(ImapError::BadResponse(reason)),
+                        _ => Err(ImapError::Command(format!("SORT failed: {}", reason))),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Envelope, internal date and size of `uids`, for sorting without SORT.
+    async fn fetch_sort_items(&self, uids: &[u32]) -> Result<Vec<SortItem>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let mut fetch_stream = session_guard
+            .uid_fetch(uid_set(uids), "(UID ENVELOPE INTERNALDATE RFC822.SIZE)")
+            .await
+            .map_err(ImapError::from)?;
+        let mut items = Vec::with_capacity(uids.len());
+        while let Some(fetch) = fetch_stream.try_next().await.map_err(ImapError::from)? {
+            let Some(uid) = fetch.uid else {
+                continue;
+            };
+            let email = Email::from_fetch(&fetch)?;
+            let envelope = email.envelope.as_ref();
+            items.push(SortItem {
+                uid,
+                arrival: email.internal_date,
+                date: envelope
+                    .and_then(|e| e.date.as_deref())
+                    .and_then(|d| chrono::DateTime::parse_from_rfc2822(d).ok())
+                    .map(|d| d.with_timezone(&chrono::Utc)),
+                from: envelope.and_then(|e| e.from.first()).and_then(|a| a.mailbox.clone()),
+                subject: envelope.and_then(|e| e.subject.clone()),
+                size: fetch.size,
+            });
+        }
+        Ok(items)
+    }
+
+    /// The headers threading needs for `uids`: envelope, internal date and
+    /// the References field.
+    async fn fetch_thread_messages(&self, uids: &[u32]) -> Result<Vec<ThreadMessage>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let mut fetch_stream = session_guard
+            .uid_fetch(uid_set(uids), "(UID ENVELOPE INTERNALDATE BODY.PEEK[HEADER.FIELDS (REFERENCES)])")
+            .await
+            .map_err(ImapError::from)?;
+        let mut messages = Vec::with_capacity(uids.len());
+        while let Some(fetch) = fetch_stream.try_next().await.map_err(ImapError::from)? {
+            let Some(uid) = fetch.uid else {
+                continue;
+            };
+            let email = Email::from_fetch(&fetch)?;
+            let envelope = email.envelope.as_ref();
+            let sent = envelope
+                .and_then(|e| e.date.as_deref())
+                .and_then(|d| chrono::DateTime::parse_from_rfc2822(d).ok())
+                .map(|d| d.with_timezone(&chrono::Utc));
+            messages.push(ThreadMessage {
+                uid,
+                message_id: envelope.and_then(|e| e.message_id.clone()),
+                in_reply_to: envelope.and_then(|e| e.in_reply_to.clone()),
+                references: fetch.header().map(threading::references_from_header).unwrap_or_default(),
+                subject: envelope.and_then(|e| e.subject.clone()),
+                date: sent.or(email.internal_date),
+            });
+        }
+        Ok(messages)
+    }
+
+    /// STATUS each folder in turn, waiting for every response before sending
+    /// the next command (one round-trip per folder).
+    pub async fn folder_statuses_sequential(&self, folders: &[String]) -> Result<Vec<FolderStatus>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let mut results = Vec::with_capacity(folders.len());
+        for folder in folders {
+            match session_guard.status(&utf7::to_imap(folder), pipeline::STATUS_ITEMS).await {
+                Ok(mailbox) => results.push(FolderStatus {
+                    name: folder.clone(),
+                    messages: Some(mailbox.exists),
+                    recent: Some(mailbox.recent),
+                    unseen: mailbox.unseen,
+                    uid_next: mailbox.uid_next,
+                    uid_validity: mailbox.uid_validity,
+                    error: None,
+                }),
+                Err(async_imap::error::Error::No(msg)) | Err(async_imap::error::Error::Bad(msg)) => {
+                    results.push(FolderStatus::failed(folder, msg));
+                }
+                Err(e) => return Err(ImapError::from(e)),
+            }
+        }
+        Ok(results)
+    }
+
+    /// STATUS folders by sending up to `max_in_flight` commands before reading
+    /// their responses. Untagged STATUS replies are matched to folders by
+    /// mailbox name; the tagged completion carries per-folder errors.
+    pub async fn folder_statuses_pipelined(&self, folders: &[String], max_in_flight: usize) -> Result<Vec<FolderStatus>, ImapError> {
+        let mut session_guard = self.session.lock().await;
+        let mut results = Vec::with_capacity(folders.len());
+
+        for window in folders.chunks(max_in_flight.max(1)) {
+            let mut in_flight = Vec::with_capacity(window.len());
+            for folder in window {
+                let command = pipeline::status_command(&utf7::to_imap(folder));
+                if !pipeline::is_pipelineable(&command) {
+                    return Err(ImapError::Command(format!("Refusing to pipeline non-allowlisted command: {}", command)));
+                }
+                let tag = session_guard.run_command(&command).await.map_err(ImapError::from)?;
+                in_flight.push((tag.0, folder));
+            }
+            debug!("Pipelined {} STATUS commands", in_flight.len());
+
+            let mut untagged: Vec<(String, Vec<StatusAttribute>)> = Vec::with_capacity(window.len());
+            let mut completions: std::collections::HashMap<String, Result<(), String>> = std::collections::HashMap::new();
+            while completions.len() < in_flight.len() {
+                let response = match session_guard.read_response().await {
+                    Some(Ok(response)) => response,
+                    Some(Err(e)) => return Err(ImapError::from(e)),
+                    None => return Err(ImapError::Connection("Connection closed while reading pipelined responses".to_string())),
+                };
+                match response.parsed() {
+                    Response::MailboxData(MailboxDatum::Status { mailbox, status }) => {
+                        untagged.push((utf7::from_imap(mailbox), status.clone()));
+                    }
+                    Response::Done { tag, status, information, .. } => {
+                        let outcome = match status {
+                            ResponseStatus::Ok => Ok(()),
+                            other => Err(information.as_ref()
+                                .map(|i| i.to_string())
+                                .unwrap_or_else(|| format!("{:?}", other))),
+                        };
+                        completions.insert(tag.0.clone(), outcome);
+                    }
+                    // EXISTS/EXPUNGE and other unsolicited data are not ours to consume
+                    _ => {}
+                }
+            }
+
+            for (tag, folder) in in_flight {
+                match completions.remove(&tag) {
+                    Some(Ok(())) => {
+                        match untagged.iter().position(|(name, _)| pipeline::mailbox_matches(folder, name)) {
+                            Some(idx) => {
+                                let (_, attributes) = untagged.swap_remove(idx);
+                                results.push(FolderStatus::from_attributes(folder, &attributes));
+                            }
+                            None => results.push(FolderStatus::failed(folder, "Server returned no STATUS data".to_string())),
+                        }
+                    }
+                    Some(Err(msg)) => results.push(FolderStatus::failed(folder, msg)),
+                    None => results.push(FolderStatus::failed(folder, "No tagged response".to_string())),
+                }
+            }
+        }
+
+        Ok(results)
+    }
 }
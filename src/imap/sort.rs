@@ -0,0 +1,201 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Server-side ordering of a folder's messages with the IMAP SORT extension
+//! (RFC 5256), and the same ordering computed locally for servers without it.
+//!
+//! Sort keys are written as `date`, `-date` (reverse) or `reverse date`;
+//! several keys are separated by commas and later keys break ties.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::dashboard::services::threads::normalize_subject;
+use crate::imap::types::SearchCriteria;
+
+/// A message property SORT can order by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    /// Internal date, when the server received the message
+    Arrival,
+    /// Date header, falling back to the internal date
+    Date,
+    /// Mailbox part of the first From address
+    From,
+    /// Subject with reply/forward prefixes removed
+    Subject,
+    /// RFC822.SIZE
+    Size,
+}
+
+impl SortField {
+    pub fn as_imap(&self) -> &'static str {
+        match self {
+            SortField::Arrival => "ARRIVAL",
+            SortField::Date => "DATE",
+            SortField::From => "FROM",
+            SortField::Subject => "SUBJECT",
+            SortField::Size => "SIZE",
+        }
+    }
+}
+
+/// One sort key, ascending unless `reverse`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SortKey {
+    pub field: SortField,
+    pub reverse: bool,
+}
+
+impl SortKey {
+    pub fn ascending(field: SortField) -> Self {
+        Self { field, reverse: false }
+    }
+
+    pub fn descending(field: SortField) -> Self {
+        Self { field, reverse: true }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.reverse {
+            write!(f, "REVERSE ")?;
+        }
+        write!(f, "{}", self.field.as_imap())
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let (reverse, name) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest.trim()),
+            None => match s.strip_prefix("reverse ") {
+                Some(rest) => (true, rest.trim()),
+                None => (false, s.as_str()),
+            },
+        };
+        let field = match name {
+            "arrival" => SortField::Arrival,
+            "date" => SortField::Date,
+            "from" => SortField::From,
+            "subject" => SortField::Subject,
+            "size" => SortField::Size,
+            other => return Err(format!("Unknown sort key '{}' (expected arrival, date, from, subject or size)", other)),
+        };
+        Ok(Self { field, reverse })
+    }
+}
+
+/// Parse a comma-separated list of sort keys, e.g. `-date,subject`.
+pub fn parse_sort_keys(spec: &str) -> Result<Vec<SortKey>, String> {
+    let keys = spec.split(',')
+        .filter(|k| !k.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<SortKey>, String>>()?;
+    if keys.is_empty() {
+        return Err("At least one sort key is required".to_string());
+    }
+    Ok(keys)
+}
+
+/// `UID SORT (<keys>) <charset> <criteria>`
+pub fn sort_command(keys: &[SortKey], charset: &str, criteria: &SearchCriteria) -> String {
+    let keys = keys.iter().map(SortKey::to_string).collect::<Vec<_>>().join(" ");
+    format!("UID SORT ({}) {} {}", keys, charset, criteria)
+}
+
+/// What the local fallback knows about one message.
+#[derive(Debug, Clone, Default)]
+pub struct SortItem {
+    pub uid: u32,
+    pub arrival: Option<DateTime<Utc>>,
+    pub date: Option<DateTime<Utc>>,
+    pub from: Option<String>,
+    pub subject: Option<String>,
+    pub size: Option<u32>,
+}
+
+impl SortItem {
+    fn compare(&self, other: &Self, field: SortField) -> Ordering {
+        match field {
+            SortField::Arrival => self.arrival.cmp(&other.arrival),
+            SortField::Date => self.date.or(self.arrival).cmp(&other.date.or(other.arrival)),
+            SortField::From => {
+                let key = |item: &Self| item.from.as_deref().unwrap_or("").to_lowercase();
+                key(self).cmp(&key(other))
+            }
+            SortField::Subject => {
+                let key = |item: &Self| normalize_subject(item.subject.as_deref().unwrap_or(""));
+                key(self).cmp(&key(other))
+            }
+            SortField::Size => self.size.cmp(&other.size),
+        }
+    }
+}
+
+/// Order `items` as SORT would and return their UIDs. Messages equal on
+/// every key keep UID order, as the server uses sequence order for ties.
+pub fn sort_locally(keys: &[SortKey], mut items: Vec<SortItem>) -> Vec<u32> {
+    items.sort_by(|a, b| {
+        keys.iter()
+            .map(|key| {
+                let ordering = a.compare(b, key.field);
+                if key.reverse { ordering.reverse() } else { ordering }
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| a.uid.cmp(&b.uid))
+    });
+    items.into_iter().map(|item| item.uid).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn item(uid: u32, day: u32, from: &str, subject: &str, size: u32) -> SortItem {
+        SortItem {
+            uid,
+            arrival: Some(Utc.with_ymd_and_hms(2025, 5, day, 12, 0, 0).unwrap()),
+            date: None,
+            from: Some(from.to_string()),
+            subject: Some(subject.to_string()),
+            size: Some(size),
+        }
+    }
+
+    #[test]
+    fn test_sort_keys_and_local_sort() {
+        let keys = parse_sort_keys("-date, subject").unwrap();
+        assert_eq!(keys, vec![SortKey::descending(SortField::Date), SortKey::ascending(SortField::Subject)]);
+        assert_eq!(
+            sort_command(&keys, "UTF-8", &SearchCriteria::All),
+            "UID SORT (REVERSE DATE SUBJECT) UTF-8 ALL"
+        );
+        assert_eq!("reverse Size".parse::<SortKey>(), Ok(SortKey::descending(SortField::Size)));
+        assert!(parse_sort_keys("date,colour").is_err());
+        assert!(parse_sort_keys(" , ").is_err());
+
+        let items = vec![
+            item(1, 3, "carol", "Re: Zebra", 500),
+            item(2, 1, "Alice", "apple", 100),
+            item(3, 3, "bob", "Mango", 300),
+            item(4, 2, "alice", "Fwd: banana", 300),
+        ];
+        assert_eq!(sort_locally(&keys, items.clone()), vec![3, 1, 4, 2]);
+        assert_eq!(sort_locally(&[SortKey::ascending(SortField::Subject)], items.clone()), vec![2, 4, 3, 1]);
+        assert_eq!(sort_locally(&[SortKey::ascending(SortField::From)], items.clone()), vec![2, 4, 3, 1]);
+        assert_eq!(sort_locally(&[SortKey::descending(SortField::Size)], items), vec![1, 3, 4, 2]);
+    }
+}
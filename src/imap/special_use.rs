@@ -0,0 +1,221 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Which physical folder holds an account's Sent, Drafts, Trash, Junk and
+//! Archive mail.
+//!
+//! Folders are matched by their RFC 6154 LIST attributes first and by the
+//! provider's folder names after that (see `ProviderProfile`), so Gmail's
+//! `[Gmail]/Trash` and Dovecot's `INBOX.Trash` both resolve as Trash. The
+//! map is kept per account for `IMAP_SPECIAL_USE_TTL_SECONDS` (default 3600)
+//! so senders and tools don't LIST on every call.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::imap::client::ImapClient;
+use crate::imap::error::ImapError;
+use crate::imap::provider_profile::ProviderProfile;
+use crate::imap::session::AsyncImapSessionWrapper;
+use crate::imap::types::{normalize_role, FolderState};
+
+/// A special-use role a folder can have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpecialUse {
+    Sent,
+    Drafts,
+    Trash,
+    Junk,
+    Archive,
+}
+
+impl SpecialUse {
+    pub const ALL: [SpecialUse; 5] = [
+        SpecialUse::Sent,
+        SpecialUse::Drafts,
+        SpecialUse::Trash,
+        SpecialUse::Junk,
+        SpecialUse::Archive,
+    ];
+
+    /// Role name as used by `find_special_use` ("trash").
+    pub fn role(&self) -> &'static str {
+        match self {
+            SpecialUse::Sent => "sent",
+            SpecialUse::Drafts => "drafts",
+            SpecialUse::Trash => "trash",
+            SpecialUse::Junk => "junk",
+            SpecialUse::Archive => "archive",
+        }
+    }
+
+    /// A folder argument written as a LIST attribute (`\Trash`, `\Junk`)
+    /// names the folder with that role rather than a literal folder.
+    pub fn from_folder_alias(folder: &str) -> Option<Self> {
+        folder.trim().strip_prefix('\\').and_then(|role| role.parse().ok())
+    }
+}
+
+impl fmt::Display for SpecialUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.role())
+    }
+}
+
+impl FromStr for SpecialUse {
+    type Err = String;
+
+    /// Accepts the role ("trash"), the attribute (`\Trash`) and "spam".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let role = normalize_role(s);
+        SpecialUse::ALL.into_iter()
+            .find(|use_| Some(use_.role()) == role)
+            .ok_or_else(|| format!("Unknown special-use folder '{}' (expected sent, drafts, trash, junk or archive)", s))
+    }
+}
+
+/// One LIST entry with its attributes in wire form (`\HasNoChildren`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FolderAttributes {
+    pub name: String,
+    pub delimiter: Option<String>,
+    pub attributes: Vec<String>,
+    pub selectable: bool,
+    /// Role from the attributes alone; name-based matches are not shown here
+    pub special_use: Option<SpecialUse>,
+}
+
+impl From<&FolderState> for FolderAttributes {
+    fn from(folder: &FolderState) -> Self {
+        Self {
+            name: folder.name.clone(),
+            delimiter: folder.delimiter.clone(),
+            attributes: folder.attributes.iter().map(|a| wire_attribute(a)).collect(),
+            selectable: folder.selectable,
+            special_use: folder.special_use().and_then(|role| role.parse().ok()),
+        }
+    }
+}
+
+/// LIST attribute as the server sent it, from the Debug form it is stored in
+/// (`NoSelect` → `\Noselect`, `Extension("\\HasChildren")` → `\HasChildren`).
+pub fn wire_attribute(stored: &str) -> String {
+    if let Some(inner) = stored.strip_prefix("Extension(").and_then(|s| s.strip_suffix(')')) {
+        return inner.trim_matches('"').replace("\\\\", "\\");
+    }
+    match stored {
+        "NoSelect" => "\\Noselect".to_string(),
+        "NoInferiors" => "\\Noinferiors".to_string(),
+        other if other.starts_with('\\') => other.to_string(),
+        other => format!("\\{}", other),
+    }
+}
+
+/// Folder for each role that resolves in `folders`.
+pub fn special_use_map(profile: &ProviderProfile, folders: &[FolderState]) -> HashMap<SpecialUse, String> {
+    SpecialUse::ALL.into_iter()
+        .filter_map(|use_| profile.find_special_use(folders, use_.role()).map(|f| (use_, f.name.clone())))
+        .collect()
+}
+
+struct Entry {
+    folders: HashMap<SpecialUse, String>,
+    resolved_at: Instant,
+}
+
+lazy_static! {
+    static ref ACCOUNT_MAPS: DashMap<String, Entry> = DashMap::new();
+}
+
+fn ttl() -> Duration {
+    Duration::from_secs(
+        std::env::var("IMAP_SPECIAL_USE_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600),
+    )
+}
+
+/// The account's map if it was resolved recently.
+pub fn cached(account: &str) -> Option<HashMap<SpecialUse, String>> {
+    ACCOUNT_MAPS.get(account)
+        .filter(|entry| entry.resolved_at.elapsed() < ttl())
+        .map(|entry| entry.folders.clone())
+}
+
+/// Forget an account's map, e.g. after its folders were renamed or deleted.
+pub fn invalidate(account: &str) {
+    ACCOUNT_MAPS.remove(account);
+}
+
+/// The account's map, from the per-account store or else from a fresh LIST
+/// on `client`.
+pub async fn resolve(
+    client: &ImapClient<AsyncImapSessionWrapper>,
+    account: &str,
+    profile: &ProviderProfile,
+) -> Result<HashMap<SpecialUse, String>, ImapError> {
+    if let Some(folders) = cached(account) {
+        return Ok(folders);
+    }
+    let folders = client.special_use_map(profile).await?;
+    debug!("Special-use folders for {}: {:?}", account, folders);
+    ACCOUNT_MAPS.insert(account.to_string(), Entry {
+        folders: folders.clone(),
+        resolved_at: Instant::now(),
+    });
+    Ok(folders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str, attributes: &[&str]) -> FolderState {
+        FolderState {
+            name: name.to_string(),
+            delimiter: Some("/".to_string()),
+            selectable: true,
+            subscribed: true,
+            attributes: attributes.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_special_use_map_and_aliases() {
+        let folders = vec![
+            state("INBOX", &[]),
+            state("[Gmail]/Bin", &["HasNoChildren", "Trash"]),
+            state("[Gmail]/Sent Mail", &[r#"Extension("\\Sent")"#]),
+            state("[Gmail]/Spam", &[]),
+            state("[Gmail]", &["NoSelect"]),
+        ];
+        let gmail = ProviderProfile::detect(None, "imap.gmail.com");
+        let map = special_use_map(gmail, &folders);
+        assert_eq!(map.get(&SpecialUse::Trash).map(String::as_str), Some("[Gmail]/Bin"));
+        assert_eq!(map.get(&SpecialUse::Sent).map(String::as_str), Some("[Gmail]/Sent Mail"));
+        assert_eq!(map.get(&SpecialUse::Junk).map(String::as_str), Some("[Gmail]/Spam"));
+        assert!(!map.contains_key(&SpecialUse::Drafts));
+
+        let listed = FolderAttributes::from(&folders[2]);
+        assert_eq!(listed.attributes, vec!["\\Sent".to_string()]);
+        assert_eq!(listed.special_use, Some(SpecialUse::Sent));
+        assert_eq!(wire_attribute("NoSelect"), "\\Noselect");
+        assert_eq!(wire_attribute("HasNoChildren"), "\\HasNoChildren");
+
+        assert_eq!(SpecialUse::from_folder_alias("\\Trash"), Some(SpecialUse::Trash));
+        assert_eq!(SpecialUse::from_folder_alias("\\spam"), Some(SpecialUse::Junk));
+        assert_eq!(SpecialUse::from_folder_alias("Trash"), None);
+        assert!("inbox".parse::<SpecialUse>().is_err());
+    }
+}
@@ -0,0 +1,289 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversation trees for a folder, following the algorithms of the IMAP
+//! THREAD extension (RFC 5256).
+//!
+//! imap-proto has no parser for untagged `* THREAD` responses; one arriving
+//! on the stream fails to parse and leaves the session unusable. Threads are
+//! therefore always built here, from the same Message-ID, In-Reply-To,
+//! References, subject and date the server would use, whether or not the
+//! server advertises `THREAD=REFERENCES` or `THREAD=ORDEREDSUBJECT`.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::dashboard::services::threads::normalize_subject;
+
+/// How messages are linked into threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadAlgorithm {
+    /// Parent/child links from In-Reply-To and References; threads whose
+    /// roots share a base subject are merged
+    References,
+    /// Messages grouped by base subject only, oldest first, replies flat
+    /// under the first message
+    OrderedSubject,
+}
+
+impl ThreadAlgorithm {
+    /// Name as used in the THREAD capability and command.
+    pub fn as_imap(&self) -> &'static str {
+        match self {
+            ThreadAlgorithm::References => "REFERENCES",
+            ThreadAlgorithm::OrderedSubject => "ORDEREDSUBJECT",
+        }
+    }
+}
+
+impl FromStr for ThreadAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace(['_', '-'], "").as_str() {
+            "references" => Ok(ThreadAlgorithm::References),
+            "orderedsubject" => Ok(ThreadAlgorithm::OrderedSubject),
+            other => Err(format!("Unknown thread algorithm '{}' (expected references or orderedsubject)", other)),
+        }
+    }
+}
+
+/// One message in a conversation tree; replies are its children, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadNode {
+    pub uid: u32,
+    pub children: Vec<ThreadNode>,
+}
+
+impl ThreadNode {
+    /// UIDs of this node and everything below it, depth first.
+    pub fn uids(&self) -> Vec<u32> {
+        let mut uids = vec![self.uid];
+        for child in &self.children {
+            uids.extend(child.uids());
+        }
+        uids
+    }
+
+    pub fn contains(&self, uid: u32) -> bool {
+        self.uid == uid || self.children.iter().any(|child| child.contains(uid))
+    }
+}
+
+/// The headers threading looks at, for one message.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadMessage {
+    pub uid: u32,
+    pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    /// Message-IDs from the References header, oldest ancestor first
+    pub references: Vec<String>,
+    pub subject: Option<String>,
+    /// Sent date, or the internal date when the Date header is missing
+    pub date: Option<DateTime<Utc>>,
+}
+
+fn normalize_id(id: &str) -> &str {
+    id.trim().trim_matches(|c| c == '<' || c == '>')
+}
+
+/// Message-IDs in a References or In-Reply-To header value.
+pub fn parse_message_ids(header: &str) -> Vec<String> {
+    header.split(|c: char| c.is_whitespace() || c == ',')
+        .map(normalize_id)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Message-IDs of the References field in a raw header block, such as the
+/// answer to `BODY.PEEK[HEADER.FIELDS (REFERENCES)]`.
+pub fn references_from_header(raw: &[u8]) -> Vec<String> {
+    let text = String::from_utf8_lossy(raw);
+    let mut value = String::new();
+    let mut in_references = false;
+    for line in text.lines() {
+        if line.starts_with([' ', '\t']) {
+            if in_references {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        in_references = false;
+        if let Some((name, rest)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("references") {
+                in_references = true;
+                value.push_str(rest.trim());
+            }
+        }
+    }
+    parse_message_ids(&value)
+}
+
+/// Build conversation trees from `messages`. Threads are ordered by the date
+/// of their first message, as the THREAD command orders them.
+pub fn build_threads(algorithm: ThreadAlgorithm, messages: &[ThreadMessage]) -> Vec<ThreadNode> {
+    build_threads_where(algorithm, messages, |_, _| true)
+}
+
+/// `build_threads`, except that with `References` a root joins an earlier
+/// root with the same base subject only when `same_conversation(earlier,
+/// root)` agrees. Both are indices into `messages`.
+pub fn build_threads_where(
+    algorithm: ThreadAlgorithm,
+    messages: &[ThreadMessage],
+    same_conversation: impl Fn(usize, usize) -> bool,
+) -> Vec<ThreadNode> {
+    let order = |i: usize| (messages[i].date, messages[i].uid);
+    let mut parent: Vec<Option<usize>> = vec![None; messages.len()];
+
+    match algorithm {
+        ThreadAlgorithm::References => {
+            let mut by_id: HashMap<&str, usize> = HashMap::new();
+            for (i, message) in messages.iter().enumerate() {
+                if let Some(id) = message.message_id.as_deref().map(normalize_id).filter(|id| !id.is_empty()) {
+                    // A duplicate Message-ID keeps its first message
+                    by_id.entry(id).or_insert(i);
+                }
+            }
+
+            for (i, message) in messages.iter().enumerate() {
+                // Nearest ancestor present in the folder; In-Reply-To counts as
+                // the last reference when References is missing or shorter
+                let ancestors = message.references.iter().map(String::as_str)
+                    .chain(message.in_reply_to.as_deref().map(normalize_id))
+                    .collect::<Vec<_>>();
+                parent[i] = ancestors.iter().rev()
+                    .filter_map(|id| by_id.get(id).copied())
+                    .find(|&p| p != i && !is_ancestor(&parent, i, p));
+            }
+
+            // Roots that share a base subject are one conversation whose
+            // parent messages were never seen; hang them under the oldest
+            // that `same_conversation` accepts
+            let mut roots_by_subject: HashMap<String, Vec<usize>> = HashMap::new();
+            let mut roots: Vec<usize> = (0..messages.len()).filter(|&i| parent[i].is_none()).collect();
+            roots.sort_by_key(|&i| order(i));
+            for i in roots {
+                let subject = normalize_subject(messages[i].subject.as_deref().unwrap_or(""));
+                if subject.is_empty() {
+                    continue;
+                }
+                let earlier = roots_by_subject.entry(subject).or_default();
+                match earlier.iter().copied().find(|&first| same_conversation(first, i)) {
+                    Some(first) => parent[i] = Some(first),
+                    None => earlier.push(i),
+                }
+            }
+        }
+        ThreadAlgorithm::OrderedSubject => {
+            let mut sorted: Vec<usize> = (0..messages.len()).collect();
+            sorted.sort_by_key(|&i| order(i));
+            let mut first_by_subject: HashMap<String, usize> = HashMap::new();
+            for i in sorted {
+                let subject = normalize_subject(messages[i].subject.as_deref().unwrap_or(""));
+                match first_by_subject.get(&subject) {
+                    Some(&first) => parent[i] = Some(first),
+                    None => {
+                        first_by_subject.insert(subject, i);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, p) in parent.iter().enumerate() {
+        match *p {
+            Some(p) => children.entry(p).or_default().push(i),
+            None => roots.push(i),
+        }
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|&i| order(i));
+    }
+    roots.sort_by_key(|&i| order(i));
+
+    fn node(i: usize, messages: &[ThreadMessage], children: &HashMap<usize, Vec<usize>>) -> ThreadNode {
+        ThreadNode {
+            uid: messages[i].uid,
+            children: children.get(&i).into_iter().flatten()
+                .map(|&c| node(c, messages, children))
+                .collect(),
+        }
+    }
+    roots.into_iter().map(|i| node(i, messages, &children)).collect()
+}
+
+/// Whether `candidate` already descends from `i`, so linking `i` under it
+/// would close a loop.
+fn is_ancestor(parent: &[Option<usize>], i: usize, candidate: usize) -> bool {
+    let mut current = Some(candidate);
+    while let Some(c) = current {
+        if c == i {
+            return true;
+        }
+        current = parent[c];
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn message(uid: u32, id: &str, references: &str, subject: &str, hour: u32) -> ThreadMessage {
+        ThreadMessage {
+            uid,
+            message_id: Some(format!("<{}>", id)),
+            in_reply_to: None,
+            references: parse_message_ids(references),
+            subject: Some(subject.to_string()),
+            date: Some(Utc.with_ymd_and_hms(2025, 3, 1, hour, 0, 0).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_build_threads_references_and_ordered_subject() {
+        let messages = vec![
+            message(10, "a@x", "", "Budget", 9),
+            message(11, "b@x", "<a@x>", "Re: Budget", 10),
+            message(12, "c@x", "<a@x> <b@x>", "Re: Budget", 11),
+            message(13, "d@x", "<a@x>", "Re: Budget", 12),
+            // Parent not in the folder; joins on the subject
+            message(14, "e@x", "<gone@x>", "RE: budget", 13),
+            message(20, "f@x", "", "Lunch", 8),
+        ];
+
+        let threads = build_threads(ThreadAlgorithm::References, &messages);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0], ThreadNode { uid: 20, children: vec![] });
+        let budget = &threads[1];
+        assert_eq!(budget.uid, 10);
+        assert_eq!(budget.children.iter().map(|c| c.uid).collect::<Vec<_>>(), vec![11, 13, 14]);
+        assert_eq!(budget.children[0].children, vec![ThreadNode { uid: 12, children: vec![] }]);
+        assert_eq!(budget.uids(), vec![10, 11, 12, 13, 14]);
+        assert!(budget.contains(12));
+
+        let flat = build_threads(ThreadAlgorithm::OrderedSubject, &messages);
+        assert_eq!(flat[1].uid, 10);
+        assert_eq!(flat[1].children.iter().map(|c| c.uid).collect::<Vec<_>>(), vec![11, 12, 13, 14]);
+        assert!(flat[1].children.iter().all(|c| c.children.is_empty()));
+
+        assert_eq!(
+            references_from_header(b"References: <a@x>\r\n\t<b@x> <c@x>\r\n\r\n"),
+            vec!["a@x".to_string(), "b@x".to_string(), "c@x".to_string()]
+        );
+        assert_eq!("ORDEREDSUBJECT".parse::<ThreadAlgorithm>(), Ok(ThreadAlgorithm::OrderedSubject));
+        assert!("jwz".parse::<ThreadAlgorithm>().is_err());
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! How an IMAP connection is secured, chosen per account: implicit TLS
+//! (usually port 993), a plaintext connection upgraded with STARTTLS before
+//! login (usually 143), or no TLS at all.
+//!
+//! Credentials only travel unencrypted when the account sets
+//! `allow_plaintext_auth`. Without it, plaintext mode and a STARTTLS the
+//! server refuses both end the connection before LOGIN.
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+
+use crate::config::TlsConfig;
+use super::error::ImapError;
+
+/// Tag of the STARTTLS command, the only one sent before async-imap takes over.
+const STARTTLS_TAG: &str = "S0";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionMode {
+    /// TLS from the first byte
+    #[default]
+    Implicit,
+    /// Plaintext until STARTTLS, which must succeed before login
+    #[serde(alias = "start_tls")]
+    StartTls,
+    /// No TLS; refused unless `allow_plaintext_auth` is set
+    Plaintext,
+}
+
+impl fmt::Display for ConnectionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Implicit => "implicit TLS",
+            Self::StartTls => "STARTTLS",
+            Self::Plaintext => "plaintext",
+        })
+    }
+}
+
+/// Everything that decides how a connection is secured.
+#[derive(Debug, Clone, Default)]
+pub struct ImapTransport {
+    pub mode: ConnectionMode,
+    /// Send credentials over a connection without TLS
+    pub allow_plaintext_auth: bool,
+    pub tls: TlsConfig,
+}
+
+impl ImapTransport {
+    /// Implicit TLS under `tls`.
+    pub fn implicit(tls: TlsConfig) -> Self {
+        Self { tls, ..Default::default() }
+    }
+
+    fn allow_plaintext(&self, host: &str, reason: &str) -> Result<(), ImapError> {
+        if self.allow_plaintext_auth {
+            warn!("IMAP to {}: {}; credentials will be sent UNENCRYPTED (allow_plaintext_auth)", host, reason);
+            Ok(())
+        } else {
+            Err(ImapError::Tls(format!(
+                "IMAP to {}: {}; refusing to send credentials in plaintext (set allow_plaintext_auth to permit)",
+                host, reason
+            )))
+        }
+    }
+}
+
+/// The byte stream under an IMAP session.
+pub enum ImapStream {
+    Tls(TlsStream<TcpStream>),
+    Plain(TcpStream),
+}
+
+impl fmt::Debug for ImapStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tls(_) => "ImapStream::Tls",
+            Self::Plain(_) => "ImapStream::Plain",
+        })
+    }
+}
+
+impl AsyncRead for ImapStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+            Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ImapStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+            Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+            Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+            Self::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Secure `tcp` to `host` the way `transport` says. Also returns whether
+/// the server greeting has been read already, as it has once STARTTLS was
+/// tried; XOAUTH2 must not wait for it a second time.
+pub async fn open(host: &str, tcp: TcpStream, transport: &ImapTransport) -> Result<(ImapStream, bool), ImapError> {
+    match transport.mode {
+        ConnectionMode::Implicit => {
+            let tls = crate::tls::connect("IMAP", host, tcp, &transport.tls).await?;
+            Ok((ImapStream::Tls(tls), false))
+        }
+        ConnectionMode::Plaintext => {
+            transport.allow_plaintext(host, "plaintext mode")?;
+            Ok((ImapStream::Plain(tcp), false))
+        }
+        ConnectionMode::StartTls => {
+            let mut reader = BufReader::new(tcp);
+            let greeting = read_line(&mut reader, host).await?;
+            if greeting.starts_with("* BYE") {
+                return Err(ImapError::Connection(format!("{} refused the connection: {}", host, greeting)));
+            }
+            match request_starttls(&mut reader, host).await? {
+                None => {
+                    let tcp = unbuffered(reader, host)?;
+                    let tls = crate::tls::connect("IMAP", host, tcp, &transport.tls).await?;
+                    Ok((ImapStream::Tls(tls), true))
+                }
+                Some(refusal) => {
+                    transport.allow_plaintext(host, &format!("STARTTLS refused ({})", refusal))?;
+                    Ok((ImapStream::Plain(unbuffered(reader, host)?), true))
+                }
+            }
+        }
+    }
+}
+
+/// Send STARTTLS; `None` when the server agreed, otherwise its reply.
+async fn request_starttls(reader: &mut BufReader<TcpStream>, host: &str) -> Result<Option<String>, ImapError> {
+    reader.get_mut().write_all(format!("{} STARTTLS\r\n", STARTTLS_TAG).as_bytes()).await?;
+    loop {
+        let line = read_line(reader, host).await?;
+        // Untagged lines (a CAPABILITY, say) may come before the tagged reply
+        let Some(reply) = line.strip_prefix(STARTTLS_TAG).and_then(|rest| rest.strip_prefix(' ')) else {
+            continue;
+        };
+        let agreed = reply.get(..2).is_some_and(|status| status.eq_ignore_ascii_case("OK"));
+        return Ok(if agreed { None } else { Some(reply.to_string()) });
+    }
+}
+
+async fn read_line(reader: &mut BufReader<TcpStream>, host: &str) -> Result<String, ImapError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Err(ImapError::Connection(format!("{} closed the connection before STARTTLS", host)));
+    }
+    Ok(line.trim_end().to_string())
+}
+
+/// The TCP stream back from its reader. Anything the server sent past its
+/// reply would otherwise be read as if it came over TLS.
+fn unbuffered(reader: BufReader<TcpStream>, host: &str) -> Result<TcpStream, ImapError> {
+    if !reader.buffer().is_empty() {
+        return Err(ImapError::Tls(format!("{} sent data after its STARTTLS reply; refusing to continue", host)));
+    }
+    Ok(reader.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// A server that greets, answers STARTTLS with `reply`, and returns
+    /// whatever the client sent.
+    async fn refusing_server(reply: &'static str) -> (TcpStream, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"* OK IMAP4rev1 ready\r\n").await.unwrap();
+            let mut command = vec![0; 64];
+            let n = socket.read(&mut command).await.unwrap();
+            socket.write_all(reply.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&command[..n]).into_owned()
+        });
+        (TcpStream::connect(addr).await.unwrap(), server)
+    }
+
+    #[tokio::test]
+    async fn test_refused_starttls_needs_allow_plaintext_auth() {
+        let transport = ImapTransport { mode: ConnectionMode::StartTls, ..Default::default() };
+        let (tcp, server) = refusing_server("* CAPABILITY IMAP4rev1\r\nS0 BAD unknown command\r\n").await;
+        let err = open("127.0.0.1", tcp, &transport).await.unwrap_err();
+        assert!(err.to_string().contains("refusing to send credentials"), "{}", err);
+        assert_eq!(server.await.unwrap(), "S0 STARTTLS\r\n");
+
+        let transport = ImapTransport { allow_plaintext_auth: true, ..transport };
+        let (tcp, _server) = refusing_server("S0 NO not here\r\n").await;
+        let (stream, greeted) = open("127.0.0.1", tcp, &transport).await.unwrap();
+        assert!(matches!(stream, ImapStream::Plain(_)));
+        assert!(greeted);
+    }
+
+    #[test]
+    fn test_connection_mode_names() {
+        let mode: ConnectionMode = serde_json::from_str(r#""starttls""#).unwrap();
+        assert_eq!(mode, ConnectionMode::StartTls);
+        assert_eq!(serde_json::from_str::<ConnectionMode>(r#""start_tls""#).unwrap(), ConnectionMode::StartTls);
+        assert_eq!(serde_json::to_string(&ConnectionMode::Implicit).unwrap(), r#""implicit""#);
+        assert_eq!(ImapTransport::default().mode, ConnectionMode::Implicit);
+    }
+}
@@ -17,12 +17,14 @@ use async_imap::types::{
     Name as AsyncImapName,
     Mailbox as AsyncImapMailbox,
 };
+use async_imap::imap_proto::{AttributeValue, QuotaResourceName, Response, UidSetMember};
 use chrono::{DateTime, Utc};
 // imap_types removed - NString was unused
 use serde::{Deserialize, Serialize};
 // use thiserror::Error; // Unused
 use log::debug;
 
+use crate::imap::dates;
 use crate::imap::error::ImapError;
 use crate::imap::session::DEFAULT_MAILBOX_DELIMITER;
 
@@ -85,6 +87,45 @@ pub struct Email {
     pub attachments: Vec<MimePart>,
 }
 
+/// A message that could not be returned from a batch FETCH.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct FetchFailure {
+    /// UID that was requested
+    pub uid: u32,
+    /// Why the message was skipped (parse error, missing data, not returned)
+    pub reason: String,
+}
+
+/// Result of a batch FETCH that tolerates per-message failures.
+///
+/// Messages that parse successfully are returned in `emails`; messages that
+/// failed to parse, came back without the requested items, or were not
+/// returned at all are reported in `errors` instead of failing the batch.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FetchBatch {
+    pub emails: Vec<Email>,
+    pub errors: Vec<FetchFailure>,
+    /// The response stream broke off partway. The rest of it may still be
+    /// on the wire, so the connection must not be used again.
+    #[serde(skip)]
+    pub aborted: bool,
+}
+
+impl FetchBatch {
+    /// Record a failure for every requested UID that is neither in `emails`
+    /// nor already in `errors`.
+    pub fn record_missing(&mut self, requested: &[u32], reason: &str) {
+        let seen: HashSet<u32> = self.emails.iter().map(|e| e.uid)
+            .chain(self.errors.iter().map(|f| f.uid))
+            .collect();
+        for uid in requested {
+            if !seen.contains(uid) {
+                self.errors.push(FetchFailure { uid: *uid, reason: reason.to_string() });
+            }
+        }
+    }
+}
+
 /// Represents an IMAP folder (mailbox) in the email system.
 ///
 /// A folder is a container for emails, organized hierarchically with a delimiter
@@ -154,7 +195,7 @@ impl Folder {
                 full_path: full_path.clone(),
                 parent,
                 children: Vec::new(),
-                selectable: !attributes.contains(&"\\Noselect".to_string()),
+                selectable: !attributes.iter().any(|a| is_noselect_attribute(a)),
                 attributes,
             };
             folder_map.insert(full_path, folder);
@@ -205,6 +246,259 @@ impl Folder {
     }
 }
 
+/// Whether a LIST attribute marks the mailbox as \Noselect. Attributes are
+/// stored in their Debug form (`NoSelect`), but the wire form is accepted too.
+pub fn is_noselect_attribute(attribute: &str) -> bool {
+    attribute.trim_start_matches('\\').eq_ignore_ascii_case("noselect")
+}
+
+/// Selectability and subscription state of one folder, from LIST and LSUB.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FolderState {
+    pub name: String,
+    pub delimiter: Option<String>,
+    /// False for \Noselect containers that cannot hold messages
+    pub selectable: bool,
+    /// Whether the folder is returned by LSUB
+    pub subscribed: bool,
+    pub attributes: Vec<String>,
+}
+
+impl FolderState {
+    /// Hierarchy position of this folder, derived from its delimiter.
+    pub fn path_info(&self) -> FolderPathInfo {
+        let (parent_path, leaf_name, depth) = match self.delimiter.as_deref().filter(|d| !d.is_empty()) {
+            Some(delim) => {
+                let depth = self.name.trim_end_matches(delim).matches(delim).count();
+                match self.name.trim_end_matches(delim).rsplit_once(delim) {
+                    Some((parent, leaf)) => (Some(parent.to_string()), leaf.to_string(), depth),
+                    None => (None, self.name.clone(), 0),
+                }
+            }
+            // A NIL delimiter means a flat namespace
+            None => (None, self.name.clone(), 0),
+        };
+
+        FolderPathInfo {
+            name: self.name.clone(),
+            leaf_name,
+            parent_path,
+            depth,
+            delimiter: self.delimiter.clone(),
+            selectable: self.selectable,
+            subscribed: self.subscribed,
+            attributes: self.attributes.clone(),
+        }
+    }
+
+    /// Special-use role from the LIST attributes (`\Trash` → "trash"), if any.
+    pub fn special_use(&self) -> Option<&'static str> {
+        self.attributes.iter().find_map(|attr| {
+            // Attributes are Debug-formatted, e.g. `Trash` or `Extension("\\Junk")`
+            let word = attr.rsplit('\\').next().unwrap_or(attr)
+                .trim_matches(|c: char| !c.is_ascii_alphanumeric());
+            normalize_role(word)
+        })
+    }
+}
+
+/// RFC 6154 special-use roles, with the folder names used for each by
+/// servers that don't advertise SPECIAL-USE.
+const SPECIAL_USE_ROLES: &[(&str, &[&str])] = &[
+    ("trash", &["Trash", "Deleted Items", "Deleted Messages", "Deleted", "Bin"]),
+    ("junk", &["Junk", "Spam", "Junk E-mail", "Junk Email", "Bulk Mail"]),
+    ("sent", &["Sent", "Sent Items", "Sent Messages", "Sent Mail"]),
+    ("drafts", &["Drafts", "Draft"]),
+    ("archive", &["Archive", "Archives"]),
+    ("all", &["All Mail"]),
+    ("flagged", &["Starred", "Flagged"]),
+];
+
+/// Canonical role name for a user-supplied role ("Spam" → "junk").
+pub(crate) fn normalize_role(role: &str) -> Option<&'static str> {
+    let role = role.trim().trim_start_matches('\\').to_ascii_lowercase();
+    let role = if role == "spam" { "junk".to_string() } else { role };
+    SPECIAL_USE_ROLES.iter().map(|(r, _)| *r).find(|r| *r == role)
+}
+
+/// Folder for a special-use role such as "trash" or "junk" (alias "spam"),
+/// matched by LIST attribute and then by the conventional names. `None` if
+/// the role is unknown or no folder matches.
+pub fn find_special_use<'a>(folders: &'a [FolderState], role: &str) -> Option<&'a FolderState> {
+    let role = normalize_role(role)?;
+    if let Some(folder) = folders.iter().find(|f| f.selectable && f.special_use() == Some(role)) {
+        return Some(folder);
+    }
+
+    let names = SPECIAL_USE_ROLES.iter().find(|(r, _)| *r == role).map(|(_, n)| *n)?;
+    names.iter().find_map(|name| {
+        folders.iter()
+            .filter(|f| f.selectable && f.path_info().leaf_name.eq_ignore_ascii_case(name))
+            .min_by_key(|f| f.path_info().depth)
+    })
+}
+
+/// Unread count of a folder, alone and summed over its subtree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FolderUnreadRollup {
+    pub name: String,
+    pub leaf_name: String,
+    pub parent_path: Option<String>,
+    pub depth: usize,
+    pub selectable: bool,
+    /// Unread messages in this folder itself
+    pub unread: i64,
+    /// Unread messages in this folder and all its descendants, the badge a
+    /// collapsed parent shows
+    pub unread_with_descendants: i64,
+}
+
+/// Roll per-folder unread counts (keyed by full folder name) up the
+/// delimiter hierarchy. Descendants count toward every listed ancestor even
+/// when an intermediate level isn't listed. Sorted by name.
+pub fn rollup_unread(folders: &[FolderState], unread: &HashMap<String, i64>) -> Vec<FolderUnreadRollup> {
+    let own = |f: &FolderState| unread.get(&f.name).copied().unwrap_or(0);
+
+    let mut rollups: Vec<FolderUnreadRollup> = folders.iter()
+        .map(|folder| {
+            let info = folder.path_info();
+            let descendants: i64 = match folder.delimiter.as_deref().filter(|d| !d.is_empty()) {
+                Some(delim) => {
+                    let prefix = format!("{}{}", folder.name.trim_end_matches(delim), delim);
+                    folders.iter()
+                        .filter(|other| other.name.len() > prefix.len() && other.name.starts_with(&prefix))
+                        .map(own)
+                        .sum()
+                }
+                None => 0,
+            };
+            FolderUnreadRollup {
+                name: info.name,
+                leaf_name: info.leaf_name,
+                parent_path: info.parent_path,
+                depth: info.depth,
+                selectable: info.selectable,
+                unread: own(folder),
+                unread_with_descendants: own(folder) + descendants,
+            }
+        })
+        .collect();
+    rollups.sort_by(|a, b| a.name.cmp(&b.name));
+    rollups
+}
+
+/// A folder in a flat listing, with the hierarchy details clients need to
+/// indent it and find its parent without re-parsing the delimiter.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FolderPathInfo {
+    /// Full path, as used to select the folder
+    pub name: String,
+    /// Last path component
+    pub leaf_name: String,
+    /// Full path of the parent, None for top-level folders. The parent may
+    /// not itself be listed.
+    pub parent_path: Option<String>,
+    /// 0 for top-level folders
+    pub depth: usize,
+    pub delimiter: Option<String>,
+    pub selectable: bool,
+    pub subscribed: bool,
+    pub attributes: Vec<String>,
+}
+
+/// A folder in the nested tree built by `build_folder_tree`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FolderNode {
+    /// Last path component
+    pub name: String,
+    /// Full path, as used to select the folder
+    pub full_path: String,
+    pub children: Vec<FolderNode>,
+    /// LIST attributes; `NonExistent` for intermediate levels the server
+    /// didn't list
+    pub attributes: Vec<String>,
+    pub unread: i64,
+}
+
+impl FolderNode {
+    fn new(name: &str, full_path: String) -> Self {
+        Self {
+            name: name.to_string(),
+            full_path,
+            children: Vec::new(),
+            attributes: vec!["NonExistent".to_string()],
+            unread: 0,
+        }
+    }
+
+    fn sort(nodes: &mut [FolderNode]) {
+        // INBOX first, the rest by name
+        nodes.sort_by_key(|node| (!node.full_path.eq_ignore_ascii_case("INBOX"), node.name.to_lowercase()));
+        for node in nodes {
+            Self::sort(&mut node.children);
+        }
+    }
+}
+
+/// Nest a flat LIST result by each folder's hierarchy delimiter, falling
+/// back to `default_delimiter` (the namespace's) for folders listed without
+/// one. Levels that aren't listed themselves, like `Work` for `Work/Q3`, get
+/// a `NonExistent` node so their children still sit under them. Courier-style
+/// `INBOX.` folders end up as children of INBOX, matched case-insensitively.
+/// `unread` is keyed by full folder name.
+pub fn build_folder_tree(
+    folders: &[FolderState],
+    default_delimiter: Option<&str>,
+    unread: &HashMap<String, i64>,
+) -> Vec<FolderNode> {
+    let mut roots = Vec::new();
+    for folder in folders {
+        let delimiter = folder.delimiter.as_deref().or(default_delimiter).filter(|d| !d.is_empty());
+        let segments: Vec<&str> = match delimiter {
+            Some(delim) => folder.name.trim_end_matches(delim).split(delim).collect(),
+            None => vec![folder.name.as_str()],
+        };
+        let own_unread = unread.get(&folder.name).copied().unwrap_or(0);
+        insert_folder(&mut roots, None, &segments, delimiter, folder, own_unread);
+    }
+    FolderNode::sort(&mut roots);
+    roots
+}
+
+fn insert_folder(
+    level: &mut Vec<FolderNode>,
+    parent_path: Option<&str>,
+    segments: &[&str],
+    delimiter: Option<&str>,
+    folder: &FolderState,
+    unread: i64,
+) {
+    let Some((segment, rest)) = segments.split_first() else {
+        return;
+    };
+    let full_path = match (parent_path, delimiter) {
+        (Some(parent), Some(delim)) => format!("{}{}{}", parent, delim, segment),
+        _ => segment.to_string(),
+    };
+    let is_inbox = parent_path.is_none() && segment.eq_ignore_ascii_case("INBOX");
+    let index = level.iter()
+        .position(|node| node.full_path == full_path || (is_inbox && node.full_path.eq_ignore_ascii_case("INBOX")))
+        .unwrap_or_else(|| {
+            level.push(FolderNode::new(segment, full_path));
+            level.len() - 1
+        });
+
+    let node = &mut level[index];
+    if rest.is_empty() {
+        node.full_path = folder.name.clone();
+        node.attributes = folder.attributes.clone();
+        node.unread = unread;
+    } else {
+        let path = node.full_path.clone();
+        insert_folder(&mut node.children, Some(&path), rest, delimiter, folder, unread);
+    }
+}
+
 /// Represents information about a selected mailbox.
 ///
 /// This struct contains metadata about a mailbox after it has been selected,
@@ -223,6 +517,7 @@ impl Folder {
 ///     unseen: Some(10),
 ///     uid_validity: Some(12345),
 ///     uid_next: Some(100),
+///     highest_modseq: None,
 ///     selectable: true,
 /// };
 /// ```
@@ -244,6 +539,9 @@ pub struct MailboxInfo {
     pub uid_validity: Option<u32>,
     /// The next UID that will be assigned to a new message
     pub uid_next: Option<u32>,
+    /// HIGHESTMODSEQ from SELECT (RFC 7162); None without CONDSTORE or on NOMODSEQ
+    #[serde(default)]
+    pub highest_modseq: Option<u64>,
 }
 
 impl From<AsyncImapName> for MailboxInfo {
@@ -257,6 +555,7 @@ impl From<AsyncImapName> for MailboxInfo {
             unseen: None,
             uid_validity: None,
             uid_next: None,
+            highest_modseq: None,
         }
     }
 }
@@ -272,6 +571,7 @@ impl From<AsyncImapMailbox> for MailboxInfo {
             unseen: mailbox.unseen,
             uid_validity: mailbox.uid_validity,
             uid_next: mailbox.uid_next,
+            highest_modseq: mailbox.highest_modseq,
         }
     }
 }
@@ -324,9 +624,10 @@ impl fmt::Display for SearchCriteria {
             SearchCriteria::Undraft => write!(f, "UNDRAFT"),
             SearchCriteria::Unflagged => write!(f, "UNFLAGGED"),
             SearchCriteria::Unseen => write!(f, "UNSEEN"),
-            SearchCriteria::Before(date) => write!(f, "BEFORE {}", date.format("%d-%b-%Y")),
-            SearchCriteria::On(date) => write!(f, "ON {}", date.format("%d-%b-%Y")),
-            SearchCriteria::Since(date) => write!(f, "SINCE {}", date.format("%d-%b-%Y")),
+            // IMAP dates are date-only; see imap::dates for how instants are rounded
+            SearchCriteria::Before(date) => write!(f, "BEFORE {}", dates::format_imap_date(dates::before_date(*date))),
+            SearchCriteria::On(date) => write!(f, "ON {}", dates::format_imap_date(date.date_naive())),
+            SearchCriteria::Since(date) => write!(f, "SINCE {}", dates::format_imap_date(dates::since_date(*date))),
             SearchCriteria::Body(text) => write!(f, "BODY \"{}\"", Self::escape_search_text(text)),
             SearchCriteria::From(text) => write!(f, "FROM \"{}\"", Self::escape_search_text(text)),
             SearchCriteria::Subject(text) => write!(f, "SUBJECT \"{}\"", Self::escape_search_text(text)),
@@ -352,6 +653,16 @@ impl SearchCriteria {
         SearchCriteria::And(criteria)
     }
 
+    /// Every one of `criteria`: ALL when empty, the criterion itself when
+    /// there is only one, otherwise a parenthesised AND.
+    pub fn all_of(mut criteria: Vec<SearchCriteria>) -> Self {
+        match criteria.len() {
+            0 => SearchCriteria::All,
+            1 => criteria.remove(0),
+            _ => SearchCriteria::And(criteria),
+        }
+    }
+
     /// Creates a compound OR search criteria
     pub fn or(criteria: Vec<SearchCriteria>) -> Self {
         SearchCriteria::Or(criteria)
@@ -437,6 +748,12 @@ mod search_tests {
         let date = Utc.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap();
         assert_eq!(SearchCriteria::Since(date).to_string(), "SINCE 15-Jan-2024");
         assert_eq!(SearchCriteria::Before(date).to_string(), "BEFORE 15-Jan-2024");
+
+        // Mid-day instants round outwards so the whole instant range is covered
+        let afternoon = Utc.with_ymd_and_hms(2024, 1, 15, 14, 30, 0).unwrap();
+        assert_eq!(SearchCriteria::Since(afternoon).to_string(), "SINCE 15-Jan-2024");
+        assert_eq!(SearchCriteria::Before(afternoon).to_string(), "BEFORE 16-Jan-2024");
+        assert_eq!(SearchCriteria::On(afternoon).to_string(), "ON 15-Jan-2024");
     }
 
     #[test]
@@ -450,6 +767,20 @@ mod search_tests {
         ]);
         assert!(matches!(and_criteria, SearchCriteria::And(_)));
     }
+
+    #[test]
+    fn test_search_criteria_all_of() {
+        assert_eq!(SearchCriteria::all_of(vec![]).to_string(), "ALL");
+        assert_eq!(SearchCriteria::all_of(vec![SearchCriteria::Unseen]).to_string(), "UNSEEN");
+
+        let since = Utc.with_ymd_and_hms(2024, 3, 5, 0, 0, 0).unwrap();
+        let criteria = SearchCriteria::all_of(vec![
+            SearchCriteria::from("boss@example.com"),
+            SearchCriteria::Since(since),
+            SearchCriteria::Flagged,
+        ]);
+        assert_eq!(criteria.to_string(), "(FROM \"boss@example.com\" SINCE 05-Mar-2024 FLAGGED)");
+    }
 }
 
 // --- New Types for Added Features ---
@@ -462,6 +793,282 @@ pub enum FlagOperation {
     Set,
 }
 
+/// Build an IMAP UID set from `uids`, collapsing consecutive runs into ranges
+/// (`[3, 1, 2, 7]` becomes `1:3,7`) so large batches keep commands short.
+pub fn uid_set(uids: &[u32]) -> String {
+    let mut sorted = uids.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let range = |start: u32, end: u32| {
+        if start == end { start.to_string() } else { format!("{}:{}", start, end) }
+    };
+    let mut parts = Vec::new();
+    let mut iter = sorted.into_iter();
+    let Some(mut start) = iter.next() else {
+        return String::new();
+    };
+    let mut end = start;
+    for uid in iter {
+        if uid == end + 1 {
+            end = uid;
+            continue;
+        }
+        parts.push(range(start, end));
+        start = uid;
+        end = uid;
+    }
+    parts.push(range(start, end));
+    parts.join(",")
+}
+
+/// A copied message's UID in the source folder and in the target folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CopiedUid {
+    pub source_uid: u32,
+    pub target_uid: u32,
+}
+
+/// Result of copying messages between folders.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CopyOutcome {
+    /// Source UIDs that were copied
+    pub copied: Vec<u32>,
+    /// Source UIDs not found in the source folder
+    pub missing: Vec<u32>,
+    /// UIDVALIDITY of the target folder, from the server's COPYUID response
+    pub target_uid_validity: Option<u32>,
+    /// New UIDs from COPYUID (RFC 4315); empty when the server lacks UIDPLUS
+    pub new_uids: Vec<CopiedUid>,
+}
+
+/// Pair up the source and target UID sets of a COPYUID response code. The
+/// sets list messages in the same order; a response whose sets differ in
+/// size is unusable and yields nothing.
+pub fn copyuid_pairs(source: &[UidSetMember], target: &[UidSetMember]) -> Vec<CopiedUid> {
+    let expand = |set: &[UidSetMember]| -> Vec<u32> {
+        set.iter()
+            .flat_map(|member| match member {
+                UidSetMember::Uid(uid) => *uid..=*uid,
+                UidSetMember::UidRange(range) => range.clone(),
+            })
+            .collect()
+    };
+    let (source, target) = (expand(source), expand(target));
+    if source.len() != target.len() {
+        return Vec::new();
+    }
+    source.into_iter()
+        .zip(target)
+        .map(|(source_uid, target_uid)| CopiedUid { source_uid, target_uid })
+        .collect()
+}
+
+/// UID of a single appended message, from the set in an APPENDUID response code.
+pub fn appended_uid(set: &[UidSetMember]) -> Option<u32> {
+    match set.first()? {
+        UidSetMember::Uid(uid) => Some(*uid),
+        UidSetMember::UidRange(range) => Some(*range.start()),
+    }
+}
+
+/// Messages whose flags changed after a given MODSEQ, from
+/// `UID FETCH 1:* (UID FLAGS) (CHANGEDSINCE n)` (RFC 7162).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChangedSince {
+    /// UID and current flags of each changed message, flags in the same
+    /// form as `fetch_flags`
+    pub changes: Vec<(u32, Vec<String>)>,
+    /// Highest MODSEQ among the changed messages
+    pub highest_modseq: Option<u64>,
+}
+
+impl ChangedSince {
+    /// Add one untagged FETCH response; other responses and FETCHes
+    /// without a UID are ignored.
+    pub fn record(&mut self, response: &Response) {
+        let Response::Fetch(_, attributes) = response else {
+            return;
+        };
+        let mut uid = None;
+        let mut flags = Vec::new();
+        for attribute in attributes {
+            match attribute {
+                AttributeValue::Uid(u) => uid = Some(*u),
+                AttributeValue::Flags(raw) => {
+                    for flag in raw.iter().map(|f| crate::imap::idle::flag_name(f)) {
+                        if !flags.contains(&flag) {
+                            flags.push(flag);
+                        }
+                    }
+                }
+                AttributeValue::ModSeq(modseq) => {
+                    self.highest_modseq = Some(self.highest_modseq.map_or(*modseq, |h| h.max(*modseq)));
+                }
+                _ => {}
+            }
+        }
+        if let Some(uid) = uid {
+            self.changes.push((uid, flags));
+        }
+    }
+}
+
+/// Storage and message-count quota of a mailbox, from GETQUOTAROOT
+/// (RFC 2087). A resource the server doesn't limit is left as `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct QuotaInfo {
+    /// Quota root the figures belong to (often the empty string)
+    pub root: Option<String>,
+    pub used_kb: Option<u64>,
+    pub limit_kb: Option<u64>,
+    pub used_messages: Option<u64>,
+    pub limit_messages: Option<u64>,
+}
+
+impl QuotaInfo {
+    /// Add one untagged QUOTA response. When a mailbox has several quota
+    /// roots the first one to report a resource is kept.
+    pub fn record(&mut self, response: &Response) {
+        let Response::Quota(quota) = response else {
+            return;
+        };
+        for resource in &quota.resources {
+            let (used, limit) = match resource.name {
+                QuotaResourceName::Storage => (&mut self.used_kb, &mut self.limit_kb),
+                QuotaResourceName::Message => (&mut self.used_messages, &mut self.limit_messages),
+                QuotaResourceName::Atom(_) => continue,
+            };
+            if limit.is_none() {
+                *used = Some(resource.usage);
+                *limit = Some(resource.limit);
+                self.root.get_or_insert_with(|| quota.root_name.to_string());
+            }
+        }
+    }
+
+    /// Usage of the fullest limited resource, in percent.
+    pub fn percent_used(&self) -> Option<f64> {
+        let percent = |used: Option<u64>, limit: Option<u64>| match (used, limit) {
+            (Some(used), Some(limit)) if limit > 0 => Some(used as f64 * 100.0 / limit as f64),
+            _ => None,
+        };
+        [percent(self.used_kb, self.limit_kb), percent(self.used_messages, self.limit_messages)]
+            .into_iter()
+            .flatten()
+            .reduce(f64::max)
+    }
+}
+
+/// A byte range of a message or of one of its MIME parts, from
+/// `UID FETCH n BODY.PEEK[section]<offset.length>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PartialBody {
+    pub uid: u32,
+    /// Section as sent to the server (`1.2`, `TEXT`); `None` for the whole message
+    pub section: Option<String>,
+    pub offset: u32,
+    /// The bytes delivered, still in their Content-Transfer-Encoding
+    #[serde(skip)]
+    pub data: Vec<u8>,
+    /// More of the message or part follows the delivered range
+    pub truncated: bool,
+    /// RFC822.SIZE of the whole message
+    pub message_size: Option<u32>,
+}
+
+impl PartialBody {
+    pub fn new(uid: u32, section: Option<String>, offset: u32) -> Self {
+        Self { uid, section, offset, ..Default::default() }
+    }
+
+    /// Add one untagged FETCH response; FETCHes for other messages are
+    /// ignored. The command asks for one byte more than `length`, so a
+    /// longer body shows up as a byte over, which is dropped here.
+    pub fn record(&mut self, response: &Response, length: u32) {
+        let Response::Fetch(_, attributes) = response else {
+            return;
+        };
+        if !attributes.iter().any(|a| matches!(a, AttributeValue::Uid(u) if *u == self.uid)) {
+            return;
+        }
+        for attribute in attributes {
+            match attribute {
+                AttributeValue::BodySection { data, .. } => {
+                    let mut data = data.as_deref().unwrap_or_default().to_vec();
+                    self.truncated = data.len() > length as usize;
+                    data.truncate(length as usize);
+                    self.data = data;
+                }
+                AttributeValue::Rfc822Size(size) => self.message_size = Some(*size),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Check a BODY[] section spec and return it upper-cased: part numbers
+/// separated by dots, optionally ending in HEADER, TEXT or MIME (`1.2`,
+/// `2.TEXT`, `HEADER`). An empty spec means the whole message.
+pub fn body_section(section: &str) -> Result<Option<String>, String> {
+    let section = section.trim().to_ascii_uppercase();
+    if section.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || format!("Invalid MIME section '{}' (expected e.g. 1, 1.2, 2.TEXT or HEADER)", section);
+    let parts: Vec<&str> = section.split('.').collect();
+    let (last, numbers) = parts.split_last().ok_or_else(invalid)?;
+    let is_part = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()) && !p.starts_with('0');
+    if !numbers.iter().all(|p| is_part(p)) {
+        return Err(invalid());
+    }
+    let valid_last = match *last {
+        "HEADER" | "TEXT" => true,
+        // MIME headers exist only for numbered parts
+        "MIME" => !numbers.is_empty(),
+        part => is_part(part),
+    };
+    if valid_last { Ok(Some(section)) } else { Err(invalid()) }
+}
+
+/// How many matches a server search returns: the requested `limit`, or
+/// `SEARCH_RESULT_LIMIT` (default 50) when none is given, never more than
+/// `SEARCH_RESULT_MAX` (default 500).
+pub fn search_result_limit(requested: Option<usize>) -> usize {
+    let env = |name: &str, default: usize| {
+        std::env::var(name).ok().and_then(|v| v.parse().ok()).filter(|&n: &usize| n > 0).unwrap_or(default)
+    };
+    let max = env("SEARCH_RESULT_MAX", 500);
+    requested.unwrap_or_else(|| env("SEARCH_RESULT_LIMIT", 50)).clamp(1, max)
+}
+
+/// The `limit` highest (most recently added) UIDs of a SEARCH result, highest
+/// first, skipping the first `offset` of them.
+pub fn newest_uids(uids: &[u32], offset: usize, limit: usize) -> Vec<u32> {
+    let mut sorted = uids.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    sorted.dedup();
+    sorted.into_iter().skip(offset).take(limit).collect()
+}
+
+/// Convert a flag as stored by this crate (the `Debug` form of
+/// `async_imap::types::Flag`, e.g. `Seen` or `Custom("$Label1")`) into IMAP
+/// STORE syntax (`\Seen`, `$Label1`).
+///
+/// Returns `None` for flags a client cannot set: `\Recent` and `\*`.
+pub fn imap_flag_syntax(flag: &str) -> Option<String> {
+    let flag = flag.trim();
+    if let Some(inner) = flag.strip_prefix("Custom(").and_then(|f| f.strip_suffix(')')) {
+        let keyword = inner.trim_matches('"');
+        return if keyword.is_empty() { None } else { Some(keyword.to_string()) };
+    }
+    match flag.trim_start_matches('\\') {
+        "Recent" | "MayCreate" | "*" | "" => None,
+        system @ ("Seen" | "Answered" | "Flagged" | "Deleted" | "Draft") => Some(format!("\\{}", system)),
+        keyword => Some(keyword.to_string()),
+    }
+}
+
 /// Represents a list of flags for modification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Flags {
@@ -697,8 +1304,7 @@ impl Email {
         });
 
         let internal_date = fetch.internal_date()
-            .and_then(|d| DateTime::parse_from_rfc2822(&d.to_string()).ok())
-            .map(|dt| dt.with_timezone(&Utc));
+            .map(|d| d.with_timezone(&Utc));
 
         // Get raw body content
         let body = fetch.body().map(|b| b.to_vec());
@@ -915,4 +1521,296 @@ impl From<Fetch> for Email {
             attachments,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email_with_uid(uid: u32) -> Email {
+        Email {
+            uid,
+            flags: vec![],
+            internal_date: None,
+            envelope: None,
+            body: None,
+            mime_parts: vec![],
+            text_body: None,
+            html_body: None,
+            attachments: vec![],
+        }
+    }
+
+    #[test]
+    fn test_fetch_batch_records_missing_uids() {
+        let mut batch = FetchBatch {
+            emails: vec![email_with_uid(1), email_with_uid(3)],
+            errors: vec![FetchFailure { uid: 2, reason: "Parse error".to_string() }],
+            ..Default::default()
+        };
+
+        batch.record_missing(&[1, 2, 3, 4, 5], "Not returned by server");
+
+        assert_eq!(batch.emails.len(), 2);
+        assert_eq!(batch.errors, vec![
+            FetchFailure { uid: 2, reason: "Parse error".to_string() },
+            FetchFailure { uid: 4, reason: "Not returned by server".to_string() },
+            FetchFailure { uid: 5, reason: "Not returned by server".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_imap_flag_syntax() {
+        assert_eq!(imap_flag_syntax("Seen"), Some("\\Seen".to_string()));
+        assert_eq!(imap_flag_syntax("\\Flagged"), Some("\\Flagged".to_string()));
+        assert_eq!(imap_flag_syntax("Custom(\"$Label1\")"), Some("$Label1".to_string()));
+        assert_eq!(imap_flag_syntax("Recent"), None);
+        assert_eq!(imap_flag_syntax("MayCreate"), None);
+    }
+
+    #[test]
+    fn test_fetch_batch_record_missing_is_idempotent() {
+        let mut batch = FetchBatch::default();
+        batch.record_missing(&[7], "Fetch stream aborted");
+        batch.record_missing(&[7], "Not returned by server");
+
+        assert_eq!(batch.errors.len(), 1);
+        assert_eq!(batch.errors[0].reason, "Fetch stream aborted");
+    }
+
+    #[test]
+    fn test_noselect_folders_are_not_selectable() {
+        assert!(is_noselect_attribute("NoSelect"));
+        assert!(is_noselect_attribute("\\Noselect"));
+        assert!(!is_noselect_attribute("NoInferiors"));
+
+        let folders = Folder::build_hierarchy(vec![
+            ("[Gmail]".to_string(), Some("/".to_string()), vec!["NoSelect".to_string()]),
+            ("[Gmail]/Sent Mail".to_string(), Some("/".to_string()), vec![]),
+        ]);
+        assert_eq!(folders.len(), 1);
+        assert!(!folders[0].selectable);
+        assert!(folders[0].children[0].selectable);
+    }
+
+    #[test]
+    fn test_folder_path_info() {
+        let state = |name: &str, delimiter: Option<&str>| FolderState {
+            name: name.to_string(),
+            delimiter: delimiter.map(String::from),
+            selectable: true,
+            subscribed: true,
+            attributes: vec![],
+        };
+
+        let nested = state("INBOX.Projects.2025", Some(".")).path_info();
+        assert_eq!(nested.leaf_name, "2025");
+        assert_eq!(nested.parent_path.as_deref(), Some("INBOX.Projects"));
+        assert_eq!(nested.depth, 2);
+
+        let top = state("INBOX", Some(".")).path_info();
+        assert_eq!((top.leaf_name.as_str(), top.parent_path, top.depth), ("INBOX", None, 0));
+
+        let gmail = state("[Gmail]/Sent Mail", Some("/")).path_info();
+        assert_eq!(gmail.leaf_name, "Sent Mail");
+        assert_eq!(gmail.depth, 1);
+
+        let flat = state("Archive.2024", None).path_info();
+        assert_eq!((flat.leaf_name.as_str(), flat.depth), ("Archive.2024", 0));
+    }
+
+    #[test]
+    fn test_rollup_unread() {
+        let state = |name: &str| FolderState {
+            name: name.to_string(),
+            delimiter: Some("/".to_string()),
+            selectable: true,
+            subscribed: true,
+            attributes: vec![],
+        };
+        // "Work/Clients" isn't listed; its child still counts toward "Work"
+        let folders = vec![
+            state("INBOX"),
+            state("Work"),
+            state("Work/Projects"),
+            state("Work/Clients/Acme"),
+            state("Workshop"),
+        ];
+        let unread: HashMap<String, i64> = [
+            ("INBOX", 4), ("Work", 1), ("Work/Projects", 2), ("Work/Clients/Acme", 5), ("Workshop", 7),
+        ].into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+        let rollups = rollup_unread(&folders, &unread);
+        let get = |name: &str| rollups.iter().find(|r| r.name == name).unwrap();
+        assert_eq!((get("Work").unread, get("Work").unread_with_descendants), (1, 8));
+        assert_eq!(get("Work/Projects").unread_with_descendants, 2);
+        assert_eq!(get("Work/Clients/Acme").depth, 2);
+        // A name prefix without the delimiter isn't a child
+        assert_eq!(get("Workshop").unread_with_descendants, 7);
+        assert_eq!(get("INBOX").unread_with_descendants, 4);
+    }
+
+    #[test]
+    fn test_build_folder_tree_mixed_delimiters() {
+        let state = |name: &str, delimiter: Option<&str>| FolderState {
+            name: name.to_string(),
+            delimiter: delimiter.map(String::from),
+            selectable: true,
+            subscribed: true,
+            attributes: vec!["HasNoChildren".to_string()],
+        };
+        let folders = vec![
+            state("INBOX.Projects.2025", Some(".")),
+            state("inbox.Receipts", Some(".")),
+            state("INBOX", Some(".")),
+            state("Shared/Team/Notes", Some("/")),
+            state("Archive/2024", None),
+            state("Flat.Name", None),
+        ];
+        let unread = HashMap::from([("INBOX".to_string(), 3), ("INBOX.Projects.2025".to_string(), 2)]);
+        let tree = build_folder_tree(&folders, Some("/"), &unread);
+
+        let names: Vec<&str> = tree.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["INBOX", "Archive", "Flat.Name", "Shared"]);
+
+        let inbox = &tree[0];
+        assert_eq!((inbox.full_path.as_str(), inbox.unread), ("INBOX", 3));
+        assert_eq!(inbox.children.len(), 2, "INBOX. folders nest under INBOX whatever their case");
+        let projects = &inbox.children[0];
+        assert_eq!(projects.full_path, "INBOX.Projects");
+        assert_eq!(projects.attributes, vec!["NonExistent"], "unlisted levels are synthetic");
+        assert_eq!(projects.children[0].full_path, "INBOX.Projects.2025");
+        assert_eq!(projects.children[0].unread, 2);
+        assert_eq!(inbox.children[1].full_path, "inbox.Receipts");
+
+        // No delimiter of its own: the namespace's "/" applies
+        assert_eq!(tree[1].children[0].full_path, "Archive/2024");
+        assert!(tree[2].children.is_empty());
+        let team = &tree[3].children[0];
+        assert_eq!((team.full_path.as_str(), team.children[0].name.as_str()), ("Shared/Team", "Notes"));
+
+        let flat = build_folder_tree(&folders[5..], None, &HashMap::new());
+        assert_eq!(flat[0].full_path, "Flat.Name");
+    }
+
+    #[test]
+    fn test_find_special_use() {
+        let state = |name: &str, attributes: &[&str]| FolderState {
+            name: name.to_string(),
+            delimiter: Some(".".to_string()),
+            selectable: true,
+            subscribed: true,
+            attributes: attributes.iter().map(|a| a.to_string()).collect(),
+        };
+        let folders = vec![
+            state("INBOX", &[]),
+            state("INBOX.Trash", &["HasNoChildren"]),
+            state("Bin", &["HasNoChildren", "Trash"]),
+            state("INBOX.Spam", &[r#"Extension("\\Junk")"#]),
+            state("INBOX.Old.Sent", &[]),
+            state("INBOX.Sent", &[]),
+        ];
+
+        // Attributes win over names
+        assert_eq!(find_special_use(&folders, "trash").map(|f| f.name.as_str()), Some("Bin"));
+        assert_eq!(find_special_use(&folders, "spam").map(|f| f.name.as_str()), Some("INBOX.Spam"));
+        // Name fallback prefers the shallowest match
+        assert_eq!(find_special_use(&folders, "Sent").map(|f| f.name.as_str()), Some("INBOX.Sent"));
+        assert!(find_special_use(&folders, "drafts").is_none());
+        assert!(find_special_use(&folders, "inbox").is_none());
+    }
+
+    #[test]
+    fn test_uid_set_collapses_runs() {
+        assert_eq!(uid_set(&[3, 1, 2, 7]), "1:3,7");
+        assert_eq!(uid_set(&[5, 5, 9, 10, 12]), "5,9:10,12");
+        assert_eq!(uid_set(&[42]), "42");
+        assert_eq!(uid_set(&[]), "");
+    }
+
+    #[test]
+    fn test_copyuid_pairs() {
+        let source = [UidSetMember::UidRange(4..=6), UidSetMember::Uid(9)];
+        let target = [UidSetMember::UidRange(101..=104)];
+        assert_eq!(
+            copyuid_pairs(&source, &target).iter().map(|c| (c.source_uid, c.target_uid)).collect::<Vec<_>>(),
+            vec![(4, 101), (5, 102), (6, 103), (9, 104)]
+        );
+        assert!(copyuid_pairs(&source, &[UidSetMember::Uid(101)]).is_empty());
+        assert_eq!(appended_uid(&[UidSetMember::Uid(57)]), Some(57));
+        assert_eq!(appended_uid(&[]), None);
+    }
+
+    #[test]
+    fn test_changed_since_records_fetches() {
+        let mut changed = ChangedSince::default();
+        for line in [
+            &b"* 3 FETCH (UID 41 FLAGS (\\Seen \\Seen $Todo) MODSEQ (9001))\r\n"[..],
+            b"* 8 FETCH (UID 57 MODSEQ (8990) FLAGS ())\r\n",
+            b"* 9 EXISTS\r\n",
+        ] {
+            let (_, response) = Response::from_bytes(line).unwrap();
+            changed.record(&response);
+        }
+        assert_eq!(changed.changes, vec![
+            (41, vec!["Seen".to_string(), "Custom(\"$Todo\")".to_string()]),
+            (57, vec![]),
+        ]);
+        assert_eq!(changed.highest_modseq, Some(9001));
+    }
+
+    #[test]
+    fn test_quota_info_records_quota_responses() {
+        let mut quota = QuotaInfo::default();
+        for line in [
+            &b"* QUOTAROOT INBOX \"\"\r\n"[..],
+            b"* QUOTA \"\" (STORAGE 900 1000 MESSAGE 120 10000)\r\n",
+            b"* QUOTA \"user2\" (STORAGE 5 50)\r\n",
+        ] {
+            let (_, response) = Response::from_bytes(line).unwrap();
+            quota.record(&response);
+        }
+        assert_eq!(quota.root.as_deref(), Some(""));
+        assert_eq!((quota.used_kb, quota.limit_kb), (Some(900), Some(1000)));
+        assert_eq!((quota.used_messages, quota.limit_messages), (Some(120), Some(10000)));
+        assert_eq!(quota.percent_used(), Some(90.0));
+        assert_eq!(QuotaInfo::default().percent_used(), None);
+    }
+
+    #[test]
+    fn test_partial_body_and_sections() {
+        let mut partial = PartialBody::new(7, Some("1.2".to_string()), 0);
+        for line in [
+            &b"* 3 FETCH (FLAGS (\\Seen) UID 9)\r\n"[..],
+            b"* 4 FETCH (UID 7 RFC822.SIZE 20480 BODY[1.2]<0> {6}\r\nHello!)\r\n",
+        ] {
+            let (_, response) = Response::from_bytes(line).unwrap();
+            partial.record(&response, 5);
+        }
+        assert_eq!(partial.data, b"Hello".to_vec());
+        assert!(partial.truncated);
+        assert_eq!(partial.message_size, Some(20480));
+
+        let mut whole = PartialBody::new(7, None, 0);
+        let (_, response) = Response::from_bytes(b"* 4 FETCH (UID 7 BODY[]<0> {3}\r\nabc)\r\n").unwrap();
+        whole.record(&response, 5);
+        assert_eq!(whole.data, b"abc".to_vec());
+        assert!(!whole.truncated);
+
+        assert_eq!(body_section(" 1.2 "), Ok(Some("1.2".to_string())));
+        assert_eq!(body_section("2.text"), Ok(Some("2.TEXT".to_string())));
+        assert_eq!(body_section("HEADER"), Ok(Some("HEADER".to_string())));
+        assert_eq!(body_section(""), Ok(None));
+        assert!(body_section("MIME").is_err());
+        assert!(body_section("1..2").is_err());
+        assert!(body_section("0.1").is_err());
+        assert!(body_section("1] UID 5 BODY[").is_err());
+    }
+
+    #[test]
+    fn test_newest_uids() {
+        let uids = [4, 90, 12, 90, 57, 3];
+        assert_eq!(newest_uids(&uids, 0, 3), vec![90, 57, 12]);
+        assert_eq!(newest_uids(&uids, 2, 10), vec![12, 4, 3]);
+        assert!(newest_uids(&uids, 5, 10).is_empty());
+    }
+}
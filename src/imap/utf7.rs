@@ -0,0 +1,156 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Modified UTF-7 mailbox names (RFC 3501 section 5.1.3).
+//!
+//! Servers send and expect non-ASCII mailbox names as `&`-prefixed runs of
+//! base64-encoded UTF-16BE (`Wichtig` with an umlaut, `Entwürfe`, is
+//! `Entw&APw-rfe`). The session layer decodes names from LIST/LSUB and
+//! encodes names before SELECT, CREATE, RENAME and friends, so everything
+//! above it works with plain UTF-8. Set `IMAP_UTF7_FOLDER_NAMES=false` to pass
+//! names through unchanged for servers that send raw UTF-8.
+
+use base64::alphabet::IMAP_MUTF7;
+use base64::engine::general_purpose::{GeneralPurpose, NO_PAD};
+use base64::Engine;
+
+const MUTF7: GeneralPurpose = GeneralPurpose::new(&IMAP_MUTF7, NO_PAD);
+
+/// Whether folder names are converted (`IMAP_UTF7_FOLDER_NAMES`, default true).
+pub fn utf7_folder_names_enabled() -> bool {
+    std::env::var("IMAP_UTF7_FOLDER_NAMES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Encode a UTF-8 mailbox name as modified UTF-7.
+pub fn encode(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut pending: Vec<u16> = Vec::new();
+
+    let flush = |pending: &mut Vec<u16>, out: &mut String| {
+        if pending.is_empty() {
+            return;
+        }
+        let bytes: Vec<u8> = pending.iter().flat_map(|unit| unit.to_be_bytes()).collect();
+        out.push('&');
+        out.push_str(&MUTF7.encode(bytes));
+        out.push('-');
+        pending.clear();
+    };
+
+    for c in name.chars() {
+        if (' '..='~').contains(&c) {
+            flush(&mut pending, &mut out);
+            if c == '&' {
+                out.push_str("&-");
+            } else {
+                out.push(c);
+            }
+        } else {
+            let mut units = [0u16; 2];
+            pending.extend_from_slice(c.encode_utf16(&mut units));
+        }
+    }
+    flush(&mut pending, &mut out);
+    out
+}
+
+/// Decode a modified UTF-7 mailbox name. Fails on an unterminated or
+/// malformed `&...-` run.
+pub fn decode(name: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(name.len());
+    let mut rest = name;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('-')
+            .ok_or_else(|| format!("Unterminated modified UTF-7 sequence in '{}'", name))?;
+        let encoded = &after[..end];
+
+        if encoded.is_empty() {
+            out.push('&');
+        } else {
+            let bytes = MUTF7
+                .decode(encoded)
+                .map_err(|e| format!("Invalid modified UTF-7 in '{}': {}", name, e))?;
+            if bytes.len() % 2 != 0 {
+                return Err(format!("Invalid modified UTF-7 in '{}': odd byte count", name));
+            }
+            let units: Vec<u16> = bytes.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect();
+            let decoded = String::from_utf16(&units)
+                .map_err(|e| format!("Invalid modified UTF-7 in '{}': {}", name, e))?;
+            out.push_str(&decoded);
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Name to send to the server for a UTF-8 folder name.
+pub fn to_imap(name: &str) -> String {
+    if utf7_folder_names_enabled() {
+        encode(name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// UTF-8 name for a folder name received from the server. Names that don't
+/// decode are returned as received rather than dropped.
+pub fn from_imap(name: &str) -> String {
+    if !utf7_folder_names_enabled() {
+        return name.to_string();
+    }
+    decode(name).unwrap_or_else(|e| {
+        log::warn!("{}; using the name as received", e);
+        name.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_non_ascii_names() {
+        let cases = [
+            ("Entwürfe", "Entw&APw-rfe"),
+            ("Gelöschte Elemente", "Gel&APY-schte Elemente"),
+            ("台北", "&U,BTFw-"),
+            ("Входящие", "&BBIERQQ+BDQETwRJBDgENQ-"),
+            ("INBOX/Wichtig ✓", "INBOX/Wichtig &JxM-"),
+        ];
+        for (utf8, wire) in cases {
+            assert_eq!(encode(utf8), wire, "encoding {}", utf8);
+            assert_eq!(decode(wire).unwrap(), utf8, "decoding {}", wire);
+        }
+    }
+
+    #[test]
+    fn test_ampersand_and_ascii_pass_through() {
+        assert_eq!(encode("R&D"), "R&-D");
+        assert_eq!(decode("R&-D").unwrap(), "R&D");
+        assert_eq!(encode("[Gmail]/Sent Mail"), "[Gmail]/Sent Mail");
+        assert_eq!(decode("INBOX").unwrap(), "INBOX");
+    }
+
+    #[test]
+    fn test_surrogate_pairs() {
+        let name = "Fun 😀";
+        assert_eq!(decode(&encode(name)).unwrap(), name);
+    }
+
+    #[test]
+    fn test_malformed_names_are_rejected() {
+        assert!(decode("Broken&AOQ").is_err());
+        assert!(decode("Bad&!!!-").is_err());
+        assert_eq!(from_imap("Broken&AOQ"), "Broken&AOQ");
+    }
+}
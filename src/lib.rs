@@ -26,6 +26,10 @@ pub mod evidence_export;
 pub mod metadata_export;
 pub mod filter_emails;
 pub mod batch_synopsis;
+pub mod semantic_search;
+pub mod sieve;
+pub mod tls;
+pub mod logging;
 
 // Test modules
 #[cfg(test)]
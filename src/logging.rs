@@ -0,0 +1,49 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Log output for the server.
+//!
+//! Logging goes through `tracing`, with `log` records bridged in, so lines
+//! written inside a request's span carry its `request_id` whichever macro
+//! wrote them. The filter comes from `RUST_LOG` (default `info`) and can be
+//! swapped at runtime by a config reload. With the `json-logs` feature each
+//! line is a JSON object, with the span fields under `span`.
+
+use std::sync::OnceLock;
+
+use tracing_subscriber::{prelude::*, reload, EnvFilter, Registry};
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Install the global subscriber. Does nothing if one is already set.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    #[cfg(feature = "json-logs")]
+    let output = tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(true)
+        .with_span_list(false);
+    #[cfg(not(feature = "json-logs"))]
+    let output = tracing_subscriber::fmt::layer();
+
+    if tracing_subscriber::registry().with(filter).with(output).try_init().is_ok() {
+        let _ = FILTER.set(handle);
+    }
+}
+
+/// Replace the filter with `directives`, in `RUST_LOG` syntax (a bare level
+/// such as `debug` works).
+pub fn set_level(directives: &str) -> Result<(), String> {
+    let handle = FILTER.get().ok_or("logging was not set up with a reloadable filter")?;
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| format!("invalid log level '{}': {}", directives, e))?;
+    handle.reload(filter).map_err(|e| format!("failed to change the log level: {}", e))?;
+    // `log` records are dropped before reaching the filter if above the
+    // level the bridge started with; let the filter decide instead
+    log::set_max_level(log::LevelFilter::Trace);
+    Ok(())
+}
@@ -9,10 +9,9 @@ use rustymail::config::Settings;
 // Remove direct ImapClient import if only used for connect check, keep if factory needs it explicitly
 // use rustymail::imap::ImapClient;
 use rustymail::api::rest::{AppState, configure_rest_service};
-use rustymail::api::auth::ApiKeyStore;
+use rustymail::api::auth::{ApiKeyStore, JwtValidator};
 use rustymail::api::rate_limit::{RateLimitConfig, RateLimitMiddleware};
 use std::sync::Arc;
-use dotenvy::dotenv;
 use log::{info, error, warn};
 // Remove tool registry creation
 // use rustymail::mcp_port::create_mcp_tool_registry;
@@ -20,7 +19,6 @@ use log::{info, error, warn};
 use rustymail::mcp::handler::McpHandler;
 use rustymail::mcp::adapters::sdk::SdkMcpAdapter;
 // --- End imports ---
-use env_logger;
 use rustymail::dashboard;
 use rustymail::dashboard::api::SseManager;
 use rustymail::api::openapi_docs;
@@ -57,11 +55,11 @@ async fn main() -> std::io::Result<()> {
     #[cfg(feature = "dhat-heap")]
     let _profiler = dhat::Profiler::new_heap();
 
-    // Load .env file if present
-    dotenv().ok();
+    // Load .env file if present; variables already set are kept
+    rustymail::dashboard::services::config_reload::load_dotenv().ok();
 
     // Initialize logger
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    rustymail::logging::init();
     info!("Starting RustyMail server...");
 
     // Log allocator info
@@ -78,6 +76,8 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    rustymail::tls::init(&settings);
+
     // Determine active interface from settings and print config details
     let active_interface = settings.interface.clone();
     info!("Using interface: {:?}", active_interface);
@@ -123,11 +123,12 @@ async fn main() -> std::io::Result<()> {
         let account = imap_account.clone();
         Box::pin(async move {
             info!("ImapSessionFactory: Creating new IMAP session...");
-            let client = ImapClient::<AsyncImapSessionWrapper>::connect(
+            let client = ImapClient::<AsyncImapSessionWrapper>::connect_with_transport(
                 &account.imap.host,
                 account.imap.port,
                 &account.imap.username,
                 &account.imap.password,
+                &account.imap_transport(),
             ).await.map_err(|e| {
                 error!("ImapSessionFactory: Failed to connect: {:?}", e);
                 e
@@ -144,15 +145,19 @@ async fn main() -> std::io::Result<()> {
     use rustymail::connection_pool::{ConnectionPool, ConnectionFactory, PoolConfig};
     use std::time::Duration;
 
-    // Create connection factory that uses our IMAP session factory
+    // Create connection factory that uses our IMAP session factory. Pooled
+    // sessions hold the default account's connection slots like any other.
     struct ImapConnectionFactory {
         session_factory: CloneableImapSessionFactory,
+        account: String,
     }
 
     #[async_trait::async_trait]
     impl ConnectionFactory for ImapConnectionFactory {
         async fn create(&self) -> Result<Arc<ImapClient<AsyncImapSessionWrapper>>, ImapError> {
-            let client = self.session_factory.create_session().await?;
+            let client = rustymail::imap::connection_limits::ConnectionLimiter::global()
+                .connect(&self.account, self.session_factory.create_session())
+                .await?;
             Ok(Arc::new(client))
         }
 
@@ -172,10 +177,19 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
-    let pool_config = PoolConfig::default();
+    let mut pool_config = PoolConfig::default();
+    // The pool logs in as the default account, so keep it within that account's limit
+    let account_limit = rustymail::imap::connection_limits::limit_for(&default_account.email_address);
+    if pool_config.max_connections > account_limit {
+        info!("Capping connection pool at {} connections for {} (IMAP_ACCOUNT_MAX_CONNECTIONS)", account_limit, default_account.email_address);
+        pool_config.max_connections = account_limit;
+        pool_config.min_connections = pool_config.min_connections.min(account_limit);
+        pool_config.reserved_interactive = pool_config.reserved_interactive.min(account_limit.saturating_sub(1));
+    }
 
     let connection_factory = Arc::new(ImapConnectionFactory {
         session_factory: imap_session_factory.clone(),
+        account: default_account.email_address.clone(),
     });
 
     let connection_pool = ConnectionPool::new(connection_factory, pool_config.clone());
@@ -201,12 +215,14 @@ async fn main() -> std::io::Result<()> {
     let session_manager = Arc::new(SessionManager::new(Arc::new(settings.clone())));
     let api_key_store = Arc::new(ApiKeyStore::new());
     api_key_store.init_from_env().await;
+    let jwt_validator = JwtValidator::from_settings(&settings).map(Arc::new);
     let app_state = AppState {
         settings: Arc::new(settings.clone()),
         mcp_handler: mcp_handler.clone(),
         session_manager: session_manager.clone(),
         dashboard_state: None, // Will be set later
         api_key_store: api_key_store.clone(),
+        jwt_validator,
     };
     info!("Application state initialized.");
 
@@ -222,13 +238,23 @@ async fn main() -> std::io::Result<()> {
     ).await;
     info!("Dashboard state initialized.");
 
-    // Start background metrics collection task (pass only connection pool to avoid circular reference)
-    dashboard_state.metrics_service.start_background_collection(Arc::clone(&dashboard_state.connection_pool));
+    // Start background metrics collection task (pass the pool and services, not the state, to avoid a circular reference)
+    dashboard_state.metrics_service.start_background_collection(
+        Arc::clone(&dashboard_state.connection_pool),
+        dashboard::services::metrics::MetricsSources {
+            cache_service: Arc::clone(&dashboard_state.cache_service),
+            email_service: Arc::clone(&dashboard_state.email_service),
+            outbox_queue_service: Arc::clone(&dashboard_state.outbox_queue_service),
+        },
+    );
 
     // Start sync process spawner instead of in-process sync
     // This runs sync in a separate process that exits after each cycle,
     // ensuring memory is fully reclaimed by the OS
-    start_sync_process_spawner();
+    start_sync_process_spawner(
+        Arc::clone(&dashboard_state.sync_service),
+        settings.cache_encryption.as_ref().and_then(|c| c.passphrase.clone())
+    );
     info!("Sync process spawner started");
 
     // Start outbox worker for asynchronous email sending
@@ -238,6 +264,10 @@ async fn main() -> std::io::Result<()> {
         imap_session_factory.clone(),
         Arc::clone(&dashboard_state.account_service),
         Arc::clone(&dashboard_state.cache_service),
+    ).with_event_bus(Arc::clone(&dashboard_state.event_bus)));
+    let shutdown = Arc::new(dashboard::services::shutdown::ShutdownCoordinator::new(
+        &dashboard_state,
+        Arc::clone(&outbox_worker),
     ));
     tokio::spawn(async move {
         outbox_worker.start().await;
@@ -264,6 +294,10 @@ async fn main() -> std::io::Result<()> {
     dashboard::services::event_integration::start_event_publishers(Arc::new(dashboard_state.as_ref().clone())).await;
     info!("Event publishers started");
 
+    // Reload settings on SIGHUP
+    #[cfg(unix)]
+    dashboard::services::config_reload::start_sighup_listener(Arc::new(dashboard_state.as_ref().clone()));
+
     // Start MCP session cleanup task
     rustymail::api::mcp_http::start_session_cleanup();
     info!("MCP session cleanup task started");
@@ -323,6 +357,8 @@ async fn main() -> std::io::Result<()> {
                 actix_web::http::header::ORIGIN,
             ])
             .allowed_header("X-Api-Key")
+            .allowed_header("X-Request-Id")
+            .expose_headers(vec!["X-Request-Id"])
             .supports_credentials()
             .max_age(3600);
 
@@ -344,6 +380,7 @@ async fn main() -> std::io::Result<()> {
             .wrap(cors)
             .wrap(actix_web::middleware::Logger::default())
             .wrap(dashboard::api::middleware::Metrics)
+            .wrap(rustymail::api::request_id::RequestIdMiddleware)
             // Configure routes
             .configure(configure_rest_service)                // RustyMail REST API
             .configure(openapi_docs::configure_openapi)       // OpenAPI/Swagger documentation
@@ -362,36 +399,45 @@ async fn main() -> std::io::Result<()> {
         e
     })?
     .workers(1)  // TEMPORARY: Use single worker to debug memory leak
+    // Signals are handled below so the outbox closes before requests drain
+    .disable_signals()
     .run();
 
+    let server_handle = server.handle();
+    let shutdown_on_signal = Arc::clone(&shutdown);
+    tokio::spawn(async move {
+        dashboard::services::shutdown::wait_for_signal().await;
+        shutdown_on_signal.begin();
+        server_handle.stop(true).await;
+    });
+
     // Spawn the Dashboard SSE broadcast task
     info!("Spawning Dashboard SSE broadcast task...");
     tokio::spawn(async move {
         sse_manager_clone_for_task.start_stats_broadcast(dashboard_state_clone_for_task).await;
     });
 
-    // Await the server
+    // Await the server, then drain the outbox and connection pools
     info!("Server run loop starting.");
-    server.await
+    let result = server.await;
+    shutdown.drain().await;
+    result
 }
 
 /// Start a background task that spawns the sync process periodically.
 /// The sync process runs in a separate process that exits after each sync cycle,
 /// ensuring all memory allocated during sync is returned to the OS.
-fn start_sync_process_spawner() {
-    use std::time::Duration;
-
-    let sync_interval: u64 = std::env::var("SYNC_INTERVAL_SECONDS")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(300); // Default: 5 minutes
-
+///
+/// A cache encryption passphrase from the config file is handed to the sync
+/// process through its environment. The interval (`SYNC_INTERVAL_SECONDS`)
+/// is read from the sync service each round, so a config reload changes it.
+fn start_sync_process_spawner(
+    sync_service: Arc<rustymail::dashboard::services::SyncService>,
+    encryption_passphrase: Option<String>,
+) {
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(sync_interval));
-        interval.tick().await; // Skip first immediate tick
-
         loop {
-            interval.tick().await;
+            tokio::time::sleep(sync_service.sync_interval()).await;
 
             // Find the sync binary - check multiple locations
             let sync_binary = if std::path::Path::new("./target/release/rustymail-sync").exists() {
@@ -403,7 +449,11 @@ fn start_sync_process_spawner() {
                 "rustymail-sync"
             };
 
-            match std::process::Command::new(sync_binary).spawn() {
+            let mut cmd = std::process::Command::new(sync_binary);
+            if let Some(ref passphrase) = encryption_passphrase {
+                cmd.env("CACHE_ENCRYPTION_PASSPHRASE", passphrase);
+            }
+            match cmd.spawn() {
                 Ok(child) => {
                     info!("Spawned sync process (pid: {:?})", child.id());
                 }
@@ -82,11 +82,14 @@ impl RustyMailService {
                 ));
             }
         };
-        let session = client.session_arc();
 
-        // Execute the tool
+        // Execute the tool under the same limit as a body fetch, so a stalled
+        // server can't hold the port state forever
         let mut state_guard = self.port_state.lock().await;
-        let result = tool.execute(session, &mut state_guard, params.unwrap_or(Value::Null)).await;
+        let state = &mut *state_guard;
+        let result = client.with_session(&tool_name, client.timeouts().fetch, |session| async move {
+            Ok(tool.execute(session, state, params.unwrap_or(Value::Null)).await)
+        }).await;
         drop(state_guard);
 
         // IMPORTANT: Logout to release BytePool buffers and prevent memory leak
@@ -95,7 +98,7 @@ impl RustyMailService {
         }
 
         match result {
-            Ok(value) => {
+            Ok(Ok(value)) => {
                 let text = serde_json::to_string_pretty(&value).unwrap_or_else(|_| "null".to_string());
                 let content = Content {
                     raw: RawContent::Text(RawTextContent { text, meta: None }),
@@ -103,11 +106,19 @@ impl RustyMailService {
                 };
                 Ok(CallToolResult::success(vec![content]))
             },
-            Err(err) => Err(ErrorData::new(
+            Ok(Err(err)) => Err(ErrorData::new(
                 ErrorCode(err.code as i32),
                 err.message,
                 err.data
-            ))
+            )),
+            Err(imap_err) => {
+                error!("IMAP session failed during tool '{}': {}", tool_name, imap_err);
+                Err(ErrorData::new(
+                    ErrorCode(-32603), // Internal error
+                    format!("IMAP session failed: {}", imap_err),
+                    None
+                ))
+            }
         }
     }
 }
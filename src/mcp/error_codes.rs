@@ -49,6 +49,9 @@ pub enum ErrorCode {
     McpMethodNotFound = -32052,
     McpInternalError = -32053,
     McpParseError = -32054,
+
+    // HTTP transport errors
+    TransportRateLimited = -32062,
     
     // Session errors
     SessionNotFound = -32080,
@@ -94,6 +97,9 @@ impl ErrorCode {
             ErrorCode::McpMethodNotFound => "MCP: Method not found",
             ErrorCode::McpInternalError => "MCP: Internal error",
             ErrorCode::McpParseError => "MCP: Parse error",
+
+            // HTTP transport error messages
+            ErrorCode::TransportRateLimited => "Rate limit exceeded",
             
             // Session error messages
             ErrorCode::SessionNotFound => "Session not found",
@@ -324,12 +324,18 @@ fn map_imap_err_to_mcp(err: &ImapError) -> (i64, String) {
     match err {
         ImapError::Connection(msg) => 
             (ErrorCode::ImapConnectionError as i64, format!("Connection error: {}", msg)),
+        ImapError::CircuitOpen(msg) =>
+            (ErrorCode::ImapConnectionError as i64, format!("Server unavailable: {}", msg)),
+        ImapError::TlsVerification(_) =>
+            (ErrorCode::ImapConnectionError as i64, err.to_string()),
         ImapError::Auth(msg) => 
             (ErrorCode::ImapAuthError as i64, format!("Authentication error: {}", msg)),
         ImapError::Parse(msg) =>
             (ErrorCode::ParseError as i64, format!("Parse error: {}", msg)),
         ImapError::Validation(msg) =>
             (ErrorCode::InvalidParams as i64, format!("Validation error: {}", msg)),
+        ImapError::Unsupported(msg) =>
+            (ErrorCode::ImapCommandError as i64, format!("Not supported: {}", msg)),
         ImapError::Command(msg) => 
             (ErrorCode::ImapCommandError as i64, format!("Command error: {}", msg)),
         ImapError::InvalidCriteria(crit) => 
@@ -346,6 +352,8 @@ fn map_imap_err_to_mcp(err: &ImapError) -> (i64, String) {
             (ErrorCode::ImapOperationFailed as i64, format!("Operation failed: {}", msg)),
         ImapError::FolderNotFound(folder) => 
             (ErrorCode::ImapFolderNotFound as i64, format!("Folder not found: {}", folder)),
+        ImapError::NotSelectable(_) =>
+            (ErrorCode::ImapInvalidMailbox as i64, err.to_string()),
         ImapError::InvalidMailbox(msg) => 
             (ErrorCode::ImapFolderNotFound as i64, format!("Invalid mailbox: {}", msg)),
         ImapError::Other(msg) =>
@@ -8,7 +8,7 @@ use std::sync::Arc;
 use serde_json::{Value, json};
 use tokio::sync::Mutex as TokioMutex;
 use crate::mcp::types::{JsonRpcError, McpPortState};
-use crate::dashboard::services::cache::CacheService;
+use crate::dashboard::services::cache::{CacheService, EmailCursor, PageStart};
 use log::{debug, error};
 use crate::prelude::AsyncImapOps;
 
@@ -57,13 +57,29 @@ pub async fn list_cached_emails_tool(
     let account_id = account_email.as_deref()
         .ok_or_else(|| JsonRpcError::invalid_params("account_id parameter is required"))?;
 
-    match cache_service.get_cached_emails_for_account(folder, account_id, limit, offset, preview_mode).await {
-        Ok(emails) => {
+    let cursor = params.as_ref().and_then(|p| p.get("cursor")).and_then(|v| v.as_str());
+    let start = match cursor.map(str::trim) {
+        None => PageStart::Offset(offset),
+        Some("") => PageStart::Offset(0),
+        Some(c) => PageStart::After(EmailCursor::decode(c)
+            .ok_or_else(|| JsonRpcError::invalid_params(format!("Invalid cursor '{}'", c)))?),
+    };
+
+    match cache_service.get_cached_emails_page(folder, account_id, limit, start, preview_mode).await {
+        Ok((emails, next_cursor)) => {
+            let next_cursor = next_cursor.map(|c| c.encode());
+            let count = emails.len();
+            let data = if cursor.is_some() {
+                json!({ "emails": emails, "next_cursor": next_cursor })
+            } else {
+                json!(emails)
+            };
             Ok(json!({
                 "success": true,
-                "data": emails,
+                "data": data,
                 "folder": folder,
-                "count": emails.len(),
+                "count": count,
+                "next_cursor": next_cursor,
                 "tool": "list_cached_emails"
             }))
         }
@@ -12,6 +12,7 @@ use log::info;
 use serde::Serialize;
 use sqlx::SqlitePool;
 
+use crate::dashboard::services::cache_crypto::reveal;
 use crate::evidence_export::{csv_escape, sanitize_filename};
 
 /// Maximum emails to export in a single call.
@@ -175,7 +176,7 @@ impl MetadataExporter {
         let metadata: Vec<EmailMetadataRow> = rows.into_iter().map(|r| {
             EmailMetadataRow {
                 uid: r.0,
-                subject: r.1,
+                subject: reveal(r.1),
                 from_address: r.2,
                 to_addresses: r.3,
                 cc_addresses: r.4,
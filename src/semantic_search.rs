@@ -0,0 +1,560 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Semantic search over the email cache. Email bodies are embedded with a
+//! configurable [`EmbeddingProvider`] (OpenAI or Ollama) after each folder
+//! sync and the vectors stored in `email_embeddings`. Queries are embedded
+//! the same way and ranked by cosine similarity.
+//!
+//! Embedding costs API calls (OpenAI) or GPU time (Ollama), so the feature is
+//! off unless `SEMANTIC_SEARCH_ENABLED=true`. When disabled the
+//! `semantic_search` tool falls back to keyword search over the cache.
+
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::dashboard::services::cache_crypto::reveal;
+
+/// Default number of results returned by a semantic search.
+const DEFAULT_LIMIT: usize = 10;
+
+/// Absolute maximum number of results per search.
+const MAX_LIMIT: usize = 100;
+
+/// Default number of emails embedded per provider request while indexing.
+const DEFAULT_INDEX_BATCH_SIZE: usize = 32;
+
+/// Default number of characters of subject + body sent to the provider.
+const DEFAULT_MAX_INPUT_CHARS: usize = 8000;
+
+const DEFAULT_OPENAI_MODEL: &str = "text-embedding-3-small";
+const DEFAULT_OLLAMA_MODEL: &str = "nomic-embed-text";
+
+/// Embedded in place of an email with neither subject nor body, since
+/// providers reject empty input.
+const EMPTY_INPUT_PLACEHOLDER: &str = "(empty message)";
+
+#[derive(Error, Debug)]
+pub enum EmbeddingError {
+    #[error("Semantic search is disabled")]
+    Disabled,
+    #[error("Embedding provider misconfigured: {0}")]
+    Config(String),
+    #[error("Embedding request failed: {0}")]
+    Request(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Which backend computes the embeddings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProviderKind {
+    OpenAi,
+    Ollama,
+}
+
+impl EmbeddingProviderKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "openai" => Some(Self::OpenAi),
+            "ollama" => Some(Self::Ollama),
+            _ => None,
+        }
+    }
+
+    fn default_model(&self) -> &'static str {
+        match self {
+            Self::OpenAi => DEFAULT_OPENAI_MODEL,
+            Self::Ollama => DEFAULT_OLLAMA_MODEL,
+        }
+    }
+}
+
+/// Semantic search settings, read from the environment.
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticSearchConfig {
+    pub enabled: bool,
+    pub provider: EmbeddingProviderKind,
+    pub model: String,
+    pub index_batch_size: usize,
+    pub max_input_chars: usize,
+}
+
+impl SemanticSearchConfig {
+    /// - `SEMANTIC_SEARCH_ENABLED`: `true` to embed during sync (default `false`)
+    /// - `EMBEDDING_PROVIDER`: `openai` (default) or `ollama`
+    /// - `EMBEDDING_MODEL`: model name (default depends on provider)
+    /// - `EMBEDDING_BATCH_SIZE`: emails per provider request (default 32)
+    /// - `EMBEDDING_MAX_INPUT_CHARS`: characters embedded per email (default 8000)
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("SEMANTIC_SEARCH_ENABLED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        let provider = std::env::var("EMBEDDING_PROVIDER")
+            .ok()
+            .and_then(|v| EmbeddingProviderKind::parse(&v))
+            .unwrap_or(EmbeddingProviderKind::OpenAi);
+        let model = std::env::var("EMBEDDING_MODEL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| provider.default_model().to_string());
+        let index_batch_size = std::env::var("EMBEDDING_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &usize| *v > 0)
+            .unwrap_or(DEFAULT_INDEX_BATCH_SIZE);
+        let max_input_chars = std::env::var("EMBEDDING_MAX_INPUT_CHARS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &usize| *v > 0)
+            .unwrap_or(DEFAULT_MAX_INPUT_CHARS);
+
+        Self { enabled, provider, model, index_batch_size, max_input_chars }
+    }
+
+    /// Build the provider described by this config.
+    pub fn build_provider(&self) -> Result<Box<dyn EmbeddingProvider>, EmbeddingError> {
+        if !self.enabled {
+            return Err(EmbeddingError::Disabled);
+        }
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| EmbeddingError::Config(e.to_string()))?;
+
+        match self.provider {
+            EmbeddingProviderKind::OpenAi => {
+                let api_key = std::env::var("OPENAI_API_KEY")
+                    .map_err(|_| EmbeddingError::Config("OPENAI_API_KEY is not set".to_string()))?;
+                let base_url = std::env::var("OPENAI_BASE_URL")
+                    .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+                Ok(Box::new(OpenAiEmbeddingProvider::new(api_key, base_url, self.model.clone(), http_client)))
+            }
+            EmbeddingProviderKind::Ollama => {
+                let base_url = std::env::var("OLLAMA_BASE_URL")
+                    .unwrap_or_else(|_| "http://localhost:11434".to_string());
+                Ok(Box::new(OllamaEmbeddingProvider::new(base_url, self.model.clone(), http_client)))
+            }
+        }
+    }
+}
+
+/// Computes embedding vectors for text.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Model identifier stored alongside each vector.
+    fn model(&self) -> &str;
+
+    /// Embed each input, returning one vector per input in the same order.
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError>;
+}
+
+/// OpenAI-compatible `/embeddings` endpoint.
+pub struct OpenAiEmbeddingProvider {
+    api_key: String,
+    base_url: String,
+    model: String,
+    http_client: Client,
+}
+
+#[derive(Serialize)]
+struct OpenAiEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl OpenAiEmbeddingProvider {
+    pub fn new(api_key: String, base_url: String, model: String, http_client: Client) -> Self {
+        Self { api_key, base_url: base_url.trim_end_matches('/').to_string(), model, http_client }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbeddingProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let url = format!("{}/embeddings", self.base_url);
+        let response = self.http_client
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .json(&OpenAiEmbeddingRequest { model: &self.model, input: inputs })
+            .send()
+            .await
+            .map_err(|e| EmbeddingError::Request(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(EmbeddingError::Request(format!("OpenAI returned {}: {}", status, body)));
+        }
+
+        let mut parsed: OpenAiEmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| EmbeddingError::Request(format!("Invalid OpenAI response: {}", e)))?;
+        parsed.data.sort_by_key(|d| d.index);
+        if parsed.data.len() != inputs.len() {
+            return Err(EmbeddingError::Request(format!(
+                "OpenAI returned {} embeddings for {} inputs", parsed.data.len(), inputs.len()
+            )));
+        }
+        Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+/// Native Ollama `/api/embed` endpoint.
+pub struct OllamaEmbeddingProvider {
+    base_url: String,
+    model: String,
+    http_client: Client,
+}
+
+#[derive(Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+impl OllamaEmbeddingProvider {
+    pub fn new(base_url: String, model: String, http_client: Client) -> Self {
+        Self { base_url: base_url.trim_end_matches('/').to_string(), model, http_client }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let url = format!("{}/api/embed", self.base_url);
+        let response = self.http_client
+            .post(&url)
+            .json(&OllamaEmbedRequest { model: &self.model, input: inputs })
+            .send()
+            .await
+            .map_err(|e| EmbeddingError::Request(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(EmbeddingError::Request(format!("Ollama returned {}: {}", status, body)));
+        }
+
+        let parsed: OllamaEmbedResponse = response
+            .json()
+            .await
+            .map_err(|e| EmbeddingError::Request(format!("Invalid Ollama response: {}", e)))?;
+        if parsed.embeddings.len() != inputs.len() {
+            return Err(EmbeddingError::Request(format!(
+                "Ollama returned {} embeddings for {} inputs", parsed.embeddings.len(), inputs.len()
+            )));
+        }
+        Ok(parsed.embeddings)
+    }
+}
+
+/// Cosine similarity of two vectors. Returns 0.0 for mismatched lengths or
+/// zero vectors.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let mut dot = 0.0f32;
+    let mut norm_a = 0.0f32;
+    let mut norm_b = 0.0f32;
+    for (x, y) in a.iter().zip(b.iter()) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Serialize a vector as little-endian f32 bytes for the `vector` BLOB column.
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Inverse of [`encode_vector`]. Trailing bytes that do not form a full f32
+/// are ignored.
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+/// Text sent to the provider for one email: subject followed by the plain
+/// text body, truncated to `max_chars` characters. Never empty.
+pub fn embedding_input(subject: Option<&str>, body_text: Option<&str>, max_chars: usize) -> String {
+    let mut text = String::new();
+    if let Some(subject) = subject.filter(|s| !s.trim().is_empty()) {
+        text.push_str(subject.trim());
+        text.push_str("\n\n");
+    }
+    if let Some(body) = body_text {
+        text.push_str(body.trim());
+    }
+    if text.trim().is_empty() {
+        return EMPTY_INPUT_PLACEHOLDER.to_string();
+    }
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => text[..idx].to_string(),
+        None => text,
+    }
+}
+
+/// A single semantic search hit.
+#[derive(Debug, Serialize)]
+pub struct SemanticMatch {
+    pub uid: i64,
+    pub folder: String,
+    pub subject: Option<String>,
+    pub from_address: Option<String>,
+    pub date: Option<String>,
+    pub score: f32,
+}
+
+/// Indexes cached emails and answers nearest-neighbour queries.
+pub struct SemanticIndex {
+    db_pool: SqlitePool,
+    provider: Box<dyn EmbeddingProvider>,
+    max_input_chars: usize,
+    index_batch_size: usize,
+}
+
+impl SemanticIndex {
+    pub fn new(db_pool: SqlitePool, provider: Box<dyn EmbeddingProvider>, config: &SemanticSearchConfig) -> Self {
+        Self {
+            db_pool,
+            provider,
+            max_input_chars: config.max_input_chars,
+            index_batch_size: config.index_batch_size,
+        }
+    }
+
+    /// Build an index from the environment config, or `None` when semantic
+    /// search is disabled or the provider cannot be constructed.
+    pub fn from_env(db_pool: SqlitePool) -> Option<Self> {
+        let config = SemanticSearchConfig::from_env();
+        if !config.enabled {
+            return None;
+        }
+        match config.build_provider() {
+            Ok(provider) => Some(Self::new(db_pool, provider, &config)),
+            Err(e) => {
+                warn!("Semantic search enabled but unavailable: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Embed every email in the folder that has no vector for the current
+    /// model yet. Returns the number of emails embedded.
+    pub async fn index_pending(&self, account_id: &str, folder: &str) -> Result<usize, EmbeddingError> {
+        let model = self.provider.model().to_string();
+        let mut indexed = 0;
+        // Each pass starts past the last row seen, so rows the provider
+        // could not embed are not selected again in this call
+        let mut last_id: i64 = 0;
+
+        loop {
+            let rows = sqlx::query(
+                r#"
+                SELECT e.id, e.subject, e.body_text
+                FROM emails e
+                JOIN folders f ON e.folder_id = f.id
+                LEFT JOIN email_embeddings v ON v.email_id = e.id AND v.model = ?
+                WHERE f.account_id = ? AND f.name = ? AND v.email_id IS NULL AND e.id > ?
+                ORDER BY e.id
+                LIMIT ?
+                "#,
+            )
+            .bind(&model)
+            .bind(account_id)
+            .bind(folder)
+            .bind(last_id)
+            .bind(self.index_batch_size as i64)
+            .fetch_all(&self.db_pool)
+            .await?;
+
+            if rows.is_empty() {
+                break;
+            }
+
+            let ids: Vec<i64> = rows.iter().map(|r| r.get("id")).collect();
+            last_id = ids.last().copied().unwrap_or(last_id);
+            let inputs: Vec<String> = rows
+                .iter()
+                .map(|r| {
+                    let subject = reveal(r.get("subject"));
+                    let body = reveal(r.get("body_text"));
+                    embedding_input(subject.as_deref(), body.as_deref(), self.max_input_chars)
+                })
+                .collect();
+
+            let vectors = self.provider.embed(&inputs).await?;
+            if vectors.len() != ids.len() {
+                return Err(EmbeddingError::Request(format!(
+                    "provider returned {} embeddings for {} inputs",
+                    vectors.len(),
+                    ids.len()
+                )));
+            }
+            for (email_id, vector) in ids.iter().zip(vectors.iter()) {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO email_embeddings (email_id, model, dimensions, vector) VALUES (?, ?, ?, ?)",
+                )
+                .bind(email_id)
+                .bind(&model)
+                .bind(vector.len() as i64)
+                .bind(encode_vector(vector))
+                .execute(&self.db_pool)
+                .await?;
+            }
+            indexed += ids.len();
+            debug!("Embedded {} emails in {}/{}", ids.len(), account_id, folder);
+        }
+
+        if indexed > 0 {
+            info!("Embedded {} emails in {}/{} with {}", indexed, account_id, folder, model);
+        }
+        Ok(indexed)
+    }
+
+    /// Embed `query` and return the `limit` closest emails for the account,
+    /// optionally restricted to one folder.
+    pub async fn search(
+        &self,
+        account_id: &str,
+        folder: Option<&str>,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<SemanticMatch>, EmbeddingError> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let query_vector = self
+            .provider
+            .embed(&[query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| EmbeddingError::Request("Provider returned no embedding".to_string()))?;
+
+        let mut qb = sqlx::QueryBuilder::new(
+            r#"
+            SELECT e.uid, f.name AS folder, e.subject, e.from_address, e.date, v.vector
+            FROM email_embeddings v
+            JOIN emails e ON v.email_id = e.id
+            JOIN folders f ON e.folder_id = f.id
+            WHERE v.model =
+            "#,
+        );
+        qb.push_bind(self.provider.model());
+        qb.push(" AND f.account_id = ");
+        qb.push_bind(account_id);
+        if let Some(folder) = folder {
+            qb.push(" AND f.name = ");
+            qb.push_bind(folder);
+        }
+        let rows = qb.build().fetch_all(&self.db_pool).await?;
+
+        let mut matches: Vec<SemanticMatch> = rows
+            .iter()
+            .map(|r| {
+                let vector: Vec<u8> = r.get("vector");
+                SemanticMatch {
+                    uid: r.get("uid"),
+                    folder: r.get("folder"),
+                    subject: reveal(r.get("subject")),
+                    from_address: r.get("from_address"),
+                    date: r.try_get::<Option<String>, _>("date").ok().flatten(),
+                    score: cosine_similarity(&query_vector, &decode_vector(&vector)),
+                }
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 2.0], &[-1.0, -2.0]) + 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 2.0]), 0.0);
+    }
+
+    #[test]
+    fn test_vector_roundtrip() {
+        let vector = vec![0.5f32, -1.25, 3.0e-7, 42.0];
+        assert_eq!(decode_vector(&encode_vector(&vector)), vector);
+    }
+
+    #[test]
+    fn test_embedding_input_truncates_on_char_boundary() {
+        let input = embedding_input(Some("Grüße"), Some("body"), 4);
+        assert_eq!(input, "Grüß");
+        assert_eq!(embedding_input(None, Some(" body "), 100), "body");
+        assert_eq!(embedding_input(Some("Hi"), None, 100), "Hi\n\n");
+    }
+
+    #[test]
+    fn test_embedding_input_never_empty() {
+        assert_eq!(embedding_input(None, None, 100), EMPTY_INPUT_PLACEHOLDER);
+        assert_eq!(embedding_input(Some("  "), Some("\n"), 100), EMPTY_INPUT_PLACEHOLDER);
+    }
+
+    #[test]
+    fn test_provider_kind_parse() {
+        assert_eq!(EmbeddingProviderKind::parse("OpenAI"), Some(EmbeddingProviderKind::OpenAi));
+        assert_eq!(EmbeddingProviderKind::parse("ollama"), Some(EmbeddingProviderKind::Ollama));
+        assert_eq!(EmbeddingProviderKind::parse("bedrock"), None);
+    }
+}
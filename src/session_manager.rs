@@ -71,9 +71,15 @@ impl SessionManager {
 #[async_trait]
 impl SessionManagerTrait for SessionManager {
     async fn get_session(&self, api_key: &str) -> SessionResult<Arc<ManagedClient>> {
-        let sessions = self.sessions.lock().await;
+        let mut sessions = self.sessions.lock().await;
         
         match sessions.get(api_key) {
+            Some(client) if client.is_invalid() => {
+                // Mid-response after a timeout or broken FETCH; connect afresh
+                warn!("Dropping unusable session for API key");
+                sessions.remove(api_key);
+                Err(SessionError::NotFound)
+            }
             Some(client) => {
                 debug!("Retrieved existing session for API key");
                 Ok(Arc::clone(client))
@@ -0,0 +1,442 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! ManageSieve (RFC 5804) client for managing server-side Sieve filters.
+//!
+//! Connects to the account's IMAP host (or the per-account `sieve` override in
+//! the accounts config) on port 4190, upgrades with STARTTLS, and
+//! authenticates with the account's IMAP credentials using SASL PLAIN, or
+//! XOAUTH2 for OAuth accounts. Credentials are never sent over an
+//! unencrypted connection.
+
+use base64::Engine;
+use log::{debug, info};
+use serde::Serialize;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Default ManageSieve port (RFC 5804 section 1.8).
+pub const DEFAULT_SIEVE_PORT: u16 = 4190;
+
+/// Timeout for connecting and for each server response.
+const SIEVE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Upper bound on a single literal from the server, to avoid unbounded allocation.
+const MAX_LITERAL_SIZE: usize = 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum SieveError {
+    #[error("ManageSieve not supported: {0}")]
+    NotSupported(String),
+    #[error("ManageSieve connection error: {0}")]
+    Connection(String),
+    #[error("ManageSieve TLS error: {0}")]
+    Tls(String),
+    #[error("ManageSieve authentication failed: {0}")]
+    Auth(String),
+    #[error("ManageSieve server rejected command: {0}")]
+    Rejected(String),
+    #[error("ManageSieve protocol error: {0}")]
+    Protocol(String),
+}
+
+impl From<std::io::Error> for SieveError {
+    fn from(err: std::io::Error) -> Self {
+        SieveError::Connection(err.to_string())
+    }
+}
+
+/// Capabilities advertised in the greeting / after STARTTLS.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SieveCapabilities {
+    pub implementation: Option<String>,
+    pub sasl: Vec<String>,
+    pub sieve_extensions: Vec<String>,
+    pub starttls: bool,
+    pub version: Option<String>,
+}
+
+impl SieveCapabilities {
+    fn apply(&mut self, line: &str) {
+        let tokens = tokenize(line);
+        let Some(name) = tokens.first() else {
+            return;
+        };
+        let value = tokens.get(1).cloned();
+        match name.to_ascii_uppercase().as_str() {
+            "IMPLEMENTATION" => self.implementation = value,
+            "SASL" => self.sasl = value.unwrap_or_default().split_whitespace().map(|s| s.to_ascii_uppercase()).collect(),
+            "SIEVE" => self.sieve_extensions = value.unwrap_or_default().split_whitespace().map(|s| s.to_string()).collect(),
+            "STARTTLS" => self.starttls = true,
+            "VERSION" => self.version = value,
+            _ => {}
+        }
+    }
+}
+
+/// A script stored on the server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SieveScript {
+    pub name: String,
+    pub active: bool,
+}
+
+/// Credentials used to authenticate.
+pub enum SieveAuth<'a> {
+    Plain { username: &'a str, password: &'a str },
+    XOAuth2 { username: &'a str, access_token: &'a str },
+}
+
+/// Final status of a ManageSieve response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponseStatus {
+    Ok,
+    No,
+    Bye,
+}
+
+/// One response line, plus the literal that followed it, if any.
+#[derive(Debug, Clone, Default)]
+struct ResponseLine {
+    text: String,
+    literal: Option<Vec<u8>>,
+}
+
+/// Data lines and the terminating OK/NO/BYE.
+#[derive(Debug)]
+struct Response {
+    lines: Vec<ResponseLine>,
+    status: ResponseStatus,
+    message: String,
+}
+
+trait SieveStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> SieveStream for T {}
+
+/// An authenticated ManageSieve session.
+pub struct ManageSieveClient {
+    stream: BufReader<Box<dyn SieveStream>>,
+    capabilities: SieveCapabilities,
+}
+
+impl ManageSieveClient {
+    /// Connect, negotiate STARTTLS and authenticate.
+    pub async fn connect(host: &str, port: u16, auth: SieveAuth<'_>) -> Result<Self, SieveError> {
+        let addr = format!("{}:{}", host, port);
+        let tcp = match tokio::time::timeout(SIEVE_TIMEOUT, TcpStream::connect(&addr)).await {
+            Ok(Ok(tcp)) => tcp,
+            Ok(Err(e)) => return Err(SieveError::NotSupported(format!("cannot connect to {}: {}", addr, e))),
+            Err(_) => return Err(SieveError::NotSupported(format!("timed out connecting to {}", addr))),
+        };
+
+        let mut plain = BufReader::new(tcp);
+        let greeting = read_response(&mut plain).await?;
+        if greeting.status != ResponseStatus::Ok {
+            return Err(SieveError::NotSupported(format!("{} did not send a ManageSieve greeting: {}", addr, greeting.message)));
+        }
+        let capabilities = parse_capabilities(&greeting.lines);
+        if !capabilities.starttls {
+            return Err(SieveError::NotSupported(format!(
+                "{} does not offer STARTTLS; refusing to send credentials in plaintext", addr
+            )));
+        }
+
+        plain.get_mut().write_all(b"STARTTLS\r\n").await?;
+        let response = read_response(&mut plain).await?;
+        if response.status != ResponseStatus::Ok {
+            return Err(SieveError::Tls(format!("STARTTLS rejected: {}", response.message)));
+        }
+
+        let tls = crate::tls::connect("ManageSieve", host, plain.into_inner(), &crate::tls::default_config())
+            .await
+            .map_err(|e| SieveError::Tls(e.to_string()))?;
+
+        let mut stream: BufReader<Box<dyn SieveStream>> = BufReader::new(Box::new(tls));
+        // RFC 5804 section 2.2: the server re-sends capabilities after STARTTLS
+        let response = read_response(&mut stream).await?;
+        let capabilities = if response.lines.is_empty() { capabilities } else { parse_capabilities(&response.lines) };
+
+        let mut client = Self { stream, capabilities };
+        client.authenticate(auth).await?;
+        info!("ManageSieve session established with {}", addr);
+        Ok(client)
+    }
+
+    pub fn capabilities(&self) -> &SieveCapabilities {
+        &self.capabilities
+    }
+
+    async fn authenticate(&mut self, auth: SieveAuth<'_>) -> Result<(), SieveError> {
+        let (mechanism, payload) = match auth {
+            SieveAuth::Plain { username, password } => ("PLAIN", format!("\0{}\0{}", username, password)),
+            SieveAuth::XOAuth2 { username, access_token } => {
+                ("XOAUTH2", format!("user={}\x01auth=Bearer {}\x01\x01", username, access_token))
+            }
+        };
+        if !self.capabilities.sasl.iter().any(|m| m == mechanism) {
+            return Err(SieveError::Auth(format!(
+                "server does not support SASL {} (offers: {})", mechanism, self.capabilities.sasl.join(" ")
+            )));
+        }
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(payload.as_bytes());
+        let command = format!("AUTHENTICATE {} {}\r\n", quote(mechanism), quote(&encoded));
+        let response = self.command(&command).await?;
+        match response.status {
+            ResponseStatus::Ok => Ok(()),
+            _ => Err(SieveError::Auth(response.message)),
+        }
+    }
+
+    async fn command(&mut self, command: &str) -> Result<Response, SieveError> {
+        debug!("ManageSieve > {}", command.split_whitespace().next().unwrap_or(""));
+        self.stream.get_mut().write_all(command.as_bytes()).await?;
+        self.stream.get_mut().flush().await?;
+        read_response(&mut self.stream).await
+    }
+
+    async fn expect_ok(&mut self, command: &str) -> Result<Response, SieveError> {
+        let response = self.command(command).await?;
+        match response.status {
+            ResponseStatus::Ok => Ok(response),
+            _ => Err(SieveError::Rejected(response.message)),
+        }
+    }
+
+    pub async fn list_scripts(&mut self) -> Result<Vec<SieveScript>, SieveError> {
+        let response = self.expect_ok("LISTSCRIPTS\r\n").await?;
+        Ok(response.lines.iter().filter_map(|l| parse_script_line(&l.text, l.literal.as_deref())).collect())
+    }
+
+    pub async fn get_script(&mut self, name: &str) -> Result<String, SieveError> {
+        let response = self.expect_ok(&format!("GETSCRIPT {}\r\n", quote(name))).await?;
+        let literal = response.lines.into_iter()
+            .find_map(|l| l.literal)
+            .ok_or_else(|| SieveError::Protocol("GETSCRIPT returned no script body".to_string()))?;
+        String::from_utf8(literal).map_err(|e| SieveError::Protocol(format!("script is not UTF-8: {}", e)))
+    }
+
+    /// Upload (create or replace) a script. The server validates it and
+    /// rejects scripts with syntax errors, returning the error message.
+    pub async fn put_script(&mut self, name: &str, content: &str) -> Result<(), SieveError> {
+        let command = format!("PUTSCRIPT {} {{{}+}}\r\n{}\r\n", quote(name), content.len(), content);
+        self.expect_ok(&command).await.map(|_| ())
+    }
+
+    /// Make `name` the active script. An empty name deactivates all scripts.
+    pub async fn set_active(&mut self, name: &str) -> Result<(), SieveError> {
+        self.expect_ok(&format!("SETACTIVE {}\r\n", quote(name))).await.map(|_| ())
+    }
+
+    pub async fn delete_script(&mut self, name: &str) -> Result<(), SieveError> {
+        self.expect_ok(&format!("DELETESCRIPT {}\r\n", quote(name))).await.map(|_| ())
+    }
+
+    pub async fn logout(mut self) -> Result<(), SieveError> {
+        self.command("LOGOUT\r\n").await.map(|_| ())
+    }
+}
+
+async fn read_line<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Result<String, SieveError> {
+    let mut line = String::new();
+    let read = tokio::time::timeout(SIEVE_TIMEOUT, reader.read_line(&mut line))
+        .await
+        .map_err(|_| SieveError::Connection("timed out waiting for server response".to_string()))??;
+    if read == 0 {
+        return Err(SieveError::Connection("connection closed by server".to_string()));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+async fn read_response<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Result<Response, SieveError> {
+    let mut lines = Vec::new();
+    loop {
+        let text = read_line(reader).await?;
+        let literal = match literal_length(&text) {
+            Some(len) if len > MAX_LITERAL_SIZE => {
+                return Err(SieveError::Protocol(format!("literal of {} bytes exceeds limit", len)));
+            }
+            Some(len) => {
+                let mut buf = vec![0u8; len];
+                tokio::time::timeout(SIEVE_TIMEOUT, reader.read_exact(&mut buf))
+                    .await
+                    .map_err(|_| SieveError::Connection("timed out reading literal".to_string()))??;
+                Some(buf)
+            }
+            None => None,
+        };
+
+        if let Some(status) = status_of(&text) {
+            let message = literal
+                .map(|l| String::from_utf8_lossy(&l).into_owned())
+                .unwrap_or_else(|| status_message(&text));
+            return Ok(Response { lines, status, message });
+        }
+        // A literal's trailing CRLF is read as an empty line; skip it
+        if text.is_empty() && literal.is_none() {
+            continue;
+        }
+        lines.push(ResponseLine { text, literal });
+    }
+}
+
+fn parse_capabilities(lines: &[ResponseLine]) -> SieveCapabilities {
+    let mut capabilities = SieveCapabilities::default();
+    for line in lines {
+        capabilities.apply(&line.text);
+    }
+    capabilities
+}
+
+/// `OK`/`NO`/`BYE` when `line` is a response status line.
+fn status_of(line: &str) -> Option<ResponseStatus> {
+    let word = line.split(|c: char| c == ' ' || c == '(').next().unwrap_or("");
+    match word.to_ascii_uppercase().as_str() {
+        "OK" => Some(ResponseStatus::Ok),
+        "NO" => Some(ResponseStatus::No),
+        "BYE" => Some(ResponseStatus::Bye),
+        _ => None,
+    }
+}
+
+/// Human-readable text of a status line: the quoted string if present,
+/// otherwise the response code, otherwise the status word.
+fn status_message(line: &str) -> String {
+    let tokens = tokenize(line);
+    tokens.last().cloned().unwrap_or_else(|| line.to_string())
+}
+
+/// Length of the literal announced at the end of `line` (`{12}` or `{12+}`).
+fn literal_length(line: &str) -> Option<usize> {
+    let trimmed = line.strip_suffix('}')?;
+    let start = trimmed.rfind('{')?;
+    trimmed[start + 1..].trim_end_matches('+').parse().ok()
+}
+
+/// `"name"` or `"name" ACTIVE` from LISTSCRIPTS.
+fn parse_script_line(line: &str, literal: Option<&[u8]>) -> Option<SieveScript> {
+    let tokens = tokenize(line);
+    let (name, rest) = match literal {
+        Some(bytes) => (String::from_utf8_lossy(bytes).into_owned(), tokens.as_slice()),
+        None => (tokens.first()?.clone(), tokens.get(1..).unwrap_or(&[])),
+    };
+    let active = rest.iter().any(|t| t.eq_ignore_ascii_case("ACTIVE"));
+    Some(SieveScript { name, active })
+}
+
+/// Split a response line into atoms and unquoted strings. Parenthesised
+/// response codes are returned as a single token including the parentheses.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                value.push(escaped);
+                            }
+                        }
+                        '"' => break,
+                        _ => value.push(c),
+                    }
+                }
+                tokens.push(value);
+            }
+            '(' => {
+                let mut value = String::new();
+                for c in chars.by_ref() {
+                    value.push(c);
+                    if c == ')' {
+                        break;
+                    }
+                }
+                tokens.push(value);
+            }
+            _ => {
+                let mut value = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == ' ' {
+                        break;
+                    }
+                    value.push(c);
+                    chars.next();
+                }
+                // Literal markers are not tokens
+                if !(value.starts_with('{') && value.ends_with('}')) {
+                    tokens.push(value);
+                }
+            }
+        }
+    }
+    tokens
+}
+
+/// Quote a string for a ManageSieve command.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_capabilities() {
+        let lines: Vec<ResponseLine> = [
+            r#""IMPLEMENTATION" "Dovecot Pigeonhole""#,
+            r#""SIEVE" "fileinto reject envelope vacation""#,
+            r#""SASL" "PLAIN LOGIN""#,
+            r#""STARTTLS""#,
+            r#""VERSION" "1.0""#,
+        ]
+        .iter()
+        .map(|t| ResponseLine { text: t.to_string(), literal: None })
+        .collect();
+        let caps = parse_capabilities(&lines);
+        assert_eq!(caps.implementation.as_deref(), Some("Dovecot Pigeonhole"));
+        assert_eq!(caps.sasl, vec!["PLAIN", "LOGIN"]);
+        assert!(caps.sieve_extensions.contains(&"vacation".to_string()));
+        assert!(caps.starttls);
+        assert_eq!(caps.version.as_deref(), Some("1.0"));
+    }
+
+    #[test]
+    fn test_status_lines() {
+        assert_eq!(status_of("OK"), Some(ResponseStatus::Ok));
+        assert_eq!(status_of(r#"NO (NONEXISTENT) "There is no script by that name""#), Some(ResponseStatus::No));
+        assert_eq!(status_of(r#""NOTE" "x""#), None);
+        assert_eq!(status_message(r#"NO (NONEXISTENT) "There is no script by that name""#), "There is no script by that name");
+        assert_eq!(status_message("OK"), "OK");
+    }
+
+    #[test]
+    fn test_literal_length() {
+        assert_eq!(literal_length("{42}"), Some(42));
+        assert_eq!(literal_length(r#"NO {17+}"#), Some(17));
+        assert_eq!(literal_length(r#""script" ACTIVE"#), None);
+    }
+
+    #[test]
+    fn test_parse_script_line() {
+        assert_eq!(parse_script_line(r#""vacation" ACTIVE"#, None), Some(SieveScript { name: "vacation".into(), active: true }));
+        assert_eq!(parse_script_line(r#""spam \"rules\"""#, None), Some(SieveScript { name: "spam \"rules\"".into(), active: false }));
+        assert_eq!(parse_script_line("{5}", Some(b"lists")), Some(SieveScript { name: "lists".into(), active: false }));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+    }
+}
@@ -0,0 +1,498 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! TLS policy shared by outgoing IMAP, ManageSieve and SMTP connections.
+//!
+//! `TLS_MIN_VERSION` (default `1.2`) sets the oldest protocol version we will
+//! negotiate and `TLS_CIPHER_SUITES` narrows the suites offered. Everything
+//! goes through rustls, so TLS 1.0/1.1 are never offered. IMAP and
+//! ManageSieve handshake here and log the negotiated version and suite;
+//! SMTP goes through lettre, which picks up the process-wide provider that
+//! `init` installs but doesn't expose the established session, so its log
+//! reports the policy instead.
+//!
+//! IMAP and ManageSieve also take the `[tls]` section (`TlsConfig`), with
+//! `TLS_CA_CERT_PATH`, `TLS_PINNED_SHA256` and `TLS_ACCEPT_INVALID_HOSTNAMES`
+//! applied over it; an account with its own `tls` uses that instead. Extra
+//! CAs are trusted alongside the system roots, a pin is checked after normal
+//! verification has passed, and certificate failures come back as
+//! `TlsError::Verification` with the library's reason.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use lettre::transport::smtp::client::TlsVersion;
+use log::{debug, warn};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+use crate::config::{Settings, TlsConfig};
+
+static DEFAULTS: OnceLock<TlsConfig> = OnceLock::new();
+
+#[derive(Error, Debug, Clone)]
+pub enum TlsError {
+    #[error("TLS configuration error: {0}")]
+    Config(String),
+    #[error("{0}")]
+    Handshake(String),
+    /// The server's certificate was rejected: untrusted, expired, issued
+    /// for another name, or not the pinned one
+    #[error("{0}")]
+    Verification(String),
+}
+
+/// Oldest TLS version a connection may negotiate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsMinVersion {
+    Tls10,
+    Tls11,
+    Tls12,
+    Tls13,
+}
+
+impl FromStr for TlsMinVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase();
+        let version = normalized
+            .trim_start_matches("tlsv")
+            .trim_start_matches("tls")
+            .trim_start_matches(['v', ' ']);
+        match version {
+            "1.0" | "1" | "10" => Ok(Self::Tls10),
+            "1.1" | "11" => Ok(Self::Tls11),
+            "1.2" | "12" => Ok(Self::Tls12),
+            "1.3" | "13" => Ok(Self::Tls13),
+            _ => Err(format!("Unknown TLS version '{}'; expected 1.0, 1.1, 1.2 or 1.3", s)),
+        }
+    }
+}
+
+impl fmt::Display for TlsMinVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = match self {
+            Self::Tls10 => "1.0",
+            Self::Tls11 => "1.1",
+            Self::Tls12 => "1.2",
+            Self::Tls13 => "1.3",
+        };
+        write!(f, "TLS {}", version)
+    }
+}
+
+/// Make the `[tls]` section, with the `TLS_*` variables applied over it, the
+/// default for connections. Call once at startup; until then, and in
+/// binaries that never call it, the defaults come from the variables alone.
+pub fn init(settings: &Settings) {
+    let config = config_from_env(settings.tls.clone().unwrap_or_default());
+    if config.accept_invalid_hostnames {
+        warn!("TLS hostname verification is DISABLED for all IMAP connections (accept_invalid_hostnames); \
+               a server with any trusted certificate can impersonate yours");
+    }
+    // lettre builds its SMTP config from the process default provider
+    match crypto_provider(&config) {
+        Ok(provider) => {
+            if CryptoProvider::install_default(provider).is_err() {
+                warn!("A TLS crypto provider was already installed; TLS_CIPHER_SUITES does not apply to SMTP");
+            }
+        }
+        Err(e) => warn!("{}; SMTP offers the default cipher suites", e),
+    }
+    let _ = DEFAULTS.set(config);
+}
+
+fn config_from_env(mut config: TlsConfig) -> TlsConfig {
+    if let Ok(value) = std::env::var("TLS_MIN_VERSION") {
+        config.min_version = Some(value);
+    }
+    if let Ok(value) = std::env::var("TLS_CA_CERT_PATH") {
+        config.ca_cert_path = Some(value);
+    }
+    if let Ok(value) = std::env::var("TLS_PINNED_SHA256") {
+        config.pinned_sha256 = Some(value);
+    }
+    if let Some(value) = std::env::var("TLS_ACCEPT_INVALID_HOSTNAMES").ok().and_then(|v| v.parse().ok()) {
+        config.accept_invalid_hostnames = value;
+    }
+    if let Ok(value) = std::env::var("TLS_CIPHER_SUITES") {
+        config.cipher_suites = Some(value);
+    }
+    config
+}
+
+/// Settings for connections that don't have their own.
+pub fn default_config() -> TlsConfig {
+    DEFAULTS.get().cloned().unwrap_or_else(|| config_from_env(TlsConfig::default()))
+}
+
+/// An account's own settings, or the defaults.
+pub fn config_for(account: Option<&TlsConfig>) -> TlsConfig {
+    account.cloned().unwrap_or_else(default_config)
+}
+
+/// Minimum version in `config` (default 1.2). An unparseable value falls
+/// back to the default rather than loosening the policy.
+pub fn min_version(config: &TlsConfig) -> TlsMinVersion {
+    match &config.min_version {
+        Some(value) => value.parse().unwrap_or_else(|e| {
+            warn!("{}; using TLS 1.2", e);
+            TlsMinVersion::Tls12
+        }),
+        None => TlsMinVersion::Tls12,
+    }
+}
+
+/// Minimum TLS version from `TLS_MIN_VERSION` or the `[tls]` section
+/// (default 1.2).
+pub fn min_tls_version() -> TlsMinVersion {
+    min_version(&default_config())
+}
+
+/// The rustls provider restricted to the suites in `cipher_suites`, in the
+/// order listed there.
+pub fn crypto_provider(config: &TlsConfig) -> Result<CryptoProvider, TlsError> {
+    let mut provider = rustls::crypto::aws_lc_rs::default_provider();
+    let Some(names) = &config.cipher_suites else {
+        return Ok(provider);
+    };
+    let available = provider.cipher_suites.clone();
+    let mut selected = Vec::new();
+    for name in names.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let suite = available.iter()
+            .find(|suite| format!("{:?}", suite.suite()).eq_ignore_ascii_case(name))
+            .ok_or_else(|| TlsError::Config(format!(
+                "Unknown cipher suite '{}'; supported: {}",
+                name,
+                available.iter().map(|suite| format!("{:?}", suite.suite())).collect::<Vec<_>>().join(", ")
+            )))?;
+        selected.push(*suite);
+    }
+    if selected.is_empty() {
+        return Err(TlsError::Config("cipher_suites lists no cipher suite".to_string()));
+    }
+    provider.cipher_suites = selected;
+    Ok(provider)
+}
+
+/// rustls client config that refuses anything older than the configured
+/// minimum, offers only the configured suites and also trusts the
+/// configured CAs.
+pub fn client_config(config: &TlsConfig) -> Result<ClientConfig, TlsError> {
+    let provider = Arc::new(crypto_provider(config)?);
+    let versions: &[&rustls::SupportedProtocolVersion] = match min_version(config) {
+        TlsMinVersion::Tls13 => &[&rustls::version::TLS13],
+        _ => rustls::ALL_VERSIONS,
+    };
+
+    let mut roots = RootCertStore::empty();
+    match rustls_native_certs::load_native_certs() {
+        Ok(certificates) => {
+            roots.add_parsable_certificates(certificates);
+        }
+        Err(e) => warn!("Cannot load the system CA certificates: {}", e),
+    }
+    if let Some(path) = &config.ca_cert_path {
+        for certificate in load_ca_certificates(path)? {
+            roots.add(certificate)
+                .map_err(|e| TlsError::Config(format!("Invalid certificate in {}: {}", path, e)))?;
+        }
+    }
+
+    let verifier = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| TlsError::Config(format!("Failed to build TLS verifier: {}", e)))?;
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(versions)
+        .map_err(|e| TlsError::Config(format!("Failed to build TLS config: {}", e)))?;
+    let client_config = if config.accept_invalid_hostnames {
+        builder.dangerous()
+            .with_custom_certificate_verifier(Arc::new(AnyHostname(verifier)))
+            .with_no_client_auth()
+    } else {
+        builder.with_webpki_verifier(verifier).with_no_client_auth()
+    };
+    Ok(client_config)
+}
+
+/// Normal chain verification that lets a certificate for another host name
+/// through, for `accept_invalid_hostnames`. webpki checks the name last, so
+/// every other check has passed by the time it is ignored.
+#[derive(Debug)]
+struct AnyHostname(Arc<WebPkiServerVerifier>);
+
+impl ServerCertVerifier for AnyHostname {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        match self.0.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now) {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
+
+/// Every certificate in a PEM bundle, or the one in a DER file.
+fn load_ca_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let data = std::fs::read(path)
+        .map_err(|e| TlsError::Config(format!("Cannot read CA certificates from {}: {}", path, e)))?;
+    let certificates = CertificateDer::pem_slice_iter(&data)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::Config(format!("Invalid certificate in {}: {}", path, e)))?;
+    if certificates.is_empty() {
+        return Ok(vec![CertificateDer::from(data)]);
+    }
+    Ok(certificates)
+}
+
+/// `pinned_sha256` as lower-case hex without separators.
+fn pinned_fingerprints(config: &TlsConfig) -> Result<Vec<String>, TlsError> {
+    let Some(pins) = &config.pinned_sha256 else {
+        return Ok(Vec::new());
+    };
+    pins.split(',')
+        .map(str::trim)
+        .filter(|pin| !pin.is_empty())
+        .map(|pin| {
+            let hex: String = pin.chars().filter(|c| *c != ':').collect::<String>().to_ascii_lowercase();
+            if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(hex)
+            } else {
+                Err(TlsError::Config(format!("pinned_sha256 '{}' is not a SHA-256 fingerprint", pin)))
+            }
+        })
+        .collect()
+}
+
+/// SHA-256 of a DER certificate, in the form `pinned_sha256` is compared in.
+pub fn fingerprint(der: &[u8]) -> String {
+    hex::encode(Sha256::digest(der))
+}
+
+/// Handshake with `host` over `stream` under `config`, then check the
+/// server certificate against the pin if one is set.
+pub async fn connect<S>(
+    service: &str,
+    host: &str,
+    stream: S,
+    config: &TlsConfig,
+) -> Result<TlsStream<S>, TlsError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let pins = pinned_fingerprints(config)?;
+    let connector = TlsConnector::from(Arc::new(client_config(config)?));
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|e| TlsError::Config(format!("'{}' is not a valid TLS server name: {}", host, e)))?;
+    if config.accept_invalid_hostnames {
+        warn!("{} TLS to {}: hostname verification is DISABLED (accept_invalid_hostnames)", service, host);
+    }
+
+    let tls_stream = connector.connect(server_name, stream).await
+        .map_err(|e| handshake_failure(host, config, &e))?;
+    let (_, session) = tls_stream.get_ref();
+
+    if !pins.is_empty() {
+        let certificate = session.peer_certificates()
+            .and_then(|chain| chain.first())
+            .ok_or_else(|| TlsError::Verification(format!("{} sent no certificate to check against pinned_sha256", host)))?;
+        let actual = fingerprint(certificate);
+        if !pins.contains(&actual) {
+            return Err(TlsError::Verification(format!(
+                "Certificate of {} does not match pinned_sha256 (server sent {})", host, actual
+            )));
+        }
+        debug!("Certificate of {} matches its pin", host);
+    }
+
+    debug!(
+        "{} TLS established with {}: {:?}, {:?}",
+        service,
+        host,
+        session.protocol_version(),
+        session.negotiated_cipher_suite().map(|suite| suite.suite())
+    );
+    Ok(tls_stream)
+}
+
+/// Sort a failed handshake into a certificate rejection or anything else.
+fn handshake_failure(host: &str, config: &TlsConfig, err: &io::Error) -> TlsError {
+    let message = err.to_string();
+    let rejected = err.get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        .is_some_and(|e| matches!(e, rustls::Error::InvalidCertificate(_)));
+
+    if rejected {
+        let hint = if config.ca_cert_path.is_none() { "; set ca_cert_path to trust a private CA" } else { "" };
+        TlsError::Verification(format!("Certificate of {} was rejected: {}{}", host, message, hint))
+    } else {
+        TlsError::Handshake(describe_handshake_error(host, min_version(config), message))
+    }
+}
+
+/// lettre's equivalent of `min_tls_version()`. rustls has no TLS 1.0/1.1
+/// support, so those settings get TLS 1.2.
+pub fn smtp_min_tls_version() -> TlsVersion {
+    match min_tls_version() {
+        TlsMinVersion::Tls13 => TlsVersion::Tlsv13,
+        _ => TlsVersion::Tlsv12,
+    }
+}
+
+/// Turn a handshake failure into an error that says when the server couldn't
+/// meet the configured minimum, instead of a bare library message.
+pub fn handshake_error(host: &str, err: impl fmt::Display) -> String {
+    describe_handshake_error(host, min_tls_version(), err.to_string())
+}
+
+fn describe_handshake_error(host: &str, min: TlsMinVersion, message: String) -> String {
+    let lower = message.to_ascii_lowercase();
+    let version_mismatch = [
+        "protocol version",
+        "protocolversion",
+        "unsupported protocol",
+        "no protocols available",
+        "wrong version number",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle));
+
+    if version_mismatch {
+        format!(
+            "{} does not support {} or newer (TLS_MIN_VERSION); refusing to connect: {}",
+            host,
+            min,
+            message
+        )
+    } else {
+        format!("TLS handshake with {} failed: {}", host, message)
+    }
+}
+
+/// Debug log for a handshake done by a library that doesn't hand back the
+/// session, so it names the policy rather than what was negotiated.
+pub fn log_established(service: &str, host: &str) {
+    let config = default_config();
+    debug!(
+        "{} TLS established with {} (minimum {}, suites {})",
+        service,
+        host,
+        min_version(&config),
+        config.cipher_suites.as_deref().unwrap_or("default")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_min_version() {
+        assert_eq!("1.2".parse::<TlsMinVersion>().unwrap(), TlsMinVersion::Tls12);
+        assert_eq!("TLSv1.3".parse::<TlsMinVersion>().unwrap(), TlsMinVersion::Tls13);
+        assert_eq!("tls1.1".parse::<TlsMinVersion>().unwrap(), TlsMinVersion::Tls11);
+        assert_eq!(" 1.0 ".parse::<TlsMinVersion>().unwrap(), TlsMinVersion::Tls10);
+        assert!("ssl3".parse::<TlsMinVersion>().is_err());
+        assert!("1.4".parse::<TlsMinVersion>().is_err());
+    }
+
+    #[test]
+    fn test_handshake_error_names_version_mismatch() {
+        let msg = handshake_error("imap.example.com", "error:0A000102:SSL routines::unsupported protocol");
+        assert!(msg.contains("does not support TLS"));
+        assert!(msg.contains("imap.example.com"));
+
+        let msg = handshake_error("imap.example.com", "certificate verify failed");
+        assert!(msg.starts_with("TLS handshake with imap.example.com failed"));
+    }
+
+    #[test]
+    fn test_certificate_rejection_is_a_verification_error() {
+        let config = TlsConfig::default();
+        let rejected = io::Error::new(
+            io::ErrorKind::InvalidData,
+            rustls::Error::InvalidCertificate(CertificateError::UnknownIssuer),
+        );
+        match handshake_failure("mail.internal", &config, &rejected) {
+            TlsError::Verification(msg) => {
+                assert!(msg.contains("mail.internal"));
+                assert!(msg.contains("UnknownIssuer"));
+                assert!(msg.contains("ca_cert_path"));
+            }
+            other => panic!("expected a verification error, got {:?}", other),
+        }
+        let reset = io::Error::new(io::ErrorKind::ConnectionReset, "connection reset");
+        assert!(matches!(handshake_failure("mail.internal", &config, &reset), TlsError::Handshake(_)));
+    }
+
+    #[test]
+    fn test_cipher_suites_filter_the_provider() {
+        let config = TlsConfig {
+            cipher_suites: Some("tls13_aes_256_gcm_sha384, TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384".to_string()),
+            ..Default::default()
+        };
+        let suites: Vec<String> = crypto_provider(&config).unwrap().cipher_suites.iter()
+            .map(|suite| format!("{:?}", suite.suite()))
+            .collect();
+        assert_eq!(suites, vec!["TLS13_AES_256_GCM_SHA384", "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384"]);
+
+        let config = TlsConfig { cipher_suites: Some("RC4-MD5".to_string()), ..Default::default() };
+        assert!(matches!(crypto_provider(&config), Err(TlsError::Config(_))));
+    }
+
+    #[test]
+    fn test_pinned_fingerprints() {
+        let colons = "AB:".repeat(31) + "AB";
+        let config = TlsConfig {
+            pinned_sha256: Some(format!("{}, {}", colons, "cd".repeat(32))),
+            ..Default::default()
+        };
+        assert_eq!(pinned_fingerprints(&config).unwrap(), vec!["ab".repeat(32), "cd".repeat(32)]);
+
+        let config = TlsConfig { pinned_sha256: Some("abc".to_string()), ..Default::default() };
+        assert!(matches!(pinned_fingerprints(&config), Err(TlsError::Config(_))));
+    }
+}
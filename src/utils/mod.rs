@@ -4,5 +4,6 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 pub mod mime_decoder;
+pub mod plain_text;
 
 pub use mime_decoder::decode_mime_header;
\ No newline at end of file
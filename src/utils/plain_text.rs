@@ -0,0 +1,264 @@
+// Copyright (c) 2025 TexasFortress.AI
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use regex::Regex;
+
+use crate::imap::types::Email;
+
+/// Length of a list-view snippet, in characters.
+pub const SNIPPET_CHARS: usize = 200;
+
+/// Which part a plain-text view was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextSource {
+    TextPlain,
+    Html,
+    Empty,
+}
+
+/// Best plain-text view of a message: the text/plain part when it has
+/// content, otherwise the HTML part converted to text.
+pub fn best_plain_text(body_text: Option<&str>, body_html: Option<&str>) -> (String, TextSource) {
+    if let Some(text) = body_text.filter(|t| !t.trim().is_empty()) {
+        return (normalize_whitespace(text), TextSource::TextPlain);
+    }
+    if let Some(html) = body_html.filter(|h| !h.trim().is_empty()) {
+        return (html_to_text(html), TextSource::Html);
+    }
+    (String::new(), TextSource::Empty)
+}
+
+/// Convert HTML to readable plain text: block elements become line breaks,
+/// list items get a leading dash, links keep their target, and script, style
+/// and head content is dropped.
+pub fn html_to_text(html: &str) -> String {
+    lazy_static::lazy_static! {
+        static ref INVISIBLE_RE: Regex = Regex::new(
+            r"(?is)<!--.*?-->|<(script|style|head|title)\b[^>]*>.*?</(script|style|head|title)\s*>"
+        ).unwrap();
+        static ref LINK_RE: Regex = Regex::new(
+            r#"(?is)<a\b[^>]*?\bhref\s*=\s*["']([^"']+)["'][^>]*>(.*?)</a\s*>"#
+        ).unwrap();
+        static ref BREAK_RE: Regex = Regex::new(r"(?i)<br\s*/?>").unwrap();
+        static ref LIST_ITEM_RE: Regex = Regex::new(r"(?i)<li\b[^>]*>").unwrap();
+        static ref BLOCK_RE: Regex = Regex::new(
+            r"(?i)</?(p|div|tr|table|ul|ol|h[1-6]|blockquote|pre|hr|section|article|header|footer)\b[^>]*>"
+        ).unwrap();
+        static ref CELL_RE: Regex = Regex::new(r"(?i)</t[dh]\s*>").unwrap();
+        static ref TAG_RE: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+    }
+
+    let text = INVISIBLE_RE.replace_all(html, "");
+    let text = LINK_RE.replace_all(&text, |caps: &regex::Captures| {
+        let href = caps[1].trim();
+        let label = TAG_RE.replace_all(&caps[2], "");
+        let label = label.trim();
+        if label.is_empty() || decode_entities(label) == decode_entities(href)
+            || href.starts_with('#') || href.to_ascii_lowercase().starts_with("mailto:")
+        {
+            label.to_string()
+        } else {
+            format!("{} ({})", label, href)
+        }
+    });
+    let text = BREAK_RE.replace_all(&text, "\n");
+    let text = LIST_ITEM_RE.replace_all(&text, "\n- ");
+    let text = BLOCK_RE.replace_all(&text, "\n\n");
+    let text = CELL_RE.replace_all(&text, " ");
+    let text = TAG_RE.replace_all(&text, "");
+    normalize_whitespace(&decode_entities(&text))
+}
+
+/// Decode the character references that commonly appear in email HTML.
+pub fn decode_entities(text: &str) -> String {
+    lazy_static::lazy_static! {
+        static ref ENTITY_RE: Regex = Regex::new(r"&(#[0-9]{1,7}|#[xX][0-9a-fA-F]{1,6}|[a-zA-Z]{2,8});").unwrap();
+    }
+
+    ENTITY_RE.replace_all(text, |caps: &regex::Captures| {
+        let entity = &caps[1];
+        let decoded = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        } else if let Some(dec) = entity.strip_prefix('#') {
+            dec.parse().ok().and_then(char::from_u32)
+        } else {
+            match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                "bull" => Some('•'),
+                "copy" => Some('©'),
+                "reg" => Some('®'),
+                "trade" => Some('™'),
+                "euro" => Some('€'),
+                _ => None,
+            }
+        };
+        decoded.map(String::from).unwrap_or_else(|| caps[0].to_string())
+    }).into_owned()
+}
+
+/// Trim trailing spaces, collapse runs of spaces inside lines and keep at
+/// most one blank line between paragraphs.
+fn normalize_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.replace("\r\n", "\n").replace('\u{a0}', " ").lines() {
+        let line = line.split([' ', '\t']).filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank_run > 0 { "\n\n" } else { "\n" });
+        }
+        blank_run = 0;
+        out.push_str(&line);
+    }
+    out
+}
+
+/// Remove the quoted tail of a reply: everything from an attribution line
+/// ("On <date>, <name> wrote:"), an Outlook "Original Message" or "From:"
+/// header block, or a trailing run of `>`-quoted lines.
+pub fn strip_quoted_reply(text: &str) -> String {
+    lazy_static::lazy_static! {
+        static ref ATTRIBUTION_RE: Regex = Regex::new(r"(?i)^\s*On\b.{0,300}\bwrote:\s*$").unwrap();
+        static ref ORIGINAL_RE: Regex = Regex::new(
+            r"(?i)^\s*(-{2,}\s*(Original Message|Forwarded message)\s*-{2,}|_{10,})\s*$"
+        ).unwrap();
+        static ref OUTLOOK_FROM_RE: Regex = Regex::new(r"(?i)^\s*From:\s.+$").unwrap();
+        static ref OUTLOOK_FIELD_RE: Regex = Regex::new(r"(?i)^\s*(Sent|Date|To|Subject):\s").unwrap();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut cut = lines.len();
+
+    for i in 0..lines.len() {
+        let line = lines[i];
+        // Attributions are often wrapped over two lines
+        let joined = lines.get(i + 1).map(|next| format!("{} {}", line.trim_end(), next.trim()));
+        let attribution = ATTRIBUTION_RE.is_match(line)
+            || (line.trim_start().to_ascii_lowercase().starts_with("on ")
+                && joined.as_deref().is_some_and(|j| ATTRIBUTION_RE.is_match(j)));
+        let outlook_header = OUTLOOK_FROM_RE.is_match(line)
+            && lines[i + 1..].iter().take(4).any(|l| OUTLOOK_FIELD_RE.is_match(l));
+        if attribution || ORIGINAL_RE.is_match(line) || outlook_header {
+            cut = i;
+            break;
+        }
+    }
+
+    let mut kept: Vec<&str> = lines[..cut].to_vec();
+    while kept.last().is_some_and(|l| l.trim().is_empty() || l.trim_start().starts_with('>')) {
+        kept.pop();
+    }
+    kept.join("\n")
+}
+
+/// Human-readable preview of a message for list views.
+pub fn generate_snippet(email: &Email) -> String {
+    snippet_from_parts(email.text_body.as_deref(), email.html_body.as_deref())
+}
+
+/// Preview of the new content of a message: the text/plain part (or the
+/// HTML converted to text), without quoted replies, `>` lines or the
+/// signature, collapsed onto one line and cut at a word boundary to about
+/// [`SNIPPET_CHARS`] characters.
+pub fn snippet_from_parts(body_text: Option<&str>, body_html: Option<&str>) -> String {
+    let (text, _) = best_plain_text(body_text, body_html);
+    let text = strip_quoted_reply(&text);
+
+    let mut words = Vec::new();
+    for line in text.lines() {
+        // "-- " opens the signature
+        if line == "-- " || line == "--" {
+            break;
+        }
+        if line.trim_start().starts_with('>') {
+            continue;
+        }
+        words.extend(line.split_whitespace());
+    }
+    let collapsed = words.join(" ");
+
+    if collapsed.chars().count() <= SNIPPET_CHARS {
+        return collapsed;
+    }
+    let cut: String = collapsed.chars().take(SNIPPET_CHARS).collect();
+    let cut = match cut.rfind(' ') {
+        Some(pos) if pos > SNIPPET_CHARS / 2 => &cut[..pos],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches([',', ';', ':', ' ']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefers_text_plain() {
+        let (text, source) = best_plain_text(Some("Hello\r\n\r\n\r\nWorld  "), Some("<p>ignored</p>"));
+        assert_eq!(text, "Hello\n\nWorld");
+        assert_eq!(source, TextSource::TextPlain);
+
+        let (_, source) = best_plain_text(Some("  "), Some("<p>used</p>"));
+        assert_eq!(source, TextSource::Html);
+        assert_eq!(best_plain_text(None, None).1, TextSource::Empty);
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<html><head><style>p{color:red}</style></head><body>
+<p>Hi&nbsp;Ana,</p><div>See <a href="https://example.com/r">the report</a> &amp; notes:</div>
+<ul><li>One</li><li>Two</li></ul>Line<br>break<script>x()</script></body></html>"#;
+        assert_eq!(
+            html_to_text(html),
+            "Hi Ana,\n\nSee the report (https://example.com/r) & notes:\n\n- One\n- Two\n\nLine\nbreak"
+        );
+    }
+
+    #[test]
+    fn test_strip_quoted_reply() {
+        let text = "Sounds good.\n\nOn Mon, Jan 6, 2025 at 9:00 AM Ana <ana@example.com>\nwrote:\n> Can we meet?\n> Thanks";
+        assert_eq!(strip_quoted_reply(text), "Sounds good.");
+
+        let outlook = "Done.\n\nFrom: Bob\nSent: Monday\nTo: Ana\nSubject: Re: x\n\nOld text";
+        assert_eq!(strip_quoted_reply(outlook), "Done.");
+
+        let inline = "Reply\n> quoted\n> more";
+        assert_eq!(strip_quoted_reply(inline), "Reply");
+
+        assert_eq!(strip_quoted_reply("No quote here."), "No quote here.");
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = "Hi team,\n\n  The build   is green again.\n> old line\nThanks!\n-- \nAna\n\nOn Mon, Jan 6, 2025 Bob wrote:\n> earlier";
+        assert_eq!(snippet_from_parts(Some(text), None), "Hi team, The build is green again. Thanks!");
+
+        let html = "<html><head><style>.x{color:red}</style></head><body><p>Quarterly&nbsp;numbers</p><div>attached</div></body></html>";
+        assert_eq!(snippet_from_parts(None, Some(html)), "Quarterly numbers attached");
+
+        let long = "word ".repeat(100);
+        let snippet = snippet_from_parts(Some(&long), None);
+        assert!(snippet.ends_with("word…"));
+        assert!(snippet.chars().count() <= SNIPPET_CHARS + 1);
+
+        assert_eq!(snippet_from_parts(None, None), "");
+    }
+}
@@ -75,9 +75,16 @@ async fn test_concurrent_acquisition_stress() {
         max_connections: 150, // Higher than normal for stress test
         idle_timeout: Duration::from_secs(60),
         health_check_interval: Duration::from_secs(30),
+        health_check_jitter: 0.2,
+        max_validation_failures: 3,
+        validation_backoff_cap: Duration::from_secs(60),
         acquire_timeout: Duration::from_secs(2), // Fast timeout for stress test
         max_session_duration: Duration::from_secs(3600),
         max_concurrent_creations: 20, // Allow more concurrent creation
+        reserved_interactive: 0,
+        breaker_failure_threshold: 0,
+        breaker_cooldown: Duration::from_secs(30),
+        ..PoolConfig::default()
     };
 
     let factory = Arc::new(MockConnectionFactory::new(10, 0.1)); // 10ms delay, 10% failure rate
@@ -183,9 +190,16 @@ async fn test_connection_pool_metrics_accuracy() {
         max_connections: 20,
         idle_timeout: Duration::from_secs(30),
         health_check_interval: Duration::from_secs(10),
+        health_check_jitter: 0.2,
+        max_validation_failures: 3,
+        validation_backoff_cap: Duration::from_secs(60),
         acquire_timeout: Duration::from_secs(1),
         max_session_duration: Duration::from_secs(60),
         max_concurrent_creations: 5,
+        reserved_interactive: 0,
+        breaker_failure_threshold: 0,
+        breaker_cooldown: Duration::from_secs(30),
+        ..PoolConfig::default()
     };
 
     let factory = Arc::new(MockConnectionFactory::new(5, 0.0)); // Fast, no failures
@@ -229,9 +243,16 @@ async fn test_pool_concurrency_no_deadlocks() {
         max_connections: 10,
         idle_timeout: Duration::from_secs(5),
         health_check_interval: Duration::from_secs(2),
+        health_check_jitter: 0.2,
+        max_validation_failures: 3,
+        validation_backoff_cap: Duration::from_secs(60),
         acquire_timeout: Duration::from_millis(500),
         max_session_duration: Duration::from_secs(10),
         max_concurrent_creations: 3,
+        reserved_interactive: 0,
+        breaker_failure_threshold: 0,
+        breaker_cooldown: Duration::from_secs(30),
+        ..PoolConfig::default()
     };
 
     let factory = Arc::new(MockConnectionFactory::new(20, 0.3)); // Slow with failures
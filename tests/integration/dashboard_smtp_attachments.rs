@@ -76,6 +76,8 @@ async fn create_test_dashboard_state(test_name: &str) -> web::Data<DashboardStat
         max_cache_size_mb: 100,
         max_email_age_days: 30,
         sync_interval_seconds: 300,
+        encryption_passphrase: None,
+        prune_keep_flagged: true,
     };
 
     let mut cache_service = CacheService::new(cache_config);
@@ -83,6 +83,8 @@ async fn create_test_dashboard_state(test_name: &str) -> web::Data<DashboardStat
         max_cache_size_mb: 100,
         max_email_age_days: 30,
         sync_interval_seconds: 300,
+        encryption_passphrase: None,
+        prune_keep_flagged: true,
     };
 
     let mut cache_service = CacheService::new(cache_config);
@@ -247,6 +249,8 @@ async fn test_mcp_initialize_handshake() {
     assert_eq!(body["result"]["serverInfo"]["name"], "rustymail-mcp", "Server name should be rustymail-mcp");
     assert!(body["result"]["serverInfo"]["version"].is_string(), "Server version should be present");
     assert!(body["result"]["capabilities"].is_object(), "Capabilities should be present");
+    assert!(body["result"]["capabilities"]["resources"].is_object(), "Resources capability should be advertised");
+    assert!(body["result"]["capabilities"]["prompts"].is_object(), "Prompts capability should be advertised");
     assert!(body["result"]["_meta"]["sessionId"].is_string(), "Session ID should be generated");
 
     println!("✓ Initialize handshake returns correct JSON-RPC response");
@@ -258,6 +262,53 @@ async fn test_mcp_initialize_handshake() {
     cleanup_test_db(test_name);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_mcp_resources() {
+    setup_test_env();
+    let test_name = "resources";
+    println!("=== Testing MCP resources/list and resources/read ===");
+
+    let dashboard_state = create_test_dashboard_state(test_name).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(dashboard_state.clone())
+            .configure(rustymail::api::mcp_http::configure_mcp_routes)
+    ).await;
+    let api_key = get_test_api_key();
+
+    let call = |id: i64, method: &str, params: serde_json::Value| test::TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("X-Api-Key", api_key.as_str()))
+        .set_json(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+        .to_request();
+
+    let resp = test::call_service(&app, call(1, "resources/list", json!({}))).await;
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["result"]["resources"].is_array(), "resources/list should return an array: {}", body);
+    for resource in body["result"]["resources"].as_array().unwrap() {
+        assert!(resource["uri"].as_str().unwrap().starts_with("imap://"));
+    }
+
+    let resp = test::call_service(&app, call(2, "resources/templates/list", json!({}))).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["result"]["resourceTemplates"][0]["uriTemplate"], "imap://{account}/{folder}/{uid}");
+
+    // A URI outside the imap:// scheme is a parameter error
+    let resp = test::call_service(&app, call(3, "resources/read", json!({ "uri": "file:///etc/passwd" }))).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], -32602, "Bad URI should be invalid params: {}", body);
+
+    let resp = test::call_service(&app, call(4, "resources/read", json!({}))).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], -32602, "Missing uri should be invalid params: {}", body);
+
+    println!("✓ resources/list, resources/templates/list and resources/read respond per spec");
+
+    cleanup_test_db(test_name);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_mcp_tools_list() {
@@ -300,7 +351,7 @@ async fn test_mcp_tools_list() {
     assert!(body["result"]["tools"].is_array(), "Result should contain tools array");
 
     let tools = body["result"]["tools"].as_array().unwrap();
-    assert_eq!(tools.len(), 38, "Should have exactly 38 tools");
+    assert_eq!(tools.len(), 79, "Should have exactly 79 tools");
 
     // Verify each tool has required fields
     let expected_tool_names = vec![
@@ -318,7 +369,48 @@ async fn test_mcp_tools_list() {
         "get_email_synopsis", "get_email_thread",
         "search_by_domain", "get_address_report",
         "get_attachment_content", "export_evidence", "export_folder_metadata",
-        "filter_emails_by_subject", "batch_get_synopsis"
+        "filter_emails_by_subject", "batch_get_synopsis",
+        "transfer_messages",
+        "get_server_info",
+        "semantic_search",
+        "all_accounts_summary",
+        "flag_messages",
+        "unflag_messages",
+        "list_flagged",
+        "sieve_list_scripts",
+        "sieve_get_script",
+        "sieve_put_script",
+        "sieve_activate_script",
+        "sieve_delete_script",
+        "refresh_email",
+        "download_folder_attachments",
+        "verify_recipient",
+        "send_from_template",
+        "mark_read_matching",
+        "folder_threads",
+        "list_recent",
+        "get_email_text",
+        "check_uids_exist",
+        "next_unread",
+        "cache_status",
+        "compare_folders",
+        "extract_links",
+        "empty_folder",
+        "hierarchical_unread",
+        "sync_lag",
+        "refresh_account_connections",
+        "search_server",
+        "search_cached_emails_fts",
+        "copy_message",
+        "get_quota",
+        "get_thread",
+        "save_draft",
+        "get_special_folders",
+        "move_to_trash",
+        "fetch_email_partial",
+        "get_unread_counts",
+        "bulk_flag",
+        "get_server_capabilities"
     ];
 
     for tool in tools {
@@ -484,6 +576,81 @@ async fn test_mcp_error_handling_invalid_method() {
     cleanup_test_db(test_name);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_mcp_transport_errors_are_jsonrpc() {
+    setup_test_env();
+    println!("=== Testing MCP Transport Errors ===");
+
+    let test_name = "transport_errors";
+    let dashboard_state = create_test_dashboard_state(test_name).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(dashboard_state.clone())
+            .configure(rustymail::api::mcp_http::configure_mcp_routes)
+    ).await;
+    let api_key = get_test_api_key();
+
+    // Body that isn't JSON
+    let req = test::TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("X-Api-Key", api_key.as_str()))
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload("{\"jsonrpc\": \"2.0\", \"id\": ")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["jsonrpc"], "2.0");
+    assert!(body["id"].is_null());
+    assert_eq!(body["error"]["code"], -32700);
+    assert_eq!(body["error"]["data"]["category"], "parse_error");
+    println!("✓ Bad JSON returns -32700 parse_error");
+
+    // JSON without a method
+    let req = test::TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("X-Api-Key", api_key.as_str()))
+        .set_json(&json!({ "jsonrpc": "2.0", "id": 7 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["id"], 7);
+    assert_eq!(body["error"]["code"], -32600);
+    assert_eq!(body["error"]["data"]["category"], "invalid_request");
+    println!("✓ Missing method returns -32600 invalid_request");
+
+    // Wrong API key
+    let req = test::TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("X-Api-Key", "wrong-key"))
+        .set_json(&json!({ "jsonrpc": "2.0", "id": 8, "method": "tools/list" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+    assert!(resp.headers().contains_key("WWW-Authenticate"));
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], -32002);
+    assert_eq!(body["error"]["data"]["category"], "unauthorized");
+    println!("✓ Bad API key returns -32002 unauthorized");
+
+    // Unknown methods carry a category too
+    let req = test::TestRequest::post()
+        .uri("/mcp")
+        .insert_header(("X-Api-Key", api_key.as_str()))
+        .set_json(&json!({ "jsonrpc": "2.0", "id": 9, "method": "nonexistent/method" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["data"]["category"], "method_not_found");
+    println!("✓ Unknown method has method_not_found category");
+
+    cleanup_test_db(test_name);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_mcp_error_handling_invalid_tool_name() {
@@ -789,7 +956,7 @@ async fn test_mcp_dashboard_api_consistency() {
     // Verify same number of tools
     assert_eq!(mcp_tools.len(), dashboard_tools.len(),
                "MCP and Dashboard should expose same number of tools");
-    assert_eq!(mcp_tools.len(), 38, "Should have 38 tools in both interfaces");
+    assert_eq!(mcp_tools.len(), 79, "Should have 79 tools in both interfaces");
 
     // Verify all tool names match
     let mut mcp_tool_names: Vec<String> = mcp_tools.iter()
@@ -60,6 +60,8 @@ mod multi_account_sync_tests {
             max_cache_size_mb: 100,
             max_email_age_days: 30,
             sync_interval_seconds: 300,
+            encryption_passphrase: None,
+            prune_keep_flagged: true,
         };
 
         let mut cache_service = CacheService::new(cache_config);
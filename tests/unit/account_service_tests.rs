@@ -5,6 +5,7 @@
 
 use rustymail::dashboard::services::account::{AccountService, Account, AccountError};
 use rustymail::dashboard::services::account_store::{AccountStore, StoredAccount, ImapConfig, SmtpConfig};
+use rustymail::imap::ConnectionMode;
 use chrono::Utc;
 use serial_test::serial;
 use std::fs;
@@ -60,6 +61,8 @@ fn create_test_account(email: &str, display_name: &str) -> Account {
         imap_user: email.to_string(),
         imap_pass: "test_password".to_string(),
         imap_use_tls: true,
+        imap_connection_mode: ConnectionMode::default(),
+        imap_allow_plaintext_auth: false,
         smtp_host: Some("smtp.gmail.com".to_string()),
         smtp_port: Some(587),
         smtp_user: Some(email.to_string()),
@@ -68,6 +71,7 @@ fn create_test_account(email: &str, display_name: &str) -> Account {
         smtp_use_starttls: Some(true),
         is_active: true,
         is_default: false,
+        tls: None,
         connection_status: None,
         oauth_provider: None,
         oauth_access_token: None,
@@ -479,6 +479,97 @@ async fn test_create_zip_archive() {
     cleanup_test_db(test_name);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_create_zip_size_cap_and_progress() {
+    let test_name = "zip_cap_progress";
+    cleanup_test_db(test_name);
+
+    let pool = create_test_db_pool(test_name).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    // Save and restore working directory
+    let original_dir = std::env::current_dir().unwrap();
+    defer! { let _ = std::env::set_current_dir(&original_dir); }
+
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let account = "test@example.com";
+    let message_id = "<zipcap@example.com>";
+
+    let a = create_mime_part("text/plain", "a.txt", vec![b'a'; 600]);
+    let b = create_mime_part("text/plain", "b.txt", vec![b'b'; 600]);
+    attachment_storage::save_attachment(&pool, account, message_id, &a).await.unwrap();
+    attachment_storage::save_attachment(&pool, account, message_id, &b).await.unwrap();
+
+    // Over the cap: refused before the archive is written
+    let capped = attachment_storage::ZipConfig { max_total_bytes: 1000, concurrency: 2, job_threshold_bytes: 0 };
+    let zip_path = temp_dir.path().join("capped.zip");
+    let result = attachment_storage::create_zip_archive_with_progress(&pool, account, message_id, &zip_path, &capped, None).await;
+    assert!(matches!(result, Err(AttachmentError::TooLarge(_))));
+    assert!(!zip_path.exists());
+
+    // Within the cap: one progress report per file
+    let config = attachment_storage::ZipConfig { max_total_bytes: 10_000, ..capped };
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let zip_path = temp_dir.path().join("ok.zip");
+    attachment_storage::create_zip_archive_with_progress(&pool, account, message_id, &zip_path, &config, Some(tx)).await.unwrap();
+
+    let mut reports = Vec::new();
+    while let Some(progress) = rx.recv().await {
+        reports.push(progress);
+    }
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[1].files_done, 2);
+    assert_eq!(reports[1].bytes_done, 1200);
+    assert_eq!(reports[1].bytes_total, 1200);
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_create_multi_zip_archive_groups_by_email() {
+    let test_name = "multi_zip";
+    cleanup_test_db(test_name);
+
+    let pool = create_test_db_pool(test_name).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    // Save and restore working directory
+    let original_dir = std::env::current_dir().unwrap();
+    defer! { let _ = std::env::set_current_dir(&original_dir); }
+
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let account = "test@example.com";
+    let first = create_mime_part("application/pdf", "invoice.pdf", vec![b'1'; 100]);
+    let second = create_mime_part("application/pdf", "invoice.pdf", vec![b'2'; 100]);
+    attachment_storage::save_attachment(&pool, account, "<inv1@example.com>", &first).await.unwrap();
+    attachment_storage::save_attachment(&pool, account, "<inv2@example.com>", &second).await.unwrap();
+
+    let messages = vec![
+        ("101 - March invoice".to_string(), "<inv1@example.com>".to_string()),
+        ("../102".to_string(), "<inv2@example.com>".to_string()),
+        ("103 - No attachments".to_string(), "<none@example.com>".to_string()),
+    ];
+    let config = attachment_storage::ZipConfig { max_total_bytes: 10_000, concurrency: 2, job_threshold_bytes: 0 };
+    let zip_path = temp_dir.path().join("folder.zip");
+    attachment_storage::create_multi_zip_archive(&pool, account, &messages, &zip_path, &config, None).await.unwrap();
+
+    let archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort();
+    assert_eq!(names, vec!["101 - March invoice/invoice.pdf", "__102/invoice.pdf"]);
+
+    // The cap applies to the archive as a whole
+    let capped = attachment_storage::ZipConfig { max_total_bytes: 150, ..config };
+    let result = attachment_storage::create_multi_zip_archive(&pool, account, &messages, &zip_path, &capped, None).await;
+    assert!(matches!(result, Err(AttachmentError::TooLarge(_))));
+
+    cleanup_test_db(test_name);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_create_zip_with_no_attachments() {
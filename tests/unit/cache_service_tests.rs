@@ -3,7 +3,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use rustymail::dashboard::services::cache::{CacheService, CacheConfig, SyncStatus};
+use rustymail::dashboard::services::cache::{backup_database, fts_match_query, AccountLag, CacheService, CacheConfig, EmailCursor, FolderDiff, FolderLag, PageStart, SyncStatus};
 use rustymail::imap::types::{Email, Envelope, Address};
 use chrono::Utc;
 use std::fs;
@@ -20,6 +20,8 @@ fn create_test_config(test_name: &str) -> CacheConfig {
         max_cache_size_mb: 100,
         max_email_age_days: 30,
         sync_interval_seconds: 300,
+        encryption_passphrase: None,
+        prune_keep_flagged: true,
     }
 }
 
@@ -174,6 +176,41 @@ async fn test_get_cached_emails_with_pagination() {
     cleanup_test_db(test_name);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_get_cached_emails_with_cursor() {
+    let test_name = "cursor_pagination";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    // Same date on every email, so the UID breaks the tie
+    for i in 1..=10 {
+        let email = create_test_email(i, &format!("Subject {}", i), "test@example.com");
+        service.cache_email("INBOX", &email, account_id).await.unwrap();
+    }
+
+    let (page1, cursor) = service.get_cached_emails_page("INBOX", account_id, 4, PageStart::Offset(0), true).await.unwrap();
+    assert_eq!(page1.iter().map(|e| e.uid).collect::<Vec<_>>(), vec![10, 9, 8, 7]);
+    let cursor = cursor.expect("full page should have a next cursor");
+    assert_eq!(EmailCursor::decode(&cursor.encode()), Some(cursor.clone()));
+
+    // New mail arriving mid-walk doesn't shift later pages
+    let newer = create_test_email(11, "Subject 11", "test@example.com");
+    service.cache_email("INBOX", &newer, account_id).await.unwrap();
+
+    let (page2, cursor) = service.get_cached_emails_page("INBOX", account_id, 4, PageStart::After(cursor), true).await.unwrap();
+    assert_eq!(page2.iter().map(|e| e.uid).collect::<Vec<_>>(), vec![6, 5, 4, 3]);
+    let (page3, cursor) = service.get_cached_emails_page("INBOX", account_id, 4, PageStart::After(cursor.unwrap()), true).await.unwrap();
+    assert_eq!(page3.iter().map(|e| e.uid).collect::<Vec<_>>(), vec![2, 1]);
+    assert!(cursor.is_none(), "last page should not have a next cursor");
+
+    assert_eq!(EmailCursor::decode("not a cursor!"), None);
+
+    cleanup_test_db(test_name);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_preview_mode_truncates_body() {
@@ -256,6 +293,13 @@ async fn test_sync_state_management() {
     let state = service.get_sync_state("INBOX", account_id).await.unwrap().unwrap();
     assert_eq!(state.last_uid_synced, Some(150));
     assert_eq!(state.sync_status, SyncStatus::Idle);
+    assert_eq!(state.modseq, None);
+
+    // HIGHESTMODSEQ is kept alongside, and survives the next state update
+    service.update_sync_modseq("INBOX", Some(9_000_000_001), account_id).await.unwrap();
+    service.update_sync_state("INBOX", 160, SyncStatus::Idle, account_id).await.unwrap();
+    let state = service.get_sync_state("INBOX", account_id).await.unwrap().unwrap();
+    assert_eq!(state.modseq, Some(9_000_000_001));
 
     cleanup_test_db(test_name);
 }
@@ -348,6 +392,198 @@ async fn test_folder_stats() {
     cleanup_test_db(test_name);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_get_next_unread_uid() {
+    let test_name = "next_unread";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    // Same envelope date on every message, so ties fall back to UID order
+    for i in 1..=4 {
+        let mut email = create_test_email(i, &format!("Subject {}", i), "test@example.com");
+        email.flags = if i % 2 == 0 { vec![] } else { vec!["Seen".to_string()] };
+        service.cache_email("INBOX", &email, account_id).await.unwrap();
+    }
+    let mut read = create_test_email(1, "All read", "test@example.com");
+    read.flags = vec!["Seen".to_string()];
+    service.cache_email("Archive", &read, account_id).await.unwrap();
+
+    assert_eq!(service.get_next_unread_uid("INBOX", account_id, false).await.unwrap(), Some((2, 2)));
+    assert_eq!(service.get_next_unread_uid("INBOX", account_id, true).await.unwrap(), Some((4, 2)));
+    assert_eq!(service.get_next_unread_uid("Archive", account_id, false).await.unwrap(), None);
+    assert_eq!(service.get_next_unread_uid("Missing", account_id, false).await.unwrap(), None);
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_backup_database_keeps_newest() {
+    let test_name = "migration_backup";
+    cleanup_test_db(test_name);
+    let backup_dir = Path::new("test_data/migration_backup_backups");
+    let _ = fs::remove_dir_all(backup_dir);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+    service.cache_email("INBOX", &create_test_email(1, "Kept", "test@example.com"), account_id).await.unwrap();
+    let pool = service.db_pool.as_ref().unwrap();
+    let db_path = Path::new("test_data/migration_backup_test.db");
+
+    let mut written = Vec::new();
+    for _ in 0..3 {
+        written.push(backup_database(pool, db_path, backup_dir, 2).await.unwrap());
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    let mut remaining: Vec<_> = fs::read_dir(backup_dir).unwrap().map(|e| e.unwrap().path()).collect();
+    remaining.sort();
+    assert_eq!(remaining, written[1..].to_vec());
+
+    // The snapshot is a usable database with the cached email in it
+    let backup = sqlx::SqlitePool::connect(&format!("sqlite:{}", written[2].display())).await.unwrap();
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM emails").fetch_one(&backup).await.unwrap();
+    assert_eq!(count, 1);
+    backup.close().await;
+
+    let _ = fs::remove_dir_all(backup_dir);
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_compare_folders_by_message_id() {
+    let test_name = "compare_folders";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    // Source has 1..=3; the copy in Dest got new UIDs and is missing message 3
+    for uid in 1..=3 {
+        service.cache_email("Source", &create_test_email(uid, &format!("Subject {}", uid), "test@example.com"), account_id).await.unwrap();
+    }
+    for (dest_uid, original) in [(10, 1), (11, 2)] {
+        let mut copy = create_test_email(dest_uid, &format!("Subject {}", original), "test@example.com");
+        copy.envelope.as_mut().unwrap().message_id = Some(format!("<msg-{}>@test.com", original));
+        service.cache_email("Dest", &copy, account_id).await.unwrap();
+    }
+
+    // Messages without a Message-ID still match on content
+    let mut no_id = create_test_email(4, "No id", "test@example.com");
+    no_id.envelope.as_mut().unwrap().message_id = None;
+    service.cache_email("Source", &no_id, account_id).await.unwrap();
+    no_id.uid = 12;
+    service.cache_email("Dest", &no_id, account_id).await.unwrap();
+
+    let source = service.get_folder_message_keys("Source", account_id).await.unwrap().unwrap();
+    let dest = service.get_folder_message_keys("Dest", account_id).await.unwrap().unwrap();
+    let diff = FolderDiff::compare(&source, &dest);
+
+    assert_eq!(diff.matched, 3);
+    assert_eq!(diff.hashed, 2);
+    assert_eq!(diff.only_in_a.iter().map(|m| m.uid).collect::<Vec<_>>(), vec![3]);
+    assert!(diff.only_in_b.is_empty());
+
+    assert!(service.get_folder_message_keys("Missing", account_id).await.unwrap().is_none());
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_folder_freshness() {
+    let test_name = "folder_freshness";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    service.cache_email("INBOX", &create_test_email(1, "Subject", "test@example.com"), account_id).await.unwrap();
+    service.update_sync_state("INBOX", 1, SyncStatus::Idle, account_id).await.unwrap();
+    service.update_sync_state("Broken", 0, SyncStatus::Error, account_id).await.unwrap();
+    service.get_or_create_folder_for_account("Never", account_id).await.unwrap();
+
+    let mut folders = service.get_folder_freshness(account_id, None).await.unwrap();
+    let names: Vec<&str> = folders.iter().map(|f| f.folder.as_str()).collect();
+    assert_eq!(names, vec!["Broken", "INBOX", "Never"]);
+
+    let now = Utc::now();
+    let threshold = chrono::Duration::seconds(900);
+    for folder in &mut folders {
+        folder.evaluate(now, threshold);
+    }
+    assert!(folders[0].stale && folders[0].sync_error.is_some());
+    assert!(!folders[1].stale, "fresh sync: {:?}", folders[1].stale_reason);
+    assert_eq!(folders[1].cached_count, 1);
+    assert!(folders[2].stale && folders[2].last_sync.is_none());
+
+    // The same sync is stale once it is older than the threshold
+    let inbox = &mut folders[1];
+    inbox.evaluate(now + chrono::Duration::seconds(901), threshold);
+    assert!(inbox.stale);
+
+    // A live server count that disagrees with the cache also makes it stale
+    inbox.server_count = Some(2);
+    inbox.live = true;
+    inbox.evaluate(now, threshold);
+    assert!(inbox.stale);
+
+    let only_inbox = service.get_folder_freshness(account_id, Some("INBOX")).await.unwrap();
+    assert_eq!(only_inbox.len(), 1);
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_account_lag_from_cached_counts() {
+    let test_name = "account_lag";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    let mut read = create_test_email(1, "Read", "test@example.com");
+    read.flags = vec!["Seen".to_string()];
+    let mut unread = create_test_email(2, "Unread", "test@example.com");
+    unread.flags = vec![];
+    service.cache_email("INBOX", &read, account_id).await.unwrap();
+    service.cache_email("INBOX", &unread, account_id).await.unwrap();
+
+    let counts = service.get_unread_counts_for_account(account_id).await.unwrap();
+    assert_eq!(counts.get("INBOX"), Some(&1));
+
+    let now = Utc::now();
+    let stale_after = chrono::Duration::seconds(900);
+    let inbox = |server_messages: i64, server_unseen: i64| FolderLag {
+        folder: "INBOX".to_string(),
+        cached_messages: 2,
+        cached_unseen: counts["INBOX"],
+        server_messages: Some(server_messages),
+        server_unseen: Some(server_unseen),
+        last_sync: Some(now - chrono::Duration::seconds(60)),
+        ..Default::default()
+    };
+
+    let in_step = AccountLag::summarize(account_id, vec![inbox(2, 1)], None, now, stale_after);
+    assert_eq!((in_step.status, in_step.lag_score), ("ok", 0));
+    assert_eq!(in_step.oldest_sync_age_seconds, Some(60));
+
+    // Three new messages on the server, two of them unread
+    let behind = AccountLag::summarize(account_id, vec![inbox(5, 3)], None, now, stale_after);
+    assert_eq!((behind.status, behind.lag_score, behind.folders_behind), ("behind", 5, 1));
+    assert_eq!(behind.folders[0].message_delta, Some(3));
+
+    let failing = AccountLag::summarize(account_id, vec![inbox(2, 1)], Some("Live STATUS failed".to_string()), now, stale_after);
+    assert_eq!(failing.status, "failing");
+
+    cleanup_test_db(test_name);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_search_cached_emails() {
@@ -379,6 +615,68 @@ async fn test_search_cached_emails() {
     cleanup_test_db(test_name);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_search_all_folders_stays_in_account() {
+    let test_name = "search_all_folders";
+    cleanup_test_db(test_name);
+
+    let account_id = "first@account.com";
+    let other_account = "second@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+    create_test_account(&service, other_account).await.unwrap();
+
+    service.cache_email("INBOX", &create_test_email(1, "Quarterly report", "a@example.com"), account_id).await.unwrap();
+    service.cache_email("Archive", &create_test_email(2, "Quarterly report draft", "a@example.com"), account_id).await.unwrap();
+    service.cache_email("INBOX", &create_test_email(1, "Quarterly report (private)", "b@example.com"), other_account).await.unwrap();
+
+    // An empty folder name searches every folder of the account, not the whole cache
+    let results = service.search_cached_emails_for_account("", "quarterly", 10, account_id).await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|e| e.subject.as_deref() != Some("Quarterly report (private)")));
+
+    let results = service.search_cached_emails_for_account("", "private", 10, account_id).await.unwrap();
+    assert!(results.is_empty(), "the other account's mail must not be returned");
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_search_cached_emails_ranked() {
+    let test_name = "search_ranked";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    let mut body_only = create_test_email(1, "Weekly notes", "a@example.com");
+    body_only.text_body = Some("Agenda: the quarterly budget review is on Friday".to_string());
+    service.cache_email("INBOX", &body_only, account_id).await.unwrap();
+    service.cache_email("INBOX", &create_test_email(2, "Budget review", "b@example.com"), account_id).await.unwrap();
+    service.cache_email("INBOX", &create_test_email(3, "Lunch", "c@example.com"), account_id).await.unwrap();
+
+    // Subject hits outrank body hits
+    let results = service.search_cached_emails_ranked("INBOX", "budget review", 10, account_id).await.unwrap();
+    assert_eq!(results.iter().map(|r| r.email.uid).collect::<Vec<_>>(), vec![2, 1]);
+    assert!(results[0].score > results[1].score);
+    assert!(results[1].snippet.as_deref().unwrap_or_default().contains("[budget]"));
+
+    // Re-caching with a new subject updates the index
+    service.cache_email("INBOX", &create_test_email(3, "Budget lunch", "c@example.com"), account_id).await.unwrap();
+    let results = service.search_cached_emails_ranked("", "lunch", 10, account_id).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].folder, "INBOX");
+
+    // Other accounts and FTS syntax in the query are harmless
+    assert!(service.search_cached_emails_ranked("INBOX", "budget", 10, "other@account.com").await.unwrap().is_empty());
+    assert!(service.search_cached_emails_ranked("INBOX", "budget OR \"(", 10, account_id).await.is_ok());
+    assert_eq!(fts_match_query("  budg* \"q4\" "), Some("\"budg\"* \"q4\"".to_string()));
+    assert_eq!(fts_match_query(" \" * "), None);
+
+    cleanup_test_db(test_name);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_multi_account_isolation() {
@@ -608,3 +906,122 @@ async fn test_address_report_filters_exchange_addresses() {
 
     cleanup_test_db(test_name);
 }
+
+#[tokio::test]
+#[serial]
+async fn test_flagged_emails_across_folders() {
+    let test_name = "flagged_emails";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    for (folder, uid) in [("INBOX", 1), ("INBOX", 2), ("Archive", 3)] {
+        let email = create_test_email(uid, &format!("{} {}", folder, uid), "test@example.com");
+        service.cache_email(folder, &email, account_id).await.unwrap();
+    }
+
+    assert!(service.get_flagged_emails_for_account(account_id, 50, 0).await.unwrap().is_empty());
+
+    service.set_cached_flag("INBOX", &[2], "Flagged", true, account_id).await.unwrap();
+    service.set_cached_flag("Archive", &[3], "Flagged", true, account_id).await.unwrap();
+
+    let flagged = service.get_flagged_emails_for_account(account_id, 50, 0).await.unwrap();
+    let mut found: Vec<(String, u32)> = flagged.iter().map(|e| (e.folder.clone(), e.uid)).collect();
+    found.sort();
+    assert_eq!(found, vec![("Archive".to_string(), 3), ("INBOX".to_string(), 2)]);
+    // Flagging must not drop existing flags
+    assert!(flagged.iter().all(|e| e.flags.contains(&"\\Seen".to_string())));
+
+    service.set_cached_flag("INBOX", &[2], "Flagged", false, account_id).await.unwrap();
+    let flagged = service.get_flagged_emails_for_account(account_id, 50, 0).await.unwrap();
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].uid, 3);
+
+    cleanup_test_db(test_name);
+}
+
+#[cfg(feature = "cache-encryption")]
+#[tokio::test]
+#[serial]
+async fn test_cache_encryption_migration_and_reencrypt() {
+    let test_name = "cache_encryption";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+    service.cache_email("INBOX", &create_test_email(1, "Quarterly figures", "test@example.com"), account_id).await.unwrap();
+    drop(service);
+
+    // Same database, now with a passphrase: readable but read-only
+    let mut config = create_test_config(test_name);
+    config.encryption_passphrase = Some("correct horse".to_string());
+    let mut service = CacheService::new(config);
+    service.initialize().await.unwrap();
+    let email = service.get_email_by_uid_for_account("INBOX", 1, account_id).await.unwrap().unwrap();
+    assert_eq!(email.subject.as_deref(), Some("Quarterly figures"));
+    let err = service.cache_email("INBOX", &create_test_email(2, "Second", "test@example.com"), account_id).await.unwrap_err();
+    assert!(err.to_string().contains("--reencrypt"), "{}", err);
+
+    let report = service.reencrypt().await.unwrap();
+    assert_eq!((report.rows_scanned, report.rows_encrypted), (1, 1));
+    service.cache_email("INBOX", &create_test_email(2, "Second quarter", "test@example.com"), account_id).await.unwrap();
+
+    // Nothing readable on disk, everything readable through the service
+    let pool = service.db_pool.as_ref().unwrap();
+    let stored: Vec<(Option<String>, Option<String>)> = sqlx::query_as("SELECT subject, body_text FROM emails")
+        .fetch_all(pool)
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 2);
+    for (subject, body) in &stored {
+        assert!(subject.as_deref().unwrap().starts_with("ENC:c1:"));
+        assert!(body.as_deref().unwrap().starts_with("ENC:c1:"));
+    }
+    let email = service.get_email_by_uid_for_account("INBOX", 1, account_id).await.unwrap().unwrap();
+    assert_eq!(email.subject.as_deref(), Some("Quarterly figures"));
+    assert_eq!(email.body_text.as_deref(), Some("Test email body 1"));
+    let results = service.search_cached_emails_for_account("INBOX", "quarter", 10, account_id).await.unwrap();
+    assert_eq!(results.len(), 2);
+    drop(service);
+
+    // A wrong passphrase is refused at startup
+    let mut config = create_test_config(test_name);
+    config.encryption_passphrase = Some("wrong horse".to_string());
+    let mut service = CacheService::new(config);
+    assert!(service.initialize().await.is_err());
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_prune_expired_keeps_flagged() {
+    let test_name = "prune";
+    cleanup_test_db(test_name);
+
+    let account_id = "test@account.com";
+    let service = setup_service_with_account(test_name, account_id).await;
+
+    // Test emails are dated 2024-01-01, well past the 30-day limit
+    for uid in 1..=3 {
+        service.cache_email("INBOX", &create_test_email(uid, &format!("Old {}", uid), "test@example.com"), account_id).await.unwrap();
+    }
+    service.set_cached_flag("INBOX", &[2], "Flagged", true, account_id).await.unwrap();
+    assert!(service.get_cached_email("INBOX", 1, account_id).await.unwrap().is_some());
+
+    let report = service.prune().await.unwrap();
+    assert_eq!(report.expired, 2);
+    assert_eq!(report.over_budget, 0);
+
+    // Gone from the database and the memory cache alike
+    assert!(service.get_cached_email("INBOX", 1, account_id).await.unwrap().is_none());
+    assert!(service.get_cached_email("INBOX", 2, account_id).await.unwrap().is_some());
+    assert_eq!(service.count_emails_in_folder_for_account("INBOX", account_id).await.unwrap(), 1);
+
+    // Nothing left to do on a second pass
+    let report = service.prune().await.unwrap();
+    assert_eq!(report.expired + report.over_budget, 0);
+
+    cleanup_test_db(test_name);
+}
@@ -141,4 +141,30 @@ port = 8080
         env::remove_var("IMAP_PASS");
         // temp_dir will be cleaned up automatically when it goes out of scope
     }
+
+    #[test]
+    #[serial]
+    fn test_require_explicit_account() {
+        setup_test_env();
+        let temp_dir = TempDir::new().unwrap();
+
+        let content = r#"
+interface = "rest"
+imap_host = "imap.example.com"
+imap_user = "default_user"
+imap_pass = "default_pass"
+"#;
+        let config_path = create_config_file(&temp_dir, "explicit.toml", content);
+
+        // Off unless configured
+        env::remove_var("REQUIRE_EXPLICIT_ACCOUNT");
+        let settings = Settings::new(Some(&config_path)).expect("Failed to load settings");
+        assert!(!settings.require_explicit_account);
+
+        env::set_var("REQUIRE_EXPLICIT_ACCOUNT", "true");
+        let settings = Settings::new(Some(&config_path)).expect("Failed to load settings");
+        assert!(settings.require_explicit_account);
+
+        env::remove_var("REQUIRE_EXPLICIT_ACCOUNT");
+    }
 }
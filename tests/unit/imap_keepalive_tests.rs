@@ -159,6 +159,8 @@ async fn test_connection_pool_health_check() {
         acquire_timeout: Duration::from_secs(5),
         max_session_duration: Duration::from_secs(3600),
         max_concurrent_creations: 10,
+        reserved_interactive: 2,
+        ..PoolConfig::default()
     };
 
     let _pool = ConnectionPool::new(Arc::clone(&factory) as Arc<dyn ConnectionFactory>, config);
@@ -201,6 +203,8 @@ async fn test_health_check_interval() {
         acquire_timeout: Duration::from_secs(5),
         max_session_duration: Duration::from_secs(3600),
         max_concurrent_creations: 10,
+        reserved_interactive: 2,
+        ..PoolConfig::default()
     };
 
     let _pool = ConnectionPool::new(Arc::clone(&factory) as Arc<dyn ConnectionFactory>, config);
@@ -335,6 +339,8 @@ async fn test_pool_config_validation() {
         acquire_timeout: Duration::from_secs(10),
         max_session_duration: Duration::from_secs(7200),
         max_concurrent_creations: 5,
+        reserved_interactive: 2,
+        ..PoolConfig::default()
     };
 
     assert_eq!(config.min_connections, 10);
@@ -285,6 +285,51 @@ async fn test_filter_max_results() {
     cleanup_test_db("filter_limit");
 }
 
+#[tokio::test]
+#[serial]
+async fn test_filter_date_bounds() {
+    let pool = create_test_pool("filter_dates").await;
+    seed_test_data(&pool, "test@example.com", "Sent Items").await;
+
+    let filter = rustymail::filter_emails::SubjectFilter::new(pool.clone());
+    let patterns = vec!["e".to_string()];
+
+    // Bare dates: the upper bound includes the whole day
+    let result = filter
+        .filter(
+            "test@example.com", "Sent Items", &patterns, None,
+            None, None, Some("2024-03-12"), Some("2024-03-13"), None,
+        )
+        .await
+        .unwrap();
+    let mut uids: Vec<i64> = result.results.iter().map(|r| r.uid).collect();
+    uids.sort();
+    assert_eq!(uids, vec![2, 3]);
+
+    // Offsets are converted to UTC: 09:00 at UTC-5 is 14:00Z, exactly uid 2's date
+    let result = filter
+        .filter(
+            "test@example.com", "Sent Items", &patterns, None,
+            None, None, Some("2024-03-13T09:00:00-05:00"), None, None,
+        )
+        .await
+        .unwrap();
+    let mut uids: Vec<i64> = result.results.iter().map(|r| r.uid).collect();
+    uids.sort();
+    assert_eq!(uids, vec![1, 2]);
+
+    // Unparseable dates are rejected rather than compared as strings
+    assert!(filter
+        .filter(
+            "test@example.com", "Sent Items", &patterns, None,
+            None, None, Some("03/12/2024"), None, None,
+        )
+        .await
+        .is_err());
+
+    cleanup_test_db("filter_dates");
+}
+
 #[tokio::test]
 #[serial]
 async fn test_filter_empty_patterns_error() {
@@ -9,6 +9,7 @@ use rustymail::dashboard::services::oauth_config::*;
 use rustymail::dashboard::services::oauth_service::*;
 use rustymail::dashboard::services::account_store::*;
 use rustymail::imap::xoauth2::XOAuth2Authenticator;
+use rustymail::imap::ConnectionMode;
 use async_imap::Authenticator;
 use serial_test::serial;
 use tempfile::TempDir;
@@ -270,6 +271,8 @@ async fn test_oauth_account_store_roundtrip() {
             username: "user@outlook.com".to_string(),
             password: String::new(),
             use_tls: true,
+            mode: ConnectionMode::default(),
+            allow_plaintext_auth: false,
         },
         smtp: Some(SmtpConfig {
             host: "smtp.office365.com".to_string(),
@@ -283,6 +286,12 @@ async fn test_oauth_account_store_roundtrip() {
         oauth_access_token: Some("access-token-abc".to_string()),
         oauth_refresh_token: Some("refresh-token-xyz".to_string()),
         oauth_token_expiry: Some(1700000000),
+        sieve: None,
+        tls: None,
+        reply_to: None,
+        aliases: Vec::new(),
+        drafts_folder: None,
+        sent_append: SentAppendMode::default(),
         is_active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -316,12 +325,20 @@ async fn test_mixed_accounts_password_and_oauth() {
             username: "user@gmail.com".to_string(),
             password: "app-password".to_string(),
             use_tls: true,
+            mode: ConnectionMode::default(),
+            allow_plaintext_auth: false,
         },
         smtp: None,
         oauth_provider: None,
         oauth_access_token: None,
         oauth_refresh_token: None,
         oauth_token_expiry: None,
+        sieve: None,
+        tls: None,
+        reply_to: None,
+        aliases: Vec::new(),
+        drafts_folder: None,
+        sent_append: SentAppendMode::default(),
         is_active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -338,12 +355,20 @@ async fn test_mixed_accounts_password_and_oauth() {
             username: "user@outlook.com".to_string(),
             password: String::new(),
             use_tls: true,
+            mode: ConnectionMode::default(),
+            allow_plaintext_auth: false,
         },
         smtp: None,
         oauth_provider: Some("microsoft".to_string()),
         oauth_access_token: Some("token".to_string()),
         oauth_refresh_token: Some("refresh".to_string()),
         oauth_token_expiry: Some(9999999999),
+        sieve: None,
+        tls: None,
+        reply_to: None,
+        aliases: Vec::new(),
+        drafts_folder: None,
+        sent_append: SentAppendMode::default(),
         is_active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -381,12 +406,20 @@ async fn test_update_preserves_oauth_fields() {
             username: "user@outlook.com".to_string(),
             password: String::new(),
             use_tls: true,
+            mode: ConnectionMode::default(),
+            allow_plaintext_auth: false,
         },
         smtp: None,
         oauth_provider: Some("microsoft".to_string()),
         oauth_access_token: Some("old-token".to_string()),
         oauth_refresh_token: Some("old-refresh".to_string()),
         oauth_token_expiry: Some(1000),
+        sieve: None,
+        tls: None,
+        reply_to: None,
+        aliases: Vec::new(),
+        drafts_folder: None,
+        sent_append: SentAppendMode::default(),
         is_active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -5,9 +5,10 @@
 
 use rustymail::dashboard::services::{
     account::{Account, AccountService},
-    smtp::{SendEmailRequest, SendEmailResponse, SmtpError, SmtpService},
+    smtp::{build_message, OutgoingAttachment, SendEmailRequest, SendEmailResponse, SmtpError, SmtpService},
 };
 use rustymail::prelude::CloneableImapSessionFactory;
+use rustymail::imap::ConnectionMode;
 use serial_test::serial;
 use sqlx::SqlitePool;
 use std::fs;
@@ -63,6 +64,8 @@ fn create_test_account_with_smtp(email: &str) -> Account {
         imap_user: email.to_string(),
         imap_pass: "test_password".to_string(),
         imap_use_tls: true,
+        imap_connection_mode: ConnectionMode::default(),
+        imap_allow_plaintext_auth: false,
         smtp_host: Some("smtp.test.com".to_string()),
         smtp_port: Some(587),
         smtp_user: Some(email.to_string()),
@@ -71,6 +74,7 @@ fn create_test_account_with_smtp(email: &str) -> Account {
         smtp_use_starttls: Some(true),
         is_active: true,
         is_default: true,
+        tls: None,
         connection_status: None,
         oauth_provider: None,
         oauth_access_token: None,
@@ -91,6 +95,8 @@ fn create_test_account_without_smtp(email: &str) -> Account {
         imap_user: email.to_string(),
         imap_pass: "test_password".to_string(),
         imap_use_tls: true,
+        imap_connection_mode: ConnectionMode::default(),
+        imap_allow_plaintext_auth: false,
         smtp_host: None,
         smtp_port: None,
         smtp_user: None,
@@ -99,6 +105,7 @@ fn create_test_account_without_smtp(email: &str) -> Account {
         smtp_use_starttls: None,
         is_active: true,
         is_default: false,
+        tls: None,
         connection_status: None,
         oauth_provider: None,
         oauth_access_token: None,
@@ -116,6 +123,9 @@ fn create_test_email_request() -> SendEmailRequest {
         subject: "Test Subject".to_string(),
         body: "Test email body".to_string(),
         body_html: None,
+        in_reply_to: None,
+        references: None,
+        attachments: None,
     }
 }
 
@@ -194,6 +204,9 @@ async fn test_send_email_request_with_cc_bcc() {
         subject: "Test Subject".to_string(),
         body: "Test email body".to_string(),
         body_html: None,
+        in_reply_to: None,
+        references: None,
+        attachments: None,
     };
 
     assert!(request.cc.is_some());
@@ -216,12 +229,61 @@ async fn test_send_email_request_with_html_body() {
         subject: "Test Subject".to_string(),
         body: "Plain text body".to_string(),
         body_html: Some("<p>HTML body</p>".to_string()),
+        in_reply_to: None,
+        references: None,
+        attachments: None,
     };
 
     assert!(request.body_html.is_some());
     assert_eq!(request.body_html.as_ref().unwrap(), "<p>HTML body</p>");
 }
 
+#[tokio::test]
+#[serial]
+async fn test_build_message_with_attachments() {
+    let attachment = |filename: &str, content_type: &str, content_id: Option<&str>| OutgoingAttachment {
+        filename: filename.to_string(),
+        content_type: Some(content_type.to_string()),
+        data: Some("JVBERi0xLjQgdGVzdA==".to_string()), // "%PDF-1.4 test"
+        path: None,
+        content_id: content_id.map(String::from),
+    };
+    let request = SendEmailRequest {
+        to: vec!["recipient@test.com".to_string()],
+        cc: None,
+        bcc: None,
+        subject: "Report".to_string(),
+        body: "See attached.".to_string(),
+        body_html: Some("<p><img src=\"cid:logo\"> See attached.</p>".to_string()),
+        in_reply_to: None,
+        references: None,
+        attachments: Some(vec![
+            attachment("report.pdf", "application/pdf", None),
+            attachment("logo.png", "image/png", Some("<logo>")),
+        ]),
+    };
+    let builder = lettre::Message::builder()
+        .from("sender@test.com".parse().unwrap())
+        .to("recipient@test.com".parse().unwrap())
+        .subject("Report");
+    let raw = String::from_utf8(build_message(builder, &request).await.unwrap().formatted()).unwrap();
+
+    assert!(raw.contains("multipart/mixed"));
+    assert!(raw.contains("multipart/related"));
+    assert!(raw.contains("Content-Disposition: attachment; filename=\"report.pdf\""));
+    assert!(raw.contains("Content-ID: <logo>"));
+    assert!(raw.contains("Content-Disposition: inline"));
+
+    // Over the limit is refused before anything is sent
+    std::env::set_var("SMTP_MAX_ATTACHMENT_MB", "0");
+    let builder = lettre::Message::builder()
+        .from("sender@test.com".parse().unwrap())
+        .to("recipient@test.com".parse().unwrap());
+    let result = build_message(builder, &request).await;
+    std::env::remove_var("SMTP_MAX_ATTACHMENT_MB");
+    assert!(matches!(result, Err(SmtpError::AttachmentsTooLarge { .. })));
+}
+
 #[tokio::test]
 #[serial]
 async fn test_smtp_error_types() {
@@ -267,6 +329,217 @@ async fn test_account_with_smtp_config_creation() {
     cleanup_test_db(test_name);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_outbox_message_with_attachment_survives_restart() {
+    use lettre::message::{header::ContentType, Attachment, MultiPart, SinglePart};
+    use rustymail::dashboard::services::{OutboxQueueItem, OutboxQueueService, OutboxStatus};
+
+    let test_name = "outbox_restart";
+    cleanup_test_db(test_name);
+
+    let message = lettre::Message::builder()
+        .from("sender@test.com".parse().unwrap())
+        .to("recipient@test.com".parse().unwrap())
+        .bcc("hidden@test.com".parse().unwrap())
+        .subject("Report attached")
+        .multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain("See attached.".to_string()))
+                .singlepart(
+                    Attachment::new("report.pdf".to_string())
+                        .body(b"%PDF-1.4 test".to_vec(), ContentType::parse("application/pdf").unwrap()),
+                ),
+        )
+        .unwrap();
+    let raw_email_bytes = message.formatted();
+
+    let item = OutboxQueueItem {
+        id: None,
+        account_email: "sender@test.com".to_string(),
+        message_id: None,
+        to_addresses: vec!["recipient@test.com".to_string()],
+        cc_addresses: None,
+        bcc_addresses: Some(vec!["hidden@test.com".to_string()]),
+        subject: "Report attached".to_string(),
+        body_text: "See attached.".to_string(),
+        body_html: None,
+        raw_email_bytes: raw_email_bytes.clone(),
+        status: OutboxStatus::Pending,
+        smtp_sent: false,
+        outbox_saved: false,
+        sent_folder_saved: false,
+        retry_count: 0,
+        max_retries: 3,
+        last_error: None,
+        created_at: chrono::Utc::now(),
+        scheduled_at: None,
+        smtp_sent_at: None,
+        last_retry_at: None,
+        completed_at: None,
+    };
+
+    let pool = create_test_db_pool(test_name).await;
+    OutboxQueueService::new(pool.clone()).enqueue(item.clone()).await.unwrap();
+    pool.close().await;
+
+    // Simulate a restart: reopen the same database with a fresh service
+    let db_url = format!("sqlite:test_data/smtp_{}_test.db", test_name);
+    let reopened = OutboxQueueService::new(SqlitePool::connect(&db_url).await.unwrap());
+    let pending = reopened.get_next_pending().await.unwrap().expect("queued item should survive restart");
+
+    assert_eq!(pending.raw_email_bytes, raw_email_bytes);
+    let raw = String::from_utf8_lossy(&pending.raw_email_bytes);
+    assert!(raw.contains("filename=\"report.pdf\""));
+    assert!(!raw.contains("hidden@test.com"), "Bcc must not appear in the stored message");
+    assert_eq!(
+        pending.envelope_recipients(),
+        vec!["recipient@test.com".to_string(), "hidden@test.com".to_string()]
+    );
+
+    // An item without an assembled message is refused at enqueue time
+    let empty = OutboxQueueItem { raw_email_bytes: Vec::new(), ..item };
+    assert!(reopened.enqueue(empty).await.is_err());
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_outbox_scheduled_items_wait_and_cancel() {
+    use rustymail::dashboard::services::{CancelOutcome, OutboxQueueItem, OutboxQueueService, OutboxStatus};
+
+    let test_name = "outbox_schedule";
+    cleanup_test_db(test_name);
+    let queue = OutboxQueueService::new(create_test_db_pool(test_name).await);
+
+    let item = |subject: &str, scheduled_at| OutboxQueueItem {
+        id: None,
+        account_email: "sender@test.com".to_string(),
+        message_id: None,
+        to_addresses: vec!["recipient@test.com".to_string()],
+        cc_addresses: None,
+        bcc_addresses: None,
+        subject: subject.to_string(),
+        body_text: "Body".to_string(),
+        body_html: None,
+        raw_email_bytes: b"Subject: test\r\n\r\nBody".to_vec(),
+        status: OutboxStatus::Pending,
+        smtp_sent: false,
+        outbox_saved: false,
+        sent_folder_saved: false,
+        retry_count: 0,
+        max_retries: 3,
+        last_error: None,
+        created_at: chrono::Utc::now(),
+        scheduled_at,
+        smtp_sent_at: None,
+        last_retry_at: None,
+        completed_at: None,
+    };
+
+    let later = chrono::Utc::now() + chrono::Duration::hours(1);
+    let scheduled_id = queue.enqueue(item("Later", Some(later))).await.unwrap();
+
+    // Not due yet, so the worker has nothing to pick up
+    assert!(queue.get_next_pending().await.unwrap().is_none());
+    let listed = queue.list(Some("sender@test.com"), false, 10).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].send_at.timestamp(), later.timestamp());
+
+    let now_id = queue.enqueue(item("Now", None)).await.unwrap();
+    let next = queue.get_next_pending().await.unwrap().expect("unscheduled item is due");
+    assert_eq!(next.id, Some(now_id));
+
+    // Once claimed by the worker it can't be cancelled
+    assert!(queue.mark_sending(now_id).await.unwrap());
+    assert_eq!(queue.cancel(now_id).await.unwrap(), CancelOutcome::NotCancellable(OutboxStatus::Sending));
+
+    assert_eq!(queue.cancel(scheduled_id).await.unwrap(), CancelOutcome::Cancelled);
+    assert!(!queue.mark_sending(scheduled_id).await.unwrap());
+    assert_eq!(queue.cancel(9999).await.unwrap(), CancelOutcome::NotFound);
+
+    let listed = queue.list(None, false, 10).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, now_id);
+    let all = queue.list(None, true, 10).await.unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[1].status, OutboxStatus::Cancelled);
+
+    cleanup_test_db(test_name);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_outbox_item_interrupted_while_sending_resumes_after_restart() {
+    use rustymail::dashboard::services::{OutboxQueueItem, OutboxQueueService, OutboxStatus};
+
+    let test_name = "outbox_interrupted";
+    cleanup_test_db(test_name);
+    let pool = create_test_db_pool(test_name).await;
+    let queue = OutboxQueueService::new(pool.clone());
+
+    let id = queue.enqueue(OutboxQueueItem {
+        id: None,
+        account_email: "sender@test.com".to_string(),
+        message_id: None,
+        to_addresses: vec!["recipient@test.com".to_string()],
+        cc_addresses: None,
+        bcc_addresses: None,
+        subject: "Interrupted".to_string(),
+        body_text: "Body".to_string(),
+        body_html: None,
+        raw_email_bytes: b"Subject: Interrupted\r\n\r\nBody".to_vec(),
+        status: OutboxStatus::Pending,
+        smtp_sent: false,
+        outbox_saved: false,
+        sent_folder_saved: false,
+        retry_count: 0,
+        max_retries: 3,
+        last_error: None,
+        created_at: chrono::Utc::now(),
+        scheduled_at: None,
+        smtp_sent_at: None,
+        last_retry_at: None,
+        completed_at: None,
+    }).await.unwrap();
+
+    // The process dies after SMTP accepted the message but before the Sent copy
+    assert!(queue.mark_sending(id).await.unwrap());
+    queue.mark_smtp_sent(id).await.unwrap();
+    pool.close().await;
+
+    let db_url = format!("sqlite:test_data/smtp_{}_test.db", test_name);
+    let reopened = OutboxQueueService::new(SqlitePool::connect(&db_url).await.unwrap());
+    assert!(reopened.get_next_pending().await.unwrap().is_none(), "a sending item is not picked up as is");
+
+    assert_eq!(reopened.recover_interrupted().await.unwrap(), 1);
+    let resumed = reopened.get_next_pending().await.unwrap().expect("interrupted item is pending again");
+    assert_eq!(resumed.id, Some(id));
+    assert!(resumed.smtp_sent, "completed steps are kept so the message isn't sent twice");
+    assert!(!resumed.sent_folder_saved);
+    assert!(reopened.mark_sending(id).await.unwrap());
+    assert_eq!(reopened.recover_interrupted().await.unwrap(), 1);
+    assert_eq!(reopened.recover_interrupted().await.unwrap(), 0);
+
+    cleanup_test_db(test_name);
+}
+
+#[test]
+fn test_envelope_for_dedups_and_accepts_display_names() {
+    use rustymail::dashboard::services::smtp::envelope_for;
+
+    let envelope = envelope_for(
+        "sender@test.com",
+        &["Ana <ana@test.com>".to_string(), "ana@test.com".to_string(), "bob@test.com".to_string()],
+    )
+    .unwrap();
+    assert_eq!(envelope.from().map(|a| a.to_string()), Some("sender@test.com".to_string()));
+    assert_eq!(envelope.to().len(), 2);
+
+    assert!(envelope_for("sender@test.com", &["not an address".to_string()]).is_err());
+}
+
 // TODO: Add these tests once we have mock SMTP server and IMAP session factory:
 // - test_send_email_success
 // - test_send_email_smtp_connection_error